@@ -4,9 +4,11 @@
 
 #pragma once
 
+#include <algorithm>
 #include <memory>
 #include "rocksdb/utilities/transaction_db.h"
 #include "rocksdb/utilities/options_util.h"
+#include "rocksdb/utilities/checkpoint.h"
 
 using namespace std;
 using namespace rocksdb;
@@ -285,6 +287,19 @@ struct TransactionDBWrapper
         return db->GetIntProperty(cf, property, value);
     }
 
+    Status flush() const
+    {
+        vector<ColumnFamilyHandle *> cfs;
+        for (auto cf : cf_handles)
+        {
+            if (cf)
+            {
+                cfs.push_back(cf);
+            }
+        }
+        return db->Flush(FlushOptions(), cfs);
+    }
+
     unique_ptr<Iterator> iter(const ReadOptions &options, ColumnFamilyHandle *cf) const
     {
         return unique_ptr<Iterator>(db->NewIterator(options, cf));
@@ -306,6 +321,18 @@ struct TransactionDBWrapper
     {
         db->ReleaseSnapshot(snapshot);
     }
+
+    Status create_checkpoint(Slice checkpoint_dir) const
+    {
+        Checkpoint *ptr;
+        Status status = Checkpoint::Create(db.get(), &ptr);
+        if (!status.ok())
+        {
+            return status;
+        }
+        unique_ptr<Checkpoint> checkpoint(ptr);
+        return checkpoint->CreateCheckpoint(checkpoint_dir.ToString());
+    }
 };
 
 // Note: make sure ReadOnlyDbWrapper is Unpin.
@@ -331,11 +358,57 @@ struct ReadOnlyDbWrapper
         return status;
     }
 
+    // Only open column families that exist in the db, handles of missing
+    // column families are null.
+    Status open_existing(
+        const DbOptionsWrapper &options)
+    {
+        vector<string> names;
+        Status status = DB::ListColumnFamilies(options.db_options, options.path, &names);
+        if (!status.ok())
+        {
+            return status;
+        }
+        vector<ColumnFamilyDescriptor> existing;
+        vector<size_t> indexes;
+        for (size_t i = 0; i < options.cf_descriptors.size(); i++)
+        {
+            auto &descriptor = options.cf_descriptors[i];
+            if (std::find(names.begin(), names.end(), descriptor.name) != names.end())
+            {
+                existing.push_back(descriptor);
+                indexes.push_back(i);
+            }
+        }
+
+        DB *ptr;
+        vector<ColumnFamilyHandle *> handles;
+        status = DB::OpenForReadOnly(
+            options.db_options,
+            options.path,
+            existing,
+            &handles,
+            &ptr);
+        if (status.ok())
+        {
+            db.reset(ptr);
+            cf_handles.assign(options.cf_descriptors.size(), nullptr);
+            for (size_t i = 0; i < indexes.size(); i++)
+            {
+                cf_handles[indexes[i]] = handles[i];
+            }
+        }
+        return status;
+    }
+
     ~ReadOnlyDbWrapper()
     {
         for (auto cf : cf_handles)
         {
-            db->DestroyColumnFamilyHandle(cf);
+            if (cf)
+            {
+                db->DestroyColumnFamilyHandle(cf);
+            }
         }
     }
 
@@ -362,6 +435,11 @@ struct ReadOnlyDbWrapper
     {
         return unique_ptr<Iterator>(db->NewIterator(options, cf));
     }
+
+    bool get_int_property(ColumnFamilyHandle *cf, const Slice &property, uint64_t *value) const
+    {
+        return db->GetIntProperty(cf, property, value);
+    }
 };
 
 // Note: make sure TransactionWrapper is Unpin.
@@ -70,6 +70,14 @@ impl DbOptions {
         ReadOnlyDb::open(&self.inner)
     }
 
+    /// Open read only, skipping column families that don't exist in the db.
+    ///
+    /// Reads of missing column families panic, except
+    /// [`ReadOnlyDb::get_int_property`].
+    pub fn open_read_only_existing(&self) -> Result<ReadOnlyDb> {
+        ReadOnlyDb::open_existing(&self.inner)
+    }
+
     pub fn open(&self) -> Result<TransactionDb> {
         moveit! {
             let txn_db_options = new_transaction_db_options();
@@ -219,6 +227,29 @@ impl TransactionDb {
         got.then_some(val)
     }
 
+    /// Flush memtables of all column families to SST files.
+    pub fn flush(&self) -> Result<()> {
+        moveit! {
+            let status = self.inner.flush();
+        }
+        into_result(&status)
+    }
+
+    /// Create an openable snapshot of the db in `checkpoint_dir`.
+    ///
+    /// SST files are hard linked when `checkpoint_dir` is on the same
+    /// filesystem as the db, so this is cheap even for large databases.
+    /// `checkpoint_dir` must not exist.
+    ///
+    /// Memtables are flushed first, so the checkpoint is consistent while the
+    /// db is being written.
+    pub fn create_checkpoint(&self, checkpoint_dir: &Path) -> Result<()> {
+        moveit! {
+            let status = self.inner.create_checkpoint(checkpoint_dir.as_os_str().as_bytes().into());
+        }
+        into_result(&status)
+    }
+
     pub fn snapshot(&self) -> Snapshot {
         Snapshot {
             inner: self.inner.get_snapshot(),
@@ -353,6 +384,17 @@ impl ReadOnlyDb {
         Ok(ReadOnlyDb { inner: db })
     }
 
+    fn open_existing(options: &DbOptionsWrapper) -> Result<ReadOnlyDb> {
+        let db = Arc::emplace(ReadOnlyDbWrapper::new());
+        let mut db = Pin::into_inner(db);
+        let db_mut = Arc::get_mut(&mut db).unwrap();
+        moveit! {
+            let status = Pin::new(db_mut).open_existing(options);
+        }
+        into_result(&status)?;
+        Ok(ReadOnlyDb { inner: db })
+    }
+
     pub fn default_col(&self) -> usize {
         self.inner.default_col()
     }
@@ -408,6 +450,21 @@ impl ReadOnlyDb {
         unsafe { DbIterator::new(self.as_inner().iter(options, cf), dir) }
     }
 
+    /// Returns `None` if the property is unknown or the column family is
+    /// missing.
+    pub fn get_int_property(&self, col: usize, property: &str) -> Option<u64> {
+        let cf = self.inner.get_cf(col);
+        if cf.is_null() {
+            return None;
+        }
+        let mut val = 0;
+        let got = unsafe {
+            self.inner
+                .get_int_property(cf, &property.as_bytes().into(), &mut val)
+        };
+        got.then_some(val)
+    }
+
     pub fn as_inner(&self) -> &ReadOnlyDbWrapper {
         &self.inner
     }
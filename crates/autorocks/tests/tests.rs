@@ -77,6 +77,48 @@ fn test_read_only_db() {
     assert_eq!(v.unwrap().as_ref(), b"value");
 }
 
+#[test]
+fn test_read_only_db_existing() {
+    let (db, dir) = open_temp(2);
+    db.put(0, b"key", b"value").unwrap();
+    drop(db);
+
+    // Column families 2 to 4 don't exist.
+    assert!(DbOptions::new(dir.path(), 5).open_read_only().is_err());
+    let rdb = DbOptions::new(dir.path(), 5)
+        .open_read_only_existing()
+        .unwrap();
+    slot!(slice);
+    let v = rdb.get(0, b"key", slice).unwrap();
+    assert_eq!(v.unwrap().as_ref(), b"value");
+    assert!(rdb
+        .get_int_property(0, "rocksdb.estimate-num-keys")
+        .is_some());
+    assert!(rdb
+        .get_int_property(4, "rocksdb.estimate-num-keys")
+        .is_none());
+}
+
+#[test]
+fn test_checkpoint() {
+    let (db, _dir) = open_temp(1);
+    db.put(0, b"key", b"value").unwrap();
+
+    // Checkpoint while the db is open and being written.
+    let checkpoint_dir = tempdir().unwrap();
+    let checkpoint_path = checkpoint_dir.path().join("checkpoint");
+    db.create_checkpoint(&checkpoint_path).unwrap();
+    db.put(0, b"key", b"new value").unwrap();
+    assert!(db.create_checkpoint(&checkpoint_path).is_err());
+
+    let cdb = DbOptions::new(&checkpoint_path, 1)
+        .open_read_only()
+        .unwrap();
+    slot!(slice);
+    let v = cdb.get(0, b"key", slice).unwrap();
+    assert_eq!(v.unwrap().as_ref(), b"value");
+}
+
 #[cfg(feature = "snappy")]
 #[test]
 fn test_db_open_snappy() {
@@ -8,4 +8,5 @@ criterion_main! {
     benchmarks::sudt::sudt,
     benchmarks::smt::smt,
     benchmarks::fee_queue::fee_queue,
+    benchmarks::state_machine::state_machine,
 }
@@ -1,4 +1,5 @@
 pub mod fee_queue;
 pub mod init_db;
 pub mod smt;
+pub mod state_machine;
 pub mod sudt;
@@ -157,12 +157,18 @@ impl BenchExecutionEnvironment {
                     generator_path: META_GENERATOR_PATH.into(),
                     validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
                     backend_type: gw_config::BackendType::Meta,
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
                 BackendConfig {
                     validator_path: SUDT_VALIDATOR_PATH.into(),
                     generator_path: SUDT_GENERATOR_PATH.into(),
                     validator_script_type_hash: SUDT_VALIDATOR_SCRIPT_TYPE_HASH.into(),
                     backend_type: gw_config::BackendType::Sudt,
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
             ];
             BackendManage::from_config(vec![BackendForkConfig {
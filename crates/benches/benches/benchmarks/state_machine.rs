@@ -0,0 +1,218 @@
+//! Hot paths of the state machine on a test chain, with accounts funded by
+//! fixtures of the gw-tests testing tool.
+
+use anyhow::Result;
+use criterion::{criterion_group, BatchSize, BenchmarkId, Criterion, Throughput};
+use gw_common::{registry_address::RegistryAddress, state::State};
+use gw_generator::traits::StateExt;
+use gw_mem_pool::pool::OutputParam;
+use gw_store::state::{traits::JournalDB, MemStateDB};
+use gw_tests::testing_tool::{
+    chain::TestChain,
+    fixtures::{always_success_accounts, ckb_deposits, ckb_transfer, ckb_withdrawal},
+    mem_pool_provider::DummyMemPoolProvider,
+};
+use gw_traits::ChainView;
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    packed::{BlockInfo, DepositInfoVec, Script},
+    prelude::*,
+    U256,
+};
+use gw_utils::RollupContext;
+use tokio::runtime::Runtime;
+
+const ACCOUNTS: usize = 50;
+const DEPOSIT_CAPACITY: u64 = 1000_00000000;
+const WITHDRAWAL_CAPACITY: u64 = 500_00000000;
+
+criterion_group! {
+    name = state_machine;
+    config = Criterion::default().sample_size(10);
+    targets = bench_state_machine
+}
+
+struct BenchChain;
+impl ChainView for BenchChain {
+    fn get_block_hash_by_number(&self, _: u64) -> Result<Option<H256>> {
+        unreachable!("bench chain store")
+    }
+}
+
+struct BenchEnv {
+    chain: TestChain,
+    rollup_context: RollupContext,
+    accounts: Vec<(Script, RegistryAddress)>,
+    block_info: BlockInfo,
+    // Dropped after the chain.
+    rt: Runtime,
+}
+
+impl BenchEnv {
+    /// A test chain with a block of deposits to `ACCOUNTS` accounts.
+    fn setup() -> Self {
+        let rt = Runtime::new().expect("tokio runtime");
+        let (chain, accounts) = rt.block_on(async {
+            let mut chain = TestChain::setup(Script::default()).await;
+            let accounts = always_success_accounts(&chain.rollup_type_hash(), ACCOUNTS);
+            let deposits = ckb_deposits(
+                chain.inner.generator().rollup_context(),
+                accounts.iter().map(|(script, _)| script.clone()),
+                DEPOSIT_CAPACITY,
+            );
+            let deposit_info_vec = DepositInfoVec::new_builder()
+                .extend(deposits.into_iter().map(|d| d.pack()))
+                .build();
+            chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+            (chain, accounts)
+        });
+        let rollup_context = chain.inner.generator().rollup_context().clone();
+        let tip = chain.last_valid_block().raw();
+        let number: u64 = tip.number().unpack();
+        let block_producer: Bytes = tip.block_producer().unpack();
+        let block_info = BlockInfo::new_builder()
+            .block_producer(block_producer.pack())
+            .number((number + 1).pack())
+            .timestamp(tip.timestamp())
+            .build();
+        BenchEnv {
+            chain,
+            rollup_context,
+            accounts,
+            block_info,
+            rt,
+        }
+    }
+
+    fn state(&self) -> MemStateDB {
+        let mem_pool_state = self.rt.block_on(self.chain.mem_pool_state());
+        mem_pool_state.load_state_db()
+    }
+
+    fn block_producer(&self) -> RegistryAddress {
+        let block_producer: Bytes = self.block_info.block_producer().unpack();
+        RegistryAddress::from_slice(&block_producer).expect("block producer")
+    }
+}
+
+pub fn bench_state_machine(c: &mut Criterion) {
+    let env = BenchEnv::setup();
+    let chain_id = env.chain.chain_id();
+    let generator = env.chain.inner.generator();
+    let (from_script, _) = &env.accounts[0];
+    let (_, to_address) = &env.accounts[1];
+    let from_id = { env.state() }
+        .get_account_id_by_script_hash(&from_script.hash())
+        .unwrap()
+        .expect("from id");
+
+    c.bench_function("execute_tx", |b| {
+        b.iter_batched(
+            || {
+                let state = env.state();
+                let nonce = state.get_nonce(from_id).unwrap();
+                let tx = ckb_transfer(chain_id, from_id, nonce, to_address, U256::one());
+                (state, tx.raw())
+            },
+            |(mut state, raw_tx)| {
+                generator
+                    .execute_transaction(
+                        &BenchChain,
+                        &mut state,
+                        &env.block_info,
+                        &raw_tx,
+                        None,
+                        None,
+                    )
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("apply_deposit_request", |b| {
+        b.iter_batched(
+            || {
+                let (script, _) =
+                    always_success_accounts(&env.chain.rollup_type_hash(), 1).remove(0);
+                let deposit = ckb_deposits(&env.rollup_context, Some(script), DEPOSIT_CAPACITY);
+                (env.state(), deposit[0].request.clone())
+            },
+            |(mut state, request)| {
+                state
+                    .apply_deposit_request(&env.rollup_context, &request)
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let block_producer = env.block_producer();
+    c.bench_function("apply_withdrawal_request", |b| {
+        b.iter_batched(
+            || {
+                let state = env.state();
+                let nonce = state.get_nonce(from_id).unwrap();
+                let withdrawal =
+                    ckb_withdrawal(chain_id, from_script.hash(), nonce, WITHDRAWAL_CAPACITY);
+                (state, withdrawal.request())
+            },
+            |(mut state, request)| {
+                state
+                    .apply_withdrawal_request(&env.rollup_context, &block_producer, &request)
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let mut group = c.benchmark_group("mem_state_db_smt_insert");
+    for keys in [100u64, 1000, 10000] {
+        group.throughput(Throughput::Elements(keys));
+        group.bench_with_input(BenchmarkId::from_parameter(keys), &keys, |b, keys| {
+            b.iter_batched(
+                || {
+                    let kvs: Vec<(H256, H256)> = (0..*keys)
+                        .map(|_| (rand::random(), rand::random()))
+                        .collect();
+                    (env.state(), kvs)
+                },
+                |(mut state, kvs)| {
+                    for (key, value) in kvs {
+                        state.update_raw(key, value).unwrap();
+                    }
+                    state.finalise().unwrap();
+                    state.calculate_root().unwrap()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+
+    // Fill the mem block with deposits of new accounts.
+    let mut mem_pool = env.rt.block_on(env.chain.mem_pool());
+    let new_accounts = always_success_accounts(&env.chain.rollup_type_hash(), ACCOUNTS);
+    let provider = DummyMemPoolProvider {
+        deposit_cells: ckb_deposits(
+            &env.rollup_context,
+            new_accounts.into_iter().map(|(script, _)| script),
+            DEPOSIT_CAPACITY,
+        ),
+        ..Default::default()
+    };
+    mem_pool.set_provider(Box::new(provider));
+    env.rt
+        .block_on(mem_pool.reset_mem_block(&Default::default()))
+        .unwrap();
+    let mut group = c.benchmark_group("package_mem_block");
+    for retry_count in [0usize, 1, 2] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(retry_count),
+            &retry_count,
+            |b, retry_count| b.iter(|| mem_pool.output_mem_block(&OutputParam::new(*retry_count))),
+        );
+    }
+    group.finish();
+}
@@ -0,0 +1,170 @@
+//! Notify a webhook of critical events, configured by `alerts`.
+//!
+//! Alerts raised with `gw_telemetry::alert::alert` are filtered by the config,
+//! e.g. challenges are only sent for blocks of this node, and alerts of the
+//! same kind are sent at most once in `min_interval_secs`.
+
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use gw_common::registry_address::RegistryAddress;
+use gw_config::{AlertFormat, AlertsConfig};
+use gw_telemetry::alert::{self, Alert, AlertSink};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+pub struct AlertNotifier {
+    config: AlertsConfig,
+    /// Hex encoded block producer of this node.
+    block_producer: Option<String>,
+    client: reqwest::Client,
+}
+
+/// Alerts are delivered by a spawned task so that raising one never blocks.
+struct ChannelSink(mpsc::UnboundedSender<Alert>);
+
+impl AlertSink for ChannelSink {
+    fn send(&self, alert: Alert) {
+        let _ = self.0.send(alert);
+    }
+}
+
+impl AlertNotifier {
+    pub fn new(config: &AlertsConfig, block_producer: Option<&RegistryAddress>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .expect("build webhook client");
+        AlertNotifier {
+            config: config.clone(),
+            block_producer: block_producer.map(|a| hex::encode(a.to_bytes())),
+            client,
+        }
+    }
+
+    pub fn webhook_url(&self) -> &str {
+        &self.config.webhook_url
+    }
+
+    /// Set the notifier as the alert sink, and monitor disk usage of
+    /// `store_path`.
+    pub fn start(self: Arc<Self>, store_path: PathBuf) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        if !alert::set_sink(ChannelSink(sender)) {
+            log::warn!("[alerts] alert sink is already set");
+            return;
+        }
+        let interval = Duration::from_secs(self.config.disk_check_interval_secs.max(1));
+        tokio::spawn(monitor_disk_usage(
+            store_path,
+            self.config.disk_usage_percent,
+            interval,
+        ));
+        tokio::spawn(self.run(receiver));
+    }
+
+    async fn run(self: Arc<Self>, mut receiver: mpsc::UnboundedReceiver<Alert>) {
+        let min_interval = Duration::from_secs(self.config.min_interval_secs);
+        let mut last_sent: HashMap<&'static str, Instant> = HashMap::new();
+        while let Some(alert) = receiver.recv().await {
+            if !self.is_critical(&alert) {
+                continue;
+            }
+            let kind = alert.kind();
+            if matches!(last_sent.get(kind), Some(t) if t.elapsed() < min_interval) {
+                log::warn!("[alerts] suppressed {:?}", alert);
+                continue;
+            }
+            last_sent.insert(kind, Instant::now());
+            self.notify(&alert).await;
+        }
+    }
+
+    fn is_critical(&self, alert: &Alert) -> bool {
+        match alert {
+            Alert::SubmissionFailures { failures, .. } => {
+                *failures == self.config.submission_failures
+            }
+            Alert::ChallengeObserved { block_producer, .. } => {
+                self.block_producer.as_ref() == Some(block_producer)
+            }
+            _ => true,
+        }
+    }
+
+    /// Send `alert` now, e.g. before exiting.
+    pub async fn notify(&self, alert: &Alert) {
+        log::error!("[alerts] {:?}", alert);
+        if let Err(err) = self.post(alert).await {
+            log::warn!("[alerts] post {:?} error {:#}", alert, err);
+        }
+    }
+
+    async fn post(&self, alert: &Alert) -> Result<()> {
+        let body = match self.config.format {
+            AlertFormat::Json => serde_json::to_value(alert)?,
+            AlertFormat::Slack => {
+                json!({ "text": format!("[godwoken] {}", serde_json::to_string(alert)?) })
+            }
+        };
+        self.client
+            .post(&self.config.webhook_url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+async fn monitor_disk_usage(path: PathBuf, threshold_percent: u64, interval: Duration) {
+    loop {
+        match disk_used_percent(&path) {
+            Ok(used_percent) if used_percent >= threshold_percent => {
+                alert::alert(Alert::DiskUsage {
+                    path: path.to_string_lossy().into_owned(),
+                    used_percent,
+                });
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("[alerts] disk usage of {:?} error {:#}", path, err),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Used percent of the file system of `path`, like `df`.
+#[allow(clippy::unnecessary_cast)]
+fn disk_used_percent(path: &Path) -> Result<u64> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    // Block counts are not u64 on all platforms.
+    let used = (stat.f_blocks - stat.f_bfree) as u64;
+    let total = used + stat.f_bavail as u64;
+    if total == 0 {
+        return Ok(0);
+    }
+    Ok((used * 100 + total - 1) / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_used_percent() {
+        let dir = std::env::temp_dir();
+        assert!(disk_used_percent(&dir).unwrap() <= 100);
+        assert!(disk_used_percent(&dir.join("gw-missing-dir/store")).is_err());
+    }
+}
@@ -3,30 +3,34 @@
 
 use crate::{
     custodian::query_mergeable_custodians,
+    funding_wallets::FundingWallets,
     produce_block::{
         generate_produce_block_param, produce_block, ProduceBlockParam, ProduceBlockResult,
     },
     test_mode_control::TestModeControl,
+    withdrawal::count_withdrawals_within_inputs,
 };
 
 use anyhow::{bail, ensure, Context, Result};
 use ckb_chain_spec::consensus::MAX_BLOCK_BYTES;
 use gw_chain::chain::Chain;
-use gw_config::BlockProducerConfig;
+use gw_config::{BlockProducerConfig, PscConfig};
 use gw_generator::Generator;
 use gw_jsonrpc_types::test_mode::TestModePayload;
 use gw_mem_pool::{
     custodian::to_custodian_cell,
     pool::{MemPool, OutputParam},
 };
-use gw_rpc_client::{contract::ContractsCellDepManager, rpc_client::RPCClient};
+use gw_rpc_client::{
+    contract::ContractsCellDepManager, fee_estimator::FeeEstimator, rpc_client::RPCClient,
+};
 use gw_smt::smt::SMTH256;
-use gw_store::Store;
+use gw_store::{traits::chain_store::ChainStore, Store};
 use gw_types::offchain::{global_state_from_slice, CompatibleFinalizedTimepoint};
 use gw_types::{
     bytes::Bytes,
     h256::*,
-    offchain::{DepositInfo, InputCellInfo},
+    offchain::{CellInfo, DepositInfo, InputCellInfo},
     packed::{
         CellDep, CellInput, CellOutput, GlobalState, L2Block, RollupAction, RollupActionUnion,
         RollupSubmitBlock, Transaction, WithdrawalRequestExtra, WitnessArgs,
@@ -34,11 +38,21 @@ use gw_types::{
     prelude::*,
 };
 use gw_utils::{
-    fee::fill_tx_fee_with_local, finalized_timepoint, genesis_info::CKBGenesisInfo,
-    local_cells::LocalCellsManager, query_rollup_cell, since::Since,
-    transaction_skeleton::TransactionSkeleton, wallet::Wallet, RollupContext,
+    fee::{calculate_required_tx_fee, fill_tx_fee_with_local, CHANGE_CELL_CAPACITY},
+    finalized_timepoint,
+    genesis_info::CKBGenesisInfo,
+    local_cells::LocalCellsManager,
+    query_rollup_cell,
+    since::Since,
+    transaction_skeleton::TransactionSkeleton,
+    wallet::{sign_tx_skeleton_with_wallets, Wallet},
+    RollupContext,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
 };
-use std::{collections::HashSet, sync::Arc, time::Instant};
 use tokio::sync::Mutex;
 use tracing::instrument;
 
@@ -81,11 +95,13 @@ pub struct BlockProducer {
     chain: Arc<Mutex<Chain>>,
     generator: Arc<Generator>,
     wallet: Wallet,
+    funding_wallets: FundingWallets,
     config: BlockProducerConfig,
     rpc_client: RPCClient,
     ckb_genesis_info: CKBGenesisInfo,
     tests_control: Option<TestModeControl>,
     contracts_dep_manager: ContractsCellDepManager,
+    fee_estimator: FeeEstimator,
 }
 
 pub struct BlockProducerCreateArgs {
@@ -98,6 +114,7 @@ pub struct BlockProducerCreateArgs {
     pub config: BlockProducerConfig,
     pub tests_control: Option<TestModeControl>,
     pub contracts_dep_manager: ContractsCellDepManager,
+    pub fee_estimator: FeeEstimator,
 }
 
 impl BlockProducer {
@@ -112,12 +129,17 @@ impl BlockProducer {
             config,
             tests_control,
             contracts_dep_manager,
+            fee_estimator,
         } = args;
 
         let wallet = match config.wallet_config {
             Some(ref c) => Wallet::from_config(c).with_context(|| "init wallet")?,
             None => bail!("no wallet config for block producer"),
         };
+        let funding_wallets = FundingWallets::from_configs(
+            &config.funding_wallet_configs,
+            config.min_funding_wallet_balance,
+        )?;
 
         let block_producer = BlockProducer {
             rollup_config_hash,
@@ -125,11 +147,13 @@ impl BlockProducer {
             chain,
             rpc_client,
             wallet,
+            funding_wallets,
             ckb_genesis_info,
             config,
             tests_control,
             store,
             contracts_dep_manager,
+            fee_estimator,
         };
         Ok(block_producer)
     }
@@ -139,6 +163,7 @@ impl BlockProducer {
         &self,
         mem_pool: &mut MemPool,
         retry_count: usize,
+        local_cells_manager: &LocalCellsManager,
     ) -> Result<ProduceBlockResult> {
         if let Some(ref tests_control) = self.tests_control {
             match tests_control.payload().await {
@@ -148,8 +173,10 @@ impl BlockProducer {
             }
         }
 
-        // get txs & withdrawal requests from mem pool
-        let (mut mem_block, post_block_state) = {
+        // get txs & withdrawal requests from mem pool, which are read from
+        // the DB, so commit batched writes first
+        mem_pool.flush_writes()?;
+        let (mut mem_block, mut post_block_state) = {
             let t = Instant::now();
             let r = mem_pool.output_mem_block(&OutputParam::new(retry_count));
             log::debug!(
@@ -159,6 +186,22 @@ impl BlockProducer {
             r
         };
 
+        // Withdrawals that need more custodian inputs than allowed are left in
+        // the mem pool for later blocks.
+        let withdrawals_count = self
+            .count_withdrawals_within_custodian_inputs(mem_block.withdrawals(), local_cells_manager)
+            .await?;
+        if withdrawals_count < mem_block.withdrawals().len() {
+            log::warn!(
+                "[produce block] package {} of {} withdrawals, limited by max custodian inputs {}",
+                withdrawals_count,
+                mem_block.withdrawals().len(),
+                self.config.custodian_merge.max_custodian_inputs
+            );
+            let output_param = OutputParam::new(retry_count).max_withdrawals(withdrawals_count);
+            (mem_block, post_block_state) = mem_pool.output_mem_block(&output_param);
+        }
+
         let remaining_capacity = mem_block.take_finalized_custodians_capacity();
         let t = Instant::now();
         let block_param = generate_produce_block_param(&self.store, mem_block, post_block_state)?;
@@ -188,6 +231,62 @@ impl BlockProducer {
         Ok(result)
     }
 
+    /// Count the leading withdrawals that finalized custodians can cover
+    /// within `custodian_merge.max_custodian_inputs` inputs.
+    async fn count_withdrawals_within_custodian_inputs(
+        &self,
+        withdrawal_hashes: &[H256],
+        local_cells_manager: &LocalCellsManager,
+    ) -> Result<usize> {
+        if withdrawal_hashes.is_empty() {
+            return Ok(0);
+        }
+
+        let snap = self.store.get_snapshot();
+        let withdrawals = withdrawal_hashes
+            .iter()
+            .map(|hash| {
+                snap.get_mem_pool_withdrawal(hash)?
+                    .map(|w| w.request())
+                    .with_context(|| format!("mem pool withdrawal {} not found", hash.pack()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // The block is submitted on top of the tip, so custodians are
+        // finalized by the tip global state.
+        let rollup_context = self.generator.rollup_context();
+        let tip_global_state = {
+            let tip_hash = snap.get_last_valid_tip_block_hash()?;
+            snap.get_block_post_global_state(&tip_hash)?
+                .context("tip block post global state")?
+        };
+        let compatible_finalized_timepoint = CompatibleFinalizedTimepoint::from_global_state(
+            &tip_global_state,
+            rollup_context.rollup_config.finality_blocks().unpack(),
+        );
+        let finalized_custodians = gw_mem_pool::custodian::query_finalized_custodians(
+            &self.rpc_client,
+            &snap,
+            withdrawals.iter().cloned(),
+            rollup_context,
+            &compatible_finalized_timepoint,
+            local_cells_manager,
+        )
+        .await?
+        .expect_any();
+
+        Ok(count_withdrawals_within_inputs(
+            rollup_context,
+            &finalized_custodians.cells_info,
+            &withdrawals,
+            self.config.custodian_merge.max_custodian_inputs,
+        ))
+    }
+
+    pub async fn fee_rate(&self) -> u64 {
+        self.fee_estimator.fee_rate().await
+    }
+
     #[instrument(skip_all, fields(block = args.block.raw().number().unpack()))]
     pub async fn compose_submit_tx(&self, args: ComposeSubmitTxArgs<'_>) -> Result<Transaction> {
         let ComposeSubmitTxArgs {
@@ -363,6 +462,7 @@ impl BlockProducer {
             rpc_client,
             finalized_custodians,
             &prev_compatible_finalized_timepoint,
+            &self.config.custodian_merge,
         )
         .await?
         .expect_any();
@@ -385,6 +485,7 @@ impl BlockProducer {
             &block,
             &contracts_dep,
             &map_withdrawal_extras.collect(),
+            self.config.custodian_merge.max_custodian_inputs,
         )? {
             tx_skeleton
                 .cell_deps_mut()
@@ -458,22 +559,65 @@ impl BlockProducer {
             *tx_skeleton.cell_deps_mut() = deps.into_iter().cloned().collect();
         }
 
-        // tx fee cell
-        fill_tx_fee_with_local(
-            &mut tx_skeleton,
-            &self.rpc_client.indexer,
-            self.wallet.lock_script().to_owned(),
-            local_cells_manager,
-            self.config.fee_rate,
-        )
-        .await?;
+        // tx fee cell, paid by a funding wallet if any
+        let fee_rate = self.fee_estimator.fee_rate().await;
+        self.funding_wallets
+            .update_balances(&self.rpc_client.indexer, &self.wallet)
+            .await;
+        let mut fee_wallet = None;
+        for wallet in self.funding_wallets.candidates() {
+            let mut skeleton = tx_skeleton.clone();
+            let result = fill_tx_fee_with_local(
+                &mut skeleton,
+                &self.rpc_client.indexer,
+                wallet.lock_script().to_owned(),
+                local_cells_manager,
+                fee_rate,
+            )
+            .await;
+            match result {
+                Ok(()) => {
+                    tx_skeleton = skeleton;
+                    fee_wallet = Some(wallet);
+                    break;
+                }
+                Err(err) => {
+                    log::warn!(
+                        "[block producer] funding wallet {:x} failed to pay fee: {:#}",
+                        wallet.lock_script().hash().pack(),
+                        err
+                    );
+                    gw_metrics::block_producer().funding_wallet_failovers.inc();
+                }
+            }
+        }
+        if fee_wallet.is_none() {
+            if !self.funding_wallets.is_empty() {
+                log::warn!(
+                    "[block producer] no funding wallet available, use block producer wallet"
+                );
+            }
+            fill_tx_fee_with_local(
+                &mut tx_skeleton,
+                &self.rpc_client.indexer,
+                self.wallet.lock_script().to_owned(),
+                local_cells_manager,
+                fee_rate,
+            )
+            .await?;
+        }
         debug_assert_eq!(
             tx_skeleton.taken_outpoints()?.len(),
             tx_skeleton.inputs().len(),
             "check duplicated inputs"
         );
         // sign
-        let tx = self.wallet.sign_tx_skeleton(tx_skeleton)?;
+        let tx = match fee_wallet {
+            Some(fee_wallet) => {
+                sign_tx_skeleton_with_wallets(&[&self.wallet, fee_wallet], tx_skeleton)?
+            }
+            None => self.wallet.sign_tx_skeleton(tx_skeleton)?,
+        };
         ensure!(
             (tx.as_slice().len() as u64) < MAX_BLOCK_BYTES,
             TransactionSizeError::TransactionTooLarge
@@ -481,6 +625,147 @@ impl BlockProducer {
         log::debug!("final tx size: {}", tx.as_slice().len());
         Ok(tx)
     }
+
+    /// Refresh contracts cell deps, e.g. after contracts are upgraded, returns
+    /// `(old, new)` pairs of changed cell deps.
+    pub async fn refresh_contracts_cell_deps(&self) -> Result<Vec<(CellDep, CellDep)>> {
+        let changed = self.contracts_dep_manager.refresh().await?;
+        Ok({ changed.into_iter() }
+            .map(|(old, new)| (old.into(), new.into()))
+            .collect())
+    }
+
+    /// Rebuild submission txs in order with `fee_rate`, the extra fee is taken
+    /// from their change outputs.
+    ///
+    /// `replaced` maps hashes of replaced txs to their replacements. Inputs
+    /// spending outputs of replaced txs, including earlier txs of `txs`, are
+    /// updated to spend outputs of the replacements.
+    ///
+    /// Cell deps are replaced according to `(old, new)` pairs of `cell_deps`.
+    pub async fn rebuild_submit_txs(
+        &self,
+        txs: &[Transaction],
+        mut replaced: HashMap<H256, Transaction>,
+        cell_deps: &[(CellDep, CellDep)],
+        fee_rate: u64,
+    ) -> Result<Vec<Transaction>> {
+        let omni_lock_code_hash = self.contracts_dep_manager.load_scripts().omni_lock.hash();
+        let wallets: Vec<&Wallet> = std::iter::once(&self.wallet)
+            .chain(self.funding_wallets.wallets())
+            .collect();
+        let wallet_locks: HashSet<H256> = wallets.iter().map(|w| w.lock_script().hash()).collect();
+
+        let mut rebuilt = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let mut tx_skeleton = TransactionSkeleton::new(omni_lock_code_hash.0);
+            tx_skeleton
+                .cell_deps_mut()
+                .extend(tx.raw().cell_deps().into_iter().map(|dep| {
+                    { cell_deps.iter() }
+                        .find(|(old, _)| old.as_slice() == dep.as_slice())
+                        .map_or(dep, |(_, new)| new.clone())
+                }));
+            for witness in tx.witnesses().into_iter() {
+                let witness_args = WitnessArgs::from_slice(&witness.raw_data())
+                    .context("rebuild tx with non WitnessArgs witness")?;
+                tx_skeleton.witnesses_mut().push(witness_args);
+            }
+            for (index, input) in tx.raw().inputs().into_iter().enumerate() {
+                let mut out_point = input.previous_output();
+                if let Some(new_tx) = replaced.get(&out_point.tx_hash().unpack()) {
+                    out_point = out_point.as_builder().tx_hash(new_tx.hash().pack()).build();
+                }
+                let cell = match rebuilt
+                    .iter()
+                    .chain(replaced.values())
+                    .find(|t: &&Transaction| t.hash() == out_point.tx_hash().unpack())
+                {
+                    Some(t) => {
+                        let i: u32 = out_point.index().unpack();
+                        let output = t.raw().outputs().get(i as usize).context("get output")?;
+                        let data = t.raw().outputs_data().get(i as usize).context("get data")?;
+                        CellInfo {
+                            out_point: out_point.clone(),
+                            output,
+                            data: data.unpack(),
+                        }
+                    }
+                    None => self
+                        .rpc_client
+                        .get_cell(out_point.clone())
+                        .await?
+                        .and_then(|c| c.cell)
+                        .with_context(|| format!("input cell {} not found", out_point))?,
+                };
+                // Signatures are renewed.
+                let lock = cell.output.lock();
+                if wallet_locks.contains(&lock.hash())
+                    || lock.code_hash().as_slice() == &omni_lock_code_hash.0[..]
+                {
+                    if let Some(witness_args) = tx_skeleton.witnesses_mut().get_mut(index) {
+                        *witness_args = witness_args.clone().as_builder().lock(None.pack()).build();
+                    }
+                }
+                let input = input.as_builder().previous_output(out_point).build();
+                tx_skeleton.inputs_mut().push(InputCellInfo { input, cell });
+            }
+            tx_skeleton.outputs_mut().extend(
+                tx.raw()
+                    .outputs()
+                    .into_iter()
+                    .zip(tx.raw().outputs_data().into_iter().map(|d| d.unpack())),
+            );
+
+            pay_fee_from_change(&mut tx_skeleton, fee_rate, &wallet_locks)?;
+
+            let new_tx = sign_tx_skeleton_with_wallets(&wallets, tx_skeleton)?;
+            replaced.insert(tx.hash(), new_tx.clone());
+            rebuilt.push(new_tx);
+        }
+        Ok(rebuilt)
+    }
+}
+
+/// Returns the fee rate to rebuild a pending submission tx with, which is
+/// raised by at least 1 shannon/KB and capped at `max_fee_rate`.
+pub fn bump_fee_rate(config: &PscConfig, fee_rate: u64) -> u64 {
+    (fee_rate * config.fee_bump_percent / 100)
+        .max(fee_rate + 1)
+        .min(config.max_fee_rate)
+}
+
+/// Take the extra fee of `fee_rate` from the change output, i.e. the last one,
+/// which must be a plain cell locked by one of `wallet_locks`.
+///
+/// Nothing is changed if the tx already pays enough.
+pub fn pay_fee_from_change(
+    tx_skeleton: &mut TransactionSkeleton,
+    fee_rate: u64,
+    wallet_locks: &HashSet<H256>,
+) -> Result<()> {
+    let required_fee = calculate_required_tx_fee(tx_skeleton.tx_in_block_size()?, fee_rate);
+    let fee = tx_skeleton.calculate_fee()?;
+    if fee >= required_fee {
+        return Ok(());
+    }
+    let (change, data) = tx_skeleton.outputs_mut().last_mut().context("no output")?;
+    ensure!(
+        wallet_locks.contains(&change.lock().hash()) && change.type_().is_none() && data.is_empty(),
+        "no change output"
+    );
+    let capacity: u64 = change.capacity().unpack();
+    let capacity = capacity.saturating_sub(required_fee - fee);
+    ensure!(
+        capacity >= CHANGE_CELL_CAPACITY,
+        "insufficient change capacity to pay fee"
+    );
+    *change = change
+        .clone()
+        .as_builder()
+        .capacity(capacity.pack())
+        .build();
+    Ok(())
 }
 
 pub struct ComposeSubmitTxArgs<'a> {
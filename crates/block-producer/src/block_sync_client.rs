@@ -18,10 +18,11 @@ use gw_telemetry::{
 };
 use gw_types::{
     packed::{
-        BlockSync, BlockSyncReader, BlockSyncUnion, NumberHash, P2PSyncRequest,
-        P2PSyncResponseReader, P2PSyncResponseUnionReader, Script,
+        BlockSync, BlockSyncUnion, L2Transaction, L2TransactionVec, NumberHash, P2PSyncRequest,
+        P2PSyncResponseReader, P2PSyncResponseUnionReader, RelayTransactions, Script,
+        SequencedBlockSync, SequencedBlockSyncReader,
     },
-    prelude::Unpack,
+    prelude::{Pack, Unpack},
 };
 use gw_utils::{compression::StreamDecoder, liveness::Liveness};
 
@@ -30,7 +31,10 @@ use tentacle::{
     service::{ProtocolMeta, ServiceAsyncControl},
     SessionId, SubstreamReadPart,
 };
-use tokio::{sync::Mutex, task::block_in_place};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::block_in_place,
+};
 use tracing::{info_span, Instrument};
 
 use crate::{
@@ -48,6 +52,11 @@ pub struct BlockSyncClient {
     pub p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>>,
     pub completed_initial_syncing: bool,
     pub liveness: Arc<Liveness>,
+    /// Publisher id and sequence number of the last applied message, for
+    /// catching up after reconnecting.
+    pub last_applied: Option<(u64, u64)>,
+    /// Transactions submitted to this node, relayed to the full node.
+    pub tx_relay: Option<mpsc::Receiver<L2Transaction>>,
 }
 
 impl SyncL1Context for BlockSyncClient {
@@ -120,36 +129,49 @@ impl std::fmt::Display for RecoverableCtx {
 }
 
 async fn run_once_without_p2p_stream(client: &mut BlockSyncClient) -> Result<()> {
+    // Mem pool is reset, can't catch up with messages any more.
+    client.last_applied = None;
     sync_l1(client).await?;
     notify_new_tip(client, true).await?;
     Ok(())
 }
 
 async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStream) -> Result<()> {
-    loop {
-        sync_l1(client).await.context(RecoverableCtx)?;
-        notify_new_tip(client, false)
-            .await
-            .context(RecoverableCtx)?;
+    // Try catching up with missed messages first, without resetting mem pool.
+    let mut catch_up = client.last_applied.take();
+    let publisher_id = loop {
+        if catch_up.is_none() {
+            sync_l1(client).await.context(RecoverableCtx)?;
+            notify_new_tip(client, false)
+                .await
+                .context(RecoverableCtx)?;
+        }
+        let (publisher_id, last_sequence) = catch_up.take().unwrap_or_default();
         let last_confirmed = client
             .store
             .get_last_confirmed_block_number_hash()
             .context("last confirmed")?;
-        log::info!("request syncing from {}", last_confirmed.number().unpack());
+        log::info!(
+            "request syncing from {}, sequence {}",
+            last_confirmed.number().unpack(),
+            last_sequence
+        );
         let request = P2PSyncRequest::new_builder()
             .block_hash(last_confirmed.block_hash())
             .block_number(last_confirmed.number())
+            .publisher_id(publisher_id.pack())
+            .last_sequence(last_sequence.pack())
             .build();
         stream.send(request.as_bytes()).await?;
         let response = stream.recv().await?.context("unexpected end of stream")?;
         let response = P2PSyncResponseReader::from_slice(&response)?;
         match response.to_enum() {
-            P2PSyncResponseUnionReader::Found(_) => break,
+            P2PSyncResponseUnionReader::Found(f) => break f.publisher_id().unpack(),
             P2PSyncResponseUnionReader::TryAgain(_) => {}
         }
         log::info!("will try again");
         tokio::time::sleep(Duration::from_secs(3)).await;
-    }
+    };
     log::info!("receiving block sync messages from peer");
     let (tx, mut rx) = tokio::sync::mpsc::channel(1);
     let mut stream = stream.take_receiver();
@@ -159,15 +181,15 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
     // When there are too many messages in the buffer that haven't been applied,
     // we skip transactions and mem block messages till next block.
     let recv_handle = tokio::spawn(async move {
-        let mut buffer: VecDeque<BlockSync> = VecDeque::new();
+        let mut buffer: VecDeque<SequencedBlockSync> = VecDeque::new();
         let mut stream_ended = false;
         loop {
             tokio::select! {
                 biased;
                 recv_result = stream.recv(), if !stream_ended && buffer.len() < 1024 => {
                     if let Some(msg) = recv_result? {
-                        BlockSyncReader::from_slice(&msg[..])?;
-                        buffer.push_back(BlockSync::new_unchecked(msg));
+                        SequencedBlockSyncReader::from_slice(&msg[..])?;
+                        buffer.push_back(SequencedBlockSync::new_unchecked(msg));
                         if buffer.len() % 128 == 0 {
                             log::info!("receive buffer: {}", buffer.len());
                         }
@@ -185,13 +207,13 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
             }
             if buffer.len() >= 512
                 && matches!(
-                    buffer[buffer.len() - 1].to_enum(),
+                    buffer[buffer.len() - 1].message().to_enum(),
                     BlockSyncUnion::LocalBlock(_)
                 )
             {
                 log::warn!("receive buffer too large, skipping transactions and mem blocks");
                 #[allow(clippy::match_like_matches_macro)]
-                buffer.retain(|msg| match msg.to_enum() {
+                buffer.retain(|msg| match msg.message().to_enum() {
                     BlockSyncUnion::PushTransaction(_) => false,
                     BlockSyncUnion::NextMemBlock(_) => false,
                     _ => true,
@@ -204,13 +226,55 @@ async fn run_with_p2p_stream(client: &mut BlockSyncClient, stream: &mut P2PStrea
         }
         anyhow::Ok(())
     });
-    while let Some(msg) = rx.recv().await {
-        apply_msg(client, msg).await?;
+    loop {
+        let msg = tokio::select! {
+            msg = rx.recv() => msg,
+            Some(tx) = recv_relay(&mut client.tx_relay) => {
+                relay_transactions(client, stream, tx).await?;
+                continue;
+            }
+        };
+        let msg = match msg {
+            Some(msg) => msg,
+            None => break,
+        };
+        apply_msg(client, msg.message()).await?;
+        client.last_applied = Some((publisher_id, msg.sequence().unpack()));
     }
     recv_handle.await??;
     Ok(())
 }
 
+async fn recv_relay(tx_relay: &mut Option<mpsc::Receiver<L2Transaction>>) -> Option<L2Transaction> {
+    match tx_relay {
+        Some(rx) => rx.recv().await,
+        None => futures::future::pending().await,
+    }
+}
+
+/// Send `tx` and other pending transactions to the full node.
+async fn relay_transactions(
+    client: &mut BlockSyncClient,
+    stream: &mut P2PStream,
+    tx: L2Transaction,
+) -> Result<()> {
+    const MAX_RELAY_BATCH: usize = 64;
+
+    let mut txs = vec![tx];
+    if let Some(ref mut rx) = client.tx_relay {
+        while txs.len() < MAX_RELAY_BATCH {
+            match rx.try_recv() {
+                Ok(tx) => txs.push(tx),
+                Err(_) => break,
+            }
+        }
+    }
+    log::debug!("relay {} transactions", txs.len());
+    let txs = L2TransactionVec::new_builder().extend(txs).build();
+    let relay = RelayTransactions::new_builder().transactions(txs).build();
+    stream.send(relay.as_bytes()).await
+}
+
 async fn apply_msg(client: &mut BlockSyncClient, msg: BlockSync) -> Result<()> {
     match msg.to_enum() {
         BlockSyncUnion::Revert(r) => {
@@ -379,8 +443,8 @@ pub struct P2PStream {
 
 impl P2PStream {
     /// After calling this, you can only receive from the returned stream, and
-    /// self can only be used for disconnecting. (This is for receiving from
-    /// another task.)
+    /// self can only be used for sending and disconnecting. (This is for
+    /// receiving from another task.)
     fn take_receiver(&mut self) -> Self {
         Self {
             id: self.id,
@@ -0,0 +1,118 @@
+//! Assemble and send cancel challenge txs for challenges against valid blocks.
+//!
+//! The verify context, i.e. the challenged tx or withdrawal with its state
+//! proofs and scripts, is built from the store by chain sync when a challenge
+//! is found to be cancellable, see `SyncEvent::BadChallenge`.
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{Context, Result};
+use gw_challenge::{
+    cancel_challenge::{CancelChallengeOutput, LoadDataStrategy},
+    types::VerifyContext,
+};
+use gw_config::ContractsCellDep;
+use gw_rpc_client::{
+    error::{get_jsonrpc_error_code, CkbRpcError},
+    rpc_client::RPCClient,
+};
+use gw_types::{
+    h256::*,
+    offchain::CellInfo,
+    packed::{CellDep, GlobalState, Script, Transaction},
+    prelude::*,
+};
+use gw_utils::RollupContext;
+
+/// Attempts to send a cancel challenge or verifier tx.
+const SEND_ATTEMPTS: usize = 3;
+const SEND_RETRY_INTERVAL: Duration = Duration::from_secs(3);
+
+pub struct CancelChallengeArgs<'a> {
+    pub rollup_context: &'a RollupContext,
+    /// Global state of the halting rollup cell.
+    pub prev_global_state: GlobalState,
+    pub challenge_cell: &'a CellInfo,
+    pub burn_lock: Script,
+    /// Owner of the verifier cell and other cells created for the cancellation.
+    pub owner_lock: Script,
+    pub context: VerifyContext,
+    pub builtin_load_data: &'a HashMap<H256, CellDep>,
+    pub load_data_strategy: Option<LoadDataStrategy>,
+    pub contracts_dep: &'a ContractsCellDep,
+}
+
+pub struct CancelChallenge {
+    pub output: CancelChallengeOutput,
+    /// Cell dep of the verifier cell lock, i.e. the account lock of the
+    /// withdrawal or tx sender, or the backend of the tx receiver.
+    pub verifier_dep: CellDep,
+}
+
+/// Build outputs of the cancel challenge tx.
+///
+/// The verifier dep is resolved before any tx is sent, so that no verifier
+/// cell is created if it can't be unlocked.
+pub fn build_cancel_challenge(args: CancelChallengeArgs) -> Result<CancelChallenge> {
+    let CancelChallengeArgs {
+        rollup_context,
+        prev_global_state,
+        challenge_cell,
+        burn_lock,
+        owner_lock,
+        context,
+        builtin_load_data,
+        load_data_strategy,
+        contracts_dep,
+    } = args;
+
+    let output = gw_challenge::cancel_challenge::build_output(
+        rollup_context,
+        prev_global_state,
+        challenge_cell,
+        burn_lock,
+        owner_lock,
+        context,
+        builtin_load_data,
+        load_data_strategy,
+    )?;
+    let verifier_dep = output.verifier_dep(contracts_dep)?;
+
+    Ok(CancelChallenge {
+        output,
+        verifier_dep,
+    })
+}
+
+/// Send the tx, retrying on errors other than failing to resolve it, e.g.
+/// the challenge cell was consumed by another cancellation or revert.
+pub async fn send_with_retries(
+    rpc_client: &RPCClient,
+    tx: &Transaction,
+    action: &str,
+) -> Result<H256> {
+    let mut attempt = 1;
+    loop {
+        let err = match rpc_client.send_transaction(tx).await {
+            Ok(tx_hash) => return Ok(tx_hash),
+            Err(err) => err,
+        };
+        let code = get_jsonrpc_error_code(&err);
+        if code == Some(CkbRpcError::PoolRejectedDuplicatedTransaction as i64) {
+            return Ok(tx.hash());
+        }
+        if attempt >= SEND_ATTEMPTS || code == Some(CkbRpcError::TransactionFailedToResolve as i64)
+        {
+            return Err(err).with_context(|| format!("send {} tx", action));
+        }
+        log::warn!(
+            "[cancel challenge] send {} tx {} attempt {}: {:#}",
+            action,
+            tx.hash().pack(),
+            attempt,
+            err
+        );
+        attempt += 1;
+        tokio::time::sleep(SEND_RETRY_INTERVAL).await;
+    }
+}
@@ -1,5 +1,8 @@
 #![allow(clippy::mutable_key_type)]
 
+use crate::cancel_challenge::{
+    build_cancel_challenge, send_with_retries, CancelChallenge, CancelChallengeArgs,
+};
 use crate::cleaner::{Cleaner, Verifier};
 use crate::test_mode_control::TestModeControl;
 use crate::types::ChainEvent;
@@ -16,10 +19,11 @@ use gw_challenge::offchain::verify_tx::{verify_tx, TxWithContext};
 use gw_challenge::offchain::{mock_cancel_challenge_tx, OffChainMockContext};
 use gw_challenge::revert::Revert;
 use gw_challenge::types::{RevertContext, VerifyContext};
-use gw_config::{BlockProducerConfig, DebugConfig};
+use gw_config::{BlockProducerConfig, ChallengerMode, DebugConfig};
 use gw_generator::types::vm::ChallengeContext;
 use gw_jsonrpc_types::test_mode::TestModePayload;
 use gw_rpc_client::contract::ContractsCellDepManager;
+use gw_rpc_client::fee_estimator::FeeEstimator;
 use gw_rpc_client::rpc_client::RPCClient;
 use gw_types::bytes::Bytes;
 use gw_types::core::{ChallengeTargetType, Status};
@@ -60,7 +64,10 @@ pub struct Challenger {
     debug_config: DebugConfig,
     offchain_mock_context: OffChainMockContext,
     contracts_dep_manager: ContractsCellDepManager,
+    fee_estimator: FeeEstimator,
     last_submit_tx: Option<H256>,
+    /// Target of the last reported bad block.
+    last_bad_block: Option<ChallengeTarget>,
 }
 
 pub struct ChallengerNewArgs {
@@ -76,6 +83,7 @@ pub struct ChallengerNewArgs {
     pub cleaner: Arc<Cleaner>,
     pub offchain_mock_context: OffChainMockContext,
     pub contracts_dep_manager: ContractsCellDepManager,
+    pub fee_estimator: FeeEstimator,
 }
 
 impl Challenger {
@@ -93,6 +101,7 @@ impl Challenger {
             cleaner,
             offchain_mock_context,
             contracts_dep_manager,
+            fee_estimator,
         } = args;
 
         Self {
@@ -108,7 +117,9 @@ impl Challenger {
             cleaner,
             offchain_mock_context,
             contracts_dep_manager,
+            fee_estimator,
             last_submit_tx: None,
+            last_bad_block: None,
         }
     }
 
@@ -162,13 +173,7 @@ impl Challenger {
                         _ => unreachable!(),
                     }
                 }
-                {
-                    let hash = hex::encode::<[u8; 32]>(context.target.block_hash().unpack());
-                    let idx: u32 = context.target.target_index().unpack();
-                    let type_ = ChallengeTargetType::try_from(context.target.target_type())
-                        .map_err(|_| anyhow!("invalid challenge type"))?;
-                    log::info!("challenge block 0x{} target {} type {:?}", hash, idx, type_);
-                }
+                self.report_bad_block(&context.target)?;
                 self.challenge_block(rollup, context).await
             }
             SyncEvent::BadChallenge { cell, context } => {
@@ -199,6 +204,30 @@ impl Challenger {
         }
     }
 
+    /// Log the bad block found by re-executing submitted blocks, once for
+    /// each target.
+    fn report_bad_block(&mut self, target: &ChallengeTarget) -> Result<()> {
+        let hash = hex::encode::<[u8; 32]>(target.block_hash().unpack());
+        let idx: u32 = target.target_index().unpack();
+        let type_ = ChallengeTargetType::try_from(target.target_type())
+            .map_err(|_| anyhow!("invalid challenge type"))?;
+        let is_new = { self.last_bad_block.as_ref() }
+            .map_or(true, |last| last.as_slice() != target.as_slice());
+        if is_new {
+            log::error!(
+                "[challenger] bad block 0x{} target {} type {:?}, mode {:?}",
+                hash,
+                idx,
+                type_,
+                self.config.challenger_config.mode
+            );
+            gw_metrics::block_producer().bad_blocks.inc();
+            self.last_bad_block = Some(target.clone());
+        }
+        log::info!("challenge block 0x{} target {} type {:?}", hash, idx, type_);
+        Ok(())
+    }
+
     async fn challenge_block(
         &mut self,
         rollup_state: RollupState,
@@ -208,6 +237,10 @@ impl Challenger {
             // Already entered challenge
             return Ok(());
         }
+        let mode = self.config.challenger_config.mode;
+        if ChallengerMode::AlertOnly == mode {
+            return Ok(());
+        }
 
         let block_numer = context.witness.raw_l2block().number().unpack();
         let rewards_lock = {
@@ -252,7 +285,7 @@ impl Challenger {
             &mut tx_skeleton,
             &self.rpc_client.indexer,
             challenger_lock,
-            self.config.fee_rate,
+            self.fee_estimator.fee_rate().await,
         )
         .await?;
 
@@ -263,9 +296,18 @@ impl Challenger {
                 .await;
             bail!(err);
         }
+        if ChallengerMode::DryRun == mode {
+            log::info!(
+                "[challenger] dry run challenge block {} tx {}, not sent",
+                block_numer,
+                tx.hash().pack()
+            );
+            return Ok(());
+        }
 
         let tx_hash = self.rpc_client.send_transaction(&tx).await?;
         log::info!("Challenge block {} in tx {}", block_numer, to_hex(&tx_hash));
+        gw_metrics::block_producer().challenges.inc();
         self.last_submit_tx = Some(tx_hash);
 
         Ok(())
@@ -291,18 +333,22 @@ impl Challenger {
         )?;
 
         let challenge_cell = to_cell_info(challenge_cell);
-        let burn_lock = self.config.challenger_config.burn_lock.clone().into();
-        let owner_lock = self.wallet.lock_script().to_owned();
-        let mut cancel_output = gw_challenge::cancel_challenge::build_output(
-            &self.rollup_context,
-            prev_state,
-            &challenge_cell,
-            burn_lock,
-            owner_lock,
+        // Not to hold the guard across awaits.
+        let contracts_dep = Arc::clone(&self.contracts_dep_manager.load());
+        let CancelChallenge {
+            output: mut cancel_output,
+            verifier_dep,
+        } = build_cancel_challenge(CancelChallengeArgs {
+            rollup_context: &self.rollup_context,
+            prev_global_state: prev_state,
+            challenge_cell: &challenge_cell,
+            burn_lock: self.config.challenger_config.burn_lock.clone().into(),
+            owner_lock: self.wallet.lock_script().to_owned(),
             context,
-            &self.builtin_load_data,
-            Some(load_data_strategy),
-        )?;
+            builtin_load_data: &self.builtin_load_data,
+            load_data_strategy: Some(load_data_strategy),
+            contracts_dep: &contracts_dep,
+        })?;
 
         // Build verifier transaction
         let verifier_cell = cancel_output.verifier_cell.clone();
@@ -315,7 +361,7 @@ impl Challenger {
                 .await?
         };
         let verifier_spent_inputs = extract_inputs(&tx);
-        let verifier_tx_hash = self.rpc_client.send_transaction(&tx).await?;
+        let verifier_tx_hash = send_with_retries(&self.rpc_client, &tx, "verifier").await?;
         log::info!("Create verifier in tx {}", to_hex(&verifier_tx_hash));
 
         tokio::time::timeout(
@@ -328,8 +374,7 @@ impl Challenger {
         // Build cancellation transaction
         let challenge_input = to_input_cell_info(challenge_cell);
         let verifier_context = {
-            let contracts_dep = self.contracts_dep_manager.load();
-            let cell_dep = cancel_output.verifier_dep(&contracts_dep)?;
+            let cell_dep = verifier_dep;
             let input = cancel_output.verifier_input(verifier_tx_hash, 0);
             let witness = cancel_output.verifier_witness.clone();
             let load_data = cancel_output.load_data.take();
@@ -373,7 +418,7 @@ impl Challenger {
             verifier_context.input,
             verifier_context.witness,
         );
-        match self.rpc_client.send_transaction(&tx).await {
+        match send_with_retries(&self.rpc_client, &tx, "cancel challenge").await {
             Ok(tx_hash) => {
                 self.cleaner.watch_verifier(verifier, Some(tx_hash)).await;
                 log::info!("Cancel challenge in tx {}", to_hex(&tx_hash));
@@ -506,7 +551,7 @@ impl Challenger {
             &mut tx_skeleton,
             &self.rpc_client.indexer,
             challenger_lock,
-            self.config.fee_rate,
+            self.fee_estimator.fee_rate().await,
         )
         .await?;
 
@@ -549,7 +594,7 @@ impl Challenger {
             &mut tx_skeleton,
             &self.rpc_client.indexer,
             challenger_lock,
-            self.config.fee_rate,
+            self.fee_estimator.fee_rate().await,
         )
         .await?;
 
@@ -658,7 +703,7 @@ impl Challenger {
             &mut tx_skeleton,
             &self.rpc_client.indexer,
             owner_lock,
-            self.config.fee_rate,
+            self.fee_estimator.fee_rate().await,
         )
         .await?;
         self.wallet.sign_tx_skeleton(tx_skeleton)
@@ -6,6 +6,7 @@ use gw_utils::{fee::fill_tx_fee, wallet::Wallet};
 use anyhow::{anyhow, Result};
 use ckb_types::prelude::{Builder, Entity};
 use gw_challenge::cancel_challenge::RecoverAccountsContext;
+use gw_rpc_client::fee_estimator::FeeEstimator;
 use gw_rpc_client::rpc_client::RPCClient;
 use gw_types::core::Status;
 use gw_types::h256::*;
@@ -61,7 +62,7 @@ pub struct Cleaner {
     ckb_genesis_info: CKBGenesisInfo,
     wallet: Wallet,
     consumed_verifiers: ConsumedVerifiers,
-    fee_rate: u64,
+    fee_estimator: FeeEstimator,
 }
 
 impl Cleaner {
@@ -69,14 +70,14 @@ impl Cleaner {
         rpc_client: RPCClient,
         ckb_genesis_info: CKBGenesisInfo,
         wallet: Wallet,
-        fee_rate: u64,
+        fee_estimator: FeeEstimator,
     ) -> Self {
         Cleaner {
             rpc_client,
             ckb_genesis_info,
             wallet,
             consumed_verifiers: Arc::new(Mutex::new(Vec::new())),
-            fee_rate,
+            fee_estimator,
         }
     }
 
@@ -240,7 +241,7 @@ impl Cleaner {
             &mut tx_skeleton,
             &self.rpc_client.indexer,
             owner_lock,
-            self.fee_rate,
+            self.fee_estimator.fee_rate().await,
         )
         .await?;
         self.wallet.sign_tx_skeleton(tx_skeleton)
@@ -3,6 +3,7 @@
 use std::collections::HashSet;
 
 use anyhow::{anyhow, bail, Result};
+use gw_config::CustodianMergeConfig;
 use gw_rpc_client::{
     indexer_types::{Order, SearchKey, SearchKeyFilter},
     rpc_client::{QueryResult, RPCClient},
@@ -12,7 +13,7 @@ use gw_types::offchain::CompatibleFinalizedTimepoint;
 use gw_types::{
     core::ScriptHashType,
     offchain::{CellInfo, CollectedCustodianCells},
-    packed::{CustodianLockArgsReader, Script},
+    packed::{CellInput, CustodianLockArgsReader, Script},
     prelude::*,
 };
 use gw_utils::local_cells::{
@@ -20,16 +21,55 @@ use gw_utils::local_cells::{
 };
 use tracing::instrument;
 
-pub const MAX_CUSTODIANS: usize = 50;
-
 #[instrument(skip_all, err(Debug), fields(timepoint = ?compatible_finalized_timepoint))]
 pub async fn query_mergeable_custodians(
     local_cells_manager: &LocalCellsManager,
     rpc_client: &RPCClient,
     collected_custodians: CollectedCustodianCells,
     compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+    merge_config: &CustodianMergeConfig,
+) -> Result<QueryResult<CollectedCustodianCells>> {
+    let withdrawal_custodians = collected_custodians.cells_info.len();
+    // Custodian inputs have no witnesses, so each merged cell only adds an input.
+    let max_cells = merge_config
+        .max_custodian_inputs
+        .min(withdrawal_custodians + merge_config.max_merge_tx_size / CellInput::TOTAL_SIZE);
+    let query_result = query_mergeable_custodians_inner(
+        local_cells_manager,
+        rpc_client,
+        collected_custodians,
+        compatible_finalized_timepoint,
+        merge_config.min_merge_cells,
+        max_cells,
+    )
+    .await?;
+
+    let metrics = gw_metrics::block_producer();
+    let custodians = match query_result {
+        QueryResult::Full(ref c) => {
+            metrics.custodian_merge_full.inc();
+            c
+        }
+        QueryResult::NotEnough(ref c) => c,
+    };
+    let inputs = custodians.cells_info.len();
+    metrics.custodian_inputs.set(inputs as u64);
+    metrics
+        .merged_custodians
+        .inc_by(inputs.saturating_sub(withdrawal_custodians) as u64);
+
+    Ok(query_result)
+}
+
+async fn query_mergeable_custodians_inner(
+    local_cells_manager: &LocalCellsManager,
+    rpc_client: &RPCClient,
+    collected_custodians: CollectedCustodianCells,
+    compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+    min_merge_cells: usize,
+    max_cells: usize,
 ) -> Result<QueryResult<CollectedCustodianCells>> {
-    if collected_custodians.cells_info.len() >= MAX_CUSTODIANS {
+    if collected_custodians.cells_info.len() >= max_cells {
         return Ok(QueryResult::Full(collected_custodians));
     }
 
@@ -38,7 +78,8 @@ pub async fn query_mergeable_custodians(
         rpc_client,
         collected_custodians,
         compatible_finalized_timepoint,
-        MAX_CUSTODIANS,
+        min_merge_cells,
+        max_cells,
     )
     .await?;
     if matches!(query_result, QueryResult::Full(_)) {
@@ -50,6 +91,8 @@ pub async fn query_mergeable_custodians(
         query_result.expect_any(),
         compatible_finalized_timepoint,
         local_cells_manager,
+        min_merge_cells,
+        max_cells,
     )
     .await
 }
@@ -60,8 +103,10 @@ async fn query_mergeable_sudt_custodians(
     collected: CollectedCustodianCells,
     compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
     local_cells_manager: &LocalCellsManager,
+    min_merge_cells: usize,
+    max_cells: usize,
 ) -> Result<QueryResult<CollectedCustodianCells>> {
-    if collected.cells_info.len() >= MAX_CUSTODIANS {
+    if collected.cells_info.len() >= max_cells {
         return Ok(QueryResult::Full(collected));
     }
 
@@ -70,7 +115,8 @@ async fn query_mergeable_sudt_custodians(
         rpc_client,
         collected,
         compatible_finalized_timepoint,
-        MAX_CUSTODIANS,
+        min_merge_cells,
+        max_cells,
     )
     .await
 }
@@ -80,14 +126,14 @@ async fn query_mergeable_ckb_custodians(
     rpc_client: &RPCClient,
     mut collected: CollectedCustodianCells,
     compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+    min_merge_cells: usize,
     max_cells: usize,
 ) -> Result<QueryResult<CollectedCustodianCells>> {
-    const MIN_MERGE_CELLS: usize = 5;
-    log::debug!("ckb merge MIN_MERGE_CELLS {}", MIN_MERGE_CELLS);
+    log::debug!("ckb merge min_merge_cells {}", min_merge_cells);
 
     let remain = max_cells.saturating_sub(collected.cells_info.len());
-    if remain < MIN_MERGE_CELLS {
-        log::debug!("ckb merge break remain < `MIN_MERGE_CELLS`");
+    if remain < min_merge_cells {
+        log::debug!("ckb merge break remain < `min_merge_cells`");
         return Ok(QueryResult::NotEnough(collected));
     }
 
@@ -110,7 +156,7 @@ async fn query_mergeable_ckb_custodians(
         cells.map(|i| i.out_point.clone()).collect()
     };
 
-    let mut cursor = CollectLocalAndIndexerCursor::Local;
+    let mut cursor = CollectLocalAndIndexerCursor::LocalThenCached;
     let mut collected_ckb_custodians = Vec::<CellInfo>::with_capacity(remain);
     while collected_ckb_custodians.len() < remain && !cursor.is_ended() {
         let cells = collect_local_and_indexer_cells(
@@ -151,8 +197,8 @@ async fn query_mergeable_ckb_custodians(
         }
     }
 
-    if collected_ckb_custodians.len() < MIN_MERGE_CELLS {
-        log::debug!("not enough `MIN_MERGE_CELLS` ckb custodians");
+    if collected_ckb_custodians.len() < min_merge_cells {
+        log::debug!("not enough `min_merge_cells` ckb custodians");
         return Ok(QueryResult::NotEnough(collected));
     }
 
@@ -177,19 +223,19 @@ pub async fn query_mergeable_sudt_custodians_cells(
     rpc_client: &RPCClient,
     mut collected: CollectedCustodianCells,
     compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+    min_merge_cells: usize,
     max_cells: usize,
 ) -> Result<QueryResult<CollectedCustodianCells>> {
     const MAX_MERGE_SUDTS: usize = 5;
-    const MIN_MERGE_CELLS: usize = 5;
     log::debug!(
-        "sudt merge MIN_MERGE_CELLS {} MAX_MERGE_SUDTS {}",
-        MIN_MERGE_CELLS,
+        "sudt merge min_merge_cells {} MAX_MERGE_SUDTS {}",
+        min_merge_cells,
         MAX_MERGE_SUDTS
     );
 
     let mut remain = max_cells.saturating_sub(collected.cells_info.len());
-    if remain < MIN_MERGE_CELLS {
-        log::debug!("sudt merge break remain < `MIN_MERGE_CELLS`");
+    if remain < min_merge_cells {
+        log::debug!("sudt merge break remain < `min_merge_cells`");
         return Ok(QueryResult::NotEnough(collected));
     }
 
@@ -279,9 +325,9 @@ pub async fn query_mergeable_sudt_custodians_cells(
         }
 
         remain = max_cells.saturating_sub(collected.cells_info.len());
-        if remain < MIN_MERGE_CELLS {
+        if remain < min_merge_cells {
             log::debug!(
-                "break `MIN_MERGE_CELLS` after sudt {} merge",
+                "break `min_merge_cells` after sudt {} merge",
                 ckb_types::H256(sudt_type_script.hash())
             );
             break;
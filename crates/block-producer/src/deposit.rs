@@ -5,8 +5,8 @@ use gw_types::bytes::Bytes;
 use gw_types::core::ScriptHashType;
 use gw_types::offchain::{CellInfo, InputCellInfo};
 use gw_types::packed::{
-    CellDep, CellInput, CellOutput, CustodianLockArgs, Script, UnlockCustodianViaRevertWitness,
-    WitnessArgs,
+    CellDep, CellInput, CellOutput, CustodianLockArgs, DepositLockArgs, Script,
+    UnlockCustodianViaRevertWitness, WitnessArgs,
 };
 use gw_types::prelude::{Builder, Pack, Unpack};
 use gw_utils::RollupContext;
@@ -98,3 +98,66 @@ pub fn revert(
         witness_args: custodian_witness,
     }))
 }
+
+pub struct RefundedDeposits {
+    pub deps: Vec<CellDep>,
+    pub inputs: Vec<InputCellInfo>,
+    pub outputs: Vec<(CellOutput, Bytes)>,
+}
+
+/// Unlock deposit cells to `owner_lock` via the cancel path.
+///
+/// The caller must add an input locked by `owner_lock`, and the cancel
+/// timeouts of the deposit cells should have passed.
+pub fn refund(
+    contracts_dep: &ContractsCellDep,
+    owner_lock: &Script,
+    deposit_cells: Vec<CellInfo>,
+) -> Result<Option<RefundedDeposits>> {
+    if deposit_cells.is_empty() {
+        return Ok(None);
+    }
+
+    let mut deposit_inputs = vec![];
+    let mut owner_outputs = vec![];
+    for deposit_cell in deposit_cells {
+        let args: Bytes = deposit_cell.output.lock().args().unpack();
+        let deposit_lock_args = DepositLockArgs::from_slice(&args.slice(32..))?;
+
+        let owner_output = {
+            let output_builder = deposit_cell.output.clone().as_builder();
+            output_builder.lock(owner_lock.clone()).build()
+        };
+
+        let deposit_input = {
+            let input = CellInput::new_builder()
+                .previous_output(deposit_cell.out_point.clone())
+                .since(deposit_lock_args.cancel_timeout())
+                .build();
+
+            InputCellInfo {
+                input,
+                cell: deposit_cell.clone(),
+            }
+        };
+
+        deposit_inputs.push(deposit_input);
+        owner_outputs.push((owner_output, deposit_cell.data));
+    }
+
+    let deposit_lock_dep = contracts_dep.deposit_cell_lock.clone();
+    let sudt_type_dep = contracts_dep.l1_sudt_type.clone();
+    let mut cell_deps = vec![deposit_lock_dep.into()];
+    if deposit_inputs
+        .iter()
+        .any(|info| info.cell.output.type_().to_opt().is_some())
+    {
+        cell_deps.push(sudt_type_dep.into())
+    }
+
+    Ok(Some(RefundedDeposits {
+        deps: cell_deps,
+        inputs: deposit_inputs,
+        outputs: owner_outputs,
+    }))
+}
@@ -0,0 +1,163 @@
+//! Refund deposit cells whose cancel timeout has passed to their owners.
+
+use anyhow::{anyhow, Result};
+use gw_config::ContractsCellDep;
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_types::bytes::Bytes;
+use gw_types::offchain::CellInfo;
+use gw_types::packed::{DepositLockArgs, Transaction};
+use gw_types::prelude::*;
+use gw_utils::fee::fill_tx_fee;
+use gw_utils::genesis_info::CKBGenesisInfo;
+use gw_utils::since::{EpochNumberWithFraction, LockValue, Since};
+use gw_utils::transaction_skeleton::TransactionSkeleton;
+use gw_utils::wallet::Wallet;
+
+pub struct DepositRefunder<'a> {
+    pub rpc_client: &'a RPCClient,
+    pub ckb_genesis_info: &'a CKBGenesisInfo,
+    pub contracts_dep: &'a ContractsCellDep,
+    /// Wallet of the depositor, its lock hash must be the `owner_lock_hash`
+    /// of the deposits.
+    pub wallet: &'a Wallet,
+    pub fee_rate: u64,
+}
+
+impl<'a> DepositRefunder<'a> {
+    /// Query deposit cells owned by the wallet whose cancel timeout has passed.
+    pub async fn query_refundable_deposits(&self, max_cells: usize) -> Result<Vec<CellInfo>> {
+        let owner_lock_hash = self.wallet.lock_script().hash();
+        let deposit_cells = self
+            .rpc_client
+            .query_owner_deposit_cells(&owner_lock_hash, max_cells)
+            .await?;
+        log::info!(
+            "[refund deposit] found {} deposit cells of owner {}",
+            deposit_cells.len(),
+            owner_lock_hash.pack()
+        );
+
+        let tip_number = self.rpc_client.get_tip().await?.number().unpack();
+        let mut refundable = Vec::with_capacity(deposit_cells.len());
+        for (cell, block_number) in deposit_cells {
+            let args: Bytes = cell.output.lock().args().unpack();
+            let cancel_timeout = DepositLockArgs::new_unchecked(args.slice(32..))
+                .cancel_timeout()
+                .unpack();
+            if self
+                .is_cancel_timeout_passed(Since::new(cancel_timeout), block_number, tip_number)
+                .await?
+            {
+                refundable.push(cell);
+            } else {
+                log::debug!(
+                    "[refund deposit] skip {:?}, cancel timeout not passed",
+                    cell.out_point
+                );
+            }
+        }
+        Ok(refundable)
+    }
+
+    /// Build and sign a tx unlocking `deposit_cells` to the wallet.
+    pub async fn build_refund_tx(
+        &self,
+        deposit_cells: Vec<CellInfo>,
+    ) -> Result<Option<Transaction>> {
+        let owner_lock = self.wallet.lock_script();
+        let refunded = match crate::deposit::refund(self.contracts_dep, owner_lock, deposit_cells)?
+        {
+            Some(refunded) => refunded,
+            None => return Ok(None),
+        };
+
+        let mut tx_skeleton = TransactionSkeleton::default();
+        tx_skeleton.cell_deps_mut().extend(refunded.deps);
+        tx_skeleton
+            .cell_deps_mut()
+            .push(self.ckb_genesis_info.sighash_dep());
+        tx_skeleton.inputs_mut().extend(refunded.inputs);
+        tx_skeleton.outputs_mut().extend(refunded.outputs);
+
+        // Paying the fee also adds the owner input required by the cancel path.
+        fill_tx_fee(
+            &mut tx_skeleton,
+            &self.rpc_client.indexer,
+            owner_lock.to_owned(),
+            self.fee_rate,
+        )
+        .await?;
+        let tx = self.wallet.sign_tx_skeleton(tx_skeleton)?;
+        Ok(Some(tx))
+    }
+
+    /// Whether an input with `since` spending a cell created at
+    /// `block_number` is mature at the tip. Timestamps are checked against
+    /// block median times, the same as CKB does.
+    async fn is_cancel_timeout_passed(
+        &self,
+        since: Since,
+        block_number: u64,
+        tip_number: u64,
+    ) -> Result<bool> {
+        if !since.flags_is_valid() {
+            return Ok(false);
+        }
+        let lock_value = match since.extract_lock_value() {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+
+        let passed = match lock_value {
+            LockValue::BlockNumber(n) if since.is_relative() => {
+                block_number.saturating_add(n) <= tip_number
+            }
+            LockValue::BlockNumber(n) => n <= tip_number,
+            LockValue::EpochNumberWithFraction(epoch) => {
+                let tip_epoch = self.header_epoch_number(tip_number).await?;
+                // Round a fractional epoch up to be conservative.
+                let epochs = epoch.number() + u64::from(epoch.index() > 0);
+                if since.is_relative() {
+                    let epoch = self.header_epoch_number(block_number).await?;
+                    epoch.saturating_add(epochs) <= tip_epoch
+                } else {
+                    epochs <= tip_epoch
+                }
+            }
+            LockValue::Timestamp(millis) => {
+                let tip_time = self.median_time(tip_number).await?;
+                if since.is_relative() {
+                    let time = self.median_time(block_number).await?;
+                    time.saturating_add(millis) <= tip_time
+                } else {
+                    millis <= tip_time
+                }
+            }
+        };
+        Ok(passed)
+    }
+
+    async fn header_epoch_number(&self, number: u64) -> Result<u64> {
+        let header = self
+            .rpc_client
+            .get_header_by_number(number)
+            .await?
+            .ok_or_else(|| anyhow!("block {} not found", number))?;
+        let epoch: u64 = header.inner.epoch.into();
+        Ok(EpochNumberWithFraction::from_full_value(epoch).number())
+    }
+
+    async fn median_time(&self, number: u64) -> Result<u64> {
+        let header = self
+            .rpc_client
+            .get_header_by_number(number)
+            .await?
+            .ok_or_else(|| anyhow!("block {} not found", number))?;
+        let median_time = self
+            .rpc_client
+            .get_block_median_time(header.hash.0)
+            .await?
+            .ok_or_else(|| anyhow!("median time of block {} not found", number))?;
+        Ok(median_time.as_millis() as u64)
+    }
+}
@@ -0,0 +1,225 @@
+//! Health checks of a rollup node and its L1 dependencies.
+
+use anyhow::{anyhow, bail, Result};
+use gw_config::Config;
+use gw_rpc_client::{contract::query_cell_deps, indexer_types::SearchKey, rpc_client::RPCClient};
+use gw_store::{readonly::StoreReadonly, schema::COLUMNS, traits::chain_store::ChainStore};
+use gw_types::{
+    offchain::global_state_from_slice,
+    packed::{GlobalState, Script},
+    prelude::*,
+};
+
+use crate::runner::build_rpc_client;
+
+const ONE_CKB: u64 = 100_000_000;
+
+pub struct DoctorArgs {
+    /// Max blocks the indexer tip may be behind the CKB tip.
+    pub max_indexer_lag: u64,
+    /// Max blocks the local L2 tip may be behind the L2 tip on L1.
+    pub max_block_lag: u64,
+    /// Min block producer wallet balance, in shannons.
+    pub min_wallet_balance: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skip,
+}
+
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, result: Result<Option<String>>) -> Self {
+        let (status, detail) = match result {
+            Ok(Some(detail)) => (CheckStatus::Pass, detail),
+            Ok(None) => (CheckStatus::Skip, "not configured".to_string()),
+            Err(err) => (CheckStatus::Fail, format!("{:#}", err)),
+        };
+        CheckResult {
+            name,
+            status,
+            detail,
+        }
+    }
+}
+
+/// Run all checks. Checks never return early, every check is reported.
+pub async fn diagnose(config: &Config, args: &DoctorArgs) -> Vec<CheckResult> {
+    let rpc_client = match build_rpc_client(config) {
+        Ok(rpc_client) => rpc_client,
+        Err(err) => {
+            return vec![CheckResult::new("rpc client", Err(err))];
+        }
+    };
+
+    let mut results = Vec::new();
+    let ckb_tip = check_ckb(&rpc_client).await;
+    let ckb_tip_number = ckb_tip.as_ref().ok().copied();
+    results.push(CheckResult::new(
+        "ckb rpc",
+        ckb_tip.map(|tip| Some(format!("tip block {}", tip))),
+    ));
+    results.push(CheckResult::new(
+        "ckb indexer",
+        check_indexer(&rpc_client, ckb_tip_number, args.max_indexer_lag).await,
+    ));
+
+    let global_state = query_global_state(&rpc_client).await;
+    let global_state_ok = global_state.as_ref().ok().cloned();
+    results.push(CheckResult::new(
+        "rollup cell",
+        global_state.and_then(|global_state| check_global_state(config, &global_state)),
+    ));
+    results.push(CheckResult::new(
+        "contracts cell deps",
+        check_contracts_cell_deps(config, &rpc_client).await,
+    ));
+    results.push(CheckResult::new(
+        "wallet balance",
+        check_wallet_balance(config, &rpc_client, args.min_wallet_balance).await,
+    ));
+    results.push(CheckResult::new(
+        "store tip",
+        check_store_tip(config, global_state_ok.as_ref(), args.max_block_lag),
+    ));
+    results
+}
+
+async fn check_ckb(rpc_client: &RPCClient) -> Result<u64> {
+    rpc_client.get_ckb_version().await?;
+    rpc_client.ckb.get_tip_block_number().await
+}
+
+async fn check_indexer(
+    rpc_client: &RPCClient,
+    ckb_tip_number: Option<u64>,
+    max_lag: u64,
+) -> Result<Option<String>> {
+    let indexer_tip: u64 = rpc_client.indexer.get_tip().await?.number().unpack();
+    let ckb_tip_number = match ckb_tip_number {
+        Some(number) => number,
+        None => bail!("indexer tip {}, ckb tip unknown", indexer_tip),
+    };
+    let lag = ckb_tip_number.saturating_sub(indexer_tip);
+    if lag > max_lag {
+        bail!(
+            "indexer tip {} is {} blocks behind ckb tip {}, check the indexer is syncing",
+            indexer_tip,
+            lag,
+            ckb_tip_number
+        );
+    }
+    Ok(Some(format!(
+        "tip block {}, {} blocks behind",
+        indexer_tip, lag
+    )))
+}
+
+async fn query_global_state(rpc_client: &RPCClient) -> Result<GlobalState> {
+    let rollup_cell = rpc_client
+        .query_rollup_cell()
+        .await?
+        .ok_or_else(|| anyhow!("rollup cell not found, check chain.rollup_type_script"))?;
+    Ok(global_state_from_slice(&rollup_cell.data)?)
+}
+
+fn check_global_state(config: &Config, global_state: &GlobalState) -> Result<Option<String>> {
+    let version = global_state.version().as_slice()[0];
+    let tip_number = global_state.block().count().unpack().saturating_sub(1);
+    let expected_version = config.fork.global_state_version(tip_number);
+    if version != expected_version {
+        bail!(
+            "global state version {} at block {}, expected {}, check fork.upgrade_global_state_version_to_v2",
+            version,
+            tip_number,
+            expected_version
+        );
+    }
+    Ok(Some(format!(
+        "global state version {}, tip block {}",
+        version, tip_number
+    )))
+}
+
+async fn check_contracts_cell_deps(
+    config: &Config,
+    rpc_client: &RPCClient,
+) -> Result<Option<String>> {
+    let block_producer_config = match config.block_producer {
+        Some(ref c) => c,
+        None => return Ok(None),
+    };
+    query_cell_deps(
+        rpc_client,
+        &config.consensus.contract_type_scripts,
+        &block_producer_config.contracts_dep_groups,
+        block_producer_config.rollup_config_cell_dep.clone(),
+    )
+    .await?;
+    Ok(Some("all resolvable".to_string()))
+}
+
+async fn check_wallet_balance(
+    config: &Config,
+    rpc_client: &RPCClient,
+    min_balance: u64,
+) -> Result<Option<String>> {
+    let wallet_config = match config
+        .block_producer
+        .as_ref()
+        .and_then(|c| c.wallet_config.as_ref())
+    {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+    let lock: Script = wallet_config.lock.clone().into();
+    let balance = rpc_client
+        .indexer
+        .get_cells_capacity(&SearchKey::with_lock(lock))
+        .await?;
+    if balance < min_balance {
+        bail!(
+            "balance {} CKB is less than {} CKB, top up the block producer wallet",
+            balance / ONE_CKB,
+            min_balance / ONE_CKB
+        );
+    }
+    Ok(Some(format!("{} CKB", balance / ONE_CKB)))
+}
+
+fn check_store_tip(
+    config: &Config,
+    global_state: Option<&GlobalState>,
+    max_lag: u64,
+) -> Result<Option<String>> {
+    if !config.store.path.exists() {
+        return Ok(None);
+    }
+    let store = StoreReadonly::open(&config.store.path, COLUMNS)?;
+    let local_tip: u64 = store.get_last_valid_tip_block()?.raw().number().unpack();
+    let l1_tip = match global_state {
+        Some(global_state) => global_state.block().count().unpack().saturating_sub(1),
+        None => bail!("local tip block {}, L1 tip unknown", local_tip),
+    };
+    let lag = l1_tip.saturating_sub(local_tip);
+    if lag > max_lag {
+        bail!(
+            "local tip block {} is {} blocks behind L1 tip block {}, check the node is syncing",
+            local_tip,
+            lag,
+            l1_tip
+        );
+    }
+    Ok(Some(format!(
+        "local tip block {}, L1 tip block {}",
+        local_tip, l1_tip
+    )))
+}
@@ -0,0 +1,130 @@
+//! Wallets paying fees of block submission txs.
+//!
+//! Funding wallets are tried in round-robin order. Wallets known to have less
+//! than `min_funding_wallet_balance` are skipped, and the next wallet is tried
+//! if one fails to pay the fee. The block producer wallet is the last resort.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use gw_config::WalletConfig;
+use gw_rpc_client::{indexer_client::CKBIndexerClient, indexer_types::SearchKey};
+use gw_types::prelude::*;
+use gw_utils::wallet::Wallet;
+
+const BALANCE_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+const UNKNOWN_BALANCE: u64 = u64::MAX;
+
+struct FundingWallet {
+    wallet: Wallet,
+    /// Last queried balance, `UNKNOWN_BALANCE` if not queried yet.
+    balance: AtomicU64,
+}
+
+pub struct FundingWallets {
+    wallets: Vec<FundingWallet>,
+    next: AtomicUsize,
+    min_balance: u64,
+    balances_updated_at: Mutex<Option<Instant>>,
+}
+
+impl FundingWallets {
+    pub fn from_configs(configs: &[WalletConfig], min_balance: u64) -> Result<Self> {
+        let wallets = configs
+            .iter()
+            .map(|c| {
+                let wallet = Wallet::from_config(c).with_context(|| "funding wallet")?;
+                Ok(FundingWallet {
+                    wallet,
+                    balance: AtomicU64::new(UNKNOWN_BALANCE),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(FundingWallets {
+            wallets,
+            next: AtomicUsize::new(0),
+            min_balance,
+            balances_updated_at: Mutex::new(None),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wallets.is_empty()
+    }
+
+    pub fn wallets(&self) -> impl Iterator<Item = &Wallet> {
+        self.wallets.iter().map(|w| &w.wallet)
+    }
+
+    /// Funding wallets to try in order, starting from the next one in
+    /// round-robin order and skipping wallets with low balance.
+    pub fn candidates(&self) -> Vec<&Wallet> {
+        if self.wallets.is_empty() {
+            return Vec::new();
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.wallets.len();
+        let (tail, head) = self.wallets.split_at(start);
+        { head.iter().chain(tail) }
+            .filter(|w| {
+                let balance = w.balance.load(Ordering::Relaxed);
+                balance == UNKNOWN_BALANCE || balance >= self.min_balance
+            })
+            .map(|w| &w.wallet)
+            .collect()
+    }
+
+    /// Query balances of the funding wallets and `producer_wallet`, at most
+    /// once per `BALANCE_UPDATE_INTERVAL`, and update the balance metrics.
+    pub async fn update_balances(&self, indexer: &CKBIndexerClient, producer_wallet: &Wallet) {
+        {
+            let mut updated_at = self.balances_updated_at.lock().unwrap();
+            if matches!(*updated_at, Some(t) if t.elapsed() < BALANCE_UPDATE_INTERVAL) {
+                return;
+            }
+            *updated_at = Some(Instant::now());
+        }
+
+        match query_balance(indexer, producer_wallet).await {
+            Ok(balance) => gw_metrics::block_producer().wallet_balance(
+                "block_producer",
+                &producer_wallet.lock_script().hash(),
+                balance,
+            ),
+            Err(err) => log::warn!("[funding wallets] query block producer balance: {:#}", err),
+        }
+        for w in &self.wallets {
+            match query_balance(indexer, &w.wallet).await {
+                Ok(balance) => {
+                    w.balance.store(balance, Ordering::Relaxed);
+                    gw_metrics::block_producer().wallet_balance(
+                        "funding",
+                        &w.wallet.lock_script().hash(),
+                        balance,
+                    );
+                    if balance < self.min_balance {
+                        log::warn!(
+                            "[funding wallets] skip wallet {:x}, balance {} is lower than {}",
+                            w.wallet.lock_script().hash().pack(),
+                            balance,
+                            self.min_balance
+                        );
+                    }
+                }
+                Err(err) => log::warn!("[funding wallets] query balance: {:#}", err),
+            }
+        }
+    }
+}
+
+async fn query_balance(indexer: &CKBIndexerClient, wallet: &Wallet) -> Result<u64> {
+    let lock = wallet.lock_script().to_owned();
+    indexer
+        .get_cells_capacity(&SearchKey::with_lock(lock))
+        .await
+}
@@ -1,19 +1,27 @@
+pub mod alerts;
 pub mod block_producer;
 pub mod block_sync_client;
+pub mod cancel_challenge;
 pub mod chain_updater;
 pub mod challenger;
 pub mod cleaner;
 pub mod custodian;
 pub mod debugger;
 pub mod deposit;
+pub mod deposit_refund;
+pub mod doctor;
+pub mod funding_wallets;
 pub mod produce_block;
 pub(crate) mod psc;
 pub mod replay_block;
 pub mod runner;
+pub mod shutdown;
 pub mod stake;
+pub mod stake_manager;
 pub mod sync_l1;
 pub mod test_mode_control;
 pub mod types;
 pub mod utils;
+pub mod watch_notifier;
 pub mod withdrawal;
 pub mod withdrawal_unlocker;
@@ -1,23 +1,38 @@
 #![allow(clippy::mutable_key_type)]
 
-use std::{collections::HashSet, fmt::Display, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::{bail, ensure, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use ckb_fixed_hash::H256 as JsonH256;
 use gw_chain::chain::Chain;
 use gw_config::PscConfig;
+use gw_dynamic_config::manager::DynamicConfigManager;
+use gw_generator::ArcSwap;
+use gw_jsonrpc_types::godwoken::L2BlockView;
 use gw_mem_pool::{block_sync_server::BlockSyncServerState, pool::MemPool};
 use gw_rpc_client::{
     error::{get_jsonrpc_error_code, CkbRpcError},
     rpc_client::RPCClient,
 };
 use gw_store::{snapshot::StoreSnapshot, traits::chain_store::ChainStore, Store};
-use gw_telemetry::traits::{OpenTelemetrySpanExt, TraceContextExt};
+use gw_telemetry::{
+    alert::{alert, Alert},
+    traits::{OpenTelemetrySpanExt, TraceContextExt},
+};
 use gw_types::{
+    core::DepType,
     h256::*,
     offchain::{CellStatus, DepositInfo, TxStatus},
     packed::{
-        self, Confirmed, GlobalState, LocalBlock, NumberHash, OutPoint, Revert, Script, ScriptVec,
-        Submitted, Transaction, WithdrawalKey,
+        self, CellDep, Confirmed, GlobalState, LocalBlock, NumberHash, OutPoint, OutPointVec,
+        Revert, Script, ScriptVec, Submitted, Transaction, WithdrawalKey,
     },
     prelude::*,
 };
@@ -33,13 +48,21 @@ use tokio::{
 use tracing::instrument;
 
 use crate::{
-    block_producer::{check_block_size, BlockProducer, ComposeSubmitTxArgs, TransactionSizeError},
+    block_producer::{
+        bump_fee_rate, check_block_size, BlockProducer, ComposeSubmitTxArgs, TransactionSizeError,
+    },
     chain_updater::ChainUpdater,
     produce_block::ProduceBlockResult,
     sync_l1::{revert, sync_l1, SyncL1Context},
 };
 
 /// Block producing, submitting and confirming state machine.
+///
+/// The stages are connected by blocks in the store: producing appends local
+/// blocks, submitting sends them to L1 in order, and confirming waits for
+/// them to be committed. Producing is fast and local, so local blocks queue up
+/// when submission is slow, e.g. L1 is congested, and producing pauses at
+/// `psc_config.local_limit` local blocks.
 pub struct ProduceSubmitConfirm {
     context: Arc<PSCContext>,
     local_count: u64,
@@ -58,8 +81,12 @@ impl ProduceSubmitConfirm {
     fn set_local_count(&mut self, count: u64) {
         self.local_count = count;
 
-        gw_metrics::block_producer().local_blocks.set(count);
+        let metrics = gw_metrics::block_producer();
+        metrics.local_blocks.set(count);
+        let paused = count >= self.context.psc_config.local_limit;
+        metrics.production_paused.set(paused as u64);
         gw_metrics::custodian().finalized_custodians(&self.context.store);
+        gw_metrics::store().column_sizes(&self.context.store);
     }
 
     fn set_submitted_count(&mut self, count: u64) {
@@ -79,6 +106,7 @@ pub struct PSCContext {
     pub chain_updater: ChainUpdater,
     pub rollup_type_script: Script,
     pub psc_config: PscConfig,
+    pub dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     pub block_sync_server_state: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
     pub liveness: Arc<Liveness>,
 }
@@ -242,6 +270,7 @@ async fn run(state: &mut ProduceSubmitConfirm) -> Result<()> {
     let config = &ctx.psc_config;
     let mut interval = tokio::time::interval(Duration::from_secs(config.block_interval_secs));
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_produced = Instant::now();
 
     let mut revert_local_signal = signal(SignalKind::user_defined1())?;
     let mut revert_submitted_signal = signal(SignalKind::user_defined2())?;
@@ -251,14 +280,26 @@ async fn run(state: &mut ProduceSubmitConfirm) -> Result<()> {
             submitting = true;
             let context = state.context.clone();
             submit_handle.replace_with(tokio::spawn(async move {
+                let t = Instant::now();
+                let mut failures = 0;
                 loop {
                     match submit_next_block(&context).await {
-                        Ok(nh) => return Ok(nh),
+                        Ok(nh) => {
+                            gw_metrics::block_producer()
+                                .submit_duration
+                                .observe(t.elapsed().as_secs_f64());
+                            return Ok(nh);
+                        }
                         Err(err) => {
                             if err.is::<ShouldResyncError>() || err.is::<ShouldRevertError>() {
                                 bail!(err);
                             }
                             log::warn!("failed to submit next block: {:#}", err);
+                            failures += 1;
+                            alert(Alert::SubmissionFailures {
+                                failures,
+                                error: format!("{:#}", err),
+                            });
                             // TOOO: backoff.
                             tokio::time::sleep(Duration::from_secs(20)).await;
                         }
@@ -270,9 +311,15 @@ async fn run(state: &mut ProduceSubmitConfirm) -> Result<()> {
             confirming = true;
             let context = state.context.clone();
             confirm_handle.replace_with(tokio::spawn(async move {
+                let t = Instant::now();
                 loop {
                     match confirm_next_block(&context).await {
-                        Ok(nh) => break Ok(nh),
+                        Ok(nh) => {
+                            gw_metrics::block_producer()
+                                .confirm_duration
+                                .observe(t.elapsed().as_secs_f64());
+                            break Ok(nh);
+                        }
                         Err(err) => {
                             if err.is::<ShouldResyncError>() || err.is::<ShouldRevertError>() {
                                 bail!(err);
@@ -354,11 +401,28 @@ async fn run(state: &mut ProduceSubmitConfirm) -> Result<()> {
             // Produce a new local block if the produce timer has expired and
             // there are not too many local blocks.
             _ = interval.tick(), if state.local_count < config.local_limit => {
-                log::info!("producing next block");
-                if let Err(e) = produce_local_block(&state.context).await {
-                    log::warn!("failed to produce local block: {:#}", e);
-                } else {
-                    state.set_local_count(state.local_count + 1);
+                // Keep checking, and ticking liveness, while no trigger holds.
+                let skip_empty = match skip_empty_block(&state.context, last_produced).await {
+                    Ok(skip) => skip,
+                    Err(e) => {
+                        log::warn!("failed to check empty block: {:#}", e);
+                        false
+                    }
+                };
+                if skip_empty {
+                    gw_metrics::block_producer().skipped_empty_blocks.inc();
+                } else if let Some(trigger) = block_trigger(&state.context, last_produced).await {
+                    log::info!("producing next block, trigger: {}", trigger);
+                    let t = Instant::now();
+                    if let Err(e) = produce_local_block(&state.context).await {
+                        log::warn!("failed to produce local block: {:#}", e);
+                    } else {
+                        gw_metrics::block_producer()
+                            .produce_duration
+                            .observe(t.elapsed().as_secs_f64());
+                        state.set_local_count(state.local_count + 1);
+                        last_produced = Instant::now();
+                    }
                 }
             }
         }
@@ -367,6 +431,70 @@ async fn run(state: &mut ProduceSubmitConfirm) -> Result<()> {
     }
 }
 
+/// Whether to skip an empty block before `psc_config.empty_block_keep_alive_secs`
+/// has passed since the last block.
+///
+/// Deposits and withdrawals are only packaged on mem pool resets, so an empty
+/// mem block is reset first to pick up new ones.
+async fn skip_empty_block(ctx: &PSCContext, last_produced: Instant) -> Result<bool> {
+    let keep_alive = Duration::from_secs(ctx.psc_config.empty_block_keep_alive_secs);
+    if keep_alive.is_zero() || last_produced.elapsed() >= keep_alive {
+        return Ok(false);
+    }
+
+    let is_empty = |pool: &MemPool| {
+        let mem_block = pool.mem_block();
+        mem_block.txs().is_empty()
+            && mem_block.deposits().is_empty()
+            && mem_block.withdrawals().is_empty()
+    };
+    let mut pool = ctx.mem_pool.lock().await;
+    if !is_empty(&pool) {
+        return Ok(false);
+    }
+    let local_cells_manager = ctx.local_cells_manager.lock().await;
+    pool.reset_mem_block(&local_cells_manager).await?;
+    Ok(is_empty(&pool))
+}
+
+/// Check `dynamic_config.block_trigger`. Returns the condition that triggers
+/// the next block, or `None` to wait for the next check.
+async fn block_trigger(ctx: &PSCContext, last_produced: Instant) -> Option<&'static str> {
+    let trigger = {
+        let manager = ctx.dynamic_config_manager.load();
+        manager.get_block_trigger().cloned()
+    };
+    let trigger = match trigger {
+        Some(trigger) => trigger,
+        None => return Some("interval"),
+    };
+
+    let max_interval = { trigger.max_interval_secs }.unwrap_or(ctx.psc_config.block_interval_secs);
+    if last_produced.elapsed() >= Duration::from_secs(max_interval) {
+        return Some("interval");
+    }
+
+    let pool = ctx.mem_pool.lock().await;
+    let mem_block = pool.mem_block();
+    if let Some(percent) = trigger.fullness_percent {
+        let max_txs = pool.config().max_txs as u64;
+        let cycles_pool = pool.cycles_pool();
+        let txs_full = mem_block.txs().len() as u64 * 100 >= percent.saturating_mul(max_txs);
+        let cycles_full = cycles_pool.cycles_used().saturating_mul(100)
+            >= percent.saturating_mul(cycles_pool.limit());
+        if txs_full || cycles_full {
+            return Some("fullness");
+        }
+    }
+    if { trigger.withdrawals }.map_or(false, |n| mem_block.withdrawals().len() >= n) {
+        return Some("withdrawals");
+    }
+    if { trigger.deposits }.map_or(false, |n| mem_block.deposits().len() >= n) {
+        return Some("deposits");
+    }
+    None
+}
+
 /// Produce and save local block.
 #[instrument(skip_all)]
 async fn produce_local_block(ctx: &PSCContext) -> Result<()> {
@@ -377,6 +505,9 @@ async fn produce_local_block(ctx: &PSCContext) -> Result<()> {
     // to be re-injected after the mem pool is reset anyway, and that creates a
     // quite some pressure on p2p syncing and read-only nodes.
     let mut pool = ctx.mem_pool.lock().await;
+    if let Some(divergence) = pool.mem_pool_state().state_divergence() {
+        bail!("safe mode, {}", divergence);
+    }
 
     let mut retry_count = 0;
     let ProduceBlockResult {
@@ -385,17 +516,20 @@ async fn produce_local_block(ctx: &PSCContext) -> Result<()> {
         withdrawal_extras,
         deposit_cells,
         remaining_capacity,
-    } = loop {
-        let result = ctx
-            .block_producer
-            .produce_next_block(&mut pool, retry_count)
-            .await?;
+    } = {
+        let local_cells_manager = ctx.local_cells_manager.lock().await;
+        loop {
+            let result = ctx
+                .block_producer
+                .produce_next_block(&mut pool, retry_count, &local_cells_manager)
+                .await?;
 
-        if check_block_size(result.block.as_slice().len()).is_ok() {
-            break result;
+            if check_block_size(result.block.as_slice().len()).is_ok() {
+                break result;
+            }
+            retry_count += 1;
+            log::warn!("block too large, retry {retry_count}");
         }
-        retry_count += 1;
-        log::warn!("block too large, retry {retry_count}");
     };
 
     let number: u64 = block.raw().number().unpack();
@@ -509,6 +643,10 @@ async fn submit_block(
         .get_block_hash_by_number(block_number)?
         .context("failed to get next block hash")?;
     let block = snap.get_block(&block_hash)?.context("get_block")?;
+    gw_telemetry::trace::links::link(
+        &tracing::Span::current(),
+        block.transactions().into_iter().map(|tx| tx.raw().hash()),
+    );
     let timestamp_millis = block.raw().timestamp().unpack();
     // Godwoken scripts require that previous block timestamp < block timestamp < since:
     // https://github.com/nervosnetwork/godwoken-scripts/blob/d983fb351410eb6fbe02bb298af909193aeb5f22/contracts/state-validator/src/verifications/submit_block.rs#L707-L726
@@ -577,10 +715,12 @@ async fn submit_block(
         tx
     };
 
-    ctx.local_cells_manager
-        .lock()
-        .await
-        .apply_tx(&tx.as_reader());
+    {
+        let mut local_cells_manager = ctx.local_cells_manager.lock().await;
+        // The stored tx may have been replaced by fee bumping.
+        let tx = ctx.store.get_block_submit_tx(block_number).unwrap_or(tx);
+        local_cells_manager.apply_tx(&tx.as_reader());
+    }
 
     // Wait until median >= since, or CKB will reject the transaction.
     loop {
@@ -614,8 +754,22 @@ async fn submit_block(
         }
     }
 
-    log::info!("sending transaction 0x{}", hex::encode(tx.hash()));
-    if let Err(e) = send_transaction_or_check_inputs(&ctx.rpc_client, &tx).await {
+    let mut tx = ctx.store.get_block_submit_tx(block_number).unwrap_or(tx);
+    let mut deps_refreshed = false;
+    loop {
+        log::info!("sending transaction 0x{}", hex::encode(tx.hash()));
+        let e = match send_transaction_or_check_inputs(&ctx.rpc_client, &tx).await {
+            Ok(()) => break,
+            Err(e) => e,
+        };
+        // Contracts may have been upgraded, rebuild the tx once.
+        if e.is::<DeadCellDepError>() && !deps_refreshed {
+            log::warn!("{:#}, refresh contracts cell deps", e);
+            deps_refreshed = true;
+            tx = refresh_cell_deps(ctx, block_number).await?[0].clone();
+            continue;
+        }
+        gw_metrics::block_producer().submission_failure.inc();
         if e.is::<UnknownCellError>() {
             if is_first {
                 bail!(e.context(ShouldResyncError));
@@ -628,20 +782,71 @@ async fn submit_block(
         }
     }
     log::info!("tx sent");
+    gw_metrics::block_producer().submission_success.inc();
+    if let Some(dir) = ctx.rpc_client.dry_run_dir() {
+        let block = ctx.store.get_block(&block_hash)?.context("get_block")?;
+        write_dry_run_block(dir, block, &tx)?;
+    }
     Ok(NumberHash::new_builder()
         .block_hash(block_hash.pack())
         .number(block_number.pack())
         .build())
 }
 
-async fn poll_tx_confirmed(rpc_client: &RPCClient, tx: &Transaction) -> Result<()> {
+/// Wait for the submission tx of `block_number` to be committed, returns the
+/// committed tx.
+///
+/// If `fee_bump_timeout_secs` is set, the tx and later submission txs are
+/// rebuilt with a higher fee rate when it has been pending for that long. If
+/// a replaced version is committed instead, later txs are rebuilt on top of
+/// it. Replaced versions are kept in memory only, so this check doesn't work
+/// across restarts.
+async fn poll_tx_confirmed(
+    ctx: &PSCContext,
+    block_number: u64,
+    mut tx: Transaction,
+) -> Result<Transaction> {
+    let rpc_client = &ctx.rpc_client;
+    let config = &ctx.psc_config;
+    let fee_bump_timeout = Duration::from_secs(config.fee_bump_timeout_secs);
+    let mut fee_rate = ctx.block_producer.fee_rate().await;
+    let mut replaced: Vec<Transaction> = Vec::new();
     log::info!("waiting for tx 0x{}", hex::encode(tx.hash()));
     let mut last_sent = Instant::now();
+    let mut last_bumped = Instant::now();
+    let mut deps_refreshed = false;
     loop {
         let status = rpc_client.ckb.get_transaction_status(tx.hash()).await?;
         let should_resend = match status {
             Some(TxStatus::Committed) => break,
             Some(TxStatus::Rejected) => true,
+            Some(TxStatus::Pending)
+                if config.fee_bump_timeout_secs > 0
+                    && last_bumped.elapsed() > fee_bump_timeout
+                    && fee_rate < config.max_fee_rate =>
+            {
+                last_bumped = Instant::now();
+                let new_fee_rate = bump_fee_rate(config, fee_rate);
+                match replace_submit_txs(ctx, block_number, None, &[], new_fee_rate).await {
+                    // Already paid enough, e.g. bumped before restarting.
+                    Ok(new_txs) if new_txs[0].hash() == tx.hash() => fee_rate = new_fee_rate,
+                    Ok(new_txs) => {
+                        log::info!(
+                            "bump fee rate of tx 0x{} to {}, replaced by 0x{}",
+                            hex::encode(tx.hash()),
+                            new_fee_rate,
+                            hex::encode(new_txs[0].hash())
+                        );
+                        gw_metrics::block_producer().fee_bumps.inc();
+                        fee_rate = new_fee_rate;
+                        replaced.push(std::mem::replace(&mut tx, new_txs[0].clone()));
+                        send_replacements(rpc_client, &new_txs).await;
+                        last_sent = Instant::now();
+                    }
+                    Err(err) => log::warn!("failed to bump fee: {:#}", err),
+                }
+                false
+            }
             // Resend the transaction if it has been unknown, pending, or
             // proposed for some time. Or the transaction could be stuck in the
             // current state.
@@ -655,8 +860,32 @@ async fn poll_tx_confirmed(rpc_client: &RPCClient, tx: &Transaction) -> Result<(
             _ => last_sent.elapsed() > Duration::from_secs(24),
         };
         if should_resend {
+            // A replaced version may have been committed instead.
+            if let Some(committed) = find_committed(rpc_client, &replaced).await? {
+                log::info!(
+                    "replaced tx 0x{} is committed",
+                    hex::encode(committed.hash())
+                );
+                let new_txs =
+                    replace_submit_txs(ctx, block_number, Some(committed), &[], fee_rate).await?;
+                tx = new_txs[0].clone();
+                replaced.clear();
+                send_replacements(rpc_client, &new_txs[1..]).await;
+                continue;
+            }
             log::info!("resend transaction 0x{}", hex::encode(tx.hash()));
-            send_transaction_or_check_inputs(rpc_client, tx).await?;
+            match send_transaction_or_check_inputs(rpc_client, &tx).await {
+                // Contracts may have been upgraded, rebuild the txs once. The
+                // old tx can't be committed so it is not kept in `replaced`.
+                Err(err) if err.is::<DeadCellDepError>() && !deps_refreshed => {
+                    log::warn!("{:#}, refresh contracts cell deps", err);
+                    deps_refreshed = true;
+                    let new_txs = refresh_cell_deps(ctx, block_number).await?;
+                    tx = new_txs[0].clone();
+                    send_replacements(rpc_client, &new_txs).await;
+                }
+                result => result?,
+            }
             last_sent = Instant::now();
             gw_metrics::block_producer().resend.inc();
         }
@@ -675,7 +904,92 @@ async fn poll_tx_confirmed(rpc_client: &RPCClient, tx: &Transaction) -> Result<(
         }
         tokio::time::sleep(Duration::from_millis(50)).await;
     }
-    Ok(())
+    Ok(tx)
+}
+
+/// Replace the stored submission txs of `block_number` and later blocks. The
+/// tx of `block_number` is replaced by `first`, or rebuilt if `None`, later
+/// txs are rebuilt to spend outputs of the replacements. Cell deps are
+/// replaced according to `(old, new)` pairs of `cell_deps`. Returns the new
+/// txs.
+async fn replace_submit_txs(
+    ctx: &PSCContext,
+    block_number: u64,
+    first: Option<Transaction>,
+    cell_deps: &[(CellDep, CellDep)],
+    fee_rate: u64,
+) -> Result<Vec<Transaction>> {
+    let mut local_cells_manager = ctx.local_cells_manager.lock().await;
+    let old_txs: Vec<Transaction> = {
+        let snap = ctx.store.get_snapshot();
+        (block_number..)
+            .map_while(|n| snap.get_block_submit_tx(n))
+            .collect()
+    };
+    ensure!(!old_txs.is_empty(), "submit tx not found");
+    let mut new_txs = Vec::with_capacity(old_txs.len());
+    let mut remap = HashMap::new();
+    let rest = match first {
+        Some(tx) => {
+            remap.insert(old_txs[0].hash(), tx.clone());
+            new_txs.push(tx);
+            &old_txs[1..]
+        }
+        None => &old_txs[..],
+    };
+    new_txs.extend(
+        ctx.block_producer
+            .rebuild_submit_txs(rest, remap, cell_deps, fee_rate)
+            .await?,
+    );
+
+    let mut store_tx = ctx.store.begin_transaction();
+    for (number, tx) in (block_number..).zip(&new_txs) {
+        store_tx.set_block_submit_tx(number, &tx.as_reader())?;
+    }
+    store_tx.commit()?;
+    for (old, new) in old_txs.iter().zip(&new_txs) {
+        local_cells_manager.replace_tx(old, &new.as_reader());
+    }
+    Ok(new_txs)
+}
+
+/// Refresh contracts cell deps and rebuild submission txs of `block_number`
+/// and later blocks with the new cell deps. Returns the new txs.
+async fn refresh_cell_deps(ctx: &PSCContext, block_number: u64) -> Result<Vec<Transaction>> {
+    gw_metrics::block_producer().contracts_dep_refreshes.inc();
+    let cell_deps = ctx.block_producer.refresh_contracts_cell_deps().await?;
+    ensure!(!cell_deps.is_empty(), "contracts cell deps are unchanged");
+    let fee_rate = ctx.block_producer.fee_rate().await;
+    replace_submit_txs(ctx, block_number, None, &cell_deps, fee_rate).await
+}
+
+/// Send replacement txs, later txs are resent when they are polled so errors
+/// are only logged.
+async fn send_replacements(rpc_client: &RPCClient, txs: &[Transaction]) {
+    for tx in txs {
+        if let Err(err) = send_transaction_or_check_inputs(rpc_client, tx).await {
+            log::warn!(
+                "failed to send replacement tx 0x{}: {:#}",
+                hex::encode(tx.hash()),
+                err
+            );
+            break;
+        }
+    }
+}
+
+async fn find_committed(
+    rpc_client: &RPCClient,
+    txs: &[Transaction],
+) -> Result<Option<Transaction>> {
+    for tx in txs {
+        let status = rpc_client.ckb.get_transaction_status(tx.hash()).await?;
+        if matches!(status, Some(TxStatus::Committed)) {
+            return Ok(Some(tx.clone()));
+        }
+    }
+    Ok(None)
 }
 
 async fn confirm_next_block(context: &PSCContext) -> Result<NumberHash> {
@@ -702,7 +1016,16 @@ async fn confirm_block(
         .get_block_submit_tx(block_number)
         .expect("get submit tx");
     drop(snap);
-    poll_tx_confirmed(&context.rpc_client, &tx)
+    // Nothing is sent in dry run mode. Keep the submitted txs in local cells
+    // so that later txs can spend their outputs.
+    if context.rpc_client.dry_run_dir().is_some() {
+        log::info!("[dry run] block confirmed");
+        return Ok(NumberHash::new_builder()
+            .block_hash(block_hash.pack())
+            .number(block_number.pack())
+            .build());
+    }
+    let tx = poll_tx_confirmed(context, block_number, tx)
         .await
         .map_err(|e| {
             if e.is::<UnknownCellError>() {
@@ -721,6 +1044,25 @@ async fn confirm_block(
         .build())
 }
 
+/// Write a block and its submission tx hash to `<dir>/blocks/<number>.json`.
+fn write_dry_run_block(dir: &Path, block: packed::L2Block, tx: &Transaction) -> Result<()> {
+    let block_number: u64 = block.raw().number().unpack();
+    let blocks_dir = dir.join("blocks");
+    fs::create_dir_all(&blocks_dir)?;
+    let path = blocks_dir.join(format!("{}.json", block_number));
+    let content = serde_json::json!({
+        "block": L2BlockView::from(block),
+        "submit_tx_hash": JsonH256::from(tx.hash()),
+    });
+    fs::write(&path, serde_json::to_string_pretty(&content)?)?;
+    log::info!(
+        "[dry run] write block {} to {}",
+        block_number,
+        path.display()
+    );
+    Ok(())
+}
+
 /// Check that current CKB tip block median time >= timestamp.
 async fn median_gte(rpc_client: &RPCClient, timestamp_millis: u64) -> Result<()> {
     let tip = rpc_client.get_tip().await?;
@@ -787,8 +1129,10 @@ async fn check_cell(rpc_client: &RPCClient, out_point: &OutPoint) -> Result<()>
 
 /// Send transaction.
 ///
-/// Will check input cells if sending fails with `TransactionFailedToResolve`.
-/// If any input cell is dead, the error returned will be a `DeadCellError`.
+/// Will check input cells and cell deps if sending fails with
+/// `TransactionFailedToResolve`. If any input cell is dead, the error returned
+/// will be a `DeadCellError`. If any cell dep is not live, e.g. contracts are
+/// upgraded, the error returned will be a `DeadCellDepError`.
 async fn send_transaction_or_check_inputs(
     rpc_client: &RPCClient,
     tx: &Transaction,
@@ -796,7 +1140,11 @@ async fn send_transaction_or_check_inputs(
     if let Err(mut err) = rpc_client.send_transaction(tx).await {
         let code = get_jsonrpc_error_code(&err);
         if code == Some(CkbRpcError::TransactionFailedToResolve as i64) {
-            if let Err(e) = check_tx_input(rpc_client, tx).await {
+            let checked = match check_tx_input(rpc_client, tx).await {
+                Ok(()) => check_tx_cell_deps(rpc_client, tx).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = checked {
                 // If the input is consumed by tx, tx is actually confirmed.
                 // This can happen if the tx is confirmed right before it is
                 // resent and its inputs is checked.
@@ -851,6 +1199,10 @@ impl Display for DeadCellError {
 #[error("previous transaction not confirmed")]
 struct UnknownCellError;
 
+#[derive(thiserror::Error, Debug)]
+#[error("cell dep {0} is not live")]
+struct DeadCellDepError(OutPoint);
+
 async fn check_tx_input(rpc_client: &RPCClient, tx: &Transaction) -> Result<()> {
     // Check inputs.
     for input in tx.raw().inputs() {
@@ -871,6 +1223,30 @@ async fn check_tx_input(rpc_client: &RPCClient, tx: &Transaction) -> Result<()>
     Ok(())
 }
 
+async fn check_tx_cell_deps(rpc_client: &RPCClient, tx: &Transaction) -> Result<()> {
+    for cell_dep in tx.raw().cell_deps() {
+        let out_point = cell_dep.out_point();
+        let cell = match rpc_client.get_cell(out_point.clone()).await? {
+            Some(c) if matches!(c.status, CellStatus::Live) => c.cell,
+            _ => bail!(DeadCellDepError(out_point)),
+        };
+        if cell_dep.dep_type() == DepType::Code.into() {
+            continue;
+        }
+        // Members of the dep group should be live too.
+        let data = cell.map(|c| c.data).unwrap_or_default();
+        let members = OutPointVec::from_slice(&data)
+            .map_err(|_| anyhow!("invalid dep group {}", out_point))?;
+        for member in members {
+            let status = rpc_client.get_cell(member.clone()).await?.map(|c| c.status);
+            if !matches!(status, Some(CellStatus::Live)) {
+                bail!(DeadCellDepError(member));
+            }
+        }
+    }
+    Ok(())
+}
+
 fn publish_local_block(
     sync_server: &mut BlockSyncServerState,
     snap: &StoreSnapshot,
@@ -1,4 +1,6 @@
-use anyhow::{anyhow, bail, Result};
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context, Result};
 use ckb_types::bytes::Bytes;
 use ckb_types::prelude::{Builder, Entity};
 use gw_common::registry_address::RegistryAddress;
@@ -6,12 +8,18 @@ use gw_common::state::State;
 use gw_generator::traits::StateExt;
 use gw_generator::Generator;
 use gw_store::chain_view::ChainView;
+use gw_store::snapshot::StoreSnapshot;
+use gw_store::state::history::history_state::RWConfig;
+use gw_store::state::overlay::mem_store::MemStore;
 use gw_store::state::traits::JournalDB;
-use gw_store::state::MemStateDB;
+use gw_store::state::{BlockStateDB, MemStateDB};
 use gw_store::traits::chain_store::ChainStore;
+use gw_store::transaction::StoreTransaction;
 use gw_store::Store;
 use gw_types::h256::*;
-use gw_types::packed::{BlockInfo, DepositRequest, L2Block, RawL2Block, WithdrawalRequestExtra};
+use gw_types::packed::{
+    BlockInfo, DepositRequest, L2Block, RawL2Block, TxReceipt, WithdrawalRequestExtra,
+};
 use gw_types::prelude::Unpack;
 
 pub struct ReplayBlock;
@@ -147,6 +155,299 @@ impl ReplayBlock {
     }
 }
 
+/// Where a replayed block first diverges from the stored one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayTarget {
+    Withdrawal {
+        index: usize,
+        hash: H256,
+    },
+    /// State after withdrawals and deposits.
+    PrevTxsState,
+    Tx {
+        index: usize,
+        hash: H256,
+    },
+    PostState,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayDivergence {
+    pub block_number: u64,
+    pub target: ReplayTarget,
+    pub reason: String,
+}
+
+impl fmt::Display for ReplayDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block #{} ", self.block_number)?;
+        match &self.target {
+            ReplayTarget::Withdrawal { index, hash } => {
+                write!(f, "withdrawal #{} 0x{}", index, hex::encode(hash))?
+            }
+            ReplayTarget::PrevTxsState => write!(f, "prev txs state")?,
+            ReplayTarget::Tx { index, hash } => write!(f, "tx #{} 0x{}", index, hex::encode(hash))?,
+            ReplayTarget::PostState => write!(f, "post state")?,
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Re-execute stored blocks in `from_block..=to_block` against a scratch
+/// state, and compare state checkpoints, tx receipts and post states with the
+/// stored ones.
+///
+/// The scratch state is the last valid tip state rewound to the parent of
+/// `from_block` in memory, the store is never modified.
+///
+/// Returns the first divergence.
+pub fn replay_range(
+    store: &Store,
+    generator: &Generator,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Option<ReplayDivergence>> {
+    let snap = store.get_snapshot();
+    let tip_number: u64 = snap.get_last_valid_tip_block()?.raw().number().unpack();
+    if from_block == 0 || from_block > to_block || to_block > tip_number {
+        bail!(
+            "invalid replay range #{}..=#{}, last valid tip #{}",
+            from_block,
+            to_block,
+            tip_number
+        );
+    }
+
+    let mut state = ScratchStateDB::from_store(
+        MemStore::new(store.begin_transaction()),
+        RWConfig::detach_block(),
+    )?;
+    for number in (from_block..=tip_number).rev() {
+        state.detach_block_state(number)?;
+    }
+    let parent_post_state = get_block_by_number(&snap, from_block - 1)?
+        .raw()
+        .post_account();
+    state.set_account_count(parent_post_state.count().unpack())?;
+    state.finalise()?;
+    if state.calculate_merkle_state()?.as_slice() != parent_post_state.as_slice() {
+        bail!("rewound state mismatch block #{}", from_block - 1);
+    }
+
+    for number in from_block..=to_block {
+        let block = get_block_by_number(&snap, number)?;
+        if let Some(divergence) = replay_block_on(&snap, generator, &mut state, &block)? {
+            return Ok(Some(divergence));
+        }
+    }
+
+    Ok(None)
+}
+
+type ScratchStateDB = BlockStateDB<MemStore<StoreTransaction>>;
+
+fn replay_block_on(
+    snap: &StoreSnapshot,
+    generator: &Generator,
+    state: &mut ScratchStateDB,
+    block: &L2Block,
+) -> Result<Option<ReplayDivergence>> {
+    let raw_block = block.raw();
+    let block_info = get_block_info(&raw_block);
+    let block_number: u64 = raw_block.number().unpack();
+    log::info!("replay block #{}", block_number);
+
+    let diverge = |target: ReplayTarget, reason: String| -> Result<Option<ReplayDivergence>> {
+        Ok(Some(ReplayDivergence {
+            block_number,
+            target,
+            reason,
+        }))
+    };
+    let check_checkpoints = generator
+        .fork_config()
+        .enforce_correctness_of_state_checkpoint_list(block_number);
+    let state_checkpoint_list: Vec<H256> = raw_block.state_checkpoint_list().unpack();
+
+    // apply withdrawals
+    let block_producer = {
+        let block_producer: Bytes = block_info.block_producer().unpack();
+        RegistryAddress::from_slice(&block_producer)
+            .ok_or_else(|| anyhow!("invalid block producer address"))?
+    };
+    for (index, request) in block.withdrawals().into_iter().enumerate() {
+        let hash: H256 = request.hash();
+        let target = ReplayTarget::Withdrawal { index, hash };
+        let withdrawal = snap
+            .get_withdrawal(&hash)?
+            .with_context(|| format!("block #{} withdrawal #{} not found", block_number, index))?;
+        if let Err(err) = generator.check_withdrawal_signature(&*state, &withdrawal) {
+            return diverge(target, format!("check signature: {}", err));
+        }
+        if let Err(err) = state.apply_withdrawal_request(
+            generator.rollup_context(),
+            &block_producer,
+            &withdrawal.request(),
+        ) {
+            return diverge(target, format!("apply: {}", err));
+        }
+        if check_checkpoints {
+            let actual = state.calculate_state_checkpoint()?;
+            let expected = state_checkpoint_list
+                .get(index)
+                .cloned()
+                .unwrap_or_default();
+            if actual != expected {
+                return diverge(target, checkpoint_mismatch(expected, actual));
+            }
+        }
+    }
+
+    // apply deposits
+    let deposits = snap
+        .get_block_deposit_info_vec(block_number)
+        .with_context(|| format!("block #{} deposits not found", block_number))?;
+    for deposit in deposits.into_iter() {
+        if let Err(err) =
+            state.apply_deposit_request(generator.rollup_context(), &deposit.request())
+        {
+            return diverge(
+                ReplayTarget::PrevTxsState,
+                format!("apply deposit: {}", err),
+            );
+        }
+    }
+    state.finalise()?;
+    if check_checkpoints {
+        let actual = state.calculate_state_checkpoint()?;
+        let expected: H256 = raw_block
+            .submit_transactions()
+            .prev_state_checkpoint()
+            .unpack();
+        if actual != expected {
+            return diverge(
+                ReplayTarget::PrevTxsState,
+                checkpoint_mismatch(expected, actual),
+            );
+        }
+    }
+
+    // execute txs
+    let parent_block_hash: H256 = raw_block.parent_block_hash().unpack();
+    let chain_view = ChainView::new(snap, parent_block_hash);
+    let max_cycles = generator.fork_config().max_l2_tx_cycles(block_number);
+    let withdrawals_len = block.withdrawals().len();
+    for (index, tx) in block.transactions().into_iter().enumerate() {
+        let hash: H256 = tx.hash();
+        let target = ReplayTarget::Tx { index, hash };
+        if let Err(err) = generator.check_transaction_signature(&*state, &tx) {
+            return diverge(target, format!("check signature: {}", err));
+        }
+        let raw_tx = tx.raw();
+        let run_result = match generator.execute_transaction(
+            &chain_view,
+            state,
+            &block_info,
+            &raw_tx,
+            Some(max_cycles),
+            None,
+        ) {
+            Ok(run_result) => run_result,
+            Err(err) => return diverge(target, format!("execute: {}", err)),
+        };
+        state.finalise()?;
+
+        if check_checkpoints {
+            let actual = state.calculate_state_checkpoint()?;
+            let expected = { state_checkpoint_list.get(withdrawals_len + index) }
+                .cloned()
+                .unwrap_or_default();
+            if actual != expected {
+                return diverge(target, checkpoint_mismatch(expected, actual));
+            }
+        }
+
+        let receipt = TxReceipt::build_receipt(
+            tx.witness_hash(),
+            run_result,
+            state.calculate_merkle_state()?,
+        );
+        let stored = snap
+            .get_transaction_receipt(&hash)?
+            .with_context(|| format!("block #{} tx #{} receipt not found", block_number, index))?;
+        if let Some(reason) = receipt_mismatch(&stored, &receipt) {
+            return diverge(target, reason);
+        }
+    }
+
+    let actual = state.calculate_merkle_state()?;
+    let expected = raw_block.post_account();
+    if actual.as_slice() != expected.as_slice() {
+        let expected_root: H256 = expected.merkle_root().unpack();
+        let actual_root: H256 = actual.merkle_root().unpack();
+        let expected_count: u32 = expected.count().unpack();
+        let actual_count: u32 = actual.count().unpack();
+        let reason = format!(
+            "expected root 0x{} count {}, actual root 0x{} count {}",
+            hex::encode(expected_root),
+            expected_count,
+            hex::encode(actual_root),
+            actual_count
+        );
+        return diverge(ReplayTarget::PostState, reason);
+    }
+
+    Ok(None)
+}
+
+fn get_block_by_number(snap: &StoreSnapshot, number: u64) -> Result<L2Block> {
+    let block_hash = snap
+        .get_block_hash_by_number(number)?
+        .with_context(|| format!("block #{} hash not found", number))?;
+    snap.get_block(&block_hash)?
+        .with_context(|| format!("block #{} not found", number))
+}
+
+fn checkpoint_mismatch(expected: H256, actual: H256) -> String {
+    format!(
+        "checkpoint mismatch, expected 0x{} actual 0x{}",
+        hex::encode(expected),
+        hex::encode(actual)
+    )
+}
+
+/// Describe the first differing field of tx receipts.
+fn receipt_mismatch(expected: &TxReceipt, actual: &TxReceipt) -> Option<String> {
+    if expected.as_slice() == actual.as_slice() {
+        return None;
+    }
+    let reason = if expected.exit_code().as_slice() != actual.exit_code().as_slice() {
+        let expected_code: u8 = expected.exit_code().into();
+        let actual_code: u8 = actual.exit_code().into();
+        format!(
+            "exit code mismatch, expected {} actual {}",
+            expected_code as i8, actual_code as i8
+        )
+    } else if expected.post_state().as_slice() != actual.post_state().as_slice() {
+        let expected_root: H256 = expected.post_state().merkle_root().unpack();
+        let actual_root: H256 = actual.post_state().merkle_root().unpack();
+        format!(
+            "receipt post state mismatch, expected 0x{} actual 0x{}",
+            hex::encode(expected_root),
+            hex::encode(actual_root)
+        )
+    } else if expected.logs().as_slice() != actual.logs().as_slice() {
+        format!(
+            "receipt logs mismatch, expected {} logs actual {} logs",
+            expected.logs().len(),
+            actual.logs().len()
+        )
+    } else {
+        "receipt mismatch".to_string()
+    };
+    Some(reason)
+}
+
 fn get_block_info(l2block: &RawL2Block) -> BlockInfo {
     BlockInfo::new_builder()
         .block_producer(l2block.block_producer())
@@ -1,13 +1,19 @@
 use crate::{
+    alerts::AlertNotifier,
     block_producer::{BlockProducer, BlockProducerCreateArgs},
     block_sync_client::{block_sync_client_protocol, BlockSyncClient, P2PStream},
     chain_updater::ChainUpdater,
     challenger::{Challenger, ChallengerNewArgs},
     cleaner::Cleaner,
+    deposit_refund::DepositRefunder,
     psc::{PSCContext, ProduceSubmitConfirm},
+    shutdown::ShutdownManager,
+    stake::StakeStatus,
+    stake_manager::{StakeManager, StakeManagerNewArgs},
     test_mode_control::TestModeControl,
     types::ChainEvent,
-    withdrawal_unlocker::FinalizedWithdrawalUnlocker,
+    watch_notifier::WatchNotifier,
+    withdrawal_unlocker::{FinalizedWithdrawalUnlocker, WithdrawalUnlockerNewArgs},
 };
 use anyhow::{anyhow, bail, Context, Result};
 use futures::future::OptionFuture;
@@ -25,21 +31,28 @@ use gw_generator::{
 use gw_mem_pool::{
     account_creator::AccountCreator,
     block_sync_server::{block_sync_server_protocol, BlockSyncServerState},
+    clock::check_clock_skew,
     default_provider::DefaultMemPoolProvider,
+    deposit_watcher::DepositWatcher,
     pool::{MemPool, MemPoolCreateArgs},
+    write_batch::flush_periodically,
 };
 use gw_p2p_network::P2PNetwork;
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
 use gw_rpc_client::{
-    ckb_client::CKBClient, contract::ContractsCellDepManager, error::RPCRequestError,
-    indexer_client::CKBIndexerClient, rpc_client::RPCClient,
+    ckb_client::CKBClient, contract::ContractsCellDepManager, cycles_limit::CyclesLimitManager,
+    error::RPCRequestError, fee_estimator::FeeEstimator, indexer_client::CKBIndexerClient,
+    rpc_client::RPCClient,
 };
 use gw_rpc_server::{
+    grpc::start_grpc_server,
     registry::{Registry, RegistryArgs},
-    server::start_jsonrpc_server,
+    server::{serve_admin_jsonrpc, serve_metrics, start_jsonrpc_server},
+    subscription::start_ws_server,
 };
 use gw_store::migrate::{init_migration_factory, open_or_create_db};
 use gw_store::Store;
+use gw_telemetry::alert::Alert;
 use gw_types::{
     bytes::Bytes,
     core::AllowedEoaType,
@@ -55,7 +68,9 @@ use gw_utils::{
 use semver::Version;
 use std::{
     collections::HashMap,
+    iter,
     net::{SocketAddr, ToSocketAddrs},
+    path::Path,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -68,10 +83,12 @@ use tracing::{info_span, instrument};
 
 const MIN_CKB_VERSION: &str = "0.40.0";
 const EVENT_TIMEOUT_SECONDS: u64 = 30;
+const RELAYED_TX_CHANNEL_SIZE: usize = 1024;
 
 struct ChainTaskContext {
     challenger: Option<Challenger>,
     withdrawal_unlocker: Option<FinalizedWithdrawalUnlocker>,
+    stake_manager: Option<StakeManager>,
     cleaner: Option<Arc<Cleaner>>,
 }
 
@@ -170,6 +187,12 @@ impl ChainTask {
                 }
             }
 
+            if let Some(ref mut stake_manager) = ctx.stake_manager {
+                if let Err(err) = stake_manager.handle_event(&event).await {
+                    log::error!("[stake manager] {:#}", err);
+                }
+            }
+
             if let Some(ref mut challenger) = ctx.challenger {
                 if let Err(err) = challenger.handle_event(event.clone()).await {
                     if is_l1_query_error(&err) {
@@ -277,22 +300,7 @@ impl BaseInitComponents {
             fork_config: config.fork.clone(),
         };
         let rollup_type_script: Script = config.chain.rollup_type_script.clone().into();
-        let rpc_client = {
-            let ckb_client = CKBClient::with_url(&config.rpc_client.ckb_url)?;
-            let indexer_client = if let Some(ref indexer_url) = config.rpc_client.indexer_url {
-                CKBIndexerClient::with_url(indexer_url)?
-            } else {
-                CKBIndexerClient::new(ckb_client.client().clone(), false)
-            };
-            let rollup_type_script =
-                ckb_types::packed::Script::new_unchecked(rollup_type_script.as_bytes());
-            RPCClient::new(
-                rollup_type_script,
-                rollup_context.rollup_config.clone(),
-                ckb_client,
-                indexer_client,
-            )
-        };
+        let rpc_client = build_rpc_client(config)?;
 
         let mut contracts_dep_manager = None;
         let opt_block_producer_config = config.block_producer.as_ref();
@@ -307,6 +315,7 @@ impl BaseInitComponents {
                 ContractsCellDepManager::build(
                     rpc_client.clone(),
                     script_config,
+                    block_producer_config.contracts_dep_groups.clone(),
                     rollup_config_cell_dep,
                 )
                 .await?,
@@ -376,21 +385,19 @@ impl BaseInitComponents {
                 eth_lock_script_type_hash.hash().unpack(),
                 Arc::new(Secp256k1Eth::default()),
             );
-            Arc::new(Generator::new(
-                backend_manage,
-                account_lock_manage,
-                rollup_context.clone(),
-                config.contract_log_config.clone(),
-            ))
+            Arc::new(
+                Generator::new(
+                    backend_manage,
+                    account_lock_manage,
+                    rollup_context.clone(),
+                    config.contract_log_config.clone(),
+                )
+                .with_debug_failed_transactions(config.debug.debug_failed_transactions)
+                .with_syscall_cycles(config.mem_pool.mem_block.syscall_cycles.clone()),
+            )
         };
 
-        let ckb_genesis_info = {
-            let ckb_genesis = rpc_client
-                .get_block_by_number(0)
-                .await?
-                .ok_or_else(|| anyhow!("can't found CKB genesis block"))?;
-            CKBGenesisInfo::from_block(&ckb_genesis)?
-        };
+        let ckb_genesis_info = query_ckb_genesis_info(&rpc_client).await?;
 
         let to_hash = |data| -> [u8; 32] {
             let mut hasher = new_blake2b();
@@ -471,6 +478,16 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             });
         }
     }
+    let alert_notifier = config.alerts.as_ref().map(|alerts| {
+        let block_producer = config.block_producer.as_ref().map(|c| {
+            let address = &c.block_producer;
+            RegistryAddress::new(address.registry_id, address.address.as_bytes().to_vec())
+        });
+        let notifier = Arc::new(AlertNotifier::new(alerts, block_producer.as_ref()));
+        log::info!("notify alerts to {}", notifier.webhook_url());
+        notifier.clone().start(config.store.path.clone());
+        notifier
+    });
     let base = BaseInitComponents::init(&config, skip_config_check).await?;
 
     let has_block_producer_and_p2p =
@@ -478,7 +495,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     let block_sync_server_state = if has_block_producer_and_p2p {
         Some(Arc::new(std::sync::Mutex::new(BlockSyncServerState::new(
             &config.sync_server,
-        ))))
+        )?)))
     } else {
         None
     };
@@ -493,11 +510,20 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             let opt_offchain_mock_context = base
                 .init_offchain_mock_context(block_producer_config)
                 .await?;
-            let mem_pool_provider = DefaultMemPoolProvider::new(
+            let mut mem_pool_provider = DefaultMemPoolProvider::new(
                 base.rpc_client.clone(),
                 base.store.clone(),
                 config.mem_pool.mem_block.clone(),
             );
+            if let Some(ref c) = config.mem_pool.mem_block.deposit_watcher {
+                let deposit_watcher = DepositWatcher::new(
+                    base.rpc_client.clone(),
+                    config.mem_pool.mem_block.clone(),
+                    c.clone(),
+                );
+                deposit_watcher.spawn_poll();
+                mem_pool_provider = mem_pool_provider.with_deposit_watcher(deposit_watcher);
+            }
             let mem_pool = {
                 let block_producer = RegistryAddress::new(
                     block_producer_config.block_producer.registry_id,
@@ -508,19 +534,44 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                         .to_vec(),
                 );
                 let account_creator = {
-                    match config.block_producer.as_ref().map(|c| &c.wallet_config) {
-                        Some(Some(c)) => {
-                            log::info!("pool account creator use block producer wallet");
+                    let creator_config = &config.mem_pool.account_creator;
+                    let block_producer_wallet_config = config
+                        .block_producer
+                        .as_ref()
+                        .and_then(|c| c.wallet_config.as_ref());
+                    let wallet_config = creator_config
+                        .payer_wallet_config
+                        .as_ref()
+                        .or(block_producer_wallet_config);
+                    match wallet_config {
+                        Some(c) => {
+                            if creator_config.payer_wallet_config.is_some() {
+                                log::info!("pool account creator use payer wallet");
+                            } else {
+                                log::info!("pool account creator use block producer wallet");
+                            }
                             let w = Wallet::from_config(c).with_context(|| "pool wallet")?;
-                            Some(AccountCreator::create(base.generator.rollup_context(), w)?)
+                            let creator =
+                                AccountCreator::create(base.generator.rollup_context(), w)?
+                                    .with_config(creator_config.clone());
+                            Some(creator)
                         }
-                        _ if config.node_mode == NodeMode::FullNode => {
+                        None if config.node_mode == NodeMode::FullNode => {
                             log::warn!("no wallet config for mem pool account creator");
                             None
                         }
                         _ => None,
                     }
                 };
+                let cycles_limit_manager = match config.mem_pool.mem_block.onchain_cycles_limit {
+                    Some(ref c) => {
+                        let manager =
+                            CyclesLimitManager::build(base.rpc_client.clone(), c.clone()).await?;
+                        manager.spawn_refresh();
+                        Some(manager)
+                    }
+                    None => None,
+                };
                 let args = MemPoolCreateArgs {
                     block_producer,
                     store: base.store.clone(),
@@ -531,6 +582,10 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                     dynamic_config_manager: base.dynamic_config_manager.clone(),
                     sync_server: block_sync_server_state.clone(),
                     account_creator,
+                    cycles_limit_manager,
+                    max_custodian_inputs: Some(
+                        block_producer_config.custodian_merge.max_custodian_inputs,
+                    ),
                 };
                 Arc::new(Mutex::new(
                     MemPool::create(args)
@@ -557,6 +612,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         dynamic_config_manager,
         ..
     } = base;
+    rpc_client.spawn_health_check(&config.rpc_client.health_check);
 
     let liveness = Arc::new(Liveness::new(Duration::from_secs(
         config.liveness_duration_secs.unwrap_or(60),
@@ -565,7 +621,13 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     // check state db
     {
         let t = Instant::now();
-        store.check_state()?;
+        if let Err(err) = store.check_state() {
+            if let Some(ref notifier) = alert_notifier {
+                let error = format!("{:#}", err);
+                notifier.notify(&Alert::DbCorruption { error }).await;
+            }
+            return Err(err);
+        }
         log::info!("Check state db done: {}ms", t.elapsed().as_millis());
     }
     let chain = Arc::new(Mutex::new(
@@ -588,11 +650,24 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         rollup_type_script.clone(),
     );
 
-    let local_cells_manager = Arc::new(Mutex::new(LocalCellsManager::default()));
-    let (block_producer, challenger, test_mode_control, withdrawal_unlocker, cleaner) = match config
-        .node_mode
-    {
-        NodeMode::ReadOnly => (None, None, None, None, None),
+    let local_cells_manager = if config.node_mode == NodeMode::ReadOnly {
+        LocalCellsManager::default()
+    } else {
+        LocalCellsManager::restore(store.clone(), &rpc_client)
+            .await
+            .context("restore local cells")?
+    };
+    let local_cells_manager = Arc::new(Mutex::new(local_cells_manager));
+    let admin_contracts_dep_manager = contracts_dep_manager.clone();
+    let (
+        block_producer,
+        challenger,
+        test_mode_control,
+        withdrawal_unlocker,
+        stake_manager,
+        cleaner,
+    ) = match config.node_mode {
+        NodeMode::ReadOnly => (None, None, None, None, None, None),
         mode => {
             let block_producer_config = config
                 .block_producer
@@ -600,8 +675,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 .ok_or_else(|| anyhow!("must provide block producer config in mode: {:?}", mode))?;
             let contracts_dep_manager =
                 contracts_dep_manager.ok_or_else(|| anyhow!("must build contracts dep"))?;
-            let wallet =
-                wallet.ok_or_else(|| anyhow!("wallet must be enabled in mode: {:?}", mode))?;
+            wallet.ok_or_else(|| anyhow!("wallet must be enabled in mode: {:?}", mode))?;
             let offchain_mock_context = {
                 let ctx = offchain_mock_context;
                 let msg = "offchain mock require block producer config and wallet in mode: ";
@@ -613,47 +687,47 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 None
             };
 
-            let unlocker_wallet = match block_producer_config.withdrawal_unlocker_wallet_config {
-                Some(ref wallet_config) => {
-                    Wallet::from_config(wallet_config).with_context(|| "unlocker wallet")?
-                }
-                None => {
-                    log::info!("[unlock withdrawal] reuse block producer wallet");
-
-                    match block_producer_config.wallet_config {
-                        Some(ref c) => Wallet::from_config(c).with_context(|| "unlocker wallet")?,
-                        None => bail!("no wallet config for withdrawal unlocker"),
-                    }
-                }
-            };
-
-            let withdrawal_unlocker = FinalizedWithdrawalUnlocker::new(
+            let fee_estimator = FeeEstimator::new(
                 rpc_client.clone(),
-                local_cells_manager.clone(),
-                ckb_genesis_info.clone(),
-                contracts_dep_manager.clone(),
-                unlocker_wallet,
-                config.debug.clone(),
+                block_producer_config.fee_estimator.clone(),
                 block_producer_config.fee_rate,
             );
 
+            let unlocker_args = WithdrawalUnlockerNewArgs {
+                rpc_client: rpc_client.clone(),
+                local_cells_manager: local_cells_manager.clone(),
+                ckb_genesis_info: ckb_genesis_info.clone(),
+                contracts_dep_manager: contracts_dep_manager.clone(),
+                wallet: unlocker_wallet(&block_producer_config)?,
+                debug_config: config.debug.clone(),
+                fee_estimator: fee_estimator.clone(),
+                config: block_producer_config.withdrawal_unlocker.clone(),
+                store: Some(store.clone()),
+            };
+            let withdrawal_unlocker = FinalizedWithdrawalUnlocker::new(unlocker_args);
+
+            let stake_manager = StakeManager::new(StakeManagerNewArgs {
+                rpc_client: rpc_client.clone(),
+                local_cells_manager: local_cells_manager.clone(),
+                ckb_genesis_info: ckb_genesis_info.clone(),
+                contracts_dep_manager: contracts_dep_manager.clone(),
+                wallet: block_producer_wallet(&block_producer_config)?,
+                fee_estimator: fee_estimator.clone(),
+                config: block_producer_config.stake_manager.clone(),
+            });
+
             let cleaner = Arc::new(Cleaner::new(
                 rpc_client.clone(),
                 ckb_genesis_info.clone(),
-                wallet,
-                block_producer_config.fee_rate,
+                challenger_wallet(&block_producer_config)?,
+                fee_estimator.clone(),
             ));
 
-            let challenger_wallet = match block_producer_config.wallet_config {
-                Some(ref c) => Wallet::from_config(c).with_context(|| "challenger wallet")?,
-                None => bail!("no wallet config for block producer"),
-            };
-
             // Challenger
             let args = ChallengerNewArgs {
                 rollup_context,
                 rpc_client: rpc_client.clone(),
-                wallet: challenger_wallet,
+                wallet: challenger_wallet(&block_producer_config)?,
                 config: block_producer_config.clone(),
                 debug_config: config.debug.clone(),
                 builtin_load_data,
@@ -663,6 +737,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 cleaner: Arc::clone(&cleaner),
                 offchain_mock_context,
                 contracts_dep_manager: contracts_dep_manager.clone(),
+                fee_estimator: fee_estimator.clone(),
             };
             let challenger = Challenger::new(args);
 
@@ -677,6 +752,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 config: block_producer_config,
                 tests_control: tests_control.clone(),
                 contracts_dep_manager,
+                fee_estimator,
             };
             let block_producer =
                 BlockProducer::create(create_args).with_context(|| "init block producer")?;
@@ -686,15 +762,17 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                 Some(challenger),
                 tests_control,
                 Some(withdrawal_unlocker),
+                Some(stake_manager),
                 Some(cleaner),
             )
         }
     };
 
-    // Wait for graceful shutdown complete.
-    let (shutdown_completed_send, mut shutdown_completed_recv) = mpsc::channel(1);
-    // Broadcast shutdown event.
-    let (shutdown_event, shutdown_event_recv) = broadcast::channel(1);
+    let shutdown = ShutdownManager::new(Duration::from_secs(
+        config.shutdown_timeout_secs.unwrap_or(30),
+    ));
+    let shutdown_event_recv = shutdown.subscribe();
+    let shutdown_mem_pool = mem_pool.clone();
 
     let block_sync_client_p2p_stream_inbox: Arc<std::sync::Mutex<Option<P2PStream>>> =
         Arc::new(std::sync::Mutex::new(None));
@@ -744,6 +822,40 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
 
         PolyjuiceSenderRecover::create(generator.rollup_context(), opt_wallet)?
     };
+    let watch_notifier = match (config.watch_list.as_ref(), mem_pool.as_ref()) {
+        (Some(watch_list), Some(mem_pool)) => {
+            let notifier = WatchNotifier::new(
+                store.clone(),
+                generator.rollup_context().clone(),
+                watch_list,
+            );
+            let new_blocks = mem_pool.lock().await.subscriptions().subscribe_new_blocks();
+            Some((notifier, new_blocks))
+        }
+        (Some(_), None) => {
+            log::warn!("Watch list requires a mem pool, ignore `watch_list`");
+            None
+        }
+        _ => None,
+    };
+
+    if let (Some(write_batch), Some(mem_pool)) =
+        (config.mem_pool.write_batch.as_ref(), mem_pool.as_ref())
+    {
+        spawn(flush_periodically(mem_pool.clone(), write_batch.clone()));
+    }
+
+    // Read-only nodes relay submitted transactions to the full node.
+    let (tx_relay, tx_relay_rx) = if config.node_mode == NodeMode::ReadOnly
+        && config.p2p_network_config.is_some()
+        && config.sync_client.relay_transactions
+    {
+        let (tx, rx) = mpsc::channel(config.sync_client.relay_channel_capacity);
+        (Some(tx), Some(rx))
+    } else {
+        (None, None)
+    };
+
     let args = RegistryArgs {
         store: store.clone(),
         mem_pool: mem_pool.clone(),
@@ -755,15 +867,21 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         mem_pool_config: config.mem_pool.clone(),
         node_mode: config.node_mode,
         rpc_client: rpc_client.clone(),
-        send_tx_rate_limit: config.dynamic_config.rpc_config.send_tx_rate_limit.clone(),
         server_config: config.rpc_server.clone(),
-        dynamic_config_manager,
+        dynamic_config_manager: dynamic_config_manager.clone(),
         polyjuice_sender_recover,
         debug_backend_forks: config.debug_backend_forks.clone(),
         gasless_tx_support_config: config.gasless_tx_support.clone(),
+        tx_relay,
+        contracts_dep_manager: admin_contracts_dep_manager,
     };
 
     let rpc_registry = Registry::create(args).await;
+    if let Some(ref state) = block_sync_server_state {
+        let (relay_tx, relay_rx) = mpsc::channel(RELAYED_TX_CHANNEL_SIZE);
+        state.lock().unwrap().set_relay_sender(relay_tx);
+        rpc_registry.spawn_relay_receiver(relay_rx);
+    }
 
     let rpc_address: SocketAddr = {
         let mut addrs: Vec<_> = config.rpc_server.listen.to_socket_addrs()?.collect();
@@ -775,6 +893,51 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         }
         addrs.remove(0)
     };
+    let metrics_address: Option<SocketAddr> = match config.rpc_server.metrics_listen {
+        Some(ref listen) => {
+            let mut addrs: Vec<_> = listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!("Invalid metrics listen address `{}`", listen));
+            }
+            Some(addrs.remove(0))
+        }
+        None => None,
+    };
+    let grpc_server = match config.rpc_server.grpc_listen {
+        Some(ref listen) => {
+            let mut addrs: Vec<_> = listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!("Invalid gRPC listen address `{}`", listen));
+            }
+            Some((addrs.remove(0), rpc_registry.build_grpc_service()))
+        }
+        None => None,
+    };
+    let admin_server = match config.rpc_server.admin.listen {
+        Some(ref listen) => {
+            let mut addrs: Vec<_> = listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!("Invalid admin RPC listen address `{}`", listen));
+            }
+            Some((addrs.remove(0), rpc_registry.build_admin_rpc_server()?))
+        }
+        None => None,
+    };
+    let ws_server = match (config.rpc_server.ws_listen.as_ref(), mem_pool.as_ref()) {
+        (Some(listen), Some(mem_pool)) => {
+            let mut addrs: Vec<_> = listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!("Invalid WebSocket listen address `{}`", listen));
+            }
+            let subscriptions = mem_pool.lock().await.subscriptions().clone();
+            Some((addrs.remove(0), subscriptions))
+        }
+        (Some(_), None) => {
+            log::warn!("WebSocket subscriptions require a mem pool, ignore `ws_listen`");
+            None
+        }
+        _ => None,
+    };
 
     {
         let rollup_type_script_hash = {
@@ -801,14 +964,15 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             chain_updater: chain_updater.clone(),
             rollup_type_script: rollup_type_script.clone(),
             psc_config: config.block_producer.as_ref().unwrap().psc_config.clone(),
+            dynamic_config_manager,
             block_sync_server_state: block_sync_server_state.clone(),
             liveness: liveness.clone(),
         }))
         .await
         .context("create ProduceSubmitConfirm")?;
 
-        let shutdown_completed_send = shutdown_completed_send.clone();
-        let mut shutdown_event_recv = shutdown_event.subscribe();
+        let shutdown_completed_send = shutdown.completed_sender();
+        let mut shutdown_event_recv = shutdown.subscribe();
         Some(tokio::spawn(async move {
             let result = tokio::select! {
                 _ = shutdown_event_recv.recv() => return,
@@ -836,9 +1000,11 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             p2p_stream_inbox: block_sync_client_p2p_stream_inbox,
             completed_initial_syncing: false,
             liveness: liveness.clone(),
+            last_applied: None,
+            tx_relay: tx_relay_rx,
         };
-        let shutdown_completed_send = shutdown_completed_send.clone();
-        let mut shutdown_event_recv = shutdown_event.subscribe();
+        let shutdown_completed_send = shutdown.completed_sender();
+        let mut shutdown_event_recv = shutdown.subscribe();
         Some(tokio::spawn(async move {
             tokio::select! {
                 _ = shutdown_event_recv.recv() => {},
@@ -855,7 +1021,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
     let (chain_task_ended_tx, chain_task) = tokio::sync::oneshot::channel::<()>();
     let rt_handle = tokio::runtime::Handle::current();
     tokio::task::spawn_blocking({
-        let shutdown_send = shutdown_completed_send.clone();
+        let shutdown_send = shutdown.completed_sender();
         move || {
             rt_handle.block_on(async move {
                 use tracing::Instrument;
@@ -865,6 +1031,7 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
                     // chain_updater,
                     challenger,
                     withdrawal_unlocker,
+                    stake_manager,
                     cleaner,
                 };
                 let mut backoff = ExponentialBackoff::new(Duration::from_secs(1));
@@ -926,13 +1093,73 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         }
     });
 
-    let sub_shutdown = shutdown_event.subscribe();
-    let rpc_shutdown_send = shutdown_completed_send.clone();
+    if let Some(metrics_address) = metrics_address {
+        let sub_shutdown = shutdown.subscribe();
+        let liveness = liveness.clone();
+        spawn(async move {
+            log::info!("metrics server listening on {}", metrics_address);
+            if let Err(err) = serve_metrics(metrics_address, liveness, sub_shutdown).await {
+                log::error!("Error running metrics server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some((ws_address, subscriptions)) = ws_server {
+        let sub_shutdown = shutdown.subscribe();
+        let store = store.clone();
+        spawn(async move {
+            if let Err(err) = start_ws_server(ws_address, store, subscriptions, sub_shutdown).await
+            {
+                log::error!("Error running WebSocket server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some((grpc_address, service)) = grpc_server {
+        let sub_shutdown = shutdown.subscribe();
+        spawn(async move {
+            if let Err(err) = start_grpc_server(grpc_address, service, sub_shutdown).await {
+                log::error!("Error running gRPC server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some((admin_address, admin_server)) = admin_server {
+        let sub_shutdown = shutdown.subscribe();
+        let liveness = liveness.clone();
+        let rpc_server_config = config.rpc_server.clone();
+        spawn(async move {
+            if let Err(err) = serve_admin_jsonrpc(
+                admin_address,
+                admin_server,
+                liveness,
+                &rpc_server_config,
+                sub_shutdown,
+            )
+            .await
+            {
+                log::error!("Error running admin JSONRPC server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some(ref clock_skew_check) = config.mem_pool.clock_skew_check {
+        spawn(check_clock_skew(clock_skew_check.clone()));
+    }
+
+    if let Some((notifier, new_blocks)) = watch_notifier {
+        log::info!("notify watch list events to {}", notifier.webhook_url());
+        spawn(notifier.run(new_blocks));
+    }
+
+    let sub_shutdown = shutdown.subscribe();
+    let rpc_shutdown_send = shutdown.completed_sender();
+    let rpc_liveness = liveness.clone();
     let rpc_task = spawn(async move {
         if let Err(err) = start_jsonrpc_server(
             rpc_address,
             rpc_registry,
-            liveness,
+            rpc_liveness,
             rpc_shutdown_send,
             sub_shutdown,
         )
@@ -950,11 +1177,8 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         _ = block_sync_task, if has_block_sync_task => {},
     };
 
-    //If any task is out of running, broadcast shutdown event.
-    log::info!("send shutdown event");
-    if let Err(err) = shutdown_event.send(()) {
-        log::error!("Failed to brodcast error message: {:?}", err);
-    }
+    // If any task is out of running, shut down.
+    shutdown.start(&liveness);
     // Shutdown p2p network.
     if let Some((control, handle)) = p2p_control_and_handle {
         log::info!("closing p2p network");
@@ -962,19 +1186,240 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         let _ = handle.await;
         log::info!("p2p network closed");
     }
+    shutdown.finish(shutdown_mem_pool, &store).await;
+    log::info!("Exiting...");
 
-    // Make sure all the senders are dropped.
-    drop(shutdown_completed_send);
+    Ok(())
+}
 
-    // When every sender has gone out of scope, the recv call
-    // will return with an error. We ignore the error. Just
-    // make sure we can hit this line.
-    let _ = shutdown_completed_recv.recv().await;
-    log::info!("Exiting...");
+pub(crate) fn build_rpc_client(config: &Config) -> Result<RPCClient> {
+    let rpc_client_config = &config.rpc_client;
+    let ckb_urls: Vec<String> = { iter::once(&rpc_client_config.ckb_url) }
+        .chain(&rpc_client_config.backup_ckb_urls)
+        .cloned()
+        .collect();
+    let ckb_client = CKBClient::with_urls(&ckb_urls)?;
+    let indexer_client = if let Some(ref indexer_url) = rpc_client_config.indexer_url {
+        let indexer_urls: Vec<String> = { iter::once(indexer_url) }
+            .chain(&rpc_client_config.backup_indexer_urls)
+            .cloned()
+            .collect();
+        CKBIndexerClient::with_urls(&indexer_urls)?
+    } else {
+        if !rpc_client_config.backup_indexer_urls.is_empty() {
+            bail!("backup_indexer_urls requires indexer_url");
+        }
+        CKBIndexerClient::with_ckb_client(&ckb_client)
+    };
+    let indexer_client = indexer_client.with_cache(rpc_client_config.indexer_cache.clone());
+    let rollup_type_script: Script = config.chain.rollup_type_script.clone().into();
+    let rollup_type_script =
+        ckb_types::packed::Script::new_unchecked(rollup_type_script.as_bytes());
+    let rpc_client = RPCClient::new(
+        rollup_type_script,
+        config.genesis.rollup_config.clone().into(),
+        ckb_client,
+        indexer_client,
+    );
+    let dry_run = { config.block_producer.as_ref() }.and_then(|c| c.dry_run.as_ref());
+    match dry_run {
+        Some(dry_run) => {
+            log::warn!(
+                "[dry run] L1 txs will be written to {} instead of being sent",
+                dry_run.output_dir.display()
+            );
+            Ok(rpc_client.with_dry_run(dry_run.output_dir.clone()))
+        }
+        None => Ok(rpc_client),
+    }
+}
+
+async fn query_ckb_genesis_info(rpc_client: &RPCClient) -> Result<CKBGenesisInfo> {
+    let ckb_genesis = rpc_client
+        .get_block_by_number(0)
+        .await?
+        .ok_or_else(|| anyhow!("can't found CKB genesis block"))?;
+    CKBGenesisInfo::from_block(&ckb_genesis)
+}
+
+fn unlocker_wallet(block_producer_config: &BlockProducerConfig) -> Result<Wallet> {
+    match block_producer_config.withdrawal_unlocker_wallet_config {
+        Some(ref wallet_config) => {
+            Wallet::from_config(wallet_config).with_context(|| "unlocker wallet")
+        }
+        None => {
+            log::info!("[unlock withdrawal] reuse block producer wallet");
+
+            match block_producer_config.wallet_config {
+                Some(ref c) => Wallet::from_config(c).with_context(|| "unlocker wallet"),
+                None => bail!("no wallet config for withdrawal unlocker"),
+            }
+        }
+    }
+}
+
+/// Wallet of block submissions, the owner of stake cells.
+fn block_producer_wallet(block_producer_config: &BlockProducerConfig) -> Result<Wallet> {
+    match block_producer_config.wallet_config {
+        Some(ref c) => Wallet::from_config(c).with_context(|| "block producer wallet"),
+        None => bail!("no block producer wallet config"),
+    }
+}
+
+/// Wallet of challenge txs and of cleaning verifier cells created by them.
+fn challenger_wallet(block_producer_config: &BlockProducerConfig) -> Result<Wallet> {
+    let wallet_config = match block_producer_config.challenger_wallet_config {
+        Some(ref c) => c,
+        None => match block_producer_config.wallet_config {
+            Some(ref c) => c,
+            None => bail!("no wallet config for challenger"),
+        },
+    };
+    Wallet::from_config(wallet_config).with_context(|| "challenger wallet")
+}
+
+/// Run the withdrawal unlocker alone, without the chain and the mem pool.
+///
+/// Metrics and `/livez` are served on `rpc_server.listen`.
+pub async fn run_withdrawal_unlocker(config: Config) -> Result<()> {
+    let block_producer_config = config
+        .block_producer
+        .as_ref()
+        .ok_or_else(|| anyhow!("withdrawal unlocker requires block producer config"))?;
+    let unlocker_config = &block_producer_config.withdrawal_unlocker;
+
+    let rpc_client = build_rpc_client(&config)?;
+    rpc_client.spawn_health_check(&config.rpc_client.health_check);
+    let rollup_config: RollupConfig = config.genesis.rollup_config.clone().into();
+    gw_rpc_client::contract::check_script(
+        &config.consensus.contract_type_scripts,
+        &rollup_config,
+        &config.chain.rollup_type_script,
+    )?;
+    let contracts_dep_manager = ContractsCellDepManager::build(
+        rpc_client.clone(),
+        config.consensus.contract_type_scripts.clone(),
+        block_producer_config.contracts_dep_groups.clone(),
+        block_producer_config.rollup_config_cell_dep.clone(),
+    )
+    .await?;
+    let ckb_genesis_info = query_ckb_genesis_info(&rpc_client).await?;
+
+    let fee_estimator = FeeEstimator::new(
+        rpc_client.clone(),
+        block_producer_config.fee_estimator.clone(),
+        block_producer_config.fee_rate,
+    );
+    let mut unlocker = FinalizedWithdrawalUnlocker::new(WithdrawalUnlockerNewArgs {
+        rpc_client,
+        local_cells_manager: Arc::new(Mutex::new(LocalCellsManager::default())),
+        ckb_genesis_info,
+        contracts_dep_manager,
+        wallet: unlocker_wallet(block_producer_config)?,
+        debug_config: config.debug.clone(),
+        fee_estimator,
+        config: unlocker_config.clone(),
+        store: None,
+    });
+
+    let poll_interval = Duration::from_secs(unlocker_config.poll_interval_secs);
+    let liveness = Arc::new(Liveness::new(Duration::from_secs(
+        config.liveness_duration_secs.unwrap_or(60),
+    )));
+    let rpc_address: SocketAddr = {
+        let mut addrs: Vec<_> = config.rpc_server.listen.to_socket_addrs()?.collect();
+        if addrs.len() != 1 {
+            bail!("Invalid RPC listen address `{}`", config.rpc_server.listen);
+        }
+        addrs.remove(0)
+    };
+    let (shutdown_send, shutdown_recv) = broadcast::channel(1);
+    let server_task = spawn(serve_metrics(rpc_address, liveness.clone(), shutdown_recv));
+
+    log::info!("[unlock withdrawal] standalone unlocker started");
+    let unlock_task = async {
+        loop {
+            if let Err(err) = unlocker.poll().await {
+                log::error!("[unlock withdrawal] {}", err);
+            }
+            liveness.tick();
+            tokio::time::sleep(poll_interval).await;
+        }
+    };
+    tokio::select! {
+        _ = sigint_or_sigterm() => {},
+        _ = unlock_task => {},
+    }
 
+    let _ = shutdown_send.send(());
+    let _ = server_task.await;
     Ok(())
 }
 
+/// Refund deposits of the depositor key at `privkey_path` whose cancel timeout
+/// has passed. Returns the refund tx hash, or `None` if there is nothing to
+/// refund.
+pub async fn refund_deposits(
+    config: &Config,
+    privkey_path: &Path,
+    max_deposits: usize,
+    dry_run: bool,
+) -> Result<Option<H256>> {
+    let block_producer_config = config
+        .block_producer
+        .as_ref()
+        .ok_or_else(|| anyhow!("refund deposits requires block producer config"))?;
+
+    let rpc_client = build_rpc_client(config)?;
+    let contracts_dep = gw_rpc_client::contract::query_cell_deps(
+        &rpc_client,
+        &config.consensus.contract_type_scripts,
+        &block_producer_config.contracts_dep_groups,
+        block_producer_config.rollup_config_cell_dep.clone(),
+    )
+    .await?;
+    let ckb_genesis_info = query_ckb_genesis_info(&rpc_client).await?;
+    let wallet =
+        Wallet::sighash_from_privkey_path(privkey_path, ckb_genesis_info.sighash_type_hash())?;
+
+    let refunder = DepositRefunder {
+        rpc_client: &rpc_client,
+        ckb_genesis_info: &ckb_genesis_info,
+        contracts_dep: &contracts_dep,
+        wallet: &wallet,
+        fee_rate: block_producer_config.fee_rate,
+    };
+    let deposits = refunder.query_refundable_deposits(max_deposits).await?;
+    log::info!("[refund deposit] {} deposits to refund", deposits.len());
+    let tx = match refunder.build_refund_tx(deposits).await? {
+        Some(tx) => tx,
+        None => return Ok(None),
+    };
+
+    let cycles = rpc_client
+        .dry_run_transaction(&tx)
+        .await
+        .context("dry run refund tx")?;
+    log::info!("[refund deposit] tx {} cycles {}", tx.hash().pack(), cycles);
+    if dry_run {
+        return Ok(Some(tx.hash()));
+    }
+    let tx_hash = rpc_client.send_transaction(&tx).await?;
+    Ok(Some(tx_hash))
+}
+
+/// Stake cells locked by the block producer wallet.
+pub async fn stake_status(config: &Config) -> Result<StakeStatus> {
+    let block_producer_config = config
+        .block_producer
+        .as_ref()
+        .ok_or_else(|| anyhow!("stake status requires block producer config"))?;
+
+    let rpc_client = build_rpc_client(config)?;
+    let wallet = block_producer_wallet(block_producer_config)?;
+    crate::stake_manager::query_owner_stake_status(&rpc_client, &wallet).await
+}
+
 async fn check_ckb_version(rpc_client: &RPCClient) -> Result<()> {
     let ckb_version = rpc_client.get_ckb_version().await?;
     let ckb_version = ckb_version.split('(').collect::<Vec<&str>>()[0].trim_end();
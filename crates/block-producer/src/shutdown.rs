@@ -0,0 +1,98 @@
+//! Graceful shutdown of the node.
+//!
+//! On SIGINT or SIGTERM, or when a main task exits, the node:
+//!
+//! 1. marks itself as shutting down, so that `/readyz` fails and RPC writes
+//!    are rejected,
+//! 2. broadcasts the shutdown event, background tasks stop at their next
+//!    await point; in-flight L1 submissions are dropped and picked up again
+//!    after restart,
+//! 3. waits for background tasks to exit, up to the shutdown timeout,
+//! 4. saves the mem block and flushes the store.
+
+use std::{sync::Arc, time::Duration};
+
+use gw_mem_pool::pool::MemPool;
+use gw_store::Store;
+use gw_utils::liveness::Liveness;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+pub struct ShutdownManager {
+    event: broadcast::Sender<()>,
+    completed_send: mpsc::Sender<()>,
+    completed_recv: mpsc::Receiver<()>,
+    timeout: Duration,
+}
+
+impl ShutdownManager {
+    pub fn new(timeout: Duration) -> Self {
+        let (event, _) = broadcast::channel(1);
+        let (completed_send, completed_recv) = mpsc::channel(1);
+        Self {
+            event,
+            completed_send,
+            completed_recv,
+            timeout,
+        }
+    }
+
+    /// Receiver of the shutdown event.
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.event.subscribe()
+    }
+
+    /// Tasks hold a sender until they exit, shutdown waits for all senders
+    /// to be dropped.
+    pub fn completed_sender(&self) -> mpsc::Sender<()> {
+        self.completed_send.clone()
+    }
+
+    /// Stop taking writes and broadcast the shutdown event.
+    pub fn start(&self, liveness: &Liveness) {
+        liveness.start_shutdown();
+        log::info!("send shutdown event");
+        if self.event.send(()).is_err() {
+            log::warn!("no task is listening to the shutdown event");
+        }
+    }
+
+    /// Wait for background tasks, then save the mem block and flush the
+    /// store.
+    pub async fn finish(self, mem_pool: Option<Arc<Mutex<MemPool>>>, store: &Store) {
+        let Self {
+            completed_send,
+            mut completed_recv,
+            timeout,
+            ..
+        } = self;
+
+        // When every sender has been dropped, recv returns None.
+        drop(completed_send);
+        if tokio::time::timeout(timeout, completed_recv.recv())
+            .await
+            .is_err()
+        {
+            log::warn!(
+                "background tasks didn't exit in {}s, continue shutting down",
+                timeout.as_secs()
+            );
+        }
+
+        if let Some(mem_pool) = mem_pool {
+            match tokio::time::timeout(timeout, mem_pool.lock()).await {
+                Ok(mut mem_pool) => {
+                    log::info!("saving mem block");
+                    if let Err(err) = mem_pool.save_mem_block() {
+                        log::error!("save mem block error: {:#}", err);
+                    }
+                }
+                Err(_) => log::error!("mem pool is still locked, skip saving mem block"),
+            }
+        }
+
+        log::info!("flushing store");
+        if let Err(err) = store.flush() {
+            log::error!("flush store error: {:#}", err);
+        }
+    }
+}
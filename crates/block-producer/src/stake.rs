@@ -4,17 +4,21 @@ use ckb_types::{
     prelude::{Builder, Entity},
 };
 use gw_config::ContractsCellDep;
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
 use gw_rpc_client::{
     indexer_client::CKBIndexerClient,
     indexer_types::{Order, SearchKey, SearchKeyFilter},
     rpc_client::RPCClient,
 };
 use gw_types::core::Timepoint;
-use gw_types::offchain::CompatibleFinalizedTimepoint;
+use gw_types::offchain::{global_state_from_slice, CompatibleFinalizedTimepoint};
 use gw_types::{
-    core::ScriptHashType,
+    core::{DepType, ScriptHashType},
     offchain::{CellInfo, InputCellInfo},
-    packed::{CellDep, CellInput, CellOutput, L2Block, Script, StakeLockArgs, StakeLockArgsReader},
+    packed::{
+        CellDep, CellInput, CellOutput, L2Block, Script, StakeLockArgs, StakeLockArgsReader,
+        WitnessArgs,
+    },
     prelude::*,
 };
 use gw_utils::local_cells::{
@@ -190,3 +194,160 @@ pub async fn query_stake(
 
     Ok(stake_cell)
 }
+
+/// A stake cell locked by the owner.
+#[derive(Debug, Clone)]
+pub struct LockedStake {
+    pub cell: CellInfo,
+    pub finalized_timepoint: Timepoint,
+    pub is_finalized: bool,
+}
+
+/// Stake cells locked by the owner, newest first.
+#[derive(Debug, Clone, Default)]
+pub struct StakeStatus {
+    pub locked: Vec<LockedStake>,
+}
+
+impl StakeStatus {
+    /// Finalized stake cells, except the newest one which is reused by the
+    /// next block submission.
+    pub fn reclaimable(&self) -> impl Iterator<Item = &LockedStake> + '_ {
+        self.locked
+            .iter()
+            .skip(1)
+            .filter(|stake| stake.is_finalized)
+    }
+
+    pub fn locked_capacity(&self) -> u64 {
+        { self.locked.iter() }
+            .map(|stake| stake.cell.output.capacity().unpack())
+            .sum()
+    }
+
+    pub fn reclaimable_capacity(&self) -> u64 {
+        { self.reclaimable() }
+            .map(|stake| stake.cell.output.capacity().unpack())
+            .sum()
+    }
+}
+
+/// Query stake cells of `owner_lock_hash`, and check their finality against
+/// the global state of `rollup_cell`.
+pub async fn query_stake_status(
+    rpc_client: &RPCClient,
+    rollup_cell: &CellInfo,
+    owner_lock_hash: &[u8; 32],
+) -> Result<StakeStatus> {
+    let rollup_config = &rpc_client.rollup_config;
+    let lock = Script::new_builder()
+        .code_hash(rollup_config.stake_script_type_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(
+            rpc_client
+                .rollup_type_script
+                .calc_script_hash()
+                .as_bytes()
+                .pack(),
+        )
+        .build();
+    let search_key = SearchKey::with_lock(lock);
+
+    let global_state = global_state_from_slice(&rollup_cell.data)?;
+    let compatible_finalized_timepoint = CompatibleFinalizedTimepoint::from_global_state(
+        &global_state,
+        rollup_config.finality_blocks().unpack(),
+    );
+
+    let mut locked = Vec::new();
+    let mut cursor: Option<JsonBytes> = None;
+    loop {
+        let page = rpc_client
+            .indexer
+            .get_cells(&search_key, &Order::Desc, None, &cursor)
+            .await?;
+        if page.last_cursor.is_empty() {
+            break;
+        }
+        cursor = Some(page.last_cursor);
+        for cell in page.objects {
+            let cell = cell.info();
+            let args = cell.output.as_reader().lock().args().raw_data();
+            let stake_lock_args = match args.get(32..).map(StakeLockArgsReader::from_slice) {
+                Some(Ok(r)) => r,
+                _ => continue,
+            };
+            if stake_lock_args.owner_lock_hash().as_slice() != owner_lock_hash {
+                continue;
+            }
+            let finalized_timepoint =
+                Timepoint::from_full_value(stake_lock_args.stake_finalized_timepoint().unpack());
+            let is_finalized = compatible_finalized_timepoint.is_finalized(&finalized_timepoint);
+            locked.push(LockedStake {
+                cell,
+                finalized_timepoint,
+                is_finalized,
+            });
+        }
+    }
+
+    Ok(StakeStatus { locked })
+}
+
+pub struct ReclaimedStake {
+    pub deps: Vec<CellDep>,
+    pub inputs: Vec<InputCellInfo>,
+    pub witness_args: Vec<WitnessArgs>,
+    pub output: CellOutput,
+}
+
+/// Unlock finalized stake cells to the owner.
+///
+/// The stake lock requires the rollup cell in cell deps and an input of the
+/// owner lock, which is the fee input added when completing the tx, since
+/// the output takes all stake capacity.
+pub fn reclaim_to_owner(
+    rollup_cell: &CellInfo,
+    contracts_dep: &ContractsCellDep,
+    owner_lock: Script,
+    stake_cells: Vec<CellInfo>,
+) -> Option<ReclaimedStake> {
+    if stake_cells.is_empty() {
+        return None;
+    }
+
+    let capacity: u64 = { stake_cells.iter() }
+        .map(|cell| cell.output.capacity().unpack())
+        .sum();
+    let output = CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(owner_lock)
+        .build();
+
+    let witness_args = vec![WitnessArgs::default(); stake_cells.len()];
+    let inputs = { stake_cells.into_iter() }
+        .map(|cell| InputCellInfo {
+            input: CellInput::new_builder()
+                .previous_output(cell.out_point.clone())
+                .build(),
+            cell,
+        })
+        .collect();
+
+    let rollup_dep = CellDep::new_builder()
+        .out_point(rollup_cell.out_point.clone())
+        .dep_type(DepType::Code.into())
+        .build();
+    let deps = vec![
+        rollup_dep,
+        contracts_dep.rollup_config.clone().into(),
+        contracts_dep.stake_cell_lock.clone().into(),
+    ];
+
+    Some(ReclaimedStake {
+        deps,
+        inputs,
+        witness_args,
+        output,
+    })
+}
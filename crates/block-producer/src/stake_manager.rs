@@ -0,0 +1,334 @@
+//! Track stake cells locked for submitted blocks, and reclaim finalized ones
+//! to the block producer wallet.
+//!
+//! Every block submission reuses the newest stake cell, but a new stake cell
+//! is created whenever none is available, e.g. it was consumed by a pending
+//! submission. These stake cells stay locked until they are unlocked by the
+//! owner after finalized.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use gw_config::StakeManagerConfig;
+use gw_rpc_client::contract::ContractsCellDepManager;
+use gw_rpc_client::fee_estimator::FeeEstimator;
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_types::h256::*;
+use gw_types::offchain::{CellInfo, TxStatus};
+use gw_types::packed::Transaction;
+use gw_types::prelude::*;
+use gw_utils::fee::fill_tx_fee_with_local;
+use gw_utils::genesis_info::CKBGenesisInfo;
+use gw_utils::local_cells::LocalCellsManager;
+use gw_utils::query_rollup_cell;
+use gw_utils::transaction_skeleton::TransactionSkeleton;
+use gw_utils::wallet::Wallet;
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::stake::{query_stake_status, reclaim_to_owner, StakeStatus};
+use crate::types::ChainEvent;
+
+pub struct StakeManagerNewArgs {
+    pub rpc_client: RPCClient,
+    pub local_cells_manager: Arc<Mutex<LocalCellsManager>>,
+    pub ckb_genesis_info: CKBGenesisInfo,
+    pub contracts_dep_manager: ContractsCellDepManager,
+    /// Block producer wallet, the owner of stake cells.
+    pub wallet: Wallet,
+    pub fee_estimator: FeeEstimator,
+    pub config: StakeManagerConfig,
+}
+
+struct ReclaimTx {
+    tx: Transaction,
+    stake_cells: usize,
+}
+
+pub struct StakeManager {
+    rpc_client: RPCClient,
+    local_cells_manager: Arc<Mutex<LocalCellsManager>>,
+    ckb_genesis_info: CKBGenesisInfo,
+    contracts_dep_manager: ContractsCellDepManager,
+    wallet: Wallet,
+    fee_estimator: FeeEstimator,
+    config: StakeManagerConfig,
+    reclaim_txs: HashMap<H256, ReclaimTx>,
+}
+
+impl StakeManager {
+    pub fn new(args: StakeManagerNewArgs) -> Self {
+        let StakeManagerNewArgs {
+            rpc_client,
+            local_cells_manager,
+            ckb_genesis_info,
+            contracts_dep_manager,
+            wallet,
+            fee_estimator,
+            config,
+        } = args;
+
+        StakeManager {
+            rpc_client,
+            local_cells_manager,
+            ckb_genesis_info,
+            contracts_dep_manager,
+            wallet,
+            fee_estimator,
+            config,
+            reclaim_txs: Default::default(),
+        }
+    }
+
+    #[instrument(skip_all, name = "stake manager handle_event")]
+    pub async fn handle_event(&mut self, _event: &ChainEvent) -> Result<()> {
+        self.check_reclaim_txs().await?;
+
+        let rollup_cell = {
+            let local_cells_manager = self.local_cells_manager.lock().await;
+            query_rollup_cell(&local_cells_manager, &self.rpc_client).await?
+        };
+        let rollup_cell = match rollup_cell {
+            Some(cell) => cell,
+            None => {
+                log::warn!("[stake manager] rollup cell not found");
+                return Ok(());
+            }
+        };
+        let owner_lock_hash = self.wallet.lock_script().hash();
+        let status = query_stake_status(&self.rpc_client, &rollup_cell, &owner_lock_hash).await?;
+        let metrics = gw_metrics::block_producer();
+        metrics.locked_stake.set(status.locked_capacity());
+        metrics.reclaimable_stake.set(status.reclaimable_capacity());
+
+        // Wait for the pending reclaim tx, since it may consume any stake
+        // cells along with fee cells of the wallet.
+        if !self.config.auto_reclaim || !self.reclaim_txs.is_empty() {
+            return Ok(());
+        }
+        self.reclaim(&rollup_cell, &status).await
+    }
+
+    async fn reclaim(&mut self, rollup_cell: &CellInfo, status: &StakeStatus) -> Result<()> {
+        let mut local_cells_manager = self.local_cells_manager.lock().await;
+        let stake_cells =
+            select_stake_cells(status, &local_cells_manager, self.config.max_inputs_per_tx);
+        let stake_cells_len = stake_cells.len();
+        let owner_lock = self.wallet.lock_script().to_owned();
+        let reclaimed = match reclaim_to_owner(
+            rollup_cell,
+            &self.contracts_dep_manager.load(),
+            owner_lock.clone(),
+            stake_cells,
+        ) {
+            Some(reclaimed) => reclaimed,
+            None => return Ok(()),
+        };
+
+        let mut tx_skeleton = TransactionSkeleton::default();
+        tx_skeleton.cell_deps_mut().extend(reclaimed.deps);
+        tx_skeleton
+            .cell_deps_mut()
+            .push(self.ckb_genesis_info.sighash_dep());
+        tx_skeleton.inputs_mut().extend(reclaimed.inputs);
+        tx_skeleton.witnesses_mut().extend(reclaimed.witness_args);
+        tx_skeleton
+            .outputs_mut()
+            .push((reclaimed.output, Default::default()));
+        let fee_rate = self.fee_estimator.fee_rate().await;
+        fill_tx_fee_with_local(
+            &mut tx_skeleton,
+            &self.rpc_client.indexer,
+            owner_lock,
+            &local_cells_manager,
+            fee_rate,
+        )
+        .await?;
+        let tx = self.wallet.sign_tx_skeleton(tx_skeleton)?;
+
+        let tx_hash = self
+            .rpc_client
+            .send_transaction(&tx)
+            .await
+            .context("send reclaim stake tx")?;
+        log::info!(
+            "[stake manager] reclaim {} stake cells in tx {}",
+            stake_cells_len,
+            tx_hash.pack()
+        );
+        local_cells_manager.apply_tx(&tx.as_reader());
+        self.reclaim_txs.insert(
+            tx_hash,
+            ReclaimTx {
+                tx,
+                stake_cells: stake_cells_len,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Forget committed or dropped reclaim txs, so that cells of dropped ones
+    /// can be spent again.
+    async fn check_reclaim_txs(&mut self) -> Result<()> {
+        let mut done_txs = vec![];
+        for (tx_hash, reclaim_tx) in self.reclaim_txs.iter() {
+            match self.rpc_client.ckb.get_transaction_status(*tx_hash).await? {
+                Some(TxStatus::Pending) | Some(TxStatus::Proposed) => continue,
+                Some(TxStatus::Committed) => {
+                    log::info!(
+                        "[stake manager] reclaimed {} stake cells in tx {}",
+                        reclaim_tx.stake_cells,
+                        tx_hash.pack()
+                    );
+                    gw_metrics::block_producer()
+                        .reclaimed_stake_cells
+                        .inc_by(reclaim_tx.stake_cells as u64);
+                }
+                status => {
+                    log::info!(
+                        "[stake manager] reclaim tx {} status {:?}, drop it",
+                        tx_hash.pack(),
+                        status
+                    );
+                }
+            }
+            done_txs.push(*tx_hash);
+        }
+
+        let mut local_cells_manager = self.local_cells_manager.lock().await;
+        for tx_hash in done_txs {
+            if let Some(reclaim_tx) = self.reclaim_txs.remove(&tx_hash) {
+                local_cells_manager.confirm_tx(&reclaim_tx.tx);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reclaimable stake cells not spent by local txs, at most `max_inputs`.
+fn select_stake_cells(
+    status: &StakeStatus,
+    local_cells_manager: &LocalCellsManager,
+    max_inputs: usize,
+) -> Vec<CellInfo> {
+    { status.reclaimable() }
+        .filter(|stake| !local_cells_manager.is_dead(&stake.cell.out_point))
+        .take(max_inputs)
+        .map(|stake| stake.cell.clone())
+        .collect()
+}
+
+/// Query stake cells of the block producer wallet.
+pub async fn query_owner_stake_status(
+    rpc_client: &RPCClient,
+    wallet: &Wallet,
+) -> Result<StakeStatus> {
+    let rollup_cell = rpc_client
+        .query_rollup_cell()
+        .await?
+        .context("rollup cell not found")?;
+    let owner_lock_hash = wallet.lock_script().hash();
+    query_stake_status(rpc_client, &rollup_cell, &owner_lock_hash).await
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_config::ContractsCellDep;
+    use gw_types::core::Timepoint;
+    use gw_types::packed::{CellOutput, OutPoint, Script};
+
+    use super::*;
+    use crate::stake::LockedStake;
+
+    const CAPACITY: u64 = 500_00000000;
+
+    fn stake(index: u32, is_finalized: bool) -> LockedStake {
+        let cell = CellInfo {
+            out_point: OutPoint::new_builder().index(index.pack()).build(),
+            output: CellOutput::new_builder()
+                .capacity((CAPACITY + index as u64).pack())
+                .build(),
+            data: Default::default(),
+        };
+        LockedStake {
+            cell,
+            finalized_timepoint: Timepoint::from_block_number(index as u64),
+            is_finalized,
+        }
+    }
+
+    fn indexes(cells: &[CellInfo]) -> Vec<u32> {
+        { cells.iter() }
+            .map(|cell| cell.out_point.index().unpack())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_stake_cells() {
+        // Newest first.
+        let status = StakeStatus {
+            locked: vec![
+                stake(0, true),
+                stake(1, false),
+                stake(2, true),
+                stake(3, true),
+                stake(4, true),
+            ],
+        };
+        assert_eq!(status.locked_capacity(), 5 * CAPACITY + 10);
+        // The newest one is kept for the next submission.
+        assert_eq!(status.reclaimable_capacity(), 3 * CAPACITY + 9);
+
+        let mut local_cells_manager = LocalCellsManager::default();
+        let cells = select_stake_cells(&status, &local_cells_manager, 10);
+        assert_eq!(indexes(&cells), vec![2, 3, 4]);
+        let cells = select_stake_cells(&status, &local_cells_manager, 2);
+        assert_eq!(indexes(&cells), vec![2, 3]);
+
+        // Skip stake cells spent by local txs, e.g. a pending reclaim tx.
+        local_cells_manager.lock_cell(status.locked[2].cell.out_point.clone());
+        let cells = select_stake_cells(&status, &local_cells_manager, 2);
+        assert_eq!(indexes(&cells), vec![3, 4]);
+
+        let status = StakeStatus {
+            locked: vec![stake(0, true), stake(1, false)],
+        };
+        assert!(select_stake_cells(&status, &local_cells_manager, 10).is_empty());
+        assert_eq!(status.reclaimable_capacity(), 0);
+    }
+
+    #[test]
+    fn test_reclaim_to_owner() {
+        let rollup_cell = CellInfo {
+            out_point: OutPoint::new_builder().tx_hash([1u8; 32].pack()).build(),
+            ..Default::default()
+        };
+        let contracts_dep = ContractsCellDep::default();
+        let owner_lock = Script::new_builder().args(vec![2u8; 20].pack()).build();
+        assert!(
+            reclaim_to_owner(&rollup_cell, &contracts_dep, owner_lock.clone(), vec![]).is_none()
+        );
+
+        let stake_cells = vec![stake(2, true).cell, stake(3, true).cell];
+        let reclaimed = reclaim_to_owner(
+            &rollup_cell,
+            &contracts_dep,
+            owner_lock.clone(),
+            stake_cells.clone(),
+        )
+        .unwrap();
+        let input_cells: Vec<_> = reclaimed.inputs.iter().map(|i| i.cell.clone()).collect();
+        assert_eq!(indexes(&input_cells), vec![2, 3]);
+        for (input, cell) in reclaimed.inputs.iter().zip(stake_cells.iter()) {
+            assert_eq!(input.input.previous_output(), cell.out_point);
+        }
+        assert_eq!(reclaimed.witness_args.len(), 2);
+        assert_eq!(reclaimed.output.lock(), owner_lock);
+        let capacity: u64 = reclaimed.output.capacity().unpack();
+        assert_eq!(capacity, 2 * CAPACITY + 5);
+        assert_eq!(reclaimed.deps[0].out_point(), rollup_cell.out_point);
+    }
+}
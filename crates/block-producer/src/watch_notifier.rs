@@ -0,0 +1,232 @@
+//! Notify a webhook of events of watched registry addresses, e.g. hot wallets
+//! of an exchange.
+//!
+//! Events are collected from blocks attached to the chain: deposits to a
+//! watched address, withdrawals of a watched address finalized by the block,
+//! and txs sent by a watched address or with sUDT logs of one. Events of
+//! reverted blocks are not retracted, so receivers should check the block
+//! hash again after reorgs.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use ckb_fixed_hash::H256 as JsonH256;
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, state::State};
+use gw_config::WatchListConfig;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{Uint128, Uint32, Uint64},
+    godwoken::RegistryAddress as JsonRegistryAddress,
+};
+use gw_store::{state::MemStateDB, traits::chain_store::ChainStore, Store};
+use gw_types::{h256::*, packed::L2Block, prelude::*, registry_address::RegistryAddress};
+use gw_utils::{
+    calc_finalizing_range,
+    script_log::{parse_log, GwLog},
+    RollupContext,
+};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchEvent {
+    Deposit {
+        address: JsonRegistryAddress,
+        block_number: Uint64,
+        block_hash: JsonH256,
+        capacity: Uint64,
+        amount: Uint128,
+        sudt_script_hash: JsonH256,
+    },
+    WithdrawalFinalized {
+        address: JsonRegistryAddress,
+        /// Block of the withdrawal.
+        block_number: Uint64,
+        /// Block finalizing the withdrawal.
+        finalized_block_number: Uint64,
+        withdrawal_hash: JsonH256,
+        capacity: Uint64,
+        amount: Uint128,
+        sudt_script_hash: JsonH256,
+    },
+    Transaction {
+        address: JsonRegistryAddress,
+        block_number: Uint64,
+        block_hash: JsonH256,
+        tx_index: Uint32,
+        tx_hash: JsonH256,
+    },
+}
+
+pub struct WatchNotifier {
+    store: Store,
+    rollup_context: RollupContext,
+    addresses: HashSet<RegistryAddress>,
+    webhook_url: String,
+    client: reqwest::Client,
+}
+
+impl WatchNotifier {
+    pub fn new(store: Store, rollup_context: RollupContext, config: &WatchListConfig) -> Self {
+        let addresses = { config.addresses.iter() }
+            .map(|a| RegistryAddress::new(a.registry_id, a.address.as_bytes().to_vec()))
+            .collect();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .expect("build webhook client");
+        WatchNotifier {
+            store,
+            rollup_context,
+            addresses,
+            webhook_url: config.webhook_url.clone(),
+            client,
+        }
+    }
+
+    pub fn webhook_url(&self) -> &str {
+        &self.webhook_url
+    }
+
+    /// Notify events of new blocks until the mem pool is dropped.
+    pub async fn run(self, mut new_blocks: broadcast::Receiver<L2Block>) {
+        loop {
+            let block = match new_blocks.recv().await {
+                Ok(block) => block,
+                Err(RecvError::Lagged(n)) => {
+                    log::warn!("[watch list] missed {} blocks", n);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let events =
+                match block_events(&self.store, &self.rollup_context, &self.addresses, &block) {
+                    Ok(events) => events,
+                    Err(err) => {
+                        log::warn!("[watch list] block events error {:#}", err);
+                        continue;
+                    }
+                };
+            for event in events {
+                if let Err(err) = self.post(&event).await {
+                    log::warn!("[watch list] post {:?} error {:#}", event, err);
+                }
+            }
+        }
+    }
+
+    async fn post(&self, event: &WatchEvent) -> Result<()> {
+        self.client
+            .post(&self.webhook_url)
+            .json(event)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Events of `addresses` in `block`, which must be attached to the chain.
+pub fn block_events(
+    store: &Store,
+    rollup_context: &RollupContext,
+    addresses: &HashSet<RegistryAddress>,
+    block: &L2Block,
+) -> Result<Vec<WatchEvent>> {
+    let snap = store.get_snapshot();
+    let state = MemStateDB::from_store(store.get_snapshot())?;
+    let block_number: u64 = block.raw().number().unpack();
+    let block_hash = block.hash();
+    let watched = |registry_id: u32, script_hash: &H256| -> Result<Option<RegistryAddress>> {
+        let address = state.get_registry_address_by_script_hash(registry_id, script_hash)?;
+        Ok(address.filter(|a| addresses.contains(a)))
+    };
+    let mut events = Vec::new();
+
+    let deposits = snap.get_block_deposit_info_vec(block_number);
+    for deposit in deposits.into_iter().flatten() {
+        let request = deposit.request();
+        let registry_id: u32 = request.registry_id().unpack();
+        if let Some(address) = watched(registry_id, &request.script().hash())? {
+            let capacity: u64 = request.capacity().unpack();
+            let amount: u128 = request.amount().unpack();
+            let sudt_script_hash: H256 = request.sudt_script_hash().unpack();
+            events.push(WatchEvent::Deposit {
+                address: address.into(),
+                block_number: block_number.into(),
+                block_hash: block_hash.into(),
+                capacity: capacity.into(),
+                amount: amount.into(),
+                sudt_script_hash: sudt_script_hash.into(),
+            });
+        }
+    }
+
+    let finalizing_range = calc_finalizing_range(
+        &rollup_context.rollup_config,
+        &rollup_context.fork_config,
+        &snap,
+        block,
+    )?;
+    for number in finalizing_range {
+        let finalized_block = snap
+            .get_block_hash_by_number(number)?
+            .and_then(|hash| snap.get_block(&hash).transpose())
+            .transpose()?
+            .with_context(|| format!("get finalized block {}", number))?;
+        for withdrawal in finalized_block.withdrawals().into_iter() {
+            let raw = withdrawal.raw();
+            let registry_id: u32 = raw.registry_id().unpack();
+            let script_hash: H256 = raw.account_script_hash().unpack();
+            if let Some(address) = watched(registry_id, &script_hash)? {
+                let capacity: u64 = raw.capacity().unpack();
+                let amount: u128 = raw.amount().unpack();
+                let sudt_script_hash: H256 = raw.sudt_script_hash().unpack();
+                events.push(WatchEvent::WithdrawalFinalized {
+                    address: address.into(),
+                    block_number: number.into(),
+                    finalized_block_number: block_number.into(),
+                    withdrawal_hash: withdrawal.hash().into(),
+                    capacity: capacity.into(),
+                    amount: amount.into(),
+                    sudt_script_hash: sudt_script_hash.into(),
+                });
+            }
+        }
+    }
+
+    for (index, tx) in block.transactions().into_iter().enumerate() {
+        let tx_hash = tx.hash();
+        let mut tx_addresses = Vec::new();
+        let from_id: u32 = tx.raw().from_id().unpack();
+        let sender_script_hash = state.get_script_hash(from_id)?;
+        tx_addresses.extend(watched(ETH_REGISTRY_ACCOUNT_ID, &sender_script_hash)?);
+        let receipt = snap.get_transaction_receipt(&tx_hash)?;
+        for log in receipt.iter().flat_map(|r| r.logs().into_iter()) {
+            match parse_log(&log) {
+                Ok(GwLog::SudtTransfer {
+                    from_address,
+                    to_address,
+                    ..
+                }) => tx_addresses.extend([from_address, to_address]),
+                Ok(GwLog::SudtPayFee { from_address, .. }) => tx_addresses.push(from_address),
+                _ => {}
+            }
+        }
+        let mut seen = HashSet::new();
+        for address in tx_addresses {
+            if addresses.contains(&address) && seen.insert(address.clone()) {
+                events.push(WatchEvent::Transaction {
+                    address: address.into(),
+                    block_number: block_number.into(),
+                    block_hash: block_hash.into(),
+                    tx_index: (index as u32).into(),
+                    tx_hash: tx_hash.into(),
+                });
+            }
+        }
+    }
+
+    Ok(events)
+}
@@ -2,7 +2,10 @@
 
 use anyhow::{anyhow, Result};
 use gw_config::ContractsCellDep;
-use gw_mem_pool::{custodian::sum_withdrawals, withdrawal::Generator};
+use gw_mem_pool::{
+    custodian::{custodian_capacity, custodian_sudt_amount, pick_custodians, sum_withdrawals},
+    withdrawal::Generator,
+};
 use gw_types::core::Timepoint;
 use gw_types::h256::*;
 use gw_types::offchain::CompatibleFinalizedTimepoint;
@@ -10,11 +13,14 @@ use gw_types::packed::RollupConfig;
 use gw_types::{
     bytes::Bytes,
     core::{DepType, ScriptHashType},
-    offchain::{global_state_from_slice, CellInfo, CollectedCustodianCells, InputCellInfo},
+    offchain::{
+        global_state_from_slice, CellInfo, CollectedCustodianCells, InputCellInfo,
+        WithdrawalsAmount,
+    },
     packed::{
         CellDep, CellInput, CellOutput, CustodianLockArgs, DepositLockArgs, L2Block, Script,
         UnlockWithdrawalViaFinalize, UnlockWithdrawalViaRevert, UnlockWithdrawalWitness,
-        UnlockWithdrawalWitnessUnion, WithdrawalRequestExtra, WitnessArgs,
+        UnlockWithdrawalWitnessUnion, WithdrawalRequest, WithdrawalRequestExtra, WitnessArgs,
     },
     prelude::*,
 };
@@ -34,20 +40,27 @@ pub struct GeneratedWithdrawals {
 // Note: custodian lock search rollup cell in inputs
 pub fn generate(
     rollup_context: &RollupContext,
-    mut finalized_custodians: CollectedCustodianCells,
+    finalized_custodians: CollectedCustodianCells,
     block: &L2Block,
     contracts_dep: &ContractsCellDep,
     withdrawal_extras: &HashMap<H256, WithdrawalRequestExtra>,
+    max_custodian_inputs: usize,
 ) -> Result<Option<GeneratedWithdrawals>> {
     if block.withdrawals().is_empty() && finalized_custodians.cells_info.len() <= 1 {
         return Ok(None);
     }
+
+    let total_withdrawal_amount = sum_withdrawals(block.withdrawals().into_iter());
+    let mut finalized_custodians = select_custodians(
+        rollup_context,
+        finalized_custodians,
+        &total_withdrawal_amount,
+        max_custodian_inputs,
+    )?;
     log::debug!("custodian inputs {:?}", finalized_custodians);
 
     let cells_info = std::mem::take(&mut finalized_custodians.cells_info);
     let cusotidan_sudt_is_empty = finalized_custodians.sudt.is_empty();
-
-    let total_withdrawal_amount = sum_withdrawals(block.withdrawals().into_iter());
     let mut generator = Generator::new(rollup_context, finalized_custodians.into());
     for req in block.withdrawals().into_iter() {
         let req_extra = match withdrawal_extras.get(&req.hash()) {
@@ -83,6 +96,79 @@ pub fn generate(
     Ok(Some(generated_withdrawals))
 }
 
+/// Select at most `max_inputs` custodian cells that cover `withdrawals_amount`.
+///
+/// All cells are used if they fit. Otherwise cells are picked largest first,
+/// sUDT cells of withdrawn sUDTs before CKB cells, then the remaining input
+/// slots are filled with the smallest cells left so that small custodians
+/// still get merged.
+fn select_custodians(
+    rollup_context: &RollupContext,
+    custodians: CollectedCustodianCells,
+    withdrawals_amount: &WithdrawalsAmount,
+    max_inputs: usize,
+) -> Result<CollectedCustodianCells> {
+    if custodians.cells_info.len() <= max_inputs {
+        return Ok(custodians);
+    }
+
+    let (mut selected, mut rest) =
+        pick_custodians(rollup_context, custodians.cells_info, withdrawals_amount)?;
+    // Withdrawals over the cap are dropped when the block is produced, see
+    // `count_withdrawals_within_inputs`. The block is committed already, so
+    // go ahead with the picked cells.
+    if selected.len() > max_inputs {
+        log::warn!(
+            "withdrawals need {} custodian inputs, more than {}",
+            selected.len(),
+            max_inputs
+        );
+    }
+
+    rest.sort_by_key(custodian_capacity);
+    let free_slots = max_inputs.saturating_sub(selected.len());
+    selected.extend(rest.into_iter().take(free_slots));
+
+    let mut collected = CollectedCustodianCells::default();
+    for cell in selected {
+        collected.capacity += custodian_capacity(&cell);
+        if let Some(type_script) = cell.output.type_().to_opt() {
+            let (amount, _) = collected
+                .sudt
+                .entry(type_script.hash())
+                .or_insert((0, type_script));
+            *amount += custodian_sudt_amount(&cell);
+        }
+        collected.cells_info.push(cell);
+    }
+    Ok(collected)
+}
+
+/// Count the leading `withdrawals` that `custodians` can cover with at most
+/// `max_inputs` custodian inputs.
+pub fn count_withdrawals_within_inputs(
+    rollup_context: &RollupContext,
+    custodians: &[CellInfo],
+    withdrawals: &[WithdrawalRequest],
+    max_inputs: usize,
+) -> usize {
+    if custodians.len() <= max_inputs {
+        return withdrawals.len();
+    }
+
+    let fits = |count: usize| {
+        let amount = sum_withdrawals(withdrawals.iter().take(count).cloned());
+        match pick_custodians(rollup_context, custodians.to_vec(), &amount) {
+            Ok((picked, _rest)) => picked.len() <= max_inputs,
+            Err(_) => false,
+        }
+    };
+    (1..=withdrawals.len())
+        .take_while(|&count| fits(count))
+        .last()
+        .unwrap_or(0)
+}
+
 pub struct RevertedWithdrawals {
     pub deps: Vec<CellDep>,
     pub inputs: Vec<InputCellInfo>,
@@ -342,12 +428,14 @@ mod test {
     use std::iter::FromIterator;
 
     use crate::utils::global_state_last_finalized_timepoint_to_since;
-    use crate::withdrawal::generate;
+    use crate::withdrawal::{count_withdrawals_within_inputs, generate, select_custodians};
     use gw_config::{ContractsCellDep, ForkConfig};
+    use gw_mem_pool::custodian::{calc_ckb_custodian_min_capacity, generate_finalized_custodian};
     use gw_types::core::{DepType, ScriptHashType, Timepoint};
     use gw_types::h256::*;
     use gw_types::offchain::{
         CellInfo, CollectedCustodianCells, CompatibleFinalizedTimepoint, InputCellInfo,
+        WithdrawalsAmount,
     };
     use gw_types::packed::{
         BlockMerkleState, CellDep, CellInput, CellOutput, GlobalState, L2Block, OutPoint,
@@ -426,6 +514,7 @@ mod test {
             &block,
             &contracts_dep,
             &withdrawal_extras,
+            usize::MAX,
         )
         .unwrap();
         let (output, data) = generated.unwrap().outputs.first().unwrap().to_owned();
@@ -462,6 +551,118 @@ mod test {
         .expect("pass verification");
     }
 
+    #[test]
+    fn test_select_custodians() {
+        const CKB: u64 = 100_000_000;
+
+        let rollup_context = RollupContext {
+            rollup_script_hash: H256::from_u32(1),
+            rollup_config: RollupConfig::new_builder()
+                .custodian_script_type_hash(H256::from_u32(100).pack())
+                .build(),
+            ..Default::default()
+        };
+        let sudt_script = Script::new_builder()
+            .code_hash(H256::from_u32(2).pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(vec![3u8; 32].pack())
+            .build();
+
+        let ckb_cell = |capacity: u64| CellInfo {
+            output: CellOutput::new_builder()
+                .capacity((capacity * CKB).pack())
+                .build(),
+            ..Default::default()
+        };
+        let sudt_cell = |amount: u128| CellInfo {
+            output: CellOutput::new_builder()
+                .capacity((500 * CKB).pack())
+                .type_(Some(sudt_script.clone()).pack())
+                .build(),
+            data: amount.pack().as_bytes(),
+            ..Default::default()
+        };
+        let mut cells_info: Vec<_> = (1..=10).map(|i| ckb_cell(i * 100)).collect();
+        cells_info.extend([10, 50, 100].into_iter().map(sudt_cell));
+        let custodians = CollectedCustodianCells {
+            cells_info,
+            ..Default::default()
+        };
+
+        let withdrawals_amount = WithdrawalsAmount {
+            capacity: (1500 * CKB) as u128,
+            sudt: HashMap::from_iter([(sudt_script.hash(), 40)]),
+        };
+
+        let selected =
+            select_custodians(&rollup_context, custodians.clone(), &withdrawals_amount, 4).unwrap();
+        assert_eq!(selected.cells_info.len(), 4);
+        // The 50 sudt cell covers the withdrawal with the least change.
+        assert_eq!(selected.sudt.get(&sudt_script.hash()).unwrap().0, 50);
+        let sudt_change_capacity: u64 =
+            generate_finalized_custodian(&rollup_context, 10, sudt_script.clone())
+                .0
+                .capacity()
+                .unpack();
+        let required_capacity = withdrawals_amount.capacity
+            + calc_ckb_custodian_min_capacity(&rollup_context) as u128
+            + sudt_change_capacity as u128;
+        assert!(selected.capacity >= required_capacity);
+        // The free input slot merges the smallest cell.
+        assert!(selected
+            .cells_info
+            .iter()
+            .any(|cell| cell.output.capacity().unpack() == 100 * CKB));
+
+        // Picked cells are used even if they exceed the cap, the block is
+        // already produced.
+        let selected =
+            select_custodians(&rollup_context, custodians, &withdrawals_amount, 2).unwrap();
+        assert_eq!(selected.cells_info.len(), 3);
+        assert!(selected.capacity >= required_capacity);
+    }
+
+    #[test]
+    fn test_count_withdrawals_within_inputs() {
+        const CKB: u64 = 100_000_000;
+
+        let rollup_context = RollupContext {
+            rollup_script_hash: H256::from_u32(1),
+            rollup_config: RollupConfig::new_builder()
+                .custodian_script_type_hash(H256::from_u32(100).pack())
+                .build(),
+            ..Default::default()
+        };
+
+        let custodians: Vec<_> = (1..=10)
+            .map(|i| CellInfo {
+                output: CellOutput::new_builder()
+                    .capacity((i * 100 * CKB).pack())
+                    .build(),
+                ..Default::default()
+            })
+            .collect();
+        let withdrawal = WithdrawalRequest::new_builder()
+            .raw(
+                RawWithdrawalRequest::new_builder()
+                    .capacity((1000 * CKB).pack())
+                    .build(),
+            )
+            .build();
+        let withdrawals = vec![withdrawal; 3];
+
+        // All custodians fit.
+        let count = count_withdrawals_within_inputs(&rollup_context, &custodians, &withdrawals, 10);
+        assert_eq!(count, 3);
+
+        // The third withdrawal needs a fourth custodian input.
+        let count = count_withdrawals_within_inputs(&rollup_context, &custodians, &withdrawals, 3);
+        assert_eq!(count, 2);
+
+        let count = count_withdrawals_within_inputs(&rollup_context, &custodians, &withdrawals, 1);
+        assert_eq!(count, 0);
+    }
+
     #[test]
     fn test_unlock_to_owner_v1() {
         // Output should only change lock to owner lock
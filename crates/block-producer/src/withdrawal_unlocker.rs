@@ -2,12 +2,17 @@
 
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Result};
 use async_trait::async_trait;
-use gw_config::{ContractsCellDep, DebugConfig};
+use gw_config::{ContractsCellDep, DebugConfig, WithdrawalUnlockerConfig};
 use gw_rpc_client::contract::ContractsCellDepManager;
+use gw_rpc_client::fee_estimator::FeeEstimator;
 use gw_rpc_client::rpc_client::RPCClient;
+use gw_rpc_client::withdrawal::owner_lock_hash;
+use gw_store::traits::chain_store::ChainStore;
+use gw_store::Store;
 use gw_types::h256::*;
 use gw_types::offchain::{
     global_state_from_slice, CellInfo, CompatibleFinalizedTimepoint, TxStatus,
@@ -20,6 +25,7 @@ use gw_utils::local_cells::LocalCellsManager;
 use gw_utils::query_rollup_cell;
 use gw_utils::transaction_skeleton::TransactionSkeleton;
 use gw_utils::wallet::Wallet;
+use gw_utils::withdrawal::parse_lock_args;
 use tokio::sync::Mutex;
 use tracing::instrument;
 
@@ -31,23 +37,49 @@ pub use gw_rpc_client::contract::Guard;
 
 const TRANSACTION_FAILED_TO_RESOLVE_ERROR: &str = "TransactionFailedToResolve";
 
+pub struct WithdrawalUnlockerNewArgs {
+    pub rpc_client: RPCClient,
+    pub local_cells_manager: Arc<Mutex<LocalCellsManager>>,
+    pub ckb_genesis_info: CKBGenesisInfo,
+    pub contracts_dep_manager: ContractsCellDepManager,
+    pub wallet: Wallet,
+    pub debug_config: DebugConfig,
+    pub fee_estimator: FeeEstimator,
+    pub config: WithdrawalUnlockerConfig,
+    /// Store to record unlock txs in. `None` when running standalone.
+    pub store: Option<Store>,
+}
+
+struct UnlockTx {
+    out_points: Vec<OutPoint>,
+    sent_at: Instant,
+}
+
 pub struct FinalizedWithdrawalUnlocker {
     unlocker: DefaultUnlocker,
     unlocked_set: HashSet<OutPoint>,
-    unlock_txs: HashMap<H256, Vec<OutPoint>>,
+    unlock_txs: HashMap<H256, UnlockTx>,
     debug_config: DebugConfig,
+    config: WithdrawalUnlockerConfig,
+    fee_estimator: FeeEstimator,
+    store: Option<Store>,
 }
 
 impl FinalizedWithdrawalUnlocker {
-    pub fn new(
-        rpc_client: RPCClient,
-        local_cells_manager: Arc<Mutex<LocalCellsManager>>,
-        ckb_genesis_info: CKBGenesisInfo,
-        contracts_dep_manager: ContractsCellDepManager,
-        wallet: Wallet,
-        debug_config: DebugConfig,
-        fee_rate: u64,
-    ) -> Self {
+    pub fn new(args: WithdrawalUnlockerNewArgs) -> Self {
+        let WithdrawalUnlockerNewArgs {
+            rpc_client,
+            local_cells_manager,
+            ckb_genesis_info,
+            contracts_dep_manager,
+            wallet,
+            debug_config,
+            fee_estimator,
+            config,
+            store,
+        } = args;
+        let fee_rate = fee_estimator.default_fee_rate();
+        let owner_lock_hashes = config.owner_lock_hashes.iter().map(|h| h.0).collect();
         let unlocker = DefaultUnlocker::new(
             rpc_client,
             local_cells_manager,
@@ -55,18 +87,31 @@ impl FinalizedWithdrawalUnlocker {
             contracts_dep_manager,
             wallet,
             fee_rate,
+            config.max_inputs_per_tx,
+            owner_lock_hashes,
         );
+        gw_metrics::withdrawal_unlocker().fee_rate.set(fee_rate);
 
         FinalizedWithdrawalUnlocker {
             unlocker,
             unlocked_set: Default::default(),
             unlock_txs: Default::default(),
             debug_config,
+            config,
+            fee_estimator,
+            store,
         }
     }
 
     #[instrument(skip_all, name = "withdrawal unlocker handle_event")]
     pub async fn handle_event(&mut self, _event: &ChainEvent) -> Result<()> {
+        self.poll().await
+    }
+
+    /// Send an unlock tx for unlockable withdrawals if there are any, and check
+    /// the status of sent unlock txs.
+    pub async fn poll(&mut self) -> Result<()> {
+        let metrics = gw_metrics::withdrawal_unlocker();
         let unlocked = &self.unlocked_set;
         let rpc_client = &self.unlocker.rpc_client;
         if let Some((tx, to_unlock)) = self.unlocker.query_and_unlock_to_owner(unlocked).await? {
@@ -93,18 +138,30 @@ impl FinalizedWithdrawalUnlocker {
             };
 
             log::info!(
-                "[unlock withdrawal] try unlock {} withdrawals in tx {}",
+                "[unlock withdrawal] try unlock {} withdrawals in tx {}, fee rate {}",
                 to_unlock.len(),
-                tx_hash.pack()
+                tx_hash.pack(),
+                self.unlocker.fee_rate
             );
+            metrics.unlock_txs.inc();
 
             self.unlocked_set.extend(to_unlock.clone());
-            self.unlock_txs.insert(tx_hash, to_unlock);
+            self.unlock_txs.insert(
+                tx_hash,
+                UnlockTx {
+                    out_points: to_unlock,
+                    sent_at: Instant::now(),
+                },
+            );
         }
 
         // Check unlock tx
+        let fee_bump_timeout = Duration::from_secs(self.config.fee_bump_timeout_secs);
         let mut drop_txs = vec![];
-        for (tx_hash, withdrawal_to_unlock) in self.unlock_txs.iter() {
+        let mut committed_txs = vec![];
+        let mut bump_fee = false;
+        for (tx_hash, unlock_tx) in self.unlock_txs.iter() {
+            let withdrawal_to_unlock = &unlock_tx.out_points;
             match rpc_client.ckb.get_transaction_status(*tx_hash).await {
                 Err(err) => {
                     // Always drop this unlock tx and retry to avoid "lock" withdrawal cell
@@ -113,17 +170,35 @@ impl FinalizedWithdrawalUnlocker {
                 }
                 Ok(None) => {
                     log::info!("[unlock withdrawal] dropped unlock tx {}", tx_hash.pack());
+                    metrics.dropped_txs.inc();
                     drop_txs.push(*tx_hash);
                 }
                 Ok(Some(tx_status)) => {
                     match tx_status {
-                        TxStatus::Pending | TxStatus::Proposed => continue, // Wait
+                        TxStatus::Pending | TxStatus::Proposed => {
+                            if unlock_tx.sent_at.elapsed() < fee_bump_timeout {
+                                continue; // Wait
+                            }
+                            // Rebuild it with a higher fee rate next time, the
+                            // new tx replaces this one since they have the
+                            // same withdrawal inputs.
+                            log::info!(
+                                "[unlock withdrawal] unlock tx {} pending for {}s, bump fee rate",
+                                tx_hash.pack(),
+                                unlock_tx.sent_at.elapsed().as_secs()
+                            );
+                            bump_fee = true;
+                        }
                         TxStatus::Committed => {
                             log::info!(
                                 "[unlock withdrawal] unlock {} withdrawals in tx {}",
                                 withdrawal_to_unlock.len(),
                                 tx_hash.pack(),
                             );
+                            metrics
+                                .unlocked_withdrawals
+                                .inc_by(withdrawal_to_unlock.len() as u64);
+                            committed_txs.push(*tx_hash);
                         }
                         TxStatus::Unknown | TxStatus::Rejected => {
                             log::debug!(
@@ -131,6 +206,7 @@ impl FinalizedWithdrawalUnlocker {
                                 tx_hash.pack(),
                                 tx_status
                             );
+                            metrics.dropped_txs.inc();
                         }
                         _ => {
                             log::warn!(
@@ -145,16 +221,107 @@ impl FinalizedWithdrawalUnlocker {
             }
         }
 
+        for tx_hash in committed_txs.iter() {
+            if let Err(err) = self.record_unlocked(*tx_hash).await {
+                log::warn!(
+                    "[unlock withdrawal] record unlock tx {}: {:#}",
+                    tx_hash.pack(),
+                    err
+                );
+            }
+        }
+
+        if bump_fee {
+            let fee_rate = self.unlocker.fee_rate * self.config.fee_bump_percent / 100;
+            self.set_fee_rate(fee_rate.min(self.config.max_fee_rate));
+            metrics.fee_bumps.inc();
+        } else if !committed_txs.is_empty() || self.unlock_txs.is_empty() {
+            // Nothing to replace, follow the estimated fee rate.
+            let fee_rate = self.fee_estimator.fee_rate().await;
+            self.set_fee_rate(fee_rate);
+        }
+
         for tx_hash in drop_txs {
-            if let Some(out_points) = self.unlock_txs.remove(&tx_hash) {
-                for out_point in out_points {
+            if let Some(unlock_tx) = self.unlock_txs.remove(&tx_hash) {
+                for out_point in unlock_tx.out_points {
                     self.unlocked_set.remove(&out_point);
                 }
             }
         }
+        metrics.pending_txs.set(self.unlock_txs.len() as u64);
 
         Ok(())
     }
+
+    fn set_fee_rate(&mut self, fee_rate: u64) {
+        self.unlocker.fee_rate = fee_rate;
+        gw_metrics::withdrawal_unlocker().fee_rate.set(fee_rate);
+    }
+
+    /// Store the unlock tx hash of withdrawals unlocked by `unlock_tx_hash`.
+    async fn record_unlocked(&self, unlock_tx_hash: H256) -> Result<()> {
+        let store = match self.store {
+            Some(ref store) => store,
+            None => return Ok(()),
+        };
+        let out_points = match self.unlock_txs.get(&unlock_tx_hash) {
+            Some(unlock_tx) => &unlock_tx.out_points,
+            None => return Ok(()),
+        };
+        let mut submit_txs = HashMap::new();
+        let mut withdrawal_hashes = Vec::with_capacity(out_points.len());
+        for out_point in out_points {
+            let submit_tx_hash: H256 = out_point.tx_hash().unpack();
+            if !submit_txs.contains_key(&submit_tx_hash) {
+                let rpc_client = &self.unlocker.rpc_client;
+                match rpc_client.ckb.get_transaction(submit_tx_hash).await? {
+                    Some(tx) => submit_txs.insert(submit_tx_hash, tx),
+                    None => continue,
+                };
+            }
+            let index = out_point.index().unpack() as usize;
+            let submit_tx = &submit_txs[&submit_tx_hash];
+            if let Some(hash) =
+                withdrawal_hash_of_cell(store, self.unlocker.rollup_config(), submit_tx, index)?
+            {
+                withdrawal_hashes.push(hash);
+            }
+        }
+
+        let mut db = store.begin_transaction();
+        for withdrawal_hash in withdrawal_hashes {
+            db.set_withdrawal_unlock_tx_hash(&withdrawal_hash, &unlock_tx_hash)?;
+        }
+        db.commit()
+    }
+}
+
+/// Withdrawal cells are generated in the same order as withdrawals in the
+/// block, so the n-th withdrawal cell of a block submission tx belongs to the
+/// n-th withdrawal of the block.
+fn withdrawal_hash_of_cell(
+    store: &Store,
+    rollup_config: &RollupConfig,
+    submit_tx: &Transaction,
+    index: usize,
+) -> Result<Option<H256>> {
+    let outputs = submit_tx.raw().outputs();
+    let output = match outputs.get(index) {
+        Some(output) => output,
+        None => return Ok(None),
+    };
+    let lock_args = parse_lock_args(&output.lock().args().unpack())?.lock_args;
+    let block = match store.get_block(&lock_args.withdrawal_block_hash().unpack())? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+    let withdrawal_code_hash = rollup_config.withdrawal_script_type_hash();
+    let nth = outputs
+        .into_iter()
+        .take(index)
+        .filter(|output| output.lock().code_hash().as_slice() == withdrawal_code_hash.as_slice())
+        .count();
+    Ok(block.withdrawals().get(nth).map(|w| w.hash()))
 }
 
 #[async_trait]
@@ -190,9 +357,11 @@ pub trait BuildUnlockWithdrawalToOwner {
             &global_state,
             self.rollup_config().finality_blocks().unpack(),
         );
-        let unlockable_withdrawals = self
+        let mut unlockable_withdrawals = self
             .query_unlockable_withdrawals(&compatible_finalized_timepoint, unlocked)
             .await?;
+        // Group withdrawals by owner lock.
+        unlockable_withdrawals.sort_by_key(owner_lock_hash);
         log::info!(
             "[unlock withdrawal] find unlockable finalized withdrawals {}",
             unlockable_withdrawals.len()
@@ -232,11 +401,12 @@ struct DefaultUnlocker {
     contracts_dep_manager: ContractsCellDepManager,
     wallet: Wallet,
     fee_rate: u64,
+    max_inputs_per_tx: usize,
+    owner_lock_hashes: HashSet<H256>,
 }
 
 impl DefaultUnlocker {
-    pub const MAX_WITHDRAWALS_PER_TX: usize = 100;
-
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         rpc_client: RPCClient,
         local_cells_manager: Arc<Mutex<LocalCellsManager>>,
@@ -244,6 +414,8 @@ impl DefaultUnlocker {
         contracts_dep_manager: ContractsCellDepManager,
         wallet: Wallet,
         fee_rate: u64,
+        max_inputs_per_tx: usize,
+        owner_lock_hashes: HashSet<H256>,
     ) -> Self {
         DefaultUnlocker {
             rpc_client,
@@ -252,6 +424,8 @@ impl DefaultUnlocker {
             contracts_dep_manager,
             wallet,
             fee_rate,
+            max_inputs_per_tx,
+            owner_lock_hashes,
         }
     }
 }
@@ -280,7 +454,8 @@ impl BuildUnlockWithdrawalToOwner for DefaultUnlocker {
             .query_finalized_owner_lock_withdrawal_cells(
                 compatible_finalized_timepoint,
                 unlocked,
-                Self::MAX_WITHDRAWALS_PER_TX,
+                &self.owner_lock_hashes,
+                self.max_inputs_per_tx,
             )
             .await
     }
@@ -6,6 +6,7 @@ use gw_common::{state::State, CKB_SUDT_SCRIPT_ARGS};
 use gw_config::ChainConfig;
 use gw_generator::{
     generator::{ApplyBlockArgs, ApplyBlockResult},
+    sudt::build_l2_sudt_script,
     traits::StateExt,
     types::vm::ChallengeContext,
     Generator,
@@ -13,12 +14,15 @@ use gw_generator::{
 use gw_jsonrpc_types::debugger::ReprMockTransaction;
 use gw_mem_pool::pool::MemPool;
 use gw_store::{
+    block_stats::BlockStats,
     chain_view::ChainView,
+    mem_pool_state::StateDivergence,
     state::{history::history_state::RWConfig, BlockStateDB},
     traits::chain_store::ChainStore,
     transaction::StoreTransaction,
     Store,
 };
+use gw_telemetry::alert::{alert, Alert};
 use gw_types::{
     bytes::Bytes,
     core::Status,
@@ -27,15 +31,26 @@ use gw_types::{
     packed::{
         BlockMerkleState, Byte32, CellInput, CellOutput, ChallengeTarget, ChallengeWitness,
         DepositInfoVec, GlobalState, L2Block, NumberHash, RawL2Block, RollupConfig, Script,
-        Transaction, WithdrawalRequestExtra,
+        Transaction, TxReceipt, WithdrawalRequestExtra,
     },
     prelude::{Builder as GWBuilder, Entity as GWEntity, Pack as GWPack, Unpack as GWUnpack},
 };
-use gw_utils::calc_finalizing_range;
-use std::{collections::HashSet, convert::TryFrom, sync::Arc, time::Instant};
+use gw_utils::{
+    calc_finalizing_range,
+    polyjuice_parser::PolyjuiceParser,
+    script_log::{parse_log, GwLog, GW_LOG_POLYJUICE_SYSTEM},
+};
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 use tokio::sync::Mutex;
 use tracing::instrument;
 
+use crate::revert::{self, RevertKind, RevertedBlocks};
+
 #[derive(Debug, Clone)]
 pub struct ChallengeCell {
     pub input: CellInput,
@@ -153,6 +168,11 @@ pub struct Chain {
     generator: Arc<Generator>,
     mem_pool: Option<Arc<Mutex<MemPool>>>,
     skipped_invalid_block_list: HashSet<H256>,
+    /// Blocks reverted since the last sync.
+    reverted_blocks: Vec<RevertedBlocks>,
+    last_reverted_blocks: Vec<RevertedBlocks>,
+    /// Set in safe mode, syncing is stopped until restarted.
+    state_divergence: Option<StateDivergence>,
 }
 
 impl Chain {
@@ -184,6 +204,17 @@ impl Chain {
             tip,
             last_global_state,
         };
+        // Register sUDTs deposited before the sUDT registry.
+        {
+            let mut db = store.begin_transaction();
+            let asset_script_hashes = { db.get_asset_script_hashes().into_iter() }
+                .filter(|h| db.get_sudt_account_id(h).is_none())
+                .collect::<Vec<_>>();
+            if !asset_script_hashes.is_empty() {
+                register_sudt_accounts(&mut db, &generator, asset_script_hashes)?;
+                db.commit()?;
+            }
+        }
         let skipped_invalid_block_list = config
             .skipped_invalid_block_list
             .iter()
@@ -200,6 +231,9 @@ impl Chain {
             rollup_type_script_hash,
             rollup_config,
             skipped_invalid_block_list,
+            reverted_blocks: Vec::new(),
+            last_reverted_blocks: Vec::new(),
+            state_divergence: None,
         })
     }
 
@@ -232,6 +266,12 @@ impl Chain {
         &self.last_sync_event
     }
 
+    /// Blocks reverted in the last sync, including those reverted by
+    /// `revert_l1action` before it.
+    pub fn last_reverted_blocks(&self) -> &[RevertedBlocks] {
+        &self.last_reverted_blocks
+    }
+
     pub fn bad_block_hash(&self) -> Option<H256> {
         self.challenge_target
             .as_ref()
@@ -396,7 +436,14 @@ impl Chain {
                         return Err(anyhow!("fork detected"));
                     }
 
-                    let challenge_block_number = witness.raw_l2block().number().unpack();
+                    let challenged_block = witness.raw_l2block();
+                    alert(Alert::ChallengeObserved {
+                        block_number: challenged_block.number().unpack(),
+                        block_hash: hex::encode(challenged_block.hash()),
+                        block_producer: hex::encode(challenged_block.block_producer().raw_data()),
+                    });
+
+                    let challenge_block_number = challenged_block.number().unpack();
                     let local_bad_block_number = {
                         let block_hash: Option<H256> = self.bad_block_hash();
                         let to_number = block_hash.map(|hash| db.get_block_number(&hash));
@@ -487,17 +534,9 @@ impl Chain {
                     assert_eq!(local_slice, submit_slice);
 
                     // Revert bad blocks
-                    let prev_reverted_block_root = db.get_reverted_block_smt_root()?;
-                    db.revert_bad_blocks(&local_reverted_blocks)?;
+                    revert::revert_bad_blocks(db, &local_reverted_blocks)?;
                     log::debug!("bad blocks reverted");
 
-                    let reverted_block_hashes = local_reverted_blocks.iter().map(|b| b.hash());
-                    db.set_reverted_block_hashes(
-                        &db.get_reverted_block_smt_root()?,
-                        prev_reverted_block_root,
-                        reverted_block_hashes.collect(),
-                    )?;
-
                     // Check reverted block root
                     let global_reverted_block_root: H256 =
                         global_state.reverted_block_root().unpack();
@@ -543,6 +582,8 @@ impl Chain {
                         assert_eq!(prev_account_smt.as_slice(), global_account_smt.as_slice());
                     }
 
+                    self.record_revert(RevertKind::Revert, local_reverted_blocks);
+
                     // If our bad block isn't reverted, just challenge it
                     match self.challenge_target {
                         Some(ref target) => {
@@ -625,11 +666,7 @@ impl Chain {
         }
 
         // Update finalized_custodians with the withdrawals of the current block
-        let withdrawals = db
-            .get_block(&block_hash)?
-            .context("get block")?
-            .withdrawals();
-        for w in withdrawals.as_reader().iter() {
+        for w in block.as_reader().withdrawals().iter() {
             finalized_custodians.capacity = finalized_custodians
                 .capacity
                 .checked_sub(w.raw().capacity().unpack().into())
@@ -694,13 +731,7 @@ impl Chain {
                         "rewind to last valid tip first"
                     );
 
-                    // detach block from DB
-                    db.detach_block(&l2block)?;
-                    // detach block state from state tree
-                    {
-                        let mut tree = BlockStateDB::from_store(&mut db, RWConfig::detach_block())?;
-                        tree.detach_block_state(l2block.raw().number().unpack())?;
-                    }
+                    revert::detach_block(db, &l2block)?;
 
                     // Check local tip block
                     let local_tip = db.get_tip_block()?;
@@ -720,29 +751,7 @@ impl Chain {
                     let local_state_reverted_block_root: H256 =
                         local_state_global_state.reverted_block_root().unpack();
                     if local_state_reverted_block_root != prev_state_reverted_block_root {
-                        // Rewind reverted block smt
-                        let genesis_hash = db.get_block_hash_by_number(0)?.expect("genesis hash");
-                        let genesis_reverted_block_root: H256 = {
-                            let genesis_global_state = db
-                                .get_block_post_global_state(&genesis_hash)?
-                                .expect("genesis global state should exists");
-                            genesis_global_state.reverted_block_root().unpack()
-                        };
-                        let mut current_reverted_block_root = local_state_reverted_block_root;
-                        while current_reverted_block_root != prev_state_reverted_block_root {
-                            if current_reverted_block_root == genesis_reverted_block_root {
-                                break;
-                            }
-
-                            let reverted_block_hashes = db
-                                .get_reverted_block_hashes_by_root(&current_reverted_block_root)?
-                                .expect("reverted block hashes should exists")
-                                .block_hashes;
-
-                            db.rewind_reverted_block_smt(reverted_block_hashes)?;
-                            current_reverted_block_root = db.get_reverted_block_smt_root()?;
-                        }
-                        assert_eq!(current_reverted_block_root, prev_state_reverted_block_root);
+                        revert::rewind_reverted_block_smt(db, prev_state_reverted_block_root)?;
                     }
 
                     // Check current state
@@ -757,6 +766,7 @@ impl Chain {
                     let script_hash = tree.get_script_hash(0)?;
                     assert!(!script_hash.is_zero());
 
+                    self.record_revert(RevertKind::Detach, vec![l2block]);
                     Ok(())
                 }
                 RevertL1ActionContext::RewindToLastValidTip => {
@@ -786,56 +796,12 @@ impl Chain {
                     // cancel challenge and revert won't modify it. We will check account state
                     // after sync complete.
 
-                    // Rewind reverted block smt to last valid tip in db
-                    let mut current_reverted_block_root = local_reverted_block_root;
-                    let genesis_hash = db.get_block_hash_by_number(0)?.expect("genesis hash");
-                    let genesis_reverted_block_root: H256 = {
-                        let genesis_global_state = db
-                            .get_block_post_global_state(&genesis_hash)?
-                            .expect("genesis global state should exists");
-                        genesis_global_state.reverted_block_root().unpack()
-                    };
-                    while current_reverted_block_root != last_valid_tip_reverted_block_root {
-                        if current_reverted_block_root == genesis_reverted_block_root {
-                            break;
-                        }
-
-                        let reverted_block_hashes = db
-                            .get_reverted_block_hashes_by_root(&current_reverted_block_root)?
-                            .expect("reverted block hashes should exists")
-                            .block_hashes;
-
-                        db.rewind_reverted_block_smt(reverted_block_hashes)?;
-                        current_reverted_block_root = db.get_reverted_block_smt_root()?;
-                    }
-                    assert_eq!(
-                        current_reverted_block_root,
-                        last_valid_tip_reverted_block_root
-                    );
-
-                    // Rewind block smt to last valid tip in db
-                    let mut current_block_hash: H256 =
-                        local_state_global_state.tip_block_hash().unpack();
-                    while current_block_hash != last_valid_tip_block_hash {
-                        if current_block_hash == genesis_hash {
-                            break;
-                        }
-
-                        let block = db
-                            .get_bad_block(&current_block_hash)
-                            .expect("rewind block should exists");
-                        // XXX: maybe the following two functions should be
-                        // merged into one function `detach_bad_block`.
-                        db.delete_bad_block(&current_block_hash)?;
-                        db.rewind_block_smt(&block)?;
-                        // XXX: delete the bad block?
-                        db.delete_bad_block_challenge_target(&current_block_hash)?;
-                        current_block_hash = block.raw().parent_block_hash().unpack();
-                    }
-                    assert_eq!(current_block_hash, last_valid_tip_block_hash);
-
-                    // Rewind tip block in db
-                    db.set_tip_block_hash(last_valid_tip_block_hash)?;
+                    revert::rewind_reverted_block_smt(db, last_valid_tip_reverted_block_root)?;
+                    let rewound_blocks = revert::rewind_bad_blocks(
+                        db,
+                        local_state_global_state.tip_block_hash().unpack(),
+                    )?;
+                    self.record_revert(RevertKind::Rewind, rewound_blocks);
 
                     Ok(())
                 }
@@ -853,14 +819,14 @@ impl Chain {
 
     /// Sync chain from layer1
     pub async fn sync(&mut self, param: SyncParam) -> Result<()> {
+        if let Some(ref divergence) = self.state_divergence {
+            bail!("safe mode, {}", divergence);
+        }
         let mut db = self.store.begin_transaction();
         let is_l1_revert_happend = !param.reverts.is_empty();
         // revert layer1 actions
-        if !param.reverts.is_empty() {
-            // revert
-            for reverted_action in param.reverts {
-                self.revert_l1action(&mut db, reverted_action)?;
-            }
+        for reverted_action in param.reverts {
+            self.revert_l1action(&mut db, reverted_action)?;
         }
         let has_bad_block_before_update = self.challenge_target.is_some();
 
@@ -870,7 +836,12 @@ impl Chain {
         log::debug!(target: "sync-block", "sync {} actions", updates.len());
         for (i, action) in updates.into_iter().enumerate() {
             let t = Instant::now();
-            self.update_l1action(&mut db, action)?;
+            if let Err(err) = self.update_l1action(&mut db, action) {
+                if let Some(divergence) = err.downcast_ref::<StateDivergence>() {
+                    self.enter_safe_mode(divergence.clone()).await;
+                }
+                return Err(err);
+            }
             log::debug!(target: "sync-block", "process {}th action cost {}ms", i, t.elapsed().as_millis());
             match self.last_sync_event() {
                 SyncEvent::Success => (),
@@ -881,26 +852,47 @@ impl Chain {
             }
         }
 
+        if !self.reverted_blocks.is_empty() {
+            let observed_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("timestamp")
+                .as_millis() as u64;
+            for reverted in self.reverted_blocks.iter() {
+                db.append_reorg_record(&reverted.reorg_record(observed_at))?;
+            }
+        }
         db.commit()?;
         db = self.store.begin_transaction();
 
+        self.last_reverted_blocks = std::mem::take(&mut self.reverted_blocks);
+        for reverted in self.last_reverted_blocks.iter() {
+            gw_metrics::chain()
+                .reorg_depth
+                .observe(reverted.blocks.len() as f64);
+        }
+
         // Should reset mem pool after bad block is reverted. Deposit cell may pass cancel timeout
         // and get reclaimed. Finalized custodians may be merged in bad block submit tx and this
         // will not be reverted.
         let is_bad_block_reverted = has_bad_block_before_update && self.challenge_target.is_none();
-        let tip_block_hash: H256 = self.local_state.tip.hash();
         if let Some(mem_pool) = &self.mem_pool {
             if matches!(self.last_sync_event, SyncEvent::Success)
                 && (is_l1_revert_happend || is_bad_block_reverted)
             {
-                // update mem pool state
+                // Txs of detached blocks are reinjected by mem pool reset, txs
+                // of reverted bad blocks, if any, are passed in.
+                let reverted_txs = { self.last_reverted_blocks.iter() }
+                    .filter(|reverted| reverted.is_bad_blocks())
+                    .flat_map(|reverted| reverted.transactions())
+                    .collect();
+                let tip_block_hash: H256 = self.local_state.tip.hash();
                 log::debug!(target: "sync-block", "acquire mem-pool",);
                 let t = Instant::now();
                 // TODO: local cells manager.
                 mem_pool
                     .lock()
                     .await
-                    .notify_new_tip(tip_block_hash, &Default::default())
+                    .notify_reverted_blocks(tip_block_hash, reverted_txs, &Default::default())
                     .await?;
                 log::debug!("[sync] unlock mem-pool {}ms", t.elapsed().as_millis());
             }
@@ -912,8 +904,18 @@ impl Chain {
             None => self.local_state.tip.raw().post_account(),
         };
 
+        let account_root = H256::from(*db.state_smt().unwrap().root()).pack();
+        if account_root != expected_account.merkle_root() {
+            alert(Alert::DbCorruption {
+                error: format!(
+                    "account root {} in DB, expected {}",
+                    account_root,
+                    expected_account.merkle_root()
+                ),
+            });
+        }
         assert_eq!(
-            H256::from(*db.state_smt().unwrap().root()).pack(),
+            account_root,
             expected_account.merkle_root(),
             "account root consistent in DB"
         );
@@ -921,6 +923,14 @@ impl Chain {
         let tree = BlockStateDB::from_store(&mut db, RWConfig::readonly())?;
         let current_account = tree.calculate_merkle_state()?;
 
+        if current_account.as_slice() != expected_account.as_slice() {
+            alert(Alert::DbCorruption {
+                error: format!(
+                    "account tree {} in DB, expected {}",
+                    current_account, expected_account
+                ),
+            });
+        }
         assert_eq!(
             current_account.as_slice(),
             expected_account.as_slice(),
@@ -931,6 +941,43 @@ impl Chain {
         Ok(())
     }
 
+    /// Record reverted blocks, in the order of reverts, i.e. descending.
+    fn record_revert(&mut self, kind: RevertKind, blocks: Vec<L2Block>) {
+        let reverted = match RevertedBlocks::new(kind, blocks) {
+            Some(reverted) => reverted,
+            None => return,
+        };
+        let fork_point_number: u64 = reverted.fork_point.number().unpack();
+        log::info!(
+            "[revert] {} {} blocks, fork point #{}",
+            kind.as_str(),
+            reverted.blocks.len(),
+            fork_point_number
+        );
+        gw_metrics::chain().reverted_blocks(kind.as_str(), reverted.blocks.len() as u64);
+        let unmerged = match self.reverted_blocks.last_mut() {
+            Some(last) => last.try_merge(reverted),
+            None => Some(reverted),
+        };
+        self.reverted_blocks.extend(unmerged);
+    }
+
+    /// Stop syncing, producing blocks and accepting txs, instead of going on
+    /// with a state that diverges from the chain.
+    async fn enter_safe_mode(&mut self, divergence: StateDivergence) {
+        alert(Alert::StateDivergence {
+            block_number: divergence.block_number,
+            error: divergence.to_string(),
+        });
+        if let Some(mem_pool) = &self.mem_pool {
+            let mem_pool_state = mem_pool.lock().await.mem_pool_state();
+            mem_pool_state.enter_safe_mode(divergence.clone());
+        } else {
+            log::error!("enter safe mode, {}", divergence);
+        }
+        self.state_divergence = Some(divergence);
+    }
+
     /// Only for testing.
     pub async fn notify_new_tip(&self) -> Result<()> {
         if let Some(mem_pool) = &self.mem_pool {
@@ -1038,14 +1085,16 @@ impl Chain {
         // process transactions
         // TODO: run offchain validator before send challenge, to make sure the block is bad
         let generator = &self.generator;
-        let (_withdrawal_receipts, prev_txs_state, tx_receipts) = match generator
+        let (prev_txs_state, tx_receipts, used_cycles, cycles_report, state_diff) = match generator
             .verify_and_apply_block(db, &chain_view, args, &self.skipped_invalid_block_list)
         {
             ApplyBlockResult::Success {
                 tx_receipts,
                 prev_txs_state,
-                withdrawal_receipts,
+                withdrawal_receipts: _,
                 offchain_used_cycles,
+                cycles_report,
+                state_diff,
             } => {
                 log::debug!(
                     "Process #{} txs: {} offchain used cycles {}",
@@ -1053,7 +1102,13 @@ impl Chain {
                     tx_receipts.len(),
                     offchain_used_cycles
                 );
-                (withdrawal_receipts, prev_txs_state, tx_receipts)
+                (
+                    prev_txs_state,
+                    tx_receipts,
+                    offchain_used_cycles,
+                    cycles_report,
+                    state_diff,
+                )
             }
             ApplyBlockResult::Challenge { target, error } => {
                 log::warn!("verify #{} state transition error {}", block_number, error);
@@ -1066,6 +1121,13 @@ impl Chain {
         let deposit_info_vec_len = deposit_info_vec.len() as u64;
         let withdrawals_len = withdrawals.len() as u64;
         let tx_receipts_len = tx_receipts.len() as u64;
+        let block_stats = build_block_stats(
+            &l2block,
+            &tx_receipts,
+            deposit_info_vec.len() as u32,
+            withdrawals.len() as u32,
+            used_cycles,
+        );
         db.insert_block(
             l2block.clone(),
             global_state.clone(),
@@ -1074,14 +1136,29 @@ impl Chain {
             deposit_info_vec,
             withdrawals,
         )?;
+        db.set_block_stats(block_number, &block_stats)?;
+        db.set_block_cycles_report(block_number, &cycles_report)?;
+        db.set_block_state_diff(block_number, &state_diff)?;
+        let asset_script_hashes: Vec<H256> =
+            deposit_asset_scripts.iter().map(|s| s.hash()).collect();
         db.insert_asset_scripts(deposit_asset_scripts)?;
         db.attach_block(l2block.clone())?;
+        register_sudt_accounts(db, &self.generator, asset_script_hashes)?;
 
         // Update metrics.
         gw_metrics::chain().block_height.set(block_number);
         gw_metrics::chain().deposits.inc_by(deposit_info_vec_len);
         gw_metrics::chain().withdrawals.inc_by(withdrawals_len);
         gw_metrics::chain().transactions.inc_by(tx_receipts_len);
+        for backend in cycles_report.backends.iter() {
+            gw_metrics::chain().backend_cycles(
+                format!("0x{}", hex::encode(backend.validator_script_type_hash)),
+                backend.execution_cycles,
+            );
+        }
+        for syscall in cycles_report.syscalls {
+            gw_metrics::chain().syscall_cycles(syscall.name, syscall.cycles);
+        }
 
         self.local_state.tip = l2block;
         self.local_state.last_global_state = global_state;
@@ -1089,6 +1166,75 @@ impl Chain {
     }
 }
 
+/// Register L2 sUDT accounts of L1 sUDT scripts in the tip state, skipping
+/// those without an account, e.g. the CKB or unknown assets.
+fn register_sudt_accounts(
+    db: &mut StoreTransaction,
+    generator: &Generator,
+    l1_script_hashes: Vec<H256>,
+) -> Result<()> {
+    let mut entries = Vec::with_capacity(l1_script_hashes.len());
+    {
+        let state = BlockStateDB::from_store(&mut *db, RWConfig::readonly())?;
+        for l1_script_hash in l1_script_hashes {
+            let l2_script = build_l2_sudt_script(generator.rollup_context(), &l1_script_hash);
+            if let Some(id) = state.get_account_id_by_script_hash(&l2_script.hash())? {
+                entries.push((l1_script_hash, id));
+            }
+        }
+    }
+    for (l1_script_hash, account_id) in entries {
+        db.insert_sudt_registry_entry(&l1_script_hash, account_id)?;
+    }
+    Ok(())
+}
+
+/// Fees and gas are of polyjuice txs, by the gas used in the polyjuice system
+/// log of receipts.
+fn build_block_stats(
+    block: &L2Block,
+    tx_receipts: &[TxReceipt],
+    deposit_count: u32,
+    withdrawal_count: u32,
+    used_cycles: u64,
+) -> BlockStats {
+    let mut stats = BlockStats {
+        used_cycles,
+        tx_count: tx_receipts.len() as u32,
+        withdrawal_count,
+        deposit_count,
+        ..Default::default()
+    };
+    for (tx, receipt) in block.transactions().into_iter().zip(tx_receipts) {
+        let parser = match PolyjuiceParser::from_raw_l2_tx(&tx.raw()) {
+            Some(parser) => parser,
+            None => continue,
+        };
+        let gas_used = receipt.logs().into_iter().find_map(|log| {
+            let service_flag: u8 = log.service_flag().into();
+            if service_flag != GW_LOG_POLYJUICE_SYSTEM {
+                return None;
+            }
+            match parse_log(&log) {
+                Ok(GwLog::PolyjuiceSystem { gas_used, .. }) => Some(gas_used),
+                _ => None,
+            }
+        });
+        let gas_used = match gas_used {
+            Some(gas_used) => gas_used,
+            None => continue,
+        };
+        let gas_price = parser.gas_price();
+        stats.gas_used = stats.gas_used.saturating_add(gas_used);
+        stats.total_fee = stats
+            .total_fee
+            .saturating_add(gas_price.saturating_mul(gas_used.into()));
+        stats.gas_prices.push((gas_price, gas_used));
+    }
+    stats.gas_prices.sort_unstable();
+    stats
+}
+
 fn parse_global_state(tx: &Transaction, rollup_id: &[u8; 32]) -> Result<GlobalState> {
     // find rollup state cell from outputs
     let (i, _) = tx
@@ -7,3 +7,4 @@
 //! * Submit new blocks to layer1(as an block_producer)
 
 pub mod chain;
+pub mod revert;
@@ -0,0 +1,216 @@
+//! Revert L2 blocks reverted on L1.
+//!
+//! * Valid blocks are detached when their submission txs are reverted, e.g.
+//!   by a L1 reorg or a L2 fork.
+//! * Bad blocks after the last valid tip are rewound when their submission
+//!   txs are reverted.
+//! * Bad blocks are reverted by a rollup revert tx after a challenge, and
+//!   their hashes are moved into the reverted block smt.
+//!
+//! In all cases the store is rolled back to the fork point and the reverted
+//! blocks are recorded, so that the chain can reinject their txs into the mem
+//! pool once the sync completes.
+
+use anyhow::{Context, Result};
+use gw_store::{
+    reorg_log::{ReorgKind, ReorgRecord},
+    state::{history::history_state::RWConfig, BlockStateDB},
+    traits::chain_store::ChainStore,
+    transaction::StoreTransaction,
+};
+use gw_types::{
+    h256::*,
+    packed::{L2Block, L2Transaction, NumberHash},
+    prelude::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevertKind {
+    /// Valid blocks detached from the chain.
+    Detach,
+    /// Bad blocks rewound to the last valid tip.
+    Rewind,
+    /// Bad blocks reverted by a rollup revert tx.
+    Revert,
+}
+
+impl RevertKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RevertKind::Detach => "detach",
+            RevertKind::Rewind => "rewind",
+            RevertKind::Revert => "revert",
+        }
+    }
+}
+
+impl From<RevertKind> for ReorgKind {
+    fn from(kind: RevertKind) -> Self {
+        match kind {
+            RevertKind::Detach => ReorgKind::Detach,
+            RevertKind::Rewind => ReorgKind::Rewind,
+            RevertKind::Revert => ReorgKind::Revert,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RevertedBlocks {
+    pub kind: RevertKind,
+    /// Tip block after the revert.
+    pub fork_point: NumberHash,
+    /// Reverted blocks in ascending order.
+    pub blocks: Vec<L2Block>,
+}
+
+impl RevertedBlocks {
+    pub fn new(kind: RevertKind, blocks: Vec<L2Block>) -> Option<Self> {
+        let first = blocks.first()?;
+        let number: u64 = first.raw().number().unpack();
+        let fork_point = NumberHash::new_builder()
+            .number(number.saturating_sub(1).pack())
+            .block_hash(first.raw().parent_block_hash())
+            .build();
+        Some(RevertedBlocks {
+            kind,
+            fork_point,
+            blocks,
+        })
+    }
+
+    /// Whether the reverted blocks are bad blocks.
+    ///
+    /// Unlike detached blocks, txs of bad blocks can't be found by walking
+    /// back from the previous tip, so they must be reinjected explicitly.
+    pub fn is_bad_blocks(&self) -> bool {
+        self.kind != RevertKind::Detach
+    }
+
+    /// Txs of reverted blocks in block order.
+    pub fn transactions(&self) -> impl Iterator<Item = L2Transaction> + '_ {
+        { self.blocks.iter() }.flat_map(|block| block.transactions().into_iter())
+    }
+
+    /// Record of the reorg log, `observed_at` is a unix timestamp in
+    /// milliseconds.
+    pub fn reorg_record(&self, observed_at: u64) -> ReorgRecord {
+        let timestamp =
+            |block: Option<&L2Block>| -> u64 { block.map_or(0, |b| b.raw().timestamp().unpack()) };
+        let withdrawal_count = { self.blocks.iter() }
+            .map(|b| b.withdrawals().len() as u32)
+            .sum();
+        ReorgRecord {
+            kind: self.kind.into(),
+            observed_at,
+            fork_point_number: self.fork_point.number().unpack(),
+            fork_point_hash: self.fork_point.block_hash().unpack(),
+            first_block_timestamp: timestamp(self.blocks.first()),
+            last_block_timestamp: timestamp(self.blocks.last()),
+            tx_count: self.transactions().count() as u32,
+            withdrawal_count,
+            block_hashes: self.blocks.iter().map(|b| b.hash()).collect(),
+        }
+    }
+
+    /// Merge blocks reverted right after `self`, i.e. the parents of
+    /// `self.blocks`.
+    pub(crate) fn try_merge(&mut self, reverted: RevertedBlocks) -> Option<RevertedBlocks> {
+        let fork_point_hash: H256 = self.fork_point.block_hash().unpack();
+        if reverted.kind != self.kind
+            || reverted.blocks.last().map(|b| b.hash()) != Some(fork_point_hash)
+        {
+            return Some(reverted);
+        }
+        let RevertedBlocks {
+            fork_point,
+            mut blocks,
+            ..
+        } = reverted;
+        blocks.append(&mut self.blocks);
+        self.blocks = blocks;
+        self.fork_point = fork_point;
+        None
+    }
+}
+
+/// Detach the valid tip block and its state.
+pub fn detach_block(mut db: &mut StoreTransaction, block: &L2Block) -> Result<()> {
+    db.detach_block(block)?;
+    let mut tree = BlockStateDB::from_store(&mut db, RWConfig::detach_block())?;
+    tree.detach_block_state(block.raw().number().unpack())?;
+    Ok(())
+}
+
+/// Revert bad blocks from the block smt to the reverted block smt, and record
+/// their hashes so that the reverted block smt can be rewound.
+pub fn revert_bad_blocks(db: &mut StoreTransaction, bad_blocks: &[L2Block]) -> Result<()> {
+    if bad_blocks.is_empty() {
+        return Ok(());
+    }
+    let prev_reverted_block_root = db.get_reverted_block_smt_root()?;
+    db.revert_bad_blocks(bad_blocks)?;
+    let reverted_block_hashes = bad_blocks.iter().map(|b| b.hash());
+    db.set_reverted_block_hashes(
+        &db.get_reverted_block_smt_root()?,
+        prev_reverted_block_root,
+        reverted_block_hashes.collect(),
+    )
+}
+
+/// Rewind bad blocks from `tip_block_hash` down to the last valid tip.
+///
+/// Returns rewound blocks in ascending order.
+pub fn rewind_bad_blocks(db: &mut StoreTransaction, tip_block_hash: H256) -> Result<Vec<L2Block>> {
+    let last_valid_tip_block_hash = db.get_last_valid_tip_block_hash()?;
+    let genesis_hash = db.get_block_hash_by_number(0)?.context("genesis hash")?;
+    let mut blocks = Vec::new();
+    let mut current_block_hash = tip_block_hash;
+    while current_block_hash != last_valid_tip_block_hash {
+        if current_block_hash == genesis_hash {
+            break;
+        }
+
+        let block = db
+            .get_bad_block(&current_block_hash)
+            .context("rewind block should exists")?;
+        db.delete_bad_block(&current_block_hash)?;
+        db.rewind_block_smt(&block)?;
+        db.delete_bad_block_challenge_target(&current_block_hash)?;
+        current_block_hash = block.raw().parent_block_hash().unpack();
+        blocks.push(block);
+    }
+    assert_eq!(current_block_hash, last_valid_tip_block_hash);
+
+    // Rewind tip block in db
+    db.set_tip_block_hash(last_valid_tip_block_hash)?;
+    blocks.reverse();
+    Ok(blocks)
+}
+
+/// Rewind the reverted block smt to `target_root`, e.g. the reverted block
+/// root of the last valid tip.
+pub fn rewind_reverted_block_smt(db: &mut StoreTransaction, target_root: H256) -> Result<()> {
+    let genesis_reverted_block_root: H256 = {
+        let genesis_hash = db.get_block_hash_by_number(0)?.context("genesis hash")?;
+        let genesis_global_state = db
+            .get_block_post_global_state(&genesis_hash)?
+            .context("genesis global state should exists")?;
+        genesis_global_state.reverted_block_root().unpack()
+    };
+    let mut current_reverted_block_root = db.get_reverted_block_smt_root()?;
+    while current_reverted_block_root != target_root {
+        if current_reverted_block_root == genesis_reverted_block_root {
+            break;
+        }
+
+        let reverted_block_hashes = db
+            .get_reverted_block_hashes_by_root(&current_reverted_block_root)?
+            .context("reverted block hashes should exists")?
+            .block_hashes;
+
+        db.rewind_reverted_block_smt(reverted_block_hashes)?;
+        current_reverted_block_root = db.get_reverted_block_smt_root()?;
+    }
+    assert_eq!(current_reverted_block_root, target_root);
+    Ok(())
+}
@@ -0,0 +1,773 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use ckb_fixed_hash::{H160, H256};
+use gw_jsonrpc_types::{
+    blockchain::{OutPoint, Script},
+    ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64},
+    debug::{DebugRunResult, DebugTxTrace},
+    godwoken::{
+        AccountProof, AccountRef, AccountSummary, AccountTransactions, BatchRunResult,
+        BlockCyclesReport, BlockProof, BlockStateDiff, CKBEndpointStatus, DepositLifecycle,
+        FeeConfig, FeeHistory, FilterChanges, ForkInfo, L2BlockCommittedInfo, L2BlockView,
+        L2BlockWithStatus, L2FullBlockView, L2TransactionWithStatus, LastL2BlockCommittedInfo,
+        LogFilterParams, LogView, NodeInfo, Order, RegistryAddress, ReorgRecord, RunResult,
+        SUDTBalance, SUDTToken, StateProof, SudtTransfers, TxReceipt, WithdrawableCapacity,
+        WithdrawalLifecycle, WithdrawalQueueItem, WithdrawalWithStatus,
+    },
+};
+use gw_types::{
+    packed::{L2Transaction, RawL2Transaction, WithdrawalRequestExtra},
+    prelude::*,
+    registry_address::RegistryAddress as GwRegistryAddress,
+    U256,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::rt;
+
+/// Params by position, trailing `None`s are omitted.
+macro_rules! params {
+    ($($param:expr),* $(,)?) => {{
+        let mut params: Vec<Value> = vec![$(serde_json::to_value($param)?),*];
+        while params.last().map_or(false, Value::is_null) {
+            params.pop();
+        }
+        params
+    }};
+}
+
+/// Timeout and retries of requests.
+#[derive(Clone, Debug)]
+pub struct RequestPolicy {
+    /// Timeout of each attempt.
+    pub timeout: Duration,
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+    /// Interval before the first retry, doubled after each retry.
+    pub retry_interval: Duration,
+    pub max_retry_interval: Duration,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_interval: Duration::from_millis(500),
+            max_retry_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Response {
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<ErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String,
+    #[serde(default)]
+    data: Option<Value>,
+}
+
+impl Response {
+    pub(crate) fn into_result<T: DeserializeOwned>(self, method: &str) -> Result<T> {
+        if let Some(ErrorObject {
+            code,
+            message,
+            data,
+        }) = self.error
+        {
+            return Err(Error::Rpc {
+                code,
+                message,
+                data,
+            });
+        }
+        serde_json::from_value(self.result)
+            .map_err(|err| Error::InvalidResponse(format!("{}: {}", method, err)))
+    }
+}
+
+/// Client of the JSON RPC of a Godwoken node.
+///
+/// Queries are retried on transport errors, timeouts and busy errors.
+/// Submissions are only retried on busy errors, because they may have been
+/// accepted by the node otherwise.
+#[derive(Clone)]
+pub struct GodwokenClient {
+    url: reqwest::Url,
+    http: reqwest::Client,
+    id: Arc<AtomicU64>,
+    policy: RequestPolicy,
+}
+
+impl GodwokenClient {
+    /// `url` is the HTTP endpoint, e.g. `http://127.0.0.1:8119`.
+    pub fn new(url: &str) -> Result<Self> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|err| Error::InvalidUrl(format!("{}: {}", url, err)))?;
+        Ok(GodwokenClient {
+            url,
+            http: reqwest::Client::new(),
+            id: Arc::new(AtomicU64::new(0)),
+            policy: RequestPolicy::default(),
+        })
+    }
+
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &RequestPolicy {
+        &self.policy
+    }
+
+    /// Call a method that is not covered by this client.
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        params: Vec<Value>,
+    ) -> Result<T> {
+        self.request_with_retry(method, params, true).await
+    }
+
+    async fn submit<T: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        params: Vec<Value>,
+    ) -> Result<T> {
+        self.request_with_retry(method, params, false).await
+    }
+
+    async fn request_with_retry<T: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        params: Vec<Value>,
+        idempotent: bool,
+    ) -> Result<T> {
+        let mut retries = 0;
+        let mut interval = self.policy.retry_interval;
+        loop {
+            let result = rt::timeout(self.policy.timeout, self.send(method, &params)).await;
+            match result {
+                Err(err)
+                    if retries < self.policy.max_retries
+                        && (err.is_busy() || idempotent && err.is_retryable()) =>
+                {
+                    log::debug!("{} error: {}, retry in {:?}", method, err, interval);
+                    rt::sleep(interval).await;
+                    retries += 1;
+                    interval = (interval * 2).min(self.policy.max_retry_interval);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, method: &str, params: &[Value]) -> Result<T> {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        let req = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let resp: Response = { self.http.post(self.url.clone()).json(&req).send().await? }
+            .error_for_status()?
+            .json()
+            .await?;
+        resp.into_result(method)
+    }
+}
+
+fn address_bytes(address: &GwRegistryAddress) -> JsonBytes {
+    JsonBytes::from_vec(address.to_bytes())
+}
+
+// Blocks
+impl GodwokenClient {
+    pub async fn ping(&self) -> Result<String> {
+        self.request("gw_ping", params![]).await
+    }
+
+    pub async fn get_tip_block_hash(&self) -> Result<H256> {
+        self.request("gw_get_tip_block_hash", params![]).await
+    }
+
+    pub async fn get_block_hash(&self, block_number: u64) -> Result<Option<H256>> {
+        self.request("gw_get_block_hash", params![Uint64::from(block_number)])
+            .await
+    }
+
+    pub async fn get_block(&self, block_hash: &H256) -> Result<Option<L2BlockWithStatus>> {
+        self.request("gw_get_block", params![block_hash]).await
+    }
+
+    pub async fn get_block_by_number(&self, block_number: u64) -> Result<Option<L2BlockView>> {
+        self.request(
+            "gw_get_block_by_number",
+            params![Uint64::from(block_number)],
+        )
+        .await
+    }
+
+    /// Block with full txs and withdrawals.
+    pub async fn get_full_block_by_number(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<L2FullBlockView>> {
+        self.request(
+            "gw_get_block_by_number",
+            params![Uint64::from(block_number), true],
+        )
+        .await
+    }
+
+    pub async fn get_block_committed_info(
+        &self,
+        block_hash: &H256,
+    ) -> Result<Option<L2BlockCommittedInfo>> {
+        self.request("gw_get_block_committed_info", params![block_hash])
+            .await
+    }
+
+    pub async fn get_last_submitted_info(&self) -> Result<LastL2BlockCommittedInfo> {
+        self.request("gw_get_last_submitted_info", params![]).await
+    }
+
+    pub async fn get_block_proof(&self, block_number: u64) -> Result<BlockProof> {
+        self.request("gw_get_block_proof", params![Uint64::from(block_number)])
+            .await
+    }
+
+    /// Reorgs with ids less than `before`, newest first.
+    pub async fn list_reorgs(
+        &self,
+        before: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<Vec<ReorgRecord>> {
+        let before = before.map(Uint64::from);
+        let limit = limit.map(Uint32::from);
+        // `before` is required even if it's `null`.
+        let mut params = vec![serde_json::to_value(before)?];
+        if let Some(limit) = limit {
+            params.push(serde_json::to_value(limit)?);
+        }
+        self.request("gw_list_reorgs", params).await
+    }
+}
+
+// Accounts and state. `block_number` of `None` queries the mem pool state.
+impl GodwokenClient {
+    pub async fn get_balance(
+        &self,
+        address: &GwRegistryAddress,
+        sudt_id: u32,
+        block_number: Option<u64>,
+    ) -> Result<U256> {
+        self.request(
+            "gw_get_balance",
+            params![
+                address_bytes(address),
+                Uint32::from(sudt_id),
+                block_number.map(Uint64::from)
+            ],
+        )
+        .await
+    }
+
+    /// Balances in the order of `sudt_ids`.
+    pub async fn get_balances(
+        &self,
+        address: &GwRegistryAddress,
+        sudt_ids: &[u32],
+    ) -> Result<Vec<U256>> {
+        let sudt_ids: Vec<Uint32> = sudt_ids.iter().map(|&id| id.into()).collect();
+        self.request("gw_get_balances", params![address_bytes(address), sudt_ids])
+            .await
+    }
+
+    /// Balances in the order of `addresses`.
+    pub async fn get_balances_bulk(
+        &self,
+        addresses: &[GwRegistryAddress],
+        sudt_id: u32,
+    ) -> Result<Vec<U256>> {
+        let addresses: Vec<JsonBytes> = addresses.iter().map(address_bytes).collect();
+        self.request(
+            "gw_get_balances_bulk",
+            params![addresses, Uint32::from(sudt_id)],
+        )
+        .await
+    }
+
+    pub async fn get_sudt_balances(&self, address: &GwRegistryAddress) -> Result<Vec<SUDTBalance>> {
+        self.request("gw_get_sudt_balances", params![address_bytes(address)])
+            .await
+    }
+
+    pub async fn list_sudt_tokens(&self) -> Result<Vec<SUDTToken>> {
+        self.request("gw_list_sudt_tokens", params![]).await
+    }
+
+    pub async fn get_sudt_proxy_addresses(&self, sudt_id: u32) -> Result<Vec<H160>> {
+        self.request(
+            "gw_get_sudt_proxy_addresses",
+            params![Uint32::from(sudt_id)],
+        )
+        .await
+    }
+
+    pub async fn get_sudt_id_by_proxy_address(&self, address: &H160) -> Result<Option<u32>> {
+        let id: Option<Uint32> = self
+            .request("gw_get_sudt_id_by_proxy_address", params![address])
+            .await?;
+        Ok(id.map(|id| id.value()))
+    }
+
+    pub async fn get_storage_at(
+        &self,
+        account_id: u32,
+        key: &H256,
+        block_number: Option<u64>,
+    ) -> Result<H256> {
+        self.request(
+            "gw_get_storage_at",
+            params![
+                Uint32::from(account_id),
+                key,
+                block_number.map(Uint64::from)
+            ],
+        )
+        .await
+    }
+
+    pub async fn get_nonce(&self, account_id: u32, block_number: Option<u64>) -> Result<u32> {
+        let nonce: Uint32 = self
+            .request(
+                "gw_get_nonce",
+                params![Uint32::from(account_id), block_number.map(Uint64::from)],
+            )
+            .await?;
+        Ok(nonce.value())
+    }
+
+    /// Nonce after txs in the mem pool and the submission queue.
+    pub async fn get_pending_nonce(&self, address: &GwRegistryAddress) -> Result<u32> {
+        let nonce: Uint32 = self
+            .request("gw_get_pending_nonce", params![address_bytes(address)])
+            .await?;
+        Ok(nonce.value())
+    }
+
+    pub async fn get_data(
+        &self,
+        data_hash: &H256,
+        block_number: Option<u64>,
+    ) -> Result<Option<JsonBytes>> {
+        self.request(
+            "gw_get_data",
+            params![data_hash, block_number.map(Uint64::from)],
+        )
+        .await
+    }
+
+    pub async fn get_script(&self, script_hash: &H256) -> Result<Option<Script>> {
+        self.request("gw_get_script", params![script_hash]).await
+    }
+
+    pub async fn get_script_hash(&self, account_id: u32) -> Result<H256> {
+        self.request("gw_get_script_hash", params![Uint32::from(account_id)])
+            .await
+    }
+
+    pub async fn get_account_id_by_script_hash(&self, script_hash: &H256) -> Result<Option<u32>> {
+        let id: Option<Uint32> = self
+            .request("gw_get_account_id_by_script_hash", params![script_hash])
+            .await?;
+        Ok(id.map(|id| id.value()))
+    }
+
+    pub async fn get_script_hash_by_registry_address(
+        &self,
+        address: &GwRegistryAddress,
+    ) -> Result<Option<H256>> {
+        self.request(
+            "gw_get_script_hash_by_registry_address",
+            params![address_bytes(address)],
+        )
+        .await
+    }
+
+    pub async fn get_registry_address_by_script_hash(
+        &self,
+        script_hash: &H256,
+        registry_id: u32,
+    ) -> Result<Option<RegistryAddress>> {
+        self.request(
+            "gw_get_registry_address_by_script_hash",
+            params![script_hash, Uint32::from(registry_id)],
+        )
+        .await
+    }
+
+    pub async fn get_registry_addresses_by_script_hash(
+        &self,
+        script_hash: &H256,
+    ) -> Result<Vec<RegistryAddress>> {
+        self.request(
+            "gw_get_registry_addresses_by_script_hash",
+            params![script_hash],
+        )
+        .await
+    }
+
+    /// Accounts with ids from `from_id`.
+    pub async fn list_accounts(
+        &self,
+        from_id: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<AccountSummary>> {
+        self.request(
+            "gw_list_accounts",
+            params![Uint32::from(from_id), limit.map(Uint32::from)],
+        )
+        .await
+    }
+
+    pub async fn get_account_proof(
+        &self,
+        script_hash: &H256,
+        block_number: Option<u64>,
+    ) -> Result<AccountProof> {
+        self.request(
+            "gw_get_account_proof",
+            params![script_hash, block_number.map(Uint64::from)],
+        )
+        .await
+    }
+
+    pub async fn get_storage_proof(
+        &self,
+        account_id: u32,
+        keys: &[H256],
+        block_number: Option<u64>,
+    ) -> Result<StateProof> {
+        self.request(
+            "gw_get_storage_proof",
+            params![
+                Uint32::from(account_id),
+                keys,
+                block_number.map(Uint64::from)
+            ],
+        )
+        .await
+    }
+
+    pub async fn compute_l2_sudt_script_hash(&self, l1_sudt_script_hash: &H256) -> Result<H256> {
+        self.request(
+            "gw_compute_l2_sudt_script_hash",
+            params![l1_sudt_script_hash],
+        )
+        .await
+    }
+}
+
+// Transactions
+impl GodwokenClient {
+    /// Returns the tx hash, or `None` if the tx is queued to be submitted,
+    /// e.g. when the node is busy.
+    pub async fn submit_l2transaction(&self, tx: &L2Transaction) -> Result<Option<H256>> {
+        let tx = JsonBytes::from_bytes(tx.as_bytes());
+        self.submit("gw_submit_l2transaction", params![tx]).await
+    }
+
+    pub async fn is_request_in_queue(&self, hash: &H256) -> Result<bool> {
+        self.request("gw_is_request_in_queue", params![hash]).await
+    }
+
+    pub async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<L2TransactionWithStatus>> {
+        self.request("gw_get_transaction", params![tx_hash]).await
+    }
+
+    pub async fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TxReceipt>> {
+        self.request("gw_get_transaction_receipt", params![tx_hash])
+            .await
+    }
+
+    pub async fn get_pending_tx_hashes(&self) -> Result<Vec<H256>> {
+        self.request("gw_get_pending_tx_hashes", params![]).await
+    }
+
+    pub async fn execute_l2transaction(&self, tx: &L2Transaction) -> Result<RunResult> {
+        let tx = JsonBytes::from_bytes(tx.as_bytes());
+        self.request("gw_execute_l2transaction", params![tx]).await
+    }
+
+    pub async fn execute_raw_l2transaction(
+        &self,
+        raw_tx: &RawL2Transaction,
+        block_number: Option<u64>,
+    ) -> Result<RunResult> {
+        let raw_tx = JsonBytes::from_bytes(raw_tx.as_bytes());
+        self.request(
+            "gw_execute_raw_l2transaction",
+            params![raw_tx, block_number.map(Uint64::from)],
+        )
+        .await
+    }
+
+    /// Execute txs in order, later txs see the state changes of earlier ones.
+    pub async fn batch_execute(&self, raw_txs: &[RawL2Transaction]) -> Result<Vec<BatchRunResult>> {
+        let raw_txs: Vec<JsonBytes> = { raw_txs.iter() }
+            .map(|tx| JsonBytes::from_bytes(tx.as_bytes()))
+            .collect();
+        self.request("gw_batch_execute", params![raw_txs]).await
+    }
+
+    /// Gas used and prices of recent blocks, `percentiles` are in `[0, 100]`.
+    pub async fn fee_history(
+        &self,
+        block_count: u64,
+        percentiles: Option<&[f64]>,
+    ) -> Result<FeeHistory> {
+        self.request(
+            "gw_fee_history",
+            params![Uint64::from(block_count), percentiles],
+        )
+        .await
+    }
+
+    /// Cycles of a block by backend and by syscall class.
+    pub async fn get_block_cycles_report(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<BlockCyclesReport>> {
+        self.request(
+            "gw_get_block_cycles_report",
+            params![Uint64::from(block_number)],
+        )
+        .await
+    }
+
+    /// State changes of a block, for mirroring the L2 state.
+    pub async fn get_block_state_diff(&self, block_number: u64) -> Result<Option<BlockStateDiff>> {
+        self.request(
+            "gw_get_block_state_diff",
+            params![Uint64::from(block_number)],
+        )
+        .await
+    }
+
+    /// Txs sent by or to an account, pass the `last_cursor` of a page as
+    /// `after` to get the next page.
+    pub async fn list_account_transactions(
+        &self,
+        account: AccountRef,
+        order: Order,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> Result<AccountTransactions> {
+        self.request(
+            "gw_list_account_transactions",
+            params![account, order, Uint32::from(limit), after],
+        )
+        .await
+    }
+
+    /// sUDT transfers sent by or to a serialized registry address, pass the
+    /// `last_cursor` of a page as `after` to get the next page.
+    pub async fn list_sudt_transfers(
+        &self,
+        registry_address: JsonBytes,
+        order: Order,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> Result<SudtTransfers> {
+        self.request(
+            "gw_list_sudt_transfers",
+            params![registry_address, order, Uint32::from(limit), after],
+        )
+        .await
+    }
+
+    pub async fn get_fee_config(&self) -> Result<FeeConfig> {
+        self.request("gw_get_fee_config", params![]).await
+    }
+}
+
+// Withdrawals and deposits
+impl GodwokenClient {
+    pub async fn submit_withdrawal_request(
+        &self,
+        withdrawal: &WithdrawalRequestExtra,
+    ) -> Result<H256> {
+        let withdrawal = JsonBytes::from_bytes(withdrawal.as_bytes());
+        self.submit("gw_submit_withdrawal_request", params![withdrawal])
+            .await
+    }
+
+    pub async fn get_withdrawal(
+        &self,
+        withdrawal_hash: &H256,
+    ) -> Result<Option<WithdrawalWithStatus>> {
+        self.request("gw_get_withdrawal", params![withdrawal_hash])
+            .await
+    }
+
+    pub async fn get_withdrawal_status(
+        &self,
+        withdrawal_hash: &H256,
+    ) -> Result<Option<WithdrawalLifecycle>> {
+        self.request("gw_get_withdrawal_status", params![withdrawal_hash])
+            .await
+    }
+
+    pub async fn get_withdrawal_queue(&self) -> Result<Vec<WithdrawalQueueItem>> {
+        self.request("gw_get_withdrawal_queue", params![]).await
+    }
+
+    pub async fn get_deferred_withdrawals(&self) -> Result<Vec<H256>> {
+        self.request("gw_get_deferred_withdrawals", params![]).await
+    }
+
+    /// Amounts of all sUDTs, or only of the sUDT if `sudt_script_hash` is given.
+    pub async fn get_withdrawable_capacity(
+        &self,
+        sudt_script_hash: Option<&H256>,
+    ) -> Result<WithdrawableCapacity> {
+        self.request("gw_get_withdrawable_capacity", params![sudt_script_hash])
+            .await
+    }
+
+    pub async fn get_deposit_status(
+        &self,
+        out_point: &OutPoint,
+    ) -> Result<Option<DepositLifecycle>> {
+        self.request("gw_get_deposit_status", params![out_point])
+            .await
+    }
+}
+
+// Logs and filters
+impl GodwokenClient {
+    pub async fn get_logs(&self, filter: &LogFilterParams) -> Result<Vec<LogView>> {
+        self.request("gw_get_logs", params![filter]).await
+    }
+
+    pub async fn new_filter(&self, filter: &LogFilterParams) -> Result<u64> {
+        let id: Uint64 = self.request("gw_new_filter", params![filter]).await?;
+        Ok(id.value())
+    }
+
+    pub async fn new_block_filter(&self) -> Result<u64> {
+        let id: Uint64 = self.request("gw_new_block_filter", params![]).await?;
+        Ok(id.value())
+    }
+
+    pub async fn new_pending_transaction_filter(&self) -> Result<u64> {
+        let id: Uint64 = self
+            .request("gw_new_pending_transaction_filter", params![])
+            .await?;
+        Ok(id.value())
+    }
+
+    pub async fn uninstall_filter(&self, filter_id: u64) -> Result<bool> {
+        self.request("gw_uninstall_filter", params![Uint64::from(filter_id)])
+            .await
+    }
+
+    pub async fn get_filter_changes(&self, filter_id: u64) -> Result<FilterChanges> {
+        self.request("gw_get_filter_changes", params![Uint64::from(filter_id)])
+            .await
+    }
+
+    pub async fn get_filter_logs(&self, filter_id: u64) -> Result<Vec<LogView>> {
+        self.request("gw_get_filter_logs", params![Uint64::from(filter_id)])
+            .await
+    }
+}
+
+// Node
+impl GodwokenClient {
+    pub async fn get_node_info(&self) -> Result<NodeInfo> {
+        self.request("gw_get_node_info", params![]).await
+    }
+
+    pub async fn get_fork_schedule(&self) -> Result<Vec<ForkInfo>> {
+        self.request("gw_get_fork_schedule", params![]).await
+    }
+
+    pub async fn get_mem_pool_state_root(&self) -> Result<H256> {
+        self.request("gw_get_mem_pool_state_root", params![]).await
+    }
+
+    pub async fn get_mem_pool_state_ready(&self) -> Result<bool> {
+        self.request("gw_get_mem_pool_state_ready", params![]).await
+    }
+
+    /// OpenRPC document of the methods enabled on the node.
+    pub async fn discover(&self) -> Result<Value> {
+        self.request("rpc.discover", params![]).await
+    }
+
+    /// Requires the `debug` RPC methods.
+    pub async fn debug_replay_transaction(
+        &self,
+        tx_hash: &H256,
+        max_cycles: Option<u64>,
+    ) -> Result<Option<DebugRunResult>> {
+        self.request(
+            "debug_replay_transaction",
+            params![tx_hash, max_cycles.map(Uint64::from)],
+        )
+        .await
+    }
+
+    /// Requires the `debug` RPC methods.
+    pub async fn trace_transaction(
+        &self,
+        tx_hash: &H256,
+        max_cycles: Option<u64>,
+    ) -> Result<Option<DebugTxTrace>> {
+        self.request(
+            "gw_trace_transaction",
+            params![tx_hash, max_cycles.map(Uint64::from)],
+        )
+        .await
+    }
+
+    /// Execute and trace a tx on the state of the tip block. Txs failed with
+    /// VM errors return an `Error::Rpc` with the machine state as `data`.
+    /// Requires the `debug` RPC methods.
+    pub async fn debug_l2transaction(
+        &self,
+        tx: &L2Transaction,
+        max_cycles: Option<u64>,
+    ) -> Result<DebugTxTrace> {
+        let tx = JsonBytes::from_bytes(tx.as_bytes());
+        self.request(
+            "gw_debug_l2transaction",
+            params![tx, max_cycles.map(Uint64::from)],
+        )
+        .await
+    }
+
+    /// Requires the `admin` RPC methods.
+    pub async fn get_ckb_endpoints(&self) -> Result<Vec<CKBEndpointStatus>> {
+        self.request("gw_get_ckb_endpoints", params![]).await
+    }
+}
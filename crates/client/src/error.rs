@@ -0,0 +1,61 @@
+use serde_json::Value;
+use thiserror::Error;
+
+/// Error code of `gw_submit_l2transaction` etc. when the mem pool is busy.
+pub const BUSY_ERR_CODE: i64 = -32006;
+/// Error code of rate limited requests.
+pub const RATE_LIMIT_ERR_CODE: i64 = -32603;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[cfg(feature = "native")]
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+    #[error("request timed out")]
+    Timeout,
+    #[error("connection closed")]
+    ConnectionClosed,
+    #[error("rpc error {code}: {message}")]
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("invalid params: {0}")]
+    InvalidParams(#[from] serde_json::Error),
+}
+
+impl Error {
+    /// JSON RPC error code, `None` if it's not an error returned by the node.
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            Error::Rpc { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// The node rejected the request without processing it, it's safe to
+    /// retry any request later.
+    pub fn is_busy(&self) -> bool {
+        matches!(self.code(), Some(BUSY_ERR_CODE) | Some(RATE_LIMIT_ERR_CODE))
+    }
+
+    /// Retrying may succeed. Requests that are not idempotent may have been
+    /// processed by the node if the error is not `is_busy`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Transport(_) | Error::Timeout | Error::ConnectionClosed => true,
+            #[cfg(feature = "native")]
+            Error::WebSocket(_) => true,
+            _ => self.is_busy(),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Typed async client of the Godwoken JSON RPC, see `docs/RPC.md`.
+//!
+//! Enable the `native` feature (default) on native targets, or the `wasm`
+//! feature for `wasm32-unknown-unknown`. WebSocket subscriptions are only
+//! available with `native`.
+
+mod client;
+pub mod error;
+mod rt;
+#[cfg(feature = "native")]
+mod subscription;
+
+pub use ckb_fixed_hash::{H160, H256};
+pub use client::{GodwokenClient, RequestPolicy};
+pub use error::{Error, Result};
+pub use gw_jsonrpc_types as types;
+#[cfg(feature = "native")]
+pub use subscription::{Subscription, SubscriptionClient};
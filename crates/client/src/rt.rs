@@ -0,0 +1,34 @@
+//! Timers of the runtime, tokio on native targets and the browser on wasm.
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures::future::{self, Either};
+
+use crate::error::{Error, Result};
+
+#[cfg(not(any(feature = "native", feature = "wasm")))]
+compile_error!("either the `native` or the `wasm` feature is required");
+
+#[cfg(feature = "native")]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(all(feature = "wasm", not(feature = "native")))]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+pub(crate) async fn timeout<T>(
+    duration: Duration,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    futures::pin_mut!(fut);
+    let timer = sleep(duration);
+    futures::pin_mut!(timer);
+    match future::select(fut, timer).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::Timeout),
+    }
+}
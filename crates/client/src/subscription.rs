@@ -0,0 +1,168 @@
+//! WebSocket subscriptions of new blocks, pending txs and logs, see
+//! `gw_subscribe`.
+//!
+//! Each subscription has its own connection. Notifications are missed if the
+//! connection is closed, subscribe again and catch up with queries then.
+
+use std::marker::PhantomData;
+
+use ckb_fixed_hash::H256;
+use futures::{SinkExt, StreamExt};
+use gw_jsonrpc_types::godwoken::{L2BlockHeaderView, LogFilterParams, LogView};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::client::{RequestPolicy, Response};
+use crate::error::{Error, Result};
+use crate::rt;
+
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Deserialize)]
+struct Notification {
+    params: NotificationParams,
+}
+
+#[derive(Deserialize)]
+struct NotificationParams {
+    subscription: String,
+    result: Value,
+}
+
+/// Client of the WebSocket subscriptions of a Godwoken node.
+#[derive(Clone)]
+pub struct SubscriptionClient {
+    url: String,
+    policy: RequestPolicy,
+}
+
+impl SubscriptionClient {
+    /// `url` is the WebSocket endpoint, e.g. `ws://127.0.0.1:8219`.
+    pub fn new(url: &str) -> Self {
+        SubscriptionClient {
+            url: url.to_string(),
+            policy: RequestPolicy::default(),
+        }
+    }
+
+    /// Timeout and retries of connecting and subscribing.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<L2BlockHeaderView>> {
+        self.subscribe(vec![json!("newHeads")]).await
+    }
+
+    /// Hashes of txs accepted by the mem pool, full nodes only.
+    pub async fn subscribe_new_pending_transactions(&self) -> Result<Subscription<H256>> {
+        self.subscribe(vec![json!("newPendingTransactions")]).await
+    }
+
+    /// EVM logs of new blocks. `from_block` and `to_block` of the filter are
+    /// ignored.
+    pub async fn subscribe_logs(&self, filter: &LogFilterParams) -> Result<Subscription<LogView>> {
+        self.subscribe(vec![json!("logs"), serde_json::to_value(filter)?])
+            .await
+    }
+
+    async fn subscribe<T>(&self, params: Vec<Value>) -> Result<Subscription<T>> {
+        let mut retries = 0;
+        let mut interval = self.policy.retry_interval;
+        loop {
+            let result = rt::timeout(self.policy.timeout, connect(&self.url, &params)).await;
+            match result {
+                Err(err) if retries < self.policy.max_retries && err.is_retryable() => {
+                    log::debug!("gw_subscribe error: {}, retry in {:?}", err, interval);
+                    rt::sleep(interval).await;
+                    retries += 1;
+                    interval = (interval * 2).min(self.policy.max_retry_interval);
+                }
+                result => {
+                    let (ws, id) = result?;
+                    return Ok(Subscription {
+                        ws,
+                        id,
+                        _result: PhantomData,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Connect and subscribe, returns the subscription id.
+async fn connect(url: &str, params: &[Value]) -> Result<(WebSocket, String)> {
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await?;
+    let req = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "gw_subscribe",
+        "params": params,
+    });
+    ws.send(Message::Text(req.to_string())).await?;
+    while let Some(msg) = ws.next().await {
+        if let Message::Text(text) = msg? {
+            let resp: Response = serde_json::from_str(&text)
+                .map_err(|err| Error::InvalidResponse(format!("gw_subscribe: {}", err)))?;
+            let id = resp.into_result("gw_subscribe")?;
+            return Ok((ws, id));
+        }
+    }
+    Err(Error::ConnectionClosed)
+}
+
+pub struct Subscription<T> {
+    ws: WebSocket,
+    id: String,
+    _result: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Subscription<T> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The next notification, `None` if the connection is closed.
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            let text = match self.ws.next().await? {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => return None,
+                // Pings are answered by tungstenite.
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err.into())),
+            };
+            let notification: Notification = match serde_json::from_str(&text) {
+                Ok(notification) => notification,
+                Err(err) => {
+                    return Some(Err(Error::InvalidResponse(format!(
+                        "gw_subscription: {}",
+                        err
+                    ))))
+                }
+            };
+            if notification.params.subscription != self.id {
+                continue;
+            }
+            let result = serde_json::from_value(notification.params.result)
+                .map_err(|err| Error::InvalidResponse(format!("gw_subscription: {}", err)));
+            return Some(result);
+        }
+    }
+
+    pub async fn unsubscribe(mut self) -> Result<()> {
+        let req = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "gw_unsubscribe",
+            "params": [self.id],
+        });
+        self.ws.send(Message::Text(req.to_string())).await?;
+        self.ws.close(None).await?;
+        Ok(())
+    }
+}
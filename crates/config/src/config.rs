@@ -1,14 +1,15 @@
 use ckb_fixed_hash::{H160, H256};
 pub use gw_jsonrpc_types::godwoken::GaslessTxSupportConfig;
 use gw_jsonrpc_types::{
-    blockchain::{CellDep, Script},
+    blockchain::{CellDep, OutPoint, Script},
     ckb_jsonrpc_types::JsonBytes,
     godwoken::{ChallengeTargetType, L2BlockCommittedInfo, RollupConfig},
 };
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::min,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    net::IpAddr,
     path::PathBuf,
 };
 
@@ -21,11 +22,119 @@ pub enum Trace {
     TokioConsole,
 }
 
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Ignored if `RUST_LOG` is set.
+    pub filter: LogFilterConfig,
+    /// Write logs to rotated files instead of stdout.
+    pub file: Option<LogFileConfig>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogFilterConfig {
+    /// Default level, e.g. `info`.
+    pub level: String,
+    /// Level overrides by target, e.g. `gw_mem_pool = "debug"`.
+    pub targets: BTreeMap<String, String>,
+}
+
+impl Default for LogFilterConfig {
+    fn default() -> Self {
+        LogFilterConfig {
+            level: "info".to_string(),
+            targets: BTreeMap::new(),
+        }
+    }
+}
+
+impl LogFilterConfig {
+    /// Filter directives in the `RUST_LOG` syntax.
+    pub fn directives(&self) -> String {
+        let mut directives = vec![self.level.clone()];
+        for (target, level) in &self.targets {
+            directives.push(format!("{}={}", target, level));
+        }
+        directives.join(",")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogFileConfig {
+    pub dir: PathBuf,
+    /// Log file names are `<prefix>.<date>`.
+    #[serde(default = "default_log_file_prefix")]
+    pub prefix: String,
+    #[serde(default)]
+    pub rotation: LogRotation,
+}
+
+fn default_log_file_prefix() -> String {
+    "godwoken.log".to_string()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        LogRotation::Daily
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpConfig {
+    /// OTLP gRPC endpoint, e.g. `http://localhost:4317`.
+    pub endpoint: String,
+    /// Ratio of traces to sample, in `[0, 1]`.
+    #[serde(default = "default_otlp_sampling_ratio")]
+    pub sampling_ratio: f64,
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+}
+
+// Manual impl because f64 is not Eq, `sampling_ratio` is checked to be in
+// [0, 1] before use.
+impl Eq for OtlpConfig {}
+
+fn default_otlp_sampling_ratio() -> f64 {
+    1.0
+}
+
+fn default_otlp_service_name() -> String {
+    "godwoken".to_string()
+}
+
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     pub node_mode: NodeMode,
     pub liveness_duration_secs: Option<u64>,
+    /// Max time to wait for background tasks on shutdown, default 30s.
+    pub shutdown_timeout_secs: Option<u64>,
     #[serde(default)]
     pub contract_log_config: ContractLogConfig,
     pub debug_backend_forks: Option<Vec<BackendForkConfig>>,
@@ -46,18 +155,35 @@ pub struct Config {
     pub store: StoreConfig,
     #[serde(default)]
     pub trace: Option<Trace>,
+    /// Export spans to an OpenTelemetry collector.
+    #[serde(default)]
+    pub otlp: Option<OtlpConfig>,
+    #[serde(default)]
+    pub logging: LoggingConfig,
     #[serde(default)]
     pub consensus: ConsensusConfig,
     pub reload_config_github_url: Option<GithubConfigUrl>,
+    /// Reload `dynamic_config` from this config file, e.g. the node config
+    /// file itself. Ignored if `reload_config_github_url` is set.
+    #[serde(default)]
+    pub reload_config_path: Option<PathBuf>,
     #[serde(default)]
     pub dynamic_config: DynamicConfig,
     #[serde(default)]
     pub p2p_network_config: Option<P2PNetworkConfig>,
     #[serde(default)]
     pub sync_server: SyncServerConfig,
+    #[serde(default)]
+    pub sync_client: SyncClientConfig,
     /// Gasless tx support is enabled when this config presents.
     #[serde(default)]
     pub gasless_tx_support: Option<GaslessTxSupportConfig>,
+    /// Notify a webhook of events of watched addresses.
+    #[serde(default)]
+    pub watch_list: Option<WatchListConfig>,
+    /// Notify a webhook of critical events, e.g. repeated submission failures.
+    #[serde(default)]
+    pub alerts: Option<AlertsConfig>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
@@ -66,6 +192,7 @@ pub enum RPCMethods {
     PProf,
     Test,
     Debug,
+    Admin,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -74,6 +201,112 @@ pub struct RPCServerConfig {
     pub listen: String,
     #[serde(default)]
     pub enable_methods: HashSet<RPCMethods>,
+    /// Serve `/metrics`, `/livez` and `/readyz` on a separate address, e.g. to keep
+    /// them off the public RPC port. They are always served on `listen` too.
+    #[serde(default)]
+    pub metrics_listen: Option<String>,
+    /// Serve WebSocket subscriptions, i.e. `gw_subscribe`.
+    #[serde(default)]
+    pub ws_listen: Option<String>,
+    /// Serve the gRPC interface, see `crates/rpc-server/proto/godwoken.proto`.
+    #[serde(default)]
+    pub grpc_listen: Option<String>,
+    /// Metadata of sUDTs, served by `gw_list_sudt_tokens`.
+    #[serde(default)]
+    pub sudt_tokens: Vec<SUDTTokenConfig>,
+    /// Concurrency limits of JSONRPC requests by method class.
+    #[serde(default)]
+    pub scheduler: RPCSchedulerConfig,
+    /// Cache of responses of immutable queries, e.g. finalized blocks.
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+    /// Serve REST paths of common queries on `listen`, e.g. `GET /block/{number}`.
+    #[serde(default)]
+    pub enable_rest: bool,
+    /// Operator-only `admin_*` methods, not served unless configured.
+    #[serde(default)]
+    pub admin: AdminRPCConfig,
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminRPCConfig {
+    /// Serve `admin_*` methods on a separate address, e.g. `127.0.0.1:8120`,
+    /// which should only be reachable by operators.
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// Require the `Authorization: Bearer <auth_token>` header for `admin_*`
+    /// methods. `admin_*` methods are also served on `rpc_server.listen` if it
+    /// is set.
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponseCacheConfig {
+    /// Maximum number of cached responses, 0 to disable the cache. Default is
+    /// 10_000.
+    pub max_entries: usize,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Separate concurrency limits of cheap reads, executions, e.g.
+/// `gw_execute_raw_l2transaction`, and writes, e.g. `gw_submit_l2transaction`,
+/// so that heavy requests can't starve cheap ones.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RPCSchedulerConfig {
+    /// Default is 256 concurrent requests and 4096 queued ones.
+    pub cheap: ConcurrencyLimitConfig,
+    /// Default is 16 concurrent requests and 256 queued ones.
+    pub execution: ConcurrencyLimitConfig,
+    /// Default is 64 concurrent requests and 1024 queued ones.
+    pub write: ConcurrencyLimitConfig,
+}
+
+impl Default for RPCSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            cheap: ConcurrencyLimitConfig {
+                max_concurrency: 256,
+                max_queue: 4096,
+            },
+            execution: ConcurrencyLimitConfig {
+                max_concurrency: 16,
+                max_queue: 256,
+            },
+            write: ConcurrencyLimitConfig {
+                max_concurrency: 64,
+                max_queue: 1024,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConcurrencyLimitConfig {
+    /// Requests served at the same time.
+    pub max_concurrency: usize,
+    /// Requests waiting for a slot, more are rejected as busy.
+    pub max_queue: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SUDTTokenConfig {
+    /// Type script hash of the L1 sUDT, zero for CKB.
+    pub l1_script_hash: H256,
+    pub symbol: String,
+    pub decimals: u8,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -84,6 +317,65 @@ pub struct RPCClientConfig {
     /// If this is None we use CKB builtin indexer RPC instead.
     pub indexer_url: Option<String>,
     pub ckb_url: String,
+    /// Backup CKB RPC URLs, used if `ckb_url` fails or falls behind.
+    #[serde(default)]
+    pub backup_ckb_urls: Vec<String>,
+    /// Backup standalone ckb indexer URLs, used if `indexer_url` fails or
+    /// falls behind.
+    #[serde(default)]
+    pub backup_indexer_urls: Vec<String>,
+    #[serde(default)]
+    pub health_check: RPCHealthCheckConfig,
+    #[serde(default)]
+    pub indexer_cache: IndexerCacheConfig,
+}
+
+/// Health checks of CKB RPC and indexer endpoints, only if there are backup
+/// endpoints.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RPCHealthCheckConfig {
+    /// Default is 10.
+    pub interval_secs: u64,
+    /// An endpoint is stale if its tip is this many blocks behind the highest
+    /// tip of all endpoints. Default is 10.
+    pub max_tip_lag: u64,
+}
+
+impl Default for RPCHealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            max_tip_lag: 10,
+        }
+    }
+}
+
+/// Cache of live cells of contract and custodian queries to the indexer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexerCacheConfig {
+    /// Default is false.
+    pub enabled: bool,
+    /// Cached cells are used without querying the indexer for this many
+    /// seconds. Default is 10.
+    pub ttl_secs: u64,
+    /// Cached cells are updated with cells of new blocks, and fully rescanned
+    /// after this many seconds. Default is 600.
+    pub full_rescan_secs: u64,
+    /// Maximum number of cached search keys. Default is 64.
+    pub max_entries: usize,
+}
+
+impl Default for IndexerCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 10,
+            full_rescan_secs: 600,
+            max_entries: 64,
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -127,8 +419,58 @@ pub struct GenesisConfig {
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WalletConfig {
+    /// Not used by remote and plugin signers.
+    #[serde(default)]
     pub privkey_path: PathBuf,
     pub lock: Script,
+    #[serde(default)]
+    pub signer: SignerConfig,
+}
+
+/// Where the wallet key lives.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Key file at `privkey_path`.
+    Local,
+    /// Remote signing service over HTTP.
+    Remote(RemoteSignerConfig),
+    /// External command, e.g. a KMS or HSM client.
+    Plugin(PluginSignerConfig),
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        SignerConfig::Local
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteSignerConfig {
+    pub url: String,
+    pub key_id: String,
+    /// File of the shared secret to sign requests with HMAC-SHA256, requests
+    /// are not signed if not set.
+    pub auth_key_path: Option<PathBuf>,
+    #[serde(default = "default_remote_signer_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_remote_signer_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_remote_signer_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_remote_signer_max_retries() -> u32 {
+    3
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginSignerConfig {
+    pub command: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
 }
 
 // NOTE: Rewards receiver lock must be different than lock in WalletConfig,
@@ -139,6 +481,29 @@ pub struct WalletConfig {
 pub struct ChallengerConfig {
     pub rewards_receiver_lock: Script,
     pub burn_lock: Script,
+    /// What to do with bad blocks found by re-executing submitted blocks.
+    /// Default is `submit`.
+    #[serde(default)]
+    pub mode: ChallengerMode,
+}
+
+/// Invalid challenges are always cancelled and challenged blocks reverted,
+/// the mode only applies to challenging bad blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengerMode {
+    /// Build and submit challenge txs.
+    Submit,
+    /// Build and dry run challenge txs without submitting them.
+    DryRun,
+    /// Only report bad blocks.
+    AlertOnly,
+}
+
+impl Default for ChallengerMode {
+    fn default() -> Self {
+        ChallengerMode::Submit
+    }
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -156,6 +521,19 @@ pub struct ContractTypeScriptConfig {
     pub allowed_contract_scripts: HashMap<H256, Script>,
 }
 
+/// Dep group cells of contracts, so that txs reference contracts by compact
+/// dep groups instead of their code cells. A dep group is only used if it
+/// contains the current code cell of the contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContractsDepGroupConfig {
+    /// Dep group cells by contract type script hash.
+    pub dep_groups: HashMap<H256, OutPoint>,
+    /// Look for dep groups of other contracts among live cells of this lock
+    /// script, e.g. the deployer lock.
+    pub discover_lock: Option<Script>,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct ContractsCellDep {
     pub rollup_config: CellDep,
@@ -193,6 +571,21 @@ pub struct BlockProducerConfig {
     pub challenger_config: ChallengerConfig,
     pub wallet_config: Option<WalletConfig>,
     pub withdrawal_unlocker_wallet_config: Option<WalletConfig>,
+    /// Wallet of challenge txs, default to `wallet_config`.
+    pub challenger_wallet_config: Option<WalletConfig>,
+    /// Wallets paying fees of block submission txs in round-robin, the stake
+    /// cell is still owned by `wallet_config`.
+    pub funding_wallet_configs: Vec<WalletConfig>,
+    /// Skip funding wallets with less balance, in shannons.
+    pub min_funding_wallet_balance: u64,
+    pub custodian_merge: CustodianMergeConfig,
+    pub withdrawal_unlocker: WithdrawalUnlockerConfig,
+    pub stake_manager: StakeManagerConfig,
+    pub fee_estimator: FeeEstimatorConfig,
+    pub contracts_dep_groups: ContractsDepGroupConfig,
+    /// Shadow producer mode, produce blocks and build L1 txs but never send
+    /// them.
+    pub dry_run: Option<DryRunConfig>,
 }
 
 impl Default for BlockProducerConfig {
@@ -206,6 +599,15 @@ impl Default for BlockProducerConfig {
             challenger_config: ChallengerConfig::default(),
             wallet_config: None,
             withdrawal_unlocker_wallet_config: None,
+            challenger_wallet_config: None,
+            funding_wallet_configs: Vec::new(),
+            min_funding_wallet_balance: 1_000_00000000,
+            custodian_merge: CustodianMergeConfig::default(),
+            withdrawal_unlocker: WithdrawalUnlockerConfig::default(),
+            stake_manager: StakeManagerConfig::default(),
+            fee_estimator: FeeEstimatorConfig::default(),
+            contracts_dep_groups: ContractsDepGroupConfig::default(),
+            dry_run: None,
         }
     }
 }
@@ -217,6 +619,138 @@ fn test_default_block_producer_config() {
     assert!(config.fee_rate > 0);
 }
 
+/// Dry run of the block producer, to validate config, fee settings and
+/// custodian selection in a new environment before going live.
+///
+/// L1 txs are written to `<output_dir>/txs` instead of being sent, and
+/// submitted blocks to `<output_dir>/blocks`. Submitted blocks are treated as
+/// confirmed without waiting for L1, so the store diverges from L1 and should
+/// be discarded afterwards.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DryRunConfig {
+    pub output_dir: PathBuf,
+}
+
+/// Consolidation of finalized custodian cells.
+///
+/// Custodian cells can only be unlocked by the rollup, so small finalized
+/// custodian cells are merged in block submission transactions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CustodianMergeConfig {
+    /// Only merge if at least this many mergeable cells are found. Default is 5.
+    pub min_merge_cells: usize,
+    /// Maximum number of custodian inputs in a submission transaction,
+    /// including those used by withdrawals. Default is 50.
+    pub max_custodian_inputs: usize,
+    /// Maximum bytes that merged custodian inputs may add to a submission
+    /// transaction. Default is 20_000.
+    pub max_merge_tx_size: usize,
+}
+
+impl Default for CustodianMergeConfig {
+    fn default() -> Self {
+        Self {
+            min_merge_cells: 5,
+            max_custodian_inputs: 50,
+            max_merge_tx_size: 20_000,
+        }
+    }
+}
+
+/// Estimation of L1 fee rates from the CKB node, used instead of `fee_rate`
+/// by block submission, withdrawal unlock and challenge txs if enabled.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeEstimatorConfig {
+    /// Default is false, i.e. always use `fee_rate`.
+    pub enabled: bool,
+    /// Number of recent blocks of the node's fee rate statistics. Default is
+    /// 21.
+    pub target_blocks: u64,
+    /// Number of recent blocks to sample if the node doesn't support fee rate
+    /// statistics. Default is 10.
+    pub sample_blocks: u64,
+    /// Lower bound of estimated fee rates. Default is 1000.
+    pub min_fee_rate: u64,
+    /// Upper bound of estimated fee rates. Default is 10000.
+    pub max_fee_rate: u64,
+    /// Estimates are reused for this many seconds. Default is 30.
+    pub update_interval_secs: u64,
+}
+
+impl Default for FeeEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_blocks: 21,
+            sample_blocks: 10,
+            min_fee_rate: 1000,
+            max_fee_rate: 10000,
+            update_interval_secs: 30,
+        }
+    }
+}
+
+/// Unlocking finalized withdrawal cells to their owner locks, either in the
+/// block producer or in the standalone `withdrawal-unlocker` subcommand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WithdrawalUnlockerConfig {
+    /// Only unlock withdrawals to these owner lock hashes. All withdrawals
+    /// are unlocked if empty.
+    pub owner_lock_hashes: Vec<H256>,
+    /// Maximum number of withdrawal cells in an unlock transaction. Default
+    /// is 100.
+    pub max_inputs_per_tx: usize,
+    /// Rebuild an unlock transaction with a higher fee rate if it is still
+    /// pending after this many seconds. Default is 300.
+    pub fee_bump_timeout_secs: u64,
+    /// Fee rate is multiplied by this percentage on each bump. Default is 150.
+    pub fee_bump_percent: u64,
+    /// Upper bound of the bumped fee rate. Default is 10000.
+    pub max_fee_rate: u64,
+    /// Polling interval of the standalone unlocker. Default is 10 seconds.
+    pub poll_interval_secs: u64,
+}
+
+impl Default for WithdrawalUnlockerConfig {
+    fn default() -> Self {
+        Self {
+            owner_lock_hashes: Vec::new(),
+            max_inputs_per_tx: 100,
+            fee_bump_timeout_secs: 300,
+            fee_bump_percent: 150,
+            max_fee_rate: 10000,
+            poll_interval_secs: 10,
+        }
+    }
+}
+
+/// Reclaiming finalized stake cells of the block producer wallet.
+///
+/// The latest stake cell is never reclaimed since it is reused by block
+/// submissions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StakeManagerConfig {
+    /// Automatically unlock finalized stake cells to the wallet. Default is
+    /// true.
+    pub auto_reclaim: bool,
+    /// Maximum number of stake cells in a reclaim transaction. Default is 50.
+    pub max_inputs_per_tx: usize,
+}
+
+impl Default for StakeManagerConfig {
+    fn default() -> Self {
+        Self {
+            auto_reclaim: true,
+            max_inputs_per_tx: 50,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct PscConfig {
@@ -226,7 +760,23 @@ pub struct PscConfig {
     /// Maximum number of submitted (but not confirmed) blocks. Default is 5.
     pub submitted_limit: u64,
     /// Minimum delay between blocks. Default is 8 seconds.
+    ///
+    /// A block is produced every interval, unless none of
+    /// `dynamic_config.block_trigger` holds.
     pub block_interval_secs: u64,
+    /// Rebuild a submission transaction with a higher fee rate if it has been
+    /// pending for this long. Default is 0, i.e. disabled.
+    pub fee_bump_timeout_secs: u64,
+    /// Fee rate of a rebuilt transaction in percent of the previous one.
+    /// Default is 150.
+    pub fee_bump_percent: u64,
+    /// Maximum fee rate of rebuilt transactions, in shannons/KB. Default is
+    /// 10000.
+    pub max_fee_rate: u64,
+    /// Skip empty blocks, i.e. blocks without txs, deposits or withdrawals,
+    /// until this long after the last block. Blocks are still needed to
+    /// advance the finalized timepoint. Default is 0, i.e. disabled.
+    pub empty_block_keep_alive_secs: u64,
 }
 
 impl Default for PscConfig {
@@ -235,6 +785,10 @@ impl Default for PscConfig {
             local_limit: 5,
             submitted_limit: 5,
             block_interval_secs: 8,
+            fee_bump_timeout_secs: 0,
+            fee_bump_percent: 150,
+            max_fee_rate: 10000,
+            empty_block_keep_alive_secs: 0,
         }
     }
 }
@@ -263,6 +817,10 @@ pub struct DebugConfig {
     pub debug_tx_dump_path: PathBuf,
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    /// Re-run txs failed with VM errors to log the machine state (pc,
+    /// registers, cycles and the last syscalls) with the error.
+    #[serde(default)]
+    pub debug_failed_transactions: bool,
 }
 
 // Field default value for backward config file compitability
@@ -280,6 +838,7 @@ impl Default for DebugConfig {
             output_l1_tx_cycles: true,
             expected_l1_tx_upper_bound_cycles: EXPECTED_TX_UPPER_BOUND_CYCLES,
             enable_debug_rpc: false,
+            debug_failed_transactions: false,
         }
     }
 }
@@ -322,6 +881,18 @@ pub struct P2PNetworkConfig {
 pub struct SyncServerConfig {
     pub buffer_capacity: u64,
     pub broadcast_channel_capacity: usize,
+    /// Recent messages kept for reconnected subscribers to catch up with.
+    /// Default is 4096.
+    #[serde(default = "default_catch_up_capacity")]
+    pub catch_up_capacity: usize,
+    /// Only allow subscribers with these peer ids, see
+    /// `P2PNetworkConfig::allowed_peer_ids`.
+    #[serde(default)]
+    pub allowed_peer_ids: Option<Vec<String>>,
+}
+
+const fn default_catch_up_capacity() -> usize {
+    4096
 }
 
 impl Default for SyncServerConfig {
@@ -329,6 +900,29 @@ impl Default for SyncServerConfig {
         Self {
             buffer_capacity: 16,
             broadcast_channel_capacity: 1024,
+            catch_up_capacity: default_catch_up_capacity(),
+            allowed_peer_ids: None,
+        }
+    }
+}
+
+/// P2P block sync client of read-only nodes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncClientConfig {
+    /// Accept `gw_submit_l2transaction` and relay transactions to the full
+    /// node over the P2P sync channel.
+    pub relay_transactions: bool,
+    /// Transactions waiting to be relayed. `gw_submit_l2transaction` returns
+    /// busy errors when full.
+    pub relay_channel_capacity: usize,
+}
+
+impl Default for SyncClientConfig {
+    fn default() -> Self {
+        Self {
+            relay_transactions: false,
+            relay_channel_capacity: 1024,
         }
     }
 }
@@ -341,6 +935,90 @@ pub struct MemPoolConfig {
     pub restore_path: PathBuf,
     #[serde(default)]
     pub mem_block: MemBlockConfig,
+    #[serde(default)]
+    pub account_creator: AccountCreatorConfig,
+    /// Compare the local clock with NTP servers and alert on skew.
+    #[serde(default)]
+    pub clock_skew_check: Option<ClockSkewCheckConfig>,
+    /// Group DB writes of pushed txs into periodic commits.
+    #[serde(default)]
+    pub write_batch: Option<MemPoolWriteBatchConfig>,
+    /// Drop pending withdrawals not packaged within this many seconds since
+    /// they're pushed, or restored on startup. Default is to keep them.
+    #[serde(default)]
+    pub pending_withdrawal_ttl_secs: Option<u64>,
+}
+
+/// Pushed txs and their receipts are committed to the DB in batches instead of
+/// one by one. They're visible to RPCs after being committed, and the last
+/// batch is lost on crashes, i.e. they have to be resubmitted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemPoolWriteBatchConfig {
+    /// Commit after this many txs. Default is 100.
+    pub max_txs: usize,
+    /// Commit txs pushed this long ago. Default is 50.
+    pub flush_interval_ms: u64,
+    /// Fsync the WAL on each commit. Default is false, i.e. it's written to
+    /// the OS and survives process crashes but not power loss.
+    pub sync: bool,
+}
+
+impl Default for MemPoolWriteBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_txs: 100,
+            flush_interval_ms: 50,
+            sync: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockSkewCheckConfig {
+    /// Default is `["pool.ntp.org:123"]`.
+    pub ntp_servers: Vec<String>,
+    /// Alert if the local clock is off by more than this. Default is 1000.
+    pub max_skew_ms: u64,
+    /// Default is 300.
+    pub interval_secs: u64,
+}
+
+impl Default for ClockSkewCheckConfig {
+    fn default() -> Self {
+        Self {
+            ntp_servers: vec!["pool.ntp.org:123".to_string()],
+            max_skew_ms: 1000,
+            interval_secs: 300,
+        }
+    }
+}
+
+/// Policy of creating accounts for new addresses which received CKB.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccountCreatorConfig {
+    /// Default is 50.
+    pub max_accounts_per_block: usize,
+    /// Wallet of the account sending account creation txs and paying their
+    /// fees, default to the block producer wallet.
+    pub payer_wallet_config: Option<WalletConfig>,
+    /// Fee of each account creation tx, in shannons. Default is 0.
+    pub fee: u64,
+    /// Never create accounts for addresses starting with these prefixes.
+    pub deny_address_prefixes: Vec<JsonBytes>,
+}
+
+impl Default for AccountCreatorConfig {
+    fn default() -> Self {
+        Self {
+            max_accounts_per_block: 50,
+            payer_wallet_config: None,
+            fee: 0,
+            deny_address_prefixes: Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -357,6 +1035,128 @@ pub struct MemBlockConfig {
     pub max_cycles_limit: u64,
     #[serde(default = "default_syscall_cycles")]
     pub syscall_cycles: SyscallCyclesConfig,
+    #[serde(default)]
+    pub deposit_filter: DepositFilterConfig,
+    /// Read `max_cycles_limit` from an on-chain cell, so that it can be changed
+    /// for all nodes without config rollouts.
+    /// `dynamic_config.mem_block_limits.max_cycles_limit` still overrides it.
+    #[serde(default)]
+    pub onchain_cycles_limit: Option<OnchainCyclesLimitConfig>,
+    /// Collect deposit cells in the background instead of querying the
+    /// indexer on every mem block reset.
+    #[serde(default)]
+    pub deposit_watcher: Option<DepositWatcherConfig>,
+}
+
+/// Deposit cells are scanned once, then only cells of new blocks are
+/// queried. Parsed and filtered deposits are cached for mem block resets.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DepositWatcherConfig {
+    /// Default is 3.
+    pub poll_interval_secs: u64,
+    /// Cached deposits are fully rescanned after this many seconds. Default
+    /// is 600.
+    pub full_rescan_secs: u64,
+}
+
+impl Default for DepositWatcherConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 3,
+            full_rescan_secs: 600,
+        }
+    }
+}
+
+/// A cell holding the max cycles per block in its data, as a little-endian
+/// u64.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OnchainCyclesLimitConfig {
+    /// Type script of the cell, the newest live cell is used.
+    pub type_script: Script,
+    /// Default is 60.
+    #[serde(default = "default_onchain_cycles_limit_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+const fn default_onchain_cycles_limit_refresh_interval_secs() -> u64 {
+    60
+}
+
+/// POST a JSON event to `webhook_url` when a watched address receives a
+/// deposit, has a withdrawal finalized, or appears in sUDT logs of a tx.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchListConfig {
+    pub webhook_url: String,
+    pub addresses: Vec<RegistryAddressConfig>,
+    /// Default is 10.
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+const fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
+/// POST alerts of critical events to `webhook_url`: repeated submission
+/// failures, challenges of blocks of this node, DB corruption, mem pool reset
+/// failures and high disk usage of the store.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertsConfig {
+    pub webhook_url: String,
+    #[serde(default)]
+    pub format: AlertFormat,
+    /// Default is 10.
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Alert when submitting a block fails this many times in a row. Default
+    /// is 3.
+    #[serde(default = "default_alert_submission_failures")]
+    pub submission_failures: u32,
+    /// Alert when the disk of the store is fuller than this. Default is 90.
+    #[serde(default = "default_alert_disk_usage_percent")]
+    pub disk_usage_percent: u64,
+    /// Default is 60.
+    #[serde(default = "default_alert_disk_check_interval_secs")]
+    pub disk_check_interval_secs: u64,
+    /// Don't repeat alerts of the same kind within this. Default is 600.
+    #[serde(default = "default_alert_min_interval_secs")]
+    pub min_interval_secs: u64,
+}
+
+const fn default_alert_submission_failures() -> u32 {
+    3
+}
+
+const fn default_alert_disk_usage_percent() -> u64 {
+    90
+}
+
+const fn default_alert_disk_check_interval_secs() -> u64 {
+    60
+}
+
+const fn default_alert_min_interval_secs() -> u64 {
+    600
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertFormat {
+    /// The alert as a JSON object.
+    Json,
+    /// `{"text": ...}`, for Slack and compatible incoming webhooks.
+    Slack,
+}
+
+impl Default for AlertFormat {
+    fn default() -> Self {
+        AlertFormat::Json
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -386,6 +1186,22 @@ impl Default for DepositTimeoutConfig {
     }
 }
 
+/// Deposit policy on top of the protocol checks. Deposits rejected here stay
+/// on L1 and can be refunded after their cancel timeout.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DepositFilterConfig {
+    /// Minimal capacity of a deposit cell, in shannons. The protocol minimum
+    /// is used if this is lower. Defaults to 0.
+    pub min_ckb_capacity: u64,
+    /// L1 sUDT script hashes allowed to be deposited. All sUDTs are allowed if
+    /// empty, which is the default.
+    pub allowed_sudt_script_hashes: Vec<H256>,
+    /// Max deposits to the same layer 2 account in one block. Unlimited if 0,
+    /// which is the default.
+    pub max_deposits_per_account: usize,
+}
+
 const fn default_max_block_cycles_limit() -> u64 {
     u64::MAX
 }
@@ -425,6 +1241,10 @@ impl Default for MemPoolConfig {
             execute_l2tx_max_cycles: 100_000_000,
             restore_path: default_restore_path(),
             mem_block: MemBlockConfig::default(),
+            account_creator: AccountCreatorConfig::default(),
+            clock_skew_check: None,
+            write_batch: None,
+            pending_withdrawal_ttl_secs: None,
         }
     }
 }
@@ -438,6 +1258,9 @@ impl Default for MemBlockConfig {
             deposit_timeout_config: Default::default(),
             max_cycles_limit: default_max_block_cycles_limit(),
             syscall_cycles: SyscallCyclesConfig::default(),
+            deposit_filter: Default::default(),
+            onchain_cycles_limit: None,
+            deposit_watcher: None,
         }
     }
 }
@@ -535,12 +1358,101 @@ pub struct GithubConfigUrl {
     pub token: String,
 }
 
-// Configs in DynamicConfig can be hot reloaded from remote. But GithubConfigUrl must be setup.
+// Configs in DynamicConfig can be hot reloaded from `reload_config_github_url`
+// or `reload_config_path`.
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct DynamicConfig {
     pub fee_config: FeeConfig,
     pub rpc_config: RPCConfig,
+    /// Replaces `logging.filter` when reloaded.
+    #[serde(default)]
+    pub log_filter: Option<LogFilterConfig>,
+    #[serde(default)]
+    pub mem_block_limits: Option<MemBlockLimitsConfig>,
+    #[serde(default)]
+    pub rpc_access: RPCAccessConfig,
+    #[serde(default)]
+    pub block_trigger: Option<BlockTriggerConfig>,
+}
+
+/// Method ACLs and token bucket rate limits of the RPC server.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RPCAccessConfig {
+    /// Only these methods are allowed if not empty.
+    pub allowed_methods: HashSet<String>,
+    pub denied_methods: HashSet<String>,
+    /// Rate limit of each client IP, across all methods.
+    pub ip_rate_limit: Option<TokenBucketConfig>,
+    /// Rate limits of each client IP by method.
+    pub method_rate_limits: HashMap<String, TokenBucketConfig>,
+    /// Read the client IP from this header, e.g. `X-Forwarded-For`, when the
+    /// server is behind a reverse proxy. The first IP in the header is used.
+    pub client_ip_header: Option<String>,
+    /// IPs not subject to ACLs and rate limits.
+    pub trusted_ips: HashSet<IpAddr>,
+    /// Limits of read-only executions, e.g. `gw_execute_raw_l2transaction`.
+    pub execution_limits: ExecutionLimitsConfig,
+    /// Read the API key from this header, e.g. `X-API-Key`, to look up
+    /// `api_key_execution_limits`.
+    pub api_key_header: Option<String>,
+    /// Overrides of `execution_limits` by API key.
+    pub api_key_execution_limits: HashMap<String, ExecutionLimitsConfig>,
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionLimitsConfig {
+    /// Defaults to `mem_pool.execute_l2tx_max_cycles`.
+    pub max_cycles: Option<u64>,
+    /// Wall-clock timeout of the VM, checked on syscalls. No timeout by
+    /// default.
+    pub timeout_ms: Option<u64>,
+}
+
+impl ExecutionLimitsConfig {
+    /// Limits of `self`, with unset fields taken from `default`.
+    pub fn or(&self, default: &ExecutionLimitsConfig) -> ExecutionLimitsConfig {
+        ExecutionLimitsConfig {
+            max_cycles: self.max_cycles.or(default.max_cycles),
+            timeout_ms: self.timeout_ms.or(default.timeout_ms),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenBucketConfig {
+    pub requests_per_second: u64,
+    /// Max requests in a burst, defaults to `requests_per_second`.
+    pub burst: Option<u64>,
+}
+
+/// Overrides of `mem_block` limits, applied on the next mem pool reset.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemBlockLimitsConfig {
+    pub max_txs: Option<usize>,
+    pub max_withdrawals: Option<usize>,
+    pub max_cycles_limit: Option<u64>,
+}
+
+/// Conditions to produce a block, checked every `psc_config.block_interval_secs`.
+/// A block is produced if any of them holds.
+///
+/// Without this config, a block is produced on every check.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockTriggerConfig {
+    /// Seconds since the last block. Default is `psc_config.block_interval_secs`.
+    pub max_interval_secs: Option<u64>,
+    /// Mem block txs or cycles in percent of the mem block limits.
+    pub fullness_percent: Option<u64>,
+    /// Withdrawals in the mem block.
+    pub withdrawals: Option<usize>,
+    /// Deposits in the mem block.
+    pub deposits: Option<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use ckb_fixed_hash::{H160, H256};
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,9 @@ pub enum BackendType {
     Sudt,
     Polyjuice,
     EthAddrReg,
+    /// Custom L2 contracts. Their tx args have no known fee field, so the txs
+    /// are ordered with zero fee in the mem pool.
+    Custom,
     Unknown,
 }
 
@@ -48,9 +51,59 @@ pub struct BackendConfig {
     pub generator_path: PathBuf,
     pub validator_script_type_hash: H256,
     pub backend_type: BackendType,
+    #[serde(default)]
+    pub cycles: BackendCyclesConfig,
+    /// Expected blake2b hash of the validator binary, checked when it's
+    /// loaded. It's the `validator_code_hash` of `gw_get_node_info`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_code_hash: Option<H256>,
+    /// Expected blake2b hash of the generator binary, checked when it's
+    /// loaded. It's the `generator_code_hash` of `gw_get_node_info`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator_code_hash: Option<H256>,
+}
+
+/// Cycles policy of txs to a backend. Like the backend binaries, it must be
+/// the same on all nodes of a chain.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendCyclesConfig {
+    /// Max cycles of a tx, only lowers the max l2 tx cycles of the fork.
+    pub max_cycles: Option<u64>,
+    /// Cycles limit of the mem pool fee rate (fee / cycles limit), instead of
+    /// the one of the backend type in `[mem_pool.fee_config]`. Not used by
+    /// Polyjuice, whose cycles limit is the gas limit of the tx.
+    pub fee_cycles_limit: Option<u64>,
+}
+
+/// Named forks, activated at the heights in `ForkConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForkSwitch {
+    /// Increase l2 tx cycles limit to 500M.
+    IncreaseMaxL2TxCyclesTo500m,
+    /// Bump GlobalState.version from v1 to v2.
+    UpgradeGlobalStateVersionToV2,
+}
+
+impl ForkSwitch {
+    pub const ALL: [ForkSwitch; 2] = [
+        ForkSwitch::IncreaseMaxL2TxCyclesTo500m,
+        ForkSwitch::UpgradeGlobalStateVersionToV2,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ForkSwitch::IncreaseMaxL2TxCyclesTo500m => "increase_max_l2_tx_cycles_to_500m",
+            ForkSwitch::UpgradeGlobalStateVersionToV2 => "upgrade_global_state_version_to_v2",
+        }
+    }
 }
 
 /// Fork changes and activation heights.
+///
+/// New forks are added as `ForkSwitch` variants and activated by name in
+/// `[fork.fork_heights]`, instead of dedicated fields.
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct ForkConfig {
@@ -63,17 +116,43 @@ pub struct ForkConfig {
     ///   - Remove `state_checkpoints` from RawL2Block
     pub upgrade_global_state_version_to_v2: Option<u64>,
 
+    /// Activation heights of named forks. The dedicated fields above take
+    /// precedence over this table.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fork_heights: BTreeMap<ForkSwitch, u64>,
+
     /// Backend fork configs
     pub backend_forks: Vec<BackendForkConfig>,
 }
 
 impl ForkConfig {
+    /// Returns the activation height of `fork`, `None` if it's disabled.
+    pub fn fork_height(&self, fork: ForkSwitch) -> Option<u64> {
+        let height = match fork {
+            ForkSwitch::IncreaseMaxL2TxCyclesTo500m => self.increase_max_l2_tx_cycles_to_500m,
+            ForkSwitch::UpgradeGlobalStateVersionToV2 => self.upgrade_global_state_version_to_v2,
+        };
+        height.or_else(|| self.fork_heights.get(&fork).copied())
+    }
+
+    /// Returns if `fork` is active at `block_number`.
+    pub fn is_active(&self, fork: ForkSwitch, block_number: u64) -> bool {
+        matches!(self.fork_height(fork), Some(height) if block_number >= height)
+    }
+
+    /// Returns all forks and their activation heights.
+    pub fn schedule(&self) -> Vec<(ForkSwitch, Option<u64>)> {
+        { ForkSwitch::ALL.iter() }
+            .map(|&fork| (fork, self.fork_height(fork)))
+            .collect()
+    }
+
     /// Returns the version of global state for `block_number`.
     pub fn global_state_version(&self, block_number: u64) -> u8 {
-        match self.upgrade_global_state_version_to_v2 {
-            None => 1,
-            Some(fork_number) if block_number < fork_number => 1,
-            Some(_) => 2,
+        if self.is_active(ForkSwitch::UpgradeGlobalStateVersionToV2, block_number) {
+            2
+        } else {
+            1
         }
     }
 
@@ -89,10 +168,10 @@ impl ForkConfig {
 
     /// Return l2 tx cycles limit by block height
     pub fn max_l2_tx_cycles(&self, block_number: u64) -> u64 {
-        match self.increase_max_l2_tx_cycles_to_500m {
-            None => L2TX_MAX_CYCLES_150M,
-            Some(fork_number) if block_number < fork_number => L2TX_MAX_CYCLES_150M,
-            Some(_) => L2TX_MAX_CYCLES_500M,
+        if self.is_active(ForkSwitch::IncreaseMaxL2TxCyclesTo500m, block_number) {
+            L2TX_MAX_CYCLES_500M
+        } else {
+            L2TX_MAX_CYCLES_150M
         }
     }
 
@@ -117,7 +196,7 @@ impl ForkConfig {
 mod tests {
     use crate::{
         constants::{L2TX_MAX_CYCLES_150M, L2TX_MAX_CYCLES_500M},
-        ForkConfig,
+        ForkConfig, ForkSwitch,
     };
 
     #[test]
@@ -140,4 +219,39 @@ mod tests {
         assert_eq!(fork.max_l2_tx_cycles(100), L2TX_MAX_CYCLES_500M);
         assert_eq!(fork.max_l2_tx_cycles(u64::MAX), L2TX_MAX_CYCLES_500M);
     }
+
+    #[test]
+    fn test_fork_heights() {
+        let fork: ForkConfig = toml::from_str(
+            r#"
+            upgrade_global_state_version_to_v2 = 10
+            backend_forks = []
+            [fork_heights]
+            increase_max_l2_tx_cycles_to_500m = 42
+            upgrade_global_state_version_to_v2 = 20
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            fork.fork_height(ForkSwitch::IncreaseMaxL2TxCyclesTo500m),
+            Some(42)
+        );
+        // Dedicated fields take precedence.
+        assert_eq!(
+            fork.fork_height(ForkSwitch::UpgradeGlobalStateVersionToV2),
+            Some(10)
+        );
+        assert!(!fork.is_active(ForkSwitch::IncreaseMaxL2TxCyclesTo500m, 41));
+        assert!(fork.is_active(ForkSwitch::IncreaseMaxL2TxCyclesTo500m, 42));
+        assert_eq!(fork.global_state_version(10), 2);
+
+        let unknown = toml::from_str::<ForkConfig>(
+            r#"
+            backend_forks = []
+            [fork_heights]
+            fork_v3 = 1
+            "#,
+        );
+        assert!(unknown.is_err());
+    }
 }
@@ -1,9 +1,15 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 
 use arc_swap::ArcSwap;
-use gw_config::{Config, DynamicConfig, FeeConfig};
+use gw_config::{
+    BlockTriggerConfig, Config, DynamicConfig, FeeConfig, LogFilterConfig, MemBlockLimitsConfig,
+    RPCAccessConfig, RPCRateLimit,
+};
 use gw_tx_filter::{
     erc20_creator_allowlist::SUDTProxyAccountAllowlist,
     polyjuice_contract_creator_allowlist::PolyjuiceContractCreatorAllowList,
@@ -13,14 +19,23 @@ use serde::{Deserialize, Serialize};
 
 use crate::{fee_config::FeeConfigManager, whitelist_config::WhilteListConfigManager};
 
+/// Max number of reconfigurations kept in the changelog.
+const MAX_CHANGELOG_LEN: usize = 100;
+
 // Some configs can be hot reloaded through DynamicConfigManager.
 // So that we don't need to restart to take effect every time.
 #[derive(Default, Clone)]
 pub struct DynamicConfigManager {
     config_github_url: Option<(String, String)>, // url and token
+    config_path: Option<PathBuf>,
 
     fee_manager: FeeConfigManager,
     whitelist_manager: WhilteListConfigManager,
+    log_filter: Option<LogFilterConfig>,
+    mem_block_limits: Option<MemBlockLimitsConfig>,
+    rpc_access: RPCAccessConfig,
+    block_trigger: Option<BlockTriggerConfig>,
+    changelog: VecDeque<ConfigChange>,
 }
 
 impl DynamicConfigManager {
@@ -39,31 +54,74 @@ impl DynamicConfigManager {
 
         Self {
             config_github_url,
+            config_path: config.reload_config_path,
             fee_manager,
             whitelist_manager,
+            log_filter: config.dynamic_config.log_filter,
+            mem_block_limits: config.dynamic_config.mem_block_limits,
+            rpc_access: config.dynamic_config.rpc_access,
+            block_trigger: config.dynamic_config.block_trigger,
+            changelog: VecDeque::new(),
         }
     }
 
+    fn has_reload_source(&self) -> bool {
+        self.config_github_url.is_some() || self.config_path.is_some()
+    }
+
     pub async fn reload(&mut self) -> Result<DynamicConfigReloadResponse> {
         // Fetch latest config.
-        let new_config = if let Some((url, token)) = &self.config_github_url {
-            get_github_config(url, token).await?
+        let (new_config, source) = if let Some((url, token)) = &self.config_github_url {
+            (get_github_config(url, token).await?, url.clone())
+        } else if let Some(path) = &self.config_path {
+            (get_file_config(path)?, path.to_string_lossy().into_owned())
         } else {
-            return Err(anyhow!("Github config url is absent!"));
+            return Err(anyhow!(
+                "Neither reload_config_github_url nor reload_config_path is set!"
+            ));
         };
 
         let new_config = new_config.dynamic_config;
         let backup_config = new_config.clone();
+        if let Some(ref log_filter) = new_config.log_filter {
+            gw_telemetry::trace::reload_log_filter(&log_filter.directives())?;
+        }
         let old_fee_config = self.fee_manager.reload(new_config.fee_config);
         let old_rpc_config = self.whitelist_manager.reload(new_config.rpc_config);
+        let old_log_filter = std::mem::replace(&mut self.log_filter, new_config.log_filter);
+        let old_mem_block_limits =
+            std::mem::replace(&mut self.mem_block_limits, new_config.mem_block_limits);
+        let old_rpc_access = std::mem::replace(&mut self.rpc_access, new_config.rpc_access);
+        let old_block_trigger =
+            std::mem::replace(&mut self.block_trigger, new_config.block_trigger);
         let old_config = DynamicConfig {
             fee_config: old_fee_config,
             rpc_config: old_rpc_config,
+            log_filter: old_log_filter,
+            mem_block_limits: old_mem_block_limits,
+            rpc_access: old_rpc_access,
+            block_trigger: old_block_trigger,
         };
         let res = DynamicConfigReloadResponse {
             old: old_config,
             new: backup_config,
         };
+
+        if res.old != res.new {
+            log::info!("[dynamic config] reloaded from {}", source);
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            self.changelog.push_back(ConfigChange {
+                timestamp,
+                source,
+                change: res.clone(),
+            });
+            if self.changelog.len() > MAX_CHANGELOG_LEN {
+                self.changelog.pop_front();
+            }
+        }
         Ok(res)
     }
 
@@ -81,6 +139,34 @@ impl DynamicConfigManager {
     pub fn get_sudt_proxy_account_whitelist(&self) -> &SUDTProxyAccountAllowlist {
         self.whitelist_manager.get_sudt_proxy_account_whitelist()
     }
+
+    pub fn get_send_tx_rate_limit(&self) -> Option<&RPCRateLimit> {
+        self.whitelist_manager.get_send_tx_rate_limit()
+    }
+
+    pub fn get_mem_block_limits(&self) -> Option<&MemBlockLimitsConfig> {
+        self.mem_block_limits.as_ref()
+    }
+
+    pub fn get_rpc_access(&self) -> &RPCAccessConfig {
+        &self.rpc_access
+    }
+
+    pub fn get_block_trigger(&self) -> Option<&BlockTriggerConfig> {
+        self.block_trigger.as_ref()
+    }
+
+    /// Applied reconfigurations, oldest first.
+    pub fn get_changelog(&self) -> &VecDeque<ConfigChange> {
+        &self.changelog
+    }
+}
+
+fn get_file_config(path: &Path) -> Result<Config> {
+    let content = std::fs::read(path)
+        .with_context(|| format!("read config file from {}", path.to_string_lossy()))?;
+    let config = toml::from_slice(&content).context("parse config file")?;
+    Ok(config)
 }
 
 async fn get_github_config(url: &str, token: &str) -> Result<Config> {
@@ -103,6 +189,15 @@ pub struct DynamicConfigReloadResponse {
     new: DynamicConfig,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    /// Github url or file path the config is reloaded from.
+    pub source: String,
+    pub change: DynamicConfigReloadResponse,
+}
+
 pub async fn reload(
     manager: Arc<ArcSwap<DynamicConfigManager>>,
 ) -> Result<DynamicConfigReloadResponse> {
@@ -116,12 +211,10 @@ pub async fn try_reload(
     manager: Arc<ArcSwap<DynamicConfigManager>>,
 ) -> Option<Result<DynamicConfigReloadResponse>> {
     let mut config = (**manager.load()).to_owned();
-    match config.config_github_url {
-        Some(_) => {
-            let resp = config.reload().await;
-            manager.store(Arc::new(config));
-            Some(resp)
-        }
-        None => None,
+    if !config.has_reload_source() {
+        return None;
     }
+    let resp = config.reload().await;
+    manager.store(Arc::new(config));
+    Some(resp)
 }
@@ -1,4 +1,4 @@
-use gw_config::RPCConfig;
+use gw_config::{RPCConfig, RPCRateLimit};
 use gw_tx_filter::{
     erc20_creator_allowlist::SUDTProxyAccountAllowlist,
     polyjuice_contract_creator_allowlist::PolyjuiceContractCreatorAllowList,
@@ -32,6 +32,10 @@ impl WhilteListConfigManager {
         &self.sudt_proxy_account_whitelist
     }
 
+    pub(crate) fn get_send_tx_rate_limit(&self) -> Option<&RPCRateLimit> {
+        self.rpc_config.send_tx_rate_limit.as_ref()
+    }
+
     // Return old config
     pub fn reload(&mut self, rpc_config: RPCConfig) -> RPCConfig {
         let (polyjuice_contract_creator_allowlist, sudt_proxy_account_whitelist) =
@@ -1,11 +1,12 @@
 use anyhow::{bail, Context, Result};
 use gw_common::blake2b::new_blake2b;
-use gw_config::{BackendConfig, BackendForkConfig, BackendType};
+use gw_config::{BackendConfig, BackendCyclesConfig, BackendForkConfig, BackendType};
 use gw_types::bytes::Bytes;
 use gw_types::h256::*;
 use std::{
     collections::{HashMap, HashSet},
     fs,
+    path::Path,
 };
 
 #[cfg(has_asm)]
@@ -33,6 +34,7 @@ pub struct Backend {
     pub validator_script_type_hash: H256,
     pub backend_type: BackendType,
     pub checksum: BackendCheckSum,
+    pub cycles: BackendCyclesConfig,
 }
 
 impl Backend {
@@ -70,8 +72,22 @@ impl Backend {
             validator_script_type_hash,
             backend_type,
             checksum,
+            cycles: BackendCyclesConfig::default(),
         }
     }
+
+    pub fn with_cycles(mut self, cycles: BackendCyclesConfig) -> Self {
+        self.cycles = cycles;
+        self
+    }
+
+    /// Max cycles of a tx to the backend, `max_cycles` is the limit of the fork
+    /// or the caller.
+    pub fn max_cycles(&self, max_cycles: u64) -> u64 {
+        self.cycles
+            .max_cycles
+            .map_or(max_cycles, |limit| limit.min(max_cycles))
+    }
 }
 
 /// SUDT Proxy config
@@ -143,6 +159,9 @@ impl BackendManage {
                 generator_path,
                 validator_script_type_hash,
                 backend_type,
+                cycles,
+                validator_code_hash,
+                generator_code_hash,
             } = config;
             let validator = fs::read(&validator_path)
                 .with_context(|| {
@@ -159,7 +178,20 @@ impl BackendManage {
                 validator_script_type_hash.into(),
                 validator,
                 generator,
-            );
+            )
+            .with_cycles(cycles);
+            check_code_hash(
+                "validator",
+                &validator_path,
+                validator_code_hash.map(Into::into),
+                backend.checksum.validator,
+            )?;
+            check_code_hash(
+                "generator",
+                &generator_path,
+                generator_code_hash.map(Into::into),
+                backend.checksum.generator,
+            )?;
             #[cfg(has_asm)]
             if compile {
                 self.compile_backend(&backend);
@@ -257,9 +289,24 @@ impl BackendManage {
     }
 }
 
+fn check_code_hash(name: &str, path: &Path, expected: Option<H256>, actual: H256) -> Result<()> {
+    if let Some(expected) = expected {
+        if expected != actual {
+            bail!(
+                "{} {} code hash mismatch, expected: {}, actual: {}",
+                name,
+                path.to_string_lossy(),
+                hex::encode(expected.as_slice()),
+                hex::encode(actual.as_slice()),
+            );
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use gw_config::{BackendConfig, BackendForkConfig, BackendType};
+    use gw_config::{BackendConfig, BackendCyclesConfig, BackendForkConfig, BackendType};
 
     use super::BackendManage;
 
@@ -289,12 +336,18 @@ mod tests {
                     backend_type: BackendType::Sudt,
                     generator_path: format!("{}/sudt_v0", dir.to_string_lossy()).into(),
                     validator_path: format!("{}/sudt_v0", dir.to_string_lossy()).into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
                 BackendConfig {
                     validator_script_type_hash: [43u8; 32].into(),
                     backend_type: BackendType::EthAddrReg,
                     generator_path: format!("{}/addr_v0", dir.to_string_lossy()).into(),
                     validator_path: format!("{}/addr_v0", dir.to_string_lossy()).into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
             ],
         };
@@ -327,12 +380,18 @@ mod tests {
                     backend_type: BackendType::Meta,
                     generator_path: format!("{}/meta_v0", dir.to_string_lossy()).into(),
                     validator_path: format!("{}/meta_v0", dir.to_string_lossy()).into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
                 BackendConfig {
                     validator_script_type_hash: [42u8; 32].into(),
                     backend_type: BackendType::Sudt,
                     generator_path: format!("{}/sudt_v1", dir.to_string_lossy()).into(),
                     validator_path: format!("{}/sudt_v1", dir.to_string_lossy()).into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
             ],
         };
@@ -374,4 +433,89 @@ mod tests {
             b"addr_v0".to_vec(),
         );
     }
+
+    #[test]
+    fn test_backend_cycles() {
+        let dir = tempfile::tempdir().unwrap().into_path();
+        let custom = dir.join("custom");
+        std::fs::write(&custom, "custom").unwrap();
+
+        let mut m = BackendManage::default();
+        let config = BackendForkConfig {
+            fork_height: 0,
+            sudt_proxy: Default::default(),
+            backends: vec![BackendConfig {
+                validator_script_type_hash: [44u8; 32].into(),
+                backend_type: BackendType::Custom,
+                generator_path: custom.clone(),
+                validator_path: custom,
+                cycles: BackendCyclesConfig {
+                    max_cycles: Some(1000),
+                    fee_cycles_limit: Some(500),
+                },
+                validator_code_hash: None,
+                generator_code_hash: None,
+            }],
+        };
+        m.register_backend_fork(config, false).unwrap();
+
+        let backend = m.get_backend(0, &[44u8; 32]).unwrap();
+        assert_eq!(backend.backend_type, BackendType::Custom);
+        assert_eq!(backend.cycles.fee_cycles_limit, Some(500));
+        assert_eq!(backend.max_cycles(u64::MAX), 1000);
+        assert_eq!(backend.max_cycles(100), 100);
+    }
+
+    #[test]
+    fn test_backend_code_hash() {
+        let dir = tempfile::tempdir().unwrap().into_path();
+        let poly_v0 = dir.join("poly_v0");
+        let poly_v1 = dir.join("poly_v1");
+        std::fs::write(&poly_v0, "poly_v0").unwrap();
+        std::fs::write(&poly_v1, "poly_v1").unwrap();
+        let code_hash = |data: &[u8]| {
+            let mut hasher = gw_common::blake2b::new_blake2b();
+            hasher.update(data);
+            let mut hash = [0u8; 32];
+            hasher.finalize(&mut hash);
+            hash
+        };
+        let backend_fork =
+            |fork_height, path: &std::path::Path, code_hash: [u8; 32]| BackendForkConfig {
+                fork_height,
+                sudt_proxy: Default::default(),
+                backends: vec![BackendConfig {
+                    validator_script_type_hash: [45u8; 32].into(),
+                    backend_type: BackendType::Polyjuice,
+                    generator_path: path.to_path_buf(),
+                    validator_path: path.to_path_buf(),
+                    cycles: Default::default(),
+                    validator_code_hash: Some(code_hash.into()),
+                    generator_code_hash: Some(code_hash.into()),
+                }],
+            };
+
+        let mut m = BackendManage::default();
+        m.register_backend_fork(backend_fork(0, &poly_v0, code_hash(b"poly_v0")), false)
+            .unwrap();
+        // mismatched code hash
+        let err = m
+            .register_backend_fork(backend_fork(10, &poly_v1, code_hash(b"poly_v0")), false)
+            .unwrap_err();
+        assert!(err.to_string().contains("code hash mismatch"), "{}", err);
+        m.register_backend_fork(backend_fork(10, &poly_v1, code_hash(b"poly_v1")), false)
+            .unwrap();
+
+        // switch at the fork boundary
+        let backend = m.get_backend(9, &[45u8; 32]).unwrap();
+        assert_eq!(backend.generator.to_vec(), b"poly_v0".to_vec());
+        assert_eq!(backend.checksum.generator, code_hash(b"poly_v0"));
+        let backend = m.get_backend(10, &[45u8; 32]).unwrap();
+        assert_eq!(backend.generator.to_vec(), b"poly_v1".to_vec());
+        assert_eq!(backend.checksum.generator, code_hash(b"poly_v1"));
+        assert_eq!(
+            m.get_backend(11, &[45u8; 32]).unwrap().generator.to_vec(),
+            b"poly_v1".to_vec()
+        );
+    }
 }
@@ -6,6 +6,8 @@ use gw_smt::sparse_merkle_tree::error::Error as SMTError;
 use gw_types::{h256::H256, offchain::CycleMeter, packed::Byte32};
 use thiserror::Error;
 
+use crate::syscalls::trace::MachineState;
+
 /// Error
 #[derive(Error, PartialEq, Eq, Debug)]
 pub enum Error {
@@ -123,6 +125,12 @@ pub enum TransactionError {
     InvalidExitCode(i8),
     #[error("VM error {0}")]
     VM(VMError),
+    /// VM error of a traced run, see `Generator::with_debug_failed_transactions`.
+    #[error("VM error {error}, machine state {state}")]
+    VMWithState {
+        error: VMError,
+        state: Box<MachineState>,
+    },
     #[error("SMT error {0}")]
     SMT(SMTError),
     #[error("invalid nonce of account {account_id} expected {expected}, actual {actual}")]
@@ -172,6 +180,8 @@ pub enum TransactionError {
     PolyjuiceCreatorIdNotFound,
     #[error("Unknown tx type original exit_code {0}.")]
     UnknownTxType(i8),
+    #[error("Execution timeout: timeout {timeout_ms}ms")]
+    ExecutionTimeout { timeout_ms: u64 },
 }
 
 impl From<VMError> for TransactionError {
@@ -1,11 +1,18 @@
 use anyhow::{Context, Result};
-use std::{collections::HashSet, sync::Arc, time::Instant};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    account_lock_manage::AccountLockManage,
+    account_lock_manage::{AccountLockManage, LockAlgorithm},
     backend_manage::{BackendManage, BlockConsensus},
     error::{BlockError, TransactionValidateError, WithdrawalError},
-    syscalls::RunContext,
+    syscalls::{
+        trace::{MachineState, SyscallTrace},
+        RunContext,
+    },
     typed_transaction::types::TypedRawTransaction,
     types::vm::VMVersion,
     utils::{get_polyjuice_creator_id, get_tx_type},
@@ -27,6 +34,9 @@ use gw_common::{
 
 use gw_config::{ContractLogConfig, ForkConfig, SyscallCyclesConfig};
 use gw_store::{
+    block_cycles::BlockCyclesReport,
+    block_state_diff::BlockStateDiff,
+    mem_pool_state::StateDivergence,
     state::{history::history_state::RWConfig, traits::JournalDB, BlockStateDB},
     transaction::StoreTransaction,
 };
@@ -39,14 +49,15 @@ use gw_types::{
     offchain::{CycleMeter, RunResult},
     packed::{
         AccountMerkleState, BlockInfo, ChallengeTarget, DepositInfoVec, L2Block, L2Transaction,
-        LogItem, RawL2Block, RawL2Transaction, TxReceipt, WithdrawalReceipt,
+        LogItem, RawL2Block, RawL2Transaction, Script, TxReceipt, WithdrawalReceipt,
         WithdrawalRequestExtra,
     },
     prelude::*,
 };
 use gw_utils::RollupContext;
+use rayon::prelude::*;
 
-use ckb_vm::{DefaultMachineBuilder, SupportMachine};
+use ckb_vm::{CoreMachine, DefaultMachineBuilder, Error as VMError, SupportMachine};
 
 #[cfg(not(has_asm))]
 use ckb_vm::TraceMachine;
@@ -65,6 +76,8 @@ pub enum ApplyBlockResult {
         prev_txs_state: AccountMerkleState,
         tx_receipts: Vec<TxReceipt>,
         offchain_used_cycles: u64,
+        cycles_report: BlockCyclesReport,
+        state_diff: BlockStateDiff,
     },
     Challenge {
         target: ChallengeTarget,
@@ -143,6 +156,30 @@ pub struct MachineRunArgs<'a, C, S> {
     max_cycles: u64,
     backend: &'a Backend,
     cycles_pool: Option<&'a mut CyclesPool>,
+    trace: Option<SyscallTrace>,
+    timeout: Option<Duration>,
+}
+
+/// Signature of a tx to verify, with the accounts read from state.
+struct SignatureCheck {
+    lock_algo: Arc<dyn LockAlgorithm + Send + Sync>,
+    sender_address: RegistryAddress,
+    script: Script,
+    receiver_script: Script,
+    tx: L2Transaction,
+}
+
+impl SignatureCheck {
+    fn verify(self, rollup_context: &RollupContext) -> Result<(), TransactionValidateError> {
+        self.lock_algo.verify_tx(
+            rollup_context,
+            self.sender_address,
+            self.script,
+            self.receiver_script,
+            self.tx,
+        )?;
+        Ok(())
+    }
 }
 
 pub struct Generator {
@@ -151,6 +188,8 @@ pub struct Generator {
     rollup_context: RollupContext,
     contract_log_config: ContractLogConfig,
     polyjuice_creator_id: ArcSwapOption<u32>,
+    debug_failed_transactions: bool,
+    syscall_cycles: SyscallCyclesConfig,
 }
 
 impl Generator {
@@ -166,9 +205,25 @@ impl Generator {
             rollup_context,
             contract_log_config,
             polyjuice_creator_id: ArcSwapOption::from(None),
+            debug_failed_transactions: false,
+            syscall_cycles: SyscallCyclesConfig::default(),
         }
     }
 
+    /// Re-run txs failed with VM errors with syscall tracing, to capture the
+    /// machine state into `TransactionError::VMWithState`.
+    pub fn with_debug_failed_transactions(mut self, enable: bool) -> Self {
+        self.debug_failed_transactions = enable;
+        self
+    }
+
+    /// Syscall cycles of `ApplyBlockResult::Success::cycles_report`, should
+    /// be the same as the mem block config.
+    pub fn with_syscall_cycles(mut self, syscall_cycles: SyscallCyclesConfig) -> Self {
+        self.syscall_cycles = syscall_cycles;
+        self
+    }
+
     pub fn clone_with_new_backends(&self, backend_manage: BackendManage) -> Self {
         Self {
             backend_manage,
@@ -176,6 +231,8 @@ impl Generator {
             rollup_context: self.rollup_context.clone(),
             contract_log_config: self.contract_log_config.clone(),
             polyjuice_creator_id: ArcSwapOption::from(self.polyjuice_creator_id.load_full()),
+            debug_failed_transactions: self.debug_failed_transactions,
+            syscall_cycles: self.syscall_cycles.clone(),
         }
     }
 
@@ -207,10 +264,20 @@ impl Generator {
             max_cycles,
             backend,
             mut cycles_pool,
+            trace,
+            timeout,
         } = args;
 
-        let mut context = RunContext::default();
+        let mut context = RunContext {
+            trace,
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+            ..Default::default()
+        };
         context.debug_log_buf.reserve(1024);
+        let traced = context
+            .trace
+            .as_ref()
+            .map_or(false, |trace| !trace.is_summary_only());
         let used_cycles;
         let exit_code;
         let org_cycles_pool = cycles_pool.as_mut().map(|p| p.clone());
@@ -250,6 +317,12 @@ impl Generator {
             machine.load_program(&backend.generator, &[])?;
             let maybe_ok = machine.run();
             let execution_cycles = machine.machine.cycles();
+            let failed_registers = match maybe_ok {
+                Err(_) if traced => {
+                    Some((*machine.machine.pc(), machine.machine.registers().to_vec()))
+                }
+                _ => None,
+            };
             drop(machine);
 
             // Subtract tx execution cycles.
@@ -288,7 +361,28 @@ impl Generator {
                     if let Some((pool, org_pool)) = cycles_pool.as_mut().zip(org_cycles_pool) {
                         **pool = org_pool;
                     }
+                    if let (Some(timeout), Some(deadline)) = (timeout, context.deadline) {
+                        if Instant::now() >= deadline {
+                            return Err(TransactionError::ExecutionTimeout {
+                                timeout_ms: timeout.as_millis() as u64,
+                            });
+                        }
+                    }
                     // unexpected VM error
+                    if let Some((pc, registers)) = failed_registers {
+                        let trace = context.trace.take().unwrap_or_default();
+                        let state = MachineState::new(
+                            pc,
+                            registers,
+                            execution_cycles,
+                            &trace,
+                            &context.debug_log_buf,
+                        );
+                        return Err(TransactionError::VMWithState {
+                            error: err,
+                            state: Box::new(state),
+                        });
+                    }
                     return Err(err.into());
                 }
             }
@@ -346,11 +440,35 @@ impl Generator {
         state: &S,
         tx: &L2Transaction,
     ) -> Result<(), TransactionValidateError> {
+        self.transaction_signature_check(state, tx)?
+            .verify(&self.rollup_context)
+    }
+
+    /// Check signatures of `txs` in parallel, only state reads are sequential.
+    #[instrument(skip_all, fields(txs = txs.len()))]
+    pub fn check_transaction_signatures<S: State + CodeStore>(
+        &self,
+        state: &S,
+        txs: &[L2Transaction],
+    ) -> Vec<Result<(), TransactionValidateError>> {
+        let checks: Vec<_> = { txs.iter() }
+            .map(|tx| self.transaction_signature_check(state, tx))
+            .collect();
+        { checks.into_par_iter() }
+            .map(|check| check?.verify(&self.rollup_context))
+            .collect()
+    }
+
+    /// Read what's needed to verify the signature of `tx` from `state`.
+    fn transaction_signature_check<S: State + CodeStore>(
+        &self,
+        state: &S,
+        tx: &L2Transaction,
+    ) -> Result<SignatureCheck, TransactionValidateError> {
         let raw_tx = tx.raw();
         let sender_id: u32 = raw_tx.from_id().unpack();
         let receiver_id: u32 = raw_tx.to_id().unpack();
 
-        // verify signature
         let script_hash = state.get_script_hash(sender_id)?;
         if script_hash.is_zero() {
             return Err(AccountError::ScriptNotFound {
@@ -375,20 +493,20 @@ impl Generator {
         let lock_algo = self
             .account_lock_manage()
             .get_lock_algorithm(&lock_code_hash)
-            .ok_or(LockAlgorithmError::UnknownAccountLock)?;
+            .ok_or(LockAlgorithmError::UnknownAccountLock)?
+            .clone();
 
         let sender_address = state
             .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)?
             .ok_or(AccountError::RegistryAddressNotFound)?;
 
-        lock_algo.verify_tx(
-            &self.rollup_context,
+        Ok(SignatureCheck {
+            lock_algo,
             sender_address,
             script,
             receiver_script,
-            tx.to_owned(),
-        )?;
-        Ok(())
+            tx: tx.to_owned(),
+        })
     }
 
     /// Apply l2 state transition
@@ -409,12 +527,16 @@ impl Generator {
             "withdrawal count"
         );
 
-        let mut state = match BlockStateDB::from_store(db, RWConfig::attach_block(block_number)) {
-            Ok(state) => state,
-            Err(err) => {
-                return ApplyBlockResult::Error(err);
-            }
-        };
+        let mut state =
+            match BlockStateDB::from_store(&mut *db, RWConfig::attach_block(block_number)) {
+                Ok(state) => state,
+                Err(err) => {
+                    return ApplyBlockResult::Error(err);
+                }
+            };
+
+        // track touched keys of the block for the state diff
+        state.set_state_tracker(Default::default());
 
         let block_hash = raw_block.hash();
         let skip_checkpoint_check = skipped_invalid_block_list.contains(&block_hash);
@@ -527,6 +649,7 @@ impl Generator {
 
         // handle transactions
         let mut offchain_used_cycles: u64 = 0;
+        let mut cycles_report = BlockCyclesReport::default();
         let mut tx_receipts = Vec::with_capacity(args.l2block.transactions().len());
         if skip_checkpoint_check {
             log::warn!(
@@ -586,16 +709,36 @@ impl Generator {
             // NOTICE users only allowed to send HandleMessage CallType txs
             let now = Instant::now();
 
+            // meter cycles by backend
+            let script_hash = match state.get_script_hash(raw_tx.to_id().unpack()) {
+                Ok(script_hash) => script_hash,
+                Err(err) => return ApplyBlockResult::Error(err.into()),
+            };
+            let backend_type_hash = self
+                .load_backend_and_block_consensus(block_number, &state, &script_hash)
+                .map(|(backend, _)| backend.validator_script_type_hash);
+
             // skip whitelist validate since we are validating a committed block
-            let run_result = match self.execute_transaction(
+            let run_result = match self.execute_transaction_with_trace(
                 chain,
                 &mut state,
                 &block_info,
                 &raw_tx,
                 Some(max_cycles),
                 None,
+                Some(SyscallTrace::summary_only(self.syscall_cycles.clone())),
+                None,
             ) {
-                Ok(run_result) => run_result,
+                Ok((run_result, trace)) => {
+                    if let (Some(hash), Some(trace)) = (backend_type_hash, trace) {
+                        let syscalls = trace
+                            .summary()
+                            .into_iter()
+                            .map(|(name, class)| (name, class.count, class.syscall_cycles));
+                        cycles_report.add_tx(hash, run_result.cycles.execution, syscalls);
+                    }
+                    run_result
+                }
                 Err(err) => {
                     let target = build_challenge_target(
                         block_hash,
@@ -685,16 +828,26 @@ impl Generator {
         if !skip_checkpoint_check {
             let post_merkle_root: H256 = raw_block.post_account().merkle_root().unpack();
             let post_merkle_count: u32 = raw_block.post_account().count().unpack();
-            assert_eq!(
-                state.calculate_root().expect("check post root"),
-                post_merkle_root,
-                "wrong block post root"
-            );
-            assert_eq!(
-                state.get_account_count().expect("check post count"),
-                post_merkle_count,
-                "wrong block post account count"
-            );
+            let (actual_root, actual_count) =
+                match (state.calculate_root(), state.get_account_count()) {
+                    (Ok(root), Ok(count)) => (root, count),
+                    (Err(err), _) | (_, Err(err)) => return ApplyBlockResult::Error(err.into()),
+                };
+            // Txs are valid but the state isn't reproduced, either the local
+            // state or the executor diverges from the chain.
+            if actual_root != post_merkle_root || actual_count != post_merkle_count {
+                return ApplyBlockResult::Error(
+                    StateDivergence {
+                        block_number,
+                        block_hash,
+                        expected_root: post_merkle_root,
+                        actual_root,
+                        expected_count: post_merkle_count,
+                        actual_count,
+                    }
+                    .into(),
+                );
+            }
         }
 
         log::debug!(
@@ -704,11 +857,20 @@ impl Generator {
             apply_state_total_ms
         );
 
+        let touched_keys: Vec<H256> = match state.take_state_tracker() {
+            Some(tracker) => tracker.touched_keys().lock().unwrap().drain().collect(),
+            None => Vec::new(),
+        };
+        drop(state);
+        let state_diff = BlockStateDiff::from_history(&*db, block_number, touched_keys);
+
         ApplyBlockResult::Success {
             withdrawal_receipts,
             prev_txs_state,
             tx_receipts,
             offchain_used_cycles,
+            cycles_report,
+            state_diff,
         }
     }
 
@@ -773,6 +935,80 @@ impl Generator {
         override_max_cycles: Option<u64>,
         cycles_pool: Option<&mut CyclesPool>,
     ) -> Result<RunResult> {
+        let (run_result, _trace) = self.execute_transaction_with_trace(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            override_max_cycles,
+            cycles_pool,
+            None,
+            None,
+        )?;
+        Ok(run_result)
+    }
+
+    /// execute a layer2 tx, the VM is interrupted with
+    /// `TransactionError::ExecutionTimeout` if it runs longer than `timeout`
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_transaction_with_timeout<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        override_max_cycles: Option<u64>,
+        cycles_pool: Option<&mut CyclesPool>,
+        timeout: Option<Duration>,
+    ) -> Result<RunResult> {
+        let (run_result, _trace) = self.execute_transaction_with_trace(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            override_max_cycles,
+            cycles_pool,
+            None,
+            timeout,
+        )?;
+        Ok(run_result)
+    }
+
+    /// execute a layer2 tx and trace its syscalls
+    pub fn trace_transaction<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        override_max_cycles: Option<u64>,
+        syscall_config: SyscallCyclesConfig,
+    ) -> Result<(RunResult, SyscallTrace)> {
+        let (run_result, trace) = self.execute_transaction_with_trace(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            override_max_cycles,
+            None,
+            Some(SyscallTrace::new(syscall_config)),
+            None,
+        )?;
+        Ok((run_result, trace.expect("syscall trace")))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_transaction_with_trace<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &mut S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        override_max_cycles: Option<u64>,
+        cycles_pool: Option<&mut CyclesPool>,
+        trace: Option<SyscallTrace>,
+        timeout: Option<Duration>,
+    ) -> Result<(RunResult, Option<SyscallTrace>)> {
         let account_id = raw_tx.to_id().unpack();
         let script_hash = state.get_script_hash(account_id)?;
         let (backend, block_consensus) = self
@@ -783,6 +1019,8 @@ impl Generator {
         let snap = state.snapshot();
         let sender_id: u32 = raw_tx.from_id().unpack();
         let nonce_before = state.get_nonce(sender_id)?;
+        // keep the tracker of the caller, e.g. of the whole block
+        let outer_tracker = state.take_state_tracker();
         state.set_state_tracker(Default::default());
 
         {
@@ -797,12 +1035,15 @@ impl Generator {
             span.record("account_id", account_id);
         }
 
-        let max_cycles = override_max_cycles.unwrap_or_else(|| {
+        let max_cycles = backend.max_cycles(override_max_cycles.unwrap_or_else(|| {
             self.rollup_context
                 .fork_config
                 .max_l2_tx_cycles(block_info.number().unpack())
-        });
+        }));
 
+        let traced = trace
+            .as_ref()
+            .map_or(false, |trace| !trace.is_summary_only());
         let args = MachineRunArgs {
             chain,
             state,
@@ -812,12 +1053,38 @@ impl Generator {
             max_cycles,
             backend,
             cycles_pool,
+            trace,
+            timeout,
         };
 
-        let run_context = self.machine_run(args).map_err(|err| {
-            state.revert(snap).expect("revert");
-            err
-        })?;
+        let mut run_context = match self.machine_run(args) {
+            Ok(run_context) => run_context,
+            Err(err) => {
+                state.revert(snap).expect("revert");
+                let err = match err {
+                    TransactionError::VM(err) if self.debug_failed_transactions && !traced => {
+                        let snap = state.snapshot();
+                        let args = MachineRunArgs {
+                            chain,
+                            state,
+                            block_info,
+                            block_consensus,
+                            raw_tx,
+                            max_cycles,
+                            backend,
+                            cycles_pool: None,
+                            trace: Some(SyscallTrace::default()),
+                            timeout,
+                        };
+                        let err = self.debug_failed_run(args, err);
+                        state.revert(snap).expect("revert");
+                        err
+                    }
+                    err => err,
+                };
+                return Err(err.into());
+            }
+        };
 
         if run_context.is_success() {
             // check sender's nonce is increased by backends
@@ -843,6 +1110,14 @@ impl Generator {
         }
 
         let state_tracker = state.take_state_tracker().unwrap();
+        if let Some(outer_tracker) = outer_tracker {
+            outer_tracker
+                .touched_keys()
+                .lock()
+                .unwrap()
+                .extend(state_tracker.touched_keys().lock().unwrap().iter());
+            state.set_state_tracker(outer_tracker);
+        }
 
         // check write data bytes
         let max_write_data_bytes = self
@@ -881,6 +1156,7 @@ impl Generator {
             }
             .into());
         }
+        let trace = run_context.trace.take();
         let r = RunResult {
             return_data: run_context.return_data,
             logs: state.appended_logs().to_vec(),
@@ -921,7 +1197,24 @@ impl Generator {
             span.record("write_data_count", r.write_data_hashes.len());
         }
 
-        Ok(r)
+        Ok((r, trace))
+    }
+
+    /// Re-run a tx failed with `error` with tracing, returns the error with
+    /// the machine state.
+    fn debug_failed_run<S: State + CodeStore + JournalDB, C: ChainView>(
+        &self,
+        args: MachineRunArgs<'_, C, S>,
+        error: VMError,
+    ) -> TransactionError {
+        log::debug!("[execute tx] re-run tx failed with VM error: {}", error);
+        match self.machine_run(args) {
+            Err(err @ TransactionError::VMWithState { .. }) => err,
+            _ => {
+                log::warn!("[execute tx] re-run of tx failed with VM error didn't fail the same");
+                TransactionError::VM(error)
+            }
+        }
     }
 
     pub fn backend_manage(&self) -> &BackendManage {
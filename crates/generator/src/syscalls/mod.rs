@@ -27,7 +27,9 @@ use gw_types::{
     prelude::*,
 };
 use gw_utils::RollupContext;
-use std::{cmp, convert::TryInto};
+use std::{cmp, convert::TryInto, time::Instant};
+
+use self::trace::SyscallTrace;
 
 use self::error_codes::{
     GW_ERROR_ACCOUNT_NOT_FOUND, GW_ERROR_DUPLICATED_SCRIPT_HASH, GW_ERROR_INVALID_ACCOUNT_SCRIPT,
@@ -37,6 +39,7 @@ use self::error_codes::{
 
 mod bn;
 pub mod error_codes;
+pub mod trace;
 
 /* Constants */
 // Increasing from 25k(ethereum contract code size) to 128k.
@@ -79,6 +82,10 @@ pub struct RunContext {
     pub return_data: Bytes,
     pub exit_code: i8,
     pub debug_log_buf: Vec<u8>,
+    /// Set to trace syscalls of the run.
+    pub trace: Option<SyscallTrace>,
+    /// The run is interrupted at the next syscall after the deadline.
+    pub deadline: Option<Instant>,
 }
 
 impl RunContext {
@@ -166,6 +173,15 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
     fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
         let code = machine.registers()[A7].to_u64();
 
+        if let Some(deadline) = self.context.deadline {
+            if Instant::now() >= deadline {
+                return Err(VMError::Unexpected("execution timeout".to_owned()));
+            }
+        }
+        if let Some(ref mut trace) = self.context.trace {
+            let syscall_cycles = Self::get_syscall_cycles(code, &trace.syscall_config);
+            trace.record_syscall(code, machine.cycles(), syscall_cycles);
+        }
         if let Some(cycles_pool) = self.cycles_pool {
             let syscall_cycles = Self::get_syscall_cycles(code, cycles_pool.syscall_config());
             if 0 != syscall_cycles {
@@ -196,6 +212,9 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
                 self.state
                     .update_raw(key, value)
                     .map_err(|err| VMError::Unexpected(format!("store kv error: {}", err)))?;
+                if let Some(ref mut trace) = self.context.trace {
+                    trace.record_write(key, value);
+                }
                 machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
                 Ok(true)
             }
@@ -207,6 +226,9 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
                     .state
                     .get_raw(&key)
                     .map_err(|err| VMError::Unexpected(format!("get raw: {}", err)))?;
+                if let Some(ref mut trace) = self.context.trace {
+                    trace.record_read(key, value);
+                }
                 machine
                     .memory_mut()
                     .store_bytes(value_addr, value.as_slice())?;
@@ -589,6 +611,10 @@ impl<'a, 'b, S: State + CodeStore + JournalDB, C: ChainView, Mac: SupportMachine
                 let input_size = machine.registers()[A4].to_u64();
                 let input = load_bytes(machine, input_addr, input_size as usize)?;
 
+                if let Some(ref mut trace) = self.context.trace {
+                    let k: u64 = input_size / 192;
+                    trace.add_syscall_cycles(k * trace.syscall_config.sys_bn_per_pairing_cycles);
+                }
                 if let Some(cycles_pool) = self.cycles_pool {
                     // k is the number of pairings being computed
                     let k: u64 = input_size / 192;
@@ -0,0 +1,195 @@
+//! Trace of syscalls of a tx execution, for debugging contracts.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+use gw_config::SyscallCyclesConfig;
+use gw_types::h256::*;
+
+use super::*;
+
+/// Syscalls kept in `MachineState`.
+const MACHINE_STATE_SYSCALLS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedSyscall {
+    pub code: u64,
+    /// VM cycles consumed before the syscall.
+    pub execution_cycles: u64,
+    /// Cycles charged for the syscall by `SyscallCyclesConfig`.
+    pub syscall_cycles: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyscallClassSummary {
+    pub count: u64,
+    pub syscall_cycles: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyscallTrace {
+    pub(crate) syscall_config: SyscallCyclesConfig,
+    /// Only count syscalls by class, syscalls and state accesses are not
+    /// kept.
+    summary_only: bool,
+    classes: BTreeMap<&'static str, SyscallClassSummary>,
+    last_class: Option<&'static str>,
+    pub syscalls: Vec<TracedSyscall>,
+    /// Raw state reads as (key, value), in order.
+    pub reads: Vec<(H256, H256)>,
+    /// Raw state writes as (key, value), in order.
+    pub writes: Vec<(H256, H256)>,
+}
+
+impl SyscallTrace {
+    /// Syscall cycles are counted by `syscall_config`, whether or not a
+    /// cycles pool is used.
+    pub fn new(syscall_config: SyscallCyclesConfig) -> Self {
+        SyscallTrace {
+            syscall_config,
+            ..Default::default()
+        }
+    }
+
+    /// Only count syscalls by class, cheap enough to meter every tx of a
+    /// block.
+    pub fn summary_only(syscall_config: SyscallCyclesConfig) -> Self {
+        SyscallTrace {
+            syscall_config,
+            summary_only: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_summary_only(&self) -> bool {
+        self.summary_only
+    }
+
+    pub(crate) fn record_syscall(&mut self, code: u64, execution_cycles: u64, cycles: u64) {
+        let name = syscall_name(code);
+        let class = self.classes.entry(name).or_default();
+        class.count += 1;
+        class.syscall_cycles = class.syscall_cycles.saturating_add(cycles);
+        self.last_class = Some(name);
+        if !self.summary_only {
+            self.syscalls.push(TracedSyscall {
+                code,
+                execution_cycles,
+                syscall_cycles: cycles,
+            });
+        }
+    }
+
+    pub(crate) fn record_read(&mut self, key: H256, value: H256) {
+        if !self.summary_only {
+            self.reads.push((key, value));
+        }
+    }
+
+    pub(crate) fn record_write(&mut self, key: H256, value: H256) {
+        if !self.summary_only {
+            self.writes.push((key, value));
+        }
+    }
+
+    /// Charge extra cycles to the last syscall, e.g. per pairing of
+    /// `SYS_BN_PAIRING`.
+    pub(crate) fn add_syscall_cycles(&mut self, cycles: u64) {
+        if let Some(class) = self.last_class.and_then(|name| self.classes.get_mut(name)) {
+            class.syscall_cycles = class.syscall_cycles.saturating_add(cycles);
+        }
+        if let Some(last) = self.syscalls.last_mut() {
+            last.syscall_cycles = last.syscall_cycles.saturating_add(cycles);
+        }
+    }
+
+    /// Count and cycles of syscalls by class name.
+    pub fn summary(&self) -> BTreeMap<&'static str, SyscallClassSummary> {
+        self.classes.clone()
+    }
+}
+
+/// State of the VM when a traced run failed with a VM error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineState {
+    pub pc: u64,
+    pub registers: Vec<u64>,
+    pub cycles: u64,
+    /// The last syscalls before the failure, in order.
+    pub last_syscalls: Vec<TracedSyscall>,
+    /// Output of the debug print syscall.
+    pub debug_log: String,
+}
+
+impl MachineState {
+    pub(crate) fn new(
+        pc: u64,
+        registers: Vec<u64>,
+        cycles: u64,
+        trace: &SyscallTrace,
+        debug_log_buf: &[u8],
+    ) -> Self {
+        let skip = trace.syscalls.len().saturating_sub(MACHINE_STATE_SYSCALLS);
+        MachineState {
+            pc,
+            registers,
+            cycles,
+            last_syscalls: trace.syscalls[skip..].to_vec(),
+            debug_log: String::from_utf8_lossy(debug_log_buf).into_owned(),
+        }
+    }
+}
+
+impl Display for MachineState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pc: {:#x}, cycles: {}, registers: [",
+            self.pc, self.cycles
+        )?;
+        for (i, reg) in self.registers.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:#x}", reg)?;
+        }
+        write!(f, "], last syscalls: [")?;
+        for (i, syscall) in self.last_syscalls.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", syscall_name(syscall.code))?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Name of a syscall, by the class of `SyscallCyclesConfig` if it has one.
+pub fn syscall_name(code: u64) -> &'static str {
+    match code {
+        SYS_CREATE => "create",
+        SYS_STORE => "store",
+        SYS_LOAD => "load",
+        SYS_LOAD_ACCOUNT_SCRIPT => "load_account_script",
+        SYS_SET_RETURN_DATA => "set_return_data",
+        SYS_STORE_DATA => "store_data",
+        SYS_LOAD_DATA => "load_data",
+        SYS_LOAD_ROLLUP_CONFIG => "load_rollup_config",
+        SYS_LOAD_TRANSACTION => "load_transaction",
+        SYS_LOAD_BLOCKINFO => "load_block_info",
+        SYS_GET_BLOCK_HASH => "get_block_hash",
+        SYS_PAY_FEE => "pay_fee",
+        SYS_LOG => "log",
+        SYS_RECOVER_ACCOUNT => "recover_account",
+        SYS_BN_ADD => "bn_add",
+        SYS_BN_MUL => "bn_mul",
+        SYS_BN_PAIRING => "bn_pairing",
+        SYS_SNAPSHOT => "snapshot",
+        SYS_REVERT => "revert",
+        SYS_CHECK_SUDT_ADDRESS => "check_sudt_address",
+        DEBUG_PRINT_SYSCALL_NUMBER => "debug_print",
+        _ => "unknown",
+    }
+}
@@ -2,18 +2,31 @@
 #[global_allocator]
 static GLOBAL_ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Arg, Command, CommandFactory, Parser};
+use godwoken_bin::subcommand::audit_deposits::{AuditDepositsCommand, COMMAND_AUDIT_DEPOSITS};
+use godwoken_bin::subcommand::backup::{
+    BackupCommand, RestoreCommand, COMMAND_BACKUP, COMMAND_RESTORE,
+};
 use godwoken_bin::subcommand::db_block_validator;
+use godwoken_bin::subcommand::doctor::{DoctorCommand, COMMAND_DOCTOR};
 use godwoken_bin::subcommand::export_block::{ExportArgs, ExportBlock};
 use godwoken_bin::subcommand::import_block::{ImportArgs, ImportBlock};
 use godwoken_bin::subcommand::migrate::{MigrateCommand, COMMAND_MIGRATE};
 use godwoken_bin::subcommand::peer_id::{PeerIdCommand, COMMAND_PEER_ID};
+use godwoken_bin::subcommand::refund_deposits::{RefundDepositsCommand, COMMAND_REFUND_DEPOSITS};
+use godwoken_bin::subcommand::reorgs::{ReorgsCommand, COMMAND_REORGS};
+use godwoken_bin::subcommand::replay::{ReplayCommand, COMMAND_REPLAY};
 use godwoken_bin::subcommand::rewind_to_last_valid_block::{
     RewindToLastValidBlockCommand, COMMAND_REWIND_TO_LAST_VALID_BLOCK,
 };
+use godwoken_bin::subcommand::serve_readonly::{ServeReadonlyCommand, COMMAND_SERVE_READONLY};
+use godwoken_bin::subcommand::stake::{StakeCommand, COMMAND_STAKE};
+use godwoken_bin::subcommand::withdrawal_unlocker::{
+    WithdrawalUnlockerCommand, COMMAND_WITHDRAWAL_UNLOCKER,
+};
 use gw_block_producer::runner;
-use gw_config::{BackendForkConfig, Config, SUDTProxyConfig};
+use gw_config::{BackendForkConfig, Config, LogFormat, LogRotation, SUDTProxyConfig};
 use gw_telemetry::trace;
 use gw_version::Version;
 use std::{env, fs, path::Path};
@@ -40,6 +53,47 @@ fn read_config<P: AsRef<Path>>(path: P) -> Result<Config> {
     Ok(config)
 }
 
+/// Init tracing with `config.logging`, and the OTLP exporter in
+/// `config.otlp` if set.
+fn init_trace(config: &Config) -> Result<trace::TraceGuard> {
+    let otlp = match config.otlp {
+        Some(ref otlp) => {
+            if !(0.0..=1.0).contains(&otlp.sampling_ratio) {
+                bail!(
+                    "otlp.sampling_ratio {} is not in [0, 1]",
+                    otlp.sampling_ratio
+                );
+            }
+            Some(trace::OtlpOptions {
+                endpoint: &otlp.endpoint,
+                sampling_ratio: otlp.sampling_ratio,
+                service_name: &otlp.service_name,
+            })
+        }
+        None => None,
+    };
+
+    let logging = &config.logging;
+    let filter = logging.filter.directives();
+    let file = logging.file.as_ref().map(|file| trace::LogFileOptions {
+        dir: &file.dir,
+        prefix: &file.prefix,
+        rotation: match file.rotation {
+            LogRotation::Minutely => trace::Rotation::MINUTELY,
+            LogRotation::Hourly => trace::Rotation::HOURLY,
+            LogRotation::Daily => trace::Rotation::DAILY,
+            LogRotation::Never => trace::Rotation::NEVER,
+        },
+    });
+    let log = trace::LogOptions {
+        json: logging.format == LogFormat::Json,
+        filter: Some(&filter),
+        file,
+    };
+
+    Ok(trace::init_with(trace::TraceOptions { otlp, log })?)
+}
+
 fn generate_example_config<P: AsRef<Path>>(path: P) -> Result<()> {
     let mut config = Config::default();
     config.fork.backend_forks.push(BackendForkConfig {
@@ -213,7 +267,17 @@ async fn run_cli() -> Result<()> {
         )
         .subcommand(PeerIdCommand::command())
         .subcommand(RewindToLastValidBlockCommand::command())
-        .subcommand(MigrateCommand::command());
+        .subcommand(MigrateCommand::command())
+        .subcommand(BackupCommand::command())
+        .subcommand(RestoreCommand::command())
+        .subcommand(ServeReadonlyCommand::command())
+        .subcommand(WithdrawalUnlockerCommand::command())
+        .subcommand(RefundDepositsCommand::command())
+        .subcommand(StakeCommand::command())
+        .subcommand(ReplayCommand::command())
+        .subcommand(ReorgsCommand::command())
+        .subcommand(DoctorCommand::command())
+        .subcommand(AuditDepositsCommand::command());
 
     // handle subcommands
     let matches = app.clone().get_matches();
@@ -221,7 +285,7 @@ async fn run_cli() -> Result<()> {
         Some((COMMAND_RUN, m)) => {
             let config_path = m.value_of(ARG_CONFIG).unwrap();
             let config = read_config(&config_path)?;
-            let _guard = trace::init()?;
+            let _guard = init_trace(&config)?;
             gw_metrics::init(&config);
             runner::run(config, m.is_present(ARG_SKIP_CONFIG_CHECK)).await?;
         }
@@ -286,11 +350,42 @@ async fn run_cli() -> Result<()> {
         Some((COMMAND_MIGRATE, m)) => {
             MigrateCommand::from_clap(m).run()?;
         }
+        Some((COMMAND_BACKUP, m)) => {
+            BackupCommand::from_clap(m).run()?;
+        }
+        Some((COMMAND_RESTORE, m)) => {
+            RestoreCommand::from_clap(m).run()?;
+        }
+        Some((COMMAND_SERVE_READONLY, m)) => {
+            ServeReadonlyCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_WITHDRAWAL_UNLOCKER, m)) => {
+            WithdrawalUnlockerCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_REFUND_DEPOSITS, m)) => {
+            RefundDepositsCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_STAKE, m)) => {
+            StakeCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_REPLAY, m)) => {
+            let _guard = trace::init()?;
+            ReplayCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_REORGS, m)) => {
+            ReorgsCommand::from_clap(m).run()?;
+        }
+        Some((COMMAND_DOCTOR, m)) => {
+            DoctorCommand::from_clap(m).run().await?;
+        }
+        Some((COMMAND_AUDIT_DEPOSITS, m)) => {
+            AuditDepositsCommand::from_clap(m).run()?;
+        }
         _ => {
             // default command: start a Godwoken node
             let config_path = "./config.toml";
             let config = read_config(&config_path)?;
-            let _guard = trace::init()?;
+            let _guard = init_trace(&config)?;
             gw_metrics::init(&config);
             runner::run(config, false).await?;
         }
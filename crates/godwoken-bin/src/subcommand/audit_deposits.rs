@@ -0,0 +1,79 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use gw_config::Config;
+use gw_store::{
+    deposit_status::DepositStatus, readonly::StoreReadonly, schema::COLUMNS,
+    traits::chain_store::ChainStore,
+};
+use gw_types::{packed::OutPoint, prelude::*};
+
+pub const COMMAND_AUDIT_DEPOSITS: &str = "audit-deposits";
+
+/// Check that no deposit cell is credited by more than one block, and that
+/// deposit statuses agree with blocks. Exits with 1 if any problem is found.
+#[derive(Parser)]
+#[clap(name = COMMAND_AUDIT_DEPOSITS)]
+pub struct AuditDepositsCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// From block number
+    #[clap(long, default_value = "0")]
+    from_block: u64,
+    /// To block number, default is the last valid tip block
+    #[clap(long)]
+    to_block: Option<u64>,
+}
+
+impl AuditDepositsCommand {
+    pub fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        let store = StoreReadonly::open(&config.store.path, COLUMNS).context("open database")?;
+
+        let tip_number: u64 = store.get_last_valid_tip_block()?.raw().number().unpack();
+        let to_block = self.to_block.unwrap_or(tip_number).min(tip_number);
+
+        let mut credited: HashMap<OutPoint, u64> = HashMap::new();
+        let (mut deposits, mut problems) = (0usize, 0usize);
+        for number in self.from_block..=to_block {
+            let deposit_info_vec = match store.get_block_deposit_info_vec(number) {
+                Some(vec) => vec,
+                None => continue,
+            };
+            for deposit in deposit_info_vec.into_iter() {
+                deposits += 1;
+                let out_point = deposit.cell().out_point();
+                if let Some(first) = credited.insert(out_point.clone(), number) {
+                    problems += 1;
+                    println!(
+                        "[DUPLICATED] deposit {} is credited by block #{} and #{}",
+                        out_point, first, number
+                    );
+                }
+                match store.get_deposit_status(&out_point) {
+                    Some(DepositStatus::Credited { block_number }) if block_number == number => {}
+                    status => {
+                        problems += 1;
+                        println!(
+                            "[STATUS] deposit {} of block #{} has status {:?}",
+                            out_point, number, status
+                        );
+                    }
+                }
+            }
+        }
+
+        println!(
+            "audited blocks #{}..=#{}, deposits: {}, problems: {}",
+            self.from_block, to_block, deposits, problems
+        );
+        if problems > 0 {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+}
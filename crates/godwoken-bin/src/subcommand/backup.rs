@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gw_config::Config;
+use gw_store::backup::{create_backup, restore_backup};
+use gw_store::schema::COLUMNS;
+use gw_store::Store;
+use gw_telemetry::trace;
+use gw_version::Version;
+
+pub const COMMAND_BACKUP: &str = "backup";
+pub const COMMAND_RESTORE: &str = "restore";
+
+fn read_config(path: &Path) -> Result<Config> {
+    let content = fs::read(path)
+        .with_context(|| format!("read config file from {}", path.to_string_lossy()))?;
+    toml::from_slice(&content).context("parse config file")
+}
+
+/// Backup database with a RocksDB checkpoint, the node must be stopped.
+///
+/// Use the `admin_create_backup` RPC to backup a running node.
+#[derive(Parser)]
+#[clap(name = COMMAND_BACKUP)]
+pub struct BackupCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// Backup output directory, must not exist
+    #[clap(short, long)]
+    output: PathBuf,
+}
+
+impl BackupCommand {
+    pub fn run(self) -> Result<()> {
+        let _guard = trace::init()?;
+        let config = read_config(&self.config)?;
+        if !config.store.path.exists() {
+            bail!(
+                "store path {} doesn't exist",
+                config.store.path.to_string_lossy()
+            );
+        }
+
+        // Fails on the lock of the database if the node is running. A
+        // checkpoint of a secondary instance isn't consistent, the primary
+        // may delete files being linked.
+        let store = Store::open(&config.store, COLUMNS)
+            .context("open database, stop the node or use the admin_create_backup RPC instead")?;
+        let manifest = create_backup(
+            &store,
+            config.genesis.rollup_type_hash,
+            Version::current().to_string(),
+            &self.output,
+        )?;
+
+        log::info!(
+            "backup tip block {} {} to {}",
+            manifest.tip_block_number,
+            manifest.tip_block_hash,
+            self.output.to_string_lossy()
+        );
+
+        Ok(())
+    }
+}
+
+/// Restore database from a backup directory
+#[derive(Parser)]
+#[clap(name = COMMAND_RESTORE)]
+pub struct RestoreCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// Backup directory created by the backup command
+    #[clap(short, long)]
+    backup: PathBuf,
+}
+
+impl RestoreCommand {
+    pub fn run(self) -> Result<()> {
+        let _guard = trace::init()?;
+        let config = read_config(&self.config)?;
+        let manifest = restore_backup(
+            &self.backup,
+            &config.genesis.rollup_type_hash,
+            &config.store.path,
+        )?;
+
+        log::info!(
+            "restore tip block {} {} to {}",
+            manifest.tip_block_number,
+            manifest.tip_block_hash,
+            config.store.path.to_string_lossy()
+        );
+
+        Ok(())
+    }
+}
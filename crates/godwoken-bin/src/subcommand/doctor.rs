@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use gw_block_producer::doctor::{diagnose, CheckStatus, DoctorArgs};
+use gw_config::Config;
+
+pub const COMMAND_DOCTOR: &str = "doctor";
+
+const ONE_CKB: u64 = 100_000_000;
+
+/// Check the node config, L1 RPCs, the rollup cell and the local database.
+/// Exits with 1 if any check fails.
+#[derive(Parser)]
+#[clap(name = COMMAND_DOCTOR)]
+pub struct DoctorCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// Max blocks the indexer tip may be behind the CKB tip
+    #[clap(long, default_value = "10")]
+    max_indexer_lag: u64,
+    /// Max blocks the local tip may be behind the L2 tip on L1
+    #[clap(long, default_value = "5")]
+    max_block_lag: u64,
+    /// Min block producer wallet balance in CKB
+    #[clap(long, default_value = "1000")]
+    min_wallet_balance: u64,
+}
+
+impl DoctorCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+
+        let args = DoctorArgs {
+            max_indexer_lag: self.max_indexer_lag,
+            max_block_lag: self.max_block_lag,
+            min_wallet_balance: self.min_wallet_balance.saturating_mul(ONE_CKB),
+        };
+        let results = diagnose(&config, &args).await;
+        let mut failed = false;
+        for result in results {
+            let status = match result.status {
+                CheckStatus::Pass => "PASS",
+                CheckStatus::Fail => {
+                    failed = true;
+                    "FAIL"
+                }
+                CheckStatus::Skip => "SKIP",
+            };
+            println!("[{}] {}: {}", status, result.name, result.detail);
+        }
+        if failed {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+}
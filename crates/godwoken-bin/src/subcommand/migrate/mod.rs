@@ -3,8 +3,12 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use clap::Parser;
 use gw_config::Config;
-use gw_store::migrate::{init_migration_factory, open_or_create_db};
+use gw_store::migrate::{
+    init_migration_factory, open_or_create_db_with_progress, open_read_only_for_migration,
+    MigrationProgress,
+};
 use gw_telemetry::trace;
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
 
 #[cfg(feature = "smt-trie")]
 mod smt_trie;
@@ -18,6 +22,37 @@ pub struct MigrateCommand {
     /// Godwoken config file path
     #[clap(long)]
     config: PathBuf,
+    /// Report migrations that would run and their estimated cost, without
+    /// modifying the database
+    #[clap(long)]
+    dry_run: bool,
+    /// Rebuild SMTs on multiple threads (smt-trie migration only)
+    #[clap(long)]
+    #[cfg_attr(not(feature = "smt-trie"), allow(dead_code))]
+    parallel: bool,
+}
+
+struct ProgressBarMigrationProgress(ProgressBar);
+
+impl MigrationProgress for ProgressBarMigrationProgress {
+    fn inc(&self, delta: u64) {
+        self.0.inc(delta);
+    }
+
+    fn finish(&self) {
+        self.0.finish_with_message("done");
+    }
+}
+
+fn progress_bar(version: &str, len: u64) -> Box<dyn MigrationProgress> {
+    let bar = ProgressBar::new(len);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {prefix} {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+            .progress_chars("##-"),
+    );
+    bar.set_prefix(version.to_string());
+    Box::new(ProgressBarMigrationProgress(bar))
 }
 
 impl MigrateCommand {
@@ -32,8 +67,34 @@ impl MigrateCommand {
         #[allow(unused_mut)]
         let mut factory = init_migration_factory();
         #[cfg(feature = "smt-trie")]
-        assert!(factory.insert(Box::new(smt_trie::SMTTrieMigration)));
-        open_or_create_db(&config.store, factory).context("open and migrate database")?;
+        assert!(factory.insert(Box::new(smt_trie::SMTTrieMigration {
+            parallel: self.parallel,
+        })));
+
+        if self.dry_run {
+            if !config.store.path.exists() {
+                println!("database not found, nothing to migrate");
+                return Ok(());
+            }
+            let db = open_read_only_for_migration(&config.store).context("open database")?;
+            let pending = factory.dry_run(&db)?;
+            if pending.is_empty() {
+                println!("database is up to date");
+            }
+            for (version, estimate) in pending {
+                println!(
+                    "{}: {} keys, {}, about {}",
+                    version,
+                    estimate.keys,
+                    HumanBytes(estimate.disk_bytes),
+                    HumanDuration(estimate.duration)
+                );
+            }
+            return Ok(());
+        }
+
+        open_or_create_db_with_progress(&config.store, factory, &progress_bar)
+            .context("open and migrate database")?;
 
         Ok(())
     }
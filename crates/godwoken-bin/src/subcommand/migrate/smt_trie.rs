@@ -1,107 +1,223 @@
-use anyhow::{ensure, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
+use gw_smt::{
+    smt::{default_store::DefaultStore, SMT},
+    smt_h256_ext::SMTH256,
+};
 use gw_store::{
     autorocks::{Direction, TransactionDb},
-    migrate::{Migration, SMTTrieMigrationPlaceHolder},
+    migrate::{
+        Migration, MigrationDb, MigrationEstimate, MigrationProgress, SMTTrieMigrationPlaceHolder,
+    },
     schema::{
-        COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_SMT_BRANCH,
-        COLUMN_BLOCK_SMT_LEAF, COLUMN_REVERTED_BLOCK_SMT_BRANCH, COLUMN_REVERTED_BLOCK_SMT_LEAF,
+        Col, COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_SMT_BRANCH,
+        COLUMN_BLOCK_SMT_LEAF, COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_BRANCH,
+        COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    },
+    smt::{
+        parallel_builder::build_smt_parallel,
+        smt_store::{SMTBlockStore, SMTRevertedBlockStore},
     },
+    traits::kv_store::{KVStoreRead, KVStoreWrite},
+    transaction::StoreTransaction,
 };
 use gw_store::{traits::chain_store::ChainStore, Store};
-use gw_types::h256::H256;
+use gw_types::{h256::H256, prelude::Unpack};
+
+/// Rough number of SMT leaves updated per second, used for time estimation.
+const ESTIMATED_LEAVES_PER_SEC: u64 = 20_000;
+
+/// Number of leaves updated in one transaction.
+const BATCH_SIZE: usize = 50_000;
 
-pub struct SMTTrieMigration;
+/// Migration cursor, `[phase]` or `[phase, last migrated leaf key..]`.
+const CURSOR_KEY: &[u8] = b"SMT_TRIE_MIGRATION_CURSOR";
+/// State smt root before the migration, to verify against at the end.
+const STATE_SMT_ROOT_KEY: &[u8] = b"SMT_TRIE_MIGRATION_STATE_SMT_ROOT";
+
+const PHASE_CLEAR_BRANCHES: u8 = 0;
+const PHASE_STATE_SMT: u8 = 1;
+const PHASE_BLOCK_SMT: u8 = 2;
+const PHASE_REVERTED_BLOCK_SMT: u8 = 3;
+const PHASE_VERIFY: u8 = 4;
+
+pub struct SMTTrieMigration {
+    /// Rebuild each SMT with the parallel builder instead of batch by batch.
+    pub parallel: bool,
+}
 
 impl Migration for SMTTrieMigration {
-    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb> {
+    fn estimate(&self, db: &dyn MigrationDb) -> Result<MigrationEstimate> {
+        Ok(MigrationEstimate::from_columns(
+            db,
+            &[
+                COLUMN_ACCOUNT_SMT_LEAF,
+                COLUMN_BLOCK_SMT_LEAF,
+                COLUMN_REVERTED_BLOCK_SMT_LEAF,
+            ],
+            ESTIMATED_LEAVES_PER_SEC,
+        ))
+    }
+
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
         log::info!("SMTTrieMigration running");
         let mut store = Store::new(db);
 
-        // Get state smt root before migration.
-        let old_state_smt_root = {
-            let mut tx = store.begin_transaction();
-            let state_smt = tx.state_smt().context("state_smt")?;
-            *state_smt.root()
+        let (mut phase, mut last_key) = match store.get(COLUMN_META, CURSOR_KEY) {
+            Some(cursor) => {
+                log::info!("resuming SMTTrieMigration from phase {}", cursor[0]);
+                (cursor[0], Some(cursor[1..].to_vec()).filter(|k| !k.is_empty()))
+            }
+            None => {
+                // Get state smt root before migration.
+                let old_state_smt_root = {
+                    let mut tx = store.begin_transaction();
+                    let state_smt = tx.state_smt().context("state_smt")?;
+                    *state_smt.root()
+                };
+                let mut tx = store.begin_transaction();
+                tx.insert_raw(COLUMN_META, STATE_SMT_ROOT_KEY, old_state_smt_root.as_slice())?;
+                tx.insert_raw(COLUMN_META, CURSOR_KEY, &[PHASE_CLEAR_BRANCHES])?;
+                tx.commit().context("commit migration cursor")?;
+                (PHASE_CLEAR_BRANCHES, None)
+            }
         };
 
-        log::info!("deleting old SMT branches");
-        let db = store.as_inner_mut();
-        db.clear_cf(COLUMN_ACCOUNT_SMT_BRANCH)
-            .context("clear COLUMN_ACCOUNT_SMT_BRANCH")?;
-        // So that if we exit in the middle of this migration, the smt branches
-        // columns are not empty and SMTTrieMigrationPlaceholder won't just
-        // succeed.
-        db.put(COLUMN_ACCOUNT_SMT_BRANCH, b"migrating", b"migrating")
-            .context("put migrating")?;
-        db.clear_cf(COLUMN_BLOCK_SMT_BRANCH)
-            .context("clear COLUMN_BLOCK_SMT_BRANCH")?;
-        db.clear_cf(COLUMN_REVERTED_BLOCK_SMT_BRANCH)
-            .context("clear COLUMN_REVERTED_BLOCK_SMT_BRANCH")?;
-
-        log::info!("migrating state smt");
-        {
-            let mut tx = store.begin_transaction_skip_concurrency_control();
-            let mut state_smt = tx.state_smt().context("state_smt")?;
-            // XXX: memory usage of long running transaction.
-            for (k, v) in store
+        if self.parallel && phase < PHASE_VERIFY {
+            // The parallel builder can't resume in the middle. Clear the
+            // branches again if it gets interrupted.
+            store
                 .as_inner()
-                .iter(COLUMN_ACCOUNT_SMT_LEAF, Direction::Forward)
-            {
-                state_smt
-                    .update(
-                        <[u8; 32]>::try_from(&k[..]).unwrap().into(),
-                        <[u8; 32]>::try_from(&v[..]).unwrap().into(),
-                    )
-                    .context("update state_smt")?;
+                .put(COLUMN_META, CURSOR_KEY, &[PHASE_CLEAR_BRANCHES])
+                .context("put migration cursor")?;
+            clear_branches(&mut store)?;
+            let old_state_smt_root: [u8; 32] = {
+                let root = store
+                    .get(COLUMN_META, STATE_SMT_ROOT_KEY)
+                    .context("old state smt root not found")?;
+                root.as_ref().try_into()?
+            };
+            for (name, leaf_col, branch_col, expected_root) in [
+                (
+                    "state",
+                    COLUMN_ACCOUNT_SMT_LEAF,
+                    COLUMN_ACCOUNT_SMT_BRANCH,
+                    old_state_smt_root,
+                ),
+                (
+                    "block",
+                    COLUMN_BLOCK_SMT_LEAF,
+                    COLUMN_BLOCK_SMT_BRANCH,
+                    store.get_block_smt_root()?,
+                ),
+                (
+                    "reverted block",
+                    COLUMN_REVERTED_BLOCK_SMT_LEAF,
+                    COLUMN_REVERTED_BLOCK_SMT_BRANCH,
+                    store.get_reverted_block_smt_root()?,
+                ),
+            ] {
+                log::info!("migrating {} smt in parallel", name);
+                let root = build_smt_parallel(&store, leaf_col, branch_col, progress)
+                    .with_context(|| format!("build {} smt in parallel", name))?;
+                ensure!(
+                    H256::from(root) == expected_root,
+                    "parallel {} smt root mismatch, run migrate again without --parallel",
+                    name
+                );
             }
-            ensure!(old_state_smt_root == *state_smt.root());
-            tx.commit().context("commit state_smt")?;
+            phase = PHASE_VERIFY;
+            store
+                .as_inner()
+                .put(COLUMN_META, CURSOR_KEY, &[phase])
+                .context("put migration cursor")?;
         }
 
-        log::info!("migrating block smt");
-        {
-            let mut tx = store.begin_transaction_skip_concurrency_control();
-            let mut block_smt = tx.block_smt().context("block_smt")?;
-            for (k, v) in store
-                .as_inner()
-                .iter(COLUMN_BLOCK_SMT_LEAF, Direction::Forward)
-            {
-                block_smt
-                    .update(
-                        <[u8; 32]>::try_from(&k[..]).unwrap().into(),
-                        <[u8; 32]>::try_from(&v[..]).unwrap().into(),
-                    )
-                    .context("update block_smt")?;
+        while phase < PHASE_VERIFY {
+            match phase {
+                PHASE_CLEAR_BRANCHES => clear_branches(&mut store)?,
+                PHASE_STATE_SMT => {
+                    log::info!("migrating state smt");
+                    migrate_leaves(
+                        &store,
+                        phase,
+                        COLUMN_ACCOUNT_SMT_LEAF,
+                        last_key.as_deref(),
+                        progress,
+                        |tx, leaves| {
+                            let mut state_smt = tx.state_smt().context("state_smt")?;
+                            for (k, v) in leaves {
+                                state_smt.update(k, v).context("update state_smt")?;
+                            }
+                            Ok(())
+                        },
+                    )?;
+                }
+                PHASE_BLOCK_SMT => {
+                    log::info!("migrating block smt");
+                    migrate_leaves(
+                        &store,
+                        phase,
+                        COLUMN_BLOCK_SMT_LEAF,
+                        last_key.as_deref(),
+                        progress,
+                        |tx, leaves| {
+                            let mut block_smt = tx.block_smt().context("block_smt")?;
+                            for (k, v) in leaves {
+                                block_smt.update(k, v).context("update block_smt")?;
+                            }
+                            Ok(())
+                        },
+                    )?;
+                }
+                PHASE_REVERTED_BLOCK_SMT => {
+                    log::info!("migrating reverted block smt");
+                    migrate_leaves(
+                        &store,
+                        phase,
+                        COLUMN_REVERTED_BLOCK_SMT_LEAF,
+                        last_key.as_deref(),
+                        progress,
+                        |tx, leaves| {
+                            let mut reverted_block_smt =
+                                tx.reverted_block_smt().context("reverted_block_smt")?;
+                            for (k, v) in leaves {
+                                reverted_block_smt
+                                    .update(k, v)
+                                    .context("update reverted_block_smt")?;
+                            }
+                            Ok(())
+                        },
+                    )?;
+                }
+                _ => bail!("unknown SMTTrieMigration phase {}", phase),
             }
-            let root = *block_smt.root();
-            ensure!(tx.get_block_smt_root().unwrap() == H256::from(root));
-            tx.commit().context("commit block smt")?;
+            phase += 1;
+            last_key = None;
+            store
+                .as_inner()
+                .put(COLUMN_META, CURSOR_KEY, &[phase])
+                .context("put migration cursor")?;
         }
 
-        log::info!("migrating reverted block smt");
-        {
-            let mut tx = store.begin_transaction_skip_concurrency_control();
-            let mut reverted_block_smt = tx.reverted_block_smt().context("reverted_block_smt")?;
-            for (k, v) in store
-                .as_inner()
-                .iter(COLUMN_REVERTED_BLOCK_SMT_LEAF, Direction::Forward)
-            {
-                reverted_block_smt
-                    .update(
-                        <[u8; 32]>::try_from(&k[..]).unwrap().into(),
-                        <[u8; 32]>::try_from(&v[..]).unwrap().into(),
-                    )
-                    .context("update reverted_block_smt")?;
+        log::info!("verifying smt roots");
+        if let Err(err) = verify_roots(&store) {
+            if self.parallel {
+                // Start over next time.
+                store
+                    .as_inner()
+                    .put(COLUMN_META, CURSOR_KEY, &[PHASE_CLEAR_BRANCHES])
+                    .context("put migration cursor")?;
+                return Err(err.context(
+                    "parallel SMT rebuild failed verification, run migrate again without --parallel",
+                ));
             }
-            let root = *reverted_block_smt.root();
-            ensure!(tx.get_reverted_block_smt_root().unwrap() == H256::from(root));
-            tx.commit().context("commit reverted_block_smt")?;
+            return Err(err);
         }
 
-        store
-            .as_inner()
-            .delete(COLUMN_ACCOUNT_SMT_BRANCH, b"migrating")?;
-
         log::info!("SMTTrieMigration completed");
         Ok(store.into_inner())
     }
@@ -109,3 +225,122 @@ impl Migration for SMTTrieMigration {
         SMTTrieMigrationPlaceHolder.version()
     }
 }
+
+fn clear_branches(store: &mut Store) -> Result<()> {
+    log::info!("deleting old SMT branches");
+    let db = store.as_inner_mut();
+    db.clear_cf(COLUMN_ACCOUNT_SMT_BRANCH)
+        .context("clear COLUMN_ACCOUNT_SMT_BRANCH")?;
+    db.clear_cf(COLUMN_BLOCK_SMT_BRANCH)
+        .context("clear COLUMN_BLOCK_SMT_BRANCH")?;
+    db.clear_cf(COLUMN_REVERTED_BLOCK_SMT_BRANCH)
+        .context("clear COLUMN_REVERTED_BLOCK_SMT_BRANCH")?;
+    Ok(())
+}
+
+/// Verify smt roots against the ones before migration, and clean up.
+///
+/// Roots are computed from the rebuilt branches, not read from the roots
+/// stored in `COLUMN_META`, which the migration doesn't touch.
+fn verify_roots(store: &Store) -> Result<()> {
+    let mut tx = store.begin_transaction();
+    let old_state_smt_root = tx
+        .get(COLUMN_META, STATE_SMT_ROOT_KEY)
+        .context("old state smt root not found")?;
+    let state_smt_root = *tx.state_smt().context("state_smt")?.root();
+    ensure!(
+        old_state_smt_root.as_ref() == state_smt_root.as_slice(),
+        "state smt root mismatch"
+    );
+
+    let block_smt_root: H256 = (*SMT::new_with_store(SMTBlockStore::new(&mut tx))?.root()).into();
+    ensure!(
+        tx.get_block_smt_root()? == block_smt_root,
+        "block smt root mismatch"
+    );
+    // Bad blocks are in the block smt too, so compare with the tip, valid or
+    // not.
+    let tip_block_hash = tx.get_tip_block_hash()?;
+    let tip_global_state = tx
+        .get_block_post_global_state(&tip_block_hash)?
+        .context("tip global state not found")?;
+    let tip_block_smt_root: H256 = tip_global_state.block().merkle_root().unpack();
+    ensure!(
+        tip_block_smt_root == block_smt_root,
+        "block smt root mismatch with tip global state"
+    );
+
+    // The global state of the tip doesn't have blocks reverted after it, so
+    // recompute the root from the leaves instead. There are only a few
+    // reverted blocks.
+    let reverted_block_smt_root: H256 =
+        (*SMT::new_with_store(SMTRevertedBlockStore::new(&mut tx))?.root()).into();
+    let mut expected = SMT::new(SMTH256::zero(), DefaultStore::<SMTH256>::default());
+    for (k, v) in store
+        .as_inner()
+        .iter(COLUMN_REVERTED_BLOCK_SMT_LEAF, Direction::Forward)
+    {
+        expected.update(
+            <[u8; 32]>::try_from(&k[..]).unwrap().into(),
+            <[u8; 32]>::try_from(&v[..]).unwrap().into(),
+        )?;
+    }
+    ensure!(
+        tx.get_reverted_block_smt_root()? == reverted_block_smt_root
+            && H256::from(*expected.root()) == reverted_block_smt_root,
+        "reverted block smt root mismatch"
+    );
+
+    tx.delete(COLUMN_META, CURSOR_KEY)?;
+    tx.delete(COLUMN_META, STATE_SMT_ROOT_KEY)?;
+    tx.commit().context("commit smt roots verification")?;
+    Ok(())
+}
+
+/// Update smt with leaves in `leaf_col` after `last_key`, committing every
+/// `BATCH_SIZE` leaves together with the migration cursor.
+fn migrate_leaves<F>(
+    store: &Store,
+    phase: u8,
+    leaf_col: Col,
+    last_key: Option<&[u8]>,
+    progress: &dyn MigrationProgress,
+    mut update: F,
+) -> Result<()>
+where
+    F: FnMut(&mut StoreTransaction, Vec<(SMTH256, SMTH256)>) -> Result<()>,
+{
+    let mut iter = store.as_inner().iter(leaf_col, Direction::Forward);
+    if let Some(last_key) = last_key {
+        iter.seek(last_key);
+        if iter.key() == Some(last_key) {
+            iter.next();
+        }
+    }
+
+    loop {
+        let leaves: Vec<(SMTH256, SMTH256)> = iter
+            .by_ref()
+            .take(BATCH_SIZE)
+            .map(|(k, v)| {
+                (
+                    <[u8; 32]>::try_from(&k[..]).unwrap().into(),
+                    <[u8; 32]>::try_from(&v[..]).unwrap().into(),
+                )
+            })
+            .collect();
+        let last_key = match leaves.last() {
+            Some((k, _)) => *k,
+            None => return Ok(()),
+        };
+        let mut cursor = vec![phase];
+        cursor.extend_from_slice(last_key.as_slice());
+        let len = leaves.len();
+
+        let mut tx = store.begin_transaction_skip_concurrency_control();
+        update(&mut tx, leaves)?;
+        tx.insert_raw(COLUMN_META, CURSOR_KEY, &cursor)?;
+        tx.commit().context("commit smt batch")?;
+        progress.inc(len as u64);
+    }
+}
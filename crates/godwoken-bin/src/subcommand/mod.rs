@@ -1,6 +1,15 @@
+pub mod audit_deposits;
+pub mod backup;
 pub mod db_block_validator;
+pub mod doctor;
 pub mod export_block;
 pub mod import_block;
 pub mod migrate;
 pub mod peer_id;
+pub mod refund_deposits;
+pub mod reorgs;
+pub mod replay;
 pub mod rewind_to_last_valid_block;
+pub mod serve_readonly;
+pub mod stake;
+pub mod withdrawal_unlocker;
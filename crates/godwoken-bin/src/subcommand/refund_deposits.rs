@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use gw_block_producer::runner::refund_deposits;
+use gw_config::Config;
+use gw_telemetry::trace;
+use gw_types::prelude::Pack;
+
+pub const COMMAND_REFUND_DEPOSITS: &str = "refund-deposits";
+
+/// Refund deposit cells whose cancel timeout has passed to the depositor.
+/// Only deposits whose owner lock is the sighash lock of the key are refunded.
+#[derive(Parser)]
+#[clap(name = COMMAND_REFUND_DEPOSITS)]
+pub struct RefundDepositsCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// Depositor private key file path
+    #[clap(short = 'k', long)]
+    privkey_path: PathBuf,
+    /// Max number of deposits refunded in one tx
+    #[clap(long, default_value = "100")]
+    max_deposits: usize,
+    /// Build and dry run the refund tx without sending it
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl RefundDepositsCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        let _guard = trace::init()?;
+
+        let tx_hash =
+            refund_deposits(&config, &self.privkey_path, self.max_deposits, self.dry_run).await?;
+        match tx_hash {
+            Some(tx_hash) if self.dry_run => println!("refund tx {} (not sent)", tx_hash.pack()),
+            Some(tx_hash) => println!("refund tx {}", tx_hash.pack()),
+            None => println!("no deposit to refund"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use gw_config::Config;
+use gw_store::{readonly::StoreReadonly, schema::COLUMNS};
+use gw_types::prelude::*;
+
+pub const COMMAND_REORGS: &str = "reorgs";
+
+/// List reorgs observed by the node, newest first.
+#[derive(Parser)]
+#[clap(name = COMMAND_REORGS)]
+pub struct ReorgsCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// Only list reorgs with ids less than it
+    #[clap(long)]
+    before: Option<u64>,
+    /// Max reorgs to list
+    #[clap(long, default_value = "20")]
+    limit: usize,
+    /// Print hashes of reverted blocks
+    #[clap(long)]
+    blocks: bool,
+}
+
+impl ReorgsCommand {
+    pub fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        let store = StoreReadonly::open(&config.store.path, COLUMNS).context("open database")?;
+
+        for (id, record) in store.iter_reorg_records(self.before).take(self.limit) {
+            println!(
+                "#{} {} observed at {}, fork point #{} {}, depth {}, txs {}, withdrawals {}, block timestamps {}..={}",
+                id,
+                record.kind.as_str(),
+                record.observed_at,
+                record.fork_point_number,
+                record.fork_point_hash.pack(),
+                record.depth(),
+                record.tx_count,
+                record.withdrawal_count,
+                record.first_block_timestamp,
+                record.last_block_timestamp,
+            );
+            if self.blocks {
+                for (number, hash) in (record.fork_point_number + 1..).zip(record.block_hashes) {
+                    println!("    #{} {}", number, hash.pack());
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gw_block_producer::{replay_block::replay_range, runner::BaseInitComponents};
+use gw_config::Config;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::prelude::*;
+
+pub const COMMAND_REPLAY: &str = "replay";
+
+/// Re-execute history blocks against a scratch state and compare the results
+/// with the stored ones
+#[derive(Parser)]
+#[clap(name = COMMAND_REPLAY)]
+pub struct ReplayCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// From block number
+    #[clap(long)]
+    from: u64,
+    /// To block number, default is the last valid tip
+    #[clap(long)]
+    to: Option<u64>,
+}
+
+impl ReplayCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        if config.store.path.as_os_str().is_empty() {
+            bail!("empty store path, no block to replay");
+        }
+
+        let base = BaseInitComponents::init(&config, true).await?;
+        let to = match self.to {
+            Some(to) => to,
+            None => base
+                .store
+                .get_last_valid_tip_block()?
+                .raw()
+                .number()
+                .unpack(),
+        };
+        let from = self.from;
+        let (store, generator) = (base.store, base.generator);
+        let divergence =
+            tokio::task::spawn_blocking(move || replay_range(&store, &generator, from, to))
+                .await??;
+
+        match divergence {
+            Some(divergence) => bail!("diverged at {}", divergence),
+            None => println!("replayed block #{}..=#{}, no divergence", from, to),
+        }
+        Ok(())
+    }
+}
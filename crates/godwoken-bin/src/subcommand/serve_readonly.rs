@@ -0,0 +1,89 @@
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use gw_config::Config;
+use gw_generator::ArcSwap;
+use gw_rpc_server::{readonly_registry::ReadonlyRegistry, server::serve_jsonrpc};
+use gw_store::{readonly::StoreReadonly, schema::COLUMNS};
+use gw_telemetry::trace;
+use gw_types::packed::RollupConfig;
+use gw_utils::liveness::Liveness;
+use tokio::sync::broadcast;
+
+pub const COMMAND_SERVE_READONLY: &str = "serve-readonly";
+
+/// Serve query-only RPCs from the database opened in readonly mode (no mem pool
+/// or chain sync)
+#[derive(Parser)]
+#[clap(name = COMMAND_SERVE_READONLY)]
+pub struct ServeReadonlyCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+    /// Listen address, defaults to `rpc_server.listen` in the config
+    #[clap(short, long)]
+    listen: Option<String>,
+    /// Reopen the database every <REOPEN_INTERVAL> seconds to pick up new
+    /// blocks. The database is only opened once if not set.
+    #[clap(long)]
+    reopen_interval: Option<u64>,
+}
+
+impl ServeReadonlyCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        let _guard = trace::init()?;
+
+        let listen = self.listen.as_ref().unwrap_or(&config.rpc_server.listen);
+        let listen_addr: SocketAddr = {
+            let mut addrs: Vec<_> = listen.to_socket_addrs()?.collect();
+            if addrs.len() != 1 {
+                return Err(anyhow!("Invalid RPC listen address `{}`", listen));
+            }
+            addrs.remove(0)
+        };
+
+        let store_path = config.store.path.clone();
+        let store = StoreReadonly::open(&store_path, COLUMNS).context("open database")?;
+        let store = Arc::new(ArcSwap::from_pointee(store));
+        let rollup_config: RollupConfig = config.genesis.rollup_config.into();
+        let rpc_server = ReadonlyRegistry::new(store.clone(), rollup_config).build_rpc_server()?;
+
+        let liveness = match self.reopen_interval {
+            Some(secs) => {
+                let interval = Duration::from_secs(secs);
+                let liveness = Arc::new(Liveness::new(interval * 3));
+                let liveness_ = liveness.clone();
+                tokio::spawn(async move {
+                    loop {
+                        tokio::time::sleep(interval).await;
+                        match StoreReadonly::open(&store_path, COLUMNS) {
+                            Ok(new_store) => {
+                                store.store(Arc::new(new_store));
+                                liveness_.tick();
+                            }
+                            Err(err) => log::warn!("reopen database: {:#}", err),
+                        }
+                    }
+                });
+                liveness
+            }
+            None => Arc::new(Liveness::new(Duration::MAX)),
+        };
+
+        let (shutdown_send, shutdown_recv) = broadcast::channel(1);
+        tokio::spawn(async move {
+            let _ = tokio::signal::ctrl_c().await;
+            log::info!("received sigint, shutting down");
+            let _ = shutdown_send.send(());
+        });
+
+        serve_jsonrpc(listen_addr, rpc_server, liveness, shutdown_recv).await
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use gw_block_producer::runner::stake_status;
+use gw_config::Config;
+use gw_types::{core::Timepoint, prelude::*};
+
+pub const COMMAND_STAKE: &str = "stake";
+
+const CKB: u64 = 100_000_000;
+
+/// Stake cells of the block producer wallet.
+#[derive(Parser)]
+#[clap(name = COMMAND_STAKE)]
+pub enum StakeCommand {
+    /// List locked stake cells and the reclaimable amount.
+    Status {
+        /// The config file path
+        #[clap(short, long, default_value = "./config.toml")]
+        config: PathBuf,
+    },
+}
+
+impl StakeCommand {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            StakeCommand::Status { config } => {
+                let content = std::fs::read(&config).with_context(|| {
+                    format!("read config file from {}", config.to_string_lossy())
+                })?;
+                let config: Config = toml::from_slice(&content).context("parse config file")?;
+
+                let status = stake_status(&config).await?;
+                let reclaimable: Vec<_> = { status.reclaimable() }
+                    .map(|stake| stake.cell.out_point.clone())
+                    .collect();
+                for stake in status.locked.iter() {
+                    let out_point = &stake.cell.out_point;
+                    let capacity: u64 = stake.cell.output.capacity().unpack();
+                    let finalized_at = match stake.finalized_timepoint {
+                        Timepoint::BlockNumber(number) => format!("block {}", number),
+                        Timepoint::Timestamp(timestamp) => format!("timestamp {}", timestamp),
+                    };
+                    let state = if reclaimable.contains(out_point) {
+                        "reclaimable"
+                    } else if stake.is_finalized {
+                        "finalized, reused by the next submission"
+                    } else {
+                        "locked"
+                    };
+                    println!(
+                        "{}:{} {} CKB, finalized at {}, {}",
+                        out_point.tx_hash(),
+                        out_point.index().unpack(),
+                        format_ckb(capacity),
+                        finalized_at,
+                        state
+                    );
+                }
+                println!(
+                    "locked {} cells, {} CKB",
+                    status.locked.len(),
+                    format_ckb(status.locked_capacity())
+                );
+                println!(
+                    "reclaimable {} cells, {} CKB",
+                    reclaimable.len(),
+                    format_ckb(status.reclaimable_capacity())
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn format_ckb(shannons: u64) -> String {
+    format!("{}.{:08}", shannons / CKB, shannons % CKB)
+}
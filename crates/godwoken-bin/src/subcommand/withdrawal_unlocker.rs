@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use gw_block_producer::runner::run_withdrawal_unlocker;
+use gw_config::Config;
+use gw_telemetry::trace;
+
+pub const COMMAND_WITHDRAWAL_UNLOCKER: &str = "withdrawal-unlocker";
+
+/// Unlock finalized withdrawal cells to their owners, without running a node.
+/// Uses `block_producer.withdrawal_unlocker` in the config.
+#[derive(Parser)]
+#[clap(name = COMMAND_WITHDRAWAL_UNLOCKER)]
+pub struct WithdrawalUnlockerCommand {
+    /// The config file path
+    #[clap(short, long, default_value = "./config.toml")]
+    config: PathBuf,
+}
+
+impl WithdrawalUnlockerCommand {
+    pub async fn run(self) -> Result<()> {
+        let content = std::fs::read(&self.config)
+            .with_context(|| format!("read config file from {}", self.config.to_string_lossy()))?;
+        let config: Config = toml::from_slice(&content).context("parse config file")?;
+        let _guard = trace::init()?;
+        gw_metrics::init(&config);
+
+        run_withdrawal_unlocker(config).await
+    }
+}
@@ -2,10 +2,11 @@ use anyhow::{anyhow, Error as JsonError};
 use ckb_fixed_hash::H256;
 use ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64};
 use gw_types::{packed, prelude::*};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ScriptHashType {
     /// Type "data" matches script code via cell data hash.
@@ -65,14 +66,16 @@ impl TryFrom<packed::Byte> for ScriptHashType {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Script {
     /// The hash used to match the script code.
+    #[schemars(with = "crate::schema::H256")]
     pub code_hash: H256,
     /// Specifies how to use the `code_hash` to match the script code.
     pub hash_type: ScriptHashType,
     /// Arguments for script.
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub args: JsonBytes,
 }
 
@@ -128,10 +131,11 @@ impl From<Script> for ckb_jsonrpc_types::Script {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Transaction {
     /// Reserved for future usage. It must equal 0 in current version.
+    #[schemars(with = "crate::schema::Uint32")]
     pub version: Version,
     /// An array of cell deps.
     ///
@@ -145,6 +149,7 @@ pub struct Transaction {
     /// The block must already be in the canonical chain.
     ///
     /// Lock script and type script can read the header information of blocks listed here.
+    #[schemars(with = "Vec<crate::schema::H256>")]
     pub header_deps: Vec<H256>,
     /// An array of input cells.
     ///
@@ -156,6 +161,7 @@ pub struct Transaction {
     ///
     /// This is a parallel array of outputs. The cell capacity, lock, and type of the output i is
     /// `outputs[i]` and its data is `outputs_data[i]`.
+    #[schemars(with = "Vec<crate::schema::JsonBytes>")]
     pub outputs_data: Vec<JsonBytes>,
     /// An array of variable-length binaries.
     ///
@@ -163,12 +169,13 @@ pub struct Transaction {
     ///
     /// For example, the bundled secp256k1 lock script requires storing the signature in
     /// `witnesses`.
+    #[schemars(with = "Vec<crate::schema::JsonBytes>")]
     pub witnesses: Vec<JsonBytes>,
 }
 
 pub type Version = Uint32;
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct CellDep {
     /// Dependency type.
@@ -209,24 +216,26 @@ impl From<ckb_jsonrpc_types::CellDep> for CellDep {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct CellInput {
     /// Restrict when the transaction can be committed into the chain.
     ///
     /// See the RFC [Transaction valid since](https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0017-tx-valid-since/0017-tx-valid-since.md).
+    #[schemars(with = "crate::schema::Uint64")]
     pub since: Uint64,
     /// Reference to the input cell.
     pub previous_output: OutPoint,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct CellOutput {
     /// The cell capacity.
     ///
     /// The capacity of a cell is the value of the cell in Shannons. It is also the upper limit of
     /// the cell occupied storage size where every 100,000,000 Shannons give 1-byte storage.
+    #[schemars(with = "crate::schema::Uint64")]
     pub capacity: Capacity,
     /// The lock script.
     pub lock: Script,
@@ -237,12 +246,14 @@ pub struct CellOutput {
     pub type_: Option<Script>,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct OutPoint {
     /// Transaction hash in which the cell is an output.
+    #[schemars(with = "crate::schema::H256")]
     pub tx_hash: H256,
     /// The output index of the cell in the transaction specified by `tx_hash`.
+    #[schemars(with = "crate::schema::Uint32")]
     pub index: Uint32,
 }
 
@@ -276,7 +287,7 @@ impl From<ckb_jsonrpc_types::OutPoint> for OutPoint {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum DepType {
     /// Type "code".
@@ -329,10 +340,12 @@ impl Default for DepType {
 
 pub type Capacity = Uint64;
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct NumberHash {
+    #[schemars(with = "crate::schema::Uint64")]
     block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
     block_hash: H256,
 }
 
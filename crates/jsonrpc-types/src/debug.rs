@@ -3,15 +3,18 @@ use std::convert::TryFrom;
 use ckb_fixed_hash::H256 as JsonH256;
 use ckb_jsonrpc_types::{JsonBytes, Uint64};
 use gw_types::offchain::{self};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::godwoken::LogItem;
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct CycleMeter {
+    #[schemars(with = "crate::schema::Uint64")]
     pub execution: Uint64,
     pub r#virtual: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub total: Uint64,
 }
 
@@ -25,16 +28,19 @@ impl From<offchain::CycleMeter> for CycleMeter {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct DebugRunResult {
     // return data
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub return_data: JsonBytes,
     // log data
     pub logs: Vec<LogItem>,
     pub exit_code: i8,
     pub cycles: CycleMeter,
+    #[schemars(with = "Vec<crate::schema::H256>")]
     pub read_data_hashes: Vec<JsonH256>,
+    #[schemars(with = "Vec<crate::schema::H256>")]
     pub write_data_hashes: Vec<JsonH256>,
     pub debug_log: Vec<String>,
     pub execution_time_ms: u32,
@@ -76,3 +82,62 @@ impl TryFrom<offchain::RunResult> for DebugRunResult {
         })
     }
 }
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TracedSyscall {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub code: Uint64,
+    pub name: String,
+    /// VM cycles consumed before the syscall
+    #[schemars(with = "crate::schema::Uint64")]
+    pub execution_cycles: Uint64,
+    /// Cycles charged for the syscall by the syscall cycles config
+    #[schemars(with = "crate::schema::Uint64")]
+    pub syscall_cycles: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SyscallClassSummary {
+    pub name: String,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub count: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub syscall_cycles: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StateAccess {
+    #[schemars(with = "crate::schema::H256")]
+    pub key: JsonH256,
+    #[schemars(with = "crate::schema::H256")]
+    pub value: JsonH256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DebugTxTrace {
+    pub run_result: DebugRunResult,
+    pub syscalls: Vec<TracedSyscall>,
+    pub syscall_summary: Vec<SyscallClassSummary>,
+    pub state_reads: Vec<StateAccess>,
+    pub state_writes: Vec<StateAccess>,
+}
+
+/// State of the VM when a tx failed with a VM error.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DebugMachineState {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub pc: Uint64,
+    /// The 32 general purpose registers
+    #[schemars(with = "Vec<crate::schema::Uint64>")]
+    pub registers: Vec<Uint64>,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub cycles: Uint64,
+    /// The last syscalls before the failure, in order
+    pub last_syscalls: Vec<TracedSyscall>,
+    pub debug_log: Vec<String>,
+}
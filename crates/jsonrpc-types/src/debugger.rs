@@ -2,44 +2,59 @@ use crate::godwoken::ChallengeTargetType;
 
 use ckb_fixed_hash::H256 as JsonH256;
 use ckb_jsonrpc_types as json_types;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReprMockCellDep {
+    #[schemars(with = "crate::schema::CkbObject")]
     pub cell_dep: json_types::CellDep,
+    #[schemars(with = "crate::schema::CkbObject")]
     pub output: json_types::CellOutput,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub data: json_types::JsonBytes,
+    #[schemars(with = "Option<crate::schema::H256>")]
     pub header: Option<JsonH256>,
 }
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReprMockInput {
+    #[schemars(with = "crate::schema::CkbObject")]
     pub input: json_types::CellInput,
+    #[schemars(with = "crate::schema::CkbObject")]
     pub output: json_types::CellOutput,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub data: json_types::JsonBytes,
+    #[schemars(with = "Option<crate::schema::H256>")]
     pub header: Option<JsonH256>,
 }
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReprMockInfo {
     pub inputs: Vec<ReprMockInput>,
     pub cell_deps: Vec<ReprMockCellDep>,
+    #[schemars(with = "Vec<crate::schema::CkbObject>")]
     pub header_deps: Vec<json_types::HeaderView>,
 }
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReprMockTransaction {
     pub mock_info: ReprMockInfo,
+    #[schemars(with = "crate::schema::CkbObject")]
     pub tx: json_types::Transaction,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum DumpChallengeTarget {
     ByBlockHash {
+        #[schemars(with = "crate::schema::H256")]
         block_hash: JsonH256,
+        #[schemars(with = "crate::schema::Uint32")]
         target_index: json_types::Uint32,
         target_type: ChallengeTargetType,
     },
     ByBlockNumber {
+        #[schemars(with = "crate::schema::Uint64")]
         block_number: json_types::Uint64,
+        #[schemars(with = "crate::schema::Uint32")]
         target_index: json_types::Uint32,
         target_type: ChallengeTargetType,
     },
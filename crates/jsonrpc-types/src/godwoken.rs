@@ -4,16 +4,21 @@ use ckb_fixed_hash::{H160, H256};
 use ckb_jsonrpc_types::{JsonBytes, Uint128, Uint32, Uint64};
 use gw_types::core::Timepoint;
 use gw_types::{bytes::Bytes, offchain, packed, prelude::*};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RawL2Transaction {
     pub chain_id: Uint64, // chain id
+    #[schemars(with = "crate::schema::Uint32")]
     pub from_id: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
     pub to_id: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
     pub nonce: Uint32,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub args: JsonBytes,
 }
 
@@ -53,10 +58,11 @@ impl From<packed::RawL2Transaction> for RawL2Transaction {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2Transaction {
     pub raw: RawL2Transaction,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub signature: JsonBytes,
 }
 
@@ -80,11 +86,12 @@ impl From<packed::L2Transaction> for L2Transaction {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2TransactionView {
     #[serde(flatten)]
     pub inner: L2Transaction,
+    #[schemars(with = "crate::schema::H256")]
     pub hash: H256,
 }
 
@@ -96,12 +103,15 @@ impl From<packed::L2Transaction> for L2TransactionView {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Hash, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Hash, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct LogItem {
+    #[schemars(with = "crate::schema::Uint32")]
     pub account_id: Uint32,
     // The actual type is `u8`
+    #[schemars(with = "crate::schema::Uint32")]
     pub service_flag: Uint32,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub data: JsonBytes,
 }
 
@@ -133,14 +143,20 @@ impl From<packed::LogItem> for LogItem {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct TxReceipt {
+    #[schemars(with = "crate::schema::H256")]
     pub tx_witness_hash: H256,
     pub post_state: AccountMerkleState,
+    #[schemars(with = "Vec<crate::schema::H256>")]
     pub read_data_hashes: Vec<H256>,
     pub logs: Vec<LogItem>,
+    #[schemars(with = "crate::schema::Uint32")]
     pub exit_code: Uint32,
+    /// sUDTs of the sUDT logs, filled by `gw_get_transaction_receipt`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sudt_tokens: Vec<SUDTToken>,
 }
 
 impl From<TxReceipt> for packed::TxReceipt {
@@ -151,6 +167,7 @@ impl From<TxReceipt> for packed::TxReceipt {
             read_data_hashes,
             logs,
             exit_code,
+            ..
         } = json;
         let tx_witness_hash: [u8; 32] = tx_witness_hash.into();
         let read_data_hashes: Vec<_> = read_data_hashes
@@ -191,11 +208,12 @@ impl From<packed::TxReceipt> for TxReceipt {
             read_data_hashes,
             logs,
             exit_code: (exit_code as u32).into(),
+            sudt_tokens: Vec::new(),
         }
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ChallengeTargetType {
     TxExecution,
@@ -242,7 +260,7 @@ impl TryFrom<packed::Byte> for ChallengeTargetType {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ChallengeTarget {
     pub block_hash: H256,                 // hash of challenged block
@@ -277,7 +295,7 @@ impl From<packed::ChallengeTarget> for ChallengeTarget {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ChallengeWitness {
     pub raw_l2block: RawL2Block,
@@ -309,13 +327,15 @@ impl From<packed::ChallengeWitness> for ChallengeWitness {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2Block {
     pub raw: RawL2Block,
     pub kv_state: Vec<KVPair>,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub kv_state_proof: JsonBytes,
     pub transactions: Vec<L2Transaction>,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub block_proof: JsonBytes,
     pub withdrawals: Vec<WithdrawalRequest>,
 }
@@ -374,19 +394,25 @@ impl From<packed::L2Block> for L2Block {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RawL2Block {
+    #[schemars(with = "crate::schema::Uint64")]
     pub number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
     pub parent_block_hash: H256,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub block_producer: JsonBytes,
+    #[schemars(with = "crate::schema::H256")]
     pub stake_cell_owner_lock_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
     pub timestamp: Uint64,
     pub prev_account: AccountMerkleState,
     pub post_account: AccountMerkleState,
     pub submit_transactions: SubmitTransactions,
     pub submit_withdrawals: SubmitWithdrawals,
     // hash(account_root | account_count) of each withdrawals & transactions
+    #[schemars(with = "Vec<crate::schema::H256>")]
     pub state_checkpoint_list: Vec<H256>,
 }
 
@@ -449,15 +475,18 @@ impl From<packed::RawL2Block> for RawL2Block {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2BlockView {
     pub raw: RawL2Block,
     pub kv_state: Vec<KVPair>,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub kv_state_proof: JsonBytes,
     pub transactions: Vec<L2TransactionView>,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub block_proof: JsonBytes,
     pub withdrawal_requests: Vec<WithdrawalRequest>,
+    #[schemars(with = "crate::schema::H256")]
     pub hash: H256,
 }
 
@@ -483,7 +512,22 @@ impl From<packed::L2Block> for L2BlockView {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+/// A block with receipts of its txs, its withdrawals with owner locks and its
+/// deposit requests.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct L2FullBlockView {
+    #[serde(flatten)]
+    pub block: L2BlockView,
+    /// In the order of `transactions`
+    pub tx_receipts: Vec<TxReceipt>,
+    /// In the order of `withdrawal_requests`
+    pub withdrawals: Vec<WithdrawalRequestExtra>,
+    pub deposit_requests: Vec<DepositRequest>,
+    pub post_global_state: GlobalState,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum L2BlockStatus {
     Unfinalized,
@@ -497,14 +541,14 @@ impl Default for L2BlockStatus {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2BlockWithStatus {
     pub block: L2BlockView,
     pub status: L2BlockStatus,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum L2TransactionStatus {
     Pending,
@@ -517,18 +561,23 @@ impl Default for L2TransactionStatus {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2TransactionWithStatus {
     pub transaction: Option<L2TransactionView>,
     pub status: L2TransactionStatus,
+    /// Inclusion proof of a committed tx, only returned with verbose 2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<TransactionProof>,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum WithdrawalStatus {
     Pending,
     Committed,
+    /// Dropped by the mem pool after `mem_pool.pending_withdrawal_ttl_secs`.
+    Expired,
 }
 
 impl Default for WithdrawalStatus {
@@ -537,7 +586,7 @@ impl Default for WithdrawalStatus {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalWithStatus {
     pub withdrawal: Option<WithdrawalRequestExtra>,
@@ -546,20 +595,108 @@ pub struct WithdrawalWithStatus {
     pub l2_committed_info: Option<L2WithdrawalCommittedInfo>,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+/// Withdrawal lifecycle, from the mem pool to being unlocked to its owner on L1.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalLifecycleStatus {
+    /// In the mem pool, not in the mem block yet.
+    Pending,
+    /// In the mem block, or in an L2 block not yet confirmed on L1.
+    Packaged,
+    /// The L2 block is confirmed on L1.
+    OnChain,
+    Finalized,
+    /// The withdrawal cell is unlocked to its owner.
+    Unlocked,
+    /// Dropped by the mem pool after `mem_pool.pending_withdrawal_ttl_secs`.
+    Expired,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawalLifecycle {
+    pub status: WithdrawalLifecycleStatus,
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub l2_block_number: Option<Uint64>,
+    #[schemars(with = "Option<crate::schema::H256>")]
+    pub l2_block_hash: Option<H256>,
+    /// Block submission tx.
+    #[schemars(with = "Option<crate::schema::H256>")]
+    pub l1_tx_hash: Option<H256>,
+    #[schemars(with = "Option<crate::schema::H256>")]
+    pub unlock_tx_hash: Option<H256>,
+    /// Estimated timestamp (in milliseconds) when the withdrawal is finalized.
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub estimated_finalized_at: Option<Uint64>,
+}
+
+/// A pending withdrawal of the mem pool, in packaging order.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawalQueueItem {
+    #[schemars(with = "crate::schema::H256")]
+    pub withdrawal_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
+    pub account_script_hash: H256,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub nonce: Uint32,
+    /// In the mem block.
+    pub packaged: bool,
+    /// Left out of the mem block for lack of finalized custodians.
+    pub deferred: bool,
+    /// Estimated L2 block including the withdrawal.
+    #[schemars(with = "crate::schema::Uint64")]
+    pub estimated_block_number: Uint64,
+    /// Estimated timestamp (in milliseconds) when the withdrawal is finalized.
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub estimated_finalized_at: Option<Uint64>,
+}
+
+/// Deposit lifecycle, from the deposit cell on L1 to being credited by an L2
+/// block.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositLifecycleStatus {
+    /// Live on L1, not collected by the mem pool yet.
+    Pending,
+    /// Collected by the mem pool, not in the mem block yet.
+    Collected,
+    /// In the mem block.
+    Packaged,
+    /// In an L2 block.
+    Credited,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DepositLifecycle {
+    pub status: DepositLifecycleStatus,
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub l2_block_number: Option<Uint64>,
+    #[schemars(with = "Option<crate::schema::H256>")]
+    pub l2_block_hash: Option<H256>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2WithdrawalCommittedInfo {
+    #[schemars(with = "crate::schema::Uint64")]
     pub block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
     pub block_hash: H256,
+    #[schemars(with = "crate::schema::Uint32")]
     pub withdrawal_index: Uint32,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct SubmitTransactions {
+    #[schemars(with = "crate::schema::H256")]
     pub tx_witness_root: H256,
+    #[schemars(with = "crate::schema::Uint32")]
     pub tx_count: Uint32,
     // hash(account_root | account_count) before apply all transactions
+    #[schemars(with = "crate::schema::H256")]
     pub prev_state_checkpoint: H256,
 }
 
@@ -589,10 +726,12 @@ impl From<packed::SubmitTransactions> for SubmitTransactions {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct SubmitWithdrawals {
+    #[schemars(with = "crate::schema::H256")]
     pub withdrawal_witness_root: H256,
+    #[schemars(with = "crate::schema::Uint32")]
     pub withdrawal_count: Uint32,
 }
 
@@ -619,10 +758,12 @@ impl From<packed::SubmitWithdrawals> for SubmitWithdrawals {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct AccountMerkleState {
+    #[schemars(with = "crate::schema::H256")]
     pub merkle_root: H256,
+    #[schemars(with = "crate::schema::Uint32")]
     pub count: Uint32,
 }
 
@@ -646,10 +787,12 @@ impl From<packed::AccountMerkleState> for AccountMerkleState {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct BlockMerkleState {
+    #[schemars(with = "crate::schema::H256")]
     pub merkle_root: H256,
+    #[schemars(with = "crate::schema::Uint64")]
     pub count: Uint64,
 }
 
@@ -673,10 +816,12 @@ impl From<packed::BlockMerkleState> for BlockMerkleState {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct KVPair {
+    #[schemars(with = "crate::schema::H256")]
     pub k: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub v: H256,
 }
 
@@ -699,11 +844,12 @@ impl From<packed::KVPair> for KVPair {
     }
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct GlobalState {
     pub account: AccountMerkleState,
     pub block: BlockMerkleState,
+    #[schemars(with = "crate::schema::H256")]
     pub reverted_block_root: H256,
 
     // As Godwoken switches from v1 to v2 by bumping GlobalState.version from 1 to 2, the last
@@ -714,10 +860,13 @@ pub struct GlobalState {
     // `last_finalized_timestamp` should be `None`; afterwards, `last_finalized_block_number` will
     // become `None` and `last_finalized_timestamp` will become `Some(timestamp)`.
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<crate::schema::Uint64>")]
     pub last_finalized_block_number: Option<Uint64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<crate::schema::Uint64>")]
     pub last_finalized_timestamp: Option<Uint64>,
 
+    #[schemars(with = "crate::schema::Uint32")]
     pub status: Uint32,
 }
 
@@ -776,12 +925,15 @@ impl From<packed::GlobalState> for GlobalState {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct DepositRequest {
     pub script: Script,
+    #[schemars(with = "crate::schema::H256")]
     pub sudt_script_hash: H256,
+    #[schemars(with = "crate::schema::Uint128")]
     pub amount: Uint128,
+    #[schemars(with = "crate::schema::Uint64")]
     pub capacity: Uint64,
 }
 
@@ -815,7 +967,7 @@ impl From<packed::DepositRequest> for DepositRequest {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalRequestExtra {
     pub request: WithdrawalRequest,
@@ -844,10 +996,11 @@ impl From<packed::WithdrawalRequestExtra> for WithdrawalRequestExtra {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalRequest {
     pub raw: RawWithdrawalRequest,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub signature: JsonBytes,
 }
 
@@ -870,18 +1023,27 @@ impl From<packed::WithdrawalRequest> for WithdrawalRequest {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RawWithdrawalRequest {
+    #[schemars(with = "crate::schema::Uint32")]
     pub nonce: Uint32,
+    #[schemars(with = "crate::schema::Uint64")]
     pub capacity: Uint64,
+    #[schemars(with = "crate::schema::Uint128")]
     pub amount: Uint128,
+    #[schemars(with = "crate::schema::H256")]
     pub sudt_script_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub account_script_hash: H256,
+    #[schemars(with = "crate::schema::Uint32")]
     pub registry_id: Uint32,
     // layer1 lock to withdraw after challenge period
+    #[schemars(with = "crate::schema::H256")]
     pub owner_lock_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
     pub chain_id: Uint64,
+    #[schemars(with = "crate::schema::Uint128")]
     pub fee: Uint128,
 }
 
@@ -934,21 +1096,25 @@ impl From<packed::RawWithdrawalRequest> for RawWithdrawalRequest {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct LastL2BlockCommittedInfo {
+    #[schemars(with = "crate::schema::H256")]
     pub transaction_hash: H256,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct L2BlockCommittedInfo {
+    #[schemars(with = "crate::schema::Uint64")]
     pub number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
     pub block_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub transaction_hash: H256,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AllowedEoaType {
     Unknown,
@@ -976,7 +1142,7 @@ impl TryFrom<packed::Byte> for AllowedEoaType {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AllowedContractType {
     Unknown,
@@ -1013,10 +1179,11 @@ impl TryFrom<packed::Byte> for AllowedContractType {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct AllowedEoaTypeHash {
     pub type_: AllowedEoaType,
+    #[schemars(with = "crate::schema::H256")]
     pub hash: H256,
 }
 
@@ -1040,10 +1207,11 @@ impl From<packed::AllowedTypeHash> for AllowedEoaTypeHash {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct AllowedContractTypeHash {
     pub type_: AllowedContractType,
+    #[schemars(with = "crate::schema::H256")]
     pub hash: H256,
 }
 
@@ -1067,19 +1235,30 @@ impl From<packed::AllowedTypeHash> for AllowedContractTypeHash {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RollupConfig {
+    #[schemars(with = "crate::schema::H256")]
     pub l1_sudt_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub custodian_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub deposit_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub withdrawal_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub challenge_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub stake_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub l2_sudt_validator_script_type_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub burn_lock_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
     pub required_staking_capacity: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub challenge_maturity_blocks: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub finality_blocks: Uint64,
     pub reward_burn_rate: Uint32, // * reward_burn_rate / 100
     pub chain_id: Uint64,         // chain id
@@ -1172,10 +1351,11 @@ impl From<packed::RollupConfig> for RollupConfig {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RunResult {
     // return data
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub return_data: JsonBytes,
     // log data
     pub logs: Vec<LogItem>,
@@ -1193,7 +1373,21 @@ impl From<offchain::RunResult> for RunResult {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+/// Run result of a tx of `gw_batch_execute`, failed txs are included with
+/// a non-zero exit code.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BatchRunResult {
+    #[schemars(with = "crate::schema::H256")]
+    pub tx_hash: H256,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub exit_code: Uint32,
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub return_data: JsonBytes,
+    pub logs: Vec<LogItem>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct NodeInfo {
     pub mode: NodeMode,
@@ -1209,7 +1403,7 @@ pub struct NodeInfo {
     pub gasless_tx_support: Option<GaslessTxSupportConfig>,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum NodeMode {
     FullNode,
@@ -1223,16 +1417,19 @@ impl Default for NodeMode {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct BackendInfo {
+    #[schemars(with = "crate::schema::H256")]
     pub validator_code_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub generator_code_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub validator_script_type_hash: H256,
     pub backend_type: BackendType,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum BackendType {
     Unknown,
@@ -1240,6 +1437,7 @@ pub enum BackendType {
     Sudt,
     Polyjuice,
     EthAddrReg,
+    Custom,
 }
 
 impl Default for BackendType {
@@ -1248,15 +1446,16 @@ impl Default for BackendType {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct GwScript {
+    #[schemars(with = "crate::schema::H256")]
     pub type_hash: H256,
     pub script: Script,
     pub script_type: GwScriptType,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum GwScriptType {
     Unknown,
@@ -1277,32 +1476,39 @@ impl Default for GwScriptType {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RollupCell {
+    #[schemars(with = "crate::schema::H256")]
     pub type_hash: H256,
     pub type_script: Script,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct NodeRollupConfig {
+    #[schemars(with = "crate::schema::Uint64")]
     pub required_staking_capacity: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub challenge_maturity_blocks: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub finality_blocks: Uint64,
+    #[schemars(with = "crate::schema::Uint32")]
     pub reward_burn_rate: Uint32,
+    #[schemars(with = "crate::schema::Uint64")]
     pub chain_id: Uint64,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct EoaScript {
+    #[schemars(with = "crate::schema::H256")]
     pub type_hash: H256,
     pub script: Script,
     pub eoa_type: EoaScriptType,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum EoaScriptType {
     Unknown,
@@ -1315,20 +1521,25 @@ impl Default for EoaScriptType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, Hash, JsonSchema)]
 pub struct GaslessTxSupportConfig {
     /// Gasless tx entrypoint address.
+    #[schemars(with = "crate::schema::H160")]
     pub entrypoint_address: H160,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ErrorTxReceipt {
+    #[schemars(with = "crate::schema::H256")]
     pub tx_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
     pub block_number: Uint64,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub return_data: JsonBytes,
     pub last_log: Option<LogItem>,
     // i8 -> u32, actual u8
+    #[schemars(with = "crate::schema::Uint32")]
     pub exit_code: Uint32,
 }
 
@@ -1345,25 +1556,186 @@ impl From<offchain::ErrorTxReceipt> for ErrorTxReceipt {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct SUDTFeeConfig {
+    #[schemars(with = "crate::schema::Uint32")]
     pub sudt_id: Uint32,
+    #[schemars(with = "crate::schema::Uint64")]
     pub fee_rate_weight: Uint64,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct FeeConfig {
+    #[schemars(with = "crate::schema::Uint64")]
     pub meta_cycles_limit: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub sudt_cycles_limit: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
     pub withdraw_cycles_limit: Uint64,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+/// An account of `gw_list_accounts`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountSummary {
+    #[schemars(with = "crate::schema::Uint32")]
+    pub id: Uint32,
+    #[schemars(with = "crate::schema::H256")]
+    pub script_hash: H256,
+    pub script: Option<Script>,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub nonce: Uint32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReorgKind {
+    /// Valid blocks detached from the chain.
+    Detach,
+    /// Bad blocks rewound to the last valid tip.
+    Rewind,
+    /// Bad blocks reverted by a rollup revert tx.
+    Revert,
+}
+
+/// A reorg observed by the node, of `gw_list_reorgs`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReorgRecord {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub id: Uint64,
+    pub kind: ReorgKind,
+    /// Timestamp in milliseconds when the reorg was observed.
+    #[schemars(with = "crate::schema::Uint64")]
+    pub observed_at: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub fork_point_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub fork_point_hash: H256,
+    /// Number of reverted blocks.
+    #[schemars(with = "crate::schema::Uint32")]
+    pub depth: Uint32,
+    /// Reverted blocks in ascending order.
+    #[schemars(with = "Vec<crate::schema::H256>")]
+    pub block_hashes: Vec<H256>,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub first_block_timestamp: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub last_block_timestamp: Uint64,
+    /// Txs of reverted blocks, reinjected into the mem pool.
+    #[schemars(with = "crate::schema::Uint32")]
+    pub tx_count: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub withdrawal_count: Uint32,
+}
+
+/// An sUDT known to the node, from deposits and the operator config.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SUDTToken {
+    /// Type script hash of the L1 sUDT, zero for CKB.
+    #[schemars(with = "crate::schema::H256")]
+    pub l1_script_hash: H256,
+    /// `None` if the sUDT is not deposited yet.
+    #[schemars(with = "Option<crate::schema::Uint32>")]
+    pub account_id: Option<Uint32>,
+    pub symbol: Option<String>,
+    #[schemars(with = "Option<crate::schema::Uint32>")]
+    pub decimals: Option<Uint32>,
+}
+
+/// Balance of an sUDT of an address.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SUDTBalance {
+    pub token: SUDTToken,
+    #[schemars(with = "crate::schema::U256")]
+    pub balance: gw_types::U256,
+}
+
+/// Activation of a named fork.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ForkInfo {
+    pub name: String,
+    /// `None` if the fork is disabled.
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub fork_height: Option<Uint64>,
+    /// Whether the fork is active at the tip block.
+    pub active: bool,
+}
+
+/// Result of `gw_syncing`, `false` if the node is synced, like `eth_syncing`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(untagged)]
+pub enum SyncingStatus {
+    NotSyncing(bool),
+    Syncing(SyncProgress),
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SyncProgress {
+    /// Local tip block when the node fell behind.
+    #[schemars(with = "crate::schema::Uint64")]
+    pub starting_block: Uint64,
+    /// Local tip block.
+    #[schemars(with = "crate::schema::Uint64")]
+    pub current_block: Uint64,
+    /// Tip block of the rollup cell on L1.
+    #[schemars(with = "crate::schema::Uint64")]
+    pub highest_block: Uint64,
+    /// L1 tip block known to the CKB indexer.
+    #[schemars(with = "crate::schema::Uint64")]
+    pub l1_tip_block: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub blocks_behind: Uint64,
+    /// Estimated by the sync speed since `starting_block`, `None` if unknown.
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub estimated_catch_up_secs: Option<Uint64>,
+}
+
+/// Replaying the block of L1 didn't reproduce its post account state.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StateDivergence {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub block_hash: H256,
+    /// Post account root of the block.
+    #[schemars(with = "crate::schema::H256")]
+    pub expected_root: H256,
+    /// Post account root of replaying the block.
+    #[schemars(with = "crate::schema::H256")]
+    pub actual_root: H256,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub expected_count: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub actual_count: Uint32,
+}
+
+/// Backup created by `admin_create_backup`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BackupManifest {
+    #[schemars(with = "crate::schema::H256")]
+    pub rollup_type_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub tip_block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub tip_block_hash: H256,
+    pub version: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalLockArgs {
+    #[schemars(with = "crate::schema::H256")]
     pub account_script_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
     pub withdrawal_block_hash: H256,
 
     // As Godwoken switches from v1 to v2 by bumping GlobalState.version from 1 to 2, the withdrawn
@@ -1374,11 +1746,14 @@ pub struct WithdrawalLockArgs {
     // `withdrawal_finalized_timestamp` should be `None`; afterwards, `withdrawal_block_number` will
     // become `None` and `withdrawal_finalized_timestamp` will become `Some(finalized_timestamp)`.
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<crate::schema::Uint64>")]
     pub withdrawal_block_number: Option<Uint64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<crate::schema::Uint64>")]
     pub withdrawal_finalized_timestamp: Option<Uint64>,
 
     // layer1 lock to withdraw after challenge period
+    #[schemars(with = "crate::schema::H256")]
     pub owner_lock_hash: H256,
 }
 
@@ -1434,10 +1809,12 @@ impl From<packed::WithdrawalLockArgs> for WithdrawalLockArgs {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct RegistryAddress {
+    #[schemars(with = "crate::schema::Uint32")]
     pub registry_id: Uint32,
+    #[schemars(with = "crate::schema::JsonBytes")]
     pub address: JsonBytes,
 }
 
@@ -1466,3 +1843,398 @@ impl From<gw_common::registry_address::RegistryAddress> for RegistryAddress {
         }
     }
 }
+
+/// Merkle proof of state key values against the post state root of a block.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StateProof {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub block_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
+    pub state_root: H256,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub account_count: Uint32,
+    /// Zero values are proofs of non-membership
+    pub kv_state: Vec<KVPair>,
+    /// Compiled SMT merkle proof of `kv_state`
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub proof: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountProof {
+    #[schemars(with = "Option<crate::schema::Uint32>")]
+    pub account_id: Option<Uint32>,
+    #[schemars(with = "Option<crate::schema::Uint32>")]
+    pub nonce: Option<Uint32>,
+    /// Script hash to account id, and the script hash and the nonce of the
+    /// account if it exists
+    pub state_proof: StateProof,
+}
+
+/// Block SMT merkle proof of a block against the block root of the global
+/// state of the tip block.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockProof {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub block_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
+    pub tip_block_hash: H256,
+    /// `GlobalState.block.merkle_root` of the tip block
+    #[schemars(with = "crate::schema::H256")]
+    pub block_root: H256,
+    /// Compiled SMT merkle proof of the block hash
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub proof: JsonBytes,
+}
+
+/// Finalized custodian assets left for withdrawals after those in the mem
+/// block.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawableCapacity {
+    /// CKB in shannons
+    #[schemars(with = "crate::schema::Uint128")]
+    pub capacity: Uint128,
+    pub sudt: Vec<WithdrawableSUDT>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawableSUDT {
+    #[schemars(with = "crate::schema::H256")]
+    pub sudt_script_hash: H256,
+    #[schemars(with = "crate::schema::Uint128")]
+    pub amount: Uint128,
+}
+
+/// CKB merkle tree proof, see `merkle_cbt::MerkleProof`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CKBMerkleProof {
+    #[schemars(with = "Vec<crate::schema::Uint32>")]
+    pub indices: Vec<Uint32>,
+    #[schemars(with = "Vec<crate::schema::H256>")]
+    pub lemmas: Vec<H256>,
+}
+
+/// Verification bundle of a committed tx, from the tx up to the L1 tx that
+/// submitted its block.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TransactionProof {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub block_hash: H256,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub tx_index: Uint32,
+    /// `SubmitTransactions.tx_witness_root` of the block
+    #[schemars(with = "crate::schema::H256")]
+    pub tx_witness_root: H256,
+    /// Proof of the tx witness hash leaf against `tx_witness_root`
+    pub tx_proof: CKBMerkleProof,
+    pub block_proof: BlockProof,
+    /// L1 tx that submitted the block
+    #[schemars(with = "Option<crate::schema::H256>")]
+    pub l1_transaction_hash: Option<H256>,
+}
+
+/// Override of an account applied before executing a tx, like the state
+/// override set of `eth_call`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct AccountStateOverride {
+    /// CKB balance of the eth address of the account
+    #[serde(default)]
+    #[schemars(with = "Option<crate::schema::U256>")]
+    pub balance: Option<gw_types::U256>,
+    #[serde(default)]
+    #[schemars(with = "Option<crate::schema::Uint32>")]
+    pub nonce: Option<Uint32>,
+    /// Storage slots of the account
+    #[serde(default)]
+    pub state_diff: Option<Vec<KVPair>>,
+    /// Polyjuice contract code
+    #[serde(default)]
+    #[schemars(with = "Option<crate::schema::JsonBytes>")]
+    pub code: Option<JsonBytes>,
+}
+
+/// Fee and cycles statistics of recent blocks, like `eth_feeHistory`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FeeHistory {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub oldest_block: Uint64,
+    /// In ascending order of block number
+    pub blocks: Vec<BlockFeeStats>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockFeeStats {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub used_cycles: Uint64,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub tx_count: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub withdrawal_count: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub deposit_count: Uint32,
+    /// Gas used by polyjuice txs
+    #[schemars(with = "crate::schema::Uint64")]
+    pub gas_used: Uint64,
+    /// Fees of polyjuice txs
+    #[schemars(with = "crate::schema::Uint128")]
+    pub total_fee: Uint128,
+    /// Gas prices at the requested percentiles, weighted by gas used
+    #[schemars(with = "Vec<crate::schema::Uint128>")]
+    pub gas_price_percentiles: Vec<Uint128>,
+}
+
+/// Cycles of a block by backend and by syscall class.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockCyclesReport {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    pub backends: Vec<BackendCycles>,
+    pub syscalls: Vec<SyscallCycles>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BackendCycles {
+    #[schemars(with = "crate::schema::H256")]
+    pub validator_script_type_hash: H256,
+    pub backend_type: BackendType,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub tx_count: Uint32,
+    /// VM cycles of txs, including syscall cycles
+    #[schemars(with = "crate::schema::Uint64")]
+    pub execution_cycles: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub syscall_cycles: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SyscallCycles {
+    /// Syscall class, e.g. `store` or `load_data`
+    pub name: String,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub count: Uint64,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub cycles: Uint64,
+}
+
+/// State changes of a block.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockStateDiff {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    /// In ascending order of key
+    pub entries: Vec<StateDiffEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StateDiffEntry {
+    #[schemars(with = "crate::schema::H256")]
+    pub key: H256,
+    /// Zero if the key didn't exist
+    #[schemars(with = "crate::schema::H256")]
+    pub old_value: H256,
+    #[schemars(with = "crate::schema::H256")]
+    pub new_value: H256,
+}
+
+/// An account by id, or by serialized registry address.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(untagged)]
+pub enum AccountRef {
+    Id(#[schemars(with = "crate::schema::Uint32")] Uint32),
+    RegistryAddress(#[schemars(with = "crate::schema::JsonBytes")] JsonBytes),
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    Desc,
+    Asc,
+}
+
+/// A page of txs of an account.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountTransactions {
+    pub transactions: Vec<AccountTransaction>,
+    /// Cursor of the last tx, pass it as `after` for the next page, empty if
+    /// there are no more txs
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub last_cursor: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountTransaction {
+    #[schemars(with = "crate::schema::H256")]
+    pub tx_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub tx_index: Uint32,
+    /// The account is the sender, i.e. `from_id`
+    pub is_sender: bool,
+    /// The account is the receiver, i.e. `to_id`
+    pub is_receiver: bool,
+}
+
+/// A page of sUDT transfers of an address.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SudtTransfers {
+    pub transfers: Vec<SudtTransfer>,
+    /// Cursor of the last transfer, pass it as `after` for the next page,
+    /// empty if there are no more transfers
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub last_cursor: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SudtTransfer {
+    #[schemars(with = "crate::schema::H256")]
+    pub tx_hash: H256,
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub tx_index: Uint32,
+    /// Account id of the sUDT
+    #[schemars(with = "crate::schema::Uint32")]
+    pub sudt_id: Uint32,
+    /// Serialized registry address of the sender
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub from_address: JsonBytes,
+    /// Serialized registry address of the receiver
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub to_address: JsonBytes,
+    #[schemars(with = "crate::schema::U256")]
+    pub amount: gw_types::U256,
+}
+
+/// Status of a CKB RPC or indexer endpoint used by the node.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CKBEndpointStatus {
+    /// `ckb` or `indexer`
+    pub client: String,
+    pub url: String,
+    /// Whether requests are sent to this endpoint
+    pub active: bool,
+    pub healthy: bool,
+    /// Tip block number in the last health check
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub tip_number: Option<Uint64>,
+    /// Latency in milliseconds in the last health check
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub latency_ms: Option<Uint64>,
+    pub last_error: Option<String>,
+}
+
+/// Notification of the `newHeads` subscription.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct L2BlockHeaderView {
+    #[schemars(with = "crate::schema::H256")]
+    pub hash: H256,
+    pub raw: RawL2Block,
+}
+
+impl From<packed::L2Block> for L2BlockHeaderView {
+    fn from(block: packed::L2Block) -> Self {
+        Self {
+            hash: H256::from(block.hash()),
+            raw: block.raw().into(),
+        }
+    }
+}
+
+/// An EVM log, i.e. a polyjuice user log.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LogView {
+    #[schemars(with = "crate::schema::Uint64")]
+    pub block_number: Uint64,
+    #[schemars(with = "crate::schema::H256")]
+    pub block_hash: H256,
+    #[schemars(with = "crate::schema::H256")]
+    pub transaction_hash: H256,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub transaction_index: Uint32,
+    #[schemars(with = "crate::schema::Uint32")]
+    pub log_index: Uint32,
+    /// Registry address of the contract
+    #[schemars(with = "crate::schema::H160")]
+    pub address: H160,
+    #[schemars(with = "crate::schema::JsonBytes")]
+    pub data: JsonBytes,
+    #[schemars(with = "Vec<crate::schema::H256>")]
+    pub topics: Vec<H256>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            OneOrMany::One(v) => std::slice::from_ref(v),
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// Filter of `gw_get_logs` and `gw_new_filter`, like `eth_getLogs`. `null`
+/// topics match any topic.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LogFilterParams {
+    /// Defaults to the tip block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub from_block: Option<Uint64>,
+    /// Defaults to the tip block, at the time of polling for filters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<crate::schema::Uint64>")]
+    pub to_block: Option<Uint64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<OneOrMany<crate::schema::H160>>")]
+    pub address: Option<OneOrMany<H160>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Vec<Option<OneOrMany<crate::schema::H256>>>")]
+    pub topics: Vec<Option<OneOrMany<H256>>>,
+}
+
+/// Result of `gw_get_filter_changes`, block or tx hashes of block and pending
+/// tx filters, logs of log filters.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(untagged)]
+pub enum FilterChanges {
+    Hashes(#[schemars(with = "Vec<crate::schema::H256>")] Vec<H256>),
+    Logs(Vec<LogView>),
+}
@@ -4,4 +4,5 @@ pub mod godwoken;
 pub use ckb_jsonrpc_types;
 pub mod debug;
 pub mod debugger;
+pub mod schema;
 pub mod test_mode;
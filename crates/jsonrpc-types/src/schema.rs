@@ -0,0 +1,101 @@
+//! JSON schemas of types from ckb crates, which don't implement
+//! `JsonSchema`. Use them with `#[schemars(with = "...")]`.
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+
+fn hex_string(description: &str, pattern: &str) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_string()),
+            ..Default::default()
+        })),
+        string: Some(Box::new(StringValidation {
+            pattern: Some(pattern.to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+macro_rules! hex_schema {
+    ($name:ident, $description:expr, $pattern:expr) => {
+        #[doc = $description]
+        pub struct $name;
+
+        impl JsonSchema for $name {
+            fn schema_name() -> String {
+                stringify!($name).to_string()
+            }
+
+            fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+                hex_string($description, $pattern)
+            }
+        }
+    };
+}
+
+hex_schema!(
+    H160,
+    "20 bytes fixed-length binary encoded as a 0x-prefixed hex string.",
+    "^0x[0-9a-f]{40}$"
+);
+hex_schema!(
+    H256,
+    "32 bytes fixed-length binary encoded as a 0x-prefixed hex string.",
+    "^0x[0-9a-f]{64}$"
+);
+hex_schema!(
+    JsonBytes,
+    "Variable-length binary encoded as a 0x-prefixed hex string.",
+    "^0x([0-9a-f]{2})*$"
+);
+hex_schema!(
+    Uint32,
+    "32-bit unsigned integer encoded as a 0x-prefixed hex string without leading zeros.",
+    "^0x(0|[1-9a-f][0-9a-f]{0,7})$"
+);
+hex_schema!(
+    Uint64,
+    "64-bit unsigned integer encoded as a 0x-prefixed hex string without leading zeros.",
+    "^0x(0|[1-9a-f][0-9a-f]{0,15})$"
+);
+hex_schema!(
+    Uint128,
+    "128-bit unsigned integer encoded as a 0x-prefixed hex string without leading zeros.",
+    "^0x(0|[1-9a-f][0-9a-f]{0,31})$"
+);
+hex_schema!(
+    U256,
+    "256-bit unsigned integer encoded as a 0x-prefixed hex string without leading zeros.",
+    "^0x(0|[1-9a-f][0-9a-f]{0,63})$"
+);
+
+/// CKB JSON RPC types, see the [CKB RPC documentation](https://github.com/nervosnetwork/ckb/blob/develop/rpc/README.md).
+pub struct CkbObject;
+
+impl JsonSchema for CkbObject {
+    fn schema_name() -> String {
+        "CkbObject".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some(
+                    "A CKB JSON RPC object, see https://github.com/nervosnetwork/ckb/blob/develop/rpc/README.md"
+                        .to_string(),
+                ),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
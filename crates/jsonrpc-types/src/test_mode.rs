@@ -1,7 +1,8 @@
 use ckb_jsonrpc_types::{Uint32, Uint64};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ShouldProduceBlock {
     Yes,
@@ -9,7 +10,7 @@ pub enum ShouldProduceBlock {
     No,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Copy)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, Copy, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ChallengeType {
     TxExecution,
@@ -17,16 +18,19 @@ pub enum ChallengeType {
     WithdrawalSignature,
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TestModePayload {
     None,
     BadBlock {
+        #[schemars(with = "crate::schema::Uint32")]
         target_index: Uint32,
         target_type: ChallengeType,
     },
     Challenge {
+        #[schemars(with = "crate::schema::Uint64")]
         block_number: Uint64,
+        #[schemars(with = "crate::schema::Uint32")]
         target_index: Uint32,
         target_type: ChallengeType,
     },
@@ -6,6 +6,7 @@ use gw_common::{
     registry_address::RegistryAddress,
     state::State,
 };
+use gw_config::AccountCreatorConfig;
 use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
 use gw_types::{
     core::{AllowedEoaType, ScriptHashType},
@@ -80,11 +81,10 @@ pub struct AccountCreator {
     pub eth_lock_code_hash: H256,
     pub creator_script_hash: H256,
     pub creator_wallet: Wallet,
+    pub config: AccountCreatorConfig,
 }
 
 impl AccountCreator {
-    pub const MAX_CREATE_ACCOUNTS_PER_BATCH: usize = 50;
-
     pub fn create(rollup_context: &RollupContext, creator_wallet: Wallet) -> Result<Self> {
         let chain_id = rollup_context.rollup_config.chain_id().unpack();
         let rollup_script_hash = rollup_context.rollup_script_hash;
@@ -112,11 +112,23 @@ impl AccountCreator {
             eth_lock_code_hash,
             creator_script_hash,
             creator_wallet,
+            config: AccountCreatorConfig::default(),
         };
 
         Ok(creator)
     }
 
+    pub fn with_config(mut self, config: AccountCreatorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Whether the address matches a prefix of `deny_address_prefixes`.
+    pub fn is_denied(&self, address: &RegistryAddress) -> bool {
+        { self.config.deny_address_prefixes.iter() }
+            .any(|prefix| address.address.starts_with(prefix.as_bytes()))
+    }
+
     #[instrument(skip_all)]
     pub fn build_batch_create_tx<'a>(
         &'a self,
@@ -140,6 +152,10 @@ impl AccountCreator {
 
         let new_addrs: Vec<_> = {
             addresses.into_iter().filter_map(|addr| {
+                if self.is_denied(&addr) {
+                    tracing::info!("skip denied address {:?}", addr);
+                    return None;
+                }
                 match state.get_script_hash_by_registry_address(&addr) {
                     Ok(None) => Some(addr),
                     Ok(Some(_)) => None,
@@ -152,9 +168,8 @@ impl AccountCreator {
         }
         .collect();
 
-        let create_accounts = { new_addrs.iter() }
-            .take(Self::MAX_CREATE_ACCOUNTS_PER_BATCH)
-            .collect::<Vec<_>>();
+        let max_accounts = self.config.max_accounts_per_block;
+        let create_accounts = { new_addrs.iter() }.take(max_accounts).collect::<Vec<_>>();
         if create_accounts.is_empty() {
             return Ok(None);
         }
@@ -164,12 +179,16 @@ impl AccountCreator {
             .map(|a| self.to_account_script(a))
             .collect::<Vec<_>>();
         let next_batch = { new_addrs.into_iter() }
-            .skip(Self::MAX_CREATE_ACCOUNTS_PER_BATCH)
+            .skip(max_accounts)
             .collect::<Vec<_>>();
 
+        // Charged from the creator, i.e. the payer account.
+        let fee_amount = CKBCapacity::from_layer1(self.config.fee)
+            .to_layer2()
+            .as_u128();
         let fee = Fee::new_builder()
             .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
-            .amount(0u128.pack())
+            .amount(fee_amount.pack())
             .build();
         let batch_create = BatchCreateEthAccounts::new_builder()
             .fee(fee)
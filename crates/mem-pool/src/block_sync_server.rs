@@ -1,12 +1,13 @@
 //! P2P sync server for local/submitted/confirmed Blocks.
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet, VecDeque},
     sync::{Arc, Mutex},
 };
 
+use anyhow::{Context, Result};
 use bytes::Bytes;
-use futures::{StreamExt, TryStreamExt};
+use futures::TryStreamExt;
 use gw_config::SyncServerConfig;
 use gw_p2p_network::{FnSpawn, P2P_SYNC_PROTOCOL, P2P_SYNC_PROTOCOL_NAME};
 use gw_telemetry::traits::{OpenTelemetrySpanExt, TraceContextExt};
@@ -14,38 +15,115 @@ use gw_types::{
     h256::*,
     packed::{
         self, BlockSync, BlockSyncUnion, Confirmed, Found, L2Transaction, LocalBlock, NextMemBlock,
-        P2PSyncRequest, P2PSyncRequestReader, P2PSyncResponse, PushTransaction, Revert, Submitted,
-        TryAgain,
+        P2PSyncRequest, P2PSyncRequestReader, P2PSyncResponse, PushTransaction, RelayTransactions,
+        RelayTransactionsReader, Revert, SequencedBlockSync, Submitted, TryAgain,
     },
     prelude::*,
 };
 use gw_utils::compression::StreamEncoder;
-use tentacle::{builder::MetaBuilder, service::ProtocolMeta};
-use tokio::sync::broadcast::{channel, Receiver, Sender};
+use tentacle::{builder::MetaBuilder, secio::PeerId, service::ProtocolMeta};
+use tokio::sync::{
+    broadcast::{channel, Receiver, Sender},
+    mpsc,
+};
 
 #[derive(Default)]
 struct BlockMessages {
     hash: H256,
-    messages: Vec<BlockSync>,
+    messages: Vec<SequencedBlockSync>,
 }
 
 pub struct BlockSyncServerState {
     // Block number -> block hash and messages.
     buffer: BTreeMap<u64, BlockMessages>,
-    tx: Sender<BlockSync>,
+    tx: Sender<SequencedBlockSync>,
     buffer_capacity: u64,
+    // Random and non-zero, so that subscribers don't catch up with sequence
+    // numbers of another publisher, or of this one before restarting.
+    publisher_id: u64,
+    last_sequence: u64,
+    // Recent messages for reconnected subscribers to catch up with.
+    recent: VecDeque<SequencedBlockSync>,
+    catch_up_capacity: usize,
+    allowed_peer_ids: Option<HashSet<PeerId>>,
+    // Transactions relayed by subscribers are sent here.
+    relay_sender: Option<mpsc::Sender<L2Transaction>>,
 }
 
 impl BlockSyncServerState {
-    pub fn new(config: &SyncServerConfig) -> Self {
+    pub fn new(config: &SyncServerConfig) -> Result<Self> {
         let (tx, _) = channel(config.broadcast_channel_capacity);
-        Self {
+        let allowed_peer_ids = match config.allowed_peer_ids {
+            Some(ref allowed) => {
+                let mut allowed_peer_ids = HashSet::new();
+                for a in allowed {
+                    allowed_peer_ids.insert(
+                        a.parse()
+                            .with_context(|| format!("parse allowed peer id {}", a))?,
+                    );
+                }
+                Some(allowed_peer_ids)
+            }
+            None => None,
+        };
+        Ok(Self {
             buffer: Default::default(),
             tx,
             buffer_capacity: config.buffer_capacity,
+            publisher_id: rand::random::<u64>().max(1),
+            last_sequence: 0,
+            recent: VecDeque::with_capacity(config.catch_up_capacity),
+            catch_up_capacity: config.catch_up_capacity,
+            allowed_peer_ids,
+            relay_sender: None,
+        })
+    }
+
+    /// Accept transactions relayed by subscribers and send them to `sender`.
+    pub fn set_relay_sender(&mut self, sender: mpsc::Sender<L2Transaction>) {
+        self.relay_sender = Some(sender);
+    }
+
+    fn relay_transactions(&self, relay: RelayTransactions) {
+        let sender = match self.relay_sender {
+            Some(ref sender) => sender,
+            None => {
+                log::debug!("relayed transactions are not accepted, ignoring");
+                return;
+            }
+        };
+        for tx in relay.transactions() {
+            if let Err(mpsc::error::TrySendError::Full(tx)) = sender.try_send(tx) {
+                log::warn!(
+                    "relay channel is full, dropping tx 0x{}",
+                    hex::encode(tx.hash())
+                );
+            }
         }
     }
 
+    fn is_allowed(&self, peer_id: Option<&PeerId>) -> bool {
+        match self.allowed_peer_ids {
+            Some(ref allowed) => peer_id.map_or(false, |id| allowed.contains(id)),
+            None => true,
+        }
+    }
+
+    /// Assign the next sequence number and send to subscribers.
+    fn publish(&mut self, msg: BlockSync) -> SequencedBlockSync {
+        self.last_sequence += 1;
+        let msg = SequencedBlockSync::new_builder()
+            .sequence(self.last_sequence.pack())
+            .message(msg)
+            .build();
+        self.recent.push_back(msg.clone());
+        while self.recent.len() > self.catch_up_capacity {
+            self.recent.pop_front();
+        }
+        let _ = self.tx.send(msg.clone());
+        msg
+    }
+
     pub fn publish_local_block(&mut self, local_block: LocalBlock) {
         log::info!("publish local block");
         let reader = local_block.as_reader();
@@ -53,32 +131,32 @@ impl BlockSyncServerState {
         let number = raw.number().unpack();
         let hash = raw.hash();
         let msg = BlockSync::new_builder().set(local_block).build();
+        let msg = self.publish(msg);
         self.buffer.insert(
             number,
             BlockMessages {
                 hash,
-                messages: vec![msg.clone()],
+                messages: vec![msg],
             },
         );
-        let _ = self.tx.send(msg);
     }
 
     pub fn publish_submitted(&mut self, submitted: Submitted) {
         let number = submitted.as_reader().number_hash().number().unpack();
         let msg = BlockSync::new_builder().set(submitted).build();
+        let msg = self.publish(msg);
         if let Some(msgs) = self.buffer.get_mut(&number) {
-            msgs.messages.push(msg.clone());
+            msgs.messages.push(msg);
         }
-        let _ = self.tx.send(msg);
     }
 
     pub fn publish_confirmed(&mut self, confirmed: Confirmed) {
         let number = confirmed.number_hash().number().unpack();
         let msg = BlockSync::new_builder().set(confirmed).build();
+        let msg = self.publish(msg);
         if let Some(msgs) = self.buffer.get_mut(&number) {
-            msgs.messages.push(msg.clone());
+            msgs.messages.push(msg);
         }
-        let _ = self.tx.send(msg);
         // Remove messages for block number < number.saturating_sub(KEEP_BLOCKS).
         self.buffer = self
             .buffer
@@ -91,7 +169,7 @@ impl BlockSyncServerState {
         // Remove messages for reverted blocks.
         self.buffer.split_off(&(number + 1));
         let msg = BlockSync::new_builder().set(revert).build();
-        let _ = self.tx.send(msg);
+        self.publish(msg);
     }
 
     pub fn publish_transaction(&mut self, tx: L2Transaction) {
@@ -106,17 +184,17 @@ impl BlockSyncServerState {
             .transaction(tx)
             .build();
         let msg = BlockSync::new_builder().set(msg).build();
+        let msg = self.publish(msg);
         if let Some((_, messages)) = self.buffer.iter_mut().rev().next() {
             // The first message is either a LocalBlock or a NextMemBlock. We
             // only need to buffer it for NextMemBlock.
             if matches!(
-                messages.messages[0].to_enum(),
+                messages.messages[0].message().to_enum(),
                 BlockSyncUnion::NextMemBlock(_)
             ) {
-                messages.messages.push(msg.clone());
+                messages.messages.push(msg);
             }
         }
-        let _ = self.tx.send(msg);
     }
 
     pub fn publish_next_mem_block(&mut self, mem_block: NextMemBlock) {
@@ -124,21 +202,41 @@ impl BlockSyncServerState {
         let number = mem_block.block_info().number().unpack();
 
         let msg = BlockSync::new_builder().set(mem_block).build();
+        let msg = self.publish(msg);
         self.buffer.insert(
             number,
             BlockMessages {
                 hash: [0; 32],
-                messages: vec![msg.clone()],
+                messages: vec![msg],
             },
         );
-
-        let _ = self.tx.send(msg);
     }
 
+    /// Messages after the requested sequence number if they are still in
+    /// `recent`, or messages of blocks after the requested block.
     fn get_and_subscribe(
         &self,
         after: P2PSyncRequest,
-    ) -> Result<(Vec<BlockSync>, Receiver<BlockSync>), TryAgain> {
+    ) -> Result<(Found, Vec<SequencedBlockSync>, Receiver<SequencedBlockSync>), TryAgain> {
+        let found = Found::new_builder()
+            .publisher_id(self.publisher_id.pack())
+            .build();
+        let publisher_id: u64 = after.publisher_id().unpack();
+        let last_sequence: u64 = after.last_sequence().unpack();
+        let first_sequence = self.last_sequence + 1 - self.recent.len() as u64;
+        if publisher_id == self.publisher_id
+            && (first_sequence.saturating_sub(1)..=self.last_sequence).contains(&last_sequence)
+        {
+            log::info!(
+                "catch up from sequence {} to {}",
+                last_sequence,
+                self.last_sequence
+            );
+            let skip = (last_sequence + 1 - first_sequence) as usize;
+            let msgs = self.recent.iter().skip(skip).cloned().collect();
+            return Ok((found, msgs, self.tx.subscribe()));
+        }
+
         let number = after.block_number().unpack();
         if let Some(msgs) = self.buffer.get(&number) {
             if msgs.hash.as_slice() == after.block_hash().as_slice() {
@@ -147,7 +245,7 @@ impl BlockSyncServerState {
                     .range(number + 1..)
                     .flat_map(|(_, msgs)| msgs.messages.iter().cloned())
                     .collect();
-                return Ok((msgs, self.tx.subscribe()));
+                return Ok((found, msgs, self.tx.subscribe()));
             }
         }
         Err(TryAgain::default())
@@ -159,7 +257,14 @@ pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -
         let publisher = publisher.clone();
         let control = control.clone();
         let session_id = context.id;
+        let peer_id = context.remote_pubkey.as_ref().map(|key| key.peer_id());
         tokio::spawn(async move {
+            let allowed = publisher.lock().unwrap().is_allowed(peer_id.as_ref());
+            if !allowed {
+                log::warn!("subscriber not allowed, closing. session: {}", session_id);
+                let _ = control.disconnect(session_id).await;
+                return anyhow::Ok(());
+            }
             // Compress messages.
             //
             // We keep using the same compression context in one session. This
@@ -177,16 +282,24 @@ pub fn block_sync_server_protocol(publisher: Arc<Mutex<BlockSyncServerState>>) -
                 };
                 let result = publisher.lock().unwrap().get_and_subscribe(request);
                 match result {
-                    Ok((msgs, mut receiver)) => {
-                        let response = P2PSyncResponse::new_builder().set(Found::default()).build();
+                    Ok((found, msgs, mut receiver)) => {
+                        let response = P2PSyncResponse::new_builder().set(found).build();
                         send(response.as_bytes()).await?;
                         for msg in msgs {
                             send(msg.as_bytes()).await?;
                         }
                         loop {
                             let result = tokio::select! {
-                                // We don't expect more messages from the peer.
-                                _ = read_part.next() => break 'outer,
+                                // The peer only sends relayed transactions now.
+                                msg = read_part.try_next() => match msg? {
+                                    Some(msg) => {
+                                        RelayTransactionsReader::from_slice(msg.as_ref())?;
+                                        let relay = RelayTransactions::new_unchecked(msg);
+                                        publisher.lock().unwrap().relay_transactions(relay);
+                                        continue;
+                                    }
+                                    None => break 'outer,
+                                },
                                 result = receiver.recv() => result,
                             };
                             match result {
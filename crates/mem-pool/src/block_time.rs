@@ -0,0 +1,106 @@
+//! Timestamp of the next mem block.
+//!
+//! Block timestamps must be strictly increasing, and lower than the `since`
+//! of the submission tx, which can only be committed once the L1 median time
+//! passes it. So the L1 median time is the anchor of mem block timestamps, the
+//! local clock is only used to extrapolate the last anchor if estimating
+//! fails.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use gw_metrics::mem_pool::TimestampCorrection;
+
+/// Step of timestamps bumped from the tip block timestamp.
+const BUMP_STEP: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct BlockTime {
+    /// The last estimated timestamp and when it was estimated.
+    anchor: Option<(Duration, Instant)>,
+}
+
+impl BlockTime {
+    /// Timestamp of the next block after `tip_timestamp`, `estimated` by the
+    /// mem pool provider.
+    pub fn next_timestamp(
+        &mut self,
+        tip_timestamp: Duration,
+        estimated: Result<Duration>,
+    ) -> Duration {
+        let now = Instant::now();
+        let anchor = match estimated {
+            Ok(estimated) => {
+                self.anchor = Some((estimated, now));
+                Some(estimated)
+            }
+            Err(err) => {
+                let extrapolated = { self.anchor }
+                    .map(|(anchor, at)| anchor + now.saturating_duration_since(at))
+                    .filter(|t| *t > tip_timestamp);
+                log::warn!(
+                    "[mem-pool] estimate next block time: {}, extrapolated: {:?}",
+                    err,
+                    extrapolated
+                );
+                if extrapolated.is_some() {
+                    correction(TimestampCorrection::Extrapolated);
+                }
+                extrapolated
+            }
+        };
+        match anchor {
+            Some(anchor) if anchor > tip_timestamp => anchor,
+            _ => {
+                let timestamp = tip_timestamp + BUMP_STEP;
+                log::warn!(
+                    "[mem-pool] next block time {:?} is not after the tip {}ms, use {}ms, the block is committed after L1 catches up",
+                    anchor.map(|t| t.as_millis()),
+                    tip_timestamp.as_millis(),
+                    timestamp.as_millis()
+                );
+                correction(TimestampCorrection::BumpedFromTip);
+                timestamp
+            }
+        }
+    }
+}
+
+fn correction(reason: TimestampCorrection) {
+    gw_metrics::mem_pool()
+        .block_timestamp_corrections(reason)
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn test_next_timestamp() {
+        let secs = Duration::from_secs;
+        let mut block_time = BlockTime::default();
+
+        // No anchor yet.
+        let next = block_time.next_timestamp(secs(100), Err(anyhow!("rpc error")));
+        assert_eq!(next, secs(101));
+
+        assert_eq!(
+            block_time.next_timestamp(secs(100), Ok(secs(110))),
+            secs(110)
+        );
+        // Not increasing.
+        assert_eq!(
+            block_time.next_timestamp(secs(110), Ok(secs(110))),
+            secs(111)
+        );
+
+        // Extrapolated from the last anchor.
+        let next = block_time.next_timestamp(secs(105), Err(anyhow!("rpc error")));
+        assert!(next >= secs(110));
+        let next = block_time.next_timestamp(secs(200), Err(anyhow!("rpc error")));
+        assert_eq!(next, secs(201));
+    }
+}
@@ -0,0 +1,118 @@
+//! Local clock skew check against NTP servers, configured by
+//! `mem_pool.clock_skew_check`.
+//!
+//! Mem block timestamps are anchored to the L1 median time, but the local
+//! clock still matters, e.g. to extrapolate timestamps and to judge
+//! timeouts, so operators are alerted if it drifts.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+use gw_config::ClockSkewCheckConfig;
+use tokio::net::UdpSocket;
+
+const NTP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Seconds from 1900-01-01, the NTP epoch, to the unix epoch.
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+/// Check the clock skew every `interval_secs`, forever.
+pub async fn check_clock_skew(config: ClockSkewCheckConfig) {
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+    loop {
+        match query_clock_offset(&config.ntp_servers).await {
+            Ok(offset_ms) => {
+                let skew_ms = offset_ms.unsigned_abs();
+                gw_metrics::mem_pool().clock_skew_ms.set(skew_ms);
+                if skew_ms > config.max_skew_ms {
+                    log::warn!(
+                        "[clock] local clock is off by {}ms, more than {}ms, check NTP sync",
+                        offset_ms,
+                        config.max_skew_ms
+                    );
+                } else {
+                    log::debug!("[clock] local clock offset {}ms", offset_ms);
+                }
+            }
+            Err(err) => log::warn!("[clock] query NTP servers: {:#}", err),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Offset of NTP time from the local clock in milliseconds, from the first
+/// server that responds.
+async fn query_clock_offset(servers: &[String]) -> Result<i64> {
+    let mut last_err = None;
+    for server in servers {
+        match tokio::time::timeout(NTP_TIMEOUT, sntp_offset(server)).await {
+            Ok(Ok(offset)) => return Ok(offset),
+            Ok(Err(err)) => last_err = Some(err.context(server.clone())),
+            Err(_) => last_err = Some(anyhow::anyhow!("{}: timeout", server)),
+        }
+    }
+    match last_err {
+        Some(err) => Err(err),
+        None => bail!("no NTP servers"),
+    }
+}
+
+/// SNTP, RFC 4330.
+async fn sntp_offset(server: &str) -> Result<i64> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+    let mut request = [0u8; 48];
+    // Leap indicator 0, version 4, mode 3, i.e. client.
+    request[0] = 0x23;
+    let sent = unix_millis(SystemTime::now());
+    socket.send(&request).await?;
+
+    let mut response = [0u8; 48];
+    let len = socket.recv(&mut response).await?;
+    let received = unix_millis(SystemTime::now());
+    if len < response.len() {
+        bail!("invalid NTP response of {} bytes", len);
+    }
+    let server_received = ntp_timestamp_millis(&response[32..40]);
+    let server_sent = ntp_timestamp_millis(&response[40..48]);
+    Ok(clock_offset(sent, server_received, server_sent, received))
+}
+
+fn clock_offset(sent: i64, server_received: i64, server_sent: i64, received: i64) -> i64 {
+    ((server_received - sent) + (server_sent - received)) / 2
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as i64,
+        Err(err) => -(err.duration().as_millis() as i64),
+    }
+}
+
+fn ntp_timestamp_millis(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64;
+    let millis = (fraction * 1000) >> 32;
+    (secs as i64 - NTP_UNIX_OFFSET_SECS as i64) * 1000 + millis as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntp_timestamp_millis() {
+        // 2022-01-01T00:00:00.5Z
+        let secs = (1_640_995_200 + NTP_UNIX_OFFSET_SECS) as u32;
+        let mut bytes = secs.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&(1u32 << 31).to_be_bytes());
+        assert_eq!(ntp_timestamp_millis(&bytes), 1_640_995_200_500);
+    }
+
+    #[test]
+    fn test_clock_offset() {
+        // The server is 1000ms ahead, with 100ms round trip.
+        assert_eq!(clock_offset(0, 1050, 1050, 100), 1000);
+        // The server is 450ms behind.
+        assert_eq!(clock_offset(1000, 550, 560, 1010), -450);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, time::Instant};
+use std::{cmp::Reverse, collections::HashSet, convert::TryInto, time::Instant};
 
 use anyhow::{anyhow, bail, Result};
 use gw_common::CKB_SUDT_SCRIPT_ARGS;
@@ -178,6 +178,143 @@ pub fn generate_finalized_custodian(
     (output, data)
 }
 
+/// Pick custodian cells that cover `withdrawals_amount` and their changes with
+/// as few cells as possible: sUDT cells of withdrawn sUDTs first, then CKB
+/// cells, each largest first.
+///
+/// Returns picked cells and the rest cells.
+pub fn pick_custodians(
+    rollup_context: &RollupContext,
+    cells: Vec<CellInfo>,
+    withdrawals_amount: &WithdrawalsAmount,
+) -> Result<(Vec<CellInfo>, Vec<CellInfo>)> {
+    let mut remaining = cells;
+    let mut picked_cells = Vec::new();
+    // Custodian changes are paid by the picked capacity too.
+    let mut required_capacity =
+        withdrawals_amount.capacity + calc_ckb_custodian_min_capacity(rollup_context) as u128;
+
+    let mut sudt_withdrawals: Vec<_> = withdrawals_amount.sudt.iter().collect();
+    sudt_withdrawals.sort();
+    for (sudt_type_hash, &required_amount) in sudt_withdrawals {
+        let (cells, rest) = remaining
+            .into_iter()
+            .partition(|cell| sudt_type_hash_of(cell).as_ref() == Some(sudt_type_hash));
+        remaining = rest;
+
+        let (picked, amount, mut unpicked) =
+            pick_largest_first(cells, required_amount, custodian_sudt_amount);
+        if amount < required_amount {
+            bail!(
+                "finalized sudt custodians not enough, {} < {}",
+                amount,
+                required_amount
+            );
+        }
+        if amount > required_amount {
+            if let Some(type_script) = picked.first().and_then(|c| c.output.type_().to_opt()) {
+                let (change, _data) = generate_finalized_custodian(
+                    rollup_context,
+                    amount - required_amount,
+                    type_script,
+                );
+                let change_capacity: u64 = change.capacity().unpack();
+                required_capacity += change_capacity as u128;
+            }
+        }
+        required_capacity =
+            required_capacity.saturating_sub(picked.iter().map(custodian_capacity).sum::<u128>());
+        picked_cells.extend(picked);
+        remaining.append(&mut unpicked);
+    }
+
+    let (ckb_cells, mut rest): (Vec<_>, Vec<_>) = remaining
+        .into_iter()
+        .partition(|cell| cell.output.type_().is_none());
+    let (picked, capacity, mut unpicked) =
+        pick_largest_first(ckb_cells, required_capacity, custodian_capacity);
+    if capacity < required_capacity {
+        bail!(
+            "finalized ckb custodians not enough, {} < {}",
+            capacity,
+            required_capacity
+        );
+    }
+    picked_cells.extend(picked);
+    rest.append(&mut unpicked);
+
+    Ok((picked_cells, rest))
+}
+
+/// Pick cells largest first until `required` is covered. The last picked cell
+/// is replaced with the smallest unpicked cell that still covers `required`,
+/// to minimize the change.
+///
+/// Returns picked cells, the picked amount and unpicked cells.
+fn pick_largest_first(
+    mut cells: Vec<CellInfo>,
+    required: u128,
+    amount_of: fn(&CellInfo) -> u128,
+) -> (Vec<CellInfo>, u128, Vec<CellInfo>) {
+    cells.sort_by_key(|cell| Reverse(amount_of(cell)));
+    let mut picked = Vec::new();
+    let mut amount = 0u128;
+    let mut cells = cells.into_iter();
+    for cell in cells.by_ref() {
+        if amount >= required {
+            let mut unpicked = vec![cell];
+            unpicked.extend(cells);
+            return minimize_change(picked, amount, unpicked, required, amount_of);
+        }
+        amount += amount_of(&cell);
+        picked.push(cell);
+    }
+    (picked, amount, Vec::new())
+}
+
+fn minimize_change(
+    mut picked: Vec<CellInfo>,
+    mut amount: u128,
+    mut unpicked: Vec<CellInfo>,
+    required: u128,
+    amount_of: fn(&CellInfo) -> u128,
+) -> (Vec<CellInfo>, u128, Vec<CellInfo>) {
+    let last = match picked.last() {
+        Some(last) => amount_of(last),
+        None => return (picked, amount, unpicked),
+    };
+    let shortfall = required - (amount - last);
+    // `unpicked` is sorted in descending order
+    let replacement = unpicked
+        .iter()
+        .rposition(|cell| amount_of(cell) >= shortfall)
+        .filter(|&idx| amount_of(&unpicked[idx]) < last);
+    if let Some(idx) = replacement {
+        let cell = unpicked.remove(idx);
+        amount = amount - last + amount_of(&cell);
+        let last = picked.pop().expect("last picked");
+        picked.push(cell);
+        unpicked.push(last);
+    }
+    (picked, amount, unpicked)
+}
+
+pub fn custodian_capacity(cell: &CellInfo) -> u128 {
+    let capacity: u64 = cell.output.capacity().unpack();
+    capacity as u128
+}
+
+pub fn custodian_sudt_amount(cell: &CellInfo) -> u128 {
+    match cell.data.get(..16) {
+        Some(amount) => u128::from_le_bytes(amount.try_into().expect("16 bytes")),
+        None => 0,
+    }
+}
+
+fn sudt_type_hash_of(cell: &CellInfo) -> Option<[u8; 32]> {
+    cell.output.type_().to_opt().map(|script| script.hash())
+}
+
 #[instrument(skip_all, fields(withdrawals_amount = ?withdrawals_amount))]
 fn sum_change_capacity(
     db: &impl ChainStore,
@@ -250,7 +387,7 @@ async fn query_finalized_custodian_cells(
 
     let mut collected = CollectedCustodianCells::default();
     let mut collected_fullfilled_sudt = HashSet::new();
-    let mut cursor = CollectLocalAndIndexerCursor::Local;
+    let mut cursor = CollectLocalAndIndexerCursor::LocalThenCached;
 
     // withdrawal ckb + change custodian capacity
     let required_capacity = {
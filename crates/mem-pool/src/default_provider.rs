@@ -4,13 +4,18 @@ use anyhow::{bail, Result};
 use async_trait::async_trait;
 use gw_config::MemBlockConfig;
 use gw_rpc_client::rpc_client::RPCClient;
-use gw_store::{traits::chain_store::ChainStore, Store};
-use gw_types::{offchain::DepositInfo, prelude::*};
-use gw_utils::local_cells::LocalCellsManager;
+use gw_store::{deposit_status::DepositStatus, traits::chain_store::ChainStore, Store};
+use gw_types::{
+    offchain::{CellInfo, CompatibleFinalizedTimepoint, DepositInfo},
+    packed::WithdrawalRequest,
+    prelude::*,
+};
+use gw_utils::{local_cells::LocalCellsManager, RollupContext};
 use tracing::instrument;
 
 use crate::{
     constants::{MIN_CKB_DEPOSIT_CAPACITY, MIN_SUDT_DEPOSIT_CAPACITY},
+    deposit_watcher::DepositWatcher,
     traits::MemPoolProvider,
 };
 
@@ -19,6 +24,7 @@ pub struct DefaultMemPoolProvider {
     rpc_client: RPCClient,
     store: Store,
     mem_block_config: MemBlockConfig,
+    deposit_watcher: Option<DepositWatcher>,
 }
 
 impl DefaultMemPoolProvider {
@@ -27,8 +33,16 @@ impl DefaultMemPoolProvider {
             rpc_client,
             store,
             mem_block_config,
+            deposit_watcher: None,
         }
     }
+
+    /// Collect deposit cells cached by the watcher, falling back to querying
+    /// the indexer if the cache is not ready or outdated.
+    pub fn with_deposit_watcher(mut self, deposit_watcher: DepositWatcher) -> Self {
+        self.deposit_watcher = Some(deposit_watcher);
+        self
+    }
 }
 
 #[async_trait]
@@ -77,16 +91,53 @@ impl MemPoolProvider for DefaultMemPoolProvider {
         &self,
         local_cells_manager: &LocalCellsManager,
     ) -> Result<Vec<DepositInfo>> {
+        if let Some(ref deposit_watcher) = self.deposit_watcher {
+            // Cached cells may be consumed by confirmed blocks.
+            let deposits = deposit_watcher.collect_deposit_cells(|info| {
+                let out_point = &info.cell.out_point;
+                !local_cells_manager.is_dead(out_point)
+                    && !matches!(
+                        self.store.get_deposit_status(out_point),
+                        Some(DepositStatus::Credited { .. })
+                    )
+            });
+            match deposits {
+                Some(deposits) => return Ok(deposits),
+                None => log::debug!("[deposit watcher] cache not ready, query deposit cells"),
+            }
+        }
+
         self.rpc_client
             .query_deposit_cells(
                 self.mem_block_config.max_deposits,
                 self.mem_block_config
                     .deposit_timeout_config
                     .deposit_minimal_blocks,
-                MIN_CKB_DEPOSIT_CAPACITY,
+                MIN_CKB_DEPOSIT_CAPACITY.max(self.mem_block_config.deposit_filter.min_ckb_capacity),
                 MIN_SUDT_DEPOSIT_CAPACITY,
                 local_cells_manager.dead_cells(),
             )
             .await
     }
+
+    #[instrument(skip_all, fields(withdrawals = withdrawals.len()))]
+    async fn query_finalized_custodians(
+        &self,
+        rollup_context: &RollupContext,
+        withdrawals: Vec<WithdrawalRequest>,
+        compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+        local_cells_manager: &LocalCellsManager,
+    ) -> Result<Option<Vec<CellInfo>>> {
+        let custodians = crate::custodian::query_finalized_custodians(
+            &self.rpc_client,
+            &self.store.get_snapshot(),
+            withdrawals.into_iter(),
+            rollup_context,
+            compatible_finalized_timepoint,
+            local_cells_manager,
+        )
+        .await?
+        .expect_any();
+        Ok(Some(custodians.cells_info))
+    }
 }
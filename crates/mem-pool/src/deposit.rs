@@ -1,7 +1,9 @@
+use std::collections::{HashMap, HashSet};
+
 use anyhow::{anyhow, Result};
 use gw_common::{registry::context::RegistryContext, state::State};
-use gw_config::DepositTimeoutConfig;
-use gw_store::state::MemStateDB;
+use gw_config::{DepositFilterConfig, DepositTimeoutConfig, MemBlockConfig};
+use gw_store::{deposit_status::DepositStatus, state::MemStateDB, traits::chain_store::ChainStore};
 use gw_types::core::Timepoint;
 use gw_types::{
     bytes::Bytes, core::ScriptHashType, h256::*, offchain::DepositInfo, packed::DepositLockArgs,
@@ -12,29 +14,100 @@ use gw_utils::RollupContext;
 
 use crate::custodian::to_custodian_cell;
 
-/// check and reject invalid deposit cells
+/// check and reject invalid deposit cells, and deposits not allowed by the
+/// deposit filter
 pub fn sanitize_deposit_cells(
     ctx: &RollupContext,
-    config: &DepositTimeoutConfig,
+    config: &MemBlockConfig,
     unsanitize_deposits: Vec<DepositInfo>,
     state: &MemStateDB,
 ) -> Vec<DepositInfo> {
     log::debug!(target: "collect-deposit-cells", "sanitize {} deposits", unsanitize_deposits.len());
+    let filter = &config.deposit_filter;
+    let mut account_deposits: HashMap<H256, usize> = HashMap::new();
     let mut deposit_cells = Vec::with_capacity(unsanitize_deposits.len());
     for cell in unsanitize_deposits {
         // check deposit lock
         // the lock should be correct unless the upstream ckb-indexer has bugs
-        if let Err(err) = check_deposit_cell(ctx, config, &cell, state) {
+        if let Err(err) = check_deposit_cell(ctx, &config.deposit_timeout_config, &cell, state) {
             log::debug!(target: "collect-deposit-cells", "invalid deposit cell: {}", err);
             continue;
         }
+        if let Err(err) = check_deposit_filter(filter, &cell) {
+            log::debug!(target: "collect-deposit-cells", "filtered deposit cell: {}", err);
+            continue;
+        }
+        if filter.max_deposits_per_account > 0 {
+            let count = account_deposits
+                .entry(cell.request.script().hash())
+                .or_default();
+            if *count >= filter.max_deposits_per_account {
+                log::debug!(target: "collect-deposit-cells", "filtered deposit cell: exceeded max deposits per account");
+                continue;
+            }
+            *count += 1;
+        }
         deposit_cells.push(cell);
+        if deposit_cells.len() >= config.max_deposits {
+            break;
+        }
     }
     log::debug!(target: "collect-deposit-cells", "return {} sanitized deposits", deposit_cells.len());
     deposit_cells
 }
 
-/// we only package deposit cells with valid cancel timeout, to prevent conflict with user's unlock
+/// Drop deposit cells that are duplicated or already credited by blocks, the
+/// upstream indexer should never return them.
+pub fn dedup_deposit_cells(db: &impl ChainStore, cells: Vec<DepositInfo>) -> Vec<DepositInfo> {
+    let mut out_points = HashSet::with_capacity(cells.len());
+    { cells.into_iter() }
+        .filter(|cell| {
+            let out_point = &cell.cell.out_point;
+            if !out_points.insert(out_point.clone()) {
+                log::warn!("[mem-pool] drop duplicated deposit cell {}", out_point);
+                return false;
+            }
+            if let Some(DepositStatus::Credited { block_number }) = db.get_deposit_status(out_point)
+            {
+                log::warn!(
+                    "[mem-pool] drop deposit cell {} credited by block #{}",
+                    out_point,
+                    block_number
+                );
+                return false;
+            }
+            true
+        })
+        .collect()
+}
+
+pub(crate) fn check_deposit_filter(filter: &DepositFilterConfig, cell: &DepositInfo) -> Result<()> {
+    let capacity: u64 = cell.cell.output.capacity().unpack();
+    if capacity < filter.min_ckb_capacity {
+        return Err(anyhow!(
+            "deposit capacity {} is less than min_ckb_capacity {}",
+            capacity,
+            filter.min_ckb_capacity
+        ));
+    }
+
+    if cell.cell.output.type_().is_some() && !filter.allowed_sudt_script_hashes.is_empty() {
+        let sudt_script_hash: H256 = cell.request.sudt_script_hash().unpack();
+        if !filter
+            .allowed_sudt_script_hashes
+            .iter()
+            .any(|h| h.0 == sudt_script_hash)
+        {
+            return Err(anyhow!(
+                "sUDT {} is not in allowed_sudt_script_hashes",
+                hex::encode(sudt_script_hash.as_slice())
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 fn check_deposit_cell_cancel_timeout(
     config: &DepositTimeoutConfig,
     deposit_args: &DepositLockArgs,
@@ -236,3 +309,38 @@ fn check_deposit_cell(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use gw_store::Store;
+    use gw_types::{offchain::CellInfo, packed::OutPoint};
+
+    use super::*;
+
+    #[test]
+    fn test_dedup_deposit_cells() {
+        let deposit = |index: u32| DepositInfo {
+            cell: CellInfo {
+                out_point: OutPoint::new_builder().index(index.pack()).build(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let store = Store::open_tmp().unwrap();
+        let mut db = store.begin_transaction();
+        let credited = DepositStatus::Credited { block_number: 1 };
+        db.set_deposit_status(&deposit(0).cell.out_point, credited)
+            .unwrap();
+        db.set_deposit_status(&deposit(1).cell.out_point, DepositStatus::Collected)
+            .unwrap();
+
+        let cells = vec![deposit(0), deposit(1), deposit(2), deposit(1)];
+        let out_points: Vec<_> = { dedup_deposit_cells(&db, cells).into_iter() }
+            .map(|d| d.cell.out_point)
+            .collect();
+        assert_eq!(
+            out_points,
+            vec![deposit(1).cell.out_point, deposit(2).cell.out_point]
+        );
+    }
+}
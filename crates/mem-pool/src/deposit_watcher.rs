@@ -0,0 +1,237 @@
+//! Background collection of deposit cells.
+//!
+//! Deposit cells of recent blocks are scanned once, then only cells of new
+//! blocks are queried every `poll_interval_secs`. Cells are parsed and checked
+//! by the deposit filter before being cached, so mem block resets don't query
+//! the indexer. Consumed cells are detected by comparing the total capacity of
+//! scanned cells with `get_cells_capacity`, and cells are rescanned on
+//! mismatch, the same as `gw_rpc_client::indexer_cache`.
+
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use gw_config::{DepositWatcherConfig, MemBlockConfig};
+use gw_generator::ArcSwapOption;
+use gw_rpc_client::{
+    indexer_types::{Order, SearchKey, SearchKeyFilter},
+    rpc_client::{parse_deposit_cell, RPCClient, DEPOSIT_BLOCKS_TO_SEARCH},
+};
+use gw_types::{offchain::DepositInfo, prelude::*};
+use tracing::instrument;
+
+use crate::{
+    constants::{MIN_CKB_DEPOSIT_CAPACITY, MIN_SUDT_DEPOSIT_CAPACITY},
+    deposit::check_deposit_filter,
+};
+
+/// Cached deposits are not used if not updated in this many polls.
+const OUTDATED_POLLS: u32 = 10;
+
+/// (block number, tx index, output index)
+type CellKey = (u64, u32, u32);
+
+struct Snapshot {
+    /// Indexer tip of the last scan or update.
+    tip_number: u64,
+    /// Deposits with their block numbers, in ascending order of `CellKey`.
+    deposits: Arc<Vec<(u64, DepositInfo)>>,
+    updated_at: Instant,
+}
+
+struct Scanned {
+    search_key: SearchKey,
+    /// `None` for invalid or filtered deposit cells.
+    cells: BTreeMap<CellKey, Option<DepositInfo>>,
+    /// Total capacity of `cells`.
+    capacity: u64,
+    deposits: Arc<Vec<(u64, DepositInfo)>>,
+    tip_number: u64,
+    scanned_at: Instant,
+}
+
+impl Scanned {
+    fn update_deposits(&mut self) {
+        let deposits = { self.cells.iter() }
+            .filter_map(|((number, _, _), info)| Some((*number, info.clone()?)))
+            .collect();
+        self.deposits = Arc::new(deposits);
+    }
+}
+
+#[derive(Clone)]
+pub struct DepositWatcher {
+    rpc_client: RPCClient,
+    mem_block_config: Arc<MemBlockConfig>,
+    config: DepositWatcherConfig,
+    snapshot: Arc<ArcSwapOption<Snapshot>>,
+}
+
+impl DepositWatcher {
+    pub fn new(
+        rpc_client: RPCClient,
+        mem_block_config: MemBlockConfig,
+        config: DepositWatcherConfig,
+    ) -> Self {
+        DepositWatcher {
+            rpc_client,
+            mem_block_config: Arc::new(mem_block_config),
+            config,
+            snapshot: Default::default(),
+        }
+    }
+
+    /// Cached deposits of the same blocks as `RPCClient::query_deposit_cells`,
+    /// at most `max_deposits`. Returns `None` if the cache is not ready or
+    /// outdated.
+    pub fn collect_deposit_cells(
+        &self,
+        mut is_live: impl FnMut(&DepositInfo) -> bool,
+    ) -> Option<Vec<DepositInfo>> {
+        let snapshot = self.snapshot.load_full()?;
+        let outdated = Duration::from_secs(self.config.poll_interval_secs) * OUTDATED_POLLS;
+        if snapshot.updated_at.elapsed() > outdated {
+            return None;
+        }
+
+        let config = &self.mem_block_config;
+        let deposit_minimal_blocks = config.deposit_timeout_config.deposit_minimal_blocks;
+        let from_block = snapshot.tip_number.saturating_sub(DEPOSIT_BLOCKS_TO_SEARCH);
+        let to_block = snapshot.tip_number.saturating_sub(deposit_minimal_blocks);
+        let deposits = { snapshot.deposits.iter() }
+            .filter(|(number, info)| (from_block..=to_block).contains(number) && is_live(info))
+            .map(|(_, info)| info.clone())
+            .take(config.max_deposits)
+            .collect();
+        Some(deposits)
+    }
+
+    /// Poll every `poll_interval_secs`.
+    pub fn spawn_poll(&self) {
+        let watcher = self.clone();
+        let interval = Duration::from_secs(self.config.poll_interval_secs);
+        tokio::spawn(async move {
+            let mut scanned = None;
+            loop {
+                match watcher.poll(scanned.take()).await {
+                    Ok(s) => scanned = Some(s),
+                    Err(err) => log::warn!("[deposit watcher] poll error {:#}", err),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    #[instrument(skip_all)]
+    async fn poll(&self, scanned: Option<Scanned>) -> Result<Scanned> {
+        let full_rescan = Duration::from_secs(self.config.full_rescan_secs);
+        let scanned = match scanned {
+            Some(scanned) if scanned.scanned_at.elapsed() < full_rescan => {
+                self.update(scanned).await?
+            }
+            _ => None,
+        };
+        let scanned = match scanned {
+            Some(scanned) => scanned,
+            None => self.scan().await?,
+        };
+        self.snapshot.store(Some(Arc::new(Snapshot {
+            tip_number: scanned.tip_number,
+            deposits: Arc::clone(&scanned.deposits),
+            updated_at: Instant::now(),
+        })));
+        Ok(scanned)
+    }
+
+    async fn scan(&self) -> Result<Scanned> {
+        let tip_number: u64 = self.rpc_client.indexer.get_tip().await?.number().unpack();
+        let from_block = tip_number.saturating_sub(DEPOSIT_BLOCKS_TO_SEARCH);
+        let min_ckb_deposit_capacity =
+            MIN_CKB_DEPOSIT_CAPACITY.max(self.mem_block_config.deposit_filter.min_ckb_capacity);
+        let mut scanned = Scanned {
+            search_key: self
+                .rpc_client
+                .deposit_search_key(from_block, min_ckb_deposit_capacity),
+            cells: BTreeMap::new(),
+            capacity: 0,
+            deposits: Default::default(),
+            tip_number,
+            scanned_at: Instant::now(),
+        };
+        self.query_new_cells(&mut scanned, from_block).await?;
+        scanned.update_deposits();
+        log::debug!(
+            "[deposit watcher] scan {} cells, {} deposits",
+            scanned.cells.len(),
+            scanned.deposits.len()
+        );
+        Ok(scanned)
+    }
+
+    /// Update with cells of new blocks, `None` if cells were consumed.
+    async fn update(&self, mut scanned: Scanned) -> Result<Option<Scanned>> {
+        let tip_number: u64 = self.rpc_client.indexer.get_tip().await?.number().unpack();
+        if tip_number == scanned.tip_number {
+            return Ok(Some(scanned));
+        }
+
+        let from_block = scanned.tip_number + 1;
+        let new_cells = self.query_new_cells(&mut scanned, from_block).await?;
+        let indexer = &self.rpc_client.indexer;
+        let capacity = indexer.get_cells_capacity(&scanned.search_key).await?;
+        if capacity != scanned.capacity {
+            log::debug!("[deposit watcher] cells consumed, rescan");
+            return Ok(None);
+        }
+        if new_cells > 0 {
+            scanned.update_deposits();
+        }
+        scanned.tip_number = tip_number;
+        Ok(Some(scanned))
+    }
+
+    /// Query cells from `from_block`, returns the number of new cells.
+    async fn query_new_cells(&self, scanned: &mut Scanned, from_block: u64) -> Result<usize> {
+        let search_key = SearchKey {
+            filter: Some(SearchKeyFilter {
+                block_range: Some([from_block.into(), u64::MAX.into()]),
+                ..scanned.search_key.filter.clone().unwrap_or_default()
+            }),
+            ..scanned.search_key.clone()
+        };
+        let indexer = &self.rpc_client.indexer;
+        let mut new_cells = 0;
+        let mut cursor = None;
+        loop {
+            let page = indexer
+                .get_cells(&search_key, &Order::Asc, None, &cursor)
+                .await?;
+            if page.last_cursor.is_empty() {
+                return Ok(new_cells);
+            }
+            cursor = Some(page.last_cursor);
+            for cell in page.objects {
+                let key = (
+                    cell.block_number.value(),
+                    cell.tx_index.value(),
+                    cell.out_point.index.value(),
+                );
+                if scanned.cells.contains_key(&key) {
+                    continue;
+                }
+                let cell = cell.info();
+                let capacity: u64 = cell.output.capacity().unpack();
+                let info = parse_deposit_cell(cell, MIN_SUDT_DEPOSIT_CAPACITY).filter(|info| {
+                    let filter = &self.mem_block_config.deposit_filter;
+                    check_deposit_filter(filter, info).is_ok()
+                });
+                scanned.capacity += capacity;
+                scanned.cells.insert(key, info);
+                new_cells += 1;
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 
 use anyhow::{anyhow, ensure, Context, Result};
 use gw_config::{BackendType, FeeConfig, GaslessTxSupportConfig};
+use gw_generator::backend_manage::Backend;
 use gw_types::{
     h256::*,
     packed::{
@@ -137,16 +138,21 @@ impl FeeEntry {
         tx: L2Transaction,
         gasless_tx_support_config: Option<&GaslessTxSupportConfig>,
         fee_config: &FeeConfig,
-        backend_type: BackendType,
+        backend: &Backend,
         order: usize,
     ) -> Result<Self> {
         let raw_l2tx = tx.raw();
-        let fee = parse_l2tx_fee_rate(
+        let mut fee = parse_l2tx_fee_rate(
             gasless_tx_support_config,
             fee_config,
             &raw_l2tx,
-            backend_type,
+            backend.backend_type,
         )?;
+        if let Some(cycles_limit) = backend.cycles.fee_cycles_limit {
+            if backend.backend_type != BackendType::Polyjuice {
+                fee.cycles_limit = cycles_limit;
+            }
+        }
         let item = FeeItem::Tx(tx);
 
         let from_id: u32 = raw_l2tx.from_id().unpack();
@@ -269,6 +275,10 @@ fn parse_l2tx_fee_rate(
                 cycles_limit: gas_limit,
             })
         }
+        BackendType::Custom => Ok(L2Fee {
+            fee: 0,
+            cycles_limit: fee_config.minimal_tx_cycles_limit(),
+        }),
         BackendType::Unknown => Err(anyhow!("Found Unknown BackendType")),
     }
 }
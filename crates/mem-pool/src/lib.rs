@@ -5,14 +5,20 @@
 
 pub mod account_creator;
 pub mod block_sync_server;
+pub mod block_time;
+pub mod clock;
 mod constants;
 pub mod custodian;
 pub mod default_provider;
 mod deposit;
+pub mod deposit_watcher;
 pub mod fee;
 pub mod mem_block;
 pub mod pool;
 pub mod restore_manager;
+pub mod simulated_provider;
+pub mod subscription;
 pub mod traits;
 mod types;
 pub mod withdrawal;
+pub mod write_batch;
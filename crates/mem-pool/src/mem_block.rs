@@ -246,7 +246,7 @@ impl MemBlock {
         self.new_addresses.extend(addrs)
     }
 
-    pub fn new_addresses(&mut self) -> &HashSet<RegistryAddress> {
+    pub fn new_addresses(&self) -> &HashSet<RegistryAddress> {
         &self.new_addresses
     }
 
@@ -22,24 +22,31 @@ use gw_generator::{
     verification::{transaction::TransactionVerifier, withdrawal::WithdrawalVerifier},
     ArcSwap, Generator,
 };
+use gw_rpc_client::cycles_limit::CyclesLimitManager;
 use gw_store::{
     chain_view::ChainView,
+    deposit_status::DepositStatus,
     mem_pool_state::{self, MemPoolState, Shared},
-    state::{traits::JournalDB, MemStateDB},
+    state::{
+        overlay::read_cache::{ReadCache, READ_CACHE_ENTRIES},
+        traits::JournalDB,
+        MemStateDB,
+    },
     traits::chain_store::ChainStore,
     transaction::StoreTransaction,
     Store,
 };
+use gw_telemetry::alert::{alert, Alert};
 use gw_traits::CodeStore;
 use gw_types::packed::GlobalState;
 use gw_types::{
     h256::*,
-    offchain::{DepositInfo, FinalizedCustodianCapacity},
+    offchain::{CellInfo, CompatibleFinalizedTimepoint, DepositInfo, FinalizedCustodianCapacity},
     packed::{
         AccountMerkleState, BlockInfo, L2Block, L2Transaction, NextMemBlock, Script, TxReceipt,
-        WithdrawalKey, WithdrawalRequest, WithdrawalRequestExtra,
+        WithdrawalKey, WithdrawalRequestExtra,
     },
-    prelude::{Builder, Entity, Pack, PackVec, Unpack},
+    prelude::{Builder, Entity, Pack, PackVec, Reader, Unpack},
 };
 use gw_utils::calc_finalizing_range;
 use gw_utils::local_cells::LocalCellsManager;
@@ -49,7 +56,7 @@ use std::{
     iter::FromIterator,
     ops::Shr,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::task::block_in_place;
 use tracing::instrument;
@@ -57,23 +64,45 @@ use tracing::instrument;
 use crate::{
     account_creator::{filter_new_address, AccountCreator},
     block_sync_server::BlockSyncServerState,
+    block_time::BlockTime,
     mem_block::MemBlock,
     restore_manager::RestoreManager,
+    subscription::Subscriptions,
     traits::MemPoolProvider,
     types::EntryList,
-    withdrawal::Generator as WithdrawalGenerator,
+    withdrawal::{CustodianNotEnough, Generator as WithdrawalGenerator},
+    write_batch::WriteBatch,
 };
 
 type StateDB = gw_store::state::MemStateDB;
 
+/// Max blocks notified to subscribers on a tip update, e.g. after catching up.
+const MAX_NOTIFY_BLOCKS: u64 = 100;
+/// How long expired withdrawal records are kept for `gw_get_withdrawal`.
+const EXPIRED_WITHDRAWAL_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Min interval between two prunings of expired withdrawal records.
+const PRUNE_EXPIRED_WITHDRAWALS_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, Default)]
 pub struct OutputParam {
     pub retry_count: usize,
+    /// Package at most this many withdrawals. Deposits and txs are dropped
+    /// too if any withdrawal is dropped, because their states are based on
+    /// all withdrawals.
+    pub max_withdrawals: Option<usize>,
 }
 
 impl OutputParam {
     pub fn new(retry_count: usize) -> Self {
-        OutputParam { retry_count }
+        OutputParam {
+            retry_count,
+            max_withdrawals: None,
+        }
+    }
+
+    pub fn max_withdrawals(mut self, max_withdrawals: usize) -> Self {
+        self.max_withdrawals = Some(max_withdrawals);
+        self
     }
 }
 
@@ -101,10 +130,33 @@ pub struct MemPool {
     dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     sync_server: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
     mem_block_config: MemBlockConfig,
+    /// `mem_block_config` before applying `dynamic_config.mem_block_limits`.
+    base_mem_block_config: MemBlockConfig,
+    /// On-chain `max_cycles_limit`
+    cycles_limit_manager: Option<CyclesLimitManager>,
     /// Cycles Pool
     cycles_pool: CyclesPool,
     /// Account creator
     account_creator: Option<AccountCreator>,
+    /// New blocks and pending txs for RPC subscriptions
+    subscriptions: Subscriptions,
+    /// Timestamps of mem blocks
+    block_time: BlockTime,
+    /// Batched DB writes of pushed txs
+    write_batch: Option<WriteBatch>,
+    /// Account and SMT reads, shared by mem pool states of all tips
+    read_cache: ReadCache,
+    /// `mem_pool.pending_withdrawal_ttl_secs`
+    withdrawal_ttl: Option<Duration>,
+    /// When pending withdrawals are pushed, if `withdrawal_ttl` is set
+    withdrawal_pushed_at: HashMap<H256, Instant>,
+    /// Last pruning of expired withdrawal records
+    expired_withdrawals_pruned_at: Option<Instant>,
+    /// `block_producer.custodian_merge.max_custodian_inputs`
+    max_custodian_inputs: Option<usize>,
+    /// Finalized custodian cells to count custodian inputs of withdrawals
+    /// packaged by the next reset
+    finalized_custodian_cells: Option<Vec<CellInfo>>,
 }
 
 pub struct MemPoolCreateArgs {
@@ -117,6 +169,9 @@ pub struct MemPoolCreateArgs {
     pub dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     pub sync_server: Option<Arc<std::sync::Mutex<BlockSyncServerState>>>,
     pub account_creator: Option<AccountCreator>,
+    pub cycles_limit_manager: Option<CyclesLimitManager>,
+    /// Defer withdrawals that need more custodian inputs than this
+    pub max_custodian_inputs: Option<usize>,
 }
 
 impl Drop for MemPool {
@@ -141,6 +196,8 @@ impl MemPool {
             dynamic_config_manager,
             sync_server,
             account_creator,
+            cycles_limit_manager,
+            max_custodian_inputs,
         } = args;
         let pending = Default::default();
 
@@ -163,21 +220,46 @@ impl MemPool {
         let mut pending_restored_tx_hashes = VecDeque::new();
 
         let restore_manager = RestoreManager::build(&config.restore_path)?;
+        let mut restored_withdrawal_hashes = vec![];
         if let Ok(Some((restored, timestamp))) = restore_manager.restore_from_latest() {
             log::info!("[mem-pool] restore mem block from timestamp {}", timestamp);
 
-            let hashes: Vec<_> = restored.withdrawals().unpack();
-            mem_block.force_reinject_withdrawal_hashes(hashes.as_slice());
-
+            restored_withdrawal_hashes = restored.withdrawals().unpack();
             pending_restored_tx_hashes = VecDeque::from(Unpack::<Vec<_>>::unpack(&restored.txs()));
             pending_deposits = restored.deposits().unpack();
             mem_block.append_new_addresses(restored.as_reader().new_addresses().unpack());
         }
 
+        // Replay txs and withdrawals pushed after the mem block was saved,
+        // e.g. the node crashed before saving it.
+        match restore_manager.read_journal() {
+            Ok(journal) => {
+                log::info!(
+                    "[mem-pool] replay mem block journal txs: {} withdrawals: {}",
+                    journal.txs.len(),
+                    journal.withdrawals.len()
+                );
+                restored_withdrawal_hashes.extend(journal.withdrawals);
+                let restored_txs: HashSet<H256> =
+                    pending_restored_tx_hashes.iter().copied().collect();
+                pending_restored_tx_hashes.extend(
+                    { journal.txs.into_iter() }.filter(|hash| !restored_txs.contains(hash)),
+                );
+            }
+            Err(err) => log::error!("[mem-pool] read mem block journal error {}", err),
+        }
+        mem_block.force_reinject_withdrawal_hashes(restored_withdrawal_hashes.as_slice());
+        // Drop a partial record left by the crash before appending to it.
+        restore_manager.rewrite_journal(
+            pending_restored_tx_hashes.iter(),
+            restored_withdrawal_hashes.iter(),
+        )?;
+
         mem_block.clear_txs();
 
+        let read_cache = ReadCache::new(READ_CACHE_ENTRIES);
         let mem_pool_state = {
-            let state_db = MemStateDB::from_store(store.get_snapshot())?;
+            let state_db = MemStateDB::from_store_with_cache(store.get_snapshot(), &read_cache)?;
             Arc::new(MemPoolState::new(state_db, false))
         };
 
@@ -199,9 +281,20 @@ impl MemPool {
             mem_pool_state,
             dynamic_config_manager,
             sync_server,
-            mem_block_config: config.mem_block,
+            mem_block_config: config.mem_block.clone(),
+            base_mem_block_config: config.mem_block,
+            cycles_limit_manager,
             cycles_pool,
             account_creator,
+            subscriptions: Subscriptions::default(),
+            block_time: BlockTime::default(),
+            write_batch: config.write_batch.map(WriteBatch::new),
+            read_cache,
+            withdrawal_ttl: config.pending_withdrawal_ttl_secs.map(Duration::from_secs),
+            withdrawal_pushed_at: HashMap::new(),
+            expired_withdrawals_pruned_at: None,
+            max_custodian_inputs,
+            finalized_custodian_cells: None,
         };
         mem_pool.restore_pending_withdrawals().await?;
         mem_pool.remove_reinjected_failed_txs()?;
@@ -216,7 +309,7 @@ impl MemPool {
             mem_pool.reset_read_only(Some(tip_hash), true)?;
         } else {
             mem_pool
-                .reset(None, Some(tip_hash), &Default::default())
+                .reset(None, Some(tip_hash), Vec::new(), &Default::default())
                 .await?;
         }
 
@@ -236,6 +329,10 @@ impl MemPool {
         self.mem_pool_state.clone()
     }
 
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.subscriptions
+    }
+
     pub fn cycles_pool(&self) -> &CyclesPool {
         &self.cycles_pool
     }
@@ -253,6 +350,7 @@ impl MemPool {
     }
 
     pub fn save_mem_block(&mut self) -> Result<()> {
+        self.flush_writes()?;
         if !self.pending_restored_tx_hashes.is_empty() {
             log::warn!(
                 "save mem block, but have pending restored txs from previous restored mem block"
@@ -267,6 +365,7 @@ impl MemPool {
     }
 
     pub fn save_mem_block_with_suffix(&mut self, suffix: &str) -> Result<()> {
+        self.flush_writes()?;
         if !self.pending_restored_tx_hashes.is_empty() {
             log::warn!(
                 "save mem block, but have pending restored txs from previous restored mem block"
@@ -298,18 +397,92 @@ impl MemPool {
     }
 
     /// Push a layer2 tx into pool
-    #[instrument(skip_all)]
     pub fn push_transaction(&mut self, tx: L2Transaction) -> Result<()> {
-        tokio::task::block_in_place(|| {
-            let mut db = self.store.begin_transaction();
+        self.push_checked_transaction(tx, &HashSet::new())
+    }
+
+    /// Check signatures of `txs` in parallel, returns witness hashes of txs
+    /// with valid signatures, to push them by `push_checked_transaction`.
+    pub fn check_signatures(&self, txs: &[L2Transaction]) -> HashSet<H256> {
+        let state = self.mem_pool_state.load_state_db();
+        let results = block_in_place(|| self.generator.check_transaction_signatures(&state, txs));
+        { txs.iter().zip(results) }
+            .filter(|(_, result)| result.is_ok())
+            .map(|(tx, _)| tx.witness_hash())
+            .collect()
+    }
+
+    /// Push a layer2 tx into pool, skip checking its signature if its witness
+    /// hash is in `checked`.
+    #[instrument(skip_all)]
+    pub fn push_checked_transaction(
+        &mut self,
+        tx: L2Transaction,
+        checked: &HashSet<H256>,
+    ) -> Result<()> {
+        let t = Instant::now();
+        let tx_hash: H256 = tx.raw().hash();
+        let signature_checked = checked.contains(&tx.witness_hash());
+        let result = tokio::task::block_in_place(|| {
+            let mut db = match self.write_batch {
+                Some(ref mut batch) => batch.begin(&self.store),
+                None => self.store.begin_transaction(),
+            };
 
             let mut state = self.mem_pool_state.load_state_db();
-            self.push_transaction_with_db(&mut db, &mut state, tx)?;
-            db.commit()?;
+            let pushed = self.push_transaction_with_db(&mut db, &mut state, tx, signature_checked);
+            match self.write_batch {
+                Some(ref mut batch) => batch.end(db, pushed.is_ok())?,
+                None if pushed.is_ok() => db.commit()?,
+                None => (),
+            }
+            pushed?;
             self.mem_pool_state.store_state_db(state);
 
             Ok(())
-        })
+        });
+
+        let metrics = gw_metrics::mem_pool();
+        metrics.push_tx_duration.observe(t.elapsed().as_secs_f64());
+        match result {
+            Ok(()) => self.subscriptions.notify_pending_tx(tx_hash),
+            Err(_) => metrics.rejected_txs.inc(),
+        }
+        self.update_mem_block_metrics();
+        result
+    }
+
+    /// Commit batched DB writes of pushed txs, see `mem_pool.write_batch`.
+    pub fn flush_writes(&mut self) -> Result<()> {
+        match self.write_batch {
+            Some(ref mut batch) => block_in_place(|| batch.flush()),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply reloadable `dynamic_config.mem_block_limits`, falling back to the
+    /// on-chain cycles limit and then the config file.
+    fn apply_mem_block_limits(&mut self) {
+        let limits = {
+            let manager = self.dynamic_config_manager.load();
+            manager.get_mem_block_limits().cloned().unwrap_or_default()
+        };
+        let onchain_cycles_limit = { self.cycles_limit_manager.as_ref() }.and_then(|m| m.load());
+        let base = &self.base_mem_block_config;
+        let config = &mut self.mem_block_config;
+        config.max_txs = limits.max_txs.unwrap_or(base.max_txs);
+        config.max_withdrawals = limits.max_withdrawals.unwrap_or(base.max_withdrawals);
+        config.max_cycles_limit =
+            { limits.max_cycles_limit.or(onchain_cycles_limit) }.unwrap_or(base.max_cycles_limit);
+    }
+
+    fn update_mem_block_metrics(&self) {
+        gw_metrics::mem_pool().mem_block(
+            self.mem_block.txs().len(),
+            self.mem_block_config.max_txs,
+            self.mem_block.deposits().len(),
+            self.mem_block.withdrawals().len(),
+        );
     }
 
     /// Push a layer2 tx into pool
@@ -319,6 +492,7 @@ impl MemPool {
         db: &mut StoreTransaction,
         state: &mut StateDB,
         tx: L2Transaction,
+        signature_checked: bool,
     ) -> Result<()> {
         // check duplication
         let tx_hash: H256 = tx.raw().hash();
@@ -345,7 +519,9 @@ impl MemPool {
         )
         .verify(&tx, self.mem_block.block_info().number().unpack())?;
         // verify signature
-        self.generator.check_transaction_signature(state, &tx)?;
+        if !signature_checked {
+            self.generator.check_transaction_signature(state, &tx)?;
+        }
 
         // instantly run tx in background & update local state
         let t = Instant::now();
@@ -353,9 +529,11 @@ impl MemPool {
         log::debug!("[push tx] finalize tx time: {}ms", t.elapsed().as_millis());
 
         // save new addresses
-        if self.account_creator.is_some() {
+        if let Some(account_creator) = self.account_creator.as_ref() {
             let logs = tx_receipt.as_reader().logs();
             if let Some(new_addresses) = filter_new_address(logs.iter(), state) {
+                let new_addresses =
+                    { new_addresses.into_iter() }.filter(|addr| !account_creator.is_denied(addr));
                 self.mem_block.append_new_addresses(new_addresses);
             }
         }
@@ -364,6 +542,9 @@ impl MemPool {
         let post_state = tx_receipt.post_state();
         self.mem_block.push_tx(tx_hash, post_state);
         db.insert_mem_pool_transaction_receipt(&tx_hash, tx_receipt)?;
+        if let Err(err) = self.restore_manager.journal_tx(&tx_hash) {
+            log::error!("[mem-pool] journal tx error {}", err);
+        }
 
         // Add to pool
         let account_id: u32 = tx.raw().from_id().unpack();
@@ -371,6 +552,9 @@ impl MemPool {
         let entry_list = self.pending.entry(account_id).or_default();
         entry_list.txs.push(tx);
 
+        // So that the L1 submission of this tx can link to this span.
+        gw_telemetry::trace::links::record(tx_hash);
+
         Ok(())
     }
 
@@ -400,6 +584,11 @@ impl MemPool {
             .expect("get account_id");
         let entry_list = self.pending.entry(account_id).or_default();
         entry_list.withdrawals.push(withdrawal.clone());
+        if self.withdrawal_ttl.is_some() {
+            self.withdrawal_pushed_at
+                .entry(withdrawal_hash)
+                .or_insert_with(Instant::now);
+        }
         // Add to pool
         let mut db = self.store.begin_transaction();
         db.insert_mem_pool_withdrawal(&withdrawal_hash, withdrawal)?;
@@ -498,12 +687,41 @@ impl MemPool {
     ) -> Result<()> {
         // reset pool state
         if self.current_tip.0 != new_tip {
-            self.reset(Some(self.current_tip.0), Some(new_tip), local_cells_manager)
-                .await?;
+            self.reset(
+                Some(self.current_tip.0),
+                Some(new_tip),
+                Vec::new(),
+                local_cells_manager,
+            )
+            .await?;
         }
         Ok(())
     }
 
+    /// Notify new tip after L2 blocks are reverted.
+    ///
+    /// Unlike txs of detached blocks, which are found from the current tip,
+    /// txs of reverted bad blocks must be passed in `reverted_txs` to be
+    /// reinjected. The pool is reset even if the tip doesn't change, i.e. only
+    /// bad blocks are reverted.
+    ///
+    /// This method should only be used on a full node or test node.
+    #[instrument(skip_all)]
+    pub async fn notify_reverted_blocks(
+        &mut self,
+        new_tip: H256,
+        reverted_txs: Vec<L2Transaction>,
+        local_cells_manager: &LocalCellsManager,
+    ) -> Result<()> {
+        self.reset(
+            Some(self.current_tip.0),
+            Some(new_tip),
+            reverted_txs,
+            local_cells_manager,
+        )
+        .await
+    }
+
     /// Clear mem block state and recollect deposits
     #[instrument(skip_all)]
     pub async fn reset_mem_block(&mut self, local_cells_manager: &LocalCellsManager) -> Result<()> {
@@ -512,6 +730,7 @@ impl MemPool {
         self.reset(
             Some(self.current_tip.0),
             Some(self.current_tip.0),
+            Vec::new(),
             local_cells_manager,
         )
         .await?;
@@ -549,9 +768,113 @@ impl MemPool {
         &mut self,
         old_tip: Option<H256>,
         new_tip: Option<H256>,
+        reverted_txs: Vec<L2Transaction>,
         local_cells_manager: &LocalCellsManager,
     ) -> Result<()> {
-        self.reset_full(old_tip, new_tip, local_cells_manager).await
+        let t = Instant::now();
+        self.flush_writes()?;
+        self.apply_mem_block_limits();
+        let result = self
+            .reset_full(old_tip, new_tip, reverted_txs, local_cells_manager)
+            .await;
+        if let Err(ref err) = result {
+            alert(Alert::MemPoolResetFailed {
+                error: format!("{:#}", err),
+            });
+        }
+        result?;
+        gw_metrics::mem_pool()
+            .reset_duration
+            .observe(t.elapsed().as_secs_f64());
+        self.update_mem_block_metrics();
+        Ok(())
+    }
+
+    /// Txs and withdrawals of blocks from `old_tip` back to the common ancestor
+    /// with `new_tip`, in block order, excluding those included by blocks up to
+    /// `new_tip`. Blocks are read in place instead of being copied.
+    fn collect_discarded(
+        &self,
+        (mut rem, mut rem_number): (H256, u64),
+        (mut add, mut add_number): (H256, u64),
+    ) -> Result<(VecDeque<L2Transaction>, VecDeque<WithdrawalRequestExtra>)> {
+        let mut discarded_txs: VecDeque<L2Transaction> = Default::default();
+        let mut discarded_withdrawals: VecDeque<WithdrawalRequestExtra> = Default::default();
+        let mut included_txs: HashSet<H256> = Default::default();
+        let mut included_withdrawals: HashSet<H256> = Default::default();
+        while rem != add {
+            let (discard, include) = (rem_number >= add_number, add_number >= rem_number);
+            if discard {
+                let (txs, withdrawals_count, parent) = self
+                    .store
+                    .get_block_reader(&rem, |b| {
+                        let txs: Vec<L2Transaction> =
+                            b.transactions().iter().map(|tx| tx.to_entity()).collect();
+                        let parent: H256 = b.raw().parent_block_hash().unpack();
+                        (txs, b.withdrawals().len(), parent)
+                    })?
+                    .expect("get block");
+                // reverse push, so we can keep txs and withdrawals in block's order
+                for tx in txs.into_iter().rev() {
+                    discarded_txs.push_front(tx);
+                }
+                for index in (0..withdrawals_count).rev() {
+                    let key = WithdrawalKey::build_withdrawal_key(rem.pack(), index as u32);
+                    let withdrawal = self
+                        .store
+                        .get_withdrawal_by_key(&key)?
+                        .expect("get withdrawal");
+                    discarded_withdrawals.push_front(withdrawal);
+                }
+                rem = parent;
+                rem_number -= 1;
+            }
+            if include {
+                add = self
+                    .store
+                    .get_block_reader(&add, |b| {
+                        included_txs.extend(b.transactions().iter().map(|tx| tx.hash()));
+                        included_withdrawals.extend(b.withdrawals().iter().map(|w| w.hash()));
+                        b.raw().parent_block_hash().unpack()
+                    })?
+                    .expect("get block");
+                add_number -= 1;
+            }
+        }
+        discarded_txs.retain(|tx| !included_txs.contains(&tx.hash()));
+        discarded_withdrawals.retain(|w| !included_withdrawals.contains(&w.hash()));
+        Ok((discarded_txs, discarded_withdrawals))
+    }
+
+    /// Notify subscribers of blocks from the current tip to `new_tip_block`.
+    ///
+    /// Must be called before updating `current_tip`. Only the tip is notified
+    /// if the chain is reverted.
+    fn notify_new_blocks(&self, new_tip_block: &L2Block) -> Result<()> {
+        let (current_tip_hash, current_tip_number, _) = &self.current_tip;
+        if !self.subscriptions.has_new_block_subscribers()
+            || *current_tip_hash == new_tip_block.hash()
+        {
+            return Ok(());
+        }
+
+        let new_tip_number: u64 = new_tip_block.raw().number().unpack();
+        let start = max(
+            current_tip_number + 1,
+            new_tip_number.saturating_sub(MAX_NOTIFY_BLOCKS - 1),
+        );
+        for number in start..new_tip_number {
+            let block = self
+                .store
+                .get_block_hash_by_number(number)?
+                .and_then(|hash| self.store.get_block(&hash).transpose())
+                .transpose()?;
+            if let Some(block) = block {
+                self.subscriptions.notify_new_block(block);
+            }
+        }
+        self.subscriptions.notify_new_block(new_tip_block.clone());
+        Ok(())
     }
 
     /// Only **ReadOnly** node.
@@ -570,6 +893,7 @@ impl MemPool {
             .store
             .get_block_post_global_state(&new_tip)?
             .expect("new tip global state");
+        self.notify_new_blocks(&new_tip_block)?;
         self.current_tip = (
             new_tip,
             new_tip_block.raw().number().unpack(),
@@ -582,8 +906,13 @@ impl MemPool {
             // re-injecting discarded txs/withdrawals.
             let snapshot = self.store.get_snapshot();
             self.mem_block.reset(&new_tip_block, Duration::ZERO);
+            self.apply_mem_block_limits();
+            self.cycles_pool = CyclesPool::new(
+                self.mem_block_config.max_cycles_limit,
+                self.mem_block_config.syscall_cycles.clone(),
+            );
             let shared = mem_pool_state::Shared {
-                state_db: MemStateDB::from_store(snapshot)?,
+                state_db: MemStateDB::from_store_with_cache(snapshot, &self.read_cache)?,
                 mem_block: Some(self.mem_block.block_info().to_owned()),
             };
             self.mem_pool_state.store_shared(Arc::new(shared));
@@ -599,9 +928,10 @@ impl MemPool {
         &mut self,
         old_tip: Option<H256>,
         new_tip: Option<H256>,
+        reverted_txs: Vec<L2Transaction>,
         local_cells_manager: &LocalCellsManager,
     ) -> Result<()> {
-        let mut reinject_txs = Default::default();
+        let mut reinject_txs: VecDeque<L2Transaction> = Default::default();
         let mut reinject_withdrawals = Default::default();
         // read block from db
         let new_tip = match new_tip {
@@ -615,89 +945,24 @@ impl MemPool {
 
         if old_tip.is_some() && old_tip != Some(new_tip_block.raw().parent_block_hash().unpack()) {
             let old_tip = old_tip.unwrap();
-            let old_tip_block = self.store.get_block(&old_tip)?.expect("old tip block");
+            let old_number: u64 = self
+                .store
+                .get_block_reader(&old_tip, |b| b.raw().number().unpack())?
+                .expect("old tip block");
 
             let new_number: u64 = new_tip_block.raw().number().unpack();
-            let old_number: u64 = old_tip_block.raw().number().unpack();
             let depth = max(new_number, old_number) - min(new_number, old_number);
             if depth > 64 {
                 log::error!("skipping deep transaction reorg: depth {}", depth);
             } else {
-                let mut rem = old_tip_block;
-                let mut add = new_tip_block.clone();
-                let mut discarded_txs: VecDeque<L2Transaction> = Default::default();
-                let mut included_txs: HashSet<L2Transaction> = Default::default();
-                let mut discarded_withdrawals: VecDeque<WithdrawalRequestExtra> =
-                    Default::default();
-                let mut included_withdrawals: HashSet<WithdrawalRequest> = Default::default();
-                while rem.raw().number().unpack() > add.raw().number().unpack() {
-                    // reverse push, so we can keep txs in block's order
-                    for index in (0..rem.transactions().len()).rev() {
-                        discarded_txs.push_front(rem.transactions().get(index).unwrap());
-                    }
-                    let block_hash = rem.hash();
-                    // reverse push, so we can keep withdrawals in block's order
-                    for index in (0..rem.withdrawals().len()).rev() {
-                        let key =
-                            WithdrawalKey::build_withdrawal_key(block_hash.pack(), index as u32);
-                        let withdrawal = rem.withdrawals().get(index).unwrap();
-                        let withdrawal_extra = self
-                            .store
-                            .get_withdrawal_by_key(&key)?
-                            .expect("get withdrawal");
-                        assert_eq!(withdrawal, withdrawal_extra.request());
-                        discarded_withdrawals.push_front(withdrawal_extra);
-                    }
-                    rem = self
-                        .store
-                        .get_block(&rem.raw().parent_block_hash().unpack())?
-                        .expect("get block");
-                }
-                while add.raw().number().unpack() > rem.raw().number().unpack() {
-                    included_txs.extend(add.transactions().into_iter());
-                    included_withdrawals.extend(rem.withdrawals().into_iter());
-                    add = self
-                        .store
-                        .get_block(&add.raw().parent_block_hash().unpack())?
-                        .expect("get block");
-                }
-                while rem.hash() != add.hash() {
-                    // reverse push, so we can keep txs in block's order
-                    for index in (0..rem.transactions().len()).rev() {
-                        discarded_txs.push_front(rem.transactions().get(index).unwrap());
-                    }
-                    // reverse push, so we can keep withdrawals in block's order
-                    for index in (0..rem.withdrawals().len()).rev() {
-                        let withdrawal = rem.withdrawals().get(index).unwrap();
-                        let withdrawal_extra = self
-                            .store
-                            .get_withdrawal(&withdrawal.hash())?
-                            .expect("get withdrawal");
-                        discarded_withdrawals.push_front(withdrawal_extra);
-                    }
-                    rem = self
-                        .store
-                        .get_block(&rem.raw().parent_block_hash().unpack())?
-                        .expect("get block");
-                    included_txs.extend(add.transactions().into_iter());
-                    included_withdrawals.extend(add.withdrawals().into_iter());
-                    add = self
-                        .store
-                        .get_block(&add.raw().parent_block_hash().unpack())?
-                        .expect("get block");
-                }
-                // remove included txs
-                discarded_txs.retain(|tx| !included_txs.contains(tx));
+                let (discarded_txs, discarded_withdrawals) =
+                    self.collect_discarded((old_tip, old_number), (new_tip, new_number))?;
                 reinject_txs = discarded_txs;
-                // remove included withdrawals
-                discarded_withdrawals
-                    .retain(|withdrawal| !included_withdrawals.contains(&withdrawal.request()));
-                reinject_withdrawals = discarded_withdrawals
-                    .into_iter()
-                    .map(Into::<WithdrawalRequestExtra>::into)
-                    .collect::<VecDeque<_>>()
+                reinject_withdrawals = discarded_withdrawals;
             }
         }
+        // txs of reverted bad blocks
+        reinject_txs.extend(reverted_txs);
 
         let mut db = self.store.begin_transaction();
 
@@ -705,19 +970,31 @@ impl MemPool {
 
         // refresh pending deposits
         if !is_mem_pool_recovery {
-            self.refresh_deposit_cells(&db, new_tip, local_cells_manager)
+            self.refresh_deposit_cells(&mut db, new_tip, local_cells_manager)
                 .await?;
         }
 
+        // query custodian cells for withdrawals to package
+        self.finalized_custodian_cells = if is_mem_pool_recovery {
+            None
+        } else {
+            self.query_finalized_custodian_cells(
+                &new_tip,
+                &reinject_withdrawals,
+                local_cells_manager,
+            )
+            .await
+            .unwrap_or_else(|err| {
+                log::warn!("[mem-pool] query finalized custodian cells error {:#}", err);
+                None
+            })
+        };
+
         // estimate next l2block timestamp
         let estimated_timestamp = {
             let estimated = self.provider.estimate_next_blocktime().await;
             let tip_timestamp = Duration::from_millis(new_tip_block.raw().timestamp().unpack());
-            match estimated {
-                Ok(e) if e <= tip_timestamp => tip_timestamp.saturating_add(Duration::from_secs(1)),
-                Err(_) => tip_timestamp.saturating_add(Duration::from_secs(1)),
-                Ok(e) => e,
-            }
+            self.block_time.next_timestamp(tip_timestamp, estimated)
         };
 
         block_in_place(move || {
@@ -733,6 +1010,7 @@ impl MemPool {
                 .store
                 .get_block_post_global_state(&new_tip)?
                 .expect("new tip block global state");
+            self.notify_new_blocks(&new_tip_block)?;
             self.current_tip = (
                 new_tip,
                 new_tip_block.raw().number().unpack(),
@@ -762,7 +1040,7 @@ impl MemPool {
             };
 
             // create new mem_store to maintain memory state
-            let mut state_db = StateDB::from_store(snapshot)?;
+            let mut state_db = StateDB::from_store_with_cache(snapshot, &self.read_cache)?;
             let mem_block = self.mem_block.block_info().to_owned();
 
             // remove from pending
@@ -800,7 +1078,8 @@ impl MemPool {
                 {
                     Ok(Some((tx, next_batch))) => {
                         self.mem_block.append_new_addresses(next_batch);
-                        if let Err(err) = self.push_transaction_with_db(&mut db, &mut state_db, tx)
+                        if let Err(err) =
+                            self.push_transaction_with_db(&mut db, &mut state_db, tx, false)
                         {
                             tracing::error!("account creator err {}", err);
                         }
@@ -827,11 +1106,63 @@ impl MemPool {
             };
             self.mem_pool_state.store_shared(Arc::new(shared));
             db.commit()?;
+            self.rewrite_journal();
 
             Ok(())
         })
     }
 
+    /// Query finalized custodian cells for pending and re-injected
+    /// withdrawals if `max_custodian_inputs` is set.
+    async fn query_finalized_custodian_cells(
+        &self,
+        new_tip: &H256,
+        reinject_withdrawals: &VecDeque<WithdrawalRequestExtra>,
+        local_cells_manager: &LocalCellsManager,
+    ) -> Result<Option<Vec<CellInfo>>> {
+        if self.max_custodian_inputs.is_none() {
+            return Ok(None);
+        }
+        let withdrawals: Vec<_> = { self.pending.values() }
+            .flat_map(|entry_list| entry_list.withdrawals.iter())
+            .chain(reinject_withdrawals)
+            .map(|withdrawal| withdrawal.request())
+            .collect();
+        if withdrawals.is_empty() {
+            return Ok(None);
+        }
+
+        let rollup_context = self.generator.rollup_context();
+        let new_tip_global_state = self
+            .store
+            .get_block_post_global_state(new_tip)?
+            .context("new tip block global state")?;
+        let compatible_finalized_timepoint = CompatibleFinalizedTimepoint::from_global_state(
+            &new_tip_global_state,
+            rollup_context.rollup_config.finality_blocks().unpack(),
+        );
+        self.provider
+            .query_finalized_custodians(
+                rollup_context,
+                withdrawals,
+                &compatible_finalized_timepoint,
+                local_cells_manager,
+            )
+            .await
+    }
+
+    /// Replace the journal with the new mem block and the restored txs not
+    /// yet pushed.
+    fn rewrite_journal(&self) {
+        let txs = { self.pending_restored_tx_hashes.iter() }.chain(self.mem_block.txs());
+        if let Err(err) = self
+            .restore_manager
+            .rewrite_journal(txs, self.mem_block.withdrawals())
+        {
+            log::error!("[mem-pool] rewrite mem block journal error {}", err);
+        }
+    }
+
     fn try_package_more_withdrawals(
         &self,
         state: &StateDB,
@@ -851,7 +1182,11 @@ impl MemPool {
 
         // package withdrawals
         if withdrawals.len() < self.mem_block_config.max_withdrawals {
-            for entry in self.pending().values() {
+            // Iterate in account id order, so withdrawals deferred because of
+            // insufficient custodians are retried in the same order.
+            let mut account_ids: Vec<u32> = self.pending().keys().copied().collect();
+            account_ids.sort_unstable();
+            for entry in account_ids.iter().map(|id| &self.pending()[id]) {
                 if let Some(withdrawal) = entry.withdrawals.first() {
                     if filter_withdrawals(state, withdrawal) {
                         withdrawals.push(withdrawal.clone());
@@ -871,6 +1206,9 @@ impl MemPool {
         state: &mut StateDB,
         db: &mut StoreTransaction,
     ) -> Result<()> {
+        self.remove_expired_withdrawals(db)?;
+        self.prune_expired_withdrawals(db)?;
+
         let mut remove_list = Vec::default();
         // iter pending accounts and demote any non-executable objects
         for (&account_id, list) in &mut self.pending {
@@ -912,6 +1250,62 @@ impl MemPool {
         Ok(())
     }
 
+    /// Drop pending withdrawals pushed longer than `withdrawal_ttl` ago, they
+    /// are reported as expired by `gw_get_withdrawal`.
+    fn remove_expired_withdrawals(&mut self, db: &mut StoreTransaction) -> Result<()> {
+        let ttl = match self.withdrawal_ttl {
+            Some(ttl) => ttl,
+            None => return Ok(()),
+        };
+        let (pushed_at, packaged) = (&self.withdrawal_pushed_at, self.mem_block.withdrawals_set());
+        let mut expired = Vec::new();
+        for list in self.pending.values_mut() {
+            list.withdrawals.retain(|withdrawal| {
+                let hash = withdrawal.hash();
+                let is_expired = !packaged.contains(&hash)
+                    && pushed_at.get(&hash).map_or(false, |t| t.elapsed() >= ttl);
+                if is_expired {
+                    expired.push(hash);
+                }
+                !is_expired
+            });
+        }
+
+        let expired_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        for hash in expired {
+            log::info!("[mem-pool] drop expired withdrawal {}", hash.pack());
+            db.expire_mem_pool_withdrawal(&hash, expired_at)?;
+            gw_metrics::mem_pool().expired_withdrawals.inc();
+        }
+        // Forget withdrawals no longer pending.
+        let pending: HashSet<H256> = { self.pending.values() }
+            .flat_map(|list| list.withdrawals.iter().map(|w| w.hash()))
+            .collect();
+        self.withdrawal_pushed_at
+            .retain(|hash, _| pending.contains(hash));
+        Ok(())
+    }
+
+    /// Delete expired withdrawal records older than
+    /// `EXPIRED_WITHDRAWAL_RETENTION`, at most once per
+    /// `PRUNE_EXPIRED_WITHDRAWALS_INTERVAL`.
+    fn prune_expired_withdrawals(&mut self, db: &mut StoreTransaction) -> Result<()> {
+        let is_due = { self.expired_withdrawals_pruned_at }
+            .map_or(true, |t| t.elapsed() >= PRUNE_EXPIRED_WITHDRAWALS_INTERVAL);
+        if !is_due {
+            return Ok(());
+        }
+        self.expired_withdrawals_pruned_at = Some(Instant::now());
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let expired_before = now.saturating_sub(EXPIRED_WITHDRAWAL_RETENTION);
+        let pruned = db.prune_expired_withdrawals(expired_before.as_millis() as u64)?;
+        if pruned > 0 {
+            log::info!("[mem-pool] pruned {} expired withdrawal records", pruned);
+        }
+        Ok(())
+    }
+
     /// Prepare for next mem block
     #[instrument(skip_all, fields(withdrawals_count = withdrawals.len(), txs_count = txs.len()))]
     fn prepare_next_mem_block(
@@ -955,6 +1349,7 @@ impl MemPool {
         self.finalize_withdrawals(state, db, withdrawals.clone())?;
         // deposits
         self.finalize_deposits(state, deposit_cells.clone())?;
+        set_deposits_status(db, &deposit_cells, DepositStatus::Packaged)?;
 
         if let Some(ref sync_server) = self.sync_server {
             let mut sync_server = sync_server.lock().unwrap();
@@ -967,9 +1362,15 @@ impl MemPool {
             );
         }
 
-        // re-inject txs
-        for tx in txs {
-            if let Err(err) = self.push_transaction_with_db(db, state, tx.clone()) {
+        // re-inject txs, check signatures in parallel first. Failed ones are
+        // checked again when pushed, their accounts may be created by txs
+        // before them.
+        let checked = self.generator.check_transaction_signatures(state, &txs);
+        for (tx, checked) in txs.into_iter().zip(checked) {
+            let signature_checked = checked.is_ok();
+            if let Err(err) =
+                self.push_transaction_with_db(db, state, tx.clone(), signature_checked)
+            {
                 let tx_hash = tx.hash();
                 log::info!(
                     "[mem pool] fail to re-inject tx {}, error: {}",
@@ -986,19 +1387,16 @@ impl MemPool {
     #[instrument(skip_all)]
     async fn refresh_deposit_cells(
         &mut self,
-        db: &StoreTransaction,
+        db: &mut StoreTransaction,
         new_block_hash: H256,
         local_cells_manager: &LocalCellsManager,
     ) -> Result<()> {
         // refresh
         let state = self.mem_pool_state.load_state_db();
         let mem_account_count = state.get_account_count()?;
-        let tip_account_count: u32 = {
-            let new_tip_block = db
-                .get_block(&new_block_hash)?
-                .ok_or_else(|| anyhow!("can't find new tip block"))?;
-            new_tip_block.raw().post_account().count().unpack()
-        };
+        let tip_account_count: u32 = db
+            .get_block_reader(&new_block_hash, |b| b.raw().post_account().count().unpack())?
+            .ok_or_else(|| anyhow!("can't find new tip block"))?;
 
         log::debug!(
             "[mem-pool] refresh pending deposits, mem_account_count: {}, tip_account_count: {}",
@@ -1009,12 +1407,14 @@ impl MemPool {
             .provider
             .collect_deposit_cells(local_cells_manager)
             .await?;
+        let cells = crate::deposit::dedup_deposit_cells(&*db, cells);
         self.pending_deposits = crate::deposit::sanitize_deposit_cells(
             self.generator.rollup_context(),
-            &self.mem_block_config.deposit_timeout_config,
+            &self.mem_block_config,
             cells,
             &state,
         );
+        set_deposits_status(db, &self.pending_deposits, DepositStatus::Collected)?;
         log::debug!(
             "[mem-pool] refreshed deposits: {}",
             self.pending_deposits.len()
@@ -1080,10 +1480,17 @@ impl MemPool {
         .collect();
         // verify the withdrawals
         let mut unused_withdrawals = Vec::with_capacity(withdrawals.len());
+        let mut deferred_withdrawals = Vec::new();
         let mut withdrawal_verifier = crate::withdrawal::Generator::new(
             self.generator.rollup_context(),
             finalized_custodians,
         );
+        if let (Some(max_inputs), Some(cells)) = (
+            self.max_custodian_inputs,
+            self.finalized_custodian_cells.take(),
+        ) {
+            withdrawal_verifier = withdrawal_verifier.with_custodian_inputs(cells, max_inputs);
+        }
         // start track withdrawal
         state.set_state_tracker(Default::default());
         for withdrawal in withdrawals {
@@ -1119,11 +1526,19 @@ impl MemPool {
             if let Err(err) =
                 withdrawal_verifier.include_and_verify(&withdrawal, &L2Block::default())
             {
-                log::info!(
-                    "[mem-pool] withdrawal contextual verification failed : {}",
-                    err
-                );
-                unused_withdrawals.push(withdrawal_hash);
+                if err.downcast_ref::<CustodianNotEnough>().is_some() {
+                    // Not enough finalized custodians, keep it in pending and
+                    // try again in the next block.
+                    log::info!(
+                        "[mem-pool] withdrawal {} deferred: {}",
+                        hex::encode(withdrawal_hash.as_slice()),
+                        err
+                    );
+                    deferred_withdrawals.push(withdrawal_hash);
+                } else {
+                    log::info!("[mem-pool] withdrawal output error: {:?}", err);
+                    unused_withdrawals.push(withdrawal_hash);
+                }
                 continue;
             }
 
@@ -1160,6 +1575,9 @@ impl MemPool {
                         post_state,
                         touched_keys.lock().unwrap().drain(),
                     );
+                    if let Err(err) = self.restore_manager.journal_withdrawal(&withdrawal_hash) {
+                        log::error!("[mem-pool] journal withdrawal error {}", err);
+                    }
                 }
                 Err(err) => {
                     log::info!("[mem-pool] withdrawal execution failed : {}", err);
@@ -1174,10 +1592,17 @@ impl MemPool {
 
         // remove unused withdrawals
         log::info!(
-            "[mem-pool] finalize withdrawals: {} staled withdrawals: {}",
+            "[mem-pool] finalize withdrawals: {} staled withdrawals: {} deferred withdrawals: {}",
             self.mem_block.withdrawals().len(),
-            unused_withdrawals.len()
+            unused_withdrawals.len(),
+            deferred_withdrawals.len()
         );
+        self.mem_pool_state
+            .store_deferred_withdrawals(deferred_withdrawals);
+        self.mem_pool_state
+            .store_packaged_withdrawals(self.mem_block.withdrawals_set().clone());
+        self.mem_pool_state
+            .store_finalized_custodians(self.mem_block.finalized_custodians().clone());
         Ok(())
     }
 
@@ -1240,6 +1665,10 @@ impl MemPool {
                 err
             })?;
 
+        gw_metrics::mem_pool()
+            .execute_tx_cycles
+            .observe(run_result.cycles.total() as f64);
+
         // check account id of sudt proxy contract creator is from whitelist
         {
             let from_id = raw_tx.from_id().unpack();
@@ -1377,7 +1806,7 @@ impl MemPool {
             );
             self.mem_block = mem_block;
 
-            let mut state = StateDB::from_store(snapshot)?;
+            let mut state = StateDB::from_store_with_cache(snapshot, &self.read_cache)?;
             let mem_block = self.mem_block.block_info().to_owned();
 
             // remove from pending
@@ -1426,6 +1855,11 @@ pub(crate) fn repackage_count(
     }
 
     let withdrawals_count = mem_block.withdrawals().iter().take(remain).count();
+    if let Some(max_withdrawals) = output_param.max_withdrawals {
+        if withdrawals_count > max_withdrawals {
+            return (max_withdrawals, 0, 0);
+        }
+    }
     remain = remain.saturating_sub(withdrawals_count);
 
     let deposits_count = mem_block.deposits().iter().take(remain).count();
@@ -1436,6 +1870,23 @@ pub(crate) fn repackage_count(
     (withdrawals_count, deposits_count, txs_count)
 }
 
+/// Record statuses of deposits of the mem pool, deposits already credited by
+/// blocks are kept.
+fn set_deposits_status(
+    db: &mut StoreTransaction,
+    deposits: &[DepositInfo],
+    status: DepositStatus,
+) -> Result<()> {
+    for deposit in deposits {
+        let out_point = &deposit.cell.out_point;
+        if let Some(DepositStatus::Credited { .. }) = db.get_deposit_status(out_point) {
+            continue;
+        }
+        db.set_deposit_status(out_point, status)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::ops::Shr;
@@ -1519,7 +1970,7 @@ mod test {
 
         // Retry count 0, package whole mem block
         let (mem_block_out, post_block_state) =
-            MemPool::package_mem_block(&mem_block, &OutputParam { retry_count: 0 });
+            MemPool::package_mem_block(&mem_block, &OutputParam::new(0));
         let expected_block = &mem_block;
 
         // Check output mem block
@@ -1577,7 +2028,7 @@ mod test {
         let remain = total.shr(1);
         assert!(remain > 0usize);
 
-        let output_param = OutputParam { retry_count: 1 };
+        let output_param = OutputParam::new(1);
         let (mem_block_out, post_block_state) =
             MemPool::package_mem_block(&mem_block, &output_param);
 
@@ -1595,7 +2046,7 @@ mod test {
         let remain = total.shr(2);
         assert!(remain > 0usize);
 
-        let output_param = OutputParam { retry_count: 2 };
+        let output_param = OutputParam::new(2);
         let (mem_block_out, post_block_state) =
             MemPool::package_mem_block(&mem_block, &output_param);
 
@@ -1613,7 +2064,7 @@ mod test {
         let remain = total.shr(3);
         assert!(remain > 0usize);
 
-        let output_param = OutputParam { retry_count: 3 };
+        let output_param = OutputParam::new(3);
         let (mem_block_out, post_block_state) =
             MemPool::package_mem_block(&mem_block, &output_param);
 
@@ -1633,7 +2084,7 @@ mod test {
             let remain = total.shr(retry_count);
             assert!(remain > 0usize);
 
-            let output_param = OutputParam { retry_count };
+            let output_param = OutputParam::new(retry_count);
             let (mem_block_out, post_block_state) =
                 MemPool::package_mem_block(&mem_block, &output_param);
 
@@ -1654,7 +2105,7 @@ mod test {
         let remain = total.shr(10);
         assert_eq!(remain, 0usize);
 
-        let output_param = OutputParam { retry_count: 10 };
+        let output_param = OutputParam::new(10);
         let (mem_block_out, post_block_state) =
             MemPool::package_mem_block(&mem_block, &output_param);
 
@@ -1670,6 +2121,30 @@ mod test {
 
         assert_eq!(mem_block_out.cmp(&expected_block), MemBlockCmp::Same);
         assert_eq!(post_block_state, expected_post_state);
+
+        // Max withdrawals drops deposits and txs too
+        let output_param = OutputParam::new(0).max_withdrawals(20);
+        let (mem_block_out, post_block_state) =
+            MemPool::package_mem_block(&mem_block, &output_param);
+
+        let (withdrawals_count, deposits_count, txs_count) =
+            repackage_count(&mem_block, &output_param);
+        assert_eq!(withdrawals_count, 20);
+        assert_eq!(deposits_count, 0);
+        assert_eq!(txs_count, 0);
+
+        let (expected_block, expected_post_state) =
+            repackage(withdrawals_count, deposits_count, txs_count);
+
+        assert_eq!(mem_block_out.cmp(&expected_block), MemBlockCmp::Same);
+        assert_eq!(post_block_state, expected_post_state);
+
+        // Max withdrawals above the packaged withdrawals changes nothing
+        let output_param = OutputParam::new(0).max_withdrawals(withdrawals.len());
+        assert_eq!(
+            repackage_count(&mem_block, &output_param),
+            repackage_count(&mem_block, &OutputParam::new(0))
+        );
     }
 
     fn random_hash() -> H256 {
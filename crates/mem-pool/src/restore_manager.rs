@@ -1,20 +1,42 @@
 use crate::mem_block::MemBlock;
 
 use anyhow::Result;
+use gw_types::h256::H256;
 use gw_types::packed;
 use gw_types::prelude::Entity;
 
+use std::collections::HashSet;
+use std::convert::TryInto;
 use std::ffi::OsStr;
-use std::fs::{create_dir_all, read, read_dir, remove_file, write};
+use std::fs::{create_dir_all, read, read_dir, remove_file, rename, write, File, OpenOptions};
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 const MEM_BLOCK_FILENAME_PREFIX: &str = "mem_block_timestamp_";
 const ONE_HOUR: Duration = Duration::from_secs(60 * 60);
 
+/// Write-ahead journal of the current mem block, so txs and withdrawals
+/// pushed since the last saved mem block survive a crash.
+///
+/// Records are a kind byte followed by a 32 bytes hash.
+const JOURNAL_FILENAME: &str = "mem_block_journal";
+const JOURNAL_RECORD_LEN: usize = 1 + 32;
+const JOURNAL_TX: u8 = 0;
+const JOURNAL_WITHDRAWAL: u8 = 1;
+
+/// Tx and withdrawal hashes replayed from the journal, in push order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct JournalEntries {
+    pub txs: Vec<H256>,
+    pub withdrawals: Vec<H256>,
+}
+
 #[derive(Clone)]
 pub struct RestoreManager {
     restore_path: PathBuf,
+    journal: Arc<Mutex<Option<File>>>,
 }
 
 impl RestoreManager {
@@ -23,6 +45,7 @@ impl RestoreManager {
 
         Ok(RestoreManager {
             restore_path: restore_path.as_ref().to_owned(),
+            journal: Default::default(),
         })
     }
 
@@ -171,6 +194,86 @@ impl RestoreManager {
         }
     }
 
+    pub fn journal_tx(&self, tx_hash: &H256) -> Result<()> {
+        self.append_journal(JOURNAL_TX, tx_hash)
+    }
+
+    pub fn journal_withdrawal(&self, withdrawal_hash: &H256) -> Result<()> {
+        self.append_journal(JOURNAL_WITHDRAWAL, withdrawal_hash)
+    }
+
+    fn append_journal(&self, kind: u8, hash: &H256) -> Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        if journal.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.journal_file_path())?;
+            *journal = Some(file);
+        }
+        let mut record = [0u8; JOURNAL_RECORD_LEN];
+        record[0] = kind;
+        record[1..].copy_from_slice(hash.as_slice());
+        journal.as_mut().expect("opened").write_all(&record)?;
+        Ok(())
+    }
+
+    /// Replace the journal with `txs` and `withdrawals`, called when the mem
+    /// block is reset so the journal doesn't grow across blocks.
+    pub fn rewrite_journal<'a>(
+        &self,
+        txs: impl IntoIterator<Item = &'a H256>,
+        withdrawals: impl IntoIterator<Item = &'a H256>,
+    ) -> Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        let tmp_path = self.restore_path.join(format!("{}.tmp", JOURNAL_FILENAME));
+        let records = { withdrawals.into_iter() }
+            .map(|hash| (JOURNAL_WITHDRAWAL, hash))
+            .chain(txs.into_iter().map(|hash| (JOURNAL_TX, hash)));
+        let mut buf = Vec::new();
+        for (kind, hash) in records {
+            buf.push(kind);
+            buf.extend_from_slice(hash.as_slice());
+        }
+        write(&tmp_path, buf)?;
+        // Drop the handle before replacing the file, it's reopened on the
+        // next append.
+        *journal = None;
+        rename(tmp_path, self.journal_file_path())?;
+        Ok(())
+    }
+
+    /// Read the journal, ignoring a trailing partial record of an
+    /// interrupted write and duplicated hashes.
+    pub fn read_journal(&self) -> Result<JournalEntries> {
+        let buf = match read(self.journal_file_path()) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Default::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut entries = JournalEntries::default();
+        let mut seen = HashSet::new();
+        for record in buf.chunks_exact(JOURNAL_RECORD_LEN) {
+            let hash: H256 = record[1..].try_into().expect("32 bytes");
+            let hashes = match record[0] {
+                JOURNAL_TX => &mut entries.txs,
+                JOURNAL_WITHDRAWAL => &mut entries.withdrawals,
+                kind => {
+                    log::warn!("[mem-pool] unknown journal record kind {}", kind);
+                    continue;
+                }
+            };
+            if seen.insert((record[0], hash)) {
+                hashes.push(hash);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn journal_file_path(&self) -> PathBuf {
+        self.restore_path.join(JOURNAL_FILENAME)
+    }
+
     fn block_file_path(&self, timestamp: u128) -> PathBuf {
         let file_name = format!("{}{}", MEM_BLOCK_FILENAME_PREFIX, timestamp);
         let mut file_path = self.restore_path.to_owned();
@@ -192,7 +295,10 @@ mod tests {
 
     use crate::mem_block::MemBlock;
 
-    use super::RestoreManager;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use super::{JournalEntries, RestoreManager, JOURNAL_TX};
 
     #[test]
     fn test_restore_manager() {
@@ -286,4 +392,41 @@ mod tests {
 
         assert_eq!(expected.as_slice(), restored_packed.as_slice());
     }
+
+    #[test]
+    fn test_mem_block_journal() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let restore_manager = RestoreManager::build(&tmp_dir).unwrap();
+        assert_eq!(restore_manager.read_journal().unwrap(), Default::default());
+
+        // Should replay appended hashes in order and skip duplications
+        restore_manager.journal_tx(&[1u8; 32]).unwrap();
+        restore_manager.journal_withdrawal(&[2u8; 32]).unwrap();
+        restore_manager.journal_tx(&[3u8; 32]).unwrap();
+        restore_manager.journal_tx(&[1u8; 32]).unwrap();
+        let expected = JournalEntries {
+            txs: vec![[1u8; 32], [3u8; 32]],
+            withdrawals: vec![[2u8; 32]],
+        };
+        assert_eq!(restore_manager.read_journal().unwrap(), expected);
+
+        // Should ignore a partial record
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(restore_manager.journal_file_path())
+            .unwrap();
+        file.write_all(&[JOURNAL_TX, 4, 4, 4]).unwrap();
+        assert_eq!(restore_manager.read_journal().unwrap(), expected);
+
+        // Should replace the journal on rewrite and keep appending after it
+        restore_manager
+            .rewrite_journal(&[[5u8; 32]], &[[6u8; 32]])
+            .unwrap();
+        restore_manager.journal_tx(&[7u8; 32]).unwrap();
+        let expected = JournalEntries {
+            txs: vec![[5u8; 32], [7u8; 32]],
+            withdrawals: vec![[6u8; 32]],
+        };
+        assert_eq!(restore_manager.read_journal().unwrap(), expected);
+    }
 }
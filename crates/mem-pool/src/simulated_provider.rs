@@ -0,0 +1,258 @@
+//! Mem pool provider without a CKB node, for tests and benchmarks.
+//!
+//! Block times advance by a fixed step on every estimation, deposits are
+//! scripted by the caller, and failures can be injected into the next calls.
+//! `SimulatedProvider` is a handle, clones share the same state, so it can be
+//! scripted after a clone is passed to the mem pool.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use gw_types::offchain::DepositInfo;
+use gw_utils::local_cells::LocalCellsManager;
+
+use crate::traits::MemPoolProvider;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulatedFailure {
+    /// Fail `estimate_next_blocktime` after waiting for the duration.
+    BlocktimeTimeout(Duration),
+    /// Fail `collect_deposit_cells` after waiting for the duration.
+    CollectTimeout(Duration),
+    /// Return deposit cells consumed by local txs, like a lagging indexer.
+    StaleCells,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_blocktime: Duration,
+    blocktime_step: Duration,
+    max_deposits: Option<usize>,
+    deposits: Vec<DepositInfo>,
+    failures: VecDeque<SimulatedFailure>,
+    blocktime_calls: usize,
+    collect_calls: usize,
+}
+
+impl State {
+    /// Take the first injected failure of the call.
+    fn take_failure(
+        &mut self,
+        of_call: impl Fn(&SimulatedFailure) -> bool,
+    ) -> Option<SimulatedFailure> {
+        let index = self.failures.iter().position(of_call)?;
+        self.failures.remove(index)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedProvider {
+    state: Arc<Mutex<State>>,
+}
+
+impl SimulatedProvider {
+    pub fn builder() -> SimulatedProviderBuilder {
+        SimulatedProviderBuilder::default()
+    }
+
+    /// Set the next estimated block time.
+    pub fn set_blocktime(&self, blocktime: Duration) {
+        self.state.lock().unwrap().next_blocktime = blocktime;
+    }
+
+    /// Add live deposit cells, returned until `remove_deposits` or consumed
+    /// by local txs.
+    pub fn push_deposits(&self, deposits: impl IntoIterator<Item = DepositInfo>) {
+        self.state.lock().unwrap().deposits.extend(deposits);
+    }
+
+    /// Remove deposit cells, e.g. consumed by submitted blocks.
+    pub fn remove_deposits(&self, f: impl Fn(&DepositInfo) -> bool) {
+        self.state.lock().unwrap().deposits.retain(|d| !f(d));
+    }
+
+    pub fn clear_deposits(&self) {
+        self.state.lock().unwrap().deposits.clear();
+    }
+
+    /// Inject a failure into the next call it applies to.
+    pub fn inject_failure(&self, failure: SimulatedFailure) {
+        self.state.lock().unwrap().failures.push_back(failure);
+    }
+
+    /// Number of `estimate_next_blocktime` calls.
+    pub fn blocktime_calls(&self) -> usize {
+        self.state.lock().unwrap().blocktime_calls
+    }
+
+    /// Number of `collect_deposit_cells` calls.
+    pub fn collect_calls(&self) -> usize {
+        self.state.lock().unwrap().collect_calls
+    }
+}
+
+#[async_trait]
+impl MemPoolProvider for SimulatedProvider {
+    async fn estimate_next_blocktime(&self) -> Result<Duration> {
+        let failure = {
+            let mut state = self.state.lock().unwrap();
+            state.blocktime_calls += 1;
+            state.take_failure(|f| matches!(f, SimulatedFailure::BlocktimeTimeout(_)))
+        };
+        if let Some(SimulatedFailure::BlocktimeTimeout(duration)) = failure {
+            tokio::time::sleep(duration).await;
+            bail!("simulated estimate next blocktime timeout");
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let blocktime = state.next_blocktime;
+        state.next_blocktime = blocktime + state.blocktime_step;
+        Ok(blocktime)
+    }
+
+    async fn collect_deposit_cells(
+        &self,
+        local_cells_manager: &LocalCellsManager,
+    ) -> Result<Vec<DepositInfo>> {
+        let failure = {
+            let mut state = self.state.lock().unwrap();
+            state.collect_calls += 1;
+            state.take_failure(|f| {
+                matches!(
+                    f,
+                    SimulatedFailure::CollectTimeout(_) | SimulatedFailure::StaleCells
+                )
+            })
+        };
+        if let Some(SimulatedFailure::CollectTimeout(duration)) = failure {
+            tokio::time::sleep(duration).await;
+            bail!("simulated collect deposit cells timeout");
+        }
+
+        let include_dead = failure == Some(SimulatedFailure::StaleCells);
+        let state = self.state.lock().unwrap();
+        let deposits = { state.deposits.iter() }
+            .filter(|d| include_dead || !local_cells_manager.is_dead(&d.cell.out_point))
+            .take(state.max_deposits.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        Ok(deposits)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SimulatedProviderBuilder {
+    state: State,
+}
+
+impl SimulatedProviderBuilder {
+    /// The first estimated block time. Default is 0.
+    pub fn blocktime(mut self, blocktime: Duration) -> Self {
+        self.state.next_blocktime = blocktime;
+        self
+    }
+
+    /// Block time advanced on every estimation. Default is 0.
+    pub fn blocktime_step(mut self, step: Duration) -> Self {
+        self.state.blocktime_step = step;
+        self
+    }
+
+    /// Maximum number of deposits collected at a time. Default is unlimited.
+    pub fn max_deposits(mut self, max_deposits: usize) -> Self {
+        self.state.max_deposits = Some(max_deposits);
+        self
+    }
+
+    pub fn deposits(mut self, deposits: Vec<DepositInfo>) -> Self {
+        self.state.deposits = deposits;
+        self
+    }
+
+    pub fn failure(mut self, failure: SimulatedFailure) -> Self {
+        self.state.failures.push_back(failure);
+        self
+    }
+
+    pub fn build(self) -> SimulatedProvider {
+        SimulatedProvider {
+            state: Arc::new(Mutex::new(self.state)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use gw_types::offchain::{CellInfo, DepositInfo};
+    use gw_types::packed::OutPoint;
+    use gw_types::prelude::{Builder, Entity, Pack};
+    use gw_utils::local_cells::LocalCellsManager;
+
+    use super::{SimulatedFailure, SimulatedProvider};
+    use crate::traits::MemPoolProvider;
+
+    fn deposit(index: u32) -> DepositInfo {
+        DepositInfo {
+            cell: CellInfo {
+                out_point: OutPoint::new_builder().index(index.pack()).build(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_simulated_blocktime() {
+        let provider = SimulatedProvider::builder()
+            .blocktime(Duration::from_secs(100))
+            .blocktime_step(Duration::from_secs(2))
+            .failure(SimulatedFailure::BlocktimeTimeout(Duration::ZERO))
+            .build();
+
+        assert!(provider.estimate_next_blocktime().await.is_err());
+        let blocktime = provider.estimate_next_blocktime().await.unwrap();
+        assert_eq!(blocktime, Duration::from_secs(100));
+        let blocktime = provider.estimate_next_blocktime().await.unwrap();
+        assert_eq!(blocktime, Duration::from_secs(102));
+        assert_eq!(provider.blocktime_calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_deposits() {
+        let provider = SimulatedProvider::builder().max_deposits(2).build();
+        provider.push_deposits(vec![deposit(0), deposit(1), deposit(2)]);
+
+        let mut local_cells_manager = LocalCellsManager::default();
+        local_cells_manager.lock_cell(deposit(0).cell.out_point);
+        let indexes = |deposits: Vec<DepositInfo>| -> Vec<Vec<u8>> {
+            { deposits.into_iter() }
+                .map(|d| d.cell.out_point.index().as_slice().to_vec())
+                .collect()
+        };
+
+        let deposits = provider
+            .collect_deposit_cells(&local_cells_manager)
+            .await
+            .unwrap();
+        assert_eq!(indexes(deposits), indexes(vec![deposit(1), deposit(2)]));
+
+        provider.inject_failure(SimulatedFailure::StaleCells);
+        let deposits = provider
+            .collect_deposit_cells(&local_cells_manager)
+            .await
+            .unwrap();
+        assert_eq!(indexes(deposits), indexes(vec![deposit(0), deposit(1)]));
+
+        provider.inject_failure(SimulatedFailure::CollectTimeout(Duration::ZERO));
+        let result = provider.collect_deposit_cells(&local_cells_manager).await;
+        assert!(result.is_err());
+        assert_eq!(provider.collect_calls(), 3);
+    }
+}
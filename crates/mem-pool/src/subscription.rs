@@ -0,0 +1,50 @@
+//! Broadcast channels of new blocks and pending txs, for RPC subscriptions.
+
+use gw_types::{h256::H256, packed::L2Block};
+use tokio::sync::broadcast;
+
+/// Capacity of each channel. Slow subscribers lag behind and miss messages
+/// beyond this.
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+pub struct Subscriptions {
+    new_blocks: broadcast::Sender<L2Block>,
+    pending_txs: broadcast::Sender<H256>,
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        let (new_blocks, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (pending_txs, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            new_blocks,
+            pending_txs,
+        }
+    }
+}
+
+impl Subscriptions {
+    /// Blocks attached to the canonical chain, in order.
+    pub fn subscribe_new_blocks(&self) -> broadcast::Receiver<L2Block> {
+        self.new_blocks.subscribe()
+    }
+
+    /// Hashes of txs accepted by the mem pool.
+    pub fn subscribe_pending_txs(&self) -> broadcast::Receiver<H256> {
+        self.pending_txs.subscribe()
+    }
+
+    pub(crate) fn has_new_block_subscribers(&self) -> bool {
+        self.new_blocks.receiver_count() > 0
+    }
+
+    pub(crate) fn notify_new_block(&self, block: L2Block) {
+        // Err only if there are no subscribers.
+        let _ = self.new_blocks.send(block);
+    }
+
+    pub(crate) fn notify_pending_tx(&self, tx_hash: H256) {
+        let _ = self.pending_txs.send(tx_hash);
+    }
+}
@@ -2,8 +2,11 @@ use std::time::Duration;
 
 use anyhow::Result;
 use async_trait::async_trait;
-use gw_types::offchain::DepositInfo;
-use gw_utils::local_cells::LocalCellsManager;
+use gw_types::{
+    offchain::{CellInfo, CompatibleFinalizedTimepoint, DepositInfo},
+    packed::WithdrawalRequest,
+};
+use gw_utils::{local_cells::LocalCellsManager, RollupContext};
 
 #[async_trait]
 pub trait MemPoolProvider {
@@ -12,4 +15,15 @@ pub trait MemPoolProvider {
         &self,
         local_cells_manager: &LocalCellsManager,
     ) -> Result<Vec<DepositInfo>>;
+    /// Finalized custodian cells to cover `withdrawals`, `None` if the provider
+    /// doesn't track custodian cells.
+    async fn query_finalized_custodians(
+        &self,
+        _rollup_context: &RollupContext,
+        _withdrawals: Vec<WithdrawalRequest>,
+        _compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+        _local_cells_manager: &LocalCellsManager,
+    ) -> Result<Option<Vec<CellInfo>>> {
+        Ok(None)
+    }
 }
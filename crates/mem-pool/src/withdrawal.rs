@@ -3,17 +3,49 @@ use gw_generator::generator::WithdrawalCellError;
 use gw_types::{
     bytes::Bytes,
     h256::*,
-    offchain::FinalizedCustodianCapacity,
+    offchain::{CellInfo, FinalizedCustodianCapacity},
     packed::{CellOutput, L2Block, Script, WithdrawalRequest, WithdrawalRequestExtra},
     prelude::*,
 };
 use gw_utils::{finalized_timepoint, RollupContext};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use crate::custodian::{
     build_finalized_custodian_lock, calc_ckb_custodian_min_capacity, generate_finalized_custodian,
+    pick_custodians, sum_withdrawals,
 };
 
+/// Finalized custodians can't cover a withdrawal. Unlike other withdrawal
+/// errors, it may pass once more custodians are finalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustodianNotEnough {
+    /// No finalized custodian of the withdrawal sUDT
+    NoSudtCustodian,
+    Sudt,
+    Ckb,
+    /// Covering the withdrawal takes more custodian inputs than allowed
+    Inputs,
+}
+
+impl fmt::Display for CustodianNotEnough {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSudtCustodian => write!(f, "no finalized simple UDT custodian to withdraw"),
+            Self::Sudt => write!(
+                f,
+                "Finalized simple UDT custodian cell is not enough to withdraw"
+            ),
+            Self::Ckb => write!(f, "Finalized CKB custodian cell is not enough to withdraw"),
+            Self::Inputs => write!(
+                f,
+                "Finalized custodian cells to withdraw exceed max custodian inputs"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CustodianNotEnough {}
+
 #[derive(Clone)]
 struct CkbCustodian {
     capacity: u128,
@@ -27,11 +59,18 @@ struct SudtCustodian {
     script: Script,
 }
 
+struct CustodianInputs {
+    cells: Vec<CellInfo>,
+    max_inputs: usize,
+    included: Vec<WithdrawalRequest>,
+}
+
 pub struct Generator<'a> {
     rollup_context: &'a RollupContext,
     ckb_custodian: CkbCustodian,
     sudt_custodians: HashMap<[u8; 32], SudtCustodian>,
     withdrawals: Vec<(CellOutput, Bytes)>,
+    custodian_inputs: Option<CustodianInputs>,
 }
 
 impl<'a> Generator<'a> {
@@ -74,9 +113,23 @@ impl<'a> Generator<'a> {
             ckb_custodian,
             sudt_custodians,
             withdrawals: Default::default(),
+            custodian_inputs: None,
         }
     }
 
+    /// Also verify that included withdrawals can be covered by at most
+    /// `max_inputs` of the finalized custodian `cells`.
+    pub fn with_custodian_inputs(mut self, cells: Vec<CellInfo>, max_inputs: usize) -> Self {
+        if cells.len() > max_inputs {
+            self.custodian_inputs = Some(CustodianInputs {
+                cells,
+                max_inputs,
+                included: Vec::new(),
+            });
+        }
+        self
+    }
+
     pub fn remaining_capacity(self) -> FinalizedCustodianCapacity {
         FinalizedCustodianCapacity {
             capacity: self.ckb_custodian.capacity
@@ -107,7 +160,7 @@ impl<'a> Generator<'a> {
         let req_sudt: u128 = req.raw().amount().unpack();
         let sudt_type_hash: [u8; 32] = req.raw().sudt_script_hash().unpack();
         if 0 != req_sudt && !self.sudt_custodians.contains_key(&sudt_type_hash) {
-            return Err(anyhow!("no finalized sudt custodian for {}", req));
+            return Err(CustodianNotEnough::NoSudtCustodian.into());
         }
 
         // Verify minimal capacity
@@ -149,19 +202,13 @@ impl<'a> Generator<'a> {
         if 0 != req_sudt {
             let sudt_custodian = match self.sudt_custodians.get(&sudt_type_hash) {
                 Some(custodian) => custodian,
-                None => {
-                    return Err(anyhow!(
-                        "Finalized simple UDT custodian cell is not enough to withdraw"
-                    ))
-                }
+                None => return Err(CustodianNotEnough::Sudt.into()),
             };
 
             let remained = sudt_custodian
                 .balance
                 .checked_sub(req_sudt)
-                .ok_or_else(|| {
-                    anyhow!("Finalized simple UDT custodian cell is not enough to withdraw")
-                })?;
+                .ok_or(CustodianNotEnough::Sudt)?;
 
             // Consume all remained sudt, give sudt custodian capacity back to ckb custodian
             if 0 == remained {
@@ -184,9 +231,22 @@ impl<'a> Generator<'a> {
             // Consume all remained ckb
             None if req_ckb == ckb_custodian.capacity => Ok(()),
             // No able to cover withdrawal cell and ckb custodian change
-            None => Err(anyhow!(
-                "Finalized CKB custodian cell is not enough to withdraw"
-            )),
+            None => Err(CustodianNotEnough::Ckb.into()),
+        }
+    }
+
+    pub fn verify_custodian_inputs(&self, req: &WithdrawalRequest) -> Result<()> {
+        let inputs = match self.custodian_inputs {
+            Some(ref inputs) => inputs,
+            None => return Ok(()),
+        };
+
+        let withdrawals = inputs.included.iter().chain(std::iter::once(req));
+        let withdrawals_amount = sum_withdrawals(withdrawals.cloned());
+        let cells = inputs.cells.clone();
+        match pick_custodians(self.rollup_context, cells, &withdrawals_amount) {
+            Ok((picked, _rest)) if picked.len() <= inputs.max_inputs => Ok(()),
+            _ => Err(CustodianNotEnough::Inputs.into()),
         }
     }
 
@@ -196,10 +256,11 @@ impl<'a> Generator<'a> {
         block: &L2Block,
     ) -> Result<()> {
         let verified_output = self.verified_output(req_extra, block)?;
+        let req = req_extra.request();
+        self.verify_custodian_inputs(&req)?;
         let ckb_custodian = &mut self.ckb_custodian;
 
         // Update custodians according to verified output
-        let req = req_extra.request();
         let req_sudt: u128 = req.raw().amount().unpack();
         if 0 != req_sudt {
             let sudt_type_hash: [u8; 32] = req.raw().sudt_script_hash().unpack();
@@ -241,6 +302,9 @@ impl<'a> Generator<'a> {
             None => return Err(anyhow!("unexpected capacity overflow for verified {}", req)),
         }
 
+        if let Some(ref mut inputs) = self.custodian_inputs {
+            inputs.included.push(req);
+        }
         self.withdrawals.push(verified_output);
         Ok(())
     }
@@ -319,15 +383,16 @@ mod test {
 
     use gw_types::core::Timepoint;
     use gw_types::h256::*;
-    use gw_types::offchain::FinalizedCustodianCapacity;
+    use gw_types::offchain::{CellInfo, FinalizedCustodianCapacity};
     use gw_types::packed::{
-        L2Block, RawWithdrawalRequest, RollupConfig, Script, WithdrawalRequest,
+        CellOutput, L2Block, RawWithdrawalRequest, RollupConfig, Script, WithdrawalRequest,
         WithdrawalRequestExtra,
     };
     use gw_types::prelude::{Builder, Entity, Pack, Unpack};
     use gw_utils::RollupContext;
 
-    use crate::withdrawal::Generator;
+    use crate::custodian::custodian_capacity;
+    use crate::withdrawal::{CustodianNotEnough, Generator};
 
     #[test]
     fn test_withdrawal_generator() {
@@ -427,6 +492,7 @@ mod test {
             .verified_output(&err_req_extra, &block)
             .unwrap_err();
         assert!(err.to_string().contains("owner lock not match hash"));
+        assert!(err.downcast_ref::<CustodianNotEnough>().is_none());
 
         // ## include_and_verify() and finish()
         generator.include_and_verify(&req_extra, &block).unwrap();
@@ -441,4 +507,69 @@ mod test {
         let (output, _data) = outputs.get(2).unwrap(); // the second is sudt change
         assert_eq!(output.capacity().unpack(), u64::MAX - 1);
     }
+
+    #[test]
+    fn test_withdrawal_generator_custodian_inputs() {
+        const CKB: u64 = 100_000_000;
+
+        let rollup_context = RollupContext {
+            rollup_script_hash: H256::from_u32(1),
+            rollup_config: RollupConfig::new_builder()
+                .withdrawal_script_type_hash(H256::from_u32(100).pack())
+                .custodian_script_type_hash(H256::from_u32(101).pack())
+                .build(),
+            ..Default::default()
+        };
+
+        let cells: Vec<_> = (1..=10)
+            .map(|i| CellInfo {
+                output: CellOutput::new_builder()
+                    .capacity((i * 100 * CKB).pack())
+                    .build(),
+                ..Default::default()
+            })
+            .collect();
+        let available_custodians = FinalizedCustodianCapacity {
+            capacity: cells.iter().map(custodian_capacity).sum(),
+            ..Default::default()
+        };
+
+        let owner_lock = Script::new_builder()
+            .code_hash(H256::from_u32(4).pack())
+            .args(vec![5; 32].pack())
+            .build();
+        let req_extra = {
+            let raw = RawWithdrawalRequest::new_builder()
+                .capacity((1000 * CKB).pack())
+                .account_script_hash(H256::from_u32(10).pack())
+                .owner_lock_hash(owner_lock.hash().pack())
+                .build();
+            WithdrawalRequestExtra::new_builder()
+                .request(WithdrawalRequest::new_builder().raw(raw).build())
+                .owner_lock(owner_lock)
+                .build()
+        };
+        let block = L2Block::default();
+
+        // The third withdrawal needs a fourth custodian input.
+        let mut generator = Generator::new(&rollup_context, available_custodians.clone())
+            .with_custodian_inputs(cells.clone(), 3);
+        generator.include_and_verify(&req_extra, &block).unwrap();
+        generator.include_and_verify(&req_extra, &block).unwrap();
+        let err = generator
+            .include_and_verify(&req_extra, &block)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CustodianNotEnough>(),
+            Some(&CustodianNotEnough::Inputs)
+        );
+        assert_eq!(generator.withdrawals().len(), 2);
+
+        // No limit if all cells fit.
+        let mut generator =
+            Generator::new(&rollup_context, available_custodians).with_custodian_inputs(cells, 10);
+        for _ in 0..3 {
+            generator.include_and_verify(&req_extra, &block).unwrap();
+        }
+    }
 }
@@ -0,0 +1,148 @@
+//! Batched DB writes of pushed txs, configured by `mem_pool.write_batch`.
+//!
+//! Pushed txs and their receipts are written to one transaction, which is
+//! committed once it has `max_txs` txs or its first tx was written
+//! `flush_interval_ms` ago. The mem pool also flushes it before anything
+//! reading mem pool txs from the DB, e.g. resetting and producing blocks.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use gw_config::MemPoolWriteBatchConfig;
+use gw_store::{transaction::StoreTransaction, Store};
+use tokio::sync::Mutex;
+
+use crate::pool::MemPool;
+
+pub struct WriteBatch {
+    config: MemPoolWriteBatchConfig,
+    /// The uncommitted transaction, `None` if it's taken or there's none.
+    db: Option<StoreTransaction>,
+    /// Txs written to the uncommitted transaction.
+    txs: usize,
+    /// When the first tx was written.
+    since: Option<Instant>,
+}
+
+impl WriteBatch {
+    pub fn new(config: MemPoolWriteBatchConfig) -> Self {
+        Self {
+            config,
+            db: None,
+            txs: 0,
+            since: None,
+        }
+    }
+
+    /// Take the uncommitted transaction, or begin a new one. It must be given
+    /// back with `end`.
+    pub fn begin(&mut self, store: &Store) -> StoreTransaction {
+        match self.db.take() {
+            Some(db) => db,
+            None => store.begin_transaction_with_sync(self.config.sync),
+        }
+    }
+
+    /// Give back the transaction taken by `begin`, a tx is written to it if
+    /// `written`. Commits if the batch is full or due.
+    pub fn end(&mut self, db: StoreTransaction, written: bool) -> Result<()> {
+        if written {
+            self.txs += 1;
+            self.since.get_or_insert_with(Instant::now);
+        }
+        if self.txs == 0 {
+            // Nothing to commit, don't keep the snapshot.
+            return Ok(());
+        }
+        self.db = Some(db);
+        if self.is_due() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the batch is full or its first tx was written long enough ago.
+    pub fn is_due(&self) -> bool {
+        match self.since {
+            Some(since) => {
+                self.txs >= self.config.max_txs
+                    || since.elapsed() >= Duration::from_millis(self.config.flush_interval_ms)
+            }
+            None => false,
+        }
+    }
+
+    /// Commit written txs.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(mut db) = self.db.take() {
+            db.commit()?;
+            gw_metrics::mem_pool()
+                .write_batch_txs
+                .observe(self.txs as f64);
+        }
+        self.txs = 0;
+        self.since = None;
+        Ok(())
+    }
+}
+
+/// Flush the write batch of the mem pool every `flush_interval_ms`, forever.
+pub async fn flush_periodically(mem_pool: Arc<Mutex<MemPool>>, config: MemPoolWriteBatchConfig) {
+    let interval = Duration::from_millis(config.flush_interval_ms.max(1));
+    loop {
+        tokio::time::sleep(interval).await;
+        let mut mem_pool = mem_pool.lock().await;
+        if let Err(err) = mem_pool.flush_writes() {
+            log::error!("[mem-pool] flush write batch error {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_store::traits::chain_store::ChainStore;
+    use gw_types::{h256::*, packed::L2Transaction};
+
+    use super::*;
+
+    fn push(batch: &mut WriteBatch, store: &Store, n: u32) {
+        let mut db = batch.begin(store);
+        db.insert_mem_pool_transaction(&H256::from_u32(n), L2Transaction::default())
+            .unwrap();
+        batch.end(db, true).unwrap();
+    }
+
+    fn is_committed(store: &Store, n: u32) -> bool {
+        let snap = store.get_snapshot();
+        let tx = snap.get_mem_pool_transaction(&H256::from_u32(n));
+        tx.unwrap().is_some()
+    }
+
+    #[test]
+    fn test_write_batch() {
+        let store = Store::open_tmp().unwrap();
+        let mut batch = WriteBatch::new(MemPoolWriteBatchConfig {
+            max_txs: 2,
+            flush_interval_ms: 60_000,
+            sync: false,
+        });
+
+        push(&mut batch, &store, 1);
+        // A failed push.
+        let db = batch.begin(&store);
+        batch.end(db, false).unwrap();
+        assert!(!is_committed(&store, 1));
+
+        // Full.
+        push(&mut batch, &store, 2);
+        assert!(is_committed(&store, 1) && is_committed(&store, 2));
+
+        push(&mut batch, &store, 3);
+        assert!(!is_committed(&store, 3));
+        batch.flush().unwrap();
+        assert!(is_committed(&store, 3));
+    }
+}
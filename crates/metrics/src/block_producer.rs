@@ -1,6 +1,9 @@
 use gw_telemetry::metric::{
     counter::Counter,
+    encoding::text::Encode,
+    family::Family,
     gauge::Gauge,
+    histogram::{exponential_buckets, Histogram},
     registry::{Registry, Unit},
     Lazy,
 };
@@ -12,14 +15,74 @@ pub fn block_producer() -> &'static BlockProducerMetrics {
     &BLOCK_PRODUCER_METRICS
 }
 
-#[derive(Default)]
 pub struct BlockProducerMetrics {
     pub resend: Counter,
+    pub fee_bumps: Counter,
+    pub contracts_dep_refreshes: Counter,
     pub witness_size: Counter,
     pub tx_size: Counter,
     pub sync_buffer_len: Gauge,
     pub local_blocks: Gauge,
     pub submitted_blocks: Gauge,
+    /// Seconds spent on producing a local block.
+    pub produce_duration: Histogram,
+    /// Seconds from starting to submit a block to the submission tx being
+    /// accepted, including retries.
+    pub submit_duration: Histogram,
+    /// Seconds spent on waiting for the next submitted block to be confirmed.
+    pub confirm_duration: Histogram,
+    /// 1 if block production is paused by `psc_config.local_limit`.
+    pub production_paused: Gauge,
+    pub skipped_empty_blocks: Counter,
+    pub custodian_inputs: Gauge,
+    pub merged_custodians: Counter,
+    pub custodian_merge_full: Counter,
+    pub submission_success: Counter,
+    pub submission_failure: Counter,
+    pub funding_wallet_failovers: Counter,
+    pub bad_blocks: Counter,
+    pub challenges: Counter,
+    pub locked_stake: Gauge,
+    pub reclaimable_stake: Gauge,
+    pub reclaimed_stake_cells: Counter,
+    /// Txs written to disk instead of being sent in dry run mode.
+    pub dry_run_txs: Counter,
+    wallet_balance: Family<WalletLabel, Gauge>,
+}
+
+impl Default for BlockProducerMetrics {
+    fn default() -> Self {
+        Self {
+            resend: Counter::default(),
+            fee_bumps: Counter::default(),
+            contracts_dep_refreshes: Counter::default(),
+            witness_size: Counter::default(),
+            tx_size: Counter::default(),
+            sync_buffer_len: Gauge::default(),
+            local_blocks: Gauge::default(),
+            submitted_blocks: Gauge::default(),
+            // 10ms ~ 160s
+            produce_duration: Histogram::new(exponential_buckets(0.01, 2.0, 15)),
+            // 100ms ~ 1600s
+            submit_duration: Histogram::new(exponential_buckets(0.1, 2.0, 15)),
+            confirm_duration: Histogram::new(exponential_buckets(0.1, 2.0, 15)),
+            production_paused: Gauge::default(),
+            skipped_empty_blocks: Counter::default(),
+            custodian_inputs: Gauge::default(),
+            merged_custodians: Counter::default(),
+            custodian_merge_full: Counter::default(),
+            submission_success: Counter::default(),
+            submission_failure: Counter::default(),
+            funding_wallet_failovers: Counter::default(),
+            bad_blocks: Counter::default(),
+            challenges: Counter::default(),
+            locked_stake: Gauge::default(),
+            reclaimable_stake: Gauge::default(),
+            reclaimed_stake_cells: Counter::default(),
+            dry_run_txs: Counter::default(),
+            wallet_balance: Family::default(),
+        }
+    }
 }
 
 impl BlockProducerMetrics {
@@ -36,6 +99,16 @@ impl BlockProducerMetrics {
                 "Number of times resending submission transactions",
                 Box::new(self.resend.clone()),
             );
+            registry.register(
+                "fee_bumps",
+                "Number of times rebuilding submission transactions with higher fee rates",
+                Box::new(self.fee_bumps.clone()),
+            );
+            registry.register(
+                "contracts_dep_refreshes",
+                "Number of times refreshing contracts cell deps after submission txs failed to resolve them",
+                Box::new(self.contracts_dep_refreshes.clone()),
+            );
             registry.register_with_unit(
                 "witness_size",
                 "Block submission txs witness size",
@@ -58,6 +131,124 @@ impl BlockProducerMetrics {
                 "Number of submitted blocks",
                 Box::new(self.submitted_blocks.clone()),
             );
+            registry.register_with_unit(
+                "produce_duration",
+                "Time spent on producing a local block",
+                Unit::Seconds,
+                Box::new(self.produce_duration.clone()),
+            );
+            registry.register_with_unit(
+                "submit_duration",
+                "Time from starting to submit a block to the submission tx being accepted",
+                Unit::Seconds,
+                Box::new(self.submit_duration.clone()),
+            );
+            registry.register_with_unit(
+                "confirm_duration",
+                "Time spent on waiting for the next submitted block to be confirmed on L1",
+                Unit::Seconds,
+                Box::new(self.confirm_duration.clone()),
+            );
+            registry.register(
+                "production_paused",
+                "1 if block production is paused because there are too many local blocks",
+                Box::new(self.production_paused.clone()),
+            );
+            registry.register(
+                "skipped_empty_blocks",
+                "Number of times skipping an empty block before the keep-alive interval",
+                Box::new(self.skipped_empty_blocks.clone()),
+            );
+            registry.register(
+                "custodian_inputs",
+                "Number of finalized custodian inputs in the last submission tx",
+                Box::new(self.custodian_inputs.clone()),
+            );
+            registry.register(
+                "merged_custodians",
+                "Number of finalized custodian cells merged in submission txs",
+                Box::new(self.merged_custodians.clone()),
+            );
+            registry.register(
+                "custodian_merge_full",
+                "Number of submission txs hitting the custodian input limit",
+                Box::new(self.custodian_merge_full.clone()),
+            );
+            registry.register(
+                "submission_success",
+                "Number of submission txs accepted by the L1 node",
+                Box::new(self.submission_success.clone()),
+            );
+            registry.register(
+                "submission_failure",
+                "Number of submission txs rejected by the L1 node",
+                Box::new(self.submission_failure.clone()),
+            );
+            registry.register(
+                "funding_wallet_failovers",
+                "Number of times skipping a funding wallet that failed to pay the fee",
+                Box::new(self.funding_wallet_failovers.clone()),
+            );
+            registry.register(
+                "bad_blocks",
+                "Number of bad blocks found by the challenger",
+                Box::new(self.bad_blocks.clone()),
+            );
+            registry.register(
+                "challenges",
+                "Number of challenge txs sent by the challenger",
+                Box::new(self.challenges.clone()),
+            );
+            registry.register(
+                "locked_stake",
+                "Capacity of stake cells locked by the block producer in shannons",
+                Box::new(self.locked_stake.clone()),
+            );
+            registry.register(
+                "reclaimable_stake",
+                "Capacity of finalized stake cells to reclaim in shannons",
+                Box::new(self.reclaimable_stake.clone()),
+            );
+            registry.register(
+                "reclaimed_stake_cells",
+                "Number of stake cells reclaimed to the block producer wallet",
+                Box::new(self.reclaimed_stake_cells.clone()),
+            );
+            registry.register(
+                "dry_run_txs",
+                "Number of L1 txs written to disk instead of being sent in dry run mode",
+                Box::new(self.dry_run_txs.clone()),
+            );
+            registry.register(
+                "wallet_balance",
+                "Balance of block producer wallets in shannons",
+                Box::new(self.wallet_balance.clone()),
+            );
+        }
+    }
+
+    pub fn wallet_balance(&self, role: &'static str, lock_hash: &[u8; 32], balance: u64) {
+        let label = WalletLabel {
+            role,
+            lock_hash: *lock_hash,
+        };
+        self.wallet_balance.get_or_create(&label).set(balance);
+    }
+}
+
+#[derive(Hash, Clone, Eq, PartialEq)]
+struct WalletLabel {
+    role: &'static str,
+    lock_hash: [u8; 32],
+}
+
+// Manual impl to encode the lock hash prefix in hex.
+impl Encode for WalletLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "role=\"{}\",wallet=\"0x", self.role)?;
+        for b in &self.lock_hash[..8] {
+            write!(writer, "{:02x}", b)?;
         }
+        write!(writer, "\"")
     }
 }
@@ -1,4 +1,7 @@
 use gw_telemetry::metric::{
+    encoding::text::Encode,
+    family::Family,
+    histogram::{exponential_buckets, Histogram},
     registry::Registry,
     Lazy,
     {counter::Counter, gauge::Gauge},
@@ -10,12 +13,32 @@ pub fn chain() -> &'static ChainMetrics {
     &CHAIN_METRICS
 }
 
-#[derive(Default)]
 pub struct ChainMetrics {
     pub transactions: Counter,
     pub deposits: Counter,
     pub withdrawals: Counter,
     pub block_height: Gauge,
+    /// Number of blocks reverted in a single reorg.
+    pub reorg_depth: Histogram,
+    reverted_blocks: Family<RevertLabel, Counter>,
+    backend_cycles: Family<BackendLabel, Counter>,
+    syscall_cycles: Family<SyscallLabel, Counter>,
+}
+
+impl Default for ChainMetrics {
+    fn default() -> Self {
+        Self {
+            transactions: Counter::default(),
+            deposits: Counter::default(),
+            withdrawals: Counter::default(),
+            block_height: Gauge::default(),
+            // 1 ~ 512 blocks
+            reorg_depth: Histogram::new(exponential_buckets(1.0, 2.0, 10)),
+            reverted_blocks: Family::default(),
+            backend_cycles: Family::default(),
+            syscall_cycles: Family::default(),
+        }
+    }
 }
 
 impl ChainMetrics {
@@ -25,6 +48,16 @@ impl ChainMetrics {
             "Number of the highest known block",
             Box::new(self.block_height.clone()),
         );
+        registry.register(
+            "reorg_depth",
+            "Number of blocks reverted in a reorg",
+            Box::new(self.reorg_depth.clone()),
+        );
+        registry.register(
+            "reverted_blocks",
+            "Number of reverted blocks",
+            Box::new(self.reverted_blocks.clone()),
+        );
 
         if config.node_mode == gw_config::NodeMode::FullNode {
             registry.register(
@@ -42,6 +75,70 @@ impl ChainMetrics {
                 "Number of packaged withdrawals",
                 Box::new(self.withdrawals.clone()),
             );
+            registry.register(
+                "backend_cycles",
+                "Execution cycles of packaged L2 transactions by backend",
+                Box::new(self.backend_cycles.clone()),
+            );
+            registry.register(
+                "syscall_cycles",
+                "Syscall cycles of packaged L2 transactions by syscall class",
+                Box::new(self.syscall_cycles.clone()),
+            );
         }
     }
+
+    /// `kind` is one of `detach`, `rewind` and `revert`.
+    pub fn reverted_blocks(&self, kind: &'static str, count: u64) {
+        self.reverted_blocks
+            .get_or_create(&RevertLabel { kind })
+            .inc_by(count);
+    }
+
+    /// `backend` is the hex validator script type hash of the backend.
+    pub fn backend_cycles(&self, backend: String, cycles: u64) {
+        self.backend_cycles
+            .get_or_create(&BackendLabel { backend })
+            .inc_by(cycles);
+    }
+
+    /// `class` is the syscall class, e.g. `store` or `load_data`.
+    pub fn syscall_cycles(&self, class: String, cycles: u64) {
+        self.syscall_cycles
+            .get_or_create(&SyscallLabel { class })
+            .inc_by(cycles);
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct RevertLabel {
+    kind: &'static str,
+}
+
+impl Encode for RevertLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "kind=\"{}\"", self.kind)
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct BackendLabel {
+    backend: String,
+}
+
+impl Encode for BackendLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "backend=\"{}\"", self.backend)
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct SyscallLabel {
+    class: String,
+}
+
+impl Encode for SyscallLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "class=\"{}\"", self.class)
+    }
 }
@@ -30,12 +30,20 @@ const ENV_METRIC_MONITOR_CUSTODIAN_VEC_JSON: &str = "METRIC_MONITOR_CUSTODIAN_VE
 pub mod block_producer;
 pub mod chain;
 pub mod custodian;
+pub mod mem_pool;
 pub mod rpc;
+pub mod rpc_client;
+pub mod store;
+pub mod withdrawal_unlocker;
 
 pub use block_producer::block_producer;
 pub use chain::chain;
 pub use custodian::custodian;
+pub use mem_pool::mem_pool;
 pub use rpc::rpc;
+pub use rpc_client::rpc_client;
+pub use store::store;
+pub use withdrawal_unlocker::withdrawal_unlocker;
 
 /// Global metrics registry.
 type TextEncodeRegistry = Registry<Box<dyn encoding::text::SendSyncEncodeMetric>>;
@@ -61,7 +69,14 @@ pub fn init(config: &gw_config::Config) {
     block_producer().register(&config, registry.sub_registry_with_prefix("block_producer"));
     chain().register(&config, registry.sub_registry_with_prefix("chain"));
     custodian().register(&config, registry.sub_registry_with_prefix("custodian"));
+    mem_pool().register(&config, registry.sub_registry_with_prefix("mem_pool"));
     rpc().register(&config, registry.sub_registry_with_prefix("rpc"));
+    rpc_client().register(&config, registry.sub_registry_with_prefix("rpc_client"));
+    store().register(&config, registry.sub_registry_with_prefix("store"));
+    withdrawal_unlocker().register(
+        &config,
+        registry.sub_registry_with_prefix("withdrawal_unlocker"),
+    );
 
     METRIC_REGISTRY.store(Arc::new(Some(registry)));
     CONFIG.store(Arc::new(config));
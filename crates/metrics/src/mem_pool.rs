@@ -0,0 +1,159 @@
+use gw_telemetry::metric::{
+    counter::Counter,
+    encoding::text::Encode,
+    family::Family,
+    gauge::Gauge,
+    histogram::{exponential_buckets, Histogram},
+    prometheus_client,
+    registry::{Registry, Unit},
+    Lazy,
+};
+
+static MEM_POOL_METRICS: Lazy<MemPoolMetrics> = Lazy::new(MemPoolMetrics::default);
+
+pub fn mem_pool() -> &'static MemPoolMetrics {
+    &MEM_POOL_METRICS
+}
+
+/// Why the estimated timestamp of the next mem block is not used.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Encode)]
+pub enum TimestampCorrection {
+    /// Estimating failed, the last L1 median time is extrapolated.
+    Extrapolated,
+    /// Not greater than the tip block timestamp, bumped from the tip.
+    BumpedFromTip,
+}
+
+pub struct MemPoolMetrics {
+    /// Seconds spent on pushing a tx, including verification and execution.
+    pub push_tx_duration: Histogram,
+    pub execute_tx_cycles: Histogram,
+    pub reset_duration: Histogram,
+    pub rejected_txs: Counter,
+    pub mem_block_txs: Gauge,
+    /// Mem block txs in percent of `mem_block.max_txs`.
+    pub mem_block_fullness: Gauge,
+    pub mem_block_deposits: Gauge,
+    pub mem_block_withdrawals: Gauge,
+    block_timestamp_corrections: Family<TimestampCorrectionLabel, Counter>,
+    /// Milliseconds the local clock is off from NTP servers.
+    pub clock_skew_ms: Gauge,
+    /// Txs committed to the DB in each batch, see `mem_pool.write_batch`.
+    pub write_batch_txs: Histogram,
+    /// Pending withdrawals dropped, see `mem_pool.pending_withdrawal_ttl_secs`.
+    pub expired_withdrawals: Counter,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct TimestampCorrectionLabel {
+    reason: TimestampCorrection,
+}
+
+impl Default for MemPoolMetrics {
+    fn default() -> Self {
+        Self {
+            // 1ms ~ 16s
+            push_tx_duration: Histogram::new(exponential_buckets(0.001, 2.0, 15)),
+            // 100K ~ 1.6G cycles
+            execute_tx_cycles: Histogram::new(exponential_buckets(100_000.0, 2.0, 15)),
+            // 10ms ~ 160s
+            reset_duration: Histogram::new(exponential_buckets(0.01, 2.0, 15)),
+            rejected_txs: Counter::default(),
+            mem_block_txs: Gauge::default(),
+            mem_block_fullness: Gauge::default(),
+            mem_block_deposits: Gauge::default(),
+            mem_block_withdrawals: Gauge::default(),
+            block_timestamp_corrections: Family::default(),
+            clock_skew_ms: Gauge::default(),
+            // 1 ~ 1024
+            write_batch_txs: Histogram::new(exponential_buckets(1.0, 2.0, 11)),
+            expired_withdrawals: Counter::default(),
+        }
+    }
+}
+
+impl MemPoolMetrics {
+    pub(crate) fn register(&self, config: &crate::Config, registry: &mut Registry) {
+        if config.node_mode != gw_config::NodeMode::FullNode {
+            return;
+        }
+
+        registry.register_with_unit(
+            "push_tx_duration",
+            "Time spent on pushing a tx into the mem pool",
+            Unit::Seconds,
+            Box::new(self.push_tx_duration.clone()),
+        );
+        registry.register(
+            "execute_tx_cycles",
+            "Cycles of executing a tx in the mem pool",
+            Box::new(self.execute_tx_cycles.clone()),
+        );
+        registry.register_with_unit(
+            "reset_duration",
+            "Time spent on resetting the mem pool to a new tip",
+            Unit::Seconds,
+            Box::new(self.reset_duration.clone()),
+        );
+        registry.register(
+            "rejected_txs",
+            "Number of txs rejected by the mem pool",
+            Box::new(self.rejected_txs.clone()),
+        );
+        registry.register(
+            "mem_block_txs",
+            "Number of txs in the mem block",
+            Box::new(self.mem_block_txs.clone()),
+        );
+        registry.register(
+            "mem_block_fullness",
+            "Mem block txs in percent of mem_block.max_txs",
+            Box::new(self.mem_block_fullness.clone()),
+        );
+        registry.register(
+            "mem_block_deposits",
+            "Number of deposits in the mem block",
+            Box::new(self.mem_block_deposits.clone()),
+        );
+        registry.register(
+            "mem_block_withdrawals",
+            "Number of withdrawals in the mem block",
+            Box::new(self.mem_block_withdrawals.clone()),
+        );
+        registry.register(
+            "block_timestamp_corrections",
+            "Number of corrected mem block timestamps",
+            Box::new(self.block_timestamp_corrections.clone()),
+        );
+        registry.register(
+            "clock_skew_ms",
+            "Milliseconds the local clock is off from NTP servers",
+            Box::new(self.clock_skew_ms.clone()),
+        );
+        registry.register(
+            "write_batch_txs",
+            "Number of txs committed to the DB in each batch",
+            Box::new(self.write_batch_txs.clone()),
+        );
+        registry.register(
+            "expired_withdrawals",
+            "Number of pending withdrawals dropped after pending_withdrawal_ttl_secs",
+            Box::new(self.expired_withdrawals.clone()),
+        );
+    }
+
+    pub fn block_timestamp_corrections(&self, reason: TimestampCorrection) -> Counter {
+        self.block_timestamp_corrections
+            .get_or_create(&TimestampCorrectionLabel { reason })
+            .clone()
+    }
+
+    /// Update the mem block gauges.
+    pub fn mem_block(&self, txs: usize, max_txs: usize, deposits: usize, withdrawals: usize) {
+        self.mem_block_txs.set(txs as u64);
+        self.mem_block_fullness
+            .set((txs * 100 / max_txs.max(1)) as u64);
+        self.mem_block_deposits.set(deposits as u64);
+        self.mem_block_withdrawals.set(withdrawals as u64);
+    }
+}
@@ -15,10 +15,32 @@ pub enum RequestKind {
     Withdrawal,
 }
 
+/// Class of JSONRPC methods with separate concurrency limits.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Encode)]
+pub enum MethodClass {
+    Cheap,
+    Write,
+    Execution,
+}
+
+/// Methods with cached responses.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Encode)]
+pub enum CachedMethod {
+    GetBlock,
+    GetTransactionReceipt,
+    GetAccountProof,
+    GetStorageProof,
+}
+
 #[derive(Default)]
 pub struct RPCMetrics {
     execute_transactions: Family<ExecutionLabel, Counter>,
     in_queue_requests: Family<RequestLabel, Gauge>,
+    scheduler_queued_requests: Family<MethodClassLabel, Gauge>,
+    scheduler_rejected_requests: Family<MethodClassLabel, Counter>,
+    response_cache_hits: Family<CachedMethodLabel, Counter>,
+    response_cache_misses: Family<CachedMethodLabel, Counter>,
+    pub response_cache_invalidations: Counter,
 }
 
 impl RPCMetrics {
@@ -29,6 +51,33 @@ impl RPCMetrics {
             Box::new(self.execute_transactions.clone()),
         );
 
+        registry.register(
+            "scheduler_queued_requests",
+            "Number of requests waiting for a concurrency slot",
+            Box::new(self.scheduler_queued_requests.clone()),
+        );
+        registry.register(
+            "scheduler_rejected_requests",
+            "Number of requests rejected because the queue is full",
+            Box::new(self.scheduler_rejected_requests.clone()),
+        );
+
+        registry.register(
+            "response_cache_hits",
+            "Number of requests served from the response cache",
+            Box::new(self.response_cache_hits.clone()),
+        );
+        registry.register(
+            "response_cache_misses",
+            "Number of cacheable requests not found in the response cache",
+            Box::new(self.response_cache_misses.clone()),
+        );
+        registry.register(
+            "response_cache_invalidations",
+            "Number of cached responses removed because of reorgs",
+            Box::new(self.response_cache_invalidations.clone()),
+        );
+
         if config.node_mode == gw_config::NodeMode::FullNode {
             registry.register(
                 "in_queue_requests",
@@ -49,6 +98,30 @@ impl RPCMetrics {
             .get_or_create(&RequestLabel { kind })
             .clone()
     }
+
+    pub fn scheduler_queued_requests(&self, class: MethodClass) -> Gauge {
+        self.scheduler_queued_requests
+            .get_or_create(&MethodClassLabel { class })
+            .clone()
+    }
+
+    pub fn scheduler_rejected_requests(&self, class: MethodClass) -> Counter {
+        self.scheduler_rejected_requests
+            .get_or_create(&MethodClassLabel { class })
+            .clone()
+    }
+
+    pub fn response_cache_hits(&self, method: CachedMethod) -> Counter {
+        self.response_cache_hits
+            .get_or_create(&CachedMethodLabel { method })
+            .clone()
+    }
+
+    pub fn response_cache_misses(&self, method: CachedMethod) -> Counter {
+        self.response_cache_misses
+            .get_or_create(&CachedMethodLabel { method })
+            .clone()
+    }
 }
 
 // Label for the execute_transactions metric.
@@ -68,3 +141,13 @@ impl Encode for ExecutionLabel {
 struct RequestLabel {
     kind: RequestKind,
 }
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct MethodClassLabel {
+    class: MethodClass,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct CachedMethodLabel {
+    method: CachedMethod,
+}
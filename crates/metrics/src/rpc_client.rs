@@ -0,0 +1,128 @@
+use std::{sync::atomic::AtomicU64, time::Duration};
+
+use gw_telemetry::metric::{
+    counter::Counter,
+    encoding::text::Encode,
+    family::Family,
+    gauge::Gauge,
+    registry::{Registry, Unit},
+    Lazy,
+};
+
+static RPC_CLIENT_METRICS: Lazy<RPCClientMetrics> = Lazy::new(RPCClientMetrics::default);
+
+pub fn rpc_client() -> &'static RPCClientMetrics {
+    &RPC_CLIENT_METRICS
+}
+
+#[derive(Default)]
+pub struct RPCClientMetrics {
+    failovers: Family<ClientLabel, Counter>,
+    endpoint_active: Family<EndpointLabel, Gauge>,
+    endpoint_healthy: Family<EndpointLabel, Gauge>,
+    endpoint_tip: Family<EndpointLabel, Gauge>,
+    endpoint_latency: Family<EndpointLabel, Gauge<f64, AtomicU64>>,
+}
+
+impl RPCClientMetrics {
+    pub(crate) fn register(&self, _config: &crate::Config, registry: &mut Registry) {
+        registry.register(
+            "failovers",
+            "Number of times switching to another CKB RPC or indexer endpoint",
+            Box::new(self.failovers.clone()),
+        );
+        registry.register(
+            "endpoint_active",
+            "Whether the endpoint is the one in use",
+            Box::new(self.endpoint_active.clone()),
+        );
+        registry.register(
+            "endpoint_healthy",
+            "Whether the endpoint passed the last health check",
+            Box::new(self.endpoint_healthy.clone()),
+        );
+        registry.register(
+            "endpoint_tip",
+            "Tip block number of the endpoint in the last health check",
+            Box::new(self.endpoint_tip.clone()),
+        );
+        registry.register_with_unit(
+            "endpoint_latency",
+            "Latency of the endpoint in the last health check",
+            Unit::Seconds,
+            Box::new(self.endpoint_latency.clone()),
+        );
+    }
+
+    pub fn failover(&self, client: &'static str) {
+        self.failovers.get_or_create(&ClientLabel { client }).inc();
+    }
+
+    pub fn endpoint_active(&self, client: &'static str, url: &str, active: bool) {
+        let label = EndpointLabel::new(client, url);
+        self.endpoint_active
+            .get_or_create(&label)
+            .set(active as u64);
+    }
+
+    /// Results of a health check, `tip` and `latency` are `None` if the
+    /// endpoint failed to respond.
+    pub fn endpoint_health(
+        &self,
+        client: &'static str,
+        url: &str,
+        healthy: bool,
+        tip: Option<u64>,
+        latency: Option<Duration>,
+    ) {
+        let label = EndpointLabel::new(client, url);
+        self.endpoint_healthy
+            .get_or_create(&label)
+            .set(healthy as u64);
+        if let Some(tip) = tip {
+            self.endpoint_tip.get_or_create(&label).set(tip);
+        }
+        if let Some(latency) = latency {
+            self.endpoint_latency
+                .get_or_create(&label)
+                .set(latency.as_secs_f64());
+        }
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct ClientLabel {
+    client: &'static str,
+}
+
+impl Encode for ClientLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "client=\"{}\"", self.client)
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct EndpointLabel {
+    client: &'static str,
+    url: String,
+}
+
+impl EndpointLabel {
+    fn new(client: &'static str, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+        }
+    }
+}
+
+impl Encode for EndpointLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(
+            writer,
+            "client=\"{}\",url=\"{}\"",
+            self.client,
+            self.url.replace('\\', "\\\\").replace('"', "\\\"")
+        )
+    }
+}
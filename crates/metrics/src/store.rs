@@ -0,0 +1,63 @@
+use gw_store::Store;
+use gw_telemetry::metric::{
+    encoding::text::Encode,
+    family::Family,
+    gauge::Gauge,
+    registry::{Registry, Unit},
+    Lazy,
+};
+
+static STORE_METRICS: Lazy<StoreMetrics> = Lazy::new(StoreMetrics::default);
+
+pub fn store() -> &'static StoreMetrics {
+    &STORE_METRICS
+}
+
+#[derive(Default)]
+pub struct StoreMetrics {
+    column_size: Family<ColumnLabel, Gauge>,
+    column_keys: Family<ColumnLabel, Gauge>,
+}
+
+impl StoreMetrics {
+    pub(crate) fn register(&self, _config: &crate::Config, registry: &mut Registry) {
+        registry.register_with_unit(
+            "column_size",
+            "Estimated live data size of each column",
+            Unit::Bytes,
+            Box::new(self.column_size.clone()),
+        );
+        registry.register(
+            "column_keys",
+            "Estimated number of keys of each column",
+            Box::new(self.column_keys.clone()),
+        );
+    }
+
+    /// Update column gauges with rocksdb properties. The properties are
+    /// estimations kept in memory by rocksdb, so this is cheap.
+    pub fn column_sizes(&self, store: &Store) {
+        let db = store.as_inner();
+        for column in 0..=db.default_col() {
+            let label = ColumnLabel { column };
+            if let Some(size) = db.get_int_property(column, "rocksdb.estimate-live-data-size") {
+                self.column_size.get_or_create(&label).set(size);
+            }
+            if let Some(keys) = db.get_int_property(column, "rocksdb.estimate-num-keys") {
+                self.column_keys.get_or_create(&label).set(keys);
+            }
+        }
+    }
+}
+
+#[derive(Hash, Clone, Eq, PartialEq)]
+struct ColumnLabel {
+    column: usize,
+}
+
+// Manual impl because usize does not implement Encode.
+impl Encode for ColumnLabel {
+    fn encode(&self, writer: &mut dyn std::io::Write) -> Result<(), std::io::Error> {
+        write!(writer, "column=\"{}\"", self.column)
+    }
+}
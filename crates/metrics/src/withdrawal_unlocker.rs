@@ -0,0 +1,55 @@
+use gw_telemetry::metric::{counter::Counter, gauge::Gauge, registry::Registry, Lazy};
+
+static WITHDRAWAL_UNLOCKER_METRICS: Lazy<WithdrawalUnlockerMetrics> =
+    Lazy::new(WithdrawalUnlockerMetrics::default);
+
+pub fn withdrawal_unlocker() -> &'static WithdrawalUnlockerMetrics {
+    &WITHDRAWAL_UNLOCKER_METRICS
+}
+
+#[derive(Default)]
+pub struct WithdrawalUnlockerMetrics {
+    pub unlock_txs: Counter,
+    pub unlocked_withdrawals: Counter,
+    pub dropped_txs: Counter,
+    pub fee_bumps: Counter,
+    pub pending_txs: Gauge,
+    pub fee_rate: Gauge,
+}
+
+impl WithdrawalUnlockerMetrics {
+    pub(crate) fn register(&self, _config: &crate::Config, registry: &mut Registry) {
+        // The unlocker can also run standalone, so register regardless of the
+        // node mode.
+        registry.register(
+            "unlock_txs",
+            "Number of unlock transactions sent",
+            Box::new(self.unlock_txs.clone()),
+        );
+        registry.register(
+            "unlocked_withdrawals",
+            "Number of withdrawal cells unlocked to owners in committed transactions",
+            Box::new(self.unlocked_withdrawals.clone()),
+        );
+        registry.register(
+            "dropped_txs",
+            "Number of unlock transactions dropped or rejected",
+            Box::new(self.dropped_txs.clone()),
+        );
+        registry.register(
+            "fee_bumps",
+            "Number of times rebuilding pending unlock transactions with a higher fee rate",
+            Box::new(self.fee_bumps.clone()),
+        );
+        registry.register(
+            "pending_txs",
+            "Number of unlock transactions waiting to be committed",
+            Box::new(self.pending_txs.clone()),
+        );
+        registry.register(
+            "fee_rate",
+            "Current fee rate of unlock transactions",
+            Box::new(self.fee_rate.clone()),
+        );
+    }
+}
@@ -224,4 +224,4 @@ impl<F: Fn(Arc<SessionContext>, &ServiceAsyncControl, SubstreamReadPart)> Protoc
 // Sync local blocks, block submission and confirmation events as well as mem
 // blocks and mem block transactions.
 pub const P2P_SYNC_PROTOCOL: ProtocolId = ProtocolId::new(3);
-pub const P2P_SYNC_PROTOCOL_NAME: &str = "/p2p/sync";
+pub const P2P_SYNC_PROTOCOL_NAME: &str = "/p2p/sync/2";
@@ -60,7 +60,7 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
             let indexer_client = if let Some(ref indexer_url) = config.rpc_client.indexer_url {
                 CKBIndexerClient::with_url(indexer_url)?
             } else {
-                CKBIndexerClient::new(ckb_client.client().clone(), false)
+                CKBIndexerClient::with_ckb_client(&ckb_client)
             };
             let rollup_type_script =
                 ckb_types::packed::Script::new_unchecked(rollup_type_script.as_bytes());
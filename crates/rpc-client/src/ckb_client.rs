@@ -1,11 +1,12 @@
 use std::time::{Duration, Instant};
 
 use crate::{
+    endpoints::{EndpointKind, Endpoints},
     error::RPCRequestError,
-    utils::{to_jsonh256, to_result, DEFAULT_HTTP_TIMEOUT},
+    utils::{to_jsonh256, to_result},
 };
 use anyhow::{anyhow, bail, Context, Result};
-use async_jsonrpc_client::{HttpClient, Params as ClientParams, Transport};
+use async_jsonrpc_client::{HttpClient, Params as ClientParams};
 use gw_jsonrpc_types::{
     blockchain::CellDep,
     ckb_jsonrpc_types::{self, Either},
@@ -17,23 +18,33 @@ use tracing::instrument;
 
 #[derive(Clone)]
 pub struct CKBClient {
-    ckb_client: HttpClient,
+    endpoints: Endpoints,
 }
 
 impl CKBClient {
     pub fn new(ckb_client: HttpClient) -> Self {
-        Self { ckb_client }
+        Self {
+            endpoints: Endpoints::from_client(EndpointKind::Ckb, ckb_client),
+        }
     }
 
     pub fn with_url(url: &str) -> Result<Self> {
-        let client = HttpClient::builder()
-            .timeout(DEFAULT_HTTP_TIMEOUT)
-            .build(url)?;
-        Ok(Self::new(client))
+        Self::with_urls(&[url.to_string()])
+    }
+
+    /// The first URL is the primary endpoint, others are backups.
+    pub fn with_urls(urls: &[String]) -> Result<Self> {
+        let endpoints = Endpoints::new(EndpointKind::Ckb, urls)?;
+        Ok(Self { endpoints })
     }
 
+    /// Client of the active endpoint.
     pub fn client(&self) -> &HttpClient {
-        &self.ckb_client
+        self.endpoints.client()
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
     }
 
     #[instrument(skip_all, fields(method = method))]
@@ -43,13 +54,20 @@ impl CKBClient {
         params: Option<ClientParams>,
     ) -> Result<T> {
         let response = self
-            .client()
+            .endpoints
             .request(method, params)
             .await
             .map_err(|err| RPCRequestError::new("ckb client", method, err))?;
         to_result(response).with_context(|| format!("ckb-client {method}"))
     }
 
+    #[instrument(skip_all)]
+    pub async fn get_tip_block_number(&self) -> Result<u64> {
+        let number: ckb_jsonrpc_types::BlockNumber =
+            self.request("get_tip_block_number", None).await?;
+        Ok(number.value())
+    }
+
     #[instrument(skip_all, fields(tx_hash = %tx_hash.pack()))]
     pub async fn get_transaction_block_hash(&self, tx_hash: H256) -> Result<Option<[u8; 32]>> {
         let tx_with_status = self.get_transaction_with_status(tx_hash).await?;
@@ -5,9 +5,12 @@ use std::time::Instant;
 use anyhow::{anyhow, bail, Result};
 use arc_swap::ArcSwap;
 use async_jsonrpc_client::Params as ClientParams;
-use gw_config::{ContractTypeScriptConfig, ContractsCellDep};
+use ckb_fixed_hash::H256;
+use ckb_types::prelude::Entity;
+use gw_config::{ContractTypeScriptConfig, ContractsCellDep, ContractsDepGroupConfig};
 use gw_jsonrpc_types::blockchain::{CellDep, Script};
-use gw_types::packed::RollupConfig;
+use gw_types::offchain::CellStatus;
+use gw_types::packed::{OutPoint, OutPointVec, RollupConfig};
 use gw_types::prelude::Pack;
 use serde_json::json;
 use tracing::instrument;
@@ -22,6 +25,7 @@ pub use arc_swap::Guard;
 pub struct ContractsCellDepManager {
     rpc_client: RPCClient,
     scripts: Arc<ContractTypeScriptConfig>,
+    dep_groups: Arc<ContractsDepGroupConfig>,
     deps: Arc<ArcSwap<ContractsCellDep>>,
 }
 
@@ -29,15 +33,18 @@ impl ContractsCellDepManager {
     pub async fn build(
         rpc_client: RPCClient,
         scripts: ContractTypeScriptConfig,
+        dep_groups: ContractsDepGroupConfig,
         rollup_config_cell_dep: CellDep,
     ) -> Result<Self> {
         let now = Instant::now();
-        let deps = query_cell_deps(&rpc_client, &scripts, rollup_config_cell_dep).await?;
+        let deps =
+            query_cell_deps(&rpc_client, &scripts, &dep_groups, rollup_config_cell_dep).await?;
         log::trace!("[contracts dep] build {}ms", now.elapsed().as_millis());
 
         Ok(Self {
             rpc_client,
             scripts: Arc::new(scripts),
+            dep_groups: Arc::new(dep_groups),
             deps: Arc::new(ArcSwap::from_pointee(deps)),
         })
     }
@@ -50,20 +57,75 @@ impl ContractsCellDepManager {
         &self.scripts
     }
 
+    /// Query contract cells again, returns `(old, new)` pairs of changed cell
+    /// deps.
     #[instrument(skip_all)]
-    pub async fn refresh(&self) -> Result<()> {
+    pub async fn refresh(&self) -> Result<Vec<(CellDep, CellDep)>> {
         log::info!("[contracts dep] refresh");
 
         // rollup_config_cell is identify by data_hash but not type_hash
-        let rollup_config_cell_dep = self.load().rollup_config.clone();
+        let old_deps = self.deps.load_full();
+        let rollup_config_cell_dep = old_deps.rollup_config.clone();
+
+        // Cached contract cells may have been consumed.
+        for type_script in contract_type_scripts(&self.scripts) {
+            let search_key = contract_search_key(type_script.clone());
+            self.rpc_client
+                .indexer
+                .cache()
+                .invalidate(&search_key)
+                .await?;
+        }
 
         let now = Instant::now();
-        let deps = query_cell_deps(&self.rpc_client, &self.scripts, rollup_config_cell_dep).await?;
+        let deps = query_cell_deps(
+            &self.rpc_client,
+            &self.scripts,
+            &self.dep_groups,
+            rollup_config_cell_dep,
+        )
+        .await?;
         log::trace!("[contracts dep] refresh {}ms", now.elapsed().as_millis());
 
+        let changed = changed_cell_deps(&old_deps, &deps);
+        for (old, new) in changed.iter() {
+            log::info!(
+                "[contracts dep] {:?} -> {:?}",
+                old.out_point.tx_hash,
+                new.out_point.tx_hash
+            );
+        }
         self.deps.store(Arc::new(deps));
-        Ok(())
+        Ok(changed)
+    }
+}
+
+fn changed_cell_deps(old: &ContractsCellDep, new: &ContractsCellDep) -> Vec<(CellDep, CellDep)> {
+    let fixed = |deps: &ContractsCellDep| {
+        vec![
+            deps.rollup_cell_type.clone(),
+            deps.deposit_cell_lock.clone(),
+            deps.stake_cell_lock.clone(),
+            deps.custodian_cell_lock.clone(),
+            deps.withdrawal_cell_lock.clone(),
+            deps.challenge_cell_lock.clone(),
+            deps.l1_sudt_type.clone(),
+            deps.omni_lock.clone(),
+        ]
+    };
+    let mut pairs: Vec<_> = fixed(old).into_iter().zip(fixed(new)).collect();
+    for (hash, dep) in old.allowed_eoa_locks.iter() {
+        if let Some(new_dep) = new.allowed_eoa_locks.get(hash) {
+            pairs.push((dep.clone(), new_dep.clone()));
+        }
+    }
+    for (hash, dep) in old.allowed_contract_types.iter() {
+        if let Some(new_dep) = new.allowed_contract_types.get(hash) {
+            pairs.push((dep.clone(), new_dep.clone()));
+        }
     }
+    pairs.retain(|(old, new)| old != new);
+    pairs
 }
 
 pub fn check_script(
@@ -120,10 +182,12 @@ pub fn check_script(
 pub async fn query_cell_deps(
     rpc_client: &RPCClient,
     script_config: &ContractTypeScriptConfig,
+    dep_groups: &ContractsDepGroupConfig,
     rollup_config_cell_dep: CellDep,
 ) -> Result<ContractsCellDep> {
+    let dep_groups = DepGroups::query(rpc_client, dep_groups).await?;
     let query = |contract, type_script: Script| -> _ {
-        query_by_type_script(rpc_client, contract, type_script)
+        query_by_type_script(rpc_client, &dep_groups, contract, type_script)
     };
 
     let rollup_cell_type = query("state validator", script_config.state_validator.clone()).await?;
@@ -163,36 +227,165 @@ pub async fn query_cell_deps(
     })
 }
 
+/// Type scripts of all contracts in the config.
+fn contract_type_scripts(
+    script_config: &ContractTypeScriptConfig,
+) -> impl Iterator<Item = &Script> {
+    vec![
+        &script_config.state_validator,
+        &script_config.deposit_lock,
+        &script_config.stake_lock,
+        &script_config.custodian_lock,
+        &script_config.withdrawal_lock,
+        &script_config.challenge_lock,
+        &script_config.l1_sudt,
+        &script_config.omni_lock,
+    ]
+    .into_iter()
+    .chain(script_config.allowed_eoa_scripts.values())
+    .chain(script_config.allowed_contract_scripts.values())
+}
+
+fn contract_search_key(type_script: Script) -> SearchKey {
+    SearchKey {
+        script: type_script.into(),
+        script_type: ScriptType::Type,
+        filter: None,
+    }
+}
+
+/// Live dep group cells and their members.
+struct DepGroups {
+    /// By contract type script hash.
+    configured: HashMap<H256, (OutPoint, OutPointVec)>,
+    discovered: Vec<(OutPoint, OutPointVec)>,
+}
+
+impl DepGroups {
+    async fn query(rpc_client: &RPCClient, config: &ContractsDepGroupConfig) -> Result<Self> {
+        let mut configured = HashMap::with_capacity(config.dep_groups.len());
+        for (type_hash, out_point) in config.dep_groups.iter() {
+            let out_point: OutPoint = out_point.clone().into();
+            let cell = { rpc_client.get_cell(out_point.clone()).await? }
+                .filter(|c| matches!(c.status, CellStatus::Live))
+                .and_then(|c| c.cell);
+            match cell {
+                Some(cell) => {
+                    let members = OutPointVec::from_slice(&cell.data)
+                        .map_err(|_| anyhow!("invalid dep group {}", out_point))?;
+                    configured.insert(type_hash.clone(), (out_point, members));
+                }
+                None => log::warn!(
+                    "[contracts dep] dep group {} of {} is not live",
+                    out_point,
+                    type_hash
+                ),
+            }
+        }
+
+        let mut discovered = Vec::new();
+        if let Some(ref lock) = config.discover_lock {
+            let search_key = SearchKey {
+                script: lock.clone().into(),
+                script_type: ScriptType::Lock,
+                filter: None,
+            };
+            let mut cursor = None;
+            loop {
+                let page = rpc_client
+                    .indexer
+                    .get_cells(&search_key, &Order::Asc, None, &cursor)
+                    .await?;
+                if page.last_cursor.is_empty() {
+                    break;
+                }
+                cursor = Some(page.last_cursor);
+                for cell in page.objects {
+                    let cell = cell.info();
+                    // Other cells of the lock, e.g. code cells, are unlikely
+                    // to be valid out point vectors.
+                    if let Ok(members) = OutPointVec::from_slice(&cell.data) {
+                        if !members.is_empty() {
+                            discovered.push((cell.out_point, members));
+                        }
+                    }
+                }
+            }
+            log::debug!("[contracts dep] discover {} dep groups", discovered.len());
+        }
+
+        Ok(DepGroups {
+            configured,
+            discovered,
+        })
+    }
+
+    /// Dep group containing the code cell of the contract.
+    fn find(&self, type_hash: &H256, code: &OutPoint) -> Option<&OutPoint> {
+        let contains = |members: &OutPointVec| {
+            { members.clone().into_iter() }.any(|m| m.as_slice() == code.as_slice())
+        };
+        match self.configured.get(type_hash) {
+            Some((out_point, members)) if contains(members) => return Some(out_point),
+            Some((out_point, _)) => log::warn!(
+                "[contracts dep] dep group {} doesn't contain code cell {} of {}",
+                out_point,
+                code,
+                type_hash
+            ),
+            None => {}
+        }
+        { self.discovered.iter() }
+            .find(|(_, members)| contains(members))
+            .map(|(out_point, _)| out_point)
+    }
+}
+
 async fn query_by_type_script(
     rpc_client: &RPCClient,
+    dep_groups: &DepGroups,
     contract: &'static str,
     type_script: Script,
 ) -> Result<CellDep> {
     use gw_jsonrpc_types::ckb_jsonrpc_types::{CellDep, DepType, Uint32};
 
-    let search_key = SearchKey {
-        script: type_script.clone().into(),
-        script_type: ScriptType::Type,
-        filter: None,
-    };
+    let search_key = contract_search_key(type_script.clone());
     let order = Order::Desc;
-    let limit = Uint32::from(1);
-
-    let get_contract_cell = rpc_client.indexer.request(
-        "get_cells",
-        Some(ClientParams::Array(vec![
-            json!(search_key),
-            json!(order),
-            json!(limit),
-        ])),
-    );
-
-    let mut cells: Pagination<Cell> = get_contract_cell.await?;
-    match cells.objects.pop() {
-        Some(cell) => Ok(Into::into(CellDep {
+
+    let out_point = if rpc_client.indexer.cache().is_enabled() {
+        let cells = rpc_client
+            .indexer
+            .get_cells_cached(&search_key, order)
+            .await?;
+        cells.first().map(|cell| {
+            ckb_types::packed::OutPoint::new_unchecked(cell.out_point.as_bytes()).into()
+        })
+    } else {
+        let limit = Uint32::from(1);
+        let get_contract_cell = rpc_client.indexer.request(
+            "get_cells",
+            Some(ClientParams::Array(vec![
+                json!(search_key),
+                json!(order),
+                json!(limit),
+            ])),
+        );
+        let mut cells: Pagination<Cell> = get_contract_cell.await?;
+        cells.objects.pop().map(|cell| cell.out_point)
+    };
+    let out_point =
+        out_point.ok_or_else(|| anyhow!("{} {} not found", contract, type_script.hash()))?;
+    let code =
+        OutPoint::new_unchecked(ckb_types::packed::OutPoint::from(out_point.clone()).as_bytes());
+    let cell_dep = match dep_groups.find(&type_script.hash(), &code) {
+        Some(dep_group) => CellDep {
+            dep_type: DepType::DepGroup,
+            out_point: ckb_types::packed::OutPoint::new_unchecked(dep_group.as_bytes()).into(),
+        },
+        None => CellDep {
             dep_type: DepType::Code,
-            out_point: cell.out_point,
-        })),
-        None => Err(anyhow!("{} {} not found", contract, type_script.hash())),
-    }
+            out_point,
+        },
+    };
+    Ok(cell_dep.into())
 }
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use arc_swap::ArcSwap;
+use async_jsonrpc_client::Params as ClientParams;
+use gw_config::OnchainCyclesLimitConfig;
+use gw_jsonrpc_types::ckb_jsonrpc_types::Uint32;
+use gw_types::packed::Uint64;
+use gw_types::prelude::*;
+use serde_json::json;
+use tracing::instrument;
+
+use crate::indexer_types::{Cell, Order, Pagination, ScriptType, SearchKey};
+use crate::rpc_client::RPCClient;
+
+/// Max cycles per block read from an on-chain cell, shared by block producer
+/// and read-only nodes.
+#[derive(Clone)]
+pub struct CyclesLimitManager {
+    rpc_client: RPCClient,
+    config: Arc<OnchainCyclesLimitConfig>,
+    limit: Arc<ArcSwap<Option<u64>>>,
+}
+
+impl CyclesLimitManager {
+    pub async fn build(rpc_client: RPCClient, config: OnchainCyclesLimitConfig) -> Result<Self> {
+        let limit = query_cycles_limit(&rpc_client, &config).await?;
+        if limit.is_none() {
+            log::warn!("[cycles limit] cell not found");
+        }
+
+        Ok(Self {
+            rpc_client,
+            config: Arc::new(config),
+            limit: Arc::new(ArcSwap::from_pointee(limit)),
+        })
+    }
+
+    /// Returns `None` if the cell is not found.
+    pub fn load(&self) -> Option<u64> {
+        **self.limit.load()
+    }
+
+    pub fn config(&self) -> &OnchainCyclesLimitConfig {
+        &self.config
+    }
+
+    /// Query the cell again, returns `(old, new)` if the limit changed. The
+    /// last limit is kept if the cell is not found.
+    #[instrument(skip_all)]
+    pub async fn refresh(&self) -> Result<Option<(Option<u64>, u64)>> {
+        let old = self.load();
+        let new = match query_cycles_limit(&self.rpc_client, &self.config).await? {
+            Some(new) if Some(new) != old => new,
+            _ => return Ok(None),
+        };
+        log::info!("[cycles limit] {:?} -> {}", old, new);
+        self.limit.store(Arc::new(Some(new)));
+        Ok(Some((old, new)))
+    }
+
+    /// Refresh every `refresh_interval_secs`.
+    pub fn spawn_refresh(&self) {
+        let manager = self.clone();
+        let interval = Duration::from_secs(self.config.refresh_interval_secs);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(err) = manager.refresh().await {
+                    log::warn!("[cycles limit] refresh error {:#}", err);
+                }
+            }
+        });
+    }
+}
+
+async fn query_cycles_limit(
+    rpc_client: &RPCClient,
+    config: &OnchainCyclesLimitConfig,
+) -> Result<Option<u64>> {
+    let search_key = SearchKey {
+        script: config.type_script.clone().into(),
+        script_type: ScriptType::Type,
+        filter: None,
+    };
+    let order = Order::Desc;
+    let limit = Uint32::from(1);
+
+    let mut cells: Pagination<Cell> = rpc_client
+        .indexer
+        .request(
+            "get_cells",
+            Some(ClientParams::Array(vec![
+                json!(search_key),
+                json!(order),
+                json!(limit),
+            ])),
+        )
+        .await?;
+    let cell = match cells.objects.pop() {
+        Some(cell) => cell,
+        None => return Ok(None),
+    };
+    let data = cell.output_data.into_bytes();
+    let cycles_limit = Uint64::from_slice(&data)
+        .map_err(|_| anyhow!("invalid cycles limit cell data {}", hex::encode(&data)))?;
+    Ok(Some(cycles_limit.unpack()))
+}
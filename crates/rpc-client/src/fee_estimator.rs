@@ -0,0 +1,326 @@
+//! L1 fee rate estimation.
+//!
+//! The estimate is the median fee rate of recent blocks, from the node's
+//! `get_fee_rate_statistics` RPC if available, or by sampling txs of recent
+//! blocks otherwise. Estimates are clamped to `[min_fee_rate, max_fee_rate]`
+//! and cached for `update_interval_secs`. The configured fee rate is used if
+//! estimation is disabled or fails.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Context, Result};
+use async_jsonrpc_client::Params as ClientParams;
+use gw_config::FeeEstimatorConfig;
+use gw_jsonrpc_types::ckb_jsonrpc_types::Uint64;
+use gw_types::{h256::H256, packed::Transaction, prelude::*};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::rpc_client::RPCClient;
+
+/// Maximum number of txs to sample, each input tx costs a request.
+const MAX_SAMPLE_TXS: usize = 100;
+
+#[derive(Deserialize)]
+struct FeeRateStatistics {
+    median: Uint64,
+}
+
+#[derive(Clone)]
+pub struct FeeEstimator {
+    rpc_client: RPCClient,
+    config: FeeEstimatorConfig,
+    default_fee_rate: u64,
+    cache: Arc<Mutex<Option<(Instant, u64)>>>,
+}
+
+impl FeeEstimator {
+    pub fn new(rpc_client: RPCClient, config: FeeEstimatorConfig, default_fee_rate: u64) -> Self {
+        FeeEstimator {
+            rpc_client,
+            config,
+            default_fee_rate,
+            cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The configured fee rate.
+    pub fn default_fee_rate(&self) -> u64 {
+        self.default_fee_rate
+    }
+
+    /// Fee rate of new txs, in shannons/KB.
+    pub async fn fee_rate(&self) -> u64 {
+        if !self.config.enabled {
+            return self.default_fee_rate;
+        }
+        let update_interval = Duration::from_secs(self.config.update_interval_secs);
+        if let Some((updated_at, fee_rate)) = *self.cache.lock().unwrap() {
+            if updated_at.elapsed() < update_interval {
+                return fee_rate;
+            }
+        }
+
+        let fee_rate = match self.estimate().await {
+            Ok(fee_rate) => fee_rate
+                .max(self.config.min_fee_rate)
+                .min(self.config.max_fee_rate),
+            Err(err) => {
+                log::warn!(
+                    "[fee estimator] {:#}, use default fee rate {}",
+                    err,
+                    self.default_fee_rate
+                );
+                self.default_fee_rate
+            }
+        };
+        log::debug!("[fee estimator] fee rate {}", fee_rate);
+        *self.cache.lock().unwrap() = Some((Instant::now(), fee_rate));
+        fee_rate
+    }
+
+    async fn estimate(&self) -> Result<u64> {
+        match self.node_statistics().await {
+            Ok(Some(fee_rate)) => return Ok(fee_rate),
+            Ok(None) => log::debug!("[fee estimator] no fee rate statistics"),
+            Err(err) => log::debug!("[fee estimator] get fee rate statistics: {:#}", err),
+        }
+        self.sample_blocks().await
+    }
+
+    async fn node_statistics(&self) -> Result<Option<u64>> {
+        let target = Uint64::from(self.config.target_blocks);
+        let statistics: Option<FeeRateStatistics> = self
+            .rpc_client
+            .ckb
+            .request(
+                "get_fee_rate_statistics",
+                Some(ClientParams::Array(vec![json!(target)])),
+            )
+            .await?;
+        Ok(statistics.map(|s| s.median.value()))
+    }
+
+    /// Median fee rate of txs in recent blocks.
+    async fn sample_blocks(&self) -> Result<u64> {
+        let tip = self.rpc_client.ckb.get_tip_block_number().await?;
+        let from = (tip + 1).saturating_sub(self.config.sample_blocks);
+        let mut input_txs = HashMap::new();
+        let mut fee_rates = Vec::new();
+        'blocks: for number in (from..=tip).rev() {
+            let block = match self.rpc_client.get_block_by_number(number).await? {
+                Some(block) => block,
+                None => continue,
+            };
+            // Skip the cellbase.
+            for tx in block.transactions().into_iter().skip(1) {
+                if fee_rates.len() >= MAX_SAMPLE_TXS {
+                    break 'blocks;
+                }
+                if let Some(fee_rate) = self.tx_fee_rate(&tx, &mut input_txs).await? {
+                    fee_rates.push(fee_rate);
+                }
+            }
+        }
+        median(fee_rates)
+    }
+
+    /// `None` if the fee is unknown, e.g. for DAO withdrawals.
+    async fn tx_fee_rate(
+        &self,
+        tx: &Transaction,
+        input_txs: &mut HashMap<H256, Transaction>,
+    ) -> Result<Option<u64>> {
+        let mut inputs_capacity = 0u64;
+        for input in tx.raw().inputs() {
+            let out_point = input.previous_output();
+            let tx_hash: H256 = out_point.tx_hash().unpack();
+            if !input_txs.contains_key(&tx_hash) {
+                let input_tx = self
+                    .rpc_client
+                    .ckb
+                    .get_transaction(tx_hash)
+                    .await?
+                    .context("input tx not found")?;
+                input_txs.insert(tx_hash, input_tx);
+            }
+            let index: u32 = out_point.index().unpack();
+            let output = input_txs[&tx_hash].raw().outputs().get(index as usize);
+            let capacity: u64 = match output {
+                Some(output) => output.capacity().unpack(),
+                None => return Ok(None),
+            };
+            inputs_capacity += capacity;
+        }
+        Ok(calculate_fee_rate(tx, inputs_capacity))
+    }
+}
+
+/// Median of sampled fee rates.
+fn median(mut fee_rates: Vec<u64>) -> Result<u64> {
+    ensure!(!fee_rates.is_empty(), "no txs in recent blocks");
+    fee_rates.sort_unstable();
+    Ok(fee_rates[fee_rates.len() / 2])
+}
+
+/// `None` if outputs of `tx` take more than `inputs_capacity`.
+fn calculate_fee_rate(tx: &Transaction, inputs_capacity: u64) -> Option<u64> {
+    let outputs_capacity: u64 = { tx.raw().outputs().into_iter() }
+        .map(|output| -> u64 { output.capacity().unpack() })
+        .sum();
+    if outputs_capacity > inputs_capacity {
+        return None;
+    }
+    // tx size + 4 in block serialization cost
+    let size = tx.as_slice().len() as u64 + 4;
+    Some((inputs_capacity - outputs_capacity) * 1000 / size)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use gw_types::packed::{CellOutput, RawTransaction, RollupConfig};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{ckb_client::CKBClient, indexer_client::CKBIndexerClient};
+
+    const DEFAULT_FEE_RATE: u64 = 1500;
+
+    /// Answer JSON-RPC requests with `responses` in order, one request per
+    /// connection, returns the url and the received methods.
+    fn serve(responses: Vec<Value>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut methods = Vec::new();
+            for mut response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut len = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((k, v)) = line.split_once(": ") {
+                        if k.eq_ignore_ascii_case("content-length") {
+                            len = v.parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0u8; len];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                methods.push(request["method"].as_str().unwrap().to_string());
+
+                response["jsonrpc"] = "2.0".into();
+                response["id"] = request["id"].clone();
+                let body = response.to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            methods
+        });
+        (url, handle)
+    }
+
+    fn fee_estimator(url: &str, config: FeeEstimatorConfig) -> FeeEstimator {
+        let rpc_client = RPCClient::new(
+            Default::default(),
+            RollupConfig::default(),
+            CKBClient::with_url(url).unwrap(),
+            CKBIndexerClient::with_url(url).unwrap(),
+        );
+        FeeEstimator::new(rpc_client, config, DEFAULT_FEE_RATE)
+    }
+
+    fn enabled_config() -> FeeEstimatorConfig {
+        FeeEstimatorConfig {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn statistics(median: u64) -> Value {
+        json!({ "result": { "mean": Uint64::from(median), "median": Uint64::from(median) } })
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_disabled() {
+        let (url, handle) = serve(vec![]);
+        let fee_estimator = fee_estimator(&url, FeeEstimatorConfig::default());
+        assert_eq!(fee_estimator.fee_rate().await, DEFAULT_FEE_RATE);
+        assert!(handle.join().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_from_node_statistics() {
+        let (url, handle) = serve(vec![statistics(3000)]);
+        let fee_estimator = fee_estimator(&url, enabled_config());
+        assert_eq!(fee_estimator.fee_rate().await, 3000);
+        // Cached for update_interval_secs.
+        assert_eq!(fee_estimator.fee_rate().await, 3000);
+        assert_eq!(handle.join().unwrap(), vec!["get_fee_rate_statistics"]);
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_clamped() {
+        let (url, handle) = serve(vec![statistics(100000), statistics(1)]);
+        let config = FeeEstimatorConfig {
+            update_interval_secs: 0,
+            ..enabled_config()
+        };
+        let fee_estimator = fee_estimator(&url, config.clone());
+        assert_eq!(fee_estimator.fee_rate().await, config.max_fee_rate);
+        assert_eq!(fee_estimator.fee_rate().await, config.min_fee_rate);
+        assert_eq!(handle.join().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_fallback() {
+        // No statistics, and sampling blocks fails.
+        let error = json!({ "error": { "code": -1, "message": "unavailable" } });
+        let (url, handle) = serve(vec![json!({ "result": null }), error]);
+        let fee_estimator = fee_estimator(&url, enabled_config());
+        assert_eq!(fee_estimator.fee_rate().await, DEFAULT_FEE_RATE);
+        assert_eq!(
+            handle.join().unwrap(),
+            vec!["get_fee_rate_statistics", "get_tip_block_number"]
+        );
+    }
+
+    #[test]
+    fn test_sampled_fee_rates() {
+        assert_eq!(median(vec![3000, 1000, 2000, 5000]).unwrap(), 3000);
+        assert_eq!(median(vec![1000]).unwrap(), 1000);
+        assert!(median(vec![]).is_err());
+
+        let output = CellOutput::new_builder()
+            .capacity(900_00000000u64.pack())
+            .build();
+        let raw = RawTransaction::new_builder()
+            .outputs(vec![output].pack())
+            .build();
+        let tx = Transaction::new_builder().raw(raw).build();
+        let size = tx.as_slice().len() as u64 + 4;
+        let fee_rate = calculate_fee_rate(&tx, 900_00000000 + size).unwrap();
+        assert_eq!(fee_rate, 1000);
+        assert_eq!(calculate_fee_rate(&tx, 900_00000000), Some(0));
+        assert_eq!(calculate_fee_rate(&tx, 800_00000000), None);
+    }
+}
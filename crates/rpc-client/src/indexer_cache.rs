@@ -0,0 +1,206 @@
+//! Cache of live cells by indexer search key.
+//!
+//! A search key is scanned once, then only cells of new blocks are queried,
+//! with a `block_range` filter starting after the last scanned tip. Consumed
+//! cells are detected by comparing the total capacity of cached cells with
+//! `get_cells_capacity`, and the search key is rescanned on mismatch, e.g.
+//! when cells were consumed or the chain reorganized.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Result};
+use gw_config::IndexerCacheConfig;
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use gw_types::{offchain::CellInfo, prelude::*};
+use tokio::sync::Mutex;
+
+use crate::{
+    indexer_client::CKBIndexerClient,
+    indexer_types::{Order, SearchKey, SearchKeyFilter},
+};
+
+/// (block number, tx index, output index)
+type CellKey = (u64, u32, u32);
+
+struct Entry {
+    cells: BTreeMap<CellKey, CellInfo>,
+    /// Cells in ascending order of `CellKey`.
+    snapshot: Arc<Vec<CellInfo>>,
+    capacity: u64,
+    /// Indexer tip of the last scan or update.
+    tip_number: u64,
+    updated_at: Instant,
+    scanned_at: Instant,
+}
+
+impl Entry {
+    fn new(cells: BTreeMap<CellKey, CellInfo>, tip_number: u64) -> Self {
+        let capacity = cells.values().map(cell_capacity).sum();
+        let snapshot = Arc::new(cells.values().cloned().collect());
+        let now = Instant::now();
+        Entry {
+            cells,
+            snapshot,
+            capacity,
+            tip_number,
+            updated_at: now,
+            scanned_at: now,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IndexerCache {
+    config: IndexerCacheConfig,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl IndexerCache {
+    pub fn new(config: IndexerCacheConfig) -> Self {
+        IndexerCache {
+            config,
+            entries: Default::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// All live cells of the search key, ordered by block number.
+    ///
+    /// `block_range` filter of the search key is not supported.
+    pub async fn get_cells(
+        &self,
+        indexer: &CKBIndexerClient,
+        search_key: &SearchKey,
+        order: Order,
+    ) -> Result<Arc<Vec<CellInfo>>> {
+        ensure!(
+            { search_key.filter.as_ref() }.map_or(true, |f| f.block_range.is_none()),
+            "block_range filter is not supported by indexer cache"
+        );
+        let key = serde_json::to_string(search_key)?;
+        // Hold the lock so that concurrent queries of a search key scan once.
+        let mut entries = self.entries.lock().await;
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let cells = match entries.get(&key) {
+            Some(entry) if entry.updated_at.elapsed() < ttl => Arc::clone(&entry.snapshot),
+            _ => {
+                let full_rescan = Duration::from_secs(self.config.full_rescan_secs);
+                let entry = match entries.remove(&key) {
+                    Some(entry) if entry.scanned_at.elapsed() < full_rescan => {
+                        update(indexer, search_key, entry).await?
+                    }
+                    _ => None,
+                };
+                let entry = match entry {
+                    Some(entry) => entry,
+                    None => scan(indexer, search_key).await?,
+                };
+                let cells = Arc::clone(&entry.snapshot);
+                if entries.len() >= self.config.max_entries {
+                    let oldest = { entries.iter() }
+                        .min_by_key(|(_, entry)| entry.updated_at)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        entries.remove(&oldest);
+                    }
+                }
+                entries.insert(key, entry);
+                cells
+            }
+        };
+        Ok(match order {
+            Order::Asc => cells,
+            Order::Desc => Arc::new(cells.iter().rev().cloned().collect()),
+        })
+    }
+
+    /// Drop cached cells of the search key, e.g. when they are known to be
+    /// consumed.
+    pub async fn invalidate(&self, search_key: &SearchKey) -> Result<()> {
+        let key = serde_json::to_string(search_key)?;
+        self.entries.lock().await.remove(&key);
+        Ok(())
+    }
+}
+
+async fn scan(indexer: &CKBIndexerClient, search_key: &SearchKey) -> Result<Entry> {
+    let tip_number = indexer.get_tip().await?.number().unpack();
+    let cells = query_all(indexer, search_key).await?;
+    log::debug!("[indexer cache] scan {} cells", cells.len());
+    Ok(Entry::new(cells, tip_number))
+}
+
+/// Update with cells of new blocks, `None` if cells were consumed.
+async fn update(
+    indexer: &CKBIndexerClient,
+    search_key: &SearchKey,
+    mut entry: Entry,
+) -> Result<Option<Entry>> {
+    let tip_number: u64 = indexer.get_tip().await?.number().unpack();
+    if tip_number == entry.tip_number {
+        entry.updated_at = Instant::now();
+        return Ok(Some(entry));
+    }
+
+    let new_blocks_key = SearchKey {
+        filter: Some(SearchKeyFilter {
+            block_range: Some([(entry.tip_number + 1).into(), u64::MAX.into()]),
+            ..search_key.filter.clone().unwrap_or_default()
+        }),
+        ..search_key.clone()
+    };
+    let new_cells = query_all(indexer, &new_blocks_key).await?;
+    let mut capacity = entry.capacity;
+    for (key, cell) in new_cells {
+        if !entry.cells.contains_key(&key) {
+            capacity += cell_capacity(&cell);
+            entry.cells.insert(key, cell);
+        }
+    }
+    if indexer.get_cells_capacity(search_key).await? != capacity {
+        log::debug!("[indexer cache] cells consumed, rescan");
+        return Ok(None);
+    }
+
+    entry.snapshot = Arc::new(entry.cells.values().cloned().collect());
+    entry.capacity = capacity;
+    entry.tip_number = tip_number;
+    entry.updated_at = Instant::now();
+    Ok(Some(entry))
+}
+
+async fn query_all(
+    indexer: &CKBIndexerClient,
+    search_key: &SearchKey,
+) -> Result<BTreeMap<CellKey, CellInfo>> {
+    let mut cells = BTreeMap::new();
+    let mut cursor: Option<JsonBytes> = None;
+    loop {
+        let page = indexer
+            .get_cells(search_key, &Order::Asc, None, &cursor)
+            .await?;
+        if page.last_cursor.is_empty() {
+            return Ok(cells);
+        }
+        cursor = Some(page.last_cursor);
+        for cell in page.objects {
+            let key = (
+                cell.block_number.value(),
+                cell.tx_index.value(),
+                cell.out_point.index.value(),
+            );
+            cells.insert(key, cell.info());
+        }
+    }
+}
+
+fn cell_capacity(cell: &CellInfo) -> u64 {
+    cell.output.capacity().unpack()
+}
@@ -1,16 +1,23 @@
 #![allow(clippy::mutable_key_type)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
+use crate::ckb_client::CKBClient;
+use crate::endpoints::{EndpointKind, Endpoints};
 use crate::error::RPCRequestError;
-use crate::indexer_types::{Cell, Order, Pagination, ScriptType, SearchKey, SearchKeyFilter, Tx};
-use crate::utils::{to_result, DEFAULT_HTTP_TIMEOUT, DEFAULT_QUERY_LIMIT};
+use crate::indexer_cache::IndexerCache;
+use crate::indexer_types::{
+    Cell, CellsCapacity, Order, Pagination, ScriptType, SearchKey, SearchKeyFilter, Tx,
+};
+use crate::utils::{to_result, DEFAULT_QUERY_LIMIT};
 use anyhow::{Context, Result};
-use async_jsonrpc_client::{HttpClient, Params as ClientParams, Transport};
+use async_jsonrpc_client::{HttpClient, Params as ClientParams};
 use ckb_types::prelude::Entity;
+use gw_config::IndexerCacheConfig;
 use gw_jsonrpc_types::ckb_jsonrpc_types::{JsonBytes, Uint32};
 use gw_types::core::Timepoint;
-use gw_types::offchain::{CompatibleFinalizedTimepoint, CustodianStat, SUDTStat};
+use gw_types::offchain::{CellInfo, CompatibleFinalizedTimepoint, CustodianStat, SUDTStat};
 use gw_types::packed::{CustodianLockArgs, NumberHash};
 use gw_types::{packed::Script, prelude::*};
 use serde::de::DeserializeOwned;
@@ -19,37 +26,85 @@ use tracing::instrument;
 
 #[derive(Clone)]
 pub struct CKBIndexerClient {
-    client: HttpClient,
+    endpoints: Endpoints,
     // True when using standalone CKB indexer, false when using the new built in CKB indexer.
     is_standalone: bool,
+    cache: IndexerCache,
 }
 
 impl CKBIndexerClient {
     pub fn new(ckb_indexer_client: HttpClient, is_standalone: bool) -> Self {
+        let kind = if is_standalone {
+            EndpointKind::Indexer
+        } else {
+            EndpointKind::Ckb
+        };
         Self {
-            client: ckb_indexer_client,
+            endpoints: Endpoints::from_client(kind, ckb_indexer_client),
             is_standalone,
+            cache: IndexerCache::new(Default::default()),
         }
     }
 
     /// Create a new CKBIndexerClient with ckb RPC url.
     pub fn with_ckb_url(url: &str) -> Result<Self> {
-        let client = HttpClient::builder()
-            .timeout(DEFAULT_HTTP_TIMEOUT)
-            .build(url)?;
-        Ok(Self::new(client, false))
+        let endpoints = Endpoints::new(EndpointKind::Ckb, &[url.to_string()])?;
+        Ok(Self {
+            endpoints,
+            is_standalone: false,
+            cache: IndexerCache::new(Default::default()),
+        })
+    }
+
+    /// Create a new CKBIndexerClient using the built-in indexer of the ckb
+    /// client, sharing its endpoints.
+    pub fn with_ckb_client(ckb_client: &CKBClient) -> Self {
+        Self {
+            endpoints: ckb_client.endpoints().clone(),
+            is_standalone: false,
+            cache: IndexerCache::new(Default::default()),
+        }
     }
 
     /// Create a new CKBIndexerClient with standalone indexer url.
     pub fn with_url(url: &str) -> Result<Self> {
-        let client = HttpClient::builder()
-            .timeout(DEFAULT_HTTP_TIMEOUT)
-            .build(url)?;
-        Ok(Self::new(client, true))
+        Self::with_urls(&[url.to_string()])
+    }
+
+    /// Create a new CKBIndexerClient with standalone indexer urls. The first
+    /// URL is the primary endpoint, others are backups.
+    pub fn with_urls(urls: &[String]) -> Result<Self> {
+        let endpoints = Endpoints::new(EndpointKind::Indexer, urls)?;
+        Ok(Self {
+            endpoints,
+            is_standalone: true,
+            cache: IndexerCache::new(Default::default()),
+        })
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    pub fn with_cache(self, config: IndexerCacheConfig) -> Self {
+        Self {
+            cache: IndexerCache::new(config),
+            ..self
+        }
     }
 
-    fn client(&self) -> &HttpClient {
-        &self.client
+    pub fn cache(&self) -> &IndexerCache {
+        &self.cache
+    }
+
+    /// All live cells of the search key from the indexer cache, see
+    /// `IndexerCache::get_cells`.
+    pub async fn get_cells_cached(
+        &self,
+        search_key: &SearchKey,
+        order: Order,
+    ) -> Result<Arc<Vec<CellInfo>>> {
+        self.cache.get_cells(self, search_key, order).await
     }
 
     #[instrument(skip_all)]
@@ -74,7 +129,7 @@ impl CKBIndexerClient {
         params: Option<ClientParams>,
     ) -> Result<T> {
         let response = self
-            .client()
+            .endpoints
             .request(method, params)
             .await
             .map_err(|err| RPCRequestError::new("ckb indexer client", method, err))?;
@@ -100,6 +155,17 @@ impl CKBIndexerClient {
         .await
     }
 
+    /// Total capacity of live cells matching `search_key`.
+    pub async fn get_cells_capacity(&self, search_key: &SearchKey) -> Result<u64> {
+        let capacity: Option<CellsCapacity> = self
+            .request(
+                "get_cells_capacity",
+                Some(ClientParams::Array(vec![json!(search_key)])),
+            )
+            .await?;
+        Ok(capacity.map_or(0, |c| c.capacity.value()))
+    }
+
     pub async fn get_transactions(
         &self,
         search_key: &SearchKey,
@@ -148,7 +214,10 @@ impl CKBIndexerClient {
         let mut total_capacity = 0u128;
         let mut finalized_capacity = 0u128;
         let mut cells_count = 0;
+        let mut finalized_cells_count = 0;
         let mut ckb_cells_count = 0;
+        let mut capacity_histogram = BTreeMap::new();
+        let mut oldest_unfinalized_block_number: Option<u64> = None;
         let mut cursor = None;
         loop {
             let cells: Pagination<Cell> = self
@@ -172,6 +241,9 @@ impl CKBIndexerClient {
             for cell in cells.objects.into_iter() {
                 let capacity: u64 = cell.output.capacity.into();
                 total_capacity += capacity as u128;
+                *capacity_histogram
+                    .entry(capacity_bucket(capacity))
+                    .or_default() += 1;
                 let is_finalized = {
                     let args = cell.output.lock.args.into_bytes();
                     let args = CustodianLockArgs::from_slice(&args[32..]).unwrap();
@@ -181,6 +253,13 @@ impl CKBIndexerClient {
                 };
                 if is_finalized {
                     finalized_capacity += capacity as u128;
+                    finalized_cells_count += 1;
+                } else {
+                    let block_number = cell.block_number.value();
+                    oldest_unfinalized_block_number = Some(
+                        oldest_unfinalized_block_number
+                            .map_or(block_number, |n| n.min(block_number)),
+                    );
                 }
 
                 if let Some(type_) = cell.output.type_.as_ref() {
@@ -197,6 +276,7 @@ impl CKBIndexerClient {
                     stat.cells_count += 1;
                     if is_finalized {
                         stat.finalized_amount += amount;
+                        stat.finalized_cells_count += 1;
                     }
                 } else {
                     ckb_cells_count += 1;
@@ -205,10 +285,25 @@ impl CKBIndexerClient {
         }
         Ok(CustodianStat {
             cells_count,
+            finalized_cells_count,
             total_capacity,
             finalized_capacity,
             sudt_stat,
             ckb_cells_count,
+            capacity_histogram,
+            oldest_unfinalized_block_number,
         })
     }
 }
+
+/// Lower bound in CKB of the power of 10 bucket `capacity` falls in.
+fn capacity_bucket(capacity: u64) -> u64 {
+    let ckb = capacity / 100_000_000;
+    let mut bucket = 0;
+    let mut next = 1;
+    while next <= ckb {
+        bucket = next;
+        next *= 10;
+    }
+    bucket
+}
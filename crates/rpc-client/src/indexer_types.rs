@@ -8,7 +8,7 @@ use gw_jsonrpc_types::ckb_jsonrpc_types::{
 use gw_types::offchain::CellInfo;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct SearchKey {
     pub script: Script,
     pub script_type: ScriptType,
@@ -39,7 +39,7 @@ impl SearchKey {
     }
 }
 
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Clone, Deserialize, Serialize, Default)]
 pub struct SearchKeyFilter {
     pub script: Option<Script>,
     pub output_data_len_range: Option<[Uint64; 2]>,
@@ -47,14 +47,14 @@ pub struct SearchKeyFilter {
     pub block_range: Option<[BlockNumber; 2]>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ScriptType {
     Lock,
     Type,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Order {
     Desc,
@@ -70,6 +70,13 @@ pub struct Tx {
     pub io_type: IOType,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct CellsCapacity {
+    pub capacity: Uint64,
+    pub block_hash: H256,
+    pub block_number: BlockNumber,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Cell {
     pub output: CellOutput,
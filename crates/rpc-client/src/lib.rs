@@ -1,6 +1,10 @@
 pub mod ckb_client;
 pub mod contract;
+pub mod cycles_limit;
+pub mod endpoints;
 pub mod error;
+pub mod fee_estimator;
+pub mod indexer_cache;
 pub mod indexer_client;
 pub mod indexer_types;
 pub mod rpc_client;
@@ -1,6 +1,7 @@
 #![allow(clippy::mutable_key_type)]
 
 use crate::ckb_client::CKBClient;
+use crate::endpoints::Endpoints;
 use crate::indexer_client::CKBIndexerClient;
 use crate::indexer_types::{Cell, Order, Pagination, ScriptType, SearchKey, SearchKeyFilter};
 use crate::utils::{to_h256, to_jsonh256, DEFAULT_QUERY_LIMIT, TYPE_ID_CODE_HASH};
@@ -8,6 +9,7 @@ use anyhow::{anyhow, Result};
 use async_jsonrpc_client::Params as ClientParams;
 use ckb_types::core::hardfork::HardForkSwitch;
 use ckb_types::prelude::Entity;
+use gw_config::RPCHealthCheckConfig;
 use gw_jsonrpc_types::ckb_jsonrpc_types::{self, BlockNumber, Consensus, Uint32};
 use gw_types::offchain::{CellStatus, CellWithStatus, CompatibleFinalizedTimepoint, DepositInfo};
 use gw_types::{
@@ -28,7 +30,31 @@ use serde_json::json;
 use tracing::instrument;
 
 use gw_types::core::Timepoint;
-use std::{collections::HashSet, time::Duration};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Write a tx to `<dir>/txs/<tx hash>.json` in place of sending it.
+fn write_dry_run_tx(
+    dir: &Path,
+    tx_hash: H256,
+    tx: &ckb_jsonrpc_types::Transaction,
+) -> Result<H256> {
+    let txs_dir = dir.join("txs");
+    fs::create_dir_all(&txs_dir)?;
+    let path = txs_dir.join(format!("{}.json", tx_hash.pack()));
+    fs::write(&path, serde_json::to_string_pretty(tx)?)?;
+    gw_metrics::block_producer().dry_run_txs.inc();
+    log::info!(
+        "[dry run] write tx {} to {}",
+        tx_hash.pack(),
+        path.display()
+    );
+    Ok(tx_hash)
+}
 
 fn to_cell_info(cell: Cell) -> CellInfo {
     let out_point = {
@@ -80,6 +106,46 @@ fn parse_deposit_request(
     Some(request)
 }
 
+/// Deposit cells are searched in this many recent blocks.
+pub const DEPOSIT_BLOCKS_TO_SEARCH: u64 = 2000;
+
+/// Parse a deposit cell, `None` if it is invalid.
+pub fn parse_deposit_cell(cell: CellInfo, min_sudt_deposit_capacity: u64) -> Option<DepositInfo> {
+    // Ensure finalized ckb custodians are clearly mergeable
+    if cell.output.type_().is_none() && !cell.data.is_empty() {
+        return None;
+    }
+
+    let args: Bytes = cell.output.lock().args().unpack();
+    let deposit_lock_args = match DepositLockArgsReader::verify(&args[32..], false) {
+        Ok(()) => DepositLockArgs::new_unchecked(args.slice(32..)),
+        Err(_) => {
+            log::debug!(target: "collect-deposit-cells", "invalid deposit cell args: \n{:#x}", args);
+            return None;
+        }
+    };
+    let request = match parse_deposit_request(&cell.output, &cell.data, &deposit_lock_args) {
+        Some(r) => r,
+        None => {
+            log::debug!(target: "collect-deposit-cells", "invalid deposit cell: \n{:?}", cell);
+            return None;
+        }
+    };
+
+    let cell_capacity = cell.output.capacity().unpack();
+    if cell.output.type_().is_some() && cell_capacity < min_sudt_deposit_capacity {
+        log::debug!(
+            target: "collect-deposit-cells",
+            "invalid sudt deposit cell, required capacity: {}, capacity: {}",
+            min_sudt_deposit_capacity,
+            cell_capacity
+        );
+        return None;
+    }
+
+    Some(DepositInfo { cell, request })
+}
+
 pub enum QueryResult<T> {
     Full(T),
     NotEnough(T),
@@ -114,6 +180,9 @@ pub struct RPCClient {
     pub ckb: CKBClient,
     pub rollup_type_script: ckb_types::packed::Script,
     pub rollup_config: RollupConfig,
+    /// Write txs to this directory instead of sending them, see
+    /// [`gw_config::DryRunConfig`].
+    dry_run_dir: Option<PathBuf>,
 }
 
 impl RPCClient {
@@ -128,7 +197,48 @@ impl RPCClient {
             ckb,
             rollup_type_script,
             rollup_config,
+            dry_run_dir: None,
+        }
+    }
+
+    /// Write txs to `<dir>/txs` instead of sending them.
+    pub fn with_dry_run(mut self, dir: PathBuf) -> Self {
+        self.dry_run_dir = Some(dir);
+        self
+    }
+
+    pub fn dry_run_dir(&self) -> Option<&Path> {
+        self.dry_run_dir.as_deref()
+    }
+
+    /// Endpoints of the ckb client and the indexer client, the latter is
+    /// `None` if it uses the built-in indexer of the ckb client.
+    pub fn endpoints(&self) -> (&Endpoints, Option<&Endpoints>) {
+        let indexer = self.indexer.endpoints();
+        let ckb = self.ckb.endpoints();
+        (ckb, Some(indexer).filter(|indexer| !indexer.ptr_eq(ckb)))
+    }
+
+    /// Check health of endpoints periodically if there are backup endpoints.
+    pub fn spawn_health_check(&self, config: &RPCHealthCheckConfig) {
+        let (ckb, indexer) = self.endpoints();
+        let endpoints: Vec<Endpoints> = { Some(ckb).into_iter().chain(indexer) }
+            .filter(|endpoints| endpoints.has_backups())
+            .cloned()
+            .collect();
+        if endpoints.is_empty() {
+            return;
         }
+        let interval = Duration::from_secs(config.interval_secs);
+        let max_tip_lag = config.max_tip_lag;
+        tokio::spawn(async move {
+            loop {
+                for endpoints in &endpoints {
+                    endpoints.check_health(max_tip_lag).await;
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
     }
 
     /// query lived rollup cell
@@ -405,22 +515,17 @@ impl RPCClient {
         }))
     }
 
-    /// return all lived deposit requests
-    /// NOTICE the returned cells may contains invalid cells.
-    #[instrument(skip(self, dead_cells))]
-    pub async fn query_deposit_cells(
+    /// Search key of deposit cells with at least `min_ckb_deposit_capacity`,
+    /// from `from_block`.
+    pub fn deposit_search_key(&self, from_block: u64, min_ckb_deposit_capacity: u64) -> SearchKey {
+        self.deposit_search_key_in_range([from_block, u64::MAX], min_ckb_deposit_capacity)
+    }
+
+    fn deposit_search_key_in_range(
         &self,
-        count: usize,
-        deposit_minimal_blocks: u64,
+        block_range: [u64; 2],
         min_ckb_deposit_capacity: u64,
-        min_sudt_deposit_capacity: u64,
-        dead_cells: &HashSet<OutPoint>,
-    ) -> Result<Vec<DepositInfo>> {
-        const BLOCKS_TO_SEARCH: u64 = 2000;
-
-        let tip_number = self.get_tip().await?.number().unpack();
-        let mut deposit_infos = Vec::new();
-
+    ) -> SearchKey {
         let script = Script::new_builder()
             .code_hash(self.rollup_config.deposit_script_type_hash())
             .hash_type(ScriptHashType::Type.into())
@@ -431,13 +536,8 @@ impl RPCClient {
             let lock = ckb_types::packed::Script::new_unchecked(script.as_bytes());
             lock.into()
         };
-        let from_block = tip_number.saturating_sub(BLOCKS_TO_SEARCH);
-        let to_block = tip_number.saturating_sub(deposit_minimal_blocks);
-
-        log::debug!(target: "collect-deposit-cells", "start searching deposit cells from_block {} to_block {} count {} min_ckb_deposit_capacity {} min_sudt_deposit_capacity {}",
-             from_block, to_block, count, min_ckb_deposit_capacity, min_sudt_deposit_capacity);
-
-        let search_key = SearchKey {
+        let [from_block, to_block] = block_range;
+        SearchKey {
             script,
             script_type: ScriptType::Lock,
             filter: Some(SearchKeyFilter {
@@ -446,7 +546,31 @@ impl RPCClient {
                 output_capacity_range: Some([min_ckb_deposit_capacity.into(), u64::MAX.into()]),
                 block_range: Some([BlockNumber::from(from_block), BlockNumber::from(to_block)]),
             }),
-        };
+        }
+    }
+
+    /// return all lived deposit requests
+    /// NOTICE the returned cells may contains invalid cells.
+    #[instrument(skip(self, dead_cells))]
+    pub async fn query_deposit_cells(
+        &self,
+        count: usize,
+        deposit_minimal_blocks: u64,
+        min_ckb_deposit_capacity: u64,
+        min_sudt_deposit_capacity: u64,
+        dead_cells: &HashSet<OutPoint>,
+    ) -> Result<Vec<DepositInfo>> {
+        let tip_number = self.get_tip().await?.number().unpack();
+        let mut deposit_infos = Vec::new();
+
+        let from_block = tip_number.saturating_sub(DEPOSIT_BLOCKS_TO_SEARCH);
+        let to_block = tip_number.saturating_sub(deposit_minimal_blocks);
+
+        log::debug!(target: "collect-deposit-cells", "start searching deposit cells from_block {} to_block {} count {} min_ckb_deposit_capacity {} min_sudt_deposit_capacity {}",
+             from_block, to_block, count, min_ckb_deposit_capacity, min_sudt_deposit_capacity);
+
+        let search_key =
+            self.deposit_search_key_in_range([from_block, to_block], min_ckb_deposit_capacity);
         let order = Order::Asc;
 
         let mut cursor = None;
@@ -474,64 +598,14 @@ impl RPCClient {
             }
             cursor = Some(cells.last_cursor);
 
-            let cells = cells.objects.into_iter().map(|cell| {
-                let out_point = {
-                    let out_point: ckb_types::packed::OutPoint = cell.out_point.into();
-                    OutPoint::new_unchecked(out_point.as_bytes())
-                };
-                let output = {
-                    let output: ckb_types::packed::CellOutput = cell.output.into();
-                    CellOutput::new_unchecked(output.as_bytes())
-                };
-                let data = cell.output_data.into_bytes();
-                CellInfo {
-                    out_point,
-                    output,
-                    data,
-                }
-            });
-
-            for cell in cells {
-                // Ensure finalized ckb custodians are clearly mergeable
-                if dead_cells.contains(&cell.out_point)
-                    || cell.output.type_().is_none() && !cell.data.is_empty()
-                {
+            for cell in cells.objects {
+                let cell = cell.info();
+                if dead_cells.contains(&cell.out_point) {
                     continue;
                 }
-
-                let args: Bytes = cell.output.lock().args().unpack();
-                let deposit_lock_args = match DepositLockArgsReader::verify(&args[32..], false) {
-                    Ok(()) => DepositLockArgs::new_unchecked(args.slice(32..)),
-                    Err(_) => {
-                        log::debug!(target: "collect-deposit-cells", "invalid deposit cell args: \n{:#x}", args);
-                        continue;
-                    }
-                };
-                let request = match parse_deposit_request(
-                    &cell.output,
-                    &cell.data,
-                    &deposit_lock_args,
-                ) {
-                    Some(r) => r,
-                    None => {
-                        log::debug!(target: "collect-deposit-cells", "invalid deposit cell: \n{:?}", cell);
-                        continue;
-                    }
-                };
-
-                let cell_capacity = cell.output.capacity().unpack();
-                if cell.output.type_().is_some() && cell_capacity < min_sudt_deposit_capacity {
-                    log::debug!(
-                        target: "collect-deposit-cells",
-                        "invalid sudt deposit cell, required capacity: {}, capacity: {}",
-                        min_sudt_deposit_capacity,
-                        cell_capacity
-                    );
-                    continue;
+                if let Some(info) = parse_deposit_cell(cell, min_sudt_deposit_capacity) {
+                    deposit_infos.push(info);
                 }
-
-                let info = DepositInfo { cell, request };
-                deposit_infos.push(info);
             }
         }
 
@@ -883,6 +957,7 @@ impl RPCClient {
         &self,
         compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
         exclusions: &HashSet<OutPoint>,
+        owner_lock_hashes: &HashSet<H256>,
         max_cells: usize,
     ) -> Result<Vec<CellInfo>> {
         let withdrawal_lock = Script::new_builder()
@@ -932,6 +1007,13 @@ impl RPCClient {
                     continue;
                 }
 
+                if !owner_lock_hashes.is_empty() {
+                    match crate::withdrawal::owner_lock_hash(&info) {
+                        Some(h) if owner_lock_hashes.contains(&h) => (),
+                        _ => continue,
+                    }
+                }
+
                 collected.push(info);
                 if collected.len() >= max_cells {
                     break;
@@ -947,6 +1029,70 @@ impl RPCClient {
         Ok(collected)
     }
 
+    /// Return deposit cells whose `owner_lock_hash` is `owner_lock_hash`, along
+    /// with the number of the block they were created in.
+    #[instrument(skip_all, err(Debug))]
+    pub async fn query_owner_deposit_cells(
+        &self,
+        owner_lock_hash: &H256,
+        max_cells: usize,
+    ) -> Result<Vec<(CellInfo, u64)>> {
+        let deposit_lock = Script::new_builder()
+            .code_hash(self.rollup_config.deposit_script_type_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .args(self.rollup_type_script.calc_script_hash().as_bytes().pack())
+            .build();
+
+        let search_key = SearchKey {
+            script: ckb_types::packed::Script::new_unchecked(deposit_lock.as_bytes()).into(),
+            script_type: ScriptType::Lock,
+            filter: None,
+        };
+        let order = Order::Asc;
+        let limit = Uint32::from(DEFAULT_QUERY_LIMIT as u32);
+
+        let mut collected = vec![];
+        let mut cursor = None;
+
+        loop {
+            let cells: Pagination<Cell> = self
+                .indexer
+                .request(
+                    "get_cells",
+                    Some(ClientParams::Array(vec![
+                        json!(search_key),
+                        json!(order),
+                        json!(limit),
+                        json!(cursor),
+                    ])),
+                )
+                .await?;
+
+            for cell in cells.objects.into_iter() {
+                let block_number = cell.block_number.value();
+                let info = to_cell_info(cell);
+                let args: Bytes = info.output.lock().args().unpack();
+                if args.len() < 32 || DepositLockArgsReader::verify(&args[32..], false).is_err() {
+                    continue;
+                }
+                let deposit_lock_args = DepositLockArgs::new_unchecked(args.slice(32..));
+                if &deposit_lock_args.owner_lock_hash().unpack() != owner_lock_hash {
+                    continue;
+                }
+
+                collected.push((info, block_number));
+                if collected.len() >= max_cells {
+                    return Ok(collected);
+                }
+            }
+
+            if cells.last_cursor.is_empty() {
+                return Ok(collected);
+            }
+            cursor = Some(cells.last_cursor);
+        }
+    }
+
     #[instrument(skip_all, fields(block_hash = %block_hash.pack()))]
     pub async fn get_header(
         &self,
@@ -982,10 +1128,14 @@ impl RPCClient {
 
     #[instrument(skip_all, fields(tx_hash = %tx.hash().pack()))]
     pub async fn send_transaction(&self, tx: &Transaction) -> Result<H256> {
+        let tx_hash = tx.hash();
         let tx: ckb_jsonrpc_types::Transaction = {
             let tx = ckb_types::packed::Transaction::new_unchecked(tx.as_bytes());
             tx.into()
         };
+        if let Some(ref dir) = self.dry_run_dir {
+            return write_dry_run_tx(dir, tx_hash, &tx);
+        }
         let tx_hash: ckb_types::H256 = self
             .ckb
             .request(
@@ -2,6 +2,7 @@ use anyhow::{bail, Result};
 use ckb_types::prelude::{Entity, Reader};
 use gw_types::bytes::Bytes;
 use gw_types::core::{ScriptHashType, Timepoint};
+use gw_types::h256::H256;
 use gw_types::offchain::{CellInfo, CompatibleFinalizedTimepoint};
 use gw_types::packed::{
     Byte32, Script, ScriptReader, WithdrawalLockArgs, WithdrawalLockArgsReader,
@@ -75,6 +76,18 @@ fn verify_finalized_owner_lock(
     Ok(())
 }
 
+/// Owner lock hash in the withdrawal lock args, `None` if the args are invalid.
+pub fn owner_lock_hash(info: &CellInfo) -> Option<H256> {
+    let args: Bytes = info.output.lock().args().unpack();
+    let lock_args_end = 32 + WithdrawalLockArgs::TOTAL_SIZE;
+    if args.len() < lock_args_end {
+        return None;
+    }
+    WithdrawalLockArgsReader::verify(&args.slice(32..lock_args_end), false).ok()?;
+    let lock_args = WithdrawalLockArgs::new_unchecked(args.slice(32..lock_args_end));
+    Some(lock_args.owner_lock_hash().unpack())
+}
+
 #[cfg(test)]
 mod test {
     use gw_types::core::{ScriptHashType, Timepoint};
@@ -0,0 +1,219 @@
+//! Method ACLs and token bucket rate limits by client IP, and limits of
+//! read-only executions by API key, configured by `dynamic_config.rpc_access`
+//! so that they can be adjusted by reloading.
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use gw_config::{ExecutionLimitsConfig, RPCAccessConfig, TokenBucketConfig};
+use gw_dynamic_config::manager::DynamicConfigManager;
+use gw_generator::ArcSwap;
+use hyper::HeaderMap;
+use lru::LruCache;
+
+/// Max number of (client IP, method) buckets tracked.
+const MAX_BUCKETS: usize = 100_000;
+
+tokio::task_local! {
+    /// Execution limits of the request being served.
+    static EXECUTION_LIMITS: ExecutionLimitsConfig;
+}
+
+/// Execution limits of the request being served, empty if not served with
+/// access control.
+pub fn execution_limits() -> ExecutionLimitsConfig {
+    EXECUTION_LIMITS.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Run `f` with `limits` as the execution limits of the request.
+pub async fn with_execution_limits<F: std::future::Future>(
+    limits: ExecutionLimitsConfig,
+    f: F,
+) -> F::Output {
+    EXECUTION_LIMITS.scope(limits, f).await
+}
+
+pub enum AccessDenied {
+    MethodNotAllowed(String),
+    RateLimited(String),
+}
+
+pub struct AccessControl {
+    dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
+    /// Buckets by client IP and method, `None` for the bucket across all
+    /// methods.
+    buckets: Mutex<LruCache<(IpAddr, Option<String>), TokenBucket>>,
+}
+
+impl AccessControl {
+    pub fn new(dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>) -> Self {
+        Self {
+            dynamic_config_manager,
+            buckets: Mutex::new(LruCache::new(MAX_BUCKETS)),
+        }
+    }
+
+    /// Whether any ACL or rate limit is configured.
+    pub fn is_enabled(&self) -> bool {
+        let manager = self.dynamic_config_manager.load();
+        let config = manager.get_rpc_access();
+        !config.allowed_methods.is_empty()
+            || !config.denied_methods.is_empty()
+            || config.ip_rate_limit.is_some()
+            || !config.method_rate_limits.is_empty()
+    }
+
+    /// Client IP of a request, from `client_ip_header` if configured.
+    pub fn client_ip(&self, headers: &HeaderMap, remote_ip: IpAddr) -> IpAddr {
+        let manager = self.dynamic_config_manager.load();
+        let header = match manager.get_rpc_access().client_ip_header {
+            Some(ref header) => header,
+            None => return remote_ip,
+        };
+        headers
+            .get(header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(remote_ip)
+    }
+
+    /// Execution limits of a request, by the API key in `api_key_header`.
+    pub fn execution_limits(&self, headers: &HeaderMap) -> ExecutionLimitsConfig {
+        let manager = self.dynamic_config_manager.load();
+        let config = manager.get_rpc_access();
+        let api_key_limits = config
+            .api_key_header
+            .as_ref()
+            .and_then(|header| headers.get(header.as_str()))
+            .and_then(|v| v.to_str().ok())
+            .and_then(|api_key| config.api_key_execution_limits.get(api_key));
+        match api_key_limits {
+            Some(limits) => limits.or(&config.execution_limits),
+            None => config.execution_limits.clone(),
+        }
+    }
+
+    /// Check calls of `methods` from `client_ip`. Each call of a batch
+    /// request takes a token.
+    pub fn check(&self, client_ip: IpAddr, methods: &[String]) -> Result<(), AccessDenied> {
+        let manager = self.dynamic_config_manager.load();
+        let config = manager.get_rpc_access();
+        if config.trusted_ips.contains(&client_ip) {
+            return Ok(());
+        }
+
+        for method in methods {
+            if !is_method_allowed(config, method) {
+                return Err(AccessDenied::MethodNotAllowed(method.clone()));
+            }
+        }
+
+        let mut buckets = self.buckets.lock().expect("lock");
+        for method in methods {
+            if let Some(ref limit) = config.ip_rate_limit {
+                if !acquire(&mut buckets, (client_ip, None), limit) {
+                    return Err(AccessDenied::RateLimited(method.clone()));
+                }
+            }
+            if let Some(limit) = config.method_rate_limits.get(method) {
+                if !acquire(&mut buckets, (client_ip, Some(method.clone())), limit) {
+                    return Err(AccessDenied::RateLimited(method.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_method_allowed(config: &RPCAccessConfig, method: &str) -> bool {
+    (config.allowed_methods.is_empty() || config.allowed_methods.contains(method))
+        && !config.denied_methods.contains(method)
+}
+
+fn acquire(
+    buckets: &mut LruCache<(IpAddr, Option<String>), TokenBucket>,
+    key: (IpAddr, Option<String>),
+    config: &TokenBucketConfig,
+) -> bool {
+    if let Some(bucket) = buckets.get_mut(&key) {
+        return bucket.try_acquire(config);
+    }
+    let mut bucket = TokenBucket::new(config);
+    let acquired = bucket.try_acquire(config);
+    buckets.put(key, bucket);
+    acquired
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(config: &TokenBucketConfig) -> Self {
+        Self {
+            tokens: burst(config),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Refill with the current config, so that reloaded limits apply to
+    /// existing buckets.
+    fn try_acquire(&mut self, config: &TokenBucketConfig) -> bool {
+        let now = Instant::now();
+        let refill =
+            now.duration_since(self.last_refill).as_secs_f64() * config.requests_per_second as f64;
+        self.tokens = (self.tokens + refill).min(burst(config));
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn burst(config: &TokenBucketConfig) -> f64 {
+    config.burst.unwrap_or(config.requests_per_second) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let config = TokenBucketConfig {
+            requests_per_second: 1,
+            burst: Some(3),
+        };
+        let mut bucket = TokenBucket::new(&config);
+        for _ in 0..3 {
+            assert!(bucket.try_acquire(&config));
+        }
+        assert!(!bucket.try_acquire(&config));
+
+        bucket.last_refill -= std::time::Duration::from_secs(1);
+        assert!(bucket.try_acquire(&config));
+        assert!(!bucket.try_acquire(&config));
+    }
+
+    #[test]
+    fn test_method_acl() {
+        let mut config = RPCAccessConfig::default();
+        assert!(is_method_allowed(&config, "gw_execute_raw_l2transaction"));
+
+        config
+            .denied_methods
+            .insert("gw_execute_raw_l2transaction".to_string());
+        assert!(!is_method_allowed(&config, "gw_execute_raw_l2transaction"));
+        assert!(is_method_allowed(&config, "gw_ping"));
+
+        config.allowed_methods.insert("gw_ping".to_string());
+        assert!(is_method_allowed(&config, "gw_ping"));
+        assert!(!is_method_allowed(&config, "gw_get_balance"));
+    }
+}
@@ -0,0 +1,109 @@
+//! `gw_list_account_transactions`, txs sent by or to an account, by the
+//! account tx index.
+//!
+//! Pages are like the `get_transactions` of the CKB indexer, pass the
+//! `last_cursor` of a page as `after` to get the next page.
+
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use gw_common::{registry_address::RegistryAddress, state::State};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    godwoken::{AccountRef, AccountTransaction, AccountTransactions, Order},
+};
+use gw_store::{
+    account_tx_index::{ROLE_RECEIVER, ROLE_SENDER},
+    mem_pool_state::MemPoolState,
+    Store,
+};
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+use serde::Deserialize;
+
+use crate::registry::invalid_param_err;
+use crate::utils::to_jsonh256;
+
+const MAX_LIMIT: u32 = 1000;
+
+// account, order, limit, after
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ListAccountTxsParams {
+    First((AccountRef, Order, Uint32)),
+    After((AccountRef, Order, Uint32, Option<JsonBytes>)),
+}
+
+pub(crate) async fn list_account_transactions(
+    Params(params): Params<ListAccountTxsParams>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<AccountTransactions, RpcError> {
+    let (account, order, limit, after) = match params {
+        ListAccountTxsParams::First((account, order, limit)) => (account, order, limit, None),
+        ListAccountTxsParams::After(p) => p,
+    };
+    let limit = limit.value();
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(invalid_param_err("limit must be in [1, 1000]"));
+    }
+    let after = match after {
+        Some(cursor) if !cursor.is_empty() => Some(decode_cursor(cursor.as_bytes())?),
+        _ => None,
+    };
+    let account_id = match account {
+        AccountRef::Id(id) => id.value(),
+        AccountRef::RegistryAddress(address) => {
+            let address = RegistryAddress::from_slice(address.as_bytes())
+                .ok_or_else(|| invalid_param_err("Invalid registry address"))?;
+            let state = mem_pool_state.load_state_db();
+            let id = match state.get_script_hash_by_registry_address(&address)? {
+                Some(script_hash) => state.get_account_id_by_script_hash(&script_hash)?,
+                None => None,
+            };
+            match id {
+                Some(id) => id,
+                None => return Ok(AccountTransactions::default()),
+            }
+        }
+    };
+
+    let snap = store.get_snapshot();
+    let transactions: Vec<_> = snap
+        .iter_account_txs(account_id, after, order == Order::Desc)
+        .take(limit as usize)
+        .collect();
+    let last_cursor = match transactions.last() {
+        Some(tx) => encode_cursor(tx.block_number, tx.tx_index),
+        None => JsonBytes::default(),
+    };
+    let transactions = transactions
+        .into_iter()
+        .map(|tx| AccountTransaction {
+            tx_hash: to_jsonh256(tx.tx_hash),
+            block_number: tx.block_number.into(),
+            tx_index: tx.tx_index.into(),
+            is_sender: tx.role & ROLE_SENDER != 0,
+            is_receiver: tx.role & ROLE_RECEIVER != 0,
+        })
+        .collect();
+    Ok(AccountTransactions {
+        transactions,
+        last_cursor,
+    })
+}
+
+/// block number | index in the block, in big endian
+pub(crate) fn encode_cursor(block_number: u64, index: u32) -> JsonBytes {
+    let mut cursor = block_number.to_be_bytes().to_vec();
+    cursor.extend_from_slice(&index.to_be_bytes());
+    JsonBytes::from_vec(cursor)
+}
+
+pub(crate) fn decode_cursor(cursor: &[u8]) -> Result<(u64, u32), RpcError> {
+    if cursor.len() != 12 {
+        return Err(invalid_param_err("Invalid cursor"));
+    }
+    let block_number = u64::from_be_bytes(cursor[..8].try_into().expect("8 bytes"));
+    let index = u32::from_be_bytes(cursor[8..].try_into().expect("4 bytes"));
+    Ok((block_number, index))
+}
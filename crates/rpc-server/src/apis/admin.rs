@@ -0,0 +1,143 @@
+//! Operator-only `admin_*` methods, configured by `rpc_server.admin`.
+//!
+//! They're served on `rpc_server.admin.listen`, and on `rpc_server.listen` to
+//! requests with the bearer token if `rpc_server.admin.auth_token` is set, see
+//! `server.rs`.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use ckb_fixed_hash::H256 as JsonH256;
+use gw_dynamic_config::manager::{DynamicConfigManager, DynamicConfigReloadResponse};
+use gw_generator::ArcSwap;
+use gw_jsonrpc_types::{
+    blockchain::CellDep,
+    godwoken::{BackupManifest, StateDivergence},
+};
+use gw_mem_pool::pool::MemPool;
+use gw_rpc_client::contract::ContractsCellDepManager;
+use gw_store::{backup::create_backup, mem_pool_state::MemPoolState, Store};
+use gw_version::Version;
+use jsonrpc_v2::{Data, MapRouter, Params, ServerBuilder};
+use tokio::sync::Mutex;
+
+use crate::in_queue_request_map::InQueueRequestMap;
+use crate::utils::{to_h256, to_jsonh256};
+
+pub(crate) struct AdminContext {
+    pub mem_pool: Option<Arc<Mutex<MemPool>>>,
+    pub in_queue_request_map: Option<Arc<InQueueRequestMap>>,
+    pub dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
+    /// `None` on read-only nodes.
+    pub contracts_dep_manager: Option<ContractsCellDepManager>,
+    pub mem_pool_state: Arc<MemPoolState>,
+    pub store: Store,
+    pub rollup_type_hash: JsonH256,
+}
+
+pub(crate) fn with_admin_methods(
+    server: ServerBuilder<MapRouter>,
+    ctx: AdminContext,
+) -> ServerBuilder<MapRouter> {
+    server
+        .with_data(Data::new(ctx))
+        .with_method("admin_evict_transaction", evict_transaction)
+        .with_method("admin_reload_config", reload_config)
+        .with_method("admin_refresh_contracts_deps", refresh_contracts_deps)
+        .with_method("admin_dump_mem_block", dump_mem_block)
+        .with_method("admin_set_log_level", set_log_level)
+        .with_method("admin_get_safe_mode", get_safe_mode)
+        .with_method("admin_create_backup", create_backup_checkpoint)
+}
+
+/// Drop an in queue tx or withdrawal instead of pushing it to the mem pool,
+/// returns false if it's not in queue. Requests already in the mem block
+/// can't be evicted.
+async fn evict_transaction(
+    Params((hash,)): Params<(JsonH256,)>,
+    ctx: Data<AdminContext>,
+) -> Result<bool> {
+    let map = match ctx.in_queue_request_map {
+        Some(ref map) => map,
+        None => return Ok(false),
+    };
+    let evicted = map.evict(&to_h256(hash.clone()));
+    if evicted {
+        log::info!("[admin] evict {}", hash);
+    }
+    Ok(evicted)
+}
+
+async fn reload_config(ctx: Data<AdminContext>) -> Result<DynamicConfigReloadResponse> {
+    gw_dynamic_config::reload(ctx.dynamic_config_manager.clone()).await
+}
+
+/// Query contract cells again, e.g. after contracts are upgraded, returns the
+/// new cell deps that changed.
+async fn refresh_contracts_deps(ctx: Data<AdminContext>) -> Result<Vec<CellDep>> {
+    let manager = { ctx.contracts_dep_manager.as_ref() }
+        .ok_or_else(|| anyhow!("no contracts deps on read-only nodes"))?;
+    let changed = manager.refresh().await?;
+    Ok(changed.into_iter().map(|(_old, new)| new).collect())
+}
+
+/// Save the mem block to the mem block restore path, returns the path.
+async fn dump_mem_block(ctx: Data<AdminContext>) -> Result<String> {
+    let mem_pool = { ctx.mem_pool.as_ref() }.ok_or_else(|| anyhow!("no mem pool"))?;
+    let mut mem_pool = mem_pool.lock().await;
+    mem_pool.save_mem_block_with_suffix("admin")?;
+    log::info!("[admin] dump mem block");
+    Ok(mem_pool.restore_manager().path().display().to_string())
+}
+
+/// Replace the log filter with directives in the `RUST_LOG` syntax, e.g.
+/// `info,gw_mem_pool=debug`, until the next config reload or restart.
+async fn set_log_level(Params((filter,)): Params<(String,)>) -> Result<()> {
+    gw_telemetry::trace::reload_log_filter(&filter)?;
+    log::info!("[admin] set log filter {}", filter);
+    Ok(())
+}
+
+/// The state divergence that the node entered safe mode for, `None` if it's
+/// not in safe mode.
+async fn get_safe_mode(ctx: Data<AdminContext>) -> Result<Option<StateDivergence>> {
+    let divergence = ctx.mem_pool_state.state_divergence();
+    Ok(divergence.map(|d| StateDivergence {
+        block_number: d.block_number.into(),
+        block_hash: to_jsonh256(d.block_hash),
+        expected_root: to_jsonh256(d.expected_root),
+        actual_root: to_jsonh256(d.actual_root),
+        expected_count: d.expected_count.into(),
+        actual_count: d.actual_count.into(),
+    }))
+}
+
+/// Checkpoint the database to the directory `path` on the node, which must not
+/// exist, see `godwoken restore`.
+async fn create_backup_checkpoint(
+    Params((path,)): Params<(String,)>,
+    ctx: Data<AdminContext>,
+) -> Result<BackupManifest> {
+    let store = ctx.store.clone();
+    let rollup_type_hash = ctx.rollup_type_hash.clone();
+    let manifest = tokio::task::spawn_blocking(move || {
+        create_backup(
+            &store,
+            rollup_type_hash,
+            Version::current().to_string(),
+            Path::new(&path),
+        )
+    })
+    .await??;
+    log::info!(
+        "[admin] backup tip block {} {}",
+        manifest.tip_block_number,
+        manifest.tip_block_hash
+    );
+    Ok(BackupManifest {
+        rollup_type_hash: manifest.rollup_type_hash,
+        tip_block_number: manifest.tip_block_number.into(),
+        tip_block_hash: manifest.tip_block_hash,
+        version: manifest.version,
+    })
+}
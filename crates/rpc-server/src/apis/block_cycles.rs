@@ -0,0 +1,67 @@
+//! `gw_get_block_cycles_report`, cycles of a block by backend and by syscall
+//! class, to see what consumes the block capacity.
+//!
+//! Reports are recorded when blocks are inserted, blocks inserted before the
+//! upgrade have no reports.
+
+use anyhow::Result;
+use gw_generator::Generator;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::Uint64,
+    godwoken::{BackendCycles, BackendType, BlockCyclesReport, SyscallCycles},
+};
+use gw_store::{traits::chain_store::ChainStore, Store};
+use jsonrpc_v2::{Data, Params};
+
+use crate::registry::to_rpc_backend_type;
+use crate::utils::to_jsonh256;
+
+pub(crate) async fn get_block_cycles_report(
+    Params((block_number,)): Params<(Uint64,)>,
+    store: Data<Store>,
+    generator: Data<Generator>,
+) -> Result<Option<BlockCyclesReport>> {
+    let block_number = block_number.value();
+    let report = match store.get_block_cycles_report(block_number) {
+        Some(report) => report,
+        None => return Ok(None),
+    };
+    let backends = generator
+        .backend_manage()
+        .get_block_consensus_at_height(block_number)
+        .map(|(_, consensus)| &consensus.backends);
+    let backends = report
+        .backends
+        .into_iter()
+        .map(|b| {
+            let backend_type = backends
+                .into_iter()
+                .flat_map(|backends| backends.values())
+                .find(|backend| backend.validator_script_type_hash == b.validator_script_type_hash)
+                .map_or(BackendType::Unknown, |backend| {
+                    to_rpc_backend_type(&backend.backend_type)
+                });
+            BackendCycles {
+                validator_script_type_hash: to_jsonh256(b.validator_script_type_hash),
+                backend_type,
+                tx_count: b.tx_count.into(),
+                execution_cycles: b.execution_cycles.into(),
+                syscall_cycles: b.syscall_cycles.into(),
+            }
+        })
+        .collect();
+    let syscalls = report
+        .syscalls
+        .into_iter()
+        .map(|s| SyscallCycles {
+            name: s.name,
+            count: s.count.into(),
+            cycles: s.cycles.into(),
+        })
+        .collect();
+    Ok(Some(BlockCyclesReport {
+        block_number: block_number.into(),
+        backends,
+        syscalls,
+    }))
+}
@@ -0,0 +1,39 @@
+//! `gw_get_block_state_diff`, state changes of a block, for indexers to
+//! mirror the L2 state without re-executing txs.
+//!
+//! Diffs are recorded when blocks are inserted, blocks inserted before the
+//! upgrade have no diffs.
+
+use anyhow::Result;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::Uint64,
+    godwoken::{BlockStateDiff, StateDiffEntry},
+};
+use gw_store::{traits::chain_store::ChainStore, Store};
+use jsonrpc_v2::{Data, Params};
+
+use crate::utils::to_jsonh256;
+
+pub(crate) async fn get_block_state_diff(
+    Params((block_number,)): Params<(Uint64,)>,
+    store: Data<Store>,
+) -> Result<Option<BlockStateDiff>> {
+    let block_number = block_number.value();
+    let diff = match store.get_block_state_diff(block_number) {
+        Some(diff) => diff,
+        None => return Ok(None),
+    };
+    let entries = diff
+        .entries
+        .into_iter()
+        .map(|entry| StateDiffEntry {
+            key: to_jsonh256(entry.key),
+            old_value: to_jsonh256(entry.old_value),
+            new_value: to_jsonh256(entry.new_value),
+        })
+        .collect();
+    Ok(Some(BlockStateDiff {
+        block_number: block_number.into(),
+        entries,
+    }))
+}
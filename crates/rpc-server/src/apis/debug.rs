@@ -3,8 +3,19 @@ use std::{convert::TryInto, sync::Arc, time::Instant};
 use anyhow::{anyhow, Result};
 use ckb_fixed_hash::H256 as JsonH256;
 use ckb_types::prelude::{Builder, Entity};
-use gw_generator::Generator;
-use gw_jsonrpc_types::{ckb_jsonrpc_types::Uint64, debug::DebugRunResult};
+use gw_config::SyscallCyclesConfig;
+use gw_generator::{
+    error::TransactionError,
+    syscalls::trace::{self, syscall_name, MachineState, SyscallTrace},
+    Generator,
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{JsonBytes, Uint64},
+    debug::{
+        DebugMachineState, DebugRunResult, DebugTxTrace, StateAccess, SyscallClassSummary,
+        TracedSyscall,
+    },
+};
 use gw_store::{
     chain_view::ChainView,
     state::{
@@ -14,17 +25,27 @@ use gw_store::{
         BlockStateDB,
     },
     traits::chain_store::ChainStore,
+    transaction::{StoreTransaction, TransactionSnapshot},
     Store,
 };
-use gw_types::{packed::BlockInfo, prelude::Unpack};
-use jsonrpc_v2::{Data, Params};
+use gw_types::{
+    h256::*,
+    packed::{BlockInfo, L2Transaction, RawL2Transaction},
+    prelude::*,
+};
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+
+use crate::utils::{to_h256, to_jsonh256};
 
-use crate::utils::to_h256;
+/// A tx failed with a VM error, the error data is the `DebugMachineState`.
+const VM_ERROR_ERR_CODE: i64 = -32009;
 
 pub(crate) struct DebugTransactionContext {
     pub store: Store,
     pub generator: Arc<Generator>,
     pub debug_generator: Arc<Generator>,
+    /// Cycles of syscall classes in traces.
+    pub syscall_cycles: SyscallCyclesConfig,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -50,82 +71,262 @@ pub(crate) async fn replay_transaction(
 
     // run target tx
     let run_result: DebugRunResult = tokio::task::spawn_blocking(move || {
-        let db = ctx.store.begin_transaction();
+        replay_block_txs(
+            &ctx.store,
+            &ctx.generator,
+            tx_hash,
+            |chain_view, hist_state, block_info, raw_tx| {
+                // execute target with debug generator
+                let t = Instant::now();
+                let run_result = ctx.debug_generator.execute_transaction(
+                    chain_view, hist_state, block_info, raw_tx, max_cycles, None,
+                )?;
+                let execution_time = t.elapsed();
+
+                // finalise
+                let t = Instant::now();
+                hist_state.finalise()?;
+                let write_mem_smt_time = t.elapsed();
+
+                // record time
+                let mut debug_run_result: DebugRunResult = run_result.try_into()?;
+                debug_run_result.execution_time_ms = execution_time.as_millis().try_into()?;
+                debug_run_result.write_mem_smt_time_ms =
+                    write_mem_smt_time.as_millis().try_into()?;
+
+                Ok(debug_run_result)
+            },
+        )
+    })
+    .await??;
+
+    // generate response
+    Ok(Some(run_result))
+}
+
+pub(crate) async fn trace_transaction(
+    Params(param): Params<DebugReplayTxParams>,
+    ctx: Data<DebugTransactionContext>,
+) -> Result<Option<DebugTxTrace>, RpcError> {
+    let (tx_hash, max_cycles) = match param {
+        DebugReplayTxParams::Default((tx_hash,)) => (tx_hash, None),
+        DebugReplayTxParams::WithMaxCycles((tx_hash, cycles)) => (tx_hash, Some(cycles.value())),
+    };
+    let tx_hash = to_h256(tx_hash);
+
+    if ctx.store.get_transaction(&tx_hash)?.is_none() {
+        return Ok(None);
+    }
+
+    let trace = tokio::task::spawn_blocking(move || {
+        replay_block_txs(
+            &ctx.store,
+            &ctx.generator,
+            tx_hash,
+            |chain_view, hist_state, block_info, raw_tx| {
+                let t = Instant::now();
+                let (run_result, trace) = ctx.debug_generator.trace_transaction(
+                    chain_view,
+                    hist_state,
+                    block_info,
+                    raw_tx,
+                    max_cycles,
+                    ctx.syscall_cycles.clone(),
+                )?;
+                let execution_time = t.elapsed();
+
+                let mut debug_run_result: DebugRunResult = run_result.try_into()?;
+                debug_run_result.execution_time_ms = execution_time.as_millis().try_into()?;
+                Ok(to_debug_trace(debug_run_result, trace))
+            },
+        )
+    })
+    .await?
+    .map_err(to_rpc_error)?;
 
-        // find tx info
-        let info = db
-            .get_transaction_info(&tx_hash)?
-            .ok_or_else(|| anyhow!("can't find tx on the chain"))?;
-        let block_number = info.block_number().unpack();
-        let tx_index = info.key().index();
-        let block = db
-            .get_block(&info.key().block_hash())?
-            .ok_or_else(|| anyhow!("can't find block"))?;
+    Ok(Some(trace))
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum DebugL2TxParams {
+    Default((JsonBytes,)),
+    WithMaxCycles((JsonBytes, Uint64)),
+}
+
+/// Execute a tx on the state of the tip block and trace it, e.g. a tx
+/// rejected by the mem pool.
+pub(crate) async fn debug_l2transaction(
+    Params(param): Params<DebugL2TxParams>,
+    ctx: Data<DebugTransactionContext>,
+) -> Result<DebugTxTrace, RpcError> {
+    let (l2tx, max_cycles) = match param {
+        DebugL2TxParams::Default((l2tx,)) => (l2tx, None),
+        DebugL2TxParams::WithMaxCycles((l2tx, cycles)) => (l2tx, Some(cycles.value())),
+    };
+    let tx = L2Transaction::from_slice(l2tx.as_bytes())?;
+
+    let trace = tokio::task::spawn_blocking(move || -> Result<DebugTxTrace> {
+        let db = ctx.store.begin_transaction();
+        let tip_block = db.get_last_valid_tip_block()?;
+        let tip_block_number: u64 = tip_block.raw().number().unpack();
         let tip_block_hash = db.get_last_valid_tip_block_hash()?;
         let snap = db.snapshot();
         let chain_view = ChainView::new(&snap, tip_block_hash);
-
-        // build history state
-        let mem_db = MemStore::new(db);
-        let parent_block_number = block_number.saturating_sub(1u64);
-        let mut hist_state = BlockStateDB::from_store(
-            mem_db,
+        let mut state = BlockStateDB::from_store(
+            MemStore::new(db),
             RWConfig {
-                read: ReadOpt::Block(parent_block_number),
-                write: WriteOpt::Block(parent_block_number),
+                read: ReadOpt::Block(tip_block_number),
+                write: WriteOpt::Block(tip_block_number),
             },
         )?;
-        let block_info = {
-            let raw = block.raw();
-            BlockInfo::new_builder()
-                .block_producer(raw.block_producer())
-                .timestamp(raw.timestamp())
-                .number(raw.number())
-                .build()
-        };
-        // execute prev txs
-        for i in 0..tx_index {
-            let tx = block.transactions().get(i as usize).unwrap();
-            let raw_tx = tx.raw();
-            ctx.generator.execute_transaction(
-                &chain_view,
-                &mut hist_state,
-                &block_info,
-                &raw_tx,
-                None,
-                None,
-            )?;
-            hist_state.finalise()?;
-        }
-
-        // execute target with debug generator
-        let tx = block.transactions().get(tx_index as usize).unwrap();
-        let raw_tx = tx.raw();
+        let block_info = BlockInfo::new_builder()
+            .block_producer(tip_block.raw().block_producer())
+            .timestamp(tip_block.raw().timestamp())
+            .number(tip_block_number.saturating_add(1).pack())
+            .build();
+
         let t = Instant::now();
-        let run_result = ctx.debug_generator.execute_transaction(
+        let (run_result, trace) = ctx.debug_generator.trace_transaction(
             &chain_view,
-            &mut hist_state,
+            &mut state,
             &block_info,
-            &raw_tx,
+            &tx.raw(),
             max_cycles,
-            None,
+            ctx.syscall_cycles.clone(),
         )?;
         let execution_time = t.elapsed();
 
-        // finalise
-        let t = Instant::now();
-        hist_state.finalise()?;
-        let write_mem_smt_time = t.elapsed();
-
-        // record time
         let mut debug_run_result: DebugRunResult = run_result.try_into()?;
         debug_run_result.execution_time_ms = execution_time.as_millis().try_into()?;
-        debug_run_result.write_mem_smt_time_ms = write_mem_smt_time.as_millis().try_into()?;
-
-        Result::<_, anyhow::Error>::Ok(debug_run_result)
+        Ok(to_debug_trace(debug_run_result, trace))
     })
-    .await??;
+    .await?
+    .map_err(to_rpc_error)?;
 
-    // generate response
-    Ok(Some(run_result))
+    Ok(trace)
+}
+
+/// VM errors are returned with the machine state as the error data.
+fn to_rpc_error(err: anyhow::Error) -> RpcError {
+    match err.downcast::<TransactionError>() {
+        Ok(TransactionError::VMWithState { error, state }) => RpcError::Full {
+            code: VM_ERROR_ERR_CODE,
+            message: format!("VM error {}", error),
+            data: Some(Box::new(to_debug_machine_state(*state))),
+        },
+        Ok(err) => err.into(),
+        Err(err) => err.into(),
+    }
+}
+
+/// Replay txs before `tx_hash` in its block on the state of the parent block,
+/// then run the tx with `run`.
+fn replay_block_txs<T>(
+    store: &Store,
+    generator: &Generator,
+    tx_hash: H256,
+    run: impl FnOnce(
+        &ChainView<'_, TransactionSnapshot>,
+        &mut BlockStateDB<MemStore<StoreTransaction>>,
+        &BlockInfo,
+        &RawL2Transaction,
+    ) -> Result<T>,
+) -> Result<T> {
+    let db = store.begin_transaction();
+
+    // find tx info
+    let info = db
+        .get_transaction_info(&tx_hash)?
+        .ok_or_else(|| anyhow!("can't find tx on the chain"))?;
+    let block_number = info.block_number().unpack();
+    let tx_index = info.key().index();
+    let block = db
+        .get_block(&info.key().block_hash())?
+        .ok_or_else(|| anyhow!("can't find block"))?;
+    let tip_block_hash = db.get_last_valid_tip_block_hash()?;
+    let snap = db.snapshot();
+    let chain_view = ChainView::new(&snap, tip_block_hash);
+
+    // build history state
+    let mem_db = MemStore::new(db);
+    let parent_block_number = block_number.saturating_sub(1u64);
+    let mut hist_state = BlockStateDB::from_store(
+        mem_db,
+        RWConfig {
+            read: ReadOpt::Block(parent_block_number),
+            write: WriteOpt::Block(parent_block_number),
+        },
+    )?;
+    let block_info = {
+        let raw = block.raw();
+        BlockInfo::new_builder()
+            .block_producer(raw.block_producer())
+            .timestamp(raw.timestamp())
+            .number(raw.number())
+            .build()
+    };
+    // execute prev txs
+    for i in 0..tx_index {
+        let tx = block.transactions().get(i as usize).unwrap();
+        let raw_tx = tx.raw();
+        generator.execute_transaction(
+            &chain_view,
+            &mut hist_state,
+            &block_info,
+            &raw_tx,
+            None,
+            None,
+        )?;
+        hist_state.finalise()?;
+    }
+
+    let tx = block.transactions().get(tx_index as usize).unwrap();
+    run(&chain_view, &mut hist_state, &block_info, &tx.raw())
+}
+
+fn to_traced_syscall(syscall: trace::TracedSyscall) -> TracedSyscall {
+    TracedSyscall {
+        code: syscall.code.into(),
+        name: syscall_name(syscall.code).to_string(),
+        execution_cycles: syscall.execution_cycles.into(),
+        syscall_cycles: syscall.syscall_cycles.into(),
+    }
+}
+
+fn to_debug_machine_state(state: MachineState) -> DebugMachineState {
+    DebugMachineState {
+        pc: state.pc.into(),
+        registers: state.registers.into_iter().map(Into::into).collect(),
+        cycles: state.cycles.into(),
+        last_syscalls: state
+            .last_syscalls
+            .into_iter()
+            .map(to_traced_syscall)
+            .collect(),
+        debug_log: state.debug_log.lines().map(ToString::to_string).collect(),
+    }
+}
+
+fn to_debug_trace(run_result: DebugRunResult, trace: SyscallTrace) -> DebugTxTrace {
+    let to_access = |(key, value): (H256, H256)| StateAccess {
+        key: to_jsonh256(key),
+        value: to_jsonh256(value),
+    };
+    let syscall_summary = trace
+        .summary()
+        .into_iter()
+        .map(|(name, class)| SyscallClassSummary {
+            name: name.to_string(),
+            count: class.count.into(),
+            syscall_cycles: class.syscall_cycles.into(),
+        })
+        .collect();
+    DebugTxTrace {
+        run_result,
+        syscalls: trace.syscalls.into_iter().map(to_traced_syscall).collect(),
+        syscall_summary,
+        state_reads: trace.reads.into_iter().map(to_access).collect(),
+        state_writes: trace.writes.into_iter().map(to_access).collect(),
+    }
 }
@@ -0,0 +1,89 @@
+//! `gw_fee_history`, fee and cycles statistics of recent blocks for fee
+//! suggestions, like `eth_feeHistory`.
+//!
+//! Statistics are recorded when blocks are inserted, blocks inserted before
+//! the upgrade have no statistics and are skipped.
+
+use anyhow::Result;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::Uint64,
+    godwoken::{BlockFeeStats, FeeHistory},
+};
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::prelude::*;
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+use serde::Deserialize;
+
+const INVALID_PARAM_ERR_CODE: i64 = -32602;
+
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+const MAX_PERCENTILES: usize = 100;
+
+// block_count, percentiles
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum FeeHistoryParams {
+    Count((Uint64,)),
+    Percentiles((Uint64, Option<Vec<f64>>)),
+}
+
+pub(crate) async fn fee_history(
+    Params(params): Params<FeeHistoryParams>,
+    store: Data<Store>,
+) -> Result<FeeHistory, RpcError> {
+    let (block_count, percentiles) = match params {
+        FeeHistoryParams::Count((block_count,)) => (block_count, None),
+        FeeHistoryParams::Percentiles((block_count, percentiles)) => (block_count, percentiles),
+    };
+    let block_count = block_count.value().min(MAX_FEE_HISTORY_BLOCKS);
+    let percentiles = percentiles.unwrap_or_default();
+    if percentiles.len() > MAX_PERCENTILES {
+        return Err(invalid_param_err("too many percentiles"));
+    }
+    if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+        return Err(invalid_param_err("percentiles must be in [0, 100]"));
+    }
+    if percentiles.windows(2).any(|w| w[0] > w[1]) {
+        return Err(invalid_param_err("percentiles must be in ascending order"));
+    }
+
+    let store = store.clone();
+    let history = tokio::task::spawn_blocking(move || -> Result<FeeHistory> {
+        let snap = store.get_snapshot();
+        let tip_number: u64 = snap.get_last_valid_tip_block()?.raw().number().unpack();
+        let oldest_block = (tip_number + 1).saturating_sub(block_count);
+        let blocks = (oldest_block..=tip_number)
+            .filter_map(|number| {
+                let stats = snap.get_block_stats(number)?;
+                let gas_price_percentiles = stats
+                    .gas_price_percentiles(&percentiles)
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                Some(BlockFeeStats {
+                    block_number: number.into(),
+                    used_cycles: stats.used_cycles.into(),
+                    tx_count: stats.tx_count.into(),
+                    withdrawal_count: stats.withdrawal_count.into(),
+                    deposit_count: stats.deposit_count.into(),
+                    gas_used: stats.gas_used.into(),
+                    total_fee: stats.total_fee.into(),
+                    gas_price_percentiles,
+                })
+            })
+            .collect();
+        Ok(FeeHistory {
+            oldest_block: oldest_block.into(),
+            blocks,
+        })
+    })
+    .await??;
+    Ok(history)
+}
+
+fn invalid_param_err(message: &'static str) -> RpcError {
+    RpcError::Provided {
+        code: INVALID_PARAM_ERR_CODE,
+        message,
+    }
+}
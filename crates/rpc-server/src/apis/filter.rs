@@ -0,0 +1,277 @@
+//! Polling filters of logs, new blocks and pending txs, like `eth_newFilter`,
+//! and `gw_get_logs`, like `eth_getLogs`.
+//!
+//! Filters are kept in memory and removed if not polled for
+//! `FILTER_TIMEOUT`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::Uint64,
+    godwoken::{FilterChanges, LogFilterParams, LogView},
+};
+use gw_mem_pool::subscription::Subscriptions;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{h256::*, prelude::*};
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use crate::logs::{query_logs, LogFilter};
+use crate::utils::to_jsonh256;
+
+const FILTER_NOT_FOUND_ERR_CODE: i64 = -32000;
+const INVALID_PARAM_ERR_CODE: i64 = -32602;
+const TOO_MANY_FILTERS_ERR_CODE: i64 = -32005;
+
+/// Filters not polled for this long are removed.
+const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const MAX_FILTERS: usize = 10_000;
+/// Max block hashes returned by a poll of a block filter.
+const MAX_BLOCK_HASHES: u64 = 1_000;
+
+enum FilterKind {
+    Logs {
+        filter: LogFilter,
+        from_block: u64,
+        to_block: Option<u64>,
+        /// Next block to poll.
+        next_block: u64,
+    },
+    Blocks {
+        next_block: u64,
+    },
+    PendingTxs(broadcast::Receiver<H256>),
+}
+
+struct Filter {
+    kind: FilterKind,
+    last_poll: Instant,
+}
+
+pub(crate) struct FilterManager {
+    store: Store,
+    subscriptions: Option<Subscriptions>,
+    filters: Mutex<HashMap<u64, Filter>>,
+    next_id: AtomicU64,
+}
+
+impl FilterManager {
+    pub fn new(store: Store, subscriptions: Option<Subscriptions>) -> Self {
+        Self {
+            store,
+            subscriptions,
+            filters: Default::default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn tip_number(&self) -> Result<u64> {
+        let tip = self.store.get_last_valid_tip_block()?;
+        Ok(tip.raw().number().unpack())
+    }
+
+    fn install(&self, kind: FilterKind) -> Result<Uint64, RpcError> {
+        let mut filters = self.filters.lock().expect("lock");
+        filters.retain(|_, f| f.last_poll.elapsed() < FILTER_TIMEOUT);
+        if filters.len() >= MAX_FILTERS {
+            return Err(RpcError::Provided {
+                code: TOO_MANY_FILTERS_ERR_CODE,
+                message: "too many filters",
+            });
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let filter = Filter {
+            kind,
+            last_poll: Instant::now(),
+        };
+        filters.insert(id, filter);
+        Ok(id.into())
+    }
+
+    fn changes(&self, id: u64) -> Result<FilterChanges, RpcError> {
+        let mut filters = self.filters.lock().expect("lock");
+        let filter = match filters.get_mut(&id) {
+            Some(filter) if filter.last_poll.elapsed() < FILTER_TIMEOUT => filter,
+            _ => {
+                filters.remove(&id);
+                return Err(filter_not_found_err());
+            }
+        };
+        filter.last_poll = Instant::now();
+
+        let changes = match filter.kind {
+            FilterKind::Logs {
+                ref filter,
+                to_block,
+                ref mut next_block,
+                ..
+            } => {
+                let tip = self.tip_number()?;
+                let to = to_block.map_or(tip, |to| to.min(tip));
+                let logs = query_logs(&self.store.get_snapshot(), filter, *next_block, to)?;
+                *next_block = (*next_block).max(to + 1);
+                FilterChanges::Logs(logs)
+            }
+            FilterKind::Blocks { ref mut next_block } => {
+                let tip = self.tip_number()?;
+                let to = tip.min(*next_block + MAX_BLOCK_HASHES - 1);
+                let mut hashes = Vec::new();
+                for number in *next_block..=to {
+                    match self.store.get_block_hash_by_number(number)? {
+                        Some(hash) => hashes.push(to_jsonh256(hash)),
+                        None => break,
+                    }
+                }
+                *next_block += hashes.len() as u64;
+                FilterChanges::Hashes(hashes)
+            }
+            FilterKind::PendingTxs(ref mut recv) => {
+                let mut hashes = Vec::new();
+                loop {
+                    match recv.try_recv() {
+                        Ok(tx_hash) => hashes.push(to_jsonh256(tx_hash)),
+                        Err(TryRecvError::Lagged(_)) => continue,
+                        Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+                    }
+                }
+                FilterChanges::Hashes(hashes)
+            }
+        };
+        Ok(changes)
+    }
+
+    fn logs(&self, id: u64) -> Result<Vec<LogView>, RpcError> {
+        let (filter, from_block, to_block) = {
+            let mut filters = self.filters.lock().expect("lock");
+            match filters.get_mut(&id) {
+                Some(Filter {
+                    kind:
+                        FilterKind::Logs {
+                            filter,
+                            from_block,
+                            to_block,
+                            ..
+                        },
+                    last_poll,
+                }) if last_poll.elapsed() < FILTER_TIMEOUT => {
+                    *last_poll = Instant::now();
+                    (filter.clone(), *from_block, *to_block)
+                }
+                _ => return Err(filter_not_found_err()),
+            }
+        };
+        let tip = self.tip_number()?;
+        let to = to_block.map_or(tip, |to| to.min(tip));
+        let logs = query_logs(&self.store.get_snapshot(), &filter, from_block, to)?;
+        Ok(logs)
+    }
+}
+
+fn filter_not_found_err() -> RpcError {
+    RpcError::Provided {
+        code: FILTER_NOT_FOUND_ERR_CODE,
+        message: "filter not found",
+    }
+}
+
+pub(crate) async fn new_filter(
+    Params((params,)): Params<(LogFilterParams,)>,
+    filters: Data<FilterManager>,
+) -> Result<Uint64, RpcError> {
+    let LogFilterParams {
+        from_block,
+        to_block,
+        address,
+        topics,
+    } = params;
+    let tip = filters.tip_number()?;
+    let from_block = from_block.map_or(tip, |n| n.value());
+    let to_block = to_block.map(|n| n.value());
+    if to_block.map_or(false, |to| to < from_block) {
+        return Err(RpcError::Provided {
+            code: INVALID_PARAM_ERR_CODE,
+            message: "to_block is less than from_block",
+        });
+    }
+    filters.install(FilterKind::Logs {
+        filter: LogFilter { address, topics },
+        from_block,
+        to_block,
+        next_block: from_block,
+    })
+}
+
+pub(crate) async fn get_logs(
+    Params((params,)): Params<(LogFilterParams,)>,
+    filters: Data<FilterManager>,
+) -> Result<Vec<LogView>, RpcError> {
+    let LogFilterParams {
+        from_block,
+        to_block,
+        address,
+        topics,
+    } = params;
+    let tip = filters.tip_number()?;
+    let from_block = from_block.map_or(tip, |n| n.value());
+    let to_block = to_block.map_or(tip, |n| n.value().min(tip));
+    let filter = LogFilter { address, topics };
+    let store = filters.store.clone();
+    let logs = tokio::task::spawn_blocking(move || {
+        query_logs(&store.get_snapshot(), &filter, from_block, to_block)
+    })
+    .await??;
+    Ok(logs)
+}
+
+pub(crate) async fn new_block_filter(filters: Data<FilterManager>) -> Result<Uint64, RpcError> {
+    let next_block = filters.tip_number()? + 1;
+    filters.install(FilterKind::Blocks { next_block })
+}
+
+pub(crate) async fn new_pending_transaction_filter(
+    filters: Data<FilterManager>,
+) -> Result<Uint64, RpcError> {
+    let recv = match filters.subscriptions {
+        Some(ref subscriptions) => subscriptions.subscribe_pending_txs(),
+        None => {
+            return Err(RpcError::Provided {
+                code: crate::registry::METHOD_NOT_AVAILABLE_ERR_CODE,
+                message: "mem-pool is disabled",
+            })
+        }
+    };
+    filters.install(FilterKind::PendingTxs(recv))
+}
+
+pub(crate) async fn uninstall_filter(
+    Params((id,)): Params<(Uint64,)>,
+    filters: Data<FilterManager>,
+) -> Result<bool, RpcError> {
+    let removed = filters
+        .filters
+        .lock()
+        .expect("lock")
+        .remove(&id.value())
+        .is_some();
+    Ok(removed)
+}
+
+pub(crate) async fn get_filter_changes(
+    Params((id,)): Params<(Uint64,)>,
+    filters: Data<FilterManager>,
+) -> Result<FilterChanges, RpcError> {
+    let filters = filters.clone();
+    tokio::task::spawn_blocking(move || filters.changes(id.value())).await?
+}
+
+pub(crate) async fn get_filter_logs(
+    Params((id,)): Params<(Uint64,)>,
+    filters: Data<FilterManager>,
+) -> Result<Vec<LogView>, RpcError> {
+    let filters = filters.clone();
+    tokio::task::spawn_blocking(move || filters.logs(id.value())).await?
+}
@@ -1 +1,12 @@
+pub mod account_txs;
+pub mod admin;
+pub mod block_cycles;
+pub mod block_state_diff;
 pub mod debug;
+pub mod fee_history;
+pub mod filter;
+pub mod proof;
+pub mod query;
+pub mod sudt_tokens;
+pub mod sudt_transfers;
+pub mod syncing;
@@ -0,0 +1,401 @@
+//! Merkle proofs of accounts and storage against the post state root of a
+//! block, of blocks against the block root of the global state, and of txs
+//! against the tx witness root of their blocks, so that light clients can
+//! verify state without trusting the node.
+//!
+//! The state tree only keeps the tip state, states of earlier blocks are
+//! rebuilt in memory by detaching later blocks.
+
+use anyhow::{bail, ensure, Context, Result};
+use ckb_fixed_hash::H256 as JsonH256;
+use gw_common::{
+    merkle_utils::{ckb_merkle_leaf_hash, CBMT},
+    state::{
+        build_account_field_key, build_account_key, build_script_hash_to_account_id_key, State,
+        GW_ACCOUNT_NONCE_TYPE, GW_ACCOUNT_SCRIPT_HASH_TYPE,
+    },
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64},
+    godwoken::{AccountProof, BlockProof, CKBMerkleProof, KVPair, StateProof, TransactionProof},
+};
+use gw_smt::smt::SMTH256;
+use gw_store::{
+    smt::smt_store::SMTBlockStore,
+    state::{
+        history::history_state::{HistoryStateStore, RWConfig},
+        overlay::mem_store::MemStore,
+        BlockStateDB,
+    },
+    traits::{chain_store::ChainStore, kv_store::KVStoreRead},
+    Store,
+};
+use gw_types::{
+    h256::*,
+    packed::{L2Block, RawL2Block, RollupConfig},
+    prelude::*,
+};
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::response_cache::{is_finalized, CacheKey, CachedResponse, ResponseCache};
+use crate::utils::{to_h256, to_jsonh256};
+
+const INVALID_PARAM_ERR_CODE: i64 = -32602;
+
+/// Max blocks behind the tip to prove, each block is detached to rebuild the
+/// state.
+const MAX_PROOF_BLOCKS_BEHIND: u64 = 1_000;
+const MAX_STORAGE_PROOF_KEYS: usize = 256;
+
+// script_hash, block_number
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetAccountProofParams {
+    Tip((JsonH256,)),
+    Number((JsonH256, Option<Uint64>)),
+}
+
+impl GetAccountProofParams {
+    pub(crate) fn into_parts(self) -> (H256, Option<u64>) {
+        match self {
+            GetAccountProofParams::Tip((script_hash,)) => (to_h256(script_hash), None),
+            GetAccountProofParams::Number((script_hash, number)) => {
+                (to_h256(script_hash), number.map(|n| n.value()))
+            }
+        }
+    }
+}
+
+pub(crate) async fn get_account_proof(
+    Params(params): Params<GetAccountProofParams>,
+    store: Data<Store>,
+    rollup_config: Data<RollupConfig>,
+    response_cache: Data<ResponseCache>,
+) -> Result<AccountProof, RpcError> {
+    let (script_hash, block_number) = params.into_parts();
+
+    let cache_key = finalized_block_hash(&store, &rollup_config, block_number)?.map(|block_hash| {
+        CacheKey::AccountProof {
+            block_hash,
+            script_hash,
+        }
+    });
+    if let Some(ref key) = cache_key {
+        if let Some(CachedResponse::AccountProof(proof)) = response_cache.get(&store, key) {
+            return Ok(proof);
+        }
+    }
+
+    let store = store.clone();
+    let proof = tokio::task::spawn_blocking(move || {
+        account_proof(
+            Arc::new(store.begin_transaction()),
+            script_hash,
+            block_number,
+        )
+    })
+    .await??;
+    if let Some(key) = cache_key {
+        cache_proof(&response_cache, key, &proof.state_proof, || {
+            CachedResponse::AccountProof(proof.clone())
+        });
+    }
+    Ok(proof)
+}
+
+// account_id, keys, block_number
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetStorageProofParams {
+    Tip((Uint32, Vec<JsonH256>)),
+    Number((Uint32, Vec<JsonH256>, Option<Uint64>)),
+}
+
+impl GetStorageProofParams {
+    /// Account id, state keys of the storage keys and block number.
+    pub(crate) fn into_parts(self) -> Result<(u32, Vec<H256>, Option<u64>), RpcError> {
+        let (account_id, keys, block_number) = match self {
+            GetStorageProofParams::Tip((account_id, keys)) => (account_id, keys, None),
+            GetStorageProofParams::Number((account_id, keys, number)) => {
+                (account_id, keys, number.map(|n| n.value()))
+            }
+        };
+        if keys.len() > MAX_STORAGE_PROOF_KEYS {
+            return Err(RpcError::Provided {
+                code: INVALID_PARAM_ERR_CODE,
+                message: "too many keys",
+            });
+        }
+        let account_id: u32 = account_id.value();
+        let keys = keys
+            .into_iter()
+            .map(|key| build_account_key(account_id, to_h256(key).as_slice()))
+            .collect();
+        Ok((account_id, keys, block_number))
+    }
+}
+
+pub(crate) async fn get_storage_proof(
+    Params(params): Params<GetStorageProofParams>,
+    store: Data<Store>,
+    rollup_config: Data<RollupConfig>,
+    response_cache: Data<ResponseCache>,
+) -> Result<StateProof, RpcError> {
+    let (account_id, keys, block_number) = params.into_parts()?;
+
+    let cache_key = finalized_block_hash(&store, &rollup_config, block_number)?.map(|block_hash| {
+        CacheKey::StorageProof {
+            block_hash,
+            account_id,
+            keys: keys.clone(),
+        }
+    });
+    if let Some(ref key) = cache_key {
+        if let Some(CachedResponse::StorageProof(proof)) = response_cache.get(&store, key) {
+            return Ok(proof);
+        }
+    }
+
+    let store = store.clone();
+    let proof = tokio::task::spawn_blocking(move || {
+        storage_proof(Arc::new(store.begin_transaction()), keys, block_number)
+    })
+    .await??;
+    if let Some(key) = cache_key {
+        cache_proof(&response_cache, key, &proof, || {
+            CachedResponse::StorageProof(proof.clone())
+        });
+    }
+    Ok(proof)
+}
+
+/// Hash of block `block_number` if it's finalized, so that its proofs can be
+/// cached. Proofs of the tip are never cached.
+fn finalized_block_hash(
+    store: &Store,
+    rollup_config: &RollupConfig,
+    block_number: Option<u64>,
+) -> Result<Option<H256>> {
+    let block_number = match block_number {
+        Some(block_number) => block_number,
+        None => return Ok(None),
+    };
+    let snap = store.get_snapshot();
+    if !is_finalized(&snap, rollup_config, block_number) {
+        return Ok(None);
+    }
+    snap.get_block_hash_by_number(block_number)
+}
+
+/// Cache a proof if it is of the block of `key`, which may have been
+/// reverted before the proof was built.
+fn cache_proof(
+    response_cache: &ResponseCache,
+    key: CacheKey,
+    state_proof: &StateProof,
+    response: impl FnOnce() -> CachedResponse,
+) {
+    let block_hash = match key {
+        CacheKey::AccountProof { block_hash, .. } | CacheKey::StorageProof { block_hash, .. } => {
+            block_hash
+        }
+        _ => return,
+    };
+    if to_h256(state_proof.block_hash.clone()) == block_hash {
+        let block_number = state_proof.block_number.value();
+        response_cache.insert(key, block_number, block_hash, response());
+    }
+}
+
+pub(crate) async fn get_block_proof(
+    Params((block_number,)): Params<(Uint64,)>,
+    store: Data<Store>,
+) -> Result<BlockProof, RpcError> {
+    let block_number = block_number.value();
+    let store = store.clone();
+    let proof =
+        tokio::task::spawn_blocking(move || block_proof(&store.begin_transaction(), block_number))
+            .await??;
+    Ok(proof)
+}
+
+/// Account id, nonce and their state proof of `script_hash`, at the post
+/// state of `block_number`.
+pub(crate) fn account_proof<S>(
+    db: Arc<S>,
+    script_hash: H256,
+    block_number: Option<u64>,
+) -> Result<AccountProof>
+where
+    S: ChainStore + KVStoreRead + HistoryStateStore,
+{
+    let (state, block) = state_of_block(db, block_number)?;
+    let account_id = state.get_account_id_by_script_hash(&script_hash)?;
+    let mut keys = vec![build_script_hash_to_account_id_key(script_hash.as_slice())];
+    let nonce = match account_id {
+        Some(id) => {
+            keys.push(build_account_field_key(id, GW_ACCOUNT_SCRIPT_HASH_TYPE));
+            keys.push(build_account_field_key(id, GW_ACCOUNT_NONCE_TYPE));
+            Some(state.get_nonce(id)?)
+        }
+        None => None,
+    };
+    Ok(AccountProof {
+        account_id: account_id.map(Into::into),
+        nonce: nonce.map(Into::into),
+        state_proof: state_proof(&state, &block, keys)?,
+    })
+}
+
+/// State proof of `keys` at the post state of `block_number`.
+pub(crate) fn storage_proof<S>(
+    db: Arc<S>,
+    keys: Vec<H256>,
+    block_number: Option<u64>,
+) -> Result<StateProof>
+where
+    S: ChainStore + KVStoreRead + HistoryStateStore,
+{
+    let (state, block) = state_of_block(db, block_number)?;
+    state_proof(&state, &block, keys)
+}
+
+/// Proof of a committed tx, `None` if the tx isn't committed.
+pub(crate) fn transaction_proof(
+    db: &(impl ChainStore + KVStoreRead),
+    tx_hash: &H256,
+) -> Result<Option<TransactionProof>> {
+    let tx_key = match db.get_transaction_info(tx_hash)? {
+        Some(tx_info) => tx_info.key(),
+        None => return Ok(None),
+    };
+    let block_hash = tx_key.block_hash();
+    let tx_index = tx_key.index();
+    let block = db.get_block(&block_hash)?.context("block")?;
+
+    let leaves: Vec<H256> = { block.transactions().into_iter().enumerate() }
+        .map(|(idx, tx)| ckb_merkle_leaf_hash(idx as u32, &tx.witness_hash()))
+        .collect();
+    let tx_proof = CBMT::build_merkle_proof(&leaves, &[tx_index]).context("build tx proof")?;
+
+    let block_number: u64 = block.raw().number().unpack();
+    let block_proof = block_proof(db, block_number)?;
+    ensure!(
+        block_proof.block_hash == to_jsonh256(block_hash),
+        "block {} changed while building the proof, please retry",
+        block_number
+    );
+    let l1_transaction_hash = db.get_block_submit_tx_hash(block_number);
+
+    Ok(Some(TransactionProof {
+        block_number: block_number.into(),
+        block_hash: to_jsonh256(block_hash),
+        tx_index: tx_index.into(),
+        tx_witness_root: to_jsonh256(block.raw().submit_transactions().tx_witness_root().unpack()),
+        tx_proof: CKBMerkleProof {
+            indices: tx_proof.indices().iter().map(|&i| i.into()).collect(),
+            lemmas: tx_proof.lemmas().iter().map(|&h| to_jsonh256(h)).collect(),
+        },
+        block_proof,
+        l1_transaction_hash: l1_transaction_hash.map(to_jsonh256),
+    }))
+}
+
+/// Block SMT proof of `block_number` against the block root of the tip.
+pub(crate) fn block_proof(
+    db: &(impl ChainStore + KVStoreRead),
+    block_number: u64,
+) -> Result<BlockProof> {
+    let tip_block_hash = db.get_last_valid_tip_block_hash()?;
+    let tip_global_state = db
+        .get_block_post_global_state(&tip_block_hash)?
+        .context("tip global state")?;
+    let block_hash = match db.get_block_hash_by_number(block_number)? {
+        Some(block_hash) => block_hash,
+        None => bail!("block {} not found", block_number),
+    };
+
+    let tip_block_root: H256 = tip_global_state.block().merkle_root().unpack();
+
+    let key: SMTH256 = RawL2Block::compute_smt_key(block_number).into();
+    let smt = SMTBlockStore::new(db).to_smt()?;
+    let block_root: H256 = (*smt.root()).into();
+    ensure!(
+        block_root == tip_block_root,
+        "block root changed while building the proof, please retry"
+    );
+    let proof = smt.merkle_proof(vec![key])?.compile(vec![key])?;
+    Ok(BlockProof {
+        block_number: block_number.into(),
+        block_hash: to_jsonh256(block_hash),
+        tip_block_hash: to_jsonh256(tip_block_hash),
+        block_root: to_jsonh256(block_root),
+        proof: JsonBytes::from_vec(proof.0),
+    })
+}
+
+type ProofState<S> = BlockStateDB<MemStore<S>>;
+
+/// State at the post state of `block_number`, default to the tip.
+fn state_of_block<S>(db: Arc<S>, block_number: Option<u64>) -> Result<(ProofState<S>, L2Block)>
+where
+    S: ChainStore + KVStoreRead + HistoryStateStore,
+{
+    let tip_number: u64 = db.get_last_valid_tip_block()?.raw().number().unpack();
+    let block_number = block_number.unwrap_or(tip_number);
+    if block_number > tip_number {
+        bail!("block {} is beyond the tip {}", block_number, tip_number);
+    }
+    if tip_number - block_number > MAX_PROOF_BLOCKS_BEHIND {
+        bail!(
+            "block {} is more than {} blocks behind the tip",
+            block_number,
+            MAX_PROOF_BLOCKS_BEHIND
+        );
+    }
+    let block_hash = db
+        .get_block_hash_by_number(block_number)?
+        .context("block hash")?;
+    let block = db.get_block(&block_hash)?.context("block")?;
+
+    let mut state = BlockStateDB::from_store(MemStore::new(db), RWConfig::detach_block())?;
+    for number in (block_number + 1..=tip_number).rev() {
+        state.detach_block_state(number)?;
+    }
+    let post_root: H256 = block.raw().post_account().merkle_root().unpack();
+    ensure!(
+        state.calculate_root()? == post_root,
+        "state changed while building the proof, please retry"
+    );
+    Ok((state, block))
+}
+
+fn state_proof<S>(state: &ProofState<S>, block: &L2Block, keys: Vec<H256>) -> Result<StateProof>
+where
+    S: ChainStore + KVStoreRead + HistoryStateStore,
+{
+    let kv_state = keys
+        .iter()
+        .map(|key| {
+            let v = state.get_raw(key)?;
+            Ok(KVPair {
+                k: to_jsonh256(*key),
+                v: to_jsonh256(v),
+            })
+        })
+        .collect::<Result<_>>()?;
+    let proof = state.merkle_proof(keys)?;
+    let block_number: u64 = block.raw().number().unpack();
+    let post_account = block.raw().post_account();
+    let account_count: u32 = post_account.count().unpack();
+    Ok(StateProof {
+        block_number: block_number.into(),
+        block_hash: to_jsonh256(block.hash()),
+        state_root: to_jsonh256(post_account.merkle_root().unpack()),
+        account_count: account_count.into(),
+        kv_state,
+        proof: JsonBytes::from_vec(proof.0),
+    })
+}
@@ -0,0 +1,376 @@
+//! Query handlers shared by `Registry` and `ReadonlyRegistry`.
+//!
+//! The registries differ in where they read from, the full node reads the
+//! mem pool state and the db, the readonly node reads a `StoreReadonly`. So
+//! these take the state or chain store to read, and the registries only pick
+//! it and add their own extras, e.g. pending txs or response caching.
+
+use std::convert::{TryFrom, TryInto};
+
+use anyhow::{anyhow, Result};
+use gw_common::{registry_address::RegistryAddress, state::State};
+use gw_jsonrpc_types::{
+    blockchain::Script,
+    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    godwoken::{
+        L2BlockStatus, L2BlockView, L2BlockWithStatus, L2FullBlockView, L2TransactionStatus,
+        L2TransactionWithStatus, RegistryAddress as JsonRegistryAddress, TxReceipt,
+    },
+};
+use gw_store::{
+    schema::COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    smt::smt_store::SMTBlockStore,
+    traits::{chain_store::ChainStore, kv_store::KVStoreRead},
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    h256::*,
+    packed::{self, RollupConfig},
+    prelude::*,
+    U256,
+};
+use gw_utils::export_block::{get_block_with_requests, BlockWithRequests};
+use jsonrpc_v2::Error as RpcError;
+
+use crate::apis::proof::transaction_proof;
+use crate::registry::{invalid_param_err, AccountID, GwUint64, JsonH256};
+use crate::utils::{to_h256, to_jsonh256};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetTxParams {
+    Default((JsonH256,)),
+    WithVerbose((JsonH256, u8)),
+}
+
+impl GetTxParams {
+    pub(crate) fn into_parts(self) -> Result<(H256, GetTxVerbose), RpcError> {
+        match self {
+            GetTxParams::Default((tx_hash,)) => Ok((to_h256(tx_hash), GetTxVerbose::TxWithStatus)),
+            GetTxParams::WithVerbose((tx_hash, verbose)) => {
+                let verbose = verbose
+                    .try_into()
+                    .map_err(|_err| invalid_param_err("invalid verbose param"))?;
+                Ok((to_h256(tx_hash), verbose))
+            }
+        }
+    }
+}
+
+pub(crate) enum GetTxVerbose {
+    TxWithStatus = 0,
+    OnlyStatus = 1,
+    /// Tx with status and the inclusion proof of a committed tx
+    TxWithProof = 2,
+}
+
+impl TryFrom<u8> for GetTxVerbose {
+    type Error = u8;
+    fn try_from(n: u8) -> Result<Self, u8> {
+        let verbose = match n {
+            0 => Self::TxWithStatus,
+            1 => Self::OnlyStatus,
+            2 => Self::TxWithProof,
+            _ => {
+                return Err(n);
+            }
+        };
+        Ok(verbose)
+    }
+}
+
+// block_number, full
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetBlockByNumberParams {
+    Number((GwUint64,)),
+    Full((GwUint64, bool)),
+}
+
+impl GetBlockByNumberParams {
+    pub(crate) fn into_parts(self) -> (u64, bool) {
+        match self {
+            GetBlockByNumberParams::Number((number,)) => (number.value(), false),
+            GetBlockByNumberParams::Full((number, full)) => (number.value(), full),
+        }
+    }
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum BlockByNumberView {
+    Block(L2BlockView),
+    Full(Box<L2FullBlockView>),
+}
+
+// registry address, sudt_id, block_number
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetBalanceParams {
+    Tip((JsonBytes, AccountID)),
+    Number((JsonBytes, AccountID, Option<GwUint64>)),
+}
+
+impl GetBalanceParams {
+    pub(crate) fn into_parts(self) -> (JsonBytes, AccountID, Option<u64>) {
+        match self {
+            GetBalanceParams::Tip((address, sudt_id)) => (address, sudt_id, None),
+            GetBalanceParams::Number((address, sudt_id, number)) => {
+                (address, sudt_id, number.map(Into::into))
+            }
+        }
+    }
+}
+
+// account_id, key, block_number
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetStorageAtParams {
+    Tip((AccountID, JsonH256)),
+    Number((AccountID, JsonH256, Option<GwUint64>)),
+}
+
+impl GetStorageAtParams {
+    pub(crate) fn into_parts(self) -> (AccountID, JsonH256, Option<u64>) {
+        match self {
+            GetStorageAtParams::Tip((account_id, key)) => (account_id, key, None),
+            GetStorageAtParams::Number((account_id, key, number)) => {
+                (account_id, key, number.map(Into::into))
+            }
+        }
+    }
+}
+
+// account_id, block_number
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetNonceParams {
+    Tip((AccountID,)),
+    Number((AccountID, Option<GwUint64>)),
+}
+
+impl GetNonceParams {
+    pub(crate) fn into_parts(self) -> (AccountID, Option<u64>) {
+        match self {
+            GetNonceParams::Tip((account_id,)) => (account_id, None),
+            GetNonceParams::Number((account_id, number)) => (account_id, number.map(Into::into)),
+        }
+    }
+}
+
+// data_hash, block_number
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum GetDataParams {
+    Tip((JsonH256,)),
+    Number((JsonH256, Option<GwUint64>)),
+}
+
+impl GetDataParams {
+    /// Data are never deleted, so the block number is ignored.
+    pub(crate) fn data_hash(self) -> JsonH256 {
+        match self {
+            GetDataParams::Tip((data_hash,)) => data_hash,
+            GetDataParams::Number((data_hash, _block_number)) => data_hash,
+        }
+    }
+}
+
+pub(crate) fn parse_registry_address(
+    serialized_address: &JsonBytes,
+) -> Result<RegistryAddress, RpcError> {
+    RegistryAddress::from_slice(serialized_address.as_bytes())
+        .ok_or_else(|| invalid_param_err("Invalid registry address"))
+}
+
+pub(crate) fn balance(
+    state: &impl State,
+    serialized_address: &JsonBytes,
+    sudt_id: AccountID,
+) -> Result<U256, RpcError> {
+    let address = parse_registry_address(serialized_address)?;
+    Ok(state.get_sudt_balance(sudt_id.into(), &address)?)
+}
+
+pub(crate) fn storage_at(
+    state: &impl State,
+    account_id: AccountID,
+    key: JsonH256,
+) -> Result<JsonH256, RpcError> {
+    let key: H256 = to_h256(key);
+    let value = state.get_value(account_id.into(), key.as_slice())?;
+    Ok(to_jsonh256(value))
+}
+
+pub(crate) fn account_id_by_script_hash(
+    state: &impl State,
+    script_hash: JsonH256,
+) -> Result<Option<AccountID>, RpcError> {
+    let account_id_opt = state
+        .get_account_id_by_script_hash(&to_h256(script_hash))?
+        .map(Into::into);
+    Ok(account_id_opt)
+}
+
+pub(crate) fn nonce(state: &impl State, account_id: AccountID) -> Result<Uint32, RpcError> {
+    Ok(state.get_nonce(account_id.into())?.into())
+}
+
+pub(crate) fn script(state: &impl CodeStore, script_hash: JsonH256) -> Option<Script> {
+    state.get_script(&to_h256(script_hash)).map(Into::into)
+}
+
+pub(crate) fn script_hash(state: &impl State, account_id: AccountID) -> Result<JsonH256, RpcError> {
+    let script_hash = state.get_script_hash(account_id.into())?;
+    Ok(to_jsonh256(script_hash))
+}
+
+pub(crate) fn script_hash_by_registry_address(
+    state: &impl State,
+    serialized_address: &JsonBytes,
+) -> Result<Option<JsonH256>, RpcError> {
+    let addr = parse_registry_address(serialized_address)?;
+    let script_hash_opt = state.get_script_hash_by_registry_address(&addr)?;
+    Ok(script_hash_opt.map(to_jsonh256))
+}
+
+pub(crate) fn registry_address_by_script_hash(
+    state: &impl State,
+    script_hash: JsonH256,
+    registry_id: Uint32,
+) -> Result<Option<JsonRegistryAddress>, RpcError> {
+    let addr =
+        state.get_registry_address_by_script_hash(registry_id.value(), &to_h256(script_hash))?;
+    Ok(addr.map(Into::into))
+}
+
+pub(crate) fn data(state: &impl CodeStore, data_hash: JsonH256) -> Option<JsonBytes> {
+    state
+        .get_data(&to_h256(data_hash))
+        .map(JsonBytes::from_bytes)
+}
+
+/// Block with its status, `None` if it is neither on the main chain nor
+/// reverted.
+pub(crate) fn block_with_status(
+    db: &(impl ChainStore + KVStoreRead),
+    rollup_config: &RollupConfig,
+    block_hash: H256,
+) -> Result<Option<L2BlockWithStatus>> {
+    let block = match db.get_block(&block_hash)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+
+    // check block status, a reverted block has a non-zero leaf in the
+    // reverted block smt
+    let mut status = L2BlockStatus::Unfinalized;
+    if db
+        .get(COLUMN_REVERTED_BLOCK_SMT_LEAF, block_hash.as_slice())
+        .map_or(false, |leaf| leaf.iter().any(|b| *b != 0))
+    {
+        status = L2BlockStatus::Reverted;
+    } else {
+        // return None if block is not on the main chain
+        let block_smt = SMTBlockStore::new(db).to_smt()?;
+        if H256::from(block_smt.get(&block.smt_key().into())?) != block_hash {
+            return Ok(None);
+        }
+
+        // block is on main chain
+        let last_confirmed_block_number = db
+            .get_last_confirmed_block_number_hash()
+            .map(|nh| nh.number().unpack())
+            .unwrap_or(0);
+        let block_number = block.raw().number().unpack();
+        if last_confirmed_block_number >= block_number + rollup_config.finality_blocks().unpack() {
+            status = L2BlockStatus::Finalized;
+        }
+    }
+
+    Ok(Some(L2BlockWithStatus {
+        block: block.into(),
+        status,
+    }))
+}
+
+/// Block `block_number` of the main chain, with its receipts, requests and
+/// post global state if `full`.
+pub(crate) fn block_by_number(
+    db: &impl ChainStore,
+    block_number: u64,
+    full: bool,
+) -> Result<Option<BlockByNumberView>> {
+    let block_hash = match db.get_block_hash_by_number(block_number)? {
+        Some(hash) => hash,
+        None => return Ok(None),
+    };
+    if !full {
+        let block_opt = db
+            .get_block(&block_hash)?
+            .map(|block| BlockByNumberView::Block(block.into()));
+        return Ok(block_opt);
+    }
+
+    let BlockWithRequests {
+        block,
+        post_global_state,
+        deposit_info_vec,
+        withdrawals,
+        ..
+    } = get_block_with_requests(db, block_number)?;
+    let tx_receipts: Vec<TxReceipt> = (0..block.transactions().len() as u32)
+        .map(|index| {
+            let key = packed::TransactionKey::build_transaction_key(block_hash.pack(), index);
+            let receipt = db.get_transaction_receipt_by_key(&key)?.ok_or_else(|| {
+                anyhow!("tx receipt {} of block {} not found", index, block_number)
+            })?;
+            Ok(receipt.into())
+        })
+        .collect::<Result<_>>()?;
+    let full_block = L2FullBlockView {
+        block: block.into(),
+        tx_receipts,
+        withdrawals: withdrawals.into_iter().map(Into::into).collect(),
+        deposit_requests: deposit_info_vec
+            .into_iter()
+            .map(|info| info.request().into())
+            .collect(),
+        post_global_state: post_global_state.into(),
+    };
+    Ok(Some(BlockByNumberView::Full(Box::new(full_block))))
+}
+
+/// Committed tx, `None` if the tx isn't committed.
+pub(crate) fn committed_transaction(
+    db: &(impl ChainStore + KVStoreRead),
+    tx_hash: &H256,
+    verbose: GetTxVerbose,
+) -> Result<Option<L2TransactionWithStatus>> {
+    let tx = match db.get_transaction_info(tx_hash)? {
+        Some(tx_info) => match db.get_transaction_by_key(&tx_info.key())? {
+            Some(tx) => tx,
+            None => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+    let status = L2TransactionStatus::Committed;
+    let tx_with_status = match verbose {
+        GetTxVerbose::OnlyStatus => L2TransactionWithStatus {
+            transaction: None,
+            status,
+            proof: None,
+        },
+        GetTxVerbose::TxWithStatus => L2TransactionWithStatus {
+            transaction: Some(tx.into()),
+            status,
+            proof: None,
+        },
+        GetTxVerbose::TxWithProof => L2TransactionWithStatus {
+            transaction: Some(tx.into()),
+            status,
+            proof: transaction_proof(db, tx_hash)?,
+        },
+    };
+    Ok(Some(tx_with_status))
+}
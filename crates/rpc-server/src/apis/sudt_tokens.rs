@@ -0,0 +1,208 @@
+//! sUDT metadata, i.e. L2 sUDT account ids of L1 sUDTs registered by
+//! deposits, with symbols and decimals curated by the operator, and ERC20
+//! proxy contracts of sUDTs in the sUDT proxy address list.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use anyhow::Result;
+use ckb_fixed_hash::H160 as JsonH160;
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    state::State,
+};
+use gw_config::SUDTTokenConfig;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{SUDTBalance, SUDTToken, TxReceipt},
+};
+use gw_store::{
+    mem_pool_state::MemPoolState, snapshot::StoreSnapshot, traits::chain_store::ChainStore, Store,
+};
+use gw_types::{h256::*, prelude::*, registry_address::RegistryAddress};
+use gw_utils::script_log::{GW_LOG_SUDT_PAY_FEE, GW_LOG_SUDT_TRANSFER};
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+
+use crate::utils::to_jsonh256;
+
+const INVALID_PARAM_ERR_CODE: i64 = -32602;
+
+/// Storage slot of `_sudtId` in `SudtERC20Proxy_UserDefinedDecimals`.
+const SUDT_PROXY_SUDT_ID_SLOT: u8 = 2;
+
+pub(crate) struct SUDTTokens {
+    configs: HashMap<H256, SUDTTokenConfig>,
+}
+
+impl SUDTTokens {
+    pub fn new(configs: &[SUDTTokenConfig]) -> Self {
+        let configs = { configs.iter() }
+            .map(|config| (config.l1_script_hash.0, config.clone()))
+            .collect();
+        SUDTTokens { configs }
+    }
+
+    fn token(&self, l1_script_hash: H256, account_id: Option<u32>) -> SUDTToken {
+        let config = self.configs.get(&l1_script_hash);
+        SUDTToken {
+            l1_script_hash: to_jsonh256(l1_script_hash),
+            account_id: account_id.map(Into::into),
+            symbol: config.map(|c| c.symbol.clone()),
+            decimals: config.map(|c| (c.decimals as u32).into()),
+        }
+    }
+
+    /// CKB and registered sUDTs by account id, followed by configured sUDTs
+    /// that are not deposited yet.
+    pub fn list(&self, snap: &StoreSnapshot) -> Vec<SUDTToken> {
+        let mut registered: BTreeMap<u32, H256> = { snap.iter_sudt_registry() }
+            .map(|(l1_script_hash, account_id)| (account_id, l1_script_hash))
+            .collect();
+        registered.insert(CKB_SUDT_ACCOUNT_ID, H256::zero());
+        let mut pending: Vec<H256> = { self.configs.keys() }
+            .filter(|h| !registered.values().any(|r| r == *h))
+            .cloned()
+            .collect();
+        pending.sort_unstable();
+
+        let registered = { registered.into_iter() }
+            .map(|(account_id, l1_script_hash)| self.token(l1_script_hash, Some(account_id)));
+        let pending = { pending.into_iter() }.map(|h| self.token(h, None));
+        registered.chain(pending).collect()
+    }
+
+    /// Tokens of sUDT accounts in `account_ids`, in the same order.
+    pub fn of_accounts(
+        &self,
+        snap: &StoreSnapshot,
+        account_ids: impl IntoIterator<Item = u32>,
+    ) -> Vec<SUDTToken> {
+        let mut seen = HashSet::new();
+        let account_ids: Vec<u32> = { account_ids.into_iter() }
+            .filter(|id| seen.insert(*id))
+            .collect();
+        if account_ids.is_empty() {
+            return Vec::new();
+        }
+        let mut registered: HashMap<u32, H256> = { snap.iter_sudt_registry() }
+            .map(|(l1_script_hash, account_id)| (account_id, l1_script_hash))
+            .collect();
+        registered.insert(CKB_SUDT_ACCOUNT_ID, H256::zero());
+        { account_ids.into_iter() }
+            .filter_map(|id| Some(self.token(*registered.get(&id)?, Some(id))))
+            .collect()
+    }
+
+    /// Fill `sudt_tokens` of a receipt by its sUDT logs.
+    pub fn fill_receipt(&self, snap: &StoreSnapshot, receipt: &mut TxReceipt) {
+        let account_ids = { receipt.logs.iter() }
+            .filter(|log| {
+                let flag = log.service_flag.value() as u8;
+                flag == GW_LOG_SUDT_TRANSFER || flag == GW_LOG_SUDT_PAY_FEE
+            })
+            .map(|log| log.account_id.value());
+        receipt.sudt_tokens = self.of_accounts(snap, account_ids);
+    }
+}
+
+pub(crate) async fn list_sudt_tokens(
+    store: Data<Store>,
+    sudt_tokens: Data<SUDTTokens>,
+) -> Result<Vec<SUDTToken>> {
+    Ok(sudt_tokens.list(&store.get_snapshot()))
+}
+
+/// Non-zero balances of known sUDTs of an address in the mem pool state.
+pub(crate) async fn get_sudt_balances(
+    Params((serialized_address,)): Params<(JsonBytes,)>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+    sudt_tokens: Data<SUDTTokens>,
+) -> Result<Vec<SUDTBalance>, RpcError> {
+    let address = RegistryAddress::from_slice(serialized_address.as_bytes()).ok_or_else(|| {
+        RpcError::Provided {
+            code: INVALID_PARAM_ERR_CODE,
+            message: "Invalid registry address",
+        }
+    })?;
+    let tokens = sudt_tokens.list(&store.get_snapshot());
+    let state = mem_pool_state.load_state_db();
+    let mut balances = Vec::new();
+    for token in tokens {
+        let account_id = match token.account_id {
+            Some(id) => id.value(),
+            None => continue,
+        };
+        let balance = state.get_sudt_balance(account_id, &address)?;
+        if !balance.is_zero() {
+            balances.push(SUDTBalance { token, balance });
+        }
+    }
+    Ok(balances)
+}
+
+/// Addresses of the sUDT proxy address list at the next block.
+fn sudt_proxy_addresses(generator: &Generator, store: &Store) -> Result<Vec<[u8; 20]>> {
+    let tip_number: u64 = store.get_last_valid_tip_block()?.raw().number().unpack();
+    let mut addresses: Vec<[u8; 20]> = { generator.backend_manage() }
+        .get_block_consensus_at_height(tip_number + 1)
+        .map(|(_height, consensus)| consensus.sudt_proxy.address_list.iter().cloned().collect())
+        .unwrap_or_default();
+    addresses.sort_unstable();
+    Ok(addresses)
+}
+
+/// sUDT id of a proxy contract, read from its storage.
+fn proxy_sudt_id(state: &impl State, address: [u8; 20]) -> Result<Option<u32>> {
+    let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, address.to_vec());
+    let script_hash = match state.get_script_hash_by_registry_address(&address)? {
+        Some(script_hash) => script_hash,
+        None => return Ok(None),
+    };
+    let account_id = match state.get_account_id_by_script_hash(&script_hash)? {
+        Some(account_id) => account_id,
+        None => return Ok(None),
+    };
+    // Storage keys and values are big endian uint256.
+    let mut key = [0u8; 32];
+    key[31] = SUDT_PROXY_SUDT_ID_SLOT;
+    let value = state.get_value(account_id, &key)?;
+    if value[..28].iter().any(|b| *b != 0) {
+        return Ok(None);
+    }
+    let sudt_id = u32::from_be_bytes(value[28..].try_into().expect("4 bytes"));
+    Ok(Some(sudt_id))
+}
+
+/// Listed proxy contracts of a sUDT in the mem pool state.
+pub(crate) async fn get_sudt_proxy_addresses(
+    Params((sudt_id,)): Params<(Uint32,)>,
+    generator: Data<Generator>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Vec<JsonH160>> {
+    let state = mem_pool_state.load_state_db();
+    let mut proxies = Vec::new();
+    for address in sudt_proxy_addresses(&generator, &store)? {
+        if proxy_sudt_id(&state, address)? == Some(sudt_id.value()) {
+            proxies.push(address.into());
+        }
+    }
+    Ok(proxies)
+}
+
+/// sUDT id of a listed proxy contract in the mem pool state.
+pub(crate) async fn get_sudt_id_by_proxy_address(
+    Params((address,)): Params<(JsonH160,)>,
+    generator: Data<Generator>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Option<Uint32>> {
+    let address: [u8; 20] = address.into();
+    if !sudt_proxy_addresses(&generator, &store)?.contains(&address) {
+        return Ok(None);
+    }
+    let state = mem_pool_state.load_state_db();
+    Ok(proxy_sudt_id(&state, address)?.map(Into::into))
+}
@@ -0,0 +1,73 @@
+//! `gw_list_sudt_transfers`, sUDT transfers sent by or to a registry address,
+//! by the sUDT transfer index.
+//!
+//! Pages are the same as `gw_list_account_transactions`.
+
+use gw_common::registry_address::RegistryAddress;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    godwoken::{Order, SudtTransfer, SudtTransfers},
+};
+use gw_store::Store;
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+use serde::Deserialize;
+
+use crate::apis::account_txs::{decode_cursor, encode_cursor};
+use crate::registry::invalid_param_err;
+use crate::utils::to_jsonh256;
+
+const MAX_LIMIT: u32 = 1000;
+
+// registry address, order, limit, after
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ListSudtTransfersParams {
+    First((JsonBytes, Order, Uint32)),
+    After((JsonBytes, Order, Uint32, Option<JsonBytes>)),
+}
+
+pub(crate) async fn list_sudt_transfers(
+    Params(params): Params<ListSudtTransfersParams>,
+    store: Data<Store>,
+) -> Result<SudtTransfers, RpcError> {
+    let (address, order, limit, after) = match params {
+        ListSudtTransfersParams::First((address, order, limit)) => (address, order, limit, None),
+        ListSudtTransfersParams::After(p) => p,
+    };
+    let limit = limit.value();
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(invalid_param_err("limit must be in [1, 1000]"));
+    }
+    let after = match after {
+        Some(cursor) if !cursor.is_empty() => Some(decode_cursor(cursor.as_bytes())?),
+        _ => None,
+    };
+    let address = RegistryAddress::from_slice(address.as_bytes())
+        .ok_or_else(|| invalid_param_err("Invalid registry address"))?;
+
+    let snap = store.get_snapshot();
+    let transfers: Vec<_> = snap
+        .iter_sudt_transfers(&address, after, order == Order::Desc)
+        .take(limit as usize)
+        .collect();
+    let last_cursor = match transfers.last() {
+        Some(t) => encode_cursor(t.block_number, t.transfer_index),
+        None => JsonBytes::default(),
+    };
+    let transfers = transfers
+        .into_iter()
+        .map(|t| SudtTransfer {
+            tx_hash: to_jsonh256(t.tx_hash),
+            block_number: t.block_number.into(),
+            tx_index: t.tx_index.into(),
+            sudt_id: t.sudt_id.into(),
+            from_address: JsonBytes::from_vec(t.from_address.to_bytes()),
+            to_address: JsonBytes::from_vec(t.to_address.to_bytes()),
+            amount: t.amount,
+        })
+        .collect();
+    Ok(SudtTransfers {
+        transfers,
+        last_cursor,
+    })
+}
@@ -0,0 +1,108 @@
+//! `gw_syncing`, progress of syncing the local chain to the rollup cell on
+//! L1, like `eth_syncing`.
+//!
+//! The catch-up time is estimated by the sync speed since the node fell
+//! behind, i.e. since `gw_syncing` first found it behind.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use gw_jsonrpc_types::godwoken::{SyncProgress, SyncingStatus};
+use gw_rpc_client::rpc_client::RPCClient;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{offchain::global_state_from_slice, prelude::*};
+use jsonrpc_v2::Data;
+
+#[derive(Default)]
+pub struct SyncTracker {
+    /// When and at which local tip the node fell behind.
+    start: Mutex<Option<(Instant, u64)>>,
+}
+
+struct Progress {
+    starting_block: u64,
+    estimated_catch_up_secs: Option<u64>,
+}
+
+impl SyncTracker {
+    /// `None` if the local tip `current` has caught up with `highest`.
+    fn update(&self, now: Instant, current: u64, highest: u64) -> Option<Progress> {
+        let mut start = self.start.lock().expect("lock");
+        if current >= highest {
+            *start = None;
+            return None;
+        }
+        let (since, starting_block) = *start.get_or_insert((now, current));
+        let synced = current.saturating_sub(starting_block);
+        let elapsed = now.saturating_duration_since(since);
+        let estimated_catch_up_secs = if synced > 0 && elapsed > Duration::ZERO {
+            let secs = elapsed.as_secs_f64() * (highest - current) as f64 / synced as f64;
+            Some(secs.ceil() as u64)
+        } else {
+            None
+        };
+        Some(Progress {
+            starting_block,
+            estimated_catch_up_secs,
+        })
+    }
+}
+
+pub(crate) async fn syncing(
+    store: Data<Store>,
+    rpc_client: Data<RPCClient>,
+    tracker: Data<SyncTracker>,
+) -> Result<SyncingStatus> {
+    let current_block: u64 = store.get_tip_block()?.raw().number().unpack();
+    let rollup_cell = { rpc_client.query_rollup_cell().await? }.context("rollup cell not found")?;
+    let global_state = global_state_from_slice(&rollup_cell.data)?;
+    let block_count: u64 = global_state.block().count().unpack();
+    let highest_block = block_count.saturating_sub(1);
+    let l1_tip_block: u64 = rpc_client.get_tip().await?.number().unpack();
+
+    let status = match tracker.update(Instant::now(), current_block, highest_block) {
+        None => SyncingStatus::NotSyncing(false),
+        Some(progress) => SyncingStatus::Syncing(SyncProgress {
+            starting_block: progress.starting_block.into(),
+            current_block: current_block.into(),
+            highest_block: highest_block.into(),
+            l1_tip_block: l1_tip_block.into(),
+            blocks_behind: (highest_block - current_block).into(),
+            estimated_catch_up_secs: progress.estimated_catch_up_secs.map(Into::into),
+        }),
+    };
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_tracker() {
+        let tracker = SyncTracker::default();
+        let t = Instant::now();
+        assert!(tracker.update(t, 10, 10).is_none());
+
+        let progress = tracker.update(t, 10, 110).unwrap();
+        assert_eq!(progress.starting_block, 10);
+        assert_eq!(progress.estimated_catch_up_secs, None);
+
+        // 10 blocks in 5 seconds, 90 blocks to go.
+        let progress = tracker.update(t + Duration::from_secs(5), 20, 110).unwrap();
+        assert_eq!(progress.starting_block, 10);
+        assert_eq!(progress.estimated_catch_up_secs, Some(45));
+
+        // Caught up, start over when behind again.
+        assert!(tracker
+            .update(t + Duration::from_secs(60), 110, 110)
+            .is_none());
+        let progress = tracker
+            .update(t + Duration::from_secs(70), 110, 112)
+            .unwrap();
+        assert_eq!(progress.starting_block, 110);
+    }
+}
@@ -0,0 +1,289 @@
+//! gRPC service of the core query and submission APIs, see
+//! `proto/godwoken.proto`.
+//!
+//! Submissions go through the same checks, queue and rate limits as
+//! `gw_submit_l2transaction` and `gw_submit_withdrawal_request`.
+
+use std::{convert::TryInto, net::SocketAddr, pin::Pin, sync::Arc};
+
+use anyhow::Result;
+use futures::Stream;
+use gw_common::{registry_address::RegistryAddress, state::State};
+use gw_generator::Generator;
+use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
+use gw_mem_pool::subscription::Subscriptions;
+use gw_store::{
+    mem_pool_state::MemPoolState,
+    state::{history::history_state::RWConfig, BlockStateDB},
+    traits::chain_store::ChainStore,
+    Store,
+};
+use gw_types::{bytes::Bytes, h256::*, packed, prelude::*};
+use jsonrpc_v2::{Data, Error as RpcError, Params};
+use tokio::sync::{broadcast, mpsc};
+use tonic::{Code, Request, Response, Status};
+
+use crate::in_queue_request_map::InQueueRequestMap;
+use crate::registry::{
+    self, RequestContext, SubmitTransactionContext, BUSY_ERR_CODE, METHOD_NOT_AVAILABLE_ERR_CODE,
+    RATE_LIMIT_ERR_CODE,
+};
+
+pub mod proto {
+    tonic::include_proto!("godwoken.v1");
+}
+
+use proto::godwoken_server::{Godwoken, GodwokenServer};
+
+pub struct GrpcService {
+    pub(crate) store: Store,
+    pub(crate) generator: Arc<Generator>,
+    pub(crate) mem_pool_state: Arc<MemPoolState>,
+    /// `None` if the mem pool is disabled.
+    pub(crate) subscriptions: Option<Subscriptions>,
+    /// `None` if submitting txs is not available, e.g. on read-only nodes
+    /// that don't relay txs.
+    pub(crate) submit_transaction_context: Option<Arc<SubmitTransactionContext>>,
+    /// `None` on read-only nodes.
+    #[allow(clippy::type_complexity)]
+    pub(crate) submit_withdrawal: Option<(
+        Option<Arc<InQueueRequestMap>>,
+        mpsc::Sender<(registry::Request, RequestContext)>,
+    )>,
+}
+
+pub async fn start_grpc_server(
+    listen_addr: SocketAddr,
+    service: GrpcService,
+    mut sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
+    log::info!("gRPC server listening on {}", listen_addr);
+    tonic::transport::Server::builder()
+        .add_service(GodwokenServer::new(service))
+        .serve_with_shutdown(listen_addr, async move {
+            let _ = sub_shutdown.recv().await;
+            log::info!("gRPC server exited successfully");
+        })
+        .await?;
+    Ok(())
+}
+
+fn internal(err: anyhow::Error) -> Status {
+    Status::internal(format!("{:#}", err))
+}
+
+fn to_status(err: RpcError) -> Status {
+    let (code, message) = match err {
+        RpcError::Full { code, message, .. } => (code, message),
+        RpcError::Provided { code, message } => (code, message.to_string()),
+    };
+    let status_code = match code {
+        BUSY_ERR_CODE | RATE_LIMIT_ERR_CODE => Code::ResourceExhausted,
+        METHOD_NOT_AVAILABLE_ERR_CODE => Code::Unimplemented,
+        _ => Code::InvalidArgument,
+    };
+    Status::new(status_code, format!("{} (code {})", message, code))
+}
+
+fn to_h256(hash: &[u8]) -> Result<H256, Status> {
+    hash.try_into()
+        .map_err(|_| Status::invalid_argument("hash must be 32 bytes"))
+}
+
+fn to_proto_block(block: &packed::L2Block) -> proto::Block {
+    let raw = block.raw();
+    let block_producer: Bytes = raw.block_producer().unpack();
+    proto::Block {
+        hash: block.hash().to_vec(),
+        number: raw.number().unpack(),
+        timestamp: raw.timestamp().unpack(),
+        parent_block_hash: raw.parent_block_hash().as_slice().to_vec(),
+        block_producer: block_producer.to_vec(),
+        tx_hashes: { block.transactions().into_iter() }
+            .map(|tx| tx.hash().to_vec())
+            .collect(),
+        molecule: block.as_slice().to_vec(),
+    }
+}
+
+fn to_proto_receipt(receipt: &packed::TxReceipt) -> proto::TxReceipt {
+    let post_state = receipt.post_state();
+    let exit_code: u8 = receipt.exit_code().into();
+    proto::TxReceipt {
+        tx_witness_hash: receipt.tx_witness_hash().as_slice().to_vec(),
+        post_state_root: post_state.merkle_root().as_slice().to_vec(),
+        post_state_count: post_state.count().unpack(),
+        read_data_hashes: { receipt.read_data_hashes().into_iter() }
+            .map(|hash| hash.as_slice().to_vec())
+            .collect(),
+        logs: { receipt.logs().into_iter() }
+            .map(|log| {
+                let service_flag: u8 = log.service_flag().into();
+                proto::LogItem {
+                    account_id: log.account_id().unpack(),
+                    service_flag: service_flag.into(),
+                    data: log.data().raw_data().to_vec(),
+                }
+            })
+            .collect(),
+        exit_code: exit_code.into(),
+    }
+}
+
+#[tonic::async_trait]
+impl Godwoken for GrpcService {
+    async fn get_tip_block_hash(
+        &self,
+        _request: Request<proto::GetTipBlockHashRequest>,
+    ) -> Result<Response<proto::GetTipBlockHashResponse>, Status> {
+        let mem_store = self.mem_pool_state.load_mem_store();
+        let tip_block_hash = mem_store
+            .get_last_valid_tip_block_hash()
+            .map_err(internal)?;
+        Ok(Response::new(proto::GetTipBlockHashResponse {
+            block_hash: tip_block_hash.to_vec(),
+        }))
+    }
+
+    async fn get_block(
+        &self,
+        request: Request<proto::GetBlockRequest>,
+    ) -> Result<Response<proto::GetBlockResponse>, Status> {
+        use proto::get_block_request::Block as BlockId;
+
+        // Read from mem pool state for the same reason as
+        // `gw_get_block_by_number`.
+        let mem_store = self.mem_pool_state.load_mem_store();
+        let block_hash = match request.into_inner().block {
+            Some(BlockId::Hash(hash)) => Some(to_h256(&hash)?),
+            Some(BlockId::Number(number)) => mem_store
+                .get_block_hash_by_number(number)
+                .map_err(internal)?,
+            None => return Err(Status::invalid_argument("block hash or number is required")),
+        };
+        let block = match block_hash {
+            Some(hash) => mem_store.get_block(&hash).map_err(internal)?,
+            None => None,
+        };
+        // Only blocks on the main chain.
+        let block = match block {
+            Some(block) => {
+                let main_chain_hash = mem_store
+                    .get_block_hash_by_number(block.raw().number().unpack())
+                    .map_err(internal)?;
+                (main_chain_hash == Some(block.hash())).then(|| to_proto_block(&block))
+            }
+            None => None,
+        };
+        Ok(Response::new(proto::GetBlockResponse { block }))
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        request: Request<proto::GetTransactionReceiptRequest>,
+    ) -> Result<Response<proto::GetTransactionReceiptResponse>, Status> {
+        let tx_hash = to_h256(&request.into_inner().tx_hash)?;
+        let db = self.store.get_snapshot();
+        // Search from db, then from mem pool.
+        let receipt = match db.get_transaction_receipt(&tx_hash).map_err(internal)? {
+            Some(receipt) => Some(receipt),
+            None => db
+                .get_mem_pool_transaction_receipt(&tx_hash)
+                .map_err(internal)?,
+        };
+        Ok(Response::new(proto::GetTransactionReceiptResponse {
+            receipt: receipt.as_ref().map(to_proto_receipt),
+        }))
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<proto::GetBalanceRequest>,
+    ) -> Result<Response<proto::GetBalanceResponse>, Status> {
+        let request = request.into_inner();
+        let address = RegistryAddress::from_slice(&request.registry_address)
+            .ok_or_else(|| Status::invalid_argument("invalid registry address"))?;
+        let balance = match request.block_number {
+            Some(block_number) => {
+                let mut db = self.store.begin_transaction();
+                let tree = BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number))
+                    .map_err(internal)?;
+                tree.get_sudt_balance(request.sudt_id, &address)
+            }
+            None => {
+                let state = self.mem_pool_state.load_state_db();
+                state.get_sudt_balance(request.sudt_id, &address)
+            }
+        }
+        .map_err(internal)?;
+        let mut buf = [0u8; 32];
+        balance.to_little_endian(&mut buf);
+        Ok(Response::new(proto::GetBalanceResponse {
+            balance: buf.to_vec(),
+        }))
+    }
+
+    async fn submit_l2_transaction(
+        &self,
+        request: Request<proto::SubmitL2TransactionRequest>,
+    ) -> Result<Response<proto::SubmitL2TransactionResponse>, Status> {
+        let ctx = self
+            .submit_transaction_context
+            .clone()
+            .ok_or_else(|| Status::unimplemented("submitting txs is not available"))?;
+        let tx = JsonBytes::from_vec(request.into_inner().transaction);
+        let tx_hash = registry::submit_l2transaction(Params((tx,)), Data(ctx))
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(proto::SubmitL2TransactionResponse {
+            tx_hash: tx_hash.map(|hash| hash.as_bytes().to_vec()),
+        }))
+    }
+
+    async fn submit_withdrawal_request(
+        &self,
+        request: Request<proto::SubmitWithdrawalRequestRequest>,
+    ) -> Result<Response<proto::SubmitWithdrawalRequestResponse>, Status> {
+        let (in_queue_request_map, submit_tx) = self
+            .submit_withdrawal
+            .clone()
+            .ok_or_else(|| Status::unimplemented("submitting withdrawals is not available"))?;
+        let withdrawal = JsonBytes::from_vec(request.into_inner().withdrawal_request);
+        let withdrawal_hash = registry::submit_withdrawal_request(
+            Params((withdrawal,)),
+            Data(self.generator.clone()),
+            Data::new(self.store.clone()),
+            Data::new(in_queue_request_map),
+            Data::new(submit_tx),
+            Data::new(self.mem_pool_state.clone()),
+        )
+        .await
+        .map_err(to_status)?;
+        Ok(Response::new(proto::SubmitWithdrawalRequestResponse {
+            withdrawal_hash: withdrawal_hash.as_bytes().to_vec(),
+        }))
+    }
+
+    type SubscribeNewBlocksStream =
+        Pin<Box<dyn Stream<Item = Result<proto::Block, Status>> + Send + 'static>>;
+
+    async fn subscribe_new_blocks(
+        &self,
+        _request: Request<proto::SubscribeNewBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeNewBlocksStream>, Status> {
+        let subscriptions = { self.subscriptions.as_ref() }
+            .ok_or_else(|| Status::unavailable("mem pool is disabled"))?;
+        let recv = subscriptions.subscribe_new_blocks();
+        let stream = futures::stream::unfold(recv, |mut recv| async move {
+            match recv.recv().await {
+                Ok(block) => Some((Ok(to_proto_block(&block)), recv)),
+                // The stream ends after an error.
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    Some((Err(Status::data_loss(format!("lagged {} blocks", n))), recv))
+                }
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -1,8 +1,12 @@
 use std::sync::{Arc, RwLock};
-use std::{collections::HashMap, sync::Weak};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Weak,
+};
 
 use gw_types::h256::*;
 use gw_types::packed::{L2Transaction, WithdrawalRequestExtra};
+use gw_types::prelude::*;
 
 use crate::registry::Request;
 
@@ -12,6 +16,8 @@ use crate::registry::Request;
 #[derive(Default)]
 pub struct InQueueRequestMap {
     map: RwLock<HashMap<H256, Request>>,
+    /// Requests to drop instead of pushing to the mem pool.
+    evicted: RwLock<HashSet<H256>>,
 }
 
 impl InQueueRequestMap {
@@ -36,6 +42,21 @@ impl InQueueRequestMap {
         if let Some(v) = map.remove(k) {
             gw_metrics::rpc().in_queue_requests((&v).into()).dec();
         }
+        self.evicted.write().unwrap().remove(k);
+    }
+
+    /// Mark the in queue request `k` to be dropped, returns false if it's not
+    /// in queue.
+    pub(crate) fn evict(&self, k: &H256) -> bool {
+        let map = self.map.read().unwrap();
+        map.contains_key(k) && {
+            self.evicted.write().unwrap().insert(*k);
+            true
+        }
+    }
+
+    pub(crate) fn is_evicted(&self, k: &H256) -> bool {
+        self.evicted.read().unwrap().contains(k)
     }
 
     pub(crate) fn get_transaction(&self, k: &H256) -> Option<L2Transaction> {
@@ -55,6 +76,23 @@ impl InQueueRequestMap {
     pub(crate) fn contains(&self, k: &H256) -> bool {
         self.map.read().unwrap().contains_key(k)
     }
+
+    /// Max nonce of in queue txs and withdrawals of the account.
+    pub(crate) fn max_nonce(&self, account_id: u32, account_script_hash: &H256) -> Option<u32> {
+        let map = self.map.read().unwrap();
+        map.values()
+            .filter_map(|req| match req {
+                Request::Tx(tx) => {
+                    let from_id: u32 = tx.raw().from_id().unpack();
+                    (from_id == account_id).then(|| tx.raw().nonce().unpack())
+                }
+                Request::Withdrawal(w) => {
+                    let script_hash: H256 = w.raw().account_script_hash().unpack();
+                    (&script_hash == account_script_hash).then(|| w.raw().nonce().unpack())
+                }
+            })
+            .max()
+    }
 }
 
 /// RAII guard for the request in an InQueueRequestMap.
@@ -1,6 +1,15 @@
+pub mod access;
+pub mod grpc;
 pub(crate) mod in_queue_request_map;
+pub mod readonly_registry;
 pub mod registry;
+pub mod response_cache;
+pub mod scheduler;
 pub mod server;
+pub mod subscription;
 
 mod apis;
+mod logs;
+mod openrpc;
+mod rest;
 mod utils;
@@ -0,0 +1,196 @@
+//! EVM logs of blocks, i.e. polyjuice user logs, and filters of them like
+//! `eth_getLogs`.
+
+use anyhow::{bail, Context, Result};
+use ckb_fixed_hash::{H160 as JsonH160, H256 as JsonH256};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{LogView, OneOrMany},
+};
+use gw_store::{
+    log_index::{self, parse_evm_log, EvmLog, LogPosition},
+    schema::{Col, COLUMN_LOG_ADDRESS_INDEX, COLUMN_LOG_TOPIC_INDEX},
+    snapshot::StoreSnapshot,
+    traits::chain_store::ChainStore,
+};
+use gw_types::{
+    h256::*,
+    packed::{L2Block, TransactionKey},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::utils::to_jsonh256;
+
+/// Max blocks of a query that has no address or topic to look up the index.
+const MAX_SCAN_BLOCKS: u64 = 1_000;
+/// Max logs of a query.
+const MAX_LOGS: usize = 10_000;
+
+fn new_log_view(block: &L2Block, tx_hash: H256, position: LogPosition, log: EvmLog) -> LogView {
+    LogView {
+        block_number: position.block_number.into(),
+        block_hash: to_jsonh256(block.hash()),
+        transaction_hash: to_jsonh256(tx_hash),
+        transaction_index: position.tx_index.into(),
+        log_index: position.log_index.into(),
+        address: JsonH160(log.address),
+        data: JsonBytes::from_vec(log.data),
+        topics: log.topics.into_iter().map(to_jsonh256).collect(),
+    }
+}
+
+/// Filter like `eth_getLogs`. `null` topics match any topic.
+#[derive(Clone, Default, Deserialize)]
+pub(crate) struct LogFilter {
+    #[serde(default)]
+    pub address: Option<OneOrMany<JsonH160>>,
+    #[serde(default)]
+    pub topics: Vec<Option<OneOrMany<JsonH256>>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &LogView) -> bool {
+        if let Some(ref address) = self.address {
+            if !address.as_slice().contains(&log.address) {
+                return false;
+            }
+        }
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(i, topic)| match topic {
+                Some(topic) => log
+                    .topics
+                    .get(i)
+                    .map_or(false, |t| topic.as_slice().contains(t)),
+                None => true,
+            })
+    }
+
+    /// Index column and prefixes to look up, addresses first, then the first
+    /// topic that is not `null`.
+    fn index_prefixes(&self) -> Option<(Col, Vec<Vec<u8>>)> {
+        if let Some(ref address) = self.address {
+            let prefixes = address
+                .as_slice()
+                .iter()
+                .map(|a| log_index::address_prefix(&a.0))
+                .collect();
+            return Some((COLUMN_LOG_ADDRESS_INDEX, prefixes));
+        }
+        self.topics.iter().enumerate().find_map(|(i, topic)| {
+            let prefixes = topic
+                .as_ref()?
+                .as_slice()
+                .iter()
+                .map(|t| log_index::topic_prefix(&t.0, i as u8))
+                .collect();
+            Some((COLUMN_LOG_TOPIC_INDEX, prefixes))
+        })
+    }
+}
+
+/// EVM logs of a block on the main chain.
+pub(crate) fn block_logs(snap: &impl ChainStore, block: &L2Block) -> Result<Vec<LogView>> {
+    let block_hash = block.hash();
+    let block_number: u64 = block.raw().number().unpack();
+    let mut logs = Vec::new();
+    for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+        let key = TransactionKey::build_transaction_key(block_hash.pack(), tx_index as u32);
+        let receipt = snap
+            .get_transaction_receipt_by_key(&key)?
+            .context("tx receipt")?;
+        for (receipt_log_index, item) in receipt.logs().into_iter().enumerate() {
+            if let Some(log) = parse_evm_log(&item) {
+                let position = LogPosition {
+                    block_number,
+                    log_index: logs.len() as u32,
+                    tx_index: tx_index as u32,
+                    receipt_log_index: receipt_log_index as u32,
+                };
+                logs.push(new_log_view(block, tx.hash(), position, log));
+            }
+        }
+    }
+    Ok(logs)
+}
+
+/// Logs matching `filter` in blocks `from..=to`.
+pub(crate) fn query_logs(
+    snap: &StoreSnapshot,
+    filter: &LogFilter,
+    from: u64,
+    to: u64,
+) -> Result<Vec<LogView>> {
+    if from > to {
+        return Ok(Vec::new());
+    }
+
+    let (col, prefixes) = match filter.index_prefixes() {
+        Some(index) => index,
+        None => {
+            if to - from >= MAX_SCAN_BLOCKS {
+                bail!(
+                    "block range exceeds {} without an address or topic",
+                    MAX_SCAN_BLOCKS
+                );
+            }
+            let mut logs = Vec::new();
+            for number in from..=to {
+                let block = match snap.get_block_hash_by_number(number)? {
+                    Some(hash) => snap.get_block(&hash)?.context("block")?,
+                    None => break,
+                };
+                logs.extend(
+                    block_logs(snap, &block)?
+                        .into_iter()
+                        .filter(|log| filter.matches(log)),
+                );
+                if logs.len() > MAX_LOGS {
+                    bail!("query returns more than {} logs", MAX_LOGS);
+                }
+            }
+            return Ok(logs);
+        }
+    };
+
+    let mut positions: Vec<LogPosition> = Vec::new();
+    for prefix in prefixes.iter() {
+        positions.extend(snap.iter_log_positions(col, prefix, from, to));
+        if positions.len() > MAX_LOGS {
+            bail!("query returns more than {} logs", MAX_LOGS);
+        }
+    }
+    positions.sort_unstable();
+    positions.dedup();
+
+    let mut logs = Vec::new();
+    let mut block: Option<(u64, L2Block)> = None;
+    for position in positions {
+        if block.as_ref().map(|(number, _)| *number) != Some(position.block_number) {
+            let hash = snap
+                .get_block_hash_by_number(position.block_number)?
+                .context("block hash")?;
+            let b = snap.get_block(&hash)?.context("block")?;
+            block = Some((position.block_number, b));
+        }
+        let (_, block) = block.as_ref().expect("block");
+        let key = TransactionKey::build_transaction_key(block.hash().pack(), position.tx_index);
+        let tx = snap.get_transaction_by_key(&key)?.context("tx")?;
+        let receipt = snap
+            .get_transaction_receipt_by_key(&key)?
+            .context("tx receipt")?;
+        let log = receipt
+            .logs()
+            .get(position.receipt_log_index as usize)
+            .as_ref()
+            .and_then(parse_evm_log)
+            .context("indexed log")?;
+        let log = new_log_view(block, tx.hash(), position, log);
+        if filter.matches(&log) {
+            logs.push(log);
+        }
+    }
+    Ok(logs)
+}
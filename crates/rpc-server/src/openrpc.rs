@@ -0,0 +1,458 @@
+//! OpenRPC document of the JSON RPC methods, served by `rpc.discover`.
+//!
+//! Schemas are derived from the request / response types, only method
+//! signatures are listed here. Keep them in sync with the handlers when
+//! adding or changing methods.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use gw_jsonrpc_types::{
+    blockchain::{CellDep, OutPoint, Script},
+    debug::{DebugRunResult, DebugTxTrace},
+    godwoken::{
+        AccountProof, AccountRef, AccountStateOverride, AccountSummary, AccountTransactions,
+        BackupManifest, BatchRunResult, BlockCyclesReport, BlockProof, BlockStateDiff,
+        CKBEndpointStatus, DepositLifecycle, FeeConfig, FeeHistory, FilterChanges, ForkInfo,
+        GlobalState, L2BlockCommittedInfo, L2BlockWithStatus, L2TransactionWithStatus,
+        LastL2BlockCommittedInfo, LogFilterParams, LogView, NodeInfo, Order, RegistryAddress,
+        ReorgRecord, RunResult, SUDTBalance, SUDTToken, StateDivergence, StateProof, SudtTransfers,
+        SyncingStatus, TxReceipt, WithdrawableCapacity, WithdrawalLifecycle, WithdrawalQueueItem,
+        WithdrawalWithStatus,
+    },
+    schema::{JsonBytes, Uint32, Uint64, H160, H256, U256},
+    test_mode::TestModePayload,
+};
+use gw_version::Version;
+use jsonrpc_v2::Data;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
+use serde_json::{json, Value};
+
+use crate::apis::query::BlockByNumberView;
+
+pub(crate) struct OpenRpcDocument(Value);
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+pub(crate) struct Param {
+    name: &'static str,
+    required: bool,
+    schema: SchemaFn,
+}
+
+pub(crate) struct Method {
+    name: &'static str,
+    params: Vec<Param>,
+    result: SchemaFn,
+}
+
+fn required<T: JsonSchema>(name: &'static str) -> Param {
+    Param {
+        name,
+        required: true,
+        schema: SchemaGenerator::subschema_for::<T>,
+    }
+}
+
+/// Trailing params that can be omitted.
+fn optional<T: JsonSchema>(name: &'static str) -> Param {
+    Param {
+        name,
+        required: false,
+        schema: SchemaGenerator::subschema_for::<T>,
+    }
+}
+
+fn method<R: JsonSchema>(name: &'static str, params: Vec<Param>) -> Method {
+    Method {
+        name,
+        params,
+        result: SchemaGenerator::subschema_for::<R>,
+    }
+}
+
+/// Methods available on all nodes.
+pub(crate) fn core_methods() -> Vec<Method> {
+    vec![
+        method::<String>("gw_ping", vec![]),
+        method::<H256>("gw_get_tip_block_hash", vec![]),
+        method::<Option<H256>>(
+            "gw_get_block_hash",
+            vec![required::<Uint64>("block_number")],
+        ),
+        method::<Option<L2BlockWithStatus>>("gw_get_block", vec![required::<H256>("block_hash")]),
+        method::<Option<BlockByNumberView>>(
+            "gw_get_block_by_number",
+            vec![required::<Uint64>("block_number"), optional::<bool>("full")],
+        ),
+        method::<Option<L2BlockCommittedInfo>>(
+            "gw_get_block_committed_info",
+            vec![required::<H256>("block_hash")],
+        ),
+        method::<U256>(
+            "gw_get_balance",
+            vec![
+                required::<JsonBytes>("registry_address"),
+                required::<Uint32>("sudt_id"),
+                optional::<Option<Uint64>>("block_number"),
+            ],
+        ),
+        method::<Vec<U256>>(
+            "gw_get_balances",
+            vec![
+                required::<JsonBytes>("registry_address"),
+                required::<Vec<Uint32>>("sudt_ids"),
+            ],
+        ),
+        method::<Vec<U256>>(
+            "gw_get_balances_bulk",
+            vec![
+                required::<Vec<JsonBytes>>("registry_addresses"),
+                required::<Uint32>("sudt_id"),
+            ],
+        ),
+        method::<Vec<SUDTBalance>>(
+            "gw_get_sudt_balances",
+            vec![required::<JsonBytes>("registry_address")],
+        ),
+        method::<Vec<SUDTToken>>("gw_list_sudt_tokens", vec![]),
+        method::<Vec<H160>>(
+            "gw_get_sudt_proxy_addresses",
+            vec![required::<Uint32>("sudt_id")],
+        ),
+        method::<Option<Uint32>>(
+            "gw_get_sudt_id_by_proxy_address",
+            vec![required::<H160>("address")],
+        ),
+        method::<H256>(
+            "gw_get_storage_at",
+            vec![
+                required::<Uint32>("account_id"),
+                required::<H256>("key"),
+                optional::<Option<Uint64>>("block_number"),
+            ],
+        ),
+        method::<AccountProof>(
+            "gw_get_account_proof",
+            vec![
+                required::<H256>("script_hash"),
+                optional::<Option<Uint64>>("block_number"),
+            ],
+        ),
+        method::<StateProof>(
+            "gw_get_storage_proof",
+            vec![
+                required::<Uint32>("account_id"),
+                required::<Vec<H256>>("keys"),
+                optional::<Option<Uint64>>("block_number"),
+            ],
+        ),
+        method::<BlockProof>(
+            "gw_get_block_proof",
+            vec![required::<Uint64>("block_number")],
+        ),
+        method::<FeeHistory>(
+            "gw_fee_history",
+            vec![
+                required::<Uint64>("block_count"),
+                optional::<Option<Vec<f64>>>("percentiles"),
+            ],
+        ),
+        method::<Option<BlockCyclesReport>>(
+            "gw_get_block_cycles_report",
+            vec![required::<Uint64>("block_number")],
+        ),
+        method::<Option<BlockStateDiff>>(
+            "gw_get_block_state_diff",
+            vec![required::<Uint64>("block_number")],
+        ),
+        method::<AccountTransactions>(
+            "gw_list_account_transactions",
+            vec![
+                required::<AccountRef>("account"),
+                required::<Order>("order"),
+                required::<Uint32>("limit"),
+                optional::<Option<JsonBytes>>("after"),
+            ],
+        ),
+        method::<SudtTransfers>(
+            "gw_list_sudt_transfers",
+            vec![
+                required::<JsonBytes>("registry_address"),
+                required::<Order>("order"),
+                required::<Uint32>("limit"),
+                optional::<Option<JsonBytes>>("after"),
+            ],
+        ),
+        method::<Option<Uint32>>(
+            "gw_get_account_id_by_script_hash",
+            vec![required::<H256>("script_hash")],
+        ),
+        method::<Uint32>(
+            "gw_get_nonce",
+            vec![
+                required::<Uint32>("account_id"),
+                optional::<Option<Uint64>>("block_number"),
+            ],
+        ),
+        method::<Uint32>(
+            "gw_get_pending_nonce",
+            vec![required::<JsonBytes>("registry_address")],
+        ),
+        method::<Option<Script>>("gw_get_script", vec![required::<H256>("script_hash")]),
+        method::<H256>("gw_get_script_hash", vec![required::<Uint32>("account_id")]),
+        method::<Vec<AccountSummary>>(
+            "gw_list_accounts",
+            vec![
+                required::<Uint32>("from_id"),
+                optional::<Option<Uint32>>("limit"),
+            ],
+        ),
+        method::<Vec<ReorgRecord>>(
+            "gw_list_reorgs",
+            vec![
+                required::<Option<Uint64>>("before"),
+                optional::<Option<Uint32>>("limit"),
+            ],
+        ),
+        method::<Option<H256>>(
+            "gw_get_script_hash_by_registry_address",
+            vec![required::<JsonBytes>("registry_address")],
+        ),
+        method::<Option<RegistryAddress>>(
+            "gw_get_registry_address_by_script_hash",
+            vec![
+                required::<H256>("script_hash"),
+                required::<Uint32>("registry_id"),
+            ],
+        ),
+        method::<Vec<RegistryAddress>>(
+            "gw_get_registry_addresses_by_script_hash",
+            vec![required::<H256>("script_hash")],
+        ),
+        method::<Option<JsonBytes>>(
+            "gw_get_data",
+            vec![
+                required::<H256>("data_hash"),
+                optional::<Option<Uint64>>("block_number"),
+            ],
+        ),
+        method::<Option<L2TransactionWithStatus>>(
+            "gw_get_transaction",
+            vec![required::<H256>("tx_hash"), optional::<u8>("verbose")],
+        ),
+        method::<Option<TxReceipt>>(
+            "gw_get_transaction_receipt",
+            vec![required::<H256>("tx_hash")],
+        ),
+        method::<Option<WithdrawalWithStatus>>(
+            "gw_get_withdrawal",
+            vec![
+                required::<H256>("withdrawal_hash"),
+                optional::<u8>("verbose"),
+            ],
+        ),
+        method::<Option<WithdrawalLifecycle>>(
+            "gw_get_withdrawal_status",
+            vec![required::<H256>("withdrawal_hash")],
+        ),
+        method::<Vec<WithdrawalQueueItem>>("gw_get_withdrawal_queue", vec![]),
+        method::<Vec<H256>>("gw_get_deferred_withdrawals", vec![]),
+        method::<WithdrawableCapacity>(
+            "gw_get_withdrawable_capacity",
+            vec![optional::<Option<H256>>("sudt_script_hash")],
+        ),
+        method::<Option<DepositLifecycle>>(
+            "gw_get_deposit_status",
+            vec![required::<OutPoint>("out_point")],
+        ),
+        method::<Vec<H256>>("gw_get_pending_tx_hashes", vec![]),
+        method::<RunResult>(
+            "gw_execute_l2transaction",
+            vec![required::<JsonBytes>("l2tx")],
+        ),
+        method::<RunResult>(
+            "gw_execute_raw_l2transaction",
+            vec![
+                required::<JsonBytes>("raw_l2tx"),
+                optional::<Option<Uint64>>("block_number"),
+                optional::<Option<JsonBytes>>("registry_address"),
+                optional::<HashMap<String, AccountStateOverride>>("state_overrides"),
+            ],
+        ),
+        method::<Vec<BatchRunResult>>(
+            "gw_batch_execute",
+            vec![
+                required::<Vec<JsonBytes>>("raw_l2txs"),
+                optional::<Option<JsonBytes>>("registry_address"),
+            ],
+        ),
+        method::<H256>(
+            "gw_compute_l2_sudt_script_hash",
+            vec![required::<H256>("l1_sudt_script_hash")],
+        ),
+        method::<FeeConfig>("gw_get_fee_config", vec![]),
+        method::<H256>("gw_get_mem_pool_state_root", vec![]),
+        method::<bool>("gw_get_mem_pool_state_ready", vec![]),
+        method::<NodeInfo>("gw_get_node_info", vec![]),
+        method::<Vec<ForkInfo>>("gw_get_fork_schedule", vec![]),
+        method::<Vec<Value>>("gw_get_config_changelog", vec![]),
+        method::<LastL2BlockCommittedInfo>("gw_get_last_submitted_info", vec![]),
+        method::<SyncingStatus>("gw_syncing", vec![]),
+        method::<Vec<LogView>>("gw_get_logs", vec![required::<LogFilterParams>("filter")]),
+        method::<Uint64>("gw_new_filter", vec![required::<LogFilterParams>("filter")]),
+        method::<Uint64>("gw_new_block_filter", vec![]),
+        method::<Uint64>("gw_new_pending_transaction_filter", vec![]),
+        method::<bool>("gw_uninstall_filter", vec![required::<Uint64>("filter_id")]),
+        method::<FilterChanges>(
+            "gw_get_filter_changes",
+            vec![required::<Uint64>("filter_id")],
+        ),
+        method::<Vec<LogView>>("gw_get_filter_logs", vec![required::<Uint64>("filter_id")]),
+    ]
+}
+
+/// Available on full nodes, and on read-only nodes relaying txs.
+pub(crate) fn submit_transaction_methods() -> Vec<Method> {
+    vec![method::<Option<H256>>(
+        "gw_submit_l2transaction",
+        vec![required::<JsonBytes>("l2tx")],
+    )]
+}
+
+/// Methods only available on full nodes, besides `submit_transaction_methods`.
+pub(crate) fn full_node_methods() -> Vec<Method> {
+    vec![
+        method::<H256>(
+            "gw_submit_withdrawal_request",
+            vec![required::<JsonBytes>("withdrawal_request")],
+        ),
+        method::<bool>("gw_is_request_in_queue", vec![required::<H256>("hash")]),
+    ]
+}
+
+pub(crate) fn tests_methods() -> Vec<Method> {
+    vec![
+        method::<()>(
+            "tests_produce_block",
+            vec![required::<TestModePayload>("payload")],
+        ),
+        method::<GlobalState>("tests_get_global_state", vec![]),
+    ]
+}
+
+pub(crate) fn pprof_methods() -> Vec<Method> {
+    vec![
+        method::<()>("gw_start_profiler", vec![]),
+        method::<()>("gw_report_pprof", vec![]),
+    ]
+}
+
+pub(crate) fn test_methods() -> Vec<Method> {
+    vec![
+        method::<Vec<Value>>("gw_get_rocksdb_mem_stats", vec![]),
+        method::<()>("gw_dump_jemalloc_profiling", vec![]),
+    ]
+}
+
+pub(crate) fn admin_methods() -> Vec<Method> {
+    vec![
+        method::<Vec<CKBEndpointStatus>>("gw_get_ckb_endpoints", vec![]),
+        method::<Vec<RegistryAddress>>("gw_get_new_address_queue", vec![]),
+    ]
+}
+
+pub(crate) fn admin_namespace_methods() -> Vec<Method> {
+    vec![
+        method::<bool>("admin_evict_transaction", vec![required::<H256>("hash")]),
+        method::<Value>("admin_reload_config", vec![]),
+        method::<Vec<CellDep>>("admin_refresh_contracts_deps", vec![]),
+        method::<String>("admin_dump_mem_block", vec![]),
+        method::<()>("admin_set_log_level", vec![required::<String>("filter")]),
+        method::<Option<StateDivergence>>("admin_get_safe_mode", vec![]),
+        method::<BackupManifest>("admin_create_backup", vec![required::<String>("path")]),
+    ]
+}
+
+pub(crate) fn debug_methods() -> Vec<Method> {
+    vec![
+        method::<Option<DebugRunResult>>(
+            "debug_replay_transaction",
+            vec![
+                required::<H256>("tx_hash"),
+                optional::<Uint64>("max_cycles"),
+            ],
+        ),
+        method::<Option<DebugTxTrace>>(
+            "gw_trace_transaction",
+            vec![
+                required::<H256>("tx_hash"),
+                optional::<Uint64>("max_cycles"),
+            ],
+        ),
+        method::<DebugTxTrace>(
+            "gw_debug_l2transaction",
+            vec![
+                required::<JsonBytes>("l2tx"),
+                optional::<Uint64>("max_cycles"),
+            ],
+        ),
+    ]
+}
+
+impl OpenRpcDocument {
+    pub(crate) fn new(methods: &[Method]) -> Result<Self> {
+        let mut gen = SchemaSettings::draft07()
+            .with(|s| s.definitions_path = "#/components/schemas/".into())
+            .into_generator();
+        let mut docs = Vec::with_capacity(methods.len() + 1);
+        docs.push(json!({
+            "name": "rpc.discover",
+            "params": [],
+            "result": {
+                "name": "result",
+                "schema": { "$ref": "https://raw.githubusercontent.com/open-rpc/meta-schema/master/schema.json" },
+            },
+        }));
+        for m in methods {
+            let params = { m.params.iter() }
+                .map(|p| {
+                    Ok(json!({
+                        "name": p.name,
+                        "required": p.required,
+                        "schema": serde_json::to_value((p.schema)(&mut gen))?,
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            docs.push(json!({
+                "name": m.name,
+                "paramStructure": "by-position",
+                "params": params,
+                "result": {
+                    "name": "result",
+                    "schema": serde_json::to_value((m.result)(&mut gen))?,
+                },
+            }));
+        }
+
+        Ok(OpenRpcDocument(json!({
+            "openrpc": "1.2.6",
+            "info": {
+                "title": "Godwoken JSON RPC",
+                "version": Version::current().to_string(),
+            },
+            "methods": docs,
+            "components": {
+                "schemas": serde_json::to_value(gen.take_definitions())?,
+            },
+        })))
+    }
+}
+
+pub(crate) async fn discover(document: Data<OpenRpcDocument>) -> Result<Value> {
+    Ok(document.0.clone())
+}
@@ -0,0 +1,279 @@
+//! Query-only RPCs served directly from a `StoreReadonly`.
+//!
+//! There is no mem pool or chain task behind these RPCs, so everything is
+//! answered from committed blocks. The store is swappable so that the serving
+//! process can reopen the db to pick up new blocks.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use gw_generator::ArcSwap;
+use gw_jsonrpc_types::{
+    blockchain::Script,
+    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    godwoken::{
+        AccountProof, BlockProof, L2BlockWithStatus, L2TransactionWithStatus, RegistryAddress,
+        StateProof, TxReceipt,
+    },
+};
+use gw_store::{
+    readonly::StoreReadonly,
+    state::{history::history_state::RWConfig, overlay::mem_store::MemStore, BlockStateDB},
+    traits::chain_store::ChainStore,
+};
+use gw_types::{packed::RollupConfig, U256};
+use jsonrpc_v2::{Data, Error as RpcError, MapRouter, Params, Server};
+use tracing::instrument;
+
+use crate::apis::proof::{
+    account_proof, block_proof, storage_proof, GetAccountProofParams, GetStorageProofParams,
+};
+use crate::apis::query::{
+    self, BlockByNumberView, GetBalanceParams, GetBlockByNumberParams, GetDataParams,
+    GetNonceParams, GetStorageAtParams, GetTxParams,
+};
+use crate::registry::{ping, AccountID, GwUint64, JsonH256};
+use crate::utils::{to_h256, to_jsonh256};
+
+type ReadonlyStateDB = BlockStateDB<MemStore<StoreReadonly>>;
+
+pub struct ReadonlyRegistry {
+    store: Arc<ArcSwap<StoreReadonly>>,
+    rollup_config: RollupConfig,
+}
+
+impl ReadonlyRegistry {
+    pub fn new(store: Arc<ArcSwap<StoreReadonly>>, rollup_config: RollupConfig) -> Self {
+        Self {
+            store,
+            rollup_config,
+        }
+    }
+
+    pub fn build_rpc_server(self) -> Result<Arc<Server<MapRouter>>> {
+        let server = Server::new()
+            .with_data(Data(self.store))
+            .with_data(Data::new(self.rollup_config))
+            .with_method("gw_ping", ping)
+            .with_method("gw_get_tip_block_hash", get_tip_block_hash)
+            .with_method("gw_get_block_hash", get_block_hash)
+            .with_method("gw_get_block", get_block)
+            .with_method("gw_get_block_by_number", get_block_by_number)
+            .with_method("gw_get_balance", get_balance)
+            .with_method("gw_get_storage_at", get_storage_at)
+            .with_method(
+                "gw_get_account_id_by_script_hash",
+                get_account_id_by_script_hash,
+            )
+            .with_method("gw_get_nonce", get_nonce)
+            .with_method("gw_get_script", get_script)
+            .with_method("gw_get_script_hash", get_script_hash)
+            .with_method(
+                "gw_get_script_hash_by_registry_address",
+                get_script_hash_by_registry_address,
+            )
+            .with_method(
+                "gw_get_registry_address_by_script_hash",
+                get_registry_address_by_script_hash,
+            )
+            .with_method("gw_get_data", get_data)
+            .with_method("gw_get_transaction", get_transaction)
+            .with_method("gw_get_transaction_receipt", get_transaction_receipt)
+            .with_method("gw_get_account_proof", get_account_proof)
+            .with_method("gw_get_storage_proof", get_storage_proof)
+            .with_method("gw_get_block_proof", get_block_proof);
+
+        Ok(server.finish())
+    }
+}
+
+/// State at `block_number`, or at the tip block if it is `None`.
+fn state_db(store: &ArcSwap<StoreReadonly>, block_number: Option<u64>) -> Result<ReadonlyStateDB> {
+    let rw_config = match block_number {
+        Some(number) => RWConfig::history_block(number),
+        None => RWConfig::readonly(),
+    };
+    BlockStateDB::from_store(MemStore::new(store.load_full()), rw_config)
+}
+
+#[instrument(skip_all)]
+async fn get_tip_block_hash(store: Data<ArcSwap<StoreReadonly>>) -> Result<JsonH256> {
+    let tip_block_hash = store.load().get_last_valid_tip_block_hash()?;
+    Ok(to_jsonh256(tip_block_hash))
+}
+
+#[instrument(skip_all)]
+async fn get_block_hash(
+    Params((block_number,)): Params<(GwUint64,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<JsonH256>> {
+    let hash_opt = store
+        .load()
+        .get_block_hash_by_number(block_number.value())?
+        .map(to_jsonh256);
+    Ok(hash_opt)
+}
+
+#[instrument(skip_all)]
+async fn get_block(
+    Params((block_hash,)): Params<(JsonH256,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+    rollup_config: Data<RollupConfig>,
+) -> Result<Option<L2BlockWithStatus>> {
+    query::block_with_status(&**store.load(), &rollup_config, to_h256(block_hash))
+}
+
+#[instrument(skip_all)]
+async fn get_block_by_number(
+    Params(params): Params<GetBlockByNumberParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<BlockByNumberView>> {
+    let (block_number, full) = params.into_parts();
+    query::block_by_number(&**store.load(), block_number, full)
+}
+
+#[instrument(skip_all)]
+async fn get_transaction(
+    Params(param): Params<GetTxParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<L2TransactionWithStatus>, RpcError> {
+    let (tx_hash, verbose) = param.into_parts()?;
+    Ok(query::committed_transaction(
+        &**store.load(),
+        &tx_hash,
+        verbose,
+    )?)
+}
+
+#[instrument(skip_all)]
+async fn get_transaction_receipt(
+    Params((tx_hash,)): Params<(JsonH256,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<TxReceipt>> {
+    let receipt_opt = store
+        .load()
+        .get_transaction_receipt(&to_h256(tx_hash))?
+        .map(Into::into);
+    Ok(receipt_opt)
+}
+
+#[instrument(skip_all)]
+async fn get_balance(
+    Params(params): Params<GetBalanceParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<U256, RpcError> {
+    let (serialized_address, sudt_id, block_number) = params.into_parts();
+    let state = state_db(&store, block_number)?;
+    query::balance(&state, &serialized_address, sudt_id)
+}
+
+#[instrument(skip_all)]
+async fn get_storage_at(
+    Params(params): Params<GetStorageAtParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<JsonH256, RpcError> {
+    let (account_id, key, block_number) = params.into_parts();
+    let state = state_db(&store, block_number)?;
+    query::storage_at(&state, account_id, key)
+}
+
+#[instrument(skip_all)]
+async fn get_account_id_by_script_hash(
+    Params((script_hash,)): Params<(JsonH256,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<AccountID>, RpcError> {
+    let state = state_db(&store, None)?;
+    query::account_id_by_script_hash(&state, script_hash)
+}
+
+#[instrument(skip_all)]
+async fn get_nonce(
+    Params(params): Params<GetNonceParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Uint32, RpcError> {
+    let (account_id, block_number) = params.into_parts();
+    let state = state_db(&store, block_number)?;
+    query::nonce(&state, account_id)
+}
+
+#[instrument(skip_all)]
+async fn get_script(
+    Params((script_hash,)): Params<(JsonH256,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<Script>, RpcError> {
+    let state = state_db(&store, None)?;
+    Ok(query::script(&state, script_hash))
+}
+
+#[instrument(skip_all)]
+async fn get_script_hash(
+    Params((account_id,)): Params<(AccountID,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<JsonH256, RpcError> {
+    let state = state_db(&store, None)?;
+    query::script_hash(&state, account_id)
+}
+
+#[instrument(skip_all)]
+async fn get_script_hash_by_registry_address(
+    Params((serialized_address,)): Params<(JsonBytes,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<JsonH256>, RpcError> {
+    let state = state_db(&store, None)?;
+    query::script_hash_by_registry_address(&state, &serialized_address)
+}
+
+#[instrument(skip_all)]
+async fn get_registry_address_by_script_hash(
+    Params((script_hash, registry_id)): Params<(JsonH256, Uint32)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<RegistryAddress>, RpcError> {
+    let state = state_db(&store, None)?;
+    query::registry_address_by_script_hash(&state, script_hash, registry_id)
+}
+
+#[instrument(skip_all)]
+async fn get_data(
+    Params(params): Params<GetDataParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<Option<JsonBytes>, RpcError> {
+    let state = state_db(&store, None)?;
+    Ok(query::data(&state, params.data_hash()))
+}
+
+// Proofs are not cached, there is no response cache on the readonly node.
+
+#[instrument(skip_all)]
+async fn get_account_proof(
+    Params(params): Params<GetAccountProofParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<AccountProof, RpcError> {
+    let (script_hash, block_number) = params.into_parts();
+    let db = store.load_full();
+    let proof =
+        tokio::task::spawn_blocking(move || account_proof(db, script_hash, block_number)).await??;
+    Ok(proof)
+}
+
+#[instrument(skip_all)]
+async fn get_storage_proof(
+    Params(params): Params<GetStorageProofParams>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<StateProof, RpcError> {
+    let (_account_id, keys, block_number) = params.into_parts()?;
+    let db = store.load_full();
+    let proof =
+        tokio::task::spawn_blocking(move || storage_proof(db, keys, block_number)).await??;
+    Ok(proof)
+}
+
+#[instrument(skip_all)]
+async fn get_block_proof(
+    Params((block_number,)): Params<(GwUint64,)>,
+    store: Data<ArcSwap<StoreReadonly>>,
+) -> Result<BlockProof, RpcError> {
+    let block_number = block_number.value();
+    let db = store.load_full();
+    let proof = tokio::task::spawn_blocking(move || block_proof(&*db, block_number)).await??;
+    Ok(proof)
+}
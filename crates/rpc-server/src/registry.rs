@@ -3,12 +3,12 @@ use async_trait::async_trait;
 use ckb_types::prelude::{Builder, Entity};
 use gw_common::blake2b::new_blake2b;
 use gw_common::builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID};
-use gw_common::state::State;
+use gw_common::state::{build_account_key, State};
 use gw_config::{
     BackendForkConfig, ChainConfig, ConsensusConfig, FeeConfig, GaslessTxSupportConfig,
-    MemPoolConfig, NodeMode, RPCMethods, RPCRateLimit, RPCServerConfig, SyscallCyclesConfig,
+    MemPoolConfig, NodeMode, RPCMethods, RPCServerConfig, SyscallCyclesConfig,
 };
-use gw_dynamic_config::manager::{DynamicConfigManager, DynamicConfigReloadResponse};
+use gw_dynamic_config::manager::{ConfigChange, DynamicConfigManager};
 use gw_generator::backend_manage::BackendManage;
 use gw_generator::generator::CyclesPool;
 use gw_generator::utils::get_tx_type;
@@ -18,13 +18,16 @@ use gw_generator::{
 };
 use gw_jsonrpc_types::godwoken::L2WithdrawalCommittedInfo;
 use gw_jsonrpc_types::{
-    blockchain::Script,
+    blockchain::{OutPoint, Script},
     ckb_jsonrpc_types::{JsonBytes, Uint32},
     godwoken::{
-        BackendInfo, BackendType, EoaScript, EoaScriptType, ErrorTxReceipt, GlobalState, GwScript,
-        GwScriptType, L2BlockCommittedInfo, L2BlockStatus, L2BlockView, L2BlockWithStatus,
-        L2TransactionStatus, L2TransactionWithStatus, LastL2BlockCommittedInfo, NodeInfo,
-        NodeRollupConfig, RegistryAddress, RollupCell, RunResult, TxReceipt, WithdrawalStatus,
+        AccountStateOverride, AccountSummary, BackendInfo, BackendType, BatchRunResult,
+        CKBEndpointStatus, DepositLifecycle, DepositLifecycleStatus, EoaScript, EoaScriptType,
+        ErrorTxReceipt, ForkInfo, GlobalState, GwScript, GwScriptType, L2BlockCommittedInfo,
+        L2BlockStatus, L2BlockWithStatus, L2TransactionStatus, L2TransactionWithStatus,
+        LastL2BlockCommittedInfo, NodeInfo, NodeRollupConfig, RegistryAddress, ReorgKind,
+        ReorgRecord, RollupCell, RunResult, TxReceipt, WithdrawableCapacity, WithdrawableSUDT,
+        WithdrawalLifecycle, WithdrawalLifecycleStatus, WithdrawalQueueItem, WithdrawalStatus,
         WithdrawalWithStatus,
     },
     test_mode::TestModePayload,
@@ -33,13 +36,14 @@ use gw_mem_pool::fee::{
     queue::FeeQueue,
     types::{FeeEntry, FeeItem, FeeItemKind, FeeItemSender},
 };
+use gw_mem_pool::subscription::Subscriptions;
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
-use gw_rpc_client::rpc_client::RPCClient;
+use gw_rpc_client::{contract::ContractsCellDepManager, rpc_client::RPCClient};
 use gw_store::state::history::history_state::RWConfig;
 use gw_store::state::{BlockStateDB, MemStateDB};
 use gw_store::{
-    chain_view::ChainView, mem_pool_state::MemPoolState, traits::chain_store::ChainStore,
-    CfMemStat, Store,
+    chain_view::ChainView, deposit_status::DepositStatus, mem_pool_state::MemPoolState, reorg_log,
+    traits::chain_store::ChainStore, CfMemStat, Store,
 };
 use gw_telemetry::traits::{TelemetryContext, TelemetryContextNewSpan, TelemetrySpanExt};
 use gw_traits::CodeStore;
@@ -58,6 +62,7 @@ use lru::LruCache;
 use once_cell::sync::Lazy;
 use pprof::ProfilerGuard;
 use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     convert::{TryFrom, TryInto},
     sync::Arc,
     time::{Duration, Instant},
@@ -65,8 +70,33 @@ use std::{
 use tokio::sync::{mpsc, Mutex};
 use tracing::instrument;
 
-use crate::apis::debug::{replay_transaction, DebugTransactionContext};
+use crate::apis::account_txs::list_account_transactions;
+use crate::apis::admin::{with_admin_methods, AdminContext};
+use crate::apis::block_cycles::get_block_cycles_report;
+use crate::apis::block_state_diff::get_block_state_diff;
+use crate::apis::debug::{
+    debug_l2transaction, replay_transaction, trace_transaction, DebugTransactionContext,
+};
+use crate::apis::fee_history::fee_history;
+use crate::apis::filter::{
+    get_filter_changes, get_filter_logs, get_logs, new_block_filter, new_filter,
+    new_pending_transaction_filter, uninstall_filter, FilterManager,
+};
+use crate::apis::proof::{get_account_proof, get_block_proof, get_storage_proof};
+use crate::apis::query::{
+    self, BlockByNumberView, GetBalanceParams, GetBlockByNumberParams, GetDataParams,
+    GetNonceParams, GetStorageAtParams, GetTxParams, GetTxVerbose,
+};
+use crate::apis::sudt_tokens::{
+    get_sudt_balances, get_sudt_id_by_proxy_address, get_sudt_proxy_addresses, list_sudt_tokens,
+    SUDTTokens,
+};
+use crate::apis::sudt_transfers::list_sudt_transfers;
+use crate::apis::syncing::{syncing, SyncTracker};
+use crate::grpc::GrpcService;
 use crate::in_queue_request_map::{InQueueRequestHandle, InQueueRequestMap};
+use crate::openrpc::{self, OpenRpcDocument};
+use crate::response_cache::{is_finalized, CacheKey, CachedResponse, ResponseCache};
 use crate::utils::{to_h256, to_jsonh256};
 
 static PROFILER_GUARD: Lazy<tokio::sync::Mutex<Option<ProfilerGuard>>> =
@@ -75,25 +105,28 @@ static PROFILER_GUARD: Lazy<tokio::sync::Mutex<Option<ProfilerGuard>>> =
 // type alias
 type RPCServer = Arc<Server<MapRouter>>;
 type MemPool = Option<Arc<Mutex<gw_mem_pool::pool::MemPool>>>;
-type AccountID = Uint32;
-type JsonH256 = ckb_fixed_hash::H256;
+pub(crate) type AccountID = Uint32;
+pub(crate) type JsonH256 = ckb_fixed_hash::H256;
 type BoxedTestsRPCImpl = Box<dyn TestModeRPC + Send + Sync>;
-type GwUint64 = gw_jsonrpc_types::ckb_jsonrpc_types::Uint64;
+pub(crate) type GwUint64 = gw_jsonrpc_types::ckb_jsonrpc_types::Uint64;
 type GwUint32 = gw_jsonrpc_types::ckb_jsonrpc_types::Uint32;
 type RpcNodeMode = gw_jsonrpc_types::godwoken::NodeMode;
 type RegistryAddressJsonBytes = JsonBytes;
 
 const HEADER_NOT_FOUND_ERR_CODE: i64 = -32000;
 const INVALID_NONCE_ERR_CODE: i64 = -32001;
-const BUSY_ERR_CODE: i64 = -32006;
+pub(crate) const BUSY_ERR_CODE: i64 = -32006;
 const CUSTODIAN_NOT_ENOUGH_CODE: i64 = -32007;
+const EXECUTION_TIMEOUT_ERR_CODE: i64 = -32008;
+pub(crate) const UNAUTHORIZED_ERR_CODE: i64 = -32010;
+const SAFE_MODE_ERR_CODE: i64 = -32011;
 const INTERNAL_ERROR_ERR_CODE: i64 = -32099;
 const INVALID_REQUEST: i64 = -32600;
-const METHOD_NOT_AVAILABLE_ERR_CODE: i64 = -32601;
-const INVALID_PARAM_ERR_CODE: i64 = -32602;
-const RATE_LIMIT_ERR_CODE: i64 = -32603;
+pub(crate) const METHOD_NOT_AVAILABLE_ERR_CODE: i64 = -32601;
+pub(crate) const INVALID_PARAM_ERR_CODE: i64 = -32602;
+pub(crate) const RATE_LIMIT_ERR_CODE: i64 = -32603;
 
-type SendTransactionRateLimiter = Mutex<LruCache<u32, Instant>>;
+type SendTransactionRateLimiter = Mutex<Option<LruCache<u32, Instant>>>;
 
 fn rate_limit_err() -> RpcError {
     RpcError::Provided {
@@ -102,6 +135,13 @@ fn rate_limit_err() -> RpcError {
     }
 }
 
+fn safe_mode_err() -> RpcError {
+    RpcError::Provided {
+        code: SAFE_MODE_ERR_CODE,
+        message: "The node is in safe mode, state diverged from the chain",
+    }
+}
+
 fn header_not_found_err() -> RpcError {
     RpcError::Provided {
         code: HEADER_NOT_FOUND_ERR_CODE,
@@ -116,13 +156,36 @@ fn mem_pool_is_disabled_err() -> RpcError {
     }
 }
 
-fn invalid_param_err(msg: &'static str) -> RpcError {
+pub(crate) fn invalid_param_err(msg: &'static str) -> RpcError {
     RpcError::Provided {
         code: INVALID_PARAM_ERR_CODE,
         message: msg,
     }
 }
 
+fn execution_timeout_err() -> RpcError {
+    RpcError::Provided {
+        code: EXECUTION_TIMEOUT_ERR_CODE,
+        message: "execution timeout",
+    }
+}
+
+fn is_execution_timeout(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<TransactionError>(),
+        Some(TransactionError::ExecutionTimeout { .. })
+    )
+}
+
+/// Max cycles and timeout of read-only executions of the request.
+fn execution_limits(mem_pool_config: &MemPoolConfig) -> (u64, Option<Duration>) {
+    let limits = crate::access::execution_limits();
+    let max_cycles = limits
+        .max_cycles
+        .unwrap_or(mem_pool_config.execute_l2tx_max_cycles);
+    (max_cycles, limits.timeout_ms.map(Duration::from_millis))
+}
+
 #[async_trait]
 pub trait TestModeRPC {
     async fn get_global_state(&self) -> Result<GlobalState>;
@@ -161,9 +224,58 @@ pub struct SubmitTransactionContext {
     generator: Arc<Generator>,
     submit_tx: mpsc::Sender<(Request, RequestContext)>,
     mem_pool_state: Arc<MemPoolState>,
-    rate_limiter: Option<SendTransactionRateLimiter>,
-    rate_limit_config: Option<RPCRateLimit>,
+    /// Created on first use, `rpc_config.send_tx_rate_limit` is reloadable.
+    rate_limiter: SendTransactionRateLimiter,
+    dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
+    tx_relay: Option<Arc<TxRelay>>,
+}
+
+/// Relays transactions submitted to read-only nodes to the full node.
+struct TxRelay {
+    sender: mpsc::Sender<L2Transaction>,
+    /// Recently relayed transactions.
+    relayed: Mutex<LruCache<H256, ()>>,
+}
+
+impl TxRelay {
+    const RELAYED_LRU_SIZE: usize = 10000;
+
+    async fn relay(&self, tx: L2Transaction) -> Result<(), RpcError> {
+        let hash = tx_hash_in_queue(&tx);
+        let mut relayed = self.relayed.lock().await;
+        if relayed.contains(&hash) {
+            return Ok(());
+        }
+        let permit = self.sender.try_reserve().map_err(|err| match err {
+            mpsc::error::TrySendError::Closed(_) => RpcError::Provided {
+                code: INTERNAL_ERROR_ERR_CODE,
+                message: "internal error, unavailable",
+            },
+            mpsc::error::TrySendError::Full(_) => RpcError::Provided {
+                code: BUSY_ERR_CODE,
+                message: "tx relay busy",
+            },
+        })?;
+        relayed.put(hash, ());
+        permit.send(tx);
+        Ok(())
+    }
+}
+
+/// Hash of the tx in `InQueueRequestMap`. Txs from zero are identified by
+/// signatures because their from ids will be updated after account creation.
+fn tx_hash_in_queue(tx: &L2Transaction) -> H256 {
+    let sender_id: u32 = tx.raw().from_id().unpack();
+    if 0 != sender_id {
+        return tx.hash();
+    }
+    let mut hasher = new_blake2b();
+    let sig: Bytes = tx.signature().unpack();
+    hasher.update(&sig);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash
 }
 
 pub struct RegistryArgs<T> {
@@ -175,7 +287,6 @@ pub struct RegistryArgs<T> {
     pub mem_pool_config: MemPoolConfig,
     pub node_mode: NodeMode,
     pub rpc_client: RPCClient,
-    pub send_tx_rate_limit: Option<RPCRateLimit>,
     pub server_config: RPCServerConfig,
     pub chain_config: ChainConfig,
     pub consensus_config: ConsensusConfig,
@@ -183,6 +294,11 @@ pub struct RegistryArgs<T> {
     pub dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     pub polyjuice_sender_recover: PolyjuiceSenderRecover,
     pub debug_backend_forks: Option<Vec<BackendForkConfig>>,
+    /// Read-only nodes accept `gw_submit_l2transaction` and relay txs to the
+    /// full node with this.
+    pub tx_relay: Option<mpsc::Sender<L2Transaction>>,
+    /// Refreshed by `admin_refresh_contracts_deps`, `None` on read-only nodes.
+    pub contracts_dep_manager: Option<ContractsCellDepManager>,
 }
 
 pub struct Registry {
@@ -196,7 +312,6 @@ pub struct Registry {
     node_mode: NodeMode,
     submit_tx: mpsc::Sender<(Request, RequestContext)>,
     rpc_client: RPCClient,
-    send_tx_rate_limit: Option<RPCRateLimit>,
     server_config: RPCServerConfig,
     chain_config: ChainConfig,
     consensus_config: ConsensusConfig,
@@ -206,6 +321,11 @@ pub struct Registry {
     in_queue_request_map: Option<Arc<InQueueRequestMap>>,
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
     debug_backend_forks: Option<Vec<BackendForkConfig>>,
+    filter_manager: FilterManager,
+    subscriptions: Option<Subscriptions>,
+    submit_transaction_context: Arc<SubmitTransactionContext>,
+    response_cache: Arc<ResponseCache>,
+    contracts_dep_manager: Option<ContractsCellDepManager>,
 }
 
 impl Registry {
@@ -222,7 +342,6 @@ impl Registry {
             mem_pool_config,
             node_mode,
             rpc_client,
-            send_tx_rate_limit,
             server_config,
             chain_config,
             consensus_config,
@@ -230,20 +349,29 @@ impl Registry {
             polyjuice_sender_recover,
             debug_backend_forks,
             gasless_tx_support_config,
+            tx_relay,
+            contracts_dep_manager,
         } = args;
 
         let backend_info = get_backend_info(generator.clone());
 
-        let mem_pool_state = match mem_pool.as_ref() {
+        let (mem_pool_state, subscriptions) = match mem_pool.as_ref() {
             Some(pool) => {
                 let mem_pool = pool.lock().await;
-                mem_pool.mem_pool_state()
+                (
+                    mem_pool.mem_pool_state(),
+                    Some(mem_pool.subscriptions().clone()),
+                )
             }
-            None => Arc::new(MemPoolState::new(
-                MemStateDB::from_store(store.get_snapshot()).expect("mem state DB"),
-                true,
-            )),
+            None => (
+                Arc::new(MemPoolState::new(
+                    MemStateDB::from_store(store.get_snapshot()).expect("mem state DB"),
+                    true,
+                )),
+                None,
+            ),
         };
+        let filter_manager = FilterManager::new(store.clone(), subscriptions.clone());
         let in_queue_request_map = if matches!(node_mode, NodeMode::FullNode | NodeMode::Test) {
             Some(Arc::new(InQueueRequestMap::default()))
         } else {
@@ -263,9 +391,31 @@ impl Registry {
                 polyjuice_sender_recover: Arc::clone(&polyjuice_sender_recover),
                 mem_pool_config: mem_pool_config.clone(),
                 gasless_tx_support_config: gasless_tx_support_config.clone(),
+                in_queue_request_map: in_queue_request_map.clone(),
             };
             tokio::spawn(submitter.in_background());
         }
+        let submit_transaction_context = Arc::new(SubmitTransactionContext {
+            in_queue_request_map: in_queue_request_map.clone(),
+            submit_tx: submit_tx.clone(),
+            generator: generator.clone(),
+            mem_pool_state: mem_pool_state.clone(),
+            rate_limiter: Mutex::new(None),
+            dynamic_config_manager: dynamic_config_manager.clone(),
+            polyjuice_sender_recover: polyjuice_sender_recover.clone(),
+            tx_relay: tx_relay.map(|sender| {
+                Arc::new(TxRelay {
+                    sender,
+                    relayed: Mutex::new(LruCache::new(TxRelay::RELAYED_LRU_SIZE)),
+                })
+            }),
+        });
+
+        let response_cache = Arc::new(ResponseCache::new(server_config.response_cache.max_entries));
+        if let Some(subscriptions) = subscriptions.clone() {
+            let response_cache = Arc::clone(&response_cache);
+            tokio::spawn(async move { response_cache.invalidate_on_reorgs(subscriptions).await });
+        }
 
         Self {
             mem_pool,
@@ -279,7 +429,6 @@ impl Registry {
             node_mode,
             submit_tx,
             rpc_client,
-            send_tx_rate_limit,
             server_config,
             chain_config,
             consensus_config,
@@ -289,16 +438,97 @@ impl Registry {
             in_queue_request_map,
             polyjuice_sender_recover,
             debug_backend_forks,
+            filter_manager,
+            subscriptions,
+            submit_transaction_context,
+            response_cache,
+            contracts_dep_manager,
+        }
+    }
+
+    /// Submit transactions relayed by read-only nodes, see
+    /// `BlockSyncServerState::set_relay_sender`.
+    pub fn spawn_relay_receiver(&self, mut relay_rx: mpsc::Receiver<L2Transaction>) {
+        let in_queue_request_map = match self.in_queue_request_map {
+            Some(ref map) => Arc::clone(map),
+            None => return,
+        };
+        let submit_tx = self.submit_tx.clone();
+        tokio::spawn(async move {
+            while let Some(tx) = relay_rx.recv().await {
+                let permit = match submit_tx.reserve().await {
+                    Ok(permit) => permit,
+                    Err(_) => return,
+                };
+                let hash = tx_hash_in_queue(&tx);
+                let request = Request::Tx(tx);
+                // Skip txs already submitted to the full node or relayed by
+                // other read-only nodes.
+                if let Some(handle) = in_queue_request_map.insert(hash, request.clone()) {
+                    let in_queue_span = tracing::info_span!("submit_queue.send");
+                    let ctx = RequestContext {
+                        _in_queue_handle: handle,
+                        trace: gw_telemetry::current_context(),
+                        in_queue_span,
+                    };
+                    permit.send((request, ctx));
+                }
+            }
+        });
+    }
+
+    /// gRPC service, submissions share the queue and rate limits with
+    /// JSON-RPC.
+    pub fn build_grpc_service(&self) -> GrpcService {
+        let read_only = self.node_mode == NodeMode::ReadOnly;
+        let relay_transactions = self.submit_transaction_context.tx_relay.is_some();
+        GrpcService {
+            store: self.store.clone(),
+            generator: self.generator.clone(),
+            mem_pool_state: self.mem_pool_state.clone(),
+            subscriptions: self.subscriptions.clone(),
+            submit_transaction_context: (!read_only || relay_transactions)
+                .then(|| self.submit_transaction_context.clone()),
+            submit_withdrawal: (!read_only)
+                .then(|| (self.in_queue_request_map.clone(), self.submit_tx.clone())),
+        }
+    }
+
+    pub fn dynamic_config_manager(&self) -> &Arc<ArcSwap<DynamicConfigManager>> {
+        &self.dynamic_config_manager
+    }
+
+    pub fn server_config(&self) -> &RPCServerConfig {
+        &self.server_config
+    }
+
+    fn admin_context(&self) -> AdminContext {
+        AdminContext {
+            mem_pool: self.mem_pool.clone(),
+            in_queue_request_map: self.in_queue_request_map.clone(),
+            dynamic_config_manager: self.dynamic_config_manager.clone(),
+            contracts_dep_manager: self.contracts_dep_manager.clone(),
+            mem_pool_state: self.mem_pool_state.clone(),
+            store: self.store.clone(),
+            rollup_type_hash: to_jsonh256(self.generator.rollup_context().rollup_script_hash),
         }
     }
 
+    /// Server of `admin_*` methods only, for `rpc_server.admin.listen`.
+    pub fn build_admin_rpc_server(&self) -> Result<RPCServer> {
+        let methods = openrpc::admin_namespace_methods();
+        let server = with_admin_methods(JsonrpcServer::new(), self.admin_context())
+            .with_data(Data::new(OpenRpcDocument::new(&methods)?))
+            .with_method("rpc.discover", openrpc::discover);
+        Ok(server.finish())
+    }
+
     pub fn build_rpc_server(self) -> Result<RPCServer> {
+        let relay_transactions = self.submit_transaction_context.tx_relay.is_some();
         let mut server = JsonrpcServer::new();
-
-        let send_transaction_rate_limiter: Option<SendTransactionRateLimiter> = self
-            .send_tx_rate_limit
-            .as_ref()
-            .map(|send_tx_rate_limit| Mutex::new(lru::LruCache::new(send_tx_rate_limit.lru_size)));
+        let mut methods = openrpc::core_methods();
+        let syscall_cycles = self.mem_pool_config.mem_block.syscall_cycles.clone();
+        let admin_context = self.admin_context();
 
         server = server
             .with_data(Data::new(ExecutionTransactionContext {
@@ -309,15 +539,7 @@ impl Registry {
                 polyjuice_sender_recover: self.polyjuice_sender_recover.clone(),
                 mem_pool_config: self.mem_pool_config.clone(),
             }))
-            .with_data(Data::new(SubmitTransactionContext {
-                in_queue_request_map: self.in_queue_request_map.clone(),
-                submit_tx: self.submit_tx.clone(),
-                generator: self.generator.clone(),
-                mem_pool_state: self.mem_pool_state.clone(),
-                rate_limiter: send_transaction_rate_limiter,
-                rate_limit_config: self.send_tx_rate_limit,
-                polyjuice_sender_recover: self.polyjuice_sender_recover.clone(),
-            }))
+            .with_data(Data(self.submit_transaction_context))
             .with_data(Data::new(self.mem_pool.clone()))
             .with_data(Data(self.generator.clone()))
             .with_data(Data::new(self.store.clone()))
@@ -333,6 +555,10 @@ impl Registry {
             .with_data(Data::new(self.in_queue_request_map))
             .with_data(Data::new(self.submit_tx))
             .with_data(Data::new(self.gasless_tx_support_config))
+            .with_data(Data::new(self.filter_manager))
+            .with_data(Data::new(SyncTracker::default()))
+            .with_data(Data::new(SUDTTokens::new(&self.server_config.sudt_tokens)))
+            .with_data(Data(self.response_cache))
             .with_method("gw_ping", ping)
             .with_method("gw_get_tip_block_hash", get_tip_block_hash)
             .with_method("gw_get_block_hash", get_block_hash)
@@ -340,14 +566,34 @@ impl Registry {
             .with_method("gw_get_block_by_number", get_block_by_number)
             .with_method("gw_get_block_committed_info", get_block_committed_info)
             .with_method("gw_get_balance", get_balance)
+            .with_method("gw_get_balances", get_balances)
+            .with_method("gw_get_balances_bulk", get_balances_bulk)
+            .with_method("gw_get_sudt_balances", get_sudt_balances)
+            .with_method("gw_list_sudt_tokens", list_sudt_tokens)
+            .with_method("gw_get_sudt_proxy_addresses", get_sudt_proxy_addresses)
+            .with_method(
+                "gw_get_sudt_id_by_proxy_address",
+                get_sudt_id_by_proxy_address,
+            )
             .with_method("gw_get_storage_at", get_storage_at)
+            .with_method("gw_get_account_proof", get_account_proof)
+            .with_method("gw_get_storage_proof", get_storage_proof)
+            .with_method("gw_get_block_proof", get_block_proof)
+            .with_method("gw_fee_history", fee_history)
+            .with_method("gw_get_block_cycles_report", get_block_cycles_report)
+            .with_method("gw_get_block_state_diff", get_block_state_diff)
+            .with_method("gw_list_account_transactions", list_account_transactions)
+            .with_method("gw_list_sudt_transfers", list_sudt_transfers)
             .with_method(
                 "gw_get_account_id_by_script_hash",
                 get_account_id_by_script_hash,
             )
             .with_method("gw_get_nonce", get_nonce)
+            .with_method("gw_get_pending_nonce", get_pending_nonce)
             .with_method("gw_get_script", get_script)
             .with_method("gw_get_script_hash", get_script_hash)
+            .with_method("gw_list_accounts", list_accounts)
+            .with_method("gw_list_reorgs", list_reorgs)
             .with_method(
                 "gw_get_script_hash_by_registry_address",
                 get_script_hash_by_registry_address,
@@ -356,13 +602,23 @@ impl Registry {
                 "gw_get_registry_address_by_script_hash",
                 get_registry_address_by_script_hash,
             )
+            .with_method(
+                "gw_get_registry_addresses_by_script_hash",
+                get_registry_addresses_by_script_hash,
+            )
             .with_method("gw_get_data", get_data)
             .with_method("gw_get_transaction", get_transaction)
             .with_method("gw_get_transaction_receipt", get_transaction_receipt)
             .with_method("gw_get_withdrawal", get_withdrawal)
+            .with_method("gw_get_withdrawal_status", get_withdrawal_status)
+            .with_method("gw_get_withdrawal_queue", get_withdrawal_queue)
+            .with_method("gw_get_deferred_withdrawals", get_deferred_withdrawals)
+            .with_method("gw_get_withdrawable_capacity", get_withdrawable_capacity)
+            .with_method("gw_get_deposit_status", get_deposit_status)
             .with_method("gw_get_pending_tx_hashes", get_pending_tx_hashes)
             .with_method("gw_execute_l2transaction", execute_l2transaction)
             .with_method("gw_execute_raw_l2transaction", execute_raw_l2transaction)
+            .with_method("gw_batch_execute", batch_execute)
             .with_method(
                 "gw_compute_l2_sudt_script_hash",
                 compute_l2_sudt_script_hash,
@@ -371,14 +627,37 @@ impl Registry {
             .with_method("gw_get_mem_pool_state_root", get_mem_pool_state_root)
             .with_method("gw_get_mem_pool_state_ready", get_mem_pool_state_ready)
             .with_method("gw_get_node_info", get_node_info)
-            .with_method("gw_reload_config", reload_config)
-            .with_method("gw_get_last_submitted_info", get_last_submitted_info);
+            .with_method("gw_get_fork_schedule", get_fork_schedule)
+            .with_method("gw_get_config_changelog", get_config_changelog)
+            .with_method("gw_get_last_submitted_info", get_last_submitted_info)
+            .with_method("gw_syncing", syncing)
+            .with_method("gw_get_logs", get_logs)
+            .with_method("gw_new_filter", new_filter)
+            .with_method("gw_new_block_filter", new_block_filter)
+            .with_method(
+                "gw_new_pending_transaction_filter",
+                new_pending_transaction_filter,
+            )
+            .with_method("gw_uninstall_filter", uninstall_filter)
+            .with_method("gw_get_filter_changes", get_filter_changes)
+            .with_method("gw_get_filter_logs", get_filter_logs);
 
         if self.node_mode != NodeMode::ReadOnly {
             server = server
                 .with_method("gw_submit_l2transaction", submit_l2transaction)
                 .with_method("gw_submit_withdrawal_request", submit_withdrawal_request)
                 .with_method("gw_is_request_in_queue", is_request_in_queue);
+            methods.extend(openrpc::submit_transaction_methods());
+            methods.extend(openrpc::full_node_methods());
+        } else if relay_transactions {
+            server = server.with_method("gw_submit_l2transaction", submit_l2transaction);
+            methods.extend(openrpc::submit_transaction_methods());
+        }
+
+        // Admin methods are served on `listen` only with the auth token.
+        if self.server_config.admin.auth_token.is_some() {
+            server = with_admin_methods(server, admin_context);
+            methods.extend(openrpc::admin_namespace_methods());
         }
 
         // Tests
@@ -387,6 +666,7 @@ impl Registry {
                 .with_data(Data(Arc::clone(&tests_rpc_impl)))
                 .with_method("tests_produce_block", tests_produce_block)
                 .with_method("tests_get_global_state", tests_get_global_state);
+            methods.extend(openrpc::tests_methods());
         }
 
         for enabled in self.server_config.enable_methods.iter() {
@@ -395,12 +675,20 @@ impl Registry {
                     server = server
                         .with_method("gw_start_profiler", start_profiler)
                         .with_method("gw_report_pprof", report_pprof);
+                    methods.extend(openrpc::pprof_methods());
                 }
                 RPCMethods::Test => {
                     server = server
                         // .with_method("gw_dump_mem_block", dump_mem_block)
                         .with_method("gw_get_rocksdb_mem_stats", get_rocksdb_memory_stats)
-                        .with_method("gw_dump_jemalloc_profiling", dump_jemalloc_profiling)
+                        .with_method("gw_dump_jemalloc_profiling", dump_jemalloc_profiling);
+                    methods.extend(openrpc::test_methods());
+                }
+                RPCMethods::Admin => {
+                    server = server
+                        .with_method("gw_get_ckb_endpoints", get_ckb_endpoints)
+                        .with_method("gw_get_new_address_queue", get_new_address_queue);
+                    methods.extend(openrpc::admin_methods());
                 }
                 RPCMethods::Debug => {
                     let debug_generator = match self.debug_backend_forks.clone() {
@@ -418,12 +706,20 @@ impl Registry {
                             store: self.store.clone(),
                             generator: self.generator.clone(),
                             debug_generator,
+                            syscall_cycles: syscall_cycles.clone(),
                         }))
                         .with_method("debug_replay_transaction", replay_transaction)
+                        .with_method("gw_trace_transaction", trace_transaction)
+                        .with_method("gw_debug_l2transaction", debug_l2transaction);
+                    methods.extend(openrpc::debug_methods());
                 }
             }
         }
 
+        server = server
+            .with_data(Data::new(OpenRpcDocument::new(&methods)?))
+            .with_method("rpc.discover", openrpc::discover);
+
         Ok(server.finish())
     }
 }
@@ -461,6 +757,7 @@ struct RequestSubmitter {
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
     mem_pool_config: MemPoolConfig,
     gasless_tx_support_config: Option<GaslessTxSupportConfig>,
+    in_queue_request_map: Option<Arc<InQueueRequestMap>>,
 }
 
 #[instrument(skip_all, fields(req_kind = req.kind()))]
@@ -470,24 +767,17 @@ fn req_to_entry(
     generator: Arc<Generator>,
     req: Request,
     state: &(impl State + CodeStore),
+    block_number: u64,
     order: usize,
 ) -> Result<FeeEntry> {
     match req {
         Request::Tx(tx) => {
             let receiver: u32 = tx.raw().to_id().unpack();
             let script_hash = state.get_script_hash(receiver)?;
-            let backend_type = generator
-                .load_backend_and_block_consensus(0, state, &script_hash)
-                .ok_or_else(|| anyhow!("can't find backend for receiver: {}", receiver))?
-                .0
-                .backend_type;
-            FeeEntry::from_tx(
-                tx,
-                gasless_tx_support_config,
-                fee_config,
-                backend_type,
-                order,
-            )
+            let (backend, _) = generator
+                .load_backend_and_block_consensus(block_number, state, &script_hash)
+                .ok_or_else(|| anyhow!("can't find backend for receiver: {}", receiver))?;
+            FeeEntry::from_tx(tx, gasless_tx_support_config, fee_config, backend, order)
         }
         Request::Withdrawal(withdraw) => {
             let script_hash = withdraw.raw().account_script_hash().unpack();
@@ -509,6 +799,14 @@ impl RequestSubmitter {
     const MAX_BATCH_SIZE: usize = 20;
     const INTERVAL_MS: Duration = Duration::from_millis(100);
 
+    /// Number of the mem block, backends of txs are loaded at this height.
+    fn mem_block_number(&self) -> u64 {
+        self.mem_pool_state
+            .get_mem_pool_block_info()
+            .map(|info| info.number().unpack())
+            .unwrap_or_default()
+    }
+
     async fn in_background(mut self) {
         // First mem pool reinject txs
         {
@@ -589,6 +887,7 @@ impl RequestSubmitter {
                 let _entered = ctx.in_queue_span.clone().entered();
 
                 let state = self.mem_pool_state.load_state_db();
+                let block_number = self.mem_block_number();
 
                 let kind = req.kind();
                 let hash = req.hash();
@@ -600,6 +899,7 @@ impl RequestSubmitter {
                     self.generator.clone(),
                     req,
                     &state,
+                    block_number,
                     queue.len(),
                 ) {
                     Ok(entry) => {
@@ -626,6 +926,7 @@ impl RequestSubmitter {
 
             // push txs to fee priority queue
             let state = self.mem_pool_state.load_state_db();
+            let block_number = self.mem_block_number();
             while let Ok((req, mut ctx)) = self.submit_rx.try_recv() {
                 gw_telemetry::with_span_ref(&ctx.in_queue_span, |span| span.end());
                 ctx.in_queue_span = ctx.trace.new_span(tracing::info_span!("fee_queue.add"));
@@ -641,6 +942,7 @@ impl RequestSubmitter {
                     self.generator.clone(),
                     req,
                     &state,
+                    block_number,
                     queue.len(),
                 ) {
                     Ok(entry) => {
@@ -724,11 +1026,33 @@ impl RequestSubmitter {
                 let state = self.mem_pool_state.load_state_db();
                 let mut block_cycles_limit_reached = false;
 
+                // check signatures in parallel, txs from id zero are changed
+                // before pushing.
+                let txs: Vec<_> = { items.iter() }
+                    .filter_map(|(entry, _ctx)| match entry.item {
+                        FeeItem::Tx(ref tx)
+                            if !matches!(entry.sender, FeeItemSender::PendingCreate(_)) =>
+                        {
+                            Some(tx.clone())
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                let checked = mem_pool.check_signatures(&txs);
+
                 for (entry, ctx) in items {
                     gw_telemetry::with_span_ref(&ctx.in_queue_span, |span| span.end());
                     let push_span = ctx.new_span(|_| tracing::info_span!("mem_pool.push"));
                     let _entered = push_span.enter();
 
+                    if let Some(ref map) = self.in_queue_request_map {
+                        let hash = entry.item.hash();
+                        if map.is_evicted(&hash) {
+                            log::info!("drop evicted {:?} {}", entry.item.kind(), hash.pack());
+                            continue;
+                        }
+                    }
+
                     if let FeeItemKind::Tx = entry.item.kind() {
                         if !block_cycles_limit_reached
                             && entry.cycles_limit > mem_pool.cycles_pool().available_cycles()
@@ -770,7 +1094,7 @@ impl RequestSubmitter {
 
                             mem_pool.push_transaction(tx)
                         }
-                        FeeItem::Tx(tx) => mem_pool.push_transaction(tx),
+                        FeeItem::Tx(tx) => mem_pool.push_checked_transaction(tx, &checked),
                         FeeItem::Withdrawal(withdrawal) => {
                             mem_pool.push_withdrawal_request(withdrawal).await
                         }
@@ -803,85 +1127,43 @@ impl RequestSubmitter {
     }
 }
 
-async fn ping() -> Result<String> {
+pub(crate) async fn ping() -> Result<String> {
     Ok("pong".to_string())
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-#[serde(untagged)]
-enum GetTxParams {
-    Default((JsonH256,)),
-    WithVerbose((JsonH256, u8)),
-}
-
-enum GetTxVerbose {
-    TxWithStatus = 0,
-    OnlyStatus = 1,
-}
-
-impl TryFrom<u8> for GetTxVerbose {
-    type Error = u8;
-    fn try_from(n: u8) -> Result<Self, u8> {
-        let verbose = match n {
-            0 => Self::TxWithStatus,
-            1 => Self::OnlyStatus,
-            _ => {
-                return Err(n);
-            }
-        };
-        Ok(verbose)
-    }
-}
-
 #[instrument(skip_all)]
 async fn get_transaction(
     Params(param): Params<GetTxParams>,
     store: Data<Store>,
     in_queue_request_map: Data<Option<Arc<InQueueRequestMap>>>,
 ) -> Result<Option<L2TransactionWithStatus>, RpcError> {
-    let (tx_hash, verbose) = match param {
-        GetTxParams::Default((tx_hash,)) => (to_h256(tx_hash), GetTxVerbose::TxWithStatus),
-        GetTxParams::WithVerbose((tx_hash, verbose)) => {
-            let verbose = verbose
-                .try_into()
-                .map_err(|_err| invalid_param_err("invalid verbose param"))?;
-            (to_h256(tx_hash), verbose)
-        }
-    };
+    let (tx_hash, verbose) = param.into_parts()?;
 
     if let Some(tx) = in_queue_request_map
         .as_deref()
         .and_then(|m| m.get_transaction(&tx_hash))
     {
         return Ok(Some(L2TransactionWithStatus {
-            transaction: matches!(verbose, GetTxVerbose::TxWithStatus).then(|| tx.into()),
+            transaction: (!matches!(verbose, GetTxVerbose::OnlyStatus)).then(|| tx.into()),
             status: L2TransactionStatus::Pending,
+            proof: None,
         }));
     }
     let db = store.get_snapshot();
-    let tx_opt;
-    let status;
-    match db.get_transaction_info(&tx_hash)? {
-        Some(tx_info) => {
-            tx_opt = db.get_transaction_by_key(&tx_info.key())?;
-            status = L2TransactionStatus::Committed;
-        }
-        None => {
-            tx_opt = db.get_mem_pool_transaction(&tx_hash)?;
-            status = L2TransactionStatus::Pending;
-        }
-    };
+    let is_committed = db.get_transaction_info(&tx_hash)?.is_some();
+    if is_committed {
+        return Ok(query::committed_transaction(&db, &tx_hash, verbose)?);
+    }
 
-    Ok(tx_opt.map(|tx| match verbose {
-        GetTxVerbose::OnlyStatus => L2TransactionWithStatus {
-            transaction: None,
-            status,
-        },
-        GetTxVerbose::TxWithStatus => L2TransactionWithStatus {
-            transaction: Some(tx.into()),
-            status,
-        },
-    }))
+    // Txs in the mem pool have no proof.
+    let tx_with_status = db
+        .get_mem_pool_transaction(&tx_hash)?
+        .map(|tx| L2TransactionWithStatus {
+            transaction: (!matches!(verbose, GetTxVerbose::OnlyStatus)).then(|| tx.into()),
+            status: L2TransactionStatus::Pending,
+            proof: None,
+        });
+    Ok(tx_with_status)
 }
 
 #[instrument(skip_all)]
@@ -946,40 +1228,24 @@ async fn get_block(
     Params((block_hash,)): Params<(JsonH256,)>,
     store: Data<Store>,
     rollup_config: Data<RollupConfig>,
+    response_cache: Data<ResponseCache>,
 ) -> Result<Option<L2BlockWithStatus>> {
     let block_hash = to_h256(block_hash);
-    let mut db = store.begin_transaction();
-    let block = match db.get_block(&block_hash)? {
+    let cache_key = CacheKey::Block(block_hash);
+    if let Some(CachedResponse::Block(block)) = response_cache.get(&store, &cache_key) {
+        return Ok(Some(block));
+    }
+    let db = store.get_snapshot();
+    let block = match query::block_with_status(&db, &rollup_config, block_hash)? {
         Some(block) => block,
         None => return Ok(None),
     };
-
-    // check block status
-    let mut status = L2BlockStatus::Unfinalized;
-    if !db.reverted_block_smt()?.get(&block_hash.into())?.is_zero() {
-        // block is reverted
-        status = L2BlockStatus::Reverted;
-    } else {
-        // return None if block is not on the main chain
-        if H256::from(db.block_smt()?.get(&block.smt_key().into())?) != block_hash {
-            return Ok(None);
-        }
-
-        // block is on main chain
-        let last_confirmed_block_number = db
-            .get_last_confirmed_block_number_hash()
-            .map(|nh| nh.number().unpack())
-            .unwrap_or(0);
-        let block_number = block.raw().number().unpack();
-        if last_confirmed_block_number >= block_number + rollup_config.finality_blocks().unpack() {
-            status = L2BlockStatus::Finalized;
-        }
+    if block.status == L2BlockStatus::Finalized {
+        let block_number = block.block.raw.number.value();
+        let response = CachedResponse::Block(block.clone());
+        response_cache.insert(cache_key, block_number, block_hash, response);
     }
-
-    Ok(Some(L2BlockWithStatus {
-        block: block.into(),
-        status,
-    }))
+    Ok(Some(block))
 }
 
 // Why do we read from `MemPoolState` instead of `Store` for these “get block”
@@ -995,20 +1261,12 @@ async fn get_block(
 // get an error response when getting scripts for accounts in the new block.
 #[instrument(skip_all)]
 async fn get_block_by_number(
-    Params((block_number,)): Params<(gw_jsonrpc_types::ckb_jsonrpc_types::Uint64,)>,
+    Params(params): Params<GetBlockByNumberParams>,
     mem_pool_state: Data<Arc<MemPoolState>>,
-) -> Result<Option<L2BlockView>> {
-    let block_number = block_number.value();
+) -> Result<Option<BlockByNumberView>> {
+    let (block_number, full) = params.into_parts();
     let mem_store = mem_pool_state.load_mem_store();
-    let block_hash = match mem_store.get_block_hash_by_number(block_number)? {
-        Some(hash) => hash,
-        None => return Ok(None),
-    };
-    let block_opt = mem_store.get_block(&block_hash)?.map(|block| {
-        let block_view: L2BlockView = block.into();
-        block_view
-    });
-    Ok(block_opt)
+    query::block_by_number(&mem_store, block_number, full)
 }
 
 #[instrument(skip_all)]
@@ -1035,20 +1293,37 @@ async fn get_tip_block_hash(mem_pool_state: Data<Arc<MemPoolState>>) -> Result<J
 async fn get_transaction_receipt(
     Params((tx_hash,)): Params<(JsonH256,)>,
     store: Data<Store>,
+    sudt_tokens: Data<SUDTTokens>,
+    rollup_config: Data<RollupConfig>,
+    response_cache: Data<ResponseCache>,
 ) -> Result<Option<TxReceipt>> {
     let tx_hash = to_h256(tx_hash);
+    let cache_key = CacheKey::TransactionReceipt(tx_hash);
+    if let Some(CachedResponse::TransactionReceipt(receipt)) =
+        response_cache.get(&store, &cache_key)
+    {
+        return Ok(Some(receipt));
+    }
     let db = store.get_snapshot();
-    // search from db
-    if let Some(receipt) = db.get_transaction_receipt(&tx_hash)?.map(|receipt| {
-        let receipt: TxReceipt = receipt.into();
+    // search from db, then from mem pool
+    let (receipt, tx_info) = match db.get_transaction_receipt(&tx_hash)? {
+        Some(receipt) => (Some(receipt), db.get_transaction_info(&tx_hash)?),
+        None => (db.get_mem_pool_transaction_receipt(&tx_hash)?, None),
+    };
+    let receipt = receipt.map(|receipt| {
+        let mut receipt: TxReceipt = receipt.into();
+        sudt_tokens.fill_receipt(&db, &mut receipt);
         receipt
-    }) {
-        return Ok(Some(receipt));
+    });
+    if let (Some(receipt), Some(tx_info)) = (receipt.as_ref(), tx_info) {
+        let block_number = tx_info.block_number().unpack();
+        if is_finalized(&db, &rollup_config, block_number) {
+            let block_hash = tx_info.key().block_hash();
+            let response = CachedResponse::TransactionReceipt(receipt.clone());
+            response_cache.insert(cache_key, block_number, block_hash, response);
+        }
     }
-    // search from mem pool
-    Ok(db
-        .get_mem_pool_transaction_receipt(&tx_hash)?
-        .map(Into::into))
+    Ok(receipt)
 }
 
 #[instrument(skip_all, err(Debug))]
@@ -1123,6 +1398,7 @@ async fn execute_l2transaction(
         }
     }
 
+    let (max_cycles, timeout) = execution_limits(&ctx.mem_pool_config);
     let execution_span = tracing::info_span!("execution");
     let mut run_result = tokio::task::spawn_blocking(move || {
         let _entered = execution_span.entered();
@@ -1157,22 +1433,28 @@ async fn execute_l2transaction(
         ctx.generator.check_transaction_signature(&state, &tx)?;
         // execute tx
         let raw_tx = tx.raw();
-        let run_result = ctx.generator.execute_transaction(
+        let run_result = ctx.generator.execute_transaction_with_timeout(
             &chain_view,
             &mut state,
             &block_info,
             &raw_tx,
-            Some(ctx.mem_pool_config.execute_l2tx_max_cycles),
+            Some(max_cycles),
             Some(&mut cycles_pool),
+            timeout,
         )?;
 
         Result::<_, anyhow::Error>::Ok(run_result)
     })
     .await?
-    .map_err(|err| RpcError::Full {
-        code: INVALID_REQUEST,
-        message: err.to_string(),
-        data: None,
+    .map_err(|err| {
+        if is_execution_timeout(&err) {
+            return execution_timeout_err();
+        }
+        RpcError::Full {
+            code: INVALID_REQUEST,
+            message: err.to_string(),
+            data: None,
+        }
     })?;
     gw_metrics::rpc()
         .execute_transactions(run_result.exit_code)
@@ -1197,13 +1479,75 @@ async fn execute_l2transaction(
     Ok(run_result.into())
 }
 
-// raw_l2tx, block_number
+type StateOverrides = HashMap<JsonH256, AccountStateOverride>;
+
+// raw_l2tx, block_number, registry_address, state_overrides
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 enum ExecuteRawL2TransactionParams {
     Tip((JsonBytes,)),
     Number((JsonBytes, Option<GwUint64>)),
     PolyjuiceFromIdZero((JsonBytes, Option<GwUint64>, RegistryAddressJsonBytes)),
+    StateOverrides(
+        (
+            JsonBytes,
+            Option<GwUint64>,
+            Option<RegistryAddressJsonBytes>,
+            StateOverrides,
+        ),
+    ),
+}
+
+/// Key of the data hash of polyjuice contract code.
+fn build_contract_code_key(account_id: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key[0..4].copy_from_slice(&account_id.to_le_bytes());
+    key[4] = 0xFF;
+    key[5] = 0x01;
+    key
+}
+
+/// Apply overrides of accounts by script hash, the accounts must exist.
+fn apply_state_overrides(
+    state: &mut (impl State + CodeStore),
+    overrides: &StateOverrides,
+) -> Result<()> {
+    for (json_script_hash, account) in overrides {
+        let script_hash = to_h256(json_script_hash.to_owned());
+        let account_id = state
+            .get_account_id_by_script_hash(&script_hash)?
+            .ok_or_else(|| anyhow!("account {:#x} not found", json_script_hash))?;
+
+        if let Some(ref balance) = account.balance {
+            let address = state
+                .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)?
+                .ok_or_else(|| anyhow!("account {} has no eth address", account_id))?;
+            let current = state.get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &address)?;
+            if *balance > current {
+                state.mint_sudt(CKB_SUDT_ACCOUNT_ID, &address, *balance - current)?;
+            } else {
+                state.burn_sudt(CKB_SUDT_ACCOUNT_ID, &address, current - *balance)?;
+            }
+        }
+        if let Some(nonce) = account.nonce {
+            state.set_nonce(account_id, nonce.value())?;
+        }
+        for kv in account.state_diff.iter().flatten() {
+            state.update_value(account_id, kv.k.as_bytes(), to_h256(kv.v.clone()))?;
+        }
+        if let Some(ref code) = account.code {
+            let code = code.clone().into_bytes();
+            let mut code_hash = [0u8; 32];
+            let mut hasher = new_blake2b();
+            hasher.update(&code);
+            hasher.finalize(&mut code_hash);
+            let data_hash = build_account_key(account_id, &code_hash);
+            state.update_value(account_id, &build_contract_code_key(account_id), data_hash)?;
+            state.store_data_hash(data_hash)?;
+            state.insert_data(data_hash, code);
+        }
+    }
+    Ok(())
 }
 
 #[instrument(skip_all)]
@@ -1212,10 +1556,13 @@ async fn execute_raw_l2transaction(
     mem_pool_config: Data<MemPoolConfig>,
     ctx: Data<ExecutionTransactionContext>,
 ) -> Result<RunResult, RpcError> {
-    let (raw_l2tx, block_number_opt, registry_address_opt) = match params {
-        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None),
-        ExecuteRawL2TransactionParams::Number(p) => (p.0, p.1, None),
-        ExecuteRawL2TransactionParams::PolyjuiceFromIdZero(p) => (p.0, p.1, Some(p.2)),
+    let (raw_l2tx, block_number_opt, registry_address_opt, state_overrides) = match params {
+        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None, StateOverrides::new()),
+        ExecuteRawL2TransactionParams::Number(p) => (p.0, p.1, None, StateOverrides::new()),
+        ExecuteRawL2TransactionParams::PolyjuiceFromIdZero(p) => {
+            (p.0, p.1, Some(p.2), StateOverrides::new())
+        }
+        ExecuteRawL2TransactionParams::StateOverrides(p) => p,
     };
     let block_number_opt = block_number_opt.map(|n| n.value());
     let registry_address_opt = registry_address_opt
@@ -1257,7 +1604,7 @@ async fn execute_raw_l2transaction(
             .expect("get mem pool block info"),
     };
 
-    let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
+    let (execute_l2tx_max_cycles, timeout) = execution_limits(&mem_pool_config);
     let tx_hash: H256 = raw_l2tx.hash();
     let block_number: u64 = block_info.number().unpack();
     let mut cycles_pool = CyclesPool::new(
@@ -1266,9 +1613,11 @@ async fn execute_raw_l2transaction(
     );
 
     // check sender's balance
-    // NOTE: for tx from id zero, its balance will be verified after mock account
+    // NOTE: for tx from id zero or with state overrides, its balance will be
+    // verified after mock account and overrides
     let from_id: u32 = raw_l2tx.from_id().unpack();
-    if 0 != from_id {
+    let verify_balance_later = 0 == from_id || !state_overrides.is_empty();
+    if !verify_balance_later {
         let check_balance_result = match block_number_opt {
             Some(block_number) => {
                 let state =
@@ -1306,50 +1655,60 @@ async fn execute_raw_l2transaction(
             Some(block_number) => {
                 let mut state =
                     BlockStateDB::from_store(&mut db_txn, RWConfig::history_block(block_number))?;
+                apply_state_overrides(&mut state, &state_overrides)?;
                 let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
                     raw_l2tx,
                     registry_address_opt,
                     &mut state,
                 )?;
-                if 0 == from_id {
+                if verify_balance_later {
                     verify_sender_balance(rollup_context, &state, &raw_l2tx)
                         .map_err(|err| anyhow!("check balance err {}", err))?;
                 }
 
-                ctx.generator.execute_transaction(
+                ctx.generator.execute_transaction_with_timeout(
                     &chain_view,
                     &mut state,
                     &block_info,
                     &raw_l2tx,
                     Some(execute_l2tx_max_cycles),
                     Some(&mut cycles_pool),
+                    timeout,
                 )?
             }
             None => {
                 let mut state = ctx.mem_pool_state.load_state_db();
+                apply_state_overrides(&mut state, &state_overrides)?;
                 let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
                     raw_l2tx,
                     registry_address_opt,
                     &mut state,
                 )?;
-                if 0 == from_id {
+                if verify_balance_later {
                     verify_sender_balance(rollup_context, &state, &raw_l2tx)
                         .map_err(|err| anyhow!("check balance err {}", err))?;
                 }
 
-                ctx.generator.execute_transaction(
+                ctx.generator.execute_transaction_with_timeout(
                     &chain_view,
                     &mut state,
                     &block_info,
                     &raw_l2tx,
                     Some(execute_l2tx_max_cycles),
                     Some(&mut cycles_pool),
+                    timeout,
                 )?
             }
         };
         Result::<_, anyhow::Error>::Ok(run_result)
     })
-    .await??;
+    .await?
+    .map_err(|err| {
+        if is_execution_timeout(&err) {
+            return execution_timeout_err();
+        }
+        RpcError::from(err)
+    })?;
     gw_metrics::rpc()
         .execute_transactions(run_result.exit_code)
         .inc();
@@ -1373,15 +1732,129 @@ async fn execute_raw_l2transaction(
     Ok(run_result.into())
 }
 
+const MAX_BATCH_EXECUTE_TXS: usize = 32;
+
+// raw_l2txs, registry_address
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum BatchExecuteParams {
+    Txs((Vec<JsonBytes>,)),
+    PolyjuiceFromIdZero((Vec<JsonBytes>, Option<RegistryAddressJsonBytes>)),
+}
+
+/// Execute raw txs in order on a copy of the mem pool state, later txs see
+/// the state changes of earlier ones. Nothing is committed.
+#[instrument(skip_all)]
+async fn batch_execute(
+    Params(params): Params<BatchExecuteParams>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<Vec<BatchRunResult>, RpcError> {
+    let (raw_l2txs, registry_address_opt) = match params {
+        BatchExecuteParams::Txs(p) => (p.0, None),
+        BatchExecuteParams::PolyjuiceFromIdZero(p) => p,
+    };
+    if raw_l2txs.len() > MAX_BATCH_EXECUTE_TXS {
+        return Err(invalid_param_err("Too many txs"));
+    }
+    let registry_address_opt = registry_address_opt
+        .map(|json_bytes| {
+            gw_common::registry_address::RegistryAddress::from_slice(json_bytes.as_bytes())
+                .ok_or_else(|| invalid_param_err("Invalid registry address"))
+        })
+        .transpose()?;
+    let raw_l2txs = raw_l2txs
+        .into_iter()
+        .map(|tx| packed::RawL2Transaction::from_slice(tx.as_bytes()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let block_info = ctx
+        .mem_pool_state
+        .get_mem_pool_block_info()
+        .expect("get mem pool block info");
+
+    let (max_cycles, timeout) = execution_limits(&ctx.mem_pool_config);
+    let execution_span = tracing::info_span!("execution");
+    let run_results = tokio::task::spawn_blocking(move || {
+        let _entered = execution_span.entered();
+
+        let eth_recover = &ctx.polyjuice_sender_recover.eth;
+        let rollup_context = ctx.generator.rollup_context();
+        let snap = ctx.store.get_snapshot();
+        let chain_view = {
+            let tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+            ChainView::new(&snap, tip_block_hash)
+        };
+        let mut state = ctx.mem_pool_state.load_state_db();
+        let mut cycles_pool = CyclesPool::new(
+            ctx.mem_pool_config.mem_block.max_cycles_limit,
+            ctx.mem_pool_config.mem_block.syscall_cycles.clone(),
+        );
+
+        let mut run_results = Vec::with_capacity(raw_l2txs.len());
+        for (index, raw_l2tx) in raw_l2txs.into_iter().enumerate() {
+            let tx_hash: H256 = raw_l2tx.hash();
+            let run_result = (|| -> Result<_> {
+                let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
+                    raw_l2tx,
+                    registry_address_opt.clone(),
+                    &mut state,
+                )?;
+                verify_sender_balance(rollup_context, &state, &raw_l2tx)
+                    .map_err(|err| anyhow!("check balance err {}", err))?;
+                ctx.generator.execute_transaction_with_timeout(
+                    &chain_view,
+                    &mut state,
+                    &block_info,
+                    &raw_l2tx,
+                    Some(max_cycles),
+                    Some(&mut cycles_pool),
+                    timeout,
+                )
+            })()
+            .with_context(|| format!("tx {}", index))?;
+            gw_metrics::rpc()
+                .execute_transactions(run_result.exit_code)
+                .inc();
+
+            let exit_code = run_result.exit_code as u8 as u32;
+            let RunResult {
+                return_data, logs, ..
+            } = run_result.into();
+            run_results.push(BatchRunResult {
+                tx_hash: to_jsonh256(tx_hash),
+                exit_code: exit_code.into(),
+                return_data,
+                logs,
+            });
+        }
+        Result::<_, anyhow::Error>::Ok(run_results)
+    })
+    .await?
+    .map_err(|err| {
+        if is_execution_timeout(&err) {
+            return execution_timeout_err();
+        }
+        RpcError::Full {
+            code: INVALID_REQUEST,
+            message: format!("{:#}", err),
+            data: None,
+        }
+    })?;
+
+    Ok(run_results)
+}
+
 #[allow(clippy::type_complexity)]
 #[instrument(skip_all)]
-async fn submit_l2transaction(
+pub(crate) async fn submit_l2transaction(
     Params((l2tx,)): Params<(JsonBytes,)>,
     ctx: Data<SubmitTransactionContext>,
 ) -> Result<Option<JsonH256>, RpcError> {
+    if ctx.mem_pool_state.state_divergence().is_some() {
+        return Err(safe_mode_err());
+    }
     let l2tx_bytes = l2tx.into_bytes();
     let tx = packed::L2Transaction::from_slice(&l2tx_bytes)?;
-    let tx_hash: H256 = tx.hash();
 
     let sender_id: u32 = tx.raw().from_id().unpack();
     let eth_recover = &ctx.polyjuice_sender_recover.eth;
@@ -1400,17 +1873,21 @@ async fn submit_l2transaction(
     };
 
     // check rate limit
-    if let Some(rate_limiter) = ctx.rate_limiter.as_ref() {
-        let mut rate_limiter = rate_limiter.lock().await;
+    let rate_limit = ctx
+        .dynamic_config_manager
+        .load()
+        .get_send_tx_rate_limit()
+        .cloned();
+    if let Some(rate_limit) = rate_limit {
+        let mut rate_limiter = ctx.rate_limiter.lock().await;
+        let rate_limiter =
+            rate_limiter.get_or_insert_with(|| lru::LruCache::new(rate_limit.lru_size));
+        if rate_limiter.cap() != rate_limit.lru_size {
+            rate_limiter.resize(rate_limit.lru_size);
+        }
         let sender_id: u32 = tx.raw().from_id().unpack();
         if let Some(last_touch) = rate_limiter.get(&sender_id) {
-            if last_touch.elapsed().as_secs()
-                < ctx
-                    .rate_limit_config
-                    .as_ref()
-                    .map(|c| c.seconds)
-                    .unwrap_or_default()
-            {
+            if last_touch.elapsed().as_secs() < rate_limit.seconds {
                 return Err(rate_limit_err());
             }
         }
@@ -1474,6 +1951,12 @@ async fn submit_l2transaction(
         }
     }
 
+    // Read-only nodes relay the tx to the full node.
+    if let Some(ref relay) = ctx.tx_relay {
+        relay.relay(tx).await?;
+        return Ok(tx_hash_json);
+    }
+
     let permit = ctx.submit_tx.try_reserve().map_err(|err| match err {
         mpsc::error::TrySendError::Closed(_) => RpcError::Provided {
             code: INTERNAL_ERROR_ERR_CODE,
@@ -1485,17 +1968,7 @@ async fn submit_l2transaction(
         },
     })?;
 
-    let tx_hash_in_queue = match tx_hash_json {
-        Some(_) => tx_hash,
-        None => {
-            let mut hasher = new_blake2b();
-            let sig: Bytes = tx.signature().unpack();
-            hasher.update(&sig);
-            let mut hash = [0u8; 32];
-            hasher.finalize(&mut hash);
-            hash
-        }
-    };
+    let tx_hash_in_queue = tx_hash_in_queue(&tx);
     let request = Request::Tx(tx);
     // Use permit to insert before send so that remove won't happen before insert.
     if let Some(handle) = ctx
@@ -1522,13 +1995,17 @@ async fn submit_l2transaction(
 // Either `RPCContext` or derive?
 #[allow(clippy::type_complexity)]
 #[instrument(skip_all)]
-async fn submit_withdrawal_request(
+pub(crate) async fn submit_withdrawal_request(
     Params((withdrawal_request,)): Params<(JsonBytes,)>,
     generator: Data<Generator>,
     store: Data<Store>,
     in_queue_request_map: Data<Option<Arc<InQueueRequestMap>>>,
     submit_tx: Data<mpsc::Sender<(Request, RequestContext)>>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<JsonH256, RpcError> {
+    if mem_pool_state.state_divergence().is_some() {
+        return Err(safe_mode_err());
+    }
     let withdrawal_bytes = withdrawal_request.into_bytes();
     let withdrawal = packed::WithdrawalRequestExtra::from_slice(&withdrawal_bytes)?;
     let withdrawal_hash = withdrawal.hash();
@@ -1690,15 +2167,277 @@ async fn get_withdrawal(
             }));
         }
     }
+    if db.get_expired_withdrawal(&withdrawal_hash).is_some() {
+        return Ok(Some(WithdrawalWithStatus {
+            status: WithdrawalStatus::Expired,
+            ..Default::default()
+        }));
+    }
     Ok(None)
 }
 
-// registry address, sudt_id, block_number
-#[derive(serde::Serialize, serde::Deserialize)]
-#[serde(untagged)]
-enum GetBalanceParams {
-    Tip((JsonBytes, AccountID)),
-    Number((JsonBytes, AccountID, Option<GwUint64>)),
+/// Number of recent blocks used to estimate the block interval.
+const BLOCK_INTERVAL_SAMPLE_BLOCKS: u64 = 100;
+
+/// Estimates timestamps of future blocks with the average interval of recent
+/// blocks.
+struct BlockTimeEstimator {
+    tip_number: u64,
+    tip_timestamp: u64,
+    interval: u64,
+}
+
+impl BlockTimeEstimator {
+    /// Returns `None` if there are not enough blocks to sample.
+    fn new(db: &impl ChainStore) -> Result<Option<Self>> {
+        let tip = db.get_last_valid_tip_block()?;
+        let tip_number = tip.raw().number().unpack();
+        let tip_timestamp: u64 = tip.raw().timestamp().unpack();
+        let sample_number = tip_number.saturating_sub(BLOCK_INTERVAL_SAMPLE_BLOCKS);
+        if tip_number == sample_number {
+            return Ok(None);
+        }
+        let sample_timestamp: Option<u64> = db
+            .get_block_hash_by_number(sample_number)?
+            .and_then(|hash| db.get_block(&hash).transpose())
+            .transpose()?
+            .map(|block| block.raw().timestamp().unpack());
+        Ok(sample_timestamp.map(|sample_timestamp| BlockTimeEstimator {
+            tip_number,
+            tip_timestamp,
+            interval: tip_timestamp.saturating_sub(sample_timestamp) / (tip_number - sample_number),
+        }))
+    }
+
+    fn timestamp_of(&self, block_number: u64) -> u64 {
+        let remaining_blocks = block_number.saturating_sub(self.tip_number);
+        self.tip_timestamp + self.interval * remaining_blocks
+    }
+}
+
+#[instrument(skip_all)]
+async fn get_withdrawal_status(
+    Params((withdrawal_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+    in_queue_request_map: Data<Option<Arc<InQueueRequestMap>>>,
+    rollup_config: Data<RollupConfig>,
+) -> Result<Option<WithdrawalLifecycle>, RpcError> {
+    let withdrawal_hash = to_h256(withdrawal_hash);
+    let db = store.get_snapshot();
+
+    let withdrawal_info = match db.get_withdrawal_info(&withdrawal_hash)? {
+        Some(info) => info,
+        None => {
+            let in_queue = in_queue_request_map
+                .as_deref()
+                .map_or(false, |m| m.get_withdrawal(&withdrawal_hash).is_some());
+            let status = if !in_queue && db.get_mem_pool_withdrawal(&withdrawal_hash)?.is_none() {
+                match db.get_expired_withdrawal(&withdrawal_hash) {
+                    Some(_) => WithdrawalLifecycleStatus::Expired,
+                    None => return Ok(None),
+                }
+            } else if mem_pool_state.is_withdrawal_packaged(&withdrawal_hash) {
+                WithdrawalLifecycleStatus::Packaged
+            } else {
+                WithdrawalLifecycleStatus::Pending
+            };
+            return Ok(Some(WithdrawalLifecycle {
+                status,
+                l2_block_number: None,
+                l2_block_hash: None,
+                l1_tx_hash: None,
+                unlock_tx_hash: None,
+                estimated_finalized_at: None,
+            }));
+        }
+    };
+
+    let block_number: u64 = withdrawal_info.block_number().unpack();
+    let block_hash: H256 =
+        packed::Byte32::from_slice(&withdrawal_info.key().as_slice()[..32])?.unpack();
+    let last_confirmed_block_number = db
+        .get_last_confirmed_block_number_hash()
+        .map(|nh| nh.number().unpack())
+        .unwrap_or(0);
+    let finalized_block_number = block_number + rollup_config.finality_blocks().unpack();
+    let unlock_tx_hash = db.get_withdrawal_unlock_tx_hash(&withdrawal_hash);
+
+    let status = if unlock_tx_hash.is_some() {
+        WithdrawalLifecycleStatus::Unlocked
+    } else if last_confirmed_block_number >= finalized_block_number {
+        WithdrawalLifecycleStatus::Finalized
+    } else if last_confirmed_block_number >= block_number {
+        WithdrawalLifecycleStatus::OnChain
+    } else {
+        WithdrawalLifecycleStatus::Packaged
+    };
+
+    let estimated_finalized_at = match status {
+        WithdrawalLifecycleStatus::Packaged | WithdrawalLifecycleStatus::OnChain => {
+            BlockTimeEstimator::new(&db)?
+                .map(|estimator| estimator.timestamp_of(finalized_block_number).into())
+        }
+        _ => None,
+    };
+
+    Ok(Some(WithdrawalLifecycle {
+        status,
+        l2_block_number: Some(block_number.into()),
+        l2_block_hash: Some(to_jsonh256(block_hash)),
+        l1_tx_hash: db.get_block_submit_tx_hash(block_number).map(to_jsonh256),
+        unlock_tx_hash: unlock_tx_hash.map(to_jsonh256),
+        estimated_finalized_at,
+    }))
+}
+
+/// Max items returned by `gw_get_withdrawal_queue`.
+const MAX_WITHDRAWAL_QUEUE_ITEMS: usize = 1000;
+
+/// Pending withdrawals of the mem pool in packaging order.
+///
+/// Withdrawals in the mem block go to the next block. The rest are assigned
+/// to later blocks the way the mem pool packages them: the first pending
+/// withdrawal of each account in account id order, up to `max_withdrawals`
+/// per block.
+#[instrument(skip_all)]
+async fn get_withdrawal_queue(
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+    mem_pool_config: Data<MemPoolConfig>,
+    dynamic_config_manager: Data<Arc<ArcSwap<DynamicConfigManager>>>,
+    generator: Data<Generator>,
+) -> Result<Vec<WithdrawalQueueItem>, RpcError> {
+    let db = store.get_snapshot();
+    let state = mem_pool_state.load_state_db();
+    let deferred: HashSet<H256> = mem_pool_state
+        .get_deferred_withdrawals()
+        .iter()
+        .copied()
+        .collect();
+
+    let mut packaged = Vec::new();
+    let mut pending: BTreeMap<u32, Vec<(u32, H256, WithdrawalRequestExtra)>> = BTreeMap::new();
+    let tx_db = store.begin_transaction();
+    for (hash, withdrawal) in tx_db.get_mem_pool_withdrawal_iter() {
+        let account_script_hash: H256 = withdrawal.raw().account_script_hash().unpack();
+        let account_id = match state.get_account_id_by_script_hash(&account_script_hash)? {
+            Some(id) => id,
+            None => continue,
+        };
+        let nonce: u32 = withdrawal.raw().nonce().unpack();
+        if mem_pool_state.is_withdrawal_packaged(&hash) {
+            packaged.push((account_id, nonce, hash, withdrawal));
+        } else if nonce >= state.get_nonce(account_id)? {
+            // Withdrawals of outdated nonces are going to be removed.
+            pending
+                .entry(account_id)
+                .or_default()
+                .push((nonce, hash, withdrawal));
+        }
+    }
+    packaged.sort_unstable_by_key(|(account_id, nonce, _, _)| (*account_id, *nonce));
+
+    let rollup_config = &generator.rollup_context().rollup_config;
+    let fork_config = generator.fork_config();
+    let finality_blocks: u64 = rollup_config.finality_blocks().unpack();
+    let estimator = BlockTimeEstimator::new(&db)?;
+    let estimate_finalized_at = |block_number: u64| {
+        estimator.as_ref().map(|estimator| {
+            if fork_config.use_timestamp_as_timepoint(block_number) {
+                estimator.timestamp_of(block_number) + rollup_config.finality_time_in_ms()
+            } else {
+                estimator.timestamp_of(block_number + finality_blocks)
+            }
+        })
+    };
+    let to_item = |nonce: u32,
+                   hash: H256,
+                   withdrawal: &WithdrawalRequestExtra,
+                   is_packaged: bool,
+                   block_number: u64| WithdrawalQueueItem {
+        withdrawal_hash: to_jsonh256(hash),
+        account_script_hash: to_jsonh256(withdrawal.raw().account_script_hash().unpack()),
+        nonce: nonce.into(),
+        packaged: is_packaged,
+        deferred: deferred.contains(&hash),
+        estimated_block_number: block_number.into(),
+        estimated_finalized_at: estimate_finalized_at(block_number).map(Into::into),
+    };
+
+    let tip_number: u64 = db.get_last_valid_tip_block()?.raw().number().unpack();
+    let mut items: Vec<_> = { packaged.iter() }
+        .map(|(_, nonce, hash, withdrawal)| {
+            to_item(*nonce, *hash, withdrawal, true, tip_number + 1)
+        })
+        .collect();
+
+    let max_withdrawals = {
+        let manager = dynamic_config_manager.load();
+        let limits = manager.get_mem_block_limits();
+        limits.and_then(|limits| limits.max_withdrawals)
+    }
+    .unwrap_or(mem_pool_config.mem_block.max_withdrawals)
+    .max(1);
+    let mut queues: Vec<VecDeque<_>> = { pending.into_values() }
+        .map(|mut withdrawals| {
+            withdrawals.sort_unstable_by_key(|(nonce, _, _)| *nonce);
+            withdrawals.into()
+        })
+        .collect();
+    let mut block_number = tip_number + 2;
+    while items.len() < MAX_WITHDRAWAL_QUEUE_ITEMS && queues.iter().any(|q| !q.is_empty()) {
+        let heads = queues.iter_mut().filter_map(VecDeque::pop_front);
+        for (nonce, hash, withdrawal) in heads.take(max_withdrawals) {
+            items.push(to_item(nonce, hash, &withdrawal, false, block_number));
+        }
+        block_number += 1;
+    }
+    items.truncate(MAX_WITHDRAWAL_QUEUE_ITEMS);
+
+    Ok(items)
+}
+
+#[instrument(skip_all)]
+async fn get_deposit_status(
+    Params((out_point,)): Params<(OutPoint,)>,
+    store: Data<Store>,
+    rpc_client: Data<RPCClient>,
+    rollup_config: Data<RollupConfig>,
+) -> Result<Option<DepositLifecycle>, RpcError> {
+    let out_point: packed::OutPoint = out_point.into();
+    let db = store.get_snapshot();
+
+    let (status, l2_block_number) = match db.get_deposit_status(&out_point) {
+        Some(DepositStatus::Collected) => (DepositLifecycleStatus::Collected, None),
+        Some(DepositStatus::Packaged) => (DepositLifecycleStatus::Packaged, None),
+        Some(DepositStatus::Credited { block_number }) => {
+            (DepositLifecycleStatus::Credited, Some(block_number))
+        }
+        None => {
+            // Not collected yet, check whether it is a live deposit cell on L1.
+            let deposit_lock_code_hash = rollup_config.deposit_script_type_hash();
+            let is_deposit = { rpc_client.get_cell(out_point).await? }
+                .and_then(|cell_with_status| cell_with_status.cell)
+                .map_or(false, |cell| {
+                    cell.output.lock().code_hash().as_slice() == deposit_lock_code_hash.as_slice()
+                });
+            if !is_deposit {
+                return Ok(None);
+            }
+            (DepositLifecycleStatus::Pending, None)
+        }
+    };
+
+    let l2_block_hash = match l2_block_number {
+        Some(number) => db.get_block_hash_by_number(number)?.map(to_jsonh256),
+        None => None,
+    };
+    Ok(Some(DepositLifecycle {
+        status,
+        l2_block_number: l2_block_number.map(Into::into),
+        l2_block_hash,
+    }))
 }
 
 #[instrument(skip_all)]
@@ -1707,35 +2446,60 @@ async fn get_balance(
     store: Data<Store>,
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<U256, RpcError> {
-    let (serialized_address, sudt_id, block_number) = match params {
-        GetBalanceParams::Tip(p) => (p.0, p.1, None),
-        GetBalanceParams::Number(p) => p,
-    };
-
-    let address =
-        gw_common::registry_address::RegistryAddress::from_slice(serialized_address.as_bytes())
-            .ok_or_else(|| invalid_param_err("Invalid registry address"))?;
-    let balance = match block_number {
+    let (serialized_address, sudt_id, block_number) = params.into_parts();
+    match block_number {
         Some(block_number) => {
             let mut db = store.begin_transaction();
-            let tree =
-                BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number.into()))?;
-            tree.get_sudt_balance(sudt_id.into(), &address)?
+            let tree = BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number))?;
+            query::balance(&tree, &serialized_address, sudt_id)
         }
         None => {
             let state = mem_pool_state.load_state_db();
-            state.get_sudt_balance(sudt_id.into(), &address)?
+            query::balance(&state, &serialized_address, sudt_id)
         }
-    };
-    Ok(balance)
+    }
 }
 
-// account_id, key, block_number
-#[derive(serde::Serialize, serde::Deserialize)]
-#[serde(untagged)]
-enum GetStorageAtParams {
-    Tip((AccountID, JsonH256)),
-    Number((AccountID, JsonH256, Option<GwUint64>)),
+/// Max balances of a `gw_get_balances` or `gw_get_balances_bulk` call.
+const MAX_BATCH_BALANCES: usize = 256;
+
+/// Balances of sUDTs of an address, in the order of `sudt_ids`.
+#[instrument(skip_all)]
+async fn get_balances(
+    Params((serialized_address, sudt_ids)): Params<(JsonBytes, Vec<AccountID>)>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Vec<U256>, RpcError> {
+    if sudt_ids.len() > MAX_BATCH_BALANCES {
+        return Err(invalid_param_err("Too many sudt ids"));
+    }
+    let address = query::parse_registry_address(&serialized_address)?;
+    let state = mem_pool_state.load_state_db();
+    let balances = sudt_ids
+        .into_iter()
+        .map(|sudt_id| state.get_sudt_balance(sudt_id.into(), &address))
+        .collect::<Result<_, _>>()?;
+    Ok(balances)
+}
+
+/// Balances of a sUDT of addresses, in the order of `addresses`.
+#[instrument(skip_all)]
+async fn get_balances_bulk(
+    Params((serialized_addresses, sudt_id)): Params<(Vec<JsonBytes>, AccountID)>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Vec<U256>, RpcError> {
+    if serialized_addresses.len() > MAX_BATCH_BALANCES {
+        return Err(invalid_param_err("Too many addresses"));
+    }
+    let addresses = serialized_addresses
+        .iter()
+        .map(query::parse_registry_address)
+        .collect::<Result<Vec<_>, _>>()?;
+    let state = mem_pool_state.load_state_db();
+    let balances = addresses
+        .iter()
+        .map(|address| state.get_sudt_balance(sudt_id.into(), address))
+        .collect::<Result<_, _>>()?;
+    Ok(balances)
 }
 
 #[instrument(skip_all)]
@@ -1744,28 +2508,18 @@ async fn get_storage_at(
     store: Data<Store>,
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<JsonH256, RpcError> {
-    let (account_id, key, block_number) = match params {
-        GetStorageAtParams::Tip(p) => (p.0, p.1, None),
-        GetStorageAtParams::Number(p) => p,
-    };
-
-    let value = match block_number {
+    let (account_id, key, block_number) = params.into_parts();
+    match block_number {
         Some(block_number) => {
             let mut db = store.begin_transaction();
-            let tree =
-                BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number.into()))?;
-            let key: H256 = to_h256(key);
-            tree.get_value(account_id.into(), key.as_slice())?
+            let tree = BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number))?;
+            query::storage_at(&tree, account_id, key)
         }
         None => {
             let state = mem_pool_state.load_state_db();
-            let key: H256 = to_h256(key);
-            state.get_value(account_id.into(), key.as_slice())?
+            query::storage_at(&state, account_id, key)
         }
-    };
-
-    let json_value = to_jsonh256(value);
-    Ok(json_value)
+    }
 }
 
 #[instrument(skip_all)]
@@ -1774,22 +2528,7 @@ async fn get_account_id_by_script_hash(
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<Option<AccountID>, RpcError> {
     let state = mem_pool_state.load_state_db();
-
-    let script_hash = to_h256(script_hash);
-
-    let account_id_opt = state
-        .get_account_id_by_script_hash(&script_hash)?
-        .map(Into::into);
-
-    Ok(account_id_opt)
-}
-
-// account_id, block_number
-#[derive(serde::Serialize, serde::Deserialize)]
-#[serde(untagged)]
-enum GetNonceParams {
-    Tip((AccountID,)),
-    Number((AccountID, Option<GwUint64>)),
+    query::account_id_by_script_hash(&state, script_hash)
 }
 
 #[instrument(skip_all)]
@@ -1798,38 +2537,62 @@ async fn get_nonce(
     store: Data<Store>,
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<Uint32, RpcError> {
-    let (account_id, block_number) = match params {
-        GetNonceParams::Tip(p) => (p.0, None),
-        GetNonceParams::Number(p) => p,
-    };
-
-    let nonce = match block_number {
+    let (account_id, block_number) = params.into_parts();
+    match block_number {
         Some(block_number) => {
             let mut db = store.begin_transaction();
-            let tree =
-                BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number.into()))?;
-            tree.get_nonce(account_id.into())?
+            let tree = BlockStateDB::from_store(&mut db, RWConfig::history_block(block_number))?;
+            query::nonce(&tree, account_id)
         }
         None => {
             let state = mem_pool_state.load_state_db();
-            state.get_nonce(account_id.into())?
+            query::nonce(&state, account_id)
         }
-    };
+    }
+}
 
+/// Next nonce of the account, counting txs in the mem block and requests
+/// still in the submission queue.
+#[instrument(skip_all)]
+async fn get_pending_nonce(
+    Params((serialized_address,)): Params<(JsonBytes,)>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+    in_queue_request_map: Data<Option<Arc<InQueueRequestMap>>>,
+) -> Result<Uint32, RpcError> {
+    let address = query::parse_registry_address(&serialized_address)?;
+    let state = mem_pool_state.load_state_db();
+    let nonce = pending_nonce(&state, &address, in_queue_request_map.as_deref())?;
     Ok(nonce.into())
 }
 
+fn pending_nonce(
+    state: &impl State,
+    address: &gw_common::registry_address::RegistryAddress,
+    in_queue_request_map: Option<&InQueueRequestMap>,
+) -> Result<u32> {
+    let script_hash = match state.get_script_hash_by_registry_address(address)? {
+        Some(script_hash) => script_hash,
+        None => return Ok(0),
+    };
+    let account_id = match state.get_account_id_by_script_hash(&script_hash)? {
+        Some(account_id) => account_id,
+        None => return Ok(0),
+    };
+    // Txs in the mem block are already applied to the mem pool state.
+    let nonce = state.get_nonce(account_id)?;
+    let in_queue_nonce = in_queue_request_map
+        .and_then(|m| m.max_nonce(account_id, &script_hash))
+        .map(|nonce| nonce.saturating_add(1));
+    Ok(nonce.max(in_queue_nonce.unwrap_or(0)))
+}
+
 #[instrument(skip_all)]
 async fn get_script(
     Params((script_hash,)): Params<(JsonH256,)>,
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<Option<Script>, RpcError> {
     let state = mem_pool_state.load_state_db();
-
-    let script_hash = to_h256(script_hash);
-    let script_opt = state.get_script(&script_hash).map(Into::into);
-
-    Ok(script_opt)
+    Ok(query::script(&state, script_hash))
 }
 
 #[instrument(skip_all)]
@@ -1838,8 +2601,7 @@ async fn get_script_hash(
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<JsonH256, RpcError> {
     let state = mem_pool_state.load_state_db();
-    let script_hash = state.get_script_hash(account_id.into())?;
-    Ok(to_jsonh256(script_hash))
+    query::script_hash(&state, account_id)
 }
 
 #[instrument(skip_all)]
@@ -1848,11 +2610,7 @@ async fn get_script_hash_by_registry_address(
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<Option<JsonH256>, RpcError> {
     let state = mem_pool_state.load_state_db();
-    let addr =
-        gw_common::registry_address::RegistryAddress::from_slice(serialized_address.as_bytes())
-            .ok_or_else(|| invalid_param_err("Invalid registry address"))?;
-    let script_hash_opt = state.get_script_hash_by_registry_address(&addr)?;
-    Ok(script_hash_opt.map(to_jsonh256))
+    query::script_hash_by_registry_address(&state, &serialized_address)
 }
 
 #[instrument(skip_all)]
@@ -1861,17 +2619,122 @@ async fn get_registry_address_by_script_hash(
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<Option<RegistryAddress>, RpcError> {
     let state = mem_pool_state.load_state_db();
-    let addr =
-        state.get_registry_address_by_script_hash(registry_id.value(), &to_h256(script_hash))?;
-    Ok(addr.map(Into::into))
+    query::registry_address_by_script_hash(&state, script_hash, registry_id)
+}
+
+/// All registry addresses of a script hash, by the registry address index.
+#[instrument(skip_all)]
+async fn get_registry_addresses_by_script_hash(
+    Params((script_hash,)): Params<(JsonH256,)>,
+    store: Data<Store>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Vec<RegistryAddress>, RpcError> {
+    let script_hash = to_h256(script_hash);
+    let snap = store.get_snapshot();
+    let state = mem_pool_state.load_state_db();
+    let mut addresses = Vec::new();
+    for (addr, _account_id) in snap.iter_registry_addresses(&script_hash) {
+        // Entries of detached blocks are not removed from the index.
+        let current = state.get_registry_address_by_script_hash(addr.registry_id, &script_hash)?;
+        if current.as_ref() == Some(&addr) {
+            addresses.push(addr.into());
+        }
+    }
+    Ok(addresses)
+}
+
+/// Max accounts of a `gw_list_accounts` call.
+const MAX_LIST_ACCOUNTS: u32 = 1_000;
+const DEFAULT_LIST_ACCOUNTS: u32 = 100;
+
+// from_id, limit
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum ListAccountsParams {
+    Default((AccountID,)),
+    WithLimit((AccountID, Option<Uint32>)),
+}
+
+/// Accounts `from_id..` in the mem pool state, at most `limit` of them.
+#[instrument(skip_all)]
+async fn list_accounts(
+    Params(params): Params<ListAccountsParams>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Vec<AccountSummary>, RpcError> {
+    let (from_id, limit) = match params {
+        ListAccountsParams::Default((from_id,)) => (from_id, None),
+        ListAccountsParams::WithLimit((from_id, limit)) => (from_id, limit),
+    };
+    let limit = limit.map_or(DEFAULT_LIST_ACCOUNTS, |l| l.value());
+    if limit > MAX_LIST_ACCOUNTS {
+        return Err(invalid_param_err("Too many accounts"));
+    }
+    let state = mem_pool_state.load_state_db();
+    let from_id: u32 = from_id.into();
+    let to_id = state
+        .get_account_count()?
+        .min(from_id.saturating_add(limit));
+    let mut accounts = Vec::with_capacity(to_id.saturating_sub(from_id) as usize);
+    for id in from_id..to_id {
+        let script_hash = state.get_script_hash(id)?;
+        accounts.push(AccountSummary {
+            id: id.into(),
+            script_hash: to_jsonh256(script_hash),
+            script: state.get_script(&script_hash).map(Into::into),
+            nonce: state.get_nonce(id)?.into(),
+        });
+    }
+    Ok(accounts)
 }
 
-// data_hash, block_number
+/// Max records of a `gw_list_reorgs` call.
+const MAX_LIST_REORGS: u32 = 100;
+const DEFAULT_LIST_REORGS: u32 = 20;
+
+// before, limit
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
-enum GetDataParams {
-    Tip((JsonH256,)),
-    Number((JsonH256, Option<GwUint64>)),
+enum ListReorgsParams {
+    Default((Option<GwUint64>,)),
+    WithLimit((Option<GwUint64>, Option<Uint32>)),
+}
+
+/// Reorgs observed by the node with ids less than `before`, newest first.
+#[instrument(skip_all)]
+async fn list_reorgs(
+    Params(params): Params<ListReorgsParams>,
+    store: Data<Store>,
+) -> Result<Vec<ReorgRecord>, RpcError> {
+    let (before, limit) = match params {
+        ListReorgsParams::Default((before,)) => (before, None),
+        ListReorgsParams::WithLimit((before, limit)) => (before, limit),
+    };
+    let limit = limit.map_or(DEFAULT_LIST_REORGS, |l| l.value());
+    if limit > MAX_LIST_REORGS {
+        return Err(invalid_param_err("Too many reorgs"));
+    }
+    let snap = store.get_snapshot();
+    let records = { snap.iter_reorg_records(before.map(|b| b.value())) }
+        .take(limit as usize)
+        .map(|(id, record)| ReorgRecord {
+            id: id.into(),
+            kind: match record.kind {
+                reorg_log::ReorgKind::Detach => ReorgKind::Detach,
+                reorg_log::ReorgKind::Rewind => ReorgKind::Rewind,
+                reorg_log::ReorgKind::Revert => ReorgKind::Revert,
+            },
+            observed_at: record.observed_at.into(),
+            fork_point_number: record.fork_point_number.into(),
+            fork_point_hash: to_jsonh256(record.fork_point_hash),
+            depth: (record.depth() as u32).into(),
+            block_hashes: record.block_hashes.into_iter().map(to_jsonh256).collect(),
+            first_block_timestamp: record.first_block_timestamp.into(),
+            last_block_timestamp: record.last_block_timestamp.into(),
+            tx_count: record.tx_count.into(),
+            withdrawal_count: record.withdrawal_count.into(),
+        })
+        .collect();
+    Ok(records)
 }
 
 #[instrument(skip_all)]
@@ -1879,17 +2742,8 @@ async fn get_data(
     Params(params): Params<GetDataParams>,
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<Option<JsonBytes>, RpcError> {
-    let (data_hash, _block_number) = match params {
-        GetDataParams::Tip(p) => (p.0, None),
-        GetDataParams::Number(p) => p,
-    };
-
     let state = mem_pool_state.load_state_db();
-    let data_opt = state
-        .get_data(&to_h256(data_hash))
-        .map(JsonBytes::from_bytes);
-
-    Ok(data_opt)
+    Ok(query::data(&state, params.data_hash()))
 }
 
 #[instrument(skip_all)]
@@ -1919,13 +2773,14 @@ fn get_backend_info(generator: Arc<Generator>) -> Vec<BackendInfo> {
         .collect()
 }
 
-fn to_rpc_backend_type(b_type: &gw_config::BackendType) -> BackendType {
+pub(crate) fn to_rpc_backend_type(b_type: &gw_config::BackendType) -> BackendType {
     match b_type {
         gw_config::BackendType::EthAddrReg => BackendType::EthAddrReg,
         gw_config::BackendType::Meta => BackendType::Meta,
         gw_config::BackendType::Sudt => BackendType::Sudt,
         gw_config::BackendType::Polyjuice => BackendType::Polyjuice,
-        _ => BackendType::Unknown,
+        gw_config::BackendType::Custom => BackendType::Custom,
+        gw_config::BackendType::Unknown => BackendType::Unknown,
     }
 }
 
@@ -2132,6 +2987,57 @@ async fn get_node_info(
     })
 }
 
+#[instrument(skip_all)]
+async fn get_fork_schedule(
+    generator: Data<Generator>,
+    store: Data<Store>,
+) -> Result<Vec<ForkInfo>> {
+    let tip_number: u64 = store.get_last_valid_tip_block()?.raw().number().unpack();
+    let fork_config = generator.fork_config();
+    let schedule = { fork_config.schedule().into_iter() }
+        .map(|(fork, fork_height)| ForkInfo {
+            name: fork.name().to_string(),
+            fork_height: fork_height.map(Into::into),
+            active: fork_config.is_active(fork, tip_number),
+        })
+        .collect();
+    Ok(schedule)
+}
+
+#[instrument(skip_all)]
+async fn get_ckb_endpoints(rpc_client: Data<RPCClient>) -> Result<Vec<CKBEndpointStatus>> {
+    let (ckb, indexer) = rpc_client.endpoints();
+    let endpoints = { Some(("ckb", ckb)).into_iter() }.chain(indexer.map(|e| ("indexer", e)));
+    let status = endpoints
+        .flat_map(|(client, endpoints)| {
+            { endpoints.status().into_iter() }.map(move |status| CKBEndpointStatus {
+                client: client.to_string(),
+                url: status.url,
+                active: status.active,
+                healthy: status.healthy,
+                tip_number: status.tip_number.map(Into::into),
+                latency_ms: status.latency.map(|l| (l.as_millis() as u64).into()),
+                last_error: status.last_error,
+            })
+        })
+        .collect();
+    Ok(status)
+}
+
+/// New addresses waiting for the mem pool account creator.
+#[instrument(skip_all)]
+async fn get_new_address_queue(mem_pool: Data<MemPool>) -> Result<Vec<RegistryAddress>> {
+    let mem_pool = match mem_pool.as_ref() {
+        Some(mem_pool) => mem_pool.lock().await,
+        None => return Ok(Vec::new()),
+    };
+    let addresses = { mem_pool.mem_block().new_addresses().iter() }
+        .cloned()
+        .map(Into::into)
+        .collect();
+    Ok(addresses)
+}
+
 #[instrument(skip_all)]
 async fn get_last_submitted_info(store: Data<Store>) -> Result<LastL2BlockCommittedInfo> {
     let last_submitted = store
@@ -2177,6 +3083,54 @@ async fn get_mem_pool_state_ready(
     Ok(mem_pool_state.completed_initial_syncing())
 }
 
+/// Hashes of pending withdrawals deferred to the next block because finalized
+/// custodians are insufficient.
+async fn get_deferred_withdrawals(
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<Vec<JsonH256>, RpcError> {
+    let hashes = mem_pool_state.get_deferred_withdrawals();
+    Ok(hashes.iter().copied().map(to_jsonh256).collect())
+}
+
+// [sudt_script_hash], all params are optional
+type GetWithdrawableCapacityParams = Option<Vec<Option<JsonH256>>>;
+
+/// Finalized custodian CKB and sUDT amounts left for withdrawals, only of the
+/// sUDT if `sudt_script_hash` is given.
+#[instrument(skip_all)]
+async fn get_withdrawable_capacity(
+    Params(params): Params<GetWithdrawableCapacityParams>,
+    mem_pool_state: Data<Arc<MemPoolState>>,
+) -> Result<WithdrawableCapacity, RpcError> {
+    let sudt_script_hash = { params.and_then(|p| p.into_iter().next()) }
+        .flatten()
+        .map(to_h256);
+    let custodians = mem_pool_state.get_finalized_custodians();
+    let sudt = match sudt_script_hash {
+        Some(hash) => {
+            let amount = custodians.sudt.get(&hash).map_or(0, |(amount, _)| *amount);
+            vec![WithdrawableSUDT {
+                sudt_script_hash: to_jsonh256(hash),
+                amount: amount.into(),
+            }]
+        }
+        None => {
+            let mut sudt: Vec<_> = { custodians.sudt.iter() }
+                .map(|(hash, (amount, _))| WithdrawableSUDT {
+                    sudt_script_hash: to_jsonh256(*hash),
+                    amount: (*amount).into(),
+                })
+                .collect();
+            sudt.sort_unstable_by(|a, b| a.sudt_script_hash.cmp(&b.sudt_script_hash));
+            sudt
+        }
+    };
+    Ok(WithdrawableCapacity {
+        capacity: custodians.capacity.into(),
+        sudt,
+    })
+}
+
 async fn tests_produce_block(
     Params((payload,)): Params<(TestModePayload,)>,
     tests_rpc_impl: Data<BoxedTestsRPCImpl>,
@@ -2246,9 +3200,142 @@ async fn dump_jemalloc_profiling() -> Result<()> {
     Ok(())
 }
 
-// Reload config dynamically and return the difference between two configs.
-async fn reload_config(
+// Reconfigurations applied by `admin_reload_config`, oldest first.
+async fn get_config_changelog(
     dynamic_config_manager: Data<Arc<ArcSwap<DynamicConfigManager>>>,
-) -> Result<DynamicConfigReloadResponse> {
-    gw_dynamic_config::reload(dynamic_config_manager.clone()).await
+) -> Result<Vec<ConfigChange>> {
+    let changelog = dynamic_config_manager.load().get_changelog().clone();
+    Ok(changelog.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_common::registry_address::RegistryAddress;
+    use gw_jsonrpc_types::godwoken::KVPair;
+    use gw_types::packed::{RawWithdrawalRequest, WithdrawalRequest};
+
+    use super::*;
+
+    #[test]
+    fn test_execute_raw_l2transaction_params() {
+        let script_hash = format!("{:#x}", JsonH256([1u8; 32]));
+        let params = serde_json::json!(["0x", null, null, { script_hash: { "nonce": "0x5" } }]);
+        let params: ExecuteRawL2TransactionParams = serde_json::from_value(params).unwrap();
+        let overrides = match params {
+            ExecuteRawL2TransactionParams::StateOverrides((_, _, _, overrides)) => overrides,
+            _ => panic!("expect state overrides"),
+        };
+        let account = &overrides[&JsonH256([1u8; 32])];
+        assert_eq!(account.nonce, Some(5u32.into()));
+        assert_eq!(account.balance, None);
+    }
+
+    #[test]
+    fn test_apply_state_overrides() {
+        let store = Store::open_tmp().unwrap();
+        let mut state = MemStateDB::from_store(store.get_snapshot()).unwrap();
+        let script_hash = [1u8; 32];
+        let account_id = state.create_account(script_hash).unwrap();
+        let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![2u8; 20]);
+        state
+            .mapping_registry_address_to_script_hash(address.clone(), script_hash)
+            .unwrap();
+        state
+            .mint_sudt(CKB_SUDT_ACCOUNT_ID, &address, 100u64.into())
+            .unwrap();
+
+        let code = Bytes::from_static(b"contract code");
+        let account = AccountStateOverride {
+            balance: Some(1000u64.into()),
+            nonce: Some(5u32.into()),
+            state_diff: Some(vec![KVPair {
+                k: JsonH256([3u8; 32]),
+                v: JsonH256([4u8; 32]),
+            }]),
+            code: Some(JsonBytes::from_bytes(code.clone())),
+        };
+        let overrides: StateOverrides = std::iter::once((JsonH256(script_hash), account)).collect();
+        apply_state_overrides(&mut state, &overrides).unwrap();
+
+        let balance = state
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &address)
+            .unwrap();
+        assert_eq!(balance, 1000u64.into());
+        assert_eq!(state.get_nonce(account_id).unwrap(), 5);
+        assert_eq!(state.get_value(account_id, &[3u8; 32]).unwrap(), [4u8; 32]);
+        let data_hash = state
+            .get_value(account_id, &build_contract_code_key(account_id))
+            .unwrap();
+        assert!(state.is_data_hash_exist(&data_hash).unwrap());
+        assert_eq!(state.get_data(&data_hash), Some(code));
+
+        // Balances can be lowered too, other fields are kept.
+        let account = AccountStateOverride {
+            balance: Some(10u64.into()),
+            ..Default::default()
+        };
+        let overrides: StateOverrides = std::iter::once((JsonH256(script_hash), account)).collect();
+        apply_state_overrides(&mut state, &overrides).unwrap();
+        let balance = state
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &address)
+            .unwrap();
+        assert_eq!(balance, 10u64.into());
+        assert_eq!(state.get_nonce(account_id).unwrap(), 5);
+
+        // Accounts must exist.
+        let overrides: StateOverrides =
+            std::iter::once((JsonH256([9u8; 32]), Default::default())).collect();
+        assert!(apply_state_overrides(&mut state, &overrides).is_err());
+    }
+
+    #[test]
+    fn test_pending_nonce() {
+        let store = Store::open_tmp().unwrap();
+        let mut state = MemStateDB::from_store(store.get_snapshot()).unwrap();
+        let script_hash = [1u8; 32];
+        let account_id = state.create_account(script_hash).unwrap();
+        let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![2u8; 20]);
+        state
+            .mapping_registry_address_to_script_hash(address.clone(), script_hash)
+            .unwrap();
+        state.set_nonce(account_id, 3).unwrap();
+
+        let unknown = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![3u8; 20]);
+        assert_eq!(pending_nonce(&state, &unknown, None).unwrap(), 0);
+        assert_eq!(pending_nonce(&state, &address, None).unwrap(), 3);
+
+        let in_queue_request_map = Arc::new(InQueueRequestMap::default());
+        let tx = |from_id: u32, nonce: u32| {
+            let raw = RawL2Transaction::new_builder()
+                .from_id(from_id.pack())
+                .nonce(nonce.pack())
+                .build();
+            let tx = L2Transaction::new_builder().raw(raw).build();
+            in_queue_request_map.insert(tx.hash(), Request::Tx(tx))
+        };
+        // Queued requests of other accounts and stale ones are ignored.
+        let _other = tx(account_id + 1, 9);
+        let _stale = tx(account_id, 1);
+        let map = Some(&*in_queue_request_map);
+        assert_eq!(pending_nonce(&state, &address, map).unwrap(), 3);
+
+        let _tx3 = tx(account_id, 3);
+        let _tx4 = tx(account_id, 4);
+        assert_eq!(pending_nonce(&state, &address, map).unwrap(), 5);
+
+        let raw = RawWithdrawalRequest::new_builder()
+            .account_script_hash(script_hash.pack())
+            .nonce(5u32.pack())
+            .build();
+        let withdrawal = WithdrawalRequestExtra::new_builder()
+            .request(WithdrawalRequest::new_builder().raw(raw).build())
+            .build();
+        let withdrawal_handle =
+            in_queue_request_map.insert(withdrawal.hash(), Request::Withdrawal(withdrawal));
+        assert_eq!(pending_nonce(&state, &address, map).unwrap(), 6);
+
+        // Requests leave the map when their handles are dropped.
+        drop(withdrawal_handle);
+        assert_eq!(pending_nonce(&state, &address, map).unwrap(), 5);
+    }
 }
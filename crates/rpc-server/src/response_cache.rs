@@ -0,0 +1,196 @@
+//! LRU cache of responses of immutable queries, i.e. finalized blocks, their
+//! tx receipts and state proofs at finalized heights, configured by
+//! `rpc_server.response_cache`.
+//!
+//! Entries are keyed with the block hash. A hit is only served if the block
+//! is still on the main chain, and entries of reverted blocks are removed on
+//! reorgs, which are seen as new blocks not higher than the last one.
+
+use std::sync::Mutex;
+
+use gw_jsonrpc_types::godwoken::{AccountProof, L2BlockWithStatus, StateProof, TxReceipt};
+use gw_mem_pool::subscription::Subscriptions;
+pub use gw_metrics::rpc::CachedMethod;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{
+    h256::*,
+    packed::{L2Block, RollupConfig},
+    prelude::*,
+};
+use lru::LruCache;
+use tokio::sync::broadcast;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) enum CacheKey {
+    Block(H256),
+    TransactionReceipt(H256),
+    AccountProof {
+        block_hash: H256,
+        script_hash: H256,
+    },
+    StorageProof {
+        block_hash: H256,
+        account_id: u32,
+        keys: Vec<H256>,
+    },
+}
+
+impl CacheKey {
+    fn method(&self) -> CachedMethod {
+        match self {
+            CacheKey::Block(_) => CachedMethod::GetBlock,
+            CacheKey::TransactionReceipt(_) => CachedMethod::GetTransactionReceipt,
+            CacheKey::AccountProof { .. } => CachedMethod::GetAccountProof,
+            CacheKey::StorageProof { .. } => CachedMethod::GetStorageProof,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) enum CachedResponse {
+    Block(L2BlockWithStatus),
+    TransactionReceipt(TxReceipt),
+    AccountProof(AccountProof),
+    StorageProof(StateProof),
+}
+
+struct Entry {
+    block_number: u64,
+    block_hash: H256,
+    response: CachedResponse,
+}
+
+pub struct ResponseCache {
+    /// `None` if disabled.
+    entries: Option<Mutex<LruCache<CacheKey, Entry>>>,
+}
+
+impl ResponseCache {
+    pub fn new(max_entries: usize) -> Self {
+        let entries = (max_entries > 0).then(|| Mutex::new(LruCache::new(max_entries)));
+        Self { entries }
+    }
+
+    /// Cached response of `key`, if its block is still on the main chain of
+    /// `store`.
+    pub(crate) fn get(&self, store: &Store, key: &CacheKey) -> Option<CachedResponse> {
+        let entries = self.entries.as_ref()?;
+        let cached = {
+            let mut entries = entries.lock().expect("lock");
+            entries
+                .get(key)
+                .map(|e| (e.block_number, e.block_hash, e.response.clone()))
+        };
+        let response = cached.and_then(|(block_number, block_hash, response)| {
+            let snap = store.get_snapshot();
+            match snap.get_block_hash_by_number(block_number) {
+                Ok(Some(hash)) if hash == block_hash => Some(response),
+                _ => None,
+            }
+        });
+        let metrics = gw_metrics::rpc();
+        match response {
+            Some(_) => metrics.response_cache_hits(key.method()).inc(),
+            None => metrics.response_cache_misses(key.method()).inc(),
+        };
+        response
+    }
+
+    /// Cache `response` of `key`, which must be immutable once the block
+    /// `block_number`, `block_hash` is on the main chain, e.g. finalized.
+    pub(crate) fn insert(
+        &self,
+        key: CacheKey,
+        block_number: u64,
+        block_hash: H256,
+        response: CachedResponse,
+    ) {
+        if let Some(ref entries) = self.entries {
+            let entry = Entry {
+                block_number,
+                block_hash,
+                response,
+            };
+            entries.lock().expect("lock").put(key, entry);
+        }
+    }
+
+    /// Remove entries of blocks at or above `block_number`.
+    pub fn invalidate_from(&self, block_number: u64) {
+        let entries = match self.entries {
+            Some(ref entries) => entries,
+            None => return,
+        };
+        let mut entries = entries.lock().expect("lock");
+        let keys: Vec<CacheKey> = { entries.iter() }
+            .filter(|(_, e)| e.block_number >= block_number)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in keys.iter() {
+            entries.pop(key);
+        }
+        gw_metrics::rpc()
+            .response_cache_invalidations
+            .inc_by(keys.len() as u64);
+    }
+
+    /// Remove entries of reverted blocks on reorgs, until the new block
+    /// channel is closed.
+    pub async fn invalidate_on_reorgs(&self, subscriptions: Subscriptions) {
+        if self.entries.is_none() {
+            return;
+        }
+        let mut new_blocks = subscriptions.subscribe_new_blocks();
+        let mut last_number = None;
+        loop {
+            let block: L2Block = match new_blocks.recv().await {
+                Ok(block) => block,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::debug!("[response cache] lagged, skipped {} blocks", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let number: u64 = block.raw().number().unpack();
+            if matches!(last_number, Some(last) if number <= last) {
+                self.invalidate_from(number);
+            }
+            last_number = Some(number);
+        }
+    }
+}
+
+/// Whether block `block_number` is finalized, so that responses of it are
+/// immutable.
+pub(crate) fn is_finalized(
+    db: &impl ChainStore,
+    rollup_config: &RollupConfig,
+    block_number: u64,
+) -> bool {
+    let last_confirmed_block_number = db
+        .get_last_confirmed_block_number_hash()
+        .map(|nh| nh.number().unpack())
+        .unwrap_or(0);
+    last_confirmed_block_number >= block_number + rollup_config.finality_blocks().unpack()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate_from() {
+        let cache = ResponseCache::new(10);
+        for number in 0..5u64 {
+            let key = CacheKey::Block(H256::from_u32(number as u32));
+            let response = CachedResponse::TransactionReceipt(Default::default());
+            cache.insert(key, number, H256::zero(), response);
+        }
+        cache.invalidate_from(3);
+
+        let entries = cache.entries.as_ref().unwrap().lock().unwrap();
+        let mut numbers: Vec<u64> = entries.iter().map(|(_, e)| e.block_number).collect();
+        numbers.sort_unstable();
+        assert_eq!(numbers, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,267 @@
+//! REST facade of common queries, served on `rpc_server.listen` if
+//! `rpc_server.enable_rest` is set:
+//!
+//! * `GET /block/{number}`: `gw_get_block_by_number`
+//! * `GET /tx/{hash}`: `gw_get_transaction`
+//! * `GET /tx/{hash}/receipt`: `gw_get_transaction_receipt`
+//! * `GET /account/{address}/balance?sudt_id={id}`: `gw_get_balance`, the
+//!   address is an eth address or a serialized registry address, the sUDT id
+//!   defaults to CKB
+//! * `GET /openapi.json`: the OpenAPI document of these paths
+//!
+//! Requests are translated to JSON-RPC calls, so they share ACLs, rate limits
+//! and concurrency limits with JSON-RPC requests.
+
+use anyhow::Result;
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    registry_address::RegistryAddress,
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{L2BlockView, L2TransactionWithStatus, TxReceipt},
+    schema::U256,
+};
+use gw_version::Version;
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value};
+
+use crate::registry::{
+    BUSY_ERR_CODE, INVALID_PARAM_ERR_CODE, RATE_LIMIT_ERR_CODE, UNAUTHORIZED_ERR_CODE,
+};
+
+pub(crate) enum RestRequest {
+    /// A JSON-RPC request.
+    Call(Value),
+    OpenApi,
+}
+
+/// Parse a REST request, `None` if `path` is not a REST path, `Err` with a
+/// message if the path is malformed.
+pub(crate) fn parse_request(
+    path: &str,
+    query: Option<&str>,
+) -> Option<Result<RestRequest, String>> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let call = match segments.as_slice() {
+        ["openapi.json"] => return Some(Ok(RestRequest::OpenApi)),
+        ["block", number] => parse_number(number)
+            .map(|number| json!(["gw_get_block_by_number", [format!("{:#x}", number)]])),
+        ["tx", hash] => parse_hash(hash).map(|hash| json!(["gw_get_transaction", [hash]])),
+        ["tx", hash, "receipt"] => {
+            parse_hash(hash).map(|hash| json!(["gw_get_transaction_receipt", [hash]]))
+        }
+        ["account", address, "balance"] => parse_address(address).and_then(|address| {
+            let sudt_id = match query_param(query, "sudt_id") {
+                Some(id) => parse_number(id)?,
+                None => CKB_SUDT_ACCOUNT_ID.into(),
+            };
+            Ok(json!([
+                "gw_get_balance",
+                [address, format!("{:#x}", sudt_id)]
+            ]))
+        }),
+        _ => return None,
+    };
+    Some(call.map(|call| {
+        RestRequest::Call(json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": call[0],
+            "params": call[1],
+        }))
+    }))
+}
+
+/// HTTP status and body of the JSON-RPC response of a REST request.
+pub(crate) fn response(rpc_response: &Value) -> (u16, Value) {
+    if let Some(error) = rpc_response.get("error") {
+        let status = match error.get("code").and_then(Value::as_i64) {
+            Some(INVALID_PARAM_ERR_CODE) => 400,
+            Some(UNAUTHORIZED_ERR_CODE) => 401,
+            Some(RATE_LIMIT_ERR_CODE) => 429,
+            Some(BUSY_ERR_CODE) => 503,
+            _ => 500,
+        };
+        return (status, error.clone());
+    }
+    match rpc_response.get("result") {
+        None | Some(Value::Null) => (404, json!({ "message": "not found" })),
+        Some(result) => (200, result.clone()),
+    }
+}
+
+pub(crate) fn error_body(message: String) -> Value {
+    json!({ "code": INVALID_PARAM_ERR_CODE, "message": message })
+}
+
+fn parse_number(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number {}", s))
+}
+
+fn parse_hash(s: &str) -> Result<String, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) if hex.len() == 64 && is_hex(hex) => Ok(s.to_string()),
+        _ => Err(format!("invalid hash {}", s)),
+    }
+}
+
+/// Serialized registry address of an eth address or a serialized registry
+/// address, as `JsonBytes`.
+fn parse_address(s: &str) -> Result<Value, String> {
+    let hex = match s.strip_prefix("0x") {
+        Some(hex) if hex.len() % 2 == 0 && is_hex(hex) => hex,
+        _ => return Err(format!("invalid address {}", s)),
+    };
+    if hex.len() != 40 {
+        return Ok(Value::String(s.to_string()));
+    }
+    let mut address = vec![0u8; 20];
+    faster_hex::hex_decode(hex.as_bytes(), &mut address)
+        .map_err(|_| format!("invalid address {}", s))?;
+    let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, address);
+    Ok(json!(JsonBytes::from_vec(address.to_bytes())))
+}
+
+fn is_hex(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v)
+}
+
+/// OpenAPI document of the REST paths.
+pub(crate) fn openapi_document() -> Result<Value> {
+    let mut gen = SchemaSettings::openapi3()
+        .with(|s| s.definitions_path = "#/components/schemas/".into())
+        .into_generator();
+    let block = serde_json::to_value(gen.subschema_for::<L2BlockView>())?;
+    let tx = serde_json::to_value(gen.subschema_for::<L2TransactionWithStatus>())?;
+    let receipt = serde_json::to_value(gen.subschema_for::<TxReceipt>())?;
+    let balance = serde_json::to_value(gen.subschema_for::<U256>())?;
+
+    let path_param = |name: &str, description: &str| {
+        json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "description": description,
+            "schema": { "type": "string" },
+        })
+    };
+    let get = |summary: &str, parameters: Vec<Value>, schema: Value| {
+        json!({
+            "get": {
+                "summary": summary,
+                "parameters": parameters,
+                "responses": {
+                    "200": {
+                        "description": "OK",
+                        "content": { "application/json": { "schema": schema } },
+                    },
+                    "400": { "description": "Invalid params" },
+                    "404": { "description": "Not found" },
+                },
+            }
+        })
+    };
+    let hash = path_param("hash", "Tx hash");
+    Ok(json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Godwoken REST API",
+            "version": Version::current().to_string(),
+        },
+        "paths": {
+            "/block/{number}": get(
+                "Block by number, see gw_get_block_by_number",
+                vec![path_param("number", "Block number, decimal or 0x prefixed hex")],
+                block,
+            ),
+            "/tx/{hash}": get(
+                "Tx and its status, see gw_get_transaction",
+                vec![hash.clone()],
+                tx,
+            ),
+            "/tx/{hash}/receipt": get(
+                "Tx receipt, see gw_get_transaction_receipt",
+                vec![hash],
+                receipt,
+            ),
+            "/account/{address}/balance": get(
+                "sUDT balance of an address, see gw_get_balance",
+                vec![
+                    path_param("address", "Eth address or serialized registry address"),
+                    json!({
+                        "name": "sudt_id",
+                        "in": "query",
+                        "required": false,
+                        "description": "sUDT account id, default to 1, i.e. CKB",
+                        "schema": { "type": "string" },
+                    }),
+                ],
+                balance,
+            ),
+        },
+        "components": {
+            "schemas": serde_json::to_value(gen.take_definitions())?,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(path: &str, query: Option<&str>) -> Value {
+        match parse_request(path, query) {
+            Some(Ok(RestRequest::Call(call))) => call,
+            _ => panic!("not a call: {}", path),
+        }
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let block = call("/block/16", None);
+        assert_eq!(block["method"], "gw_get_block_by_number");
+        assert_eq!(block["params"], json!(["0x10"]));
+
+        let hash = format!("0x{}", "ab".repeat(32));
+        let receipt = call(&format!("/tx/{}/receipt", hash), None);
+        assert_eq!(receipt["method"], "gw_get_transaction_receipt");
+        assert_eq!(receipt["params"], json!([hash]));
+
+        let balance = call(
+            &format!("/account/0x{}/balance", "11".repeat(20)),
+            Some("sudt_id=3"),
+        );
+        let address = format!("0x0200000014000000{}", "11".repeat(20));
+        assert_eq!(balance["params"], json!([address, "0x3"]));
+
+        assert!(matches!(parse_request("/tx/0x12", None), Some(Err(_))));
+        assert!(parse_request("/", None).is_none());
+        assert!(parse_request("/livez", None).is_none());
+    }
+
+    #[test]
+    fn test_response() {
+        let (status, body) = response(&json!({ "jsonrpc": "2.0", "id": 0, "result": null }));
+        assert_eq!(status, 404);
+        assert_eq!(body["message"], "not found");
+        let error = json!({ "code": INVALID_PARAM_ERR_CODE, "message": "invalid" });
+        let (status, body) = response(&json!({ "jsonrpc": "2.0", "id": 0, "error": error }));
+        assert_eq!((status, body), (400, error));
+        let error = json!({ "code": UNAUTHORIZED_ERR_CODE, "message": "unauthorized" });
+        let (status, _) = response(&json!({ "jsonrpc": "2.0", "id": 0, "error": error }));
+        assert_eq!(status, 401);
+    }
+}
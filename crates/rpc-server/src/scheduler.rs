@@ -0,0 +1,176 @@
+//! Concurrency limits of JSONRPC requests by method class, configured by
+//! `rpc_server.scheduler`.
+//!
+//! Cheap reads, executions and writes have separate slots and queues, so that
+//! e.g. a flood of `gw_execute_raw_l2transaction` doesn't delay
+//! `gw_get_balance`. Requests are rejected as busy once the queue of their
+//! class is full.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use gw_config::{ConcurrencyLimitConfig, RPCSchedulerConfig};
+pub use gw_metrics::rpc::MethodClass;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Methods rejected once the node starts shutting down.
+pub(crate) const WRITE_METHODS: &[&str] =
+    &["gw_submit_l2transaction", "gw_submit_withdrawal_request"];
+
+/// Methods that execute txs.
+const EXECUTION_METHODS: &[&str] = &[
+    "gw_execute_l2transaction",
+    "gw_execute_raw_l2transaction",
+    "gw_batch_execute",
+    "gw_debug_l2transaction",
+    "gw_trace_transaction",
+    "debug_replay_transaction",
+];
+
+pub fn method_class(method: &str) -> MethodClass {
+    if EXECUTION_METHODS.contains(&method) {
+        MethodClass::Execution
+    } else if WRITE_METHODS.contains(&method) {
+        MethodClass::Write
+    } else {
+        MethodClass::Cheap
+    }
+}
+
+/// Class of a single or batch request, the heaviest class of its calls.
+pub fn request_class<'a>(methods: impl IntoIterator<Item = &'a str>) -> MethodClass {
+    methods
+        .into_iter()
+        .map(method_class)
+        .max()
+        .unwrap_or(MethodClass::Cheap)
+}
+
+pub struct RequestScheduler {
+    cheap: Limiter,
+    execution: Limiter,
+    write: Limiter,
+}
+
+impl RequestScheduler {
+    pub fn new(config: &RPCSchedulerConfig) -> Self {
+        Self {
+            cheap: Limiter::new(MethodClass::Cheap, &config.cheap),
+            execution: Limiter::new(MethodClass::Execution, &config.execution),
+            write: Limiter::new(MethodClass::Write, &config.write),
+        }
+    }
+
+    /// Wait for a slot of `class`, `None` if the queue is full. The slot is
+    /// released when the permit is dropped.
+    pub async fn acquire(&self, class: MethodClass) -> Option<OwnedSemaphorePermit> {
+        let limiter = match class {
+            MethodClass::Cheap => &self.cheap,
+            MethodClass::Execution => &self.execution,
+            MethodClass::Write => &self.write,
+        };
+        limiter.acquire().await
+    }
+}
+
+struct Limiter {
+    class: MethodClass,
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+    max_queue: usize,
+}
+
+impl Limiter {
+    fn new(class: MethodClass, config: &ConcurrencyLimitConfig) -> Self {
+        Self {
+            class,
+            semaphore: Arc::new(Semaphore::new(config.max_concurrency.max(1))),
+            queued: AtomicUsize::new(0),
+            max_queue: config.max_queue,
+        }
+    }
+
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = Arc::clone(&self.semaphore).try_acquire_owned() {
+            return Some(permit);
+        }
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queue {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            gw_metrics::rpc()
+                .scheduler_rejected_requests(self.class)
+                .inc();
+            return None;
+        }
+        // Leave the queue even if the request is cancelled while waiting.
+        let _queued = QueuedGuard::new(self);
+        Arc::clone(&self.semaphore).acquire_owned().await.ok()
+    }
+}
+
+struct QueuedGuard<'a>(&'a Limiter);
+
+impl<'a> QueuedGuard<'a> {
+    fn new(limiter: &'a Limiter) -> Self {
+        gw_metrics::rpc()
+            .scheduler_queued_requests(limiter.class)
+            .inc();
+        Self(limiter)
+    }
+}
+
+impl<'a> Drop for QueuedGuard<'a> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::SeqCst);
+        gw_metrics::rpc()
+            .scheduler_queued_requests(self.0.class)
+            .dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_class() {
+        assert_eq!(request_class(["gw_get_balance"]), MethodClass::Cheap);
+        assert_eq!(
+            request_class(["gw_get_balance", "gw_submit_l2transaction"]),
+            MethodClass::Write
+        );
+        assert_eq!(
+            request_class(["gw_submit_l2transaction", "gw_execute_raw_l2transaction"]),
+            MethodClass::Execution
+        );
+        assert_eq!(request_class([]), MethodClass::Cheap);
+    }
+
+    #[tokio::test]
+    async fn test_queue_limit() {
+        let limit = ConcurrencyLimitConfig {
+            max_concurrency: 1,
+            max_queue: 1,
+        };
+        let config = RPCSchedulerConfig {
+            execution: limit,
+            ..Default::default()
+        };
+        let scheduler = Arc::new(RequestScheduler::new(&config));
+
+        let permit = scheduler.acquire(MethodClass::Execution).await.unwrap();
+        let queued = {
+            let scheduler = Arc::clone(&scheduler);
+            tokio::spawn(async move { scheduler.acquire(MethodClass::Execution).await.is_some() })
+        };
+        while scheduler.execution.queued.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        // The queue is full, but cheap requests are not affected.
+        assert!(scheduler.acquire(MethodClass::Execution).await.is_none());
+        assert!(scheduler.acquire(MethodClass::Cheap).await.is_some());
+
+        drop(permit);
+        assert!(queued.await.unwrap());
+        assert_eq!(scheduler.execution.queued.load(Ordering::SeqCst), 0);
+    }
+}
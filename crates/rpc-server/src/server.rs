@@ -1,31 +1,104 @@
 // Taken and adapted from https://github.com/smol-rs/smol/blob/ad0839e1b3700dd33abb9bf23c1efd3c83b5bb2d/examples/hyper-server.rs
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Error, Result};
+use gw_config::RPCServerConfig;
 use gw_telemetry::trace::http::HeaderExtractor;
 use gw_telemetry::traits::{TelemetryContextNewSpan, TelemetryContextRemote};
 use gw_utils::liveness::Liveness;
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{body::HttpBody, server::conn::AddrIncoming, Body, Method, Request, Response, Server};
+use hyper::{
+    body::HttpBody,
+    server::conn::{AddrIncoming, AddrStream},
+    Body, Method, Request, Response, Server,
+};
+use serde::Deserialize;
 use tokio::net::TcpListener;
 
-use jsonrpc_v2::{RequestKind, ResponseObjects, Router, Server as JsonrpcServer};
+use jsonrpc_v2::{MapRouter, RequestKind, ResponseObjects, Router, Server as JsonrpcServer};
 use tokio::sync::{broadcast, mpsc};
 use tracing::Instrument;
 
-use crate::registry::Registry;
+use crate::access::{with_execution_limits, AccessControl, AccessDenied};
+use crate::registry::{
+    Registry, BUSY_ERR_CODE, METHOD_NOT_AVAILABLE_ERR_CODE, RATE_LIMIT_ERR_CODE,
+    UNAUTHORIZED_ERR_CODE,
+};
+use crate::rest::{self, RestRequest};
+use crate::scheduler::{request_class, RequestScheduler, WRITE_METHODS};
 
 pub async fn start_jsonrpc_server(
     listen_addr: SocketAddr,
     registry: Registry,
     liveness: Arc<Liveness>,
     _shutdown_send: mpsc::Sender<()>,
-    mut sub_shutdown: broadcast::Receiver<()>,
+    sub_shutdown: broadcast::Receiver<()>,
 ) -> Result<()> {
+    let access = Arc::new(AccessControl::new(
+        registry.dynamic_config_manager().clone(),
+    ));
+    let scheduler = Arc::new(RequestScheduler::new(&registry.server_config().scheduler));
+    let options = ServeOptions {
+        access: Some(access),
+        scheduler: Some(scheduler),
+        rest: registry.server_config().enable_rest,
+        admin_token: admin_token(registry.server_config()),
+    };
     let rpc_server = registry.build_rpc_server()?;
+    serve_jsonrpc_with_options(listen_addr, rpc_server, liveness, options, sub_shutdown).await
+}
 
+/// Serve `admin_*` methods on `rpc_server.admin.listen`, see
+/// `Registry::build_admin_rpc_server`.
+pub async fn serve_admin_jsonrpc(
+    listen_addr: SocketAddr,
+    rpc_server: Arc<JsonrpcServer<MapRouter>>,
+    liveness: Arc<Liveness>,
+    config: &RPCServerConfig,
+    sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
+    let options = ServeOptions {
+        admin_token: admin_token(config),
+        ..Default::default()
+    };
+    serve_jsonrpc_with_options(listen_addr, rpc_server, liveness, options, sub_shutdown).await
+}
+
+/// Serve an already built JSONRPC server until `sub_shutdown` fires.
+pub async fn serve_jsonrpc(
+    listen_addr: SocketAddr,
+    rpc_server: Arc<JsonrpcServer<MapRouter>>,
+    liveness: Arc<Liveness>,
+    sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
+    let options = ServeOptions::default();
+    serve_jsonrpc_with_options(listen_addr, rpc_server, liveness, options, sub_shutdown).await
+}
+
+/// Checks and extra paths of a JSONRPC server.
+#[derive(Clone, Default)]
+struct ServeOptions {
+    access: Option<Arc<AccessControl>>,
+    scheduler: Option<Arc<RequestScheduler>>,
+    /// Serve the REST paths, see `rest.rs`.
+    rest: bool,
+    /// Bearer token required by `admin_*` methods.
+    admin_token: Option<Arc<str>>,
+}
+
+fn admin_token(config: &RPCServerConfig) -> Option<Arc<str>> {
+    config.admin.auth_token.as_deref().map(Into::into)
+}
+
+async fn serve_jsonrpc_with_options(
+    listen_addr: SocketAddr,
+    rpc_server: Arc<JsonrpcServer<MapRouter>>,
+    liveness: Arc<Liveness>,
+    options: ServeOptions,
+    mut sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
     let listener = TcpListener::bind(listen_addr).await?;
 
     // Format the full address.
@@ -38,9 +111,11 @@ pub async fn start_jsonrpc_server(
     // Start a hyper server.
     let server = Server::builder(incoming)
         .tcp_nodelay(true)
-        .serve(make_service_fn(move |_| {
+        .serve(make_service_fn(move |conn: &AddrStream| {
             let rpc_server = Arc::clone(&rpc_server);
             let liveness = liveness.clone();
+            let options = options.clone();
+            let remote_ip = conn.remote_addr().ip();
             async move {
                 Ok::<_, Error>(service_fn(move |req| {
                     let remote_ctx = gw_telemetry::extract_context(&HeaderExtractor(req.headers()));
@@ -49,7 +124,14 @@ pub async fn start_jsonrpc_server(
                     let serve_span = otel_ctx.new_span(tracing::info_span!("rpc.serve"));
                     serve_span.record("path", req.uri().path());
 
-                    serve(Arc::clone(&rpc_server), liveness.clone(), req).instrument(serve_span)
+                    serve(
+                        Arc::clone(&rpc_server),
+                        liveness.clone(),
+                        options.clone(),
+                        remote_ip,
+                        req,
+                    )
+                    .instrument(serve_span)
                 }))
             }
         }));
@@ -62,12 +144,25 @@ pub async fn start_jsonrpc_server(
     Ok(())
 }
 
+/// Serve only `/metrics`, `/livez` and `/readyz`, for processes without RPCs.
+pub async fn serve_metrics(
+    listen_addr: SocketAddr,
+    liveness: Arc<Liveness>,
+    sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
+    let rpc_server = JsonrpcServer::new().finish();
+    serve_jsonrpc(listen_addr, rpc_server, liveness, sub_shutdown).await
+}
+
 // Serves a request and returns a response.
 async fn serve<R: Router + 'static>(
     rpc: Arc<JsonrpcServer<R>>,
     liveness: Arc<Liveness>,
+    options: ServeOptions,
+    remote_ip: IpAddr,
     req: Request<Body>,
 ) -> Result<Response<Body>> {
+    let access = options.access.map(|access| (access, remote_ip));
     if (req.method() == Method::GET || req.method() == Method::HEAD) && req.uri().path() == "/livez"
     {
         return hyper::Response::builder()
@@ -80,6 +175,19 @@ async fn serve<R: Router + 'static>(
             .map_err(anyhow::Error::new);
     }
 
+    if (req.method() == Method::GET || req.method() == Method::HEAD)
+        && req.uri().path() == "/readyz"
+    {
+        return hyper::Response::builder()
+            .status(if liveness.is_ready() {
+                hyper::StatusCode::OK
+            } else {
+                hyper::StatusCode::SERVICE_UNAVAILABLE
+            })
+            .body(Body::empty())
+            .map_err(anyhow::Error::new);
+    }
+
     if (req.method() == Method::GET || req.method() == Method::HEAD)
         && req.uri().path() == "/metrics"
     {
@@ -108,6 +216,20 @@ async fn serve<R: Router + 'static>(
             .map_err(|e| anyhow::anyhow!("JSONRPC Preflight Request error: {:?}", e));
     }
 
+    let rest_call = if options.rest && req.method() == Method::GET {
+        match rest::parse_request(req.uri().path(), req.uri().query()) {
+            Some(Ok(RestRequest::OpenApi)) => {
+                return json_response(200, &rest::openapi_document()?);
+            }
+            Some(Ok(RestRequest::Call(call))) => Some(call),
+            Some(Err(message)) => return json_response(400, &rest::error_body(message)),
+            None => None,
+        }
+    } else {
+        None
+    };
+    let is_rest = rest_call.is_some();
+
     // Handler here is adapted from https://github.com/kardeiz/jsonrpc-v2/blob/1acf0b911c698413950d0b101ec4255cabd0d4ec/src/lib.rs#L1302
     let mut buf = if let Some(content_length) = req
         .headers()
@@ -120,17 +242,86 @@ async fn serve<R: Router + 'static>(
         bytes_v10::BytesMut::default()
     };
 
+    let client_ip = access
+        .as_ref()
+        .map(|(access, remote_ip)| access.client_ip(req.headers(), *remote_ip));
+    let execution_limits = access
+        .as_ref()
+        .map(|(access, _)| access.execution_limits(req.headers()))
+        .unwrap_or_default();
+    let admin_authorized = match options.admin_token {
+        Some(ref token) => is_bearer_token(req.headers(), token),
+        None => true,
+    };
     let mut body = req.into_body();
 
     while let Some(chunk) = body.data().await {
         buf.extend(chunk?);
     }
+    if let Some(call) = rest_call {
+        buf = bytes_v10::BytesMut::from(serde_json::to_vec(&call)?.as_slice());
+    }
 
-    match rpc
-        .handle(RequestKind::Bytes(buf.freeze()))
-        .instrument(tracing::info_span!("rpc.handle"))
-        .await
+    let calls = parse_calls(&buf);
+
+    if !admin_authorized && calls.iter().any(|c| c.method.starts_with("admin_")) {
+        return error_response(
+            reply_id(&calls),
+            UNAUTHORIZED_ERR_CODE,
+            "Invalid or missing admin auth token".to_string(),
+            is_rest,
+        );
+    }
+
+    if let (Some((access, _)), Some(client_ip)) = (access, client_ip) {
+        if access.is_enabled() {
+            let methods: Vec<String> = calls.iter().map(|c| c.method.clone()).collect();
+            if let Err(denied) = access.check(client_ip, &methods) {
+                return access_denied_response(reply_id(&calls), denied, is_rest);
+            }
+        }
+    }
+
+    if liveness.is_shutting_down()
+        && calls
+            .iter()
+            .any(|c| WRITE_METHODS.contains(&c.method.as_str()))
     {
+        return error_response(
+            reply_id(&calls),
+            BUSY_ERR_CODE,
+            "Node is shutting down".to_string(),
+            is_rest,
+        );
+    }
+
+    // Hold the slot until the response is built.
+    let _permit = match options.scheduler {
+        Some(scheduler) => {
+            let class = request_class(calls.iter().map(|c| c.method.as_str()));
+            match scheduler.acquire(class).await {
+                Some(permit) => Some(permit),
+                None => {
+                    return error_response(
+                        reply_id(&calls),
+                        BUSY_ERR_CODE,
+                        "Too many requests, please try again later".to_string(),
+                        is_rest,
+                    );
+                }
+            }
+        }
+        None => None,
+    };
+
+    let handle = rpc
+        .handle(RequestKind::Bytes(buf.freeze()))
+        .instrument(tracing::info_span!("rpc.handle"));
+    match with_execution_limits(execution_limits, handle).await {
+        json if is_rest => {
+            let (status, body) = rest::response(&serde_json::to_value(&json)?);
+            return json_response(status, &body);
+        }
         ResponseObjects::Empty => hyper::Response::builder()
             .status(hyper::StatusCode::NO_CONTENT)
             .body(hyper::Body::from(Vec::<u8>::new()))
@@ -150,3 +341,221 @@ async fn serve<R: Router + 'static>(
     }
     .map_err(|e| anyhow::anyhow!("JSONRPC Request error: {:?}", e))
 }
+
+#[derive(Deserialize)]
+struct Call {
+    method: String,
+    #[serde(default)]
+    id: serde_json::Value,
+}
+
+/// Calls of a single or batch request. Invalid requests are left to the
+/// JSONRPC server to reply.
+fn parse_calls(body: &[u8]) -> Vec<Call> {
+    if let Ok(calls) = serde_json::from_slice::<Vec<Call>>(body) {
+        return calls;
+    }
+    serde_json::from_slice::<Call>(body)
+        .map(|call| vec![call])
+        .unwrap_or_default()
+}
+
+/// Whether the `Authorization` header is `Bearer <token>`.
+fn is_bearer_token(headers: &hyper::HeaderMap, token: &str) -> bool {
+    let provided = headers
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match provided {
+        // Compare in constant time.
+        Some(provided) if provided.len() == token.len() => {
+            { provided.bytes().zip(token.bytes()) }.fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+        }
+        _ => false,
+    }
+}
+
+/// Id of the error reply, a single error is replied for batch requests too.
+fn reply_id(calls: &[Call]) -> serde_json::Value {
+    match calls {
+        [call] => call.id.clone(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+fn access_denied_response(
+    id: serde_json::Value,
+    denied: AccessDenied,
+    rest: bool,
+) -> Result<Response<Body>> {
+    let (code, message) = match denied {
+        AccessDenied::MethodNotAllowed(method) => (
+            METHOD_NOT_AVAILABLE_ERR_CODE,
+            format!("Method {} is not allowed", method),
+        ),
+        AccessDenied::RateLimited(method) => (
+            RATE_LIMIT_ERR_CODE,
+            format!(
+                "Rate limit of {}, please wait few seconds and try again",
+                method
+            ),
+        ),
+    };
+    error_response(id, code, message, rest)
+}
+
+fn error_response(
+    id: serde_json::Value,
+    code: i64,
+    message: String,
+    rest: bool,
+) -> Result<Response<Body>> {
+    let json = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    });
+    if rest {
+        let (status, body) = rest::response(&json);
+        return json_response(status, &body);
+    }
+    json_response(200, &json)
+}
+
+fn json_response(status: u16, json: &serde_json::Value) -> Result<Response<Body>> {
+    hyper::Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Methods", "*")
+        .header("Access-Control-Allow-Headers", "*")
+        .body(hyper::Body::from(serde_json::to_vec(&json)?))
+        .map_err(anyhow::Error::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{header::AUTHORIZATION, HeaderMap};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const TOKEN: &str = "secret";
+
+    async fn pong() -> Result<&'static str> {
+        Ok("pong")
+    }
+
+    async fn no_block() -> Result<Option<()>> {
+        Ok(None)
+    }
+
+    fn admin_options() -> ServeOptions {
+        ServeOptions {
+            rest: true,
+            admin_token: Some(TOKEN.into()),
+            ..Default::default()
+        }
+    }
+
+    async fn request(
+        options: ServeOptions,
+        req: hyper::http::request::Builder,
+        body: Value,
+    ) -> (u16, Value) {
+        let rpc = JsonrpcServer::new()
+            .with_method("admin_ping", pong)
+            .with_method("gw_ping", pong)
+            .with_method("gw_get_block_by_number", no_block)
+            .finish();
+        let liveness = Arc::new(Liveness::new(Duration::from_secs(60)));
+        let req = req
+            .body(Body::from(serde_json::to_vec(&body).unwrap()))
+            .unwrap();
+        let remote_ip = IpAddr::from([127, 0, 0, 1]);
+        let resp = serve(rpc, liveness, options, remote_ip, req).await.unwrap();
+        let status = resp.status().as_u16();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn post(token: Option<&str>) -> hyper::http::request::Builder {
+        let req = Request::builder().method(Method::POST).uri("/");
+        match token {
+            Some(token) => req.header(AUTHORIZATION, format!("Bearer {}", token)),
+            None => req,
+        }
+    }
+
+    fn call(method: &str) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": [] })
+    }
+
+    #[test]
+    fn test_is_bearer_token() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
+        assert!(is_bearer_token(&headers("Bearer secret"), TOKEN));
+        assert!(!is_bearer_token(&HeaderMap::new(), TOKEN));
+        assert!(!is_bearer_token(&headers("Bearer secreT"), TOKEN));
+        assert!(!is_bearer_token(&headers("Bearer secret2"), TOKEN));
+        assert!(!is_bearer_token(&headers("Bearer "), TOKEN));
+        assert!(!is_bearer_token(&headers("Basic secret"), TOKEN));
+        assert!(!is_bearer_token(&headers("secret"), TOKEN));
+    }
+
+    #[tokio::test]
+    async fn test_admin_auth() {
+        let (_, resp) = request(admin_options(), post(Some(TOKEN)), call("admin_ping")).await;
+        assert_eq!(resp["result"], "pong");
+
+        for token in [None, Some("wrong")] {
+            let (status, resp) = request(admin_options(), post(token), call("admin_ping")).await;
+            assert_eq!(status, 200);
+            assert_eq!(resp["id"], 1);
+            assert_eq!(resp["error"]["code"], UNAUTHORIZED_ERR_CODE);
+        }
+
+        // Other methods don't need the token.
+        let (_, resp) = request(admin_options(), post(None), call("gw_ping")).await;
+        assert_eq!(resp["result"], "pong");
+
+        // Without the token, admin methods are not checked, e.g. on
+        // `rpc_server.admin.listen` without `auth_token`.
+        let (_, resp) = request(ServeOptions::default(), post(None), call("admin_ping")).await;
+        assert_eq!(resp["result"], "pong");
+    }
+
+    #[tokio::test]
+    async fn test_admin_auth_batch() {
+        let batch = json!([call("gw_ping"), call("admin_ping")]);
+        let (_, resp) = request(admin_options(), post(None), batch.clone()).await;
+        assert_eq!(resp["id"], Value::Null);
+        assert_eq!(resp["error"]["code"], UNAUTHORIZED_ERR_CODE);
+
+        let (_, resp) = request(admin_options(), post(Some(TOKEN)), batch).await;
+        let results: Vec<&Value> = resp
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| &r["result"])
+            .collect();
+        assert_eq!(results, [&json!("pong"), &json!("pong")]);
+    }
+
+    #[tokio::test]
+    async fn test_admin_auth_rest() {
+        // REST paths are not admin methods, they don't need the token.
+        let get = Request::builder().method(Method::GET).uri("/block/1");
+        let (status, resp) = request(admin_options(), get, Value::Null).await;
+        assert_eq!((status, resp), (404, json!({ "message": "not found" })));
+
+        // Admin calls in the body of a GET request are still checked.
+        let get = Request::builder().method(Method::GET).uri("/");
+        let (_, resp) = request(admin_options(), get, call("admin_ping")).await;
+        assert_eq!(resp["error"]["code"], UNAUTHORIZED_ERR_CODE);
+    }
+}
@@ -0,0 +1,277 @@
+//! WebSocket subscriptions of new blocks, logs and pending txs.
+//!
+//! Subscribe with `gw_subscribe`, e.g.
+//! `{"jsonrpc":"2.0","id":1,"method":"gw_subscribe","params":["newHeads"]}`,
+//! and notifications are sent as `gw_subscription` requests with the
+//! subscription id.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use gw_jsonrpc_types::godwoken::L2BlockHeaderView;
+use gw_mem_pool::subscription::Subscriptions;
+use gw_store::Store;
+use gw_types::{h256::*, packed::L2Block, prelude::*};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::logs::{block_logs, LogFilter};
+use crate::utils::to_jsonh256;
+
+const INVALID_REQUEST_ERR_CODE: i64 = -32600;
+const METHOD_NOT_FOUND_ERR_CODE: i64 = -32601;
+const INVALID_PARAMS_ERR_CODE: i64 = -32602;
+const TOO_MANY_SUBSCRIPTIONS_ERR_CODE: i64 = -32005;
+
+/// Max subscriptions of a connection.
+const MAX_SUBSCRIPTIONS: usize = 32;
+/// Notifications buffered for a connection before subscriptions lag.
+const NOTIFICATION_BUFFER: usize = 1024;
+
+pub async fn start_ws_server(
+    listen_addr: SocketAddr,
+    store: Store,
+    subscriptions: Subscriptions,
+    mut sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
+    let listener = TcpListener::bind(listen_addr).await?;
+    log::info!("WebSocket server listening on {}", listen_addr);
+    loop {
+        tokio::select! {
+            _ = sub_shutdown.recv() => {
+                log::info!("WebSocket server exited successfully");
+                return Ok(());
+            }
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        log::warn!("WebSocket accept error: {}", err);
+                        continue;
+                    }
+                };
+                let store = store.clone();
+                let subscriptions = subscriptions.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = serve_connection(stream, store, subscriptions).await {
+                        log::debug!("WebSocket connection {} error: {}", peer, err);
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    store: Store,
+    subscriptions: Subscriptions,
+) -> Result<()> {
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut ws_send, mut ws_recv) = ws.split();
+    let (notify_send, mut notify_recv) = mpsc::channel::<String>(NOTIFICATION_BUFFER);
+    let mut connection = Connection {
+        store,
+        subscriptions,
+        notify_send,
+        tasks: HashMap::new(),
+        next_id: 0,
+    };
+
+    let result = loop {
+        tokio::select! {
+            msg = ws_recv.next() => {
+                let msg = match msg {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(err)) => break Err(err.into()),
+                    None => break Ok(()),
+                };
+                let reply = match msg {
+                    Message::Text(text) => connection.handle(&text),
+                    Message::Ping(data) => Message::Pong(data),
+                    Message::Close(_) => break Ok(()),
+                    _ => continue,
+                };
+                if let Err(err) = ws_send.send(reply).await {
+                    break Err(err.into());
+                }
+            }
+            Some(notification) = notify_recv.recv() => {
+                if let Err(err) = ws_send.send(Message::Text(notification)).await {
+                    break Err(err.into());
+                }
+            }
+        }
+    };
+
+    for (_, task) in connection.tasks.drain() {
+        task.abort();
+    }
+    result
+}
+
+struct Connection {
+    store: Store,
+    subscriptions: Subscriptions,
+    notify_send: mpsc::Sender<String>,
+    tasks: HashMap<String, JoinHandle<()>>,
+    next_id: u64,
+}
+
+impl Connection {
+    fn handle(&mut self, text: &str) -> Message {
+        let req: Request = match serde_json::from_str(text) {
+            Ok(req) => req,
+            Err(err) => {
+                return error_reply(Value::Null, INVALID_REQUEST_ERR_CODE, err.to_string());
+            }
+        };
+        let result = match req.method.as_str() {
+            "gw_subscribe" => self.subscribe(&req.params),
+            "gw_unsubscribe" => self.unsubscribe(&req.params),
+            method => Err((
+                METHOD_NOT_FOUND_ERR_CODE,
+                format!("Method {} not found", method),
+            )),
+        };
+        match result {
+            Ok(result) => Message::Text(
+                json!({ "jsonrpc": "2.0", "id": req.id, "result": result }).to_string(),
+            ),
+            Err((code, message)) => error_reply(req.id, code, message),
+        }
+    }
+
+    fn subscribe(&mut self, params: &[Value]) -> Result<Value, (i64, String)> {
+        if self.tasks.len() >= MAX_SUBSCRIPTIONS {
+            return Err((
+                TOO_MANY_SUBSCRIPTIONS_ERR_CODE,
+                format!("Too many subscriptions, max {}", MAX_SUBSCRIPTIONS),
+            ));
+        }
+        let kind = params.get(0).and_then(Value::as_str).unwrap_or_default();
+        let id = format!("{:#x}", self.next_id);
+        let notify_send = self.notify_send.clone();
+        let task = match kind {
+            "newHeads" => {
+                let recv = self.subscriptions.subscribe_new_blocks();
+                let id = id.clone();
+                tokio::spawn(forward(recv, notify_send, move |block: L2Block| {
+                    vec![notification(&id, &L2BlockHeaderView::from(block))]
+                }))
+            }
+            "newPendingTransactions" => {
+                let recv = self.subscriptions.subscribe_pending_txs();
+                let id = id.clone();
+                tokio::spawn(forward(recv, notify_send, move |tx_hash: H256| {
+                    vec![notification(&id, &to_jsonh256(tx_hash))]
+                }))
+            }
+            "logs" => {
+                let filter: LogFilter = match params.get(1) {
+                    Some(filter) => serde_json::from_value(filter.clone())
+                        .map_err(|err| (INVALID_PARAMS_ERR_CODE, err.to_string()))?,
+                    None => LogFilter::default(),
+                };
+                let recv = self.subscriptions.subscribe_new_blocks();
+                let store = self.store.clone();
+                let id = id.clone();
+                tokio::spawn(forward(
+                    recv,
+                    notify_send,
+                    move |block: L2Block| match block_logs(&store.get_snapshot(), &block) {
+                        Ok(logs) => logs
+                            .into_iter()
+                            .filter(|log| filter.matches(log))
+                            .map(|log| notification(&id, &log))
+                            .collect(),
+                        Err(err) => {
+                            log::warn!("[subscription] get logs error: {}", err);
+                            Vec::new()
+                        }
+                    },
+                ))
+            }
+            kind => {
+                return Err((
+                    INVALID_PARAMS_ERR_CODE,
+                    format!("Unknown subscription {:?}", kind),
+                ));
+            }
+        };
+        self.next_id += 1;
+        self.tasks.insert(id.clone(), task);
+        Ok(Value::String(id))
+    }
+
+    fn unsubscribe(&mut self, params: &[Value]) -> Result<Value, (i64, String)> {
+        let id = params.get(0).and_then(Value::as_str).unwrap_or_default();
+        let removed = match self.tasks.remove(id) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        };
+        Ok(Value::Bool(removed))
+    }
+}
+
+/// Forward messages of `recv` to the connection as notifications.
+async fn forward<T: Clone>(
+    mut recv: broadcast::Receiver<T>,
+    notify_send: mpsc::Sender<String>,
+    to_notifications: impl Fn(T) -> Vec<String>,
+) {
+    loop {
+        let msg = match recv.recv().await {
+            Ok(msg) => msg,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::debug!("[subscription] lagged, skipped {} messages", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let notifications = tokio::task::block_in_place(|| to_notifications(msg));
+        for notification in notifications {
+            if notify_send.send(notification).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn notification(id: &str, result: &impl Serialize) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "gw_subscription",
+        "params": { "subscription": id, "result": result },
+    })
+    .to_string()
+}
+
+fn error_reply(id: Value, code: i64, message: String) -> Message {
+    Message::Text(
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        })
+        .to_string(),
+    )
+}
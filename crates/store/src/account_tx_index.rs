@@ -0,0 +1,80 @@
+//! Index of txs by account, i.e. the sender (`from_id`) and the receiver
+//! (`to_id`) of txs, for the history of an account without scanning blocks.
+//!
+//! Keys are the account id followed by the block number and the tx index in
+//! the block, all in big endian so that txs of an account are in order.
+//! Values are the `ROLE_*` flags of the account followed by the tx hash.
+//!
+//! Only txs of valid blocks on the main chain are indexed.
+
+use std::convert::TryInto;
+
+use gw_types::{h256::*, packed::L2Block, prelude::*};
+
+/// The account sent the tx.
+pub const ROLE_SENDER: u8 = 0b01;
+/// The tx was sent to the account.
+pub const ROLE_RECEIVER: u8 = 0b10;
+
+/// Account id, block number and tx index.
+const KEY_LEN: usize = 4 + 8 + 4;
+/// Role flags and tx hash.
+const VALUE_LEN: usize = 1 + 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountTx {
+    pub block_number: u64,
+    pub tx_index: u32,
+    pub tx_hash: H256,
+    /// `ROLE_*` flags.
+    pub role: u8,
+}
+
+/// Key of a tx of `account_id` at `block_number`, `tx_index`.
+pub fn position_key(account_id: u32, block_number: u64, tx_index: u32) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    key[..4].copy_from_slice(&account_id.to_be_bytes());
+    key[4..12].copy_from_slice(&block_number.to_be_bytes());
+    key[12..].copy_from_slice(&tx_index.to_be_bytes());
+    key
+}
+
+/// Index entries of txs of `block`, as (key, value).
+pub(crate) fn block_entries(block: &L2Block) -> Vec<([u8; KEY_LEN], [u8; VALUE_LEN])> {
+    let block_number = block.raw().number().unpack();
+    let mut entries = Vec::with_capacity(block.transactions().len() * 2);
+    for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+        let raw = tx.raw();
+        let from_id: u32 = raw.from_id().unpack();
+        let to_id: u32 = raw.to_id().unpack();
+        let mut roles = vec![(from_id, ROLE_SENDER)];
+        if to_id == from_id {
+            roles[0].1 |= ROLE_RECEIVER;
+        } else {
+            roles.push((to_id, ROLE_RECEIVER));
+        }
+        for (account_id, role) in roles {
+            let mut value = [0u8; VALUE_LEN];
+            value[0] = role;
+            value[1..].copy_from_slice(&tx.hash());
+            entries.push((
+                position_key(account_id, block_number, tx_index as u32),
+                value,
+            ));
+        }
+    }
+    entries
+}
+
+/// Decode an index entry, `None` if `key` is not of `account_id`.
+pub(crate) fn decode_entry(account_id: u32, key: &[u8], value: &[u8]) -> Option<AccountTx> {
+    if key.len() != KEY_LEN || key[..4] != account_id.to_be_bytes() || value.len() != VALUE_LEN {
+        return None;
+    }
+    Some(AccountTx {
+        block_number: u64::from_be_bytes(key[4..12].try_into().ok()?),
+        tx_index: u32::from_be_bytes(key[12..].try_into().ok()?),
+        tx_hash: value[1..].try_into().ok()?,
+        role: value[0],
+    })
+}
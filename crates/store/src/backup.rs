@@ -0,0 +1,131 @@
+//! Backups of the database, i.e. RocksDB checkpoints with a manifest of what
+//! they contain.
+//!
+//! A backup directory contains the checkpoint in `db` and `manifest.json`.
+//! Backups are created from the primary database, by `admin_create_backup` of
+//! a running node or `godwoken backup` of a stopped one.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use ckb_fixed_hash::H256;
+use gw_types::prelude::Unpack;
+use serde::{Deserialize, Serialize};
+
+use crate::{readonly::StoreReadonly, schema::COLUMNS, traits::chain_store::ChainStore, Store};
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+const DB_DIR_NAME: &str = "db";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub rollup_type_hash: H256,
+    pub tip_block_number: u64,
+    pub tip_block_hash: H256,
+    /// Version of the node that created the backup.
+    pub version: String,
+}
+
+impl BackupManifest {
+    pub fn read(backup_dir: &Path) -> Result<Self> {
+        let path = backup_dir.join(MANIFEST_FILE_NAME);
+        let content = fs::read(&path)
+            .with_context(|| format!("read manifest from {}", path.to_string_lossy()))?;
+        serde_json::from_slice(&content).context("parse manifest")
+    }
+
+    fn write(&self, backup_dir: &Path) -> Result<()> {
+        let path = backup_dir.join(MANIFEST_FILE_NAME);
+        let content = serde_json::to_vec_pretty(self)?;
+        fs::write(&path, content)
+            .with_context(|| format!("write manifest to {}", path.to_string_lossy()))
+    }
+
+    fn check_tip(&self, store: &StoreReadonly) -> Result<()> {
+        let tip = store.get_tip_block()?;
+        let tip_block_number: u64 = tip.raw().number().unpack();
+        let tip_block_hash = H256(tip.hash());
+        if tip_block_number != self.tip_block_number || tip_block_hash != self.tip_block_hash {
+            bail!(
+                "tip block mismatch, manifest: {} {}, db: {} {}",
+                self.tip_block_number,
+                self.tip_block_hash,
+                tip_block_number,
+                tip_block_hash
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Checkpoint `store` to `backup_dir`, which must not exist.
+pub fn create_backup(
+    store: &Store,
+    rollup_type_hash: H256,
+    version: String,
+    backup_dir: &Path,
+) -> Result<BackupManifest> {
+    if backup_dir.exists() {
+        bail!("backup dir {} already exists", backup_dir.to_string_lossy());
+    }
+    fs::create_dir_all(backup_dir)?;
+    let db_dir = backup_dir.join(DB_DIR_NAME);
+    store
+        .create_checkpoint(&db_dir)
+        .context("create checkpoint")?;
+
+    // The tip of the store may have moved on, read the tip of the checkpoint.
+    let checkpoint = StoreReadonly::open(&db_dir, COLUMNS).context("open checkpoint")?;
+    let tip = checkpoint.get_tip_block()?;
+    let manifest = BackupManifest {
+        rollup_type_hash,
+        tip_block_number: tip.raw().number().unpack(),
+        tip_block_hash: H256(tip.hash()),
+        version,
+    };
+    manifest.write(backup_dir)?;
+    Ok(manifest)
+}
+
+/// Restore the backup in `backup_dir` to `store_path`, which must not exist.
+///
+/// SST files are hard linked if possible, and other files are copied, so the
+/// backup is left untouched when the restored database is opened.
+pub fn restore_backup(
+    backup_dir: &Path,
+    rollup_type_hash: &H256,
+    store_path: &Path,
+) -> Result<BackupManifest> {
+    let manifest = BackupManifest::read(backup_dir)?;
+    if &manifest.rollup_type_hash != rollup_type_hash {
+        bail!(
+            "rollup type hash mismatch, backup: {}, config: {}",
+            manifest.rollup_type_hash,
+            rollup_type_hash
+        );
+    }
+    if store_path.exists() {
+        bail!("store path {} already exists", store_path.to_string_lossy());
+    }
+
+    let db_dir = backup_dir.join(DB_DIR_NAME);
+    {
+        let backup = StoreReadonly::open(&db_dir, COLUMNS).context("open backup database")?;
+        manifest.check_tip(&backup)?;
+    }
+
+    fs::create_dir_all(store_path)?;
+    for entry in fs::read_dir(&db_dir)? {
+        let entry = entry?;
+        let from = entry.path();
+        let to = store_path.join(entry.file_name());
+        let is_sst = from.extension().map_or(false, |ext| ext == "sst");
+        if !is_sst || fs::hard_link(&from, &to).is_err() {
+            fs::copy(&from, &to).with_context(|| format!("copy {}", from.to_string_lossy()))?;
+        }
+    }
+
+    let restored = StoreReadonly::open(store_path, COLUMNS).context("open restored database")?;
+    manifest.check_tip(&restored)?;
+    Ok(manifest)
+}
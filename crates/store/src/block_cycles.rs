@@ -0,0 +1,153 @@
+//! Cycles of a block by backend and by syscall class, recorded when the block
+//! is inserted, to see what consumes the block capacity.
+//!
+//! Syscall classes are the names of `syscall_name`, with cycles counted by
+//! `SyscallCyclesConfig` of the node.
+
+use std::convert::TryInto;
+
+use gw_types::h256::*;
+
+/// validator script type hash | tx count | execution cycles | syscall cycles
+const BACKEND_LEN: usize = 32 + 4 + 8 + 8;
+/// count | cycles | name len, followed by the name
+const SYSCALL_HEADER_LEN: usize = 8 + 8 + 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackendCycles {
+    pub validator_script_type_hash: H256,
+    pub tx_count: u32,
+    /// VM cycles, including syscall cycles.
+    pub execution_cycles: u64,
+    pub syscall_cycles: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyscallCycles {
+    pub name: String,
+    pub count: u64,
+    pub cycles: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockCyclesReport {
+    /// In order of first tx of the backend.
+    pub backends: Vec<BackendCycles>,
+    /// In order of name.
+    pub syscalls: Vec<SyscallCycles>,
+}
+
+impl BlockCyclesReport {
+    /// Add a tx of the backend, with its syscalls as (class, count, cycles).
+    pub fn add_tx<'a>(
+        &mut self,
+        validator_script_type_hash: H256,
+        execution_cycles: u64,
+        syscalls: impl IntoIterator<Item = (&'a str, u64, u64)>,
+    ) {
+        let pos = self
+            .backends
+            .iter()
+            .position(|b| b.validator_script_type_hash == validator_script_type_hash);
+        let backend = match pos {
+            Some(pos) => &mut self.backends[pos],
+            None => {
+                self.backends.push(BackendCycles {
+                    validator_script_type_hash,
+                    ..Default::default()
+                });
+                self.backends.last_mut().expect("pushed")
+            }
+        };
+        backend.tx_count += 1;
+        backend.execution_cycles = backend.execution_cycles.saturating_add(execution_cycles);
+        for (name, count, cycles) in syscalls {
+            backend.syscall_cycles = backend.syscall_cycles.saturating_add(cycles);
+            let syscall = match self
+                .syscalls
+                .binary_search_by(|s| s.name.as_str().cmp(name))
+            {
+                Ok(pos) => &mut self.syscalls[pos],
+                Err(pos) => {
+                    self.syscalls.insert(
+                        pos,
+                        SyscallCycles {
+                            name: name.to_string(),
+                            ..Default::default()
+                        },
+                    );
+                    &mut self.syscalls[pos]
+                }
+            };
+            syscall.count = syscall.count.saturating_add(count);
+            syscall.cycles = syscall.cycles.saturating_add(cycles);
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            8 + self.backends.len() * BACKEND_LEN + self.syscalls.len() * SYSCALL_HEADER_LEN * 2,
+        );
+        buf.extend_from_slice(&(self.backends.len() as u32).to_le_bytes());
+        for backend in self.backends.iter() {
+            buf.extend_from_slice(&backend.validator_script_type_hash);
+            buf.extend_from_slice(&backend.tx_count.to_le_bytes());
+            buf.extend_from_slice(&backend.execution_cycles.to_le_bytes());
+            buf.extend_from_slice(&backend.syscall_cycles.to_le_bytes());
+        }
+        buf.extend_from_slice(&(self.syscalls.len() as u32).to_le_bytes());
+        for syscall in self.syscalls.iter() {
+            // Names are short constants
+            let name = &syscall.name.as_bytes()[..syscall.name.len().min(u8::MAX as usize)];
+            buf.extend_from_slice(&syscall.count.to_le_bytes());
+            buf.extend_from_slice(&syscall.cycles.to_le_bytes());
+            buf.push(name.len() as u8);
+            buf.extend_from_slice(name);
+        }
+        buf
+    }
+
+    /// `None` if the slice is malformed.
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        let (count, mut rest) = read_u32(slice)?;
+        let backends_len = (count as usize).checked_mul(BACKEND_LEN)?;
+        if rest.len() < backends_len {
+            return None;
+        }
+        let backends = rest[..backends_len]
+            .chunks_exact(BACKEND_LEN)
+            .map(|chunk| BackendCycles {
+                validator_script_type_hash: chunk[..32].try_into().expect("32 bytes"),
+                tx_count: u32::from_le_bytes(chunk[32..36].try_into().expect("4 bytes")),
+                execution_cycles: u64::from_le_bytes(chunk[36..44].try_into().expect("8 bytes")),
+                syscall_cycles: u64::from_le_bytes(chunk[44..52].try_into().expect("8 bytes")),
+            })
+            .collect();
+        rest = &rest[backends_len..];
+
+        let (count, mut rest) = read_u32(rest)?;
+        let mut syscalls = Vec::with_capacity(count.min(64) as usize);
+        for _ in 0..count {
+            if rest.len() < SYSCALL_HEADER_LEN {
+                return None;
+            }
+            let name_len = rest[16] as usize;
+            let name = rest.get(SYSCALL_HEADER_LEN..SYSCALL_HEADER_LEN + name_len)?;
+            syscalls.push(SyscallCycles {
+                name: String::from_utf8(name.to_vec()).ok()?,
+                count: u64::from_le_bytes(rest[0..8].try_into().ok()?),
+                cycles: u64::from_le_bytes(rest[8..16].try_into().ok()?),
+            });
+            rest = &rest[SYSCALL_HEADER_LEN + name_len..];
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+        Some(BlockCyclesReport { backends, syscalls })
+    }
+}
+
+fn read_u32(slice: &[u8]) -> Option<(u32, &[u8])> {
+    let n = u32::from_le_bytes(slice.get(..4)?.try_into().ok()?);
+    Some((n, &slice[4..]))
+}
@@ -0,0 +1,87 @@
+//! State changes of a block, recorded when the block is inserted, for
+//! indexers to mirror the L2 state without re-executing txs.
+//!
+//! Keys are collected by the state tracker while the block is applied, values
+//! are read from the history state before and after the block.
+
+use std::convert::TryInto;
+
+use gw_types::h256::*;
+
+use crate::state::history::history_state::HistoryStateStore;
+
+/// key | old value | new value
+const ENTRY_LEN: usize = 32 * 3;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiffEntry {
+    pub key: H256,
+    /// Zero if the key didn't exist.
+    pub old_value: H256,
+    pub new_value: H256,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockStateDiff {
+    /// In ascending order of key.
+    pub entries: Vec<StateDiffEntry>,
+}
+
+impl BlockStateDiff {
+    /// Diff of `touched_keys` by the history state of the block, keys whose
+    /// values are not changed by the block are skipped.
+    pub fn from_history<S: HistoryStateStore>(
+        store: &S,
+        block_number: u64,
+        touched_keys: impl IntoIterator<Item = H256>,
+    ) -> Self {
+        let mut entries: Vec<_> = touched_keys
+            .into_iter()
+            .filter_map(|key| {
+                let old_value = match block_number.checked_sub(1) {
+                    Some(prev) => store.get_history_state(prev, &key).unwrap_or_default(),
+                    None => H256::zero(),
+                };
+                let new_value = store
+                    .get_history_state(block_number, &key)
+                    .unwrap_or_default();
+                (old_value != new_value).then(|| StateDiffEntry {
+                    key,
+                    old_value,
+                    new_value,
+                })
+            })
+            .collect();
+        entries.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+        entries.dedup_by(|a, b| a.key == b.key);
+        BlockStateDiff { entries }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(4 + self.entries.len() * ENTRY_LEN);
+        buf.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in self.entries.iter() {
+            buf.extend_from_slice(&entry.key);
+            buf.extend_from_slice(&entry.old_value);
+            buf.extend_from_slice(&entry.new_value);
+        }
+        buf
+    }
+
+    /// `None` if the slice is malformed.
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        let count = u32::from_le_bytes(slice.get(..4)?.try_into().ok()?) as usize;
+        if slice.len() != 4 + count.checked_mul(ENTRY_LEN)? {
+            return None;
+        }
+        let entries = slice[4..]
+            .chunks_exact(ENTRY_LEN)
+            .map(|chunk| StateDiffEntry {
+                key: chunk[..32].try_into().expect("32 bytes"),
+                old_value: chunk[32..64].try_into().expect("32 bytes"),
+                new_value: chunk[64..].try_into().expect("32 bytes"),
+            })
+            .collect();
+        Some(BlockStateDiff { entries })
+    }
+}
@@ -0,0 +1,96 @@
+//! Aggregate cycles, txs and fees of a block, recorded when the block is
+//! inserted, for fee suggestions like `eth_feeHistory`.
+//!
+//! Fees are of polyjuice txs only, i.e. `gas_used * gas_price`.
+
+use std::convert::TryInto;
+
+/// used_cycles | tx_count | withdrawal_count | deposit_count | gas_used |
+/// total_fee | gas_prices count
+const HEADER_LEN: usize = 8 + 4 + 4 + 4 + 8 + 16 + 4;
+/// gas_price | gas_used
+const GAS_PRICE_LEN: usize = 16 + 8;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockStats {
+    /// Execution cycles of txs.
+    pub used_cycles: u64,
+    pub tx_count: u32,
+    pub withdrawal_count: u32,
+    pub deposit_count: u32,
+    /// Gas used by polyjuice txs.
+    pub gas_used: u64,
+    pub total_fee: u128,
+    /// Gas price and gas used of polyjuice txs, in ascending order of gas
+    /// price.
+    pub gas_prices: Vec<(u128, u64)>,
+}
+
+impl BlockStats {
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_LEN + self.gas_prices.len() * GAS_PRICE_LEN);
+        buf.extend_from_slice(&self.used_cycles.to_le_bytes());
+        buf.extend_from_slice(&self.tx_count.to_le_bytes());
+        buf.extend_from_slice(&self.withdrawal_count.to_le_bytes());
+        buf.extend_from_slice(&self.deposit_count.to_le_bytes());
+        buf.extend_from_slice(&self.gas_used.to_le_bytes());
+        buf.extend_from_slice(&self.total_fee.to_le_bytes());
+        buf.extend_from_slice(&(self.gas_prices.len() as u32).to_le_bytes());
+        for (gas_price, gas_used) in self.gas_prices.iter() {
+            buf.extend_from_slice(&gas_price.to_le_bytes());
+            buf.extend_from_slice(&gas_used.to_le_bytes());
+        }
+        buf
+    }
+
+    /// `None` if the slice is malformed.
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        if slice.len() < HEADER_LEN {
+            return None;
+        }
+        let count = u32::from_le_bytes(slice[44..48].try_into().ok()?) as usize;
+        if slice.len() != HEADER_LEN + count * GAS_PRICE_LEN {
+            return None;
+        }
+        let gas_prices = slice[HEADER_LEN..]
+            .chunks_exact(GAS_PRICE_LEN)
+            .map(|chunk| {
+                let gas_price = u128::from_le_bytes(chunk[..16].try_into().expect("16 bytes"));
+                let gas_used = u64::from_le_bytes(chunk[16..].try_into().expect("8 bytes"));
+                (gas_price, gas_used)
+            })
+            .collect();
+        Some(BlockStats {
+            used_cycles: u64::from_le_bytes(slice[0..8].try_into().ok()?),
+            tx_count: u32::from_le_bytes(slice[8..12].try_into().ok()?),
+            withdrawal_count: u32::from_le_bytes(slice[12..16].try_into().ok()?),
+            deposit_count: u32::from_le_bytes(slice[16..20].try_into().ok()?),
+            gas_used: u64::from_le_bytes(slice[20..28].try_into().ok()?),
+            total_fee: u128::from_le_bytes(slice[28..44].try_into().ok()?),
+            gas_prices,
+        })
+    }
+
+    /// Gas prices at `percentiles`, weighted by gas used like the rewards of
+    /// `eth_feeHistory`. Zeros if the block has no polyjuice txs.
+    pub fn gas_price_percentiles(&self, percentiles: &[f64]) -> Vec<u128> {
+        let total_gas: u64 = self.gas_prices.iter().map(|(_, gas)| *gas).sum();
+        if self.gas_prices.is_empty() {
+            return vec![0; percentiles.len()];
+        }
+        percentiles
+            .iter()
+            .map(|p| {
+                let threshold = (total_gas as f64 * p / 100.0) as u64;
+                let mut sum = 0u64;
+                for (gas_price, gas_used) in self.gas_prices.iter() {
+                    sum = sum.saturating_add(*gas_used);
+                    if sum >= threshold {
+                        return *gas_price;
+                    }
+                }
+                self.gas_prices.last().map(|(price, _)| *price).unwrap_or(0)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,53 @@
+//! Lifecycle of deposit cells, from collected by the mem pool to credited by
+//! an L2 block, keyed by the out point of the deposit cell.
+//!
+//! Deposits collected or packaged by the mem pool of a block producer are
+//! recorded when the mem block is reset, deposits of attached blocks are
+//! recorded when the block is inserted. Deposits not recorded are pending,
+//! e.g. waiting for L1 confirmations.
+//!
+//! A deposit cell can only be credited once, inserting a block fails if any
+//! of its deposits is credited by another block. The `audit-deposits` command
+//! checks historical blocks.
+
+use std::convert::TryInto;
+
+const COLLECTED: u8 = 0;
+const PACKAGED: u8 = 1;
+const CREDITED: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepositStatus {
+    /// Collected from L1 by the mem pool.
+    Collected,
+    /// Packaged into the mem block, credited in the mem pool state.
+    Packaged,
+    /// Included in an attached L2 block.
+    Credited { block_number: u64 },
+}
+
+impl DepositStatus {
+    pub fn to_vec(&self) -> Vec<u8> {
+        match self {
+            DepositStatus::Collected => vec![COLLECTED],
+            DepositStatus::Packaged => vec![PACKAGED],
+            DepositStatus::Credited { block_number } => {
+                let mut buf = vec![CREDITED];
+                buf.extend_from_slice(&block_number.to_be_bytes());
+                buf
+            }
+        }
+    }
+
+    /// `None` if the slice is malformed.
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        match slice {
+            [COLLECTED] => Some(DepositStatus::Collected),
+            [PACKAGED] => Some(DepositStatus::Packaged),
+            [CREDITED, number @ ..] => Some(DepositStatus::Credited {
+                block_number: u64::from_be_bytes(number.try_into().ok()?),
+            }),
+            _ => None,
+        }
+    }
+}
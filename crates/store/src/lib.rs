@@ -1,14 +1,24 @@
 pub extern crate autorocks;
 
+pub mod account_tx_index;
+pub mod backup;
+pub mod block_cycles;
+pub mod block_state_diff;
+pub mod block_stats;
 pub mod chain_view;
+pub mod deposit_status;
+pub mod log_index;
 pub mod mem_pool_state;
 pub mod migrate;
 pub mod readonly;
+pub mod registry_index;
+pub mod reorg_log;
 pub mod schema;
 pub mod smt;
 pub mod snapshot;
 pub mod state;
 mod store_impl;
+pub mod sudt_transfer_index;
 pub mod traits;
 pub mod transaction;
 
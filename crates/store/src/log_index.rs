@@ -0,0 +1,154 @@
+//! Index of EVM logs, i.e. polyjuice user logs, by address and by topic.
+//!
+//! Keys are an address, or a topic and its position in the log, followed by
+//! the block number and the log index in the block, both in big endian so
+//! that logs of an address or topic are in order. Values are the tx index in
+//! the block and the log index in the tx receipt, both in little endian.
+//!
+//! Only logs of valid blocks on the main chain are indexed.
+
+use std::convert::TryInto;
+
+use anyhow::{Context, Result};
+use gw_types::{
+    packed::{L2Block, LogItem, TransactionKey, TxReceipt},
+    prelude::*,
+};
+
+use crate::schema::{Col, COLUMN_LOG_ADDRESS_INDEX, COLUMN_LOG_TOPIC_INDEX};
+use crate::traits::chain_store::ChainStore;
+
+/// Service flag of polyjuice user logs.
+pub const GW_LOG_POLYJUICE_USER: u8 = 0x3;
+
+/// Block number and log index.
+const POSITION_LEN: usize = 8 + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LogPosition {
+    pub block_number: u64,
+    /// Index of the log among EVM logs of the block.
+    pub log_index: u32,
+    pub tx_index: u32,
+    /// Index of the log among all logs of the tx receipt.
+    pub receipt_log_index: u32,
+}
+
+pub struct EvmLog {
+    pub address: [u8; 20],
+    pub data: Vec<u8>,
+    pub topics: Vec<[u8; 32]>,
+}
+
+/// Parse an EVM log, `None` for other logs or malformed ones.
+pub fn parse_evm_log(item: &LogItem) -> Option<EvmLog> {
+    if u8::from(item.service_flag()) != GW_LOG_POLYJUICE_USER {
+        return None;
+    }
+    let raw_data = item.data().raw_data();
+    let data = raw_data.as_ref();
+    let address: [u8; 20] = data.get(..20)?.try_into().ok()?;
+    let data_size = u32::from_le_bytes(data.get(20..24)?.try_into().ok()?) as usize;
+    let mut offset = 24usize.checked_add(data_size)?;
+    let log_data = data.get(24..offset)?.to_vec();
+    let topics_count = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+    offset += 4;
+    let mut topics = Vec::new();
+    for _ in 0..topics_count {
+        topics.push(data.get(offset..offset + 32)?.try_into().ok()?);
+        offset += 32;
+    }
+    Some(EvmLog {
+        address,
+        data: log_data,
+        topics,
+    })
+}
+
+pub fn address_prefix(address: &[u8; 20]) -> Vec<u8> {
+    address.to_vec()
+}
+
+pub fn topic_prefix(topic: &[u8; 32], topic_index: u8) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.extend_from_slice(topic);
+    prefix.push(topic_index);
+    prefix
+}
+
+/// Key of a log with `prefix` at `block_number`, `log_index`.
+pub fn position_key(prefix: &[u8], block_number: u64, log_index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(prefix.len() + POSITION_LEN);
+    key.extend_from_slice(prefix);
+    key.extend_from_slice(&block_number.to_be_bytes());
+    key.extend_from_slice(&log_index.to_be_bytes());
+    key
+}
+
+/// Tx receipts of a stored block, in order.
+pub(crate) fn block_receipts(store: &impl ChainStore, block: &L2Block) -> Result<Vec<TxReceipt>> {
+    let block_hash = block.hash();
+    let mut receipts = Vec::with_capacity(block.transactions().len());
+    for index in 0..block.transactions().len() {
+        let key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+        let receipt = store
+            .get_transaction_receipt_by_key(&key)?
+            .context("block tx receipt")?;
+        receipts.push(receipt);
+    }
+    Ok(receipts)
+}
+
+/// Index entries of EVM logs of a block with `receipts`, as (column, key,
+/// value).
+pub(crate) fn block_entries<'a>(
+    block_number: u64,
+    receipts: impl IntoIterator<Item = &'a TxReceipt>,
+) -> Vec<(Col, Vec<u8>, [u8; 8])> {
+    let mut entries = Vec::new();
+    let mut log_index = 0u32;
+    for (tx_index, receipt) in receipts.into_iter().enumerate() {
+        for (receipt_log_index, item) in receipt.logs().into_iter().enumerate() {
+            let log = match parse_evm_log(&item) {
+                Some(log) => log,
+                None => continue,
+            };
+            let mut value = [0u8; 8];
+            value[..4].copy_from_slice(&(tx_index as u32).to_le_bytes());
+            value[4..].copy_from_slice(&(receipt_log_index as u32).to_le_bytes());
+
+            entries.push((
+                COLUMN_LOG_ADDRESS_INDEX,
+                position_key(&address_prefix(&log.address), block_number, log_index),
+                value,
+            ));
+            for (topic_index, topic) in log.topics.iter().enumerate() {
+                entries.push((
+                    COLUMN_LOG_TOPIC_INDEX,
+                    position_key(
+                        &topic_prefix(topic, topic_index as u8),
+                        block_number,
+                        log_index,
+                    ),
+                    value,
+                ));
+            }
+            log_index += 1;
+        }
+    }
+    entries
+}
+
+/// Decode an index entry, `None` if `key` is not of `prefix`.
+pub(crate) fn decode_entry(prefix: &[u8], key: &[u8], value: &[u8]) -> Option<LogPosition> {
+    if key.len() != prefix.len() + POSITION_LEN || !key.starts_with(prefix) {
+        return None;
+    }
+    let position = &key[prefix.len()..];
+    Some(LogPosition {
+        block_number: u64::from_be_bytes(position[..8].try_into().ok()?),
+        log_index: u32::from_be_bytes(position[8..].try_into().ok()?),
+        tx_index: u32::from_le_bytes(value.get(..4)?.try_into().ok()?),
+        receipt_log_index: u32::from_le_bytes(value.get(4..8)?.try_into().ok()?),
+    })
+}
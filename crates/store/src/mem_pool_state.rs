@@ -1,10 +1,19 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
-use arc_swap::ArcSwap;
-use gw_types::packed::{self, BlockInfo};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use gw_types::{
+    h256::H256,
+    offchain::FinalizedCustodianCapacity,
+    packed::{self, BlockInfo},
+    prelude::*,
+};
 
 use crate::{
     snapshot::StoreSnapshot,
@@ -23,9 +32,46 @@ pub struct Shared {
     pub mem_block: Option<BlockInfo>,
 }
 
+/// Replaying a block of L1 didn't reproduce its post account state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDivergence {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub expected_root: H256,
+    pub actual_root: H256,
+    pub expected_count: u32,
+    pub actual_count: u32,
+}
+
+impl fmt::Display for StateDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "state diverged at block #{}, post account root {} count {}, expected {} count {}",
+            self.block_number,
+            self.actual_root.pack(),
+            self.actual_count,
+            self.expected_root.pack(),
+            self.expected_count
+        )
+    }
+}
+
+impl std::error::Error for StateDivergence {}
+
 pub struct MemPoolState {
     inner: ArcSwap<Shared>,
     completed_initial_syncing: AtomicBool,
+    /// Set when the node enters safe mode, it stops producing blocks and
+    /// accepting txs until restarted.
+    state_divergence: ArcSwapOption<StateDivergence>,
+    /// Withdrawals left out of the mem block because finalized custodians
+    /// can't cover them. They stay in the pool for the next block.
+    deferred_withdrawals: ArcSwap<Vec<H256>>,
+    /// Withdrawals in the current mem block.
+    packaged_withdrawals: ArcSwap<HashSet<H256>>,
+    /// Finalized custodians left after withdrawals in the current mem block.
+    finalized_custodians: ArcSwap<FinalizedCustodianCapacity>,
 }
 
 impl MemPoolState {
@@ -36,6 +82,10 @@ impl MemPoolState {
                 mem_block: None,
             })),
             completed_initial_syncing: AtomicBool::new(completed_initial_syncing),
+            state_divergence: ArcSwapOption::empty(),
+            deferred_withdrawals: ArcSwap::from_pointee(Vec::new()),
+            packaged_withdrawals: ArcSwap::from_pointee(HashSet::new()),
+            finalized_custodians: ArcSwap::from_pointee(Default::default()),
         }
     }
 
@@ -81,6 +131,31 @@ impl MemPoolState {
         self.inner.store(shared);
     }
 
+    pub fn get_deferred_withdrawals(&self) -> Arc<Vec<H256>> {
+        self.deferred_withdrawals.load_full()
+    }
+
+    pub fn store_deferred_withdrawals(&self, withdrawal_hashes: Vec<H256>) {
+        self.deferred_withdrawals.store(Arc::new(withdrawal_hashes));
+    }
+
+    pub fn is_withdrawal_packaged(&self, withdrawal_hash: &H256) -> bool {
+        self.packaged_withdrawals.load().contains(withdrawal_hash)
+    }
+
+    pub fn store_packaged_withdrawals(&self, withdrawal_hashes: HashSet<H256>) {
+        self.packaged_withdrawals.store(Arc::new(withdrawal_hashes));
+    }
+
+    pub fn get_finalized_custodians(&self) -> Arc<FinalizedCustodianCapacity> {
+        self.finalized_custodians.load_full()
+    }
+
+    pub fn store_finalized_custodians(&self, finalized_custodians: FinalizedCustodianCapacity) {
+        self.finalized_custodians
+            .store(Arc::new(finalized_custodians));
+    }
+
     pub fn completed_initial_syncing(&self) -> bool {
         self.completed_initial_syncing.load(Ordering::SeqCst)
     }
@@ -89,4 +164,14 @@ impl MemPoolState {
         log::info!("set completed initial syncing");
         self.completed_initial_syncing.store(true, Ordering::SeqCst);
     }
+
+    /// `Some` if the node is in safe mode.
+    pub fn state_divergence(&self) -> Option<Arc<StateDivergence>> {
+        self.state_divergence.load_full()
+    }
+
+    pub fn enter_safe_mode(&self, divergence: StateDivergence) {
+        log::error!("enter safe mode, {}", divergence);
+        self.state_divergence.store(Some(Arc::new(divergence)));
+    }
 }
@@ -2,25 +2,46 @@
 // And check present db version is still compatible. Godwoken must run on a valid db.
 // If godwoken with an advanced verion runs on an old db, this is the time we can run migrations.
 
-use std::{cmp::Ordering, collections::BTreeMap};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    sync::atomic::{self, AtomicU64},
+    time::Duration,
+};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use autorocks::{
     autorocks_sys::rocksdb::Status_SubCode, moveit::slot, DbOptions, Direction, ReadOnlyDb,
-    TransactionDb,
+    TransactionDb, WriteBatch,
 };
+use gw_common::state::State;
 use gw_config::StoreConfig;
+use gw_types::prelude::Unpack;
 
 use crate::{
+    account_tx_index, log_index, registry_index,
     schema::{
-        COLUMNS, COLUMN_BAD_BLOCK, COLUMN_BLOCK, COLUMN_META, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
+        COLUMNS, COLUMN_ACCOUNT_TX_INDEX, COLUMN_BAD_BLOCK, COLUMN_BLOCK, COLUMN_META,
+        COLUMN_REGISTRY_ADDRESS_INDEX, COLUMN_SCRIPT, COLUMN_SUDT_TRANSFER_INDEX,
+        COLUMN_TRANSACTION, COLUMN_TRANSACTION_RECEIPT, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
         META_TIP_BLOCK_HASH_KEY, MIGRATION_VERSION_KEY, REMOVED_COLUMN_BLOCK_DEPOSIT_REQUESTS,
         REMOVED_COLUMN_L2BLOCK_COMMITTED_INFO,
     },
+    state::{history::history_state::RWConfig, BlockStateDB},
+    sudt_transfer_index,
+    traits::chain_store::ChainStore,
     Store,
 };
 
 pub fn open_or_create_db(config: &StoreConfig, factory: MigrationFactory) -> Result<TransactionDb> {
+    open_or_create_db_with_progress(config, factory, &log_progress)
+}
+
+pub fn open_or_create_db_with_progress(
+    config: &StoreConfig,
+    factory: MigrationFactory,
+    progress: ProgressFactory,
+) -> Result<TransactionDb> {
     let read_only_db = match DbOptions::new(&config.path, 1).open_read_only() {
         Ok(db) => Some(db),
         Err(e) if e.sub_code == Status_SubCode::kPathNotFound => None,
@@ -43,7 +64,7 @@ pub fn open_or_create_db(config: &StoreConfig, factory: MigrationFactory) -> Res
 
                 let db = Store::open(config, COLUMNS)?.into_inner();
 
-                let _ = factory.migrate(db)?;
+                let _ = factory.migrate(db, progress)?;
 
                 Ok(Store::open(config, COLUMNS)?.into_inner())
             }
@@ -83,6 +104,53 @@ fn check_readonly_db_version(db: &ReadOnlyDb, db_ver: Option<&str>) -> Result<Or
     Ok(version.as_str().cmp(db_ver.expect("Db version is absent!")))
 }
 
+/// Reads to plan migrations, from either the read-write db or a read-only
+/// one.
+pub trait MigrationDb {
+    fn db_version(&self) -> Result<String>;
+    fn get_int_property(&self, col: usize, property: &str) -> Option<u64>;
+}
+
+impl MigrationDb for TransactionDb {
+    fn db_version(&self) -> Result<String> {
+        slot!(slice);
+        let version = self
+            .get(self.default_col(), MIGRATION_VERSION_KEY, slice)?
+            .map(|v| String::from_utf8(v.to_vec()).expect("version bytes to utf8"))
+            .unwrap_or_default();
+        Ok(version)
+    }
+
+    fn get_int_property(&self, col: usize, property: &str) -> Option<u64> {
+        TransactionDb::get_int_property(self, col, property)
+    }
+}
+
+impl MigrationDb for ReadOnlyDb {
+    fn db_version(&self) -> Result<String> {
+        slot!(slice);
+        let version = self
+            .get(self.default_col(), MIGRATION_VERSION_KEY, slice)?
+            .map(|v| String::from_utf8(v.to_vec()).expect("version bytes to utf8"))
+            .unwrap_or_default();
+        Ok(version)
+    }
+
+    fn get_int_property(&self, col: usize, property: &str) -> Option<u64> {
+        ReadOnlyDb::get_int_property(self, col, property)
+    }
+}
+
+/// Open the db read only to plan migrations, e.g. for a dry run. Column
+/// families created by newer versions may not exist yet, they are skipped.
+pub fn open_read_only_for_migration(config: &StoreConfig) -> Result<ReadOnlyDb> {
+    let mut opts = DbOptions::new(&config.path, COLUMNS);
+    if let Some(ref opts_file) = config.options_file {
+        opts.load_options_from_file(opts_file, config.cache_size.unwrap_or(0))?;
+    }
+    Ok(opts.open_read_only_existing()?)
+}
+
 fn is_non_empty_rdb(db: &ReadOnlyDb) -> bool {
     slot!(slice);
     if let Ok(v) = db.get(COLUMN_META, META_TIP_BLOCK_HASH_KEY, slice) {
@@ -94,14 +162,164 @@ fn is_non_empty_rdb(db: &ReadOnlyDb) -> bool {
 }
 
 pub trait Migration {
-    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb>;
+    fn migrate(&self, db: TransactionDb, progress: &dyn MigrationProgress)
+        -> Result<TransactionDb>;
     // Version can be genereated with: date '+%Y%m%d%H%M%S'
     fn version(&self) -> &str;
+    /// Estimate the cost of this migration on `db`.
+    ///
+    /// The default is zero cost, which is right for migrations that only
+    /// drop or create column families.
+    fn estimate(&self, _db: &dyn MigrationDb) -> Result<MigrationEstimate> {
+        Ok(MigrationEstimate::default())
+    }
+}
+
+/// Expected cost of a migration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationEstimate {
+    /// Number of keys to process, also used as the progress length.
+    pub keys: u64,
+    /// Disk space that will be written.
+    pub disk_bytes: u64,
+    pub duration: Duration,
+}
+
+impl MigrationEstimate {
+    /// Estimate with rocksdb properties of `columns`, assuming
+    /// `keys_per_sec` keys are processed per second.
+    pub fn from_columns(db: &dyn MigrationDb, columns: &[usize], keys_per_sec: u64) -> Self {
+        let mut keys = 0;
+        let mut disk_bytes = 0;
+        for &col in columns {
+            keys += db
+                .get_int_property(col, "rocksdb.estimate-num-keys")
+                .unwrap_or_default();
+            disk_bytes += db
+                .get_int_property(col, "rocksdb.estimate-live-data-size")
+                .unwrap_or_default();
+        }
+        let duration = Duration::from_secs(keys / keys_per_sec.max(1));
+        Self {
+            keys,
+            disk_bytes,
+            duration,
+        }
+    }
+}
+
+/// Progress reporting of a running migration.
+///
+/// Migrations may report progress from multiple threads.
+pub trait MigrationProgress: Sync {
+    fn inc(&self, delta: u64);
+    fn finish(&self);
+}
+
+/// Report progress in logs every 10%.
+pub struct LogProgress {
+    len: u64,
+    pos: AtomicU64,
+}
+
+impl LogProgress {
+    pub fn new(len: u64) -> Self {
+        Self {
+            len,
+            pos: Default::default(),
+        }
+    }
+}
+
+impl MigrationProgress for LogProgress {
+    fn inc(&self, delta: u64) {
+        if self.len == 0 {
+            return;
+        }
+        let prev = self.pos.fetch_add(delta, atomic::Ordering::Relaxed);
+        let pos = prev.saturating_add(delta);
+        if prev * 10 / self.len != pos * 10 / self.len {
+            log::info!("migrated {}/{} keys", pos, self.len);
+        }
+    }
+
+    fn finish(&self) {}
+}
+
+/// Create progress for a migration, with its version and estimated keys.
+pub type ProgressFactory<'a> = &'a dyn Fn(&str, u64) -> Box<dyn MigrationProgress>;
+
+pub fn log_progress(_version: &str, len: u64) -> Box<dyn MigrationProgress> {
+    Box::new(LogProgress::new(len))
+}
+
+/// Write batch that commits every `chunk_size` operations, so that migrating
+/// a large column doesn't hold all the writes in memory.
+pub struct ChunkedWriteBatch<'a> {
+    db: &'a TransactionDb,
+    batch: WriteBatch,
+    chunk_size: usize,
+    pending: usize,
+    progress: &'a dyn MigrationProgress,
+}
+
+impl<'a> ChunkedWriteBatch<'a> {
+    pub fn new(
+        db: &'a TransactionDb,
+        chunk_size: usize,
+        progress: &'a dyn MigrationProgress,
+    ) -> Self {
+        Self {
+            db,
+            batch: db.new_write_batch(),
+            chunk_size: chunk_size.max(1),
+            pending: 0,
+            progress,
+        }
+    }
+
+    pub fn put(&mut self, col: usize, key: &[u8], value: &[u8]) -> Result<()> {
+        self.batch.put(col, key, value)?;
+        self.inc()
+    }
+
+    pub fn delete(&mut self, col: usize, key: &[u8]) -> Result<()> {
+        self.batch.delete(col, key)?;
+        self.inc()
+    }
+
+    /// Commit the remaining writes.
+    pub fn finish(mut self) -> Result<()> {
+        self.commit()
+    }
+
+    fn inc(&mut self) -> Result<()> {
+        self.pending += 1;
+        if self.pending >= self.chunk_size {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+        self.db.write(&mut self.batch)?;
+        self.batch = self.db.new_write_batch();
+        self.progress.inc(self.pending as u64);
+        self.pending = 0;
+        Ok(())
+    }
 }
 
 struct DefaultMigration;
 impl Migration for DefaultMigration {
-    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb> {
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        _progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
         Ok(db)
     }
     #[allow(clippy::needless_return)]
@@ -113,7 +331,11 @@ impl Migration for DefaultMigration {
 struct DecoupleBlockProducingSubmissionAndConfirmationMigration;
 
 impl Migration for DecoupleBlockProducingSubmissionAndConfirmationMigration {
-    fn migrate(&self, mut db: TransactionDb) -> Result<TransactionDb> {
+    fn migrate(
+        &self,
+        mut db: TransactionDb,
+        _progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
         if db.iter(COLUMN_BLOCK, Direction::Forward).next().is_some() {
             bail!("Cannot migrate a database with existing data to version 20220517. You have to deploy a new node");
         }
@@ -130,7 +352,11 @@ impl Migration for DecoupleBlockProducingSubmissionAndConfirmationMigration {
 struct BadBlockColumnMigration;
 
 impl Migration for BadBlockColumnMigration {
-    fn migrate(&self, mut db: TransactionDb) -> Result<TransactionDb> {
+    fn migrate(
+        &self,
+        mut db: TransactionDb,
+        _progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
         // Check that there are no bad blocks.
         slot!(slice1, slice2);
         let tip = db.get(COLUMN_META, META_TIP_BLOCK_HASH_KEY, slice1)?;
@@ -150,12 +376,180 @@ impl Migration for BadBlockColumnMigration {
     }
 }
 
+/// Build the EVM log index of existing blocks.
+struct LogIndexMigration;
+
+impl Migration for LogIndexMigration {
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
+        let store = Store::new(db);
+        {
+            let snap = store.get_snapshot();
+            let tip_number: u64 = snap.get_last_valid_tip_block()?.raw().number().unpack();
+            let mut batch = ChunkedWriteBatch::new(store.as_inner(), 10_000, progress);
+            for number in 0..=tip_number {
+                let block_hash = snap
+                    .get_block_hash_by_number(number)?
+                    .context("block hash")?;
+                let block = snap.get_block(&block_hash)?.context("block")?;
+                let receipts = log_index::block_receipts(&snap, &block)?;
+                for (col, key, value) in log_index::block_entries(number, &receipts) {
+                    batch.put(col, &key, &value)?;
+                }
+            }
+            batch.finish()?;
+        }
+        progress.finish();
+        Ok(store.into_inner())
+    }
+    fn version(&self) -> &str {
+        "20221206"
+    }
+    fn estimate(&self, db: &dyn MigrationDb) -> Result<MigrationEstimate> {
+        // Roughly one index entry per receipt.
+        Ok(MigrationEstimate::from_columns(
+            db,
+            &[COLUMN_TRANSACTION_RECEIPT],
+            50_000,
+        ))
+    }
+}
+
+/// Build the registry address index of existing accounts.
+struct RegistryIndexMigration;
+
+impl Migration for RegistryIndexMigration {
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
+        let store = Store::new(db);
+        {
+            let mut tx = store.begin_transaction();
+            let state = BlockStateDB::from_store(&mut tx, RWConfig::readonly())?;
+            let account_count = state.get_account_count()?;
+            let script_hashes = (0..account_count)
+                .map(|id| state.get_script_hash(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut batch = ChunkedWriteBatch::new(store.as_inner(), 10_000, progress);
+            for (key, value) in registry_index::entries(&state, script_hashes)? {
+                batch.put(COLUMN_REGISTRY_ADDRESS_INDEX, &key, &value)?;
+            }
+            batch.finish()?;
+        }
+        progress.finish();
+        Ok(store.into_inner())
+    }
+    fn version(&self) -> &str {
+        "20221215"
+    }
+    fn estimate(&self, db: &dyn MigrationDb) -> Result<MigrationEstimate> {
+        // Roughly one script per account.
+        Ok(MigrationEstimate::from_columns(
+            db,
+            &[COLUMN_SCRIPT],
+            10_000,
+        ))
+    }
+}
+
+/// Build the account tx index of existing blocks.
+struct AccountTxIndexMigration;
+
+impl Migration for AccountTxIndexMigration {
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
+        let store = Store::new(db);
+        {
+            let snap = store.get_snapshot();
+            let tip_number: u64 = snap.get_last_valid_tip_block()?.raw().number().unpack();
+            let mut batch = ChunkedWriteBatch::new(store.as_inner(), 10_000, progress);
+            for number in 0..=tip_number {
+                let block_hash = snap
+                    .get_block_hash_by_number(number)?
+                    .context("block hash")?;
+                let block = snap.get_block(&block_hash)?.context("block")?;
+                for (key, value) in account_tx_index::block_entries(&block) {
+                    batch.put(COLUMN_ACCOUNT_TX_INDEX, &key, &value)?;
+                }
+            }
+            batch.finish()?;
+        }
+        progress.finish();
+        Ok(store.into_inner())
+    }
+    fn version(&self) -> &str {
+        "20221220"
+    }
+    fn estimate(&self, db: &dyn MigrationDb) -> Result<MigrationEstimate> {
+        // Roughly two index entries per tx.
+        Ok(MigrationEstimate::from_columns(
+            db,
+            &[COLUMN_TRANSACTION],
+            50_000,
+        ))
+    }
+}
+
+/// Build the sUDT transfer index of existing blocks.
+struct SudtTransferIndexMigration;
+
+impl Migration for SudtTransferIndexMigration {
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
+        let store = Store::new(db);
+        {
+            let snap = store.get_snapshot();
+            let tip_number: u64 = snap.get_last_valid_tip_block()?.raw().number().unpack();
+            let mut batch = ChunkedWriteBatch::new(store.as_inner(), 10_000, progress);
+            for number in 0..=tip_number {
+                let block_hash = snap
+                    .get_block_hash_by_number(number)?
+                    .context("block hash")?;
+                let block = snap.get_block(&block_hash)?.context("block")?;
+                let receipts = log_index::block_receipts(&snap, &block)?;
+                for (key, value) in sudt_transfer_index::block_entries(&block, &receipts) {
+                    batch.put(COLUMN_SUDT_TRANSFER_INDEX, &key, &value)?;
+                }
+            }
+            batch.finish()?;
+        }
+        progress.finish();
+        Ok(store.into_inner())
+    }
+    fn version(&self) -> &str {
+        "20221225"
+    }
+    fn estimate(&self, db: &dyn MigrationDb) -> Result<MigrationEstimate> {
+        // Most receipts have a transfer log or two.
+        Ok(MigrationEstimate::from_columns(
+            db,
+            &[COLUMN_TRANSACTION_RECEIPT],
+            50_000,
+        ))
+    }
+}
+
 #[cfg(feature = "smt-trie")]
 pub struct SMTTrieMigrationPlaceHolder;
 
 #[cfg(feature = "smt-trie")]
 impl Migration for SMTTrieMigrationPlaceHolder {
-    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb> {
+    fn migrate(
+        &self,
+        db: TransactionDb,
+        _progress: &dyn MigrationProgress,
+    ) -> Result<TransactionDb> {
         use crate::schema::{
             COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_SMT_LEAF, COLUMN_REVERTED_BLOCK_SMT_LEAF,
         };
@@ -194,6 +588,10 @@ pub fn init_migration_factory() -> MigrationFactory {
     factory.insert(Box::new(
         DecoupleBlockProducingSubmissionAndConfirmationMigration,
     ));
+    factory.insert(Box::new(LogIndexMigration));
+    factory.insert(Box::new(RegistryIndexMigration));
+    factory.insert(Box::new(AccountTxIndexMigration));
+    factory.insert(Box::new(SudtTransferIndexMigration));
     #[cfg(feature = "smt-trie")]
     factory.insert(Box::new(SMTTrieMigrationPlaceHolder));
     factory
@@ -214,25 +612,54 @@ impl MigrationFactory {
             .is_some()
     }
 
-    fn migrate(&self, db: TransactionDb) -> Result<TransactionDb> {
-        slot!(slice);
-        let db_version = db
-            .get(db.default_col(), MIGRATION_VERSION_KEY, slice)?
-            .map(|v| String::from_utf8(v.to_vec()).expect("version bytes to utf8"))
-            .unwrap_or_else(|| "".to_string());
-        let mut db = db;
-        let v = db_version.as_str();
-        let mut last_version = None;
-        for (mv, migration) in &self.migration_map {
-            let mv = mv.as_str();
-            if mv > v {
-                db = migration.migrate(db)?;
-                last_version = Some(mv);
-            }
-        }
-        if let Some(v) = last_version {
-            db.put(db.default_col(), MIGRATION_VERSION_KEY, v.as_bytes())?;
-            log::info!("Current db version is: {}", v);
+    /// Migrations that would run on `db`, in order.
+    pub fn pending(&self, db: &dyn MigrationDb) -> Result<Vec<&dyn Migration>> {
+        let db_version = db.db_version()?;
+        let pending = self
+            .migration_map
+            .iter()
+            .filter(|(mv, _)| mv.as_str() > db_version.as_str())
+            .map(|(_, m)| m.as_ref())
+            .collect();
+        Ok(pending)
+    }
+
+    /// Report which migrations would run and their estimated cost, without
+    /// modifying the database.
+    pub fn dry_run(&self, db: &dyn MigrationDb) -> Result<Vec<(String, MigrationEstimate)>> {
+        self.pending(db)?
+            .into_iter()
+            .map(|m| Ok((m.version().to_string(), m.estimate(db)?)))
+            .collect()
+    }
+
+    /// Run pending migrations.
+    ///
+    /// The db version is updated after each migration, so if a migration
+    /// fails, the completed ones are not run again next time.
+    pub fn migrate(
+        &self,
+        mut db: TransactionDb,
+        progress: ProgressFactory,
+    ) -> Result<TransactionDb> {
+        let pending: Vec<String> = self
+            .pending(&db)?
+            .iter()
+            .map(|m| m.version().to_string())
+            .collect();
+        for mv in pending {
+            let migration = &self.migration_map[&mv];
+            let estimate = migration.estimate(&db)?;
+            log::info!(
+                "migrating to version {}, estimated keys: {}",
+                mv,
+                estimate.keys
+            );
+            let progress = progress(&mv, estimate.keys);
+            db = migration.migrate(db, progress.as_ref())?;
+            progress.finish();
+            db.put(db.default_col(), MIGRATION_VERSION_KEY, mv.as_bytes())?;
+            log::info!("Current db version is: {}", mv);
         }
         Ok(db)
     }
@@ -259,7 +686,7 @@ mod tests {
         let factory = init_migration_factory();
         assert!(factory.last_db_version().is_some());
 
-        let db = factory.migrate(old_db);
+        let db = factory.migrate(old_db, &log_progress);
 
         assert!(db.is_ok());
         let db = db.unwrap();
@@ -272,6 +699,81 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_migration_dry_run() -> Result<()> {
+        let dir = tempfile::tempdir().expect("create temp dir");
+
+        let config = StoreConfig {
+            path: dir.path().to_owned(),
+            options_file: None,
+            cache_size: None,
+        };
+        let db = Store::open(&config, COLUMNS)?.into_inner();
+        let factory = init_migration_factory();
+
+        // Dry run opens the db read only, even if it's already open.
+        let read_only_db = open_read_only_for_migration(&config)?;
+        let pending = factory.dry_run(&read_only_db)?;
+        assert_eq!(
+            pending.last().map(|(v, _)| v.as_str()),
+            factory.last_db_version()
+        );
+        // Dry run doesn't touch db version.
+        assert_eq!(db.db_version()?, "");
+        drop(read_only_db);
+
+        let db = factory.migrate(db, &log_progress)?;
+        assert!(factory.dry_run(&db)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migration_dry_run_missing_columns() -> Result<()> {
+        let dir = tempfile::tempdir().expect("create temp dir");
+
+        let config = StoreConfig {
+            path: dir.path().to_owned(),
+            options_file: None,
+            cache_size: None,
+        };
+        // A db of an old version, without the last column family.
+        drop(
+            DbOptions::new(&config.path, COLUMNS - 1)
+                .create_if_missing(true)
+                .create_missing_column_families(true)
+                .open()?,
+        );
+
+        let db = open_read_only_for_migration(&config)?;
+        let factory = init_migration_factory();
+        let pending = factory.dry_run(&db)?;
+        assert_eq!(
+            pending.last().map(|(v, _)| v.as_str()),
+            factory.last_db_version()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunked_write_batch() -> Result<()> {
+        let store = Store::open_tmp()?;
+        let db = store.as_inner();
+        let progress = LogProgress::new(10);
+
+        let mut batch = ChunkedWriteBatch::new(db, 3, &progress);
+        for i in 0u8..10 {
+            batch.put(COLUMN_META, &[i], &[i])?;
+        }
+        // 3 chunks have been committed.
+        assert_eq!(progress.pos.load(atomic::Ordering::Relaxed), 9);
+        batch.finish()?;
+        assert_eq!(progress.pos.load(atomic::Ordering::Relaxed), 10);
+
+        slot!(slice);
+        assert!(db.get(COLUMN_META, &[9], slice)?.is_some());
+        Ok(())
+    }
+
     #[test]
     fn test_migration_with_fresh_new() -> Result<()> {
         let dir = tempfile::tempdir().expect("create temp dir");
@@ -1,16 +1,24 @@
 use std::path::Path;
 
-use anyhow::Result;
-use autorocks::{moveit::slot, DbOptions, ReadOnlyDb};
+use anyhow::{bail, Result};
+use autorocks::{moveit::slot, DbOptions, Direction, ReadOnlyDb};
 use gw_types::{
     from_box_should_be_ok,
-    h256::H256,
+    h256::{H256Ext, H256},
     packed,
     prelude::{Entity, FromSliceShouldBeOk, Unpack},
 };
 
 use crate::{
-    schema::{Col, COLUMN_REVERTED_BLOCK_SMT_ROOT},
+    reorg_log::{self, ReorgRecord},
+    schema::{
+        Col, COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD, COLUMN_REORG_LOG,
+        COLUMN_REVERTED_BLOCK_SMT_ROOT,
+    },
+    state::history::{
+        block_state_record::{BlockStateRecordKey, BlockStateRecordKeyReverse},
+        history_state::HistoryStateStore,
+    },
     traits::{chain_store::ChainStore, kv_store::KVStoreRead},
 };
 
@@ -38,6 +46,17 @@ impl StoreReadonly {
             next_root: root,
         }
     }
+
+    /// Reorg records with ids less than `before`, newest first.
+    pub fn iter_reorg_records(
+        &self,
+        before: Option<u64>,
+    ) -> impl Iterator<Item = (u64, ReorgRecord)> + '_ {
+        reorg_log::list(
+            self.inner.iter(COLUMN_REORG_LOG, Direction::Backward),
+            before,
+        )
+    }
 }
 
 impl ChainStore for StoreReadonly {}
@@ -52,6 +71,63 @@ impl KVStoreRead for StoreReadonly {
     }
 }
 
+/// Read history state directly from the db, writes are rejected.
+///
+/// Wrap it in a `MemStore` to build a `BlockStateDB`.
+impl HistoryStateStore for StoreReadonly {
+    type BlockStateRecordKeyIter = Vec<BlockStateRecordKey>;
+
+    fn iter_block_state_record(&self, block_number: u64) -> Self::BlockStateRecordKeyIter {
+        let start_key = BlockStateRecordKey::new(block_number, &H256::zero());
+        let mut iter = self
+            .inner
+            .iter(COLUMN_BLOCK_STATE_RECORD, Direction::Forward);
+        iter.seek(start_key.as_slice());
+        iter.map(|(key, _value)| BlockStateRecordKey::from_slice(&key))
+            .take_while(move |key| key.block_number() == block_number)
+            .collect()
+    }
+
+    fn remove_block_state_record(&mut self, _block_number: u64) -> Result<()> {
+        bail!("readonly store")
+    }
+
+    fn get_history_state(&self, block_number: u64, state_key: &H256) -> Option<H256> {
+        let key = BlockStateRecordKeyReverse::new(block_number, state_key);
+        let mut raw_iter = self
+            .inner
+            .iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, Direction::Forward);
+        raw_iter.seek_for_prev(key.as_slice());
+        if !raw_iter.valid() {
+            return None;
+        }
+        let prev_key = raw_iter.key()?;
+        if &prev_key[..32] != key.state_key().as_slice() {
+            return None;
+        }
+        let prev_reverse_key = BlockStateRecordKeyReverse::from_slice(prev_key);
+        let prev_key = BlockStateRecordKey::new(
+            prev_reverse_key.block_number(),
+            &prev_reverse_key.state_key(),
+        );
+        self.get(COLUMN_BLOCK_STATE_RECORD, prev_key.as_slice())
+            .map(|raw| {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&raw);
+                buf
+            })
+    }
+
+    fn record_block_state(
+        &mut self,
+        _block_number: u64,
+        _state_key: H256,
+        _value: H256,
+    ) -> Result<()> {
+        bail!("readonly store")
+    }
+}
+
 pub struct RervertedBlockHashesIter<'a> {
     snap: &'a StoreReadonly,
     next_root: H256,
@@ -0,0 +1,95 @@
+//! Index of registry addresses by script hash, for listing the registry
+//! addresses of an account without scanning the state.
+//!
+//! Keys are a script hash followed by a serialized registry address, values
+//! are the account id in little endian.
+//!
+//! Candidates of a block are accounts created in the block and script hashes
+//! of `SetMapping` and `BatchSetMapping` txs to the ETH registry. Entries are
+//! kept when blocks are detached, so readers should check them against the
+//! state.
+
+use std::convert::TryInto;
+
+use anyhow::Result;
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, state::State};
+use gw_types::{
+    h256::*,
+    packed::{ETHAddrRegArgs, ETHAddrRegArgsUnion, L2Block},
+    prelude::*,
+    registry_address::RegistryAddress,
+};
+
+pub fn key(script_hash: &H256, address: &RegistryAddress) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32 + address.len());
+    key.extend_from_slice(script_hash.as_slice());
+    key.extend_from_slice(&address.to_bytes());
+    key
+}
+
+/// Decode an index entry, `None` if `key` is not of `script_hash`.
+pub(crate) fn decode_entry(
+    script_hash: &H256,
+    key: &[u8],
+    value: &[u8],
+) -> Option<(RegistryAddress, u32)> {
+    if !key.starts_with(script_hash.as_slice()) {
+        return None;
+    }
+    let address = RegistryAddress::from_slice(&key[32..])?;
+    let account_id = u32::from_le_bytes(value.get(..4)?.try_into().ok()?);
+    Some((address, account_id))
+}
+
+/// Script hashes that may be mapped to registry addresses by `block`.
+pub(crate) fn block_candidates(state: &impl State, block: &L2Block) -> Result<Vec<H256>> {
+    let raw = block.raw();
+    let prev_count: u32 = raw.prev_account().count().unpack();
+    let post_count: u32 = raw.post_account().count().unpack();
+    let mut script_hashes = Vec::new();
+    for id in prev_count..post_count {
+        script_hashes.push(state.get_script_hash(id)?);
+    }
+    for tx in block.transactions().into_iter() {
+        let to_id: u32 = tx.raw().to_id().unpack();
+        if to_id != ETH_REGISTRY_ACCOUNT_ID {
+            continue;
+        }
+        let args = match ETHAddrRegArgs::from_slice(&tx.raw().args().raw_data()) {
+            Ok(args) => args,
+            Err(_) => continue,
+        };
+        match args.to_enum() {
+            ETHAddrRegArgsUnion::SetMapping(args) => {
+                script_hashes.push(args.gw_script_hash().unpack())
+            }
+            ETHAddrRegArgsUnion::BatchSetMapping(args) => {
+                script_hashes.extend(args.gw_script_hashes().into_iter().map(|h| h.unpack()))
+            }
+            ETHAddrRegArgsUnion::EthToGw(_) | ETHAddrRegArgsUnion::GwToEth(_) => {}
+        }
+    }
+    Ok(script_hashes)
+}
+
+/// Index entries of `script_hashes` mapped in `state`, as (key, value).
+pub(crate) fn entries(
+    state: &impl State,
+    script_hashes: impl IntoIterator<Item = H256>,
+) -> Result<Vec<(Vec<u8>, [u8; 4])>> {
+    let mut entries = Vec::new();
+    for script_hash in script_hashes {
+        let address = match state
+            .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)?
+        {
+            Some(address) => address,
+            None => continue,
+        };
+        let account_id = match state.get_account_id_by_script_hash(&script_hash)? {
+            Some(account_id) => account_id,
+            None => continue,
+        };
+        entries.push((key(&script_hash, &address), account_id.to_le_bytes()));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,140 @@
+//! Log of reorgs observed by the chain, i.e. L2 blocks reverted by L1
+//! actions, so operators can audit instability after incidents.
+//!
+//! Records are keyed by an increasing id in big endian and never pruned.
+
+use std::convert::TryInto;
+
+use autorocks::DbIterator;
+use gw_types::h256::*;
+
+/// kind | observed_at | fork point number | fork point hash | first block
+/// timestamp | last block timestamp | tx count | withdrawal count | block
+/// hashes count
+const HEADER_LEN: usize = 1 + 8 + 8 + 32 + 8 + 8 + 4 + 4 + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReorgKind {
+    /// Valid blocks detached from the chain.
+    Detach,
+    /// Bad blocks rewound to the last valid tip.
+    Rewind,
+    /// Bad blocks reverted by a rollup revert tx.
+    Revert,
+}
+
+impl ReorgKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReorgKind::Detach => "detach",
+            ReorgKind::Rewind => "rewind",
+            ReorgKind::Revert => "revert",
+        }
+    }
+
+    fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(ReorgKind::Detach),
+            1 => Some(ReorgKind::Rewind),
+            2 => Some(ReorgKind::Revert),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReorgRecord {
+    pub kind: ReorgKind,
+    /// Unix timestamp in milliseconds when the reorg was observed.
+    pub observed_at: u64,
+    /// Tip block after the reorg.
+    pub fork_point_number: u64,
+    pub fork_point_hash: H256,
+    /// Timestamps of the first and the last reverted blocks.
+    pub first_block_timestamp: u64,
+    pub last_block_timestamp: u64,
+    /// Txs of reverted blocks, reinjected into the mem pool unless included
+    /// again by new blocks.
+    pub tx_count: u32,
+    pub withdrawal_count: u32,
+    /// Reverted blocks in ascending order, starting from the child of the
+    /// fork point.
+    pub block_hashes: Vec<H256>,
+}
+
+impl ReorgRecord {
+    /// Number of reverted blocks.
+    pub fn depth(&self) -> usize {
+        self.block_hashes.len()
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_LEN + self.block_hashes.len() * 32);
+        buf.push(self.kind as u8);
+        buf.extend_from_slice(&self.observed_at.to_le_bytes());
+        buf.extend_from_slice(&self.fork_point_number.to_le_bytes());
+        buf.extend_from_slice(self.fork_point_hash.as_slice());
+        buf.extend_from_slice(&self.first_block_timestamp.to_le_bytes());
+        buf.extend_from_slice(&self.last_block_timestamp.to_le_bytes());
+        buf.extend_from_slice(&self.tx_count.to_le_bytes());
+        buf.extend_from_slice(&self.withdrawal_count.to_le_bytes());
+        buf.extend_from_slice(&(self.block_hashes.len() as u32).to_le_bytes());
+        for hash in self.block_hashes.iter() {
+            buf.extend_from_slice(hash.as_slice());
+        }
+        buf
+    }
+
+    /// `None` if the slice is malformed.
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        if slice.len() < HEADER_LEN {
+            return None;
+        }
+        let count = u32::from_le_bytes(slice[73..77].try_into().ok()?) as usize;
+        if slice.len() != HEADER_LEN + count * 32 {
+            return None;
+        }
+        let block_hashes = slice[HEADER_LEN..]
+            .chunks_exact(32)
+            .map(|chunk| chunk.try_into().expect("32 bytes"))
+            .collect();
+        Some(ReorgRecord {
+            kind: ReorgKind::from_u8(slice[0])?,
+            observed_at: u64::from_le_bytes(slice[1..9].try_into().ok()?),
+            fork_point_number: u64::from_le_bytes(slice[9..17].try_into().ok()?),
+            fork_point_hash: slice[17..49].try_into().ok()?,
+            first_block_timestamp: u64::from_le_bytes(slice[49..57].try_into().ok()?),
+            last_block_timestamp: u64::from_le_bytes(slice[57..65].try_into().ok()?),
+            tx_count: u32::from_le_bytes(slice[65..69].try_into().ok()?),
+            withdrawal_count: u32::from_le_bytes(slice[69..73].try_into().ok()?),
+            block_hashes,
+        })
+    }
+}
+
+/// Id of the next record, given a backward iterator of the reorg log column.
+pub(crate) fn next_id<T>(mut iter: DbIterator<T>) -> u64 {
+    match iter.next() {
+        Some((key, _)) => {
+            let id: [u8; 8] = key.as_ref().try_into().expect("reorg id");
+            u64::from_be_bytes(id) + 1
+        }
+        None => 0,
+    }
+}
+
+/// Records with ids less than `before`, newest first, given a backward
+/// iterator of the reorg log column.
+pub(crate) fn list<T>(
+    mut iter: DbIterator<T>,
+    before: Option<u64>,
+) -> impl Iterator<Item = (u64, ReorgRecord)> {
+    if let Some(before) = before {
+        iter.seek_for_prev(&before.saturating_sub(1).to_be_bytes());
+    }
+    iter.filter_map(|(key, value)| {
+        let id = u64::from_be_bytes(key.as_ref().try_into().ok()?);
+        Some((id, ReorgRecord::from_slice(&value)?))
+    })
+    .take_while(move |(id, _)| before.map_or(true, |before| *id < before))
+}
@@ -3,7 +3,7 @@
 /// Column families alias type
 pub type Col = usize;
 /// Total column number
-pub const COLUMNS: usize = 37;
+pub const COLUMNS: usize = 51;
 /// Column store meta data
 pub const COLUMN_META: Col = 0;
 /// Column store chain index
@@ -79,6 +79,68 @@ pub const COLUMN_BLOCK_SUBMIT_TX_HASH: Col = 7;
 pub const COLUMN_BLOCK_DEPOSIT_INFO_VEC: Col = 16;
 /// block number (in big endian) -> FinalizedCustodianCapacity.
 pub const COLUMN_BLOCK_POST_FINALIZED_CUSTODIAN_CAPACITY: Col = 36;
+/// withdrawal hash -> L1 tx hash unlocking the withdrawal cell to its owner.
+///
+/// Only recorded for withdrawals unlocked by this node.
+pub const COLUMN_WITHDRAWAL_UNLOCK_TX_HASH: Col = 37;
+/// Address | block number (big endian) | log index in block (big endian) ->
+/// tx index | log index in tx receipt.
+///
+/// See `log_index` for details.
+pub const COLUMN_LOG_ADDRESS_INDEX: Col = 38;
+/// Topic | topic index | block number (big endian) | log index in block (big
+/// endian) -> tx index | log index in tx receipt.
+pub const COLUMN_LOG_TOPIC_INDEX: Col = 39;
+/// block number (in big endian) -> BlockStats.
+///
+/// See `block_stats` for details.
+pub const COLUMN_BLOCK_STATS: Col = 40;
+/// Script hash | registry address -> account id.
+///
+/// See `registry_index` for details.
+pub const COLUMN_REGISTRY_ADDRESS_INDEX: Col = 41;
+/// Kind (0 for live, 1 for dead) | out point -> `CellInfo` of a live cell, or
+/// hash of the tx consuming a dead cell, empty if unknown.
+///
+/// Local cells of the block producer, i.e. cells created or consumed by its
+/// txs that are not confirmed yet.
+pub const COLUMN_LOCAL_CELLS: Col = 42;
+/// L1 sUDT script hash -> L2 sUDT account id.
+///
+/// Registered when deposits of the sUDT are attached.
+pub const COLUMN_SUDT_REGISTRY: Col = 43;
+/// Deposit cell out point -> DepositStatus.
+///
+/// See `deposit_status` for details.
+pub const COLUMN_DEPOSIT_STATUS: Col = 44;
+/// Reorg id (big endian) -> ReorgRecord.
+///
+/// See `reorg_log` for details.
+pub const COLUMN_REORG_LOG: Col = 45;
+/// block number (in big endian) -> BlockCyclesReport.
+///
+/// See `block_cycles` for details.
+pub const COLUMN_BLOCK_CYCLES_REPORT: Col = 46;
+/// block number (in big endian) -> BlockStateDiff.
+///
+/// See `block_state_diff` for details.
+pub const COLUMN_BLOCK_STATE_DIFF: Col = 47;
+/// Account id | block number (big endian) | tx index (big endian) -> role |
+/// tx hash.
+///
+/// See `account_tx_index` for details.
+pub const COLUMN_ACCOUNT_TX_INDEX: Col = 48;
+/// Registry address | block number (big endian) | transfer index in block
+/// (big endian) -> tx hash | tx index | sUDT id | amount | from | to.
+///
+/// See `sudt_transfer_index` for details.
+pub const COLUMN_SUDT_TRANSFER_INDEX: Col = 49;
+/// withdrawal hash -> unix timestamp in milliseconds (big endian).
+///
+/// Pending withdrawals dropped by the mem pool because of
+/// `mem_pool.pending_withdrawal_ttl_secs`. Records are pruned by the mem pool
+/// 7 days after expiry.
+pub const COLUMN_EXPIRED_WITHDRAWAL: Col = 50;
 
 /// chain id
 pub const META_CHAIN_ID_KEY: &[u8] = b"CHAIN_ID";
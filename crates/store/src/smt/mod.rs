@@ -1,2 +1,3 @@
+pub mod parallel_builder;
 pub mod serde;
 pub mod smt_store;
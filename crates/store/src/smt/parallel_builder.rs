@@ -0,0 +1,229 @@
+//! Rebuild SMT branches from leaves in parallel.
+//!
+//! The SMT branches on the highest bits of keys first, i.e. the highest bits
+//! of the last byte. Leaves are partitioned by the highest `PARTITION_BITS`
+//! bits, so each partition is a subtree and the branches below
+//! `SHARED_HEIGHT` belong to exactly one partition. These aren't contiguous
+//! ranges of the leaf column, which is ordered by the first byte, so the
+//! column is scanned once in chunks, and the leaves of a chunk are split by
+//! partition and updated on the rayon pool.
+//!
+//! Branches at or above `SHARED_HEIGHT` are kept in memory by each partition,
+//! because they only have the leaves of its partition. They are rebuilt
+//! afterwards by updating one leaf of every partition again in the db, which
+//! merges the subtree roots into the final root like any other update. The
+//! root is then read back from the branches, callers should verify it.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use anyhow::{Context, Result};
+use autorocks::Direction;
+use gw_smt::{
+    smt::SMT,
+    smt_h256_ext::SMTH256,
+    sparse_merkle_tree::{
+        error::Error as SMTError,
+        traits::{StoreReadOps, StoreWriteOps},
+        BranchKey, BranchNode,
+    },
+};
+use rayon::prelude::*;
+
+use crate::{
+    migrate::MigrationProgress,
+    schema::Col,
+    smt::serde::{branch_key_to_vec, branch_node_to_vec, slice_to_branch_node},
+    traits::kv_store::{KVStoreRead, KVStoreWrite},
+    transaction::StoreTransaction,
+    Store,
+};
+
+const PARTITION_BITS: u8 = 4;
+/// Lowest height of branches that may have leaves from more than one partition.
+const SHARED_HEIGHT: u8 = (256 - PARTITION_BITS as u16) as u8;
+/// Number of leaves of a partition updated in one transaction.
+const BATCH_SIZE: usize = 10_000;
+
+fn partition_of(key: &[u8]) -> usize {
+    (key[31] >> (8 - PARTITION_BITS)) as usize
+}
+
+/// Build branches in `branch_col` from leaves in `leaf_col`, returns the root
+/// computed from the built branches.
+///
+/// `branch_col` should be empty.
+pub fn build_smt_parallel(
+    store: &Store,
+    leaf_col: Col,
+    branch_col: Col,
+    progress: &dyn MigrationProgress,
+) -> Result<SMTH256> {
+    let mut partitions: Vec<Partition> = (0..1usize << PARTITION_BITS)
+        .map(|_| Partition::default())
+        .collect();
+    let chunk_size = BATCH_SIZE * partitions.len();
+    let mut chunk_len = 0;
+    for (k, v) in store.as_inner().iter(leaf_col, Direction::Forward) {
+        partitions[partition_of(&k)].leaves.push((
+            <[u8; 32]>::try_from(&k[..]).unwrap().into(),
+            <[u8; 32]>::try_from(&v[..]).unwrap().into(),
+        ));
+        chunk_len += 1;
+        if chunk_len >= chunk_size {
+            build_partitions(store, leaf_col, branch_col, &mut partitions, progress)?;
+            chunk_len = 0;
+        }
+    }
+    build_partitions(store, leaf_col, branch_col, &mut partitions, progress)?;
+
+    // Merge subtrees.
+    let mut tx = store.begin_transaction_skip_concurrency_control();
+    {
+        let mut smt = SMT::new_with_store(ColumnSMTStore {
+            tx: &mut tx,
+            leaf_col,
+            branch_col,
+            shared: None,
+        })?;
+        for key in partitions.into_iter().filter_map(|p| p.last_key) {
+            let value = smt.get(&key).context("get leaf")?;
+            smt.update(key, value).context("merge subtree")?;
+        }
+    }
+    tx.commit().context("commit merged subtrees")?;
+
+    let mut tx = store.begin_transaction();
+    let smt = SMT::new_with_store(ColumnSMTStore {
+        tx: &mut tx,
+        leaf_col,
+        branch_col,
+        shared: None,
+    })?;
+    Ok(*smt.root())
+}
+
+#[derive(Default)]
+struct Partition {
+    /// Leaves to update
+    leaves: Vec<(SMTH256, SMTH256)>,
+    /// Branches at or above `SHARED_HEIGHT`
+    shared: HashMap<BranchKey, BranchNode>,
+    /// A leaf key of the partition, if there is any leaf
+    last_key: Option<SMTH256>,
+}
+
+/// Update pending leaves of all partitions in parallel.
+fn build_partitions(
+    store: &Store,
+    leaf_col: Col,
+    branch_col: Col,
+    partitions: &mut [Partition],
+    progress: &dyn MigrationProgress,
+) -> Result<()> {
+    partitions
+        .par_iter_mut()
+        .try_for_each(|partition| -> Result<()> {
+            let leaves = std::mem::take(&mut partition.leaves);
+            if leaves.is_empty() {
+                return Ok(());
+            }
+            let len = leaves.len();
+            let mut tx = store.begin_transaction_skip_concurrency_control();
+            {
+                let mut smt = SMT::new_with_store(ColumnSMTStore {
+                    tx: &mut tx,
+                    leaf_col,
+                    branch_col,
+                    shared: Some(&mut partition.shared),
+                })?;
+                for (k, v) in leaves {
+                    smt.update(k, v).context("update smt")?;
+                    partition.last_key = Some(k);
+                }
+            }
+            tx.commit().context("commit smt partition batch")?;
+            progress.inc(len as u64);
+            Ok(())
+        })
+}
+
+/// SMT store over arbitrary leaf and branch columns.
+///
+/// If `shared` is set, branches at or above `SHARED_HEIGHT` are read from and
+/// written to it instead of the db.
+struct ColumnSMTStore<'a> {
+    tx: &'a mut StoreTransaction,
+    leaf_col: Col,
+    branch_col: Col,
+    shared: Option<&'a mut HashMap<BranchKey, BranchNode>>,
+}
+
+impl<'a> StoreReadOps<SMTH256> for ColumnSMTStore<'a> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, SMTError> {
+        match self.shared {
+            Some(ref shared) if branch_key.height >= SHARED_HEIGHT => {
+                Ok(shared.get(branch_key).cloned())
+            }
+            _ => Ok(self
+                .tx
+                .get(self.branch_col, &branch_key_to_vec(branch_key))
+                .map(|slice| slice_to_branch_node(&slice))),
+        }
+    }
+
+    fn get_leaf(&self, leaf_key: &SMTH256) -> Result<Option<SMTH256>, SMTError> {
+        match self.tx.get(self.leaf_col, leaf_key.as_slice()) {
+            Some(slice) if 32 == slice.len() => {
+                let leaf: [u8; 32] = slice.as_ref().try_into().unwrap();
+                Ok(Some(leaf.into()))
+            }
+            Some(_) => Err(SMTError::Store("get corrupted leaf".to_string())),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a> StoreWriteOps<SMTH256> for ColumnSMTStore<'a> {
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<(), SMTError> {
+        match self.shared {
+            Some(ref mut shared) if branch_key.height >= SHARED_HEIGHT => {
+                shared.insert(branch_key, branch);
+                Ok(())
+            }
+            _ => self
+                .tx
+                .insert_raw(
+                    self.branch_col,
+                    &branch_key_to_vec(&branch_key),
+                    &branch_node_to_vec(&branch),
+                )
+                .map_err(|err| SMTError::Store(format!("insert error {}", err))),
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf_key: SMTH256, leaf: SMTH256) -> Result<(), SMTError> {
+        self.tx
+            .insert_raw(self.leaf_col, leaf_key.as_slice(), leaf.as_slice())
+            .map_err(|err| SMTError::Store(format!("insert error {}", err)))
+    }
+
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<(), SMTError> {
+        match self.shared {
+            Some(ref mut shared) if branch_key.height >= SHARED_HEIGHT => {
+                shared.remove(branch_key);
+                Ok(())
+            }
+            _ => self
+                .tx
+                .delete(self.branch_col, &branch_key_to_vec(branch_key))
+                .map_err(|err| SMTError::Store(format!("delete error {}", err))),
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf_key: &SMTH256) -> Result<(), SMTError> {
+        self.tx
+            .delete(self.leaf_col, leaf_key.as_slice())
+            .map_err(|err| SMTError::Store(format!("delete error {}", err)))
+    }
+}
@@ -1,7 +1,19 @@
 use autorocks::{moveit::slot, Direction, Snapshot};
 
+use std::convert::TryInto;
+
+use gw_types::{h256::*, registry_address::RegistryAddress};
+
 use crate::{
-    schema::{Col, COLUMN_MEM_POOL_TRANSACTION},
+    account_tx_index::{self, AccountTx},
+    log_index::{self, LogPosition},
+    registry_index,
+    reorg_log::{self, ReorgRecord},
+    schema::{
+        Col, COLUMN_ACCOUNT_TX_INDEX, COLUMN_MEM_POOL_TRANSACTION, COLUMN_REGISTRY_ADDRESS_INDEX,
+        COLUMN_REORG_LOG, COLUMN_SUDT_REGISTRY, COLUMN_SUDT_TRANSFER_INDEX,
+    },
+    sudt_transfer_index::{self, SudtTransfer},
     traits::{chain_store::ChainStore, kv_store::KVStoreRead},
 };
 
@@ -34,3 +46,142 @@ impl StoreSnapshot {
             .map(|(k, _)| k)
     }
 }
+
+impl StoreSnapshot {
+    /// Positions of indexed logs with `prefix` in blocks `from..=to`, in order.
+    ///
+    /// `col` is `COLUMN_LOG_ADDRESS_INDEX` with a `log_index::address_prefix`,
+    /// or `COLUMN_LOG_TOPIC_INDEX` with a `log_index::topic_prefix`.
+    pub fn iter_log_positions<'a>(
+        &'a self,
+        col: Col,
+        prefix: &'a [u8],
+        from: u64,
+        to: u64,
+    ) -> impl Iterator<Item = LogPosition> + 'a {
+        let mut iter = self.inner.iter(col, Direction::Forward);
+        iter.seek(&log_index::position_key(prefix, from, 0));
+        iter.map_while(move |(key, value)| log_index::decode_entry(prefix, &key, &value))
+            .take_while(move |position| position.block_number <= to)
+    }
+
+    /// Indexed txs of `account_id` after the position `after`, i.e. (block
+    /// number, tx index), in ascending or descending order.
+    pub fn iter_account_txs(
+        &self,
+        account_id: u32,
+        after: Option<(u64, u32)>,
+        descending: bool,
+    ) -> impl Iterator<Item = AccountTx> + '_ {
+        let mut iter = if descending {
+            let (block_number, tx_index) = after.unwrap_or((u64::MAX, u32::MAX));
+            let mut iter = self
+                .inner
+                .iter(COLUMN_ACCOUNT_TX_INDEX, Direction::Backward);
+            iter.seek_for_prev(&account_tx_index::position_key(
+                account_id,
+                block_number,
+                tx_index,
+            ));
+            iter
+        } else {
+            let (block_number, tx_index) = after.unwrap_or((0, 0));
+            let mut iter = self.inner.iter(COLUMN_ACCOUNT_TX_INDEX, Direction::Forward);
+            iter.seek(&account_tx_index::position_key(
+                account_id,
+                block_number,
+                tx_index,
+            ));
+            iter
+        };
+        // skip `after` itself
+        let skip = after.map_or(false, |(block_number, tx_index)| {
+            iter.key().map_or(false, |key| {
+                key == account_tx_index::position_key(account_id, block_number, tx_index)
+            })
+        });
+        if skip {
+            iter.next();
+        }
+        iter.map_while(move |(key, value)| account_tx_index::decode_entry(account_id, &key, &value))
+    }
+
+    /// sUDT transfers of `address` as the sender or the receiver, after the
+    /// (block number, transfer index) position `after` if any. See
+    /// `sudt_transfer_index`.
+    pub fn iter_sudt_transfers(
+        &self,
+        address: &RegistryAddress,
+        after: Option<(u64, u32)>,
+        descending: bool,
+    ) -> impl Iterator<Item = SudtTransfer> + '_ {
+        let mut iter = if descending {
+            let (block_number, transfer_index) = after.unwrap_or((u64::MAX, u32::MAX));
+            let mut iter = self
+                .inner
+                .iter(COLUMN_SUDT_TRANSFER_INDEX, Direction::Backward);
+            iter.seek_for_prev(&sudt_transfer_index::position_key(
+                address,
+                block_number,
+                transfer_index,
+            ));
+            iter
+        } else {
+            let (block_number, transfer_index) = after.unwrap_or((0, 0));
+            let mut iter = self
+                .inner
+                .iter(COLUMN_SUDT_TRANSFER_INDEX, Direction::Forward);
+            iter.seek(&sudt_transfer_index::position_key(
+                address,
+                block_number,
+                transfer_index,
+            ));
+            iter
+        };
+        // skip `after` itself
+        let skip = after.map_or(false, |(block_number, transfer_index)| {
+            iter.key().map_or(false, |key| {
+                key == sudt_transfer_index::position_key(address, block_number, transfer_index)
+            })
+        });
+        if skip {
+            iter.next();
+        }
+        let prefix = address.to_bytes();
+        iter.map_while(move |(key, value)| sudt_transfer_index::decode_entry(&prefix, &key, &value))
+    }
+
+    /// Indexed registry addresses of `script_hash` with the account id. See
+    /// `registry_index`, entries may be stale after blocks are detached.
+    pub fn iter_registry_addresses<'a>(
+        &'a self,
+        script_hash: &'a H256,
+    ) -> impl Iterator<Item = (RegistryAddress, u32)> + 'a {
+        let mut iter = self
+            .inner
+            .iter(COLUMN_REGISTRY_ADDRESS_INDEX, Direction::Forward);
+        iter.seek(script_hash.as_slice());
+        iter.map_while(move |(key, value)| registry_index::decode_entry(script_hash, &key, &value))
+    }
+
+    /// Registered L1 sUDT script hashes with the L2 sUDT account id, ordered
+    /// by script hash.
+    pub fn iter_sudt_registry(&self) -> impl Iterator<Item = (H256, u32)> + '_ {
+        { self.inner.iter(COLUMN_SUDT_REGISTRY, Direction::Forward) }.filter_map(|(key, value)| {
+            let l1_script_hash: H256 = key.as_ref().try_into().ok()?;
+            let account_id = u32::from_le_bytes(value.get(..4)?.try_into().ok()?);
+            Some((l1_script_hash, account_id))
+        })
+    }
+
+    /// Reorg records with ids less than `before`, newest first.
+    pub fn iter_reorg_records(
+        &self,
+        before: Option<u64>,
+    ) -> impl Iterator<Item = (u64, ReorgRecord)> + '_ {
+        reorg_log::list(
+            self.inner.iter(COLUMN_REORG_LOG, Direction::Backward),
+            before,
+        )
+    }
+}
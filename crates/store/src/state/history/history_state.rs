@@ -4,7 +4,8 @@ use std::cell::RefCell;
 
 use anyhow::Result;
 use gw_common::{error::Error as StateError, state::State};
-use gw_smt::smt::SMT;
+use gw_smt::smt::{SMT, SMTH256};
+use gw_smt::sparse_merkle_tree::CompiledMerkleProof;
 use gw_traits::CodeStore;
 use gw_types::{
     bytes::Bytes,
@@ -163,6 +164,13 @@ impl<Store: HistoryStateStore + CodeStore + KVStore> HistoryState<Store> {
             .build()
     }
 
+    /// Compiled merkle proof of `keys` against the current root.
+    pub fn merkle_proof(&self, keys: Vec<H256>) -> Result<CompiledMerkleProof> {
+        let keys: Vec<SMTH256> = keys.into_iter().map(Into::into).collect();
+        let proof = self.tree.merkle_proof(keys.clone())?.compile(keys)?;
+        Ok(proof)
+    }
+
     /// Detach block state from state tree
     pub fn detach_block_state(&mut self, block_number: u64) -> Result<()> {
         // reset states to previous value
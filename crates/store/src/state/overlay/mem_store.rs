@@ -16,6 +16,7 @@ use gw_types::{
 };
 use im::HashMap;
 
+use super::read_cache::ReadCache;
 use crate::{
     schema::{Col, COLUMN_DATA, COLUMN_SCRIPT},
     state::history::{block_state_record::BlockStateRecordKey, history_state::HistoryStateStore},
@@ -39,6 +40,8 @@ pub struct MemStore<S> {
     // (column, key) -> value.
     mem: ColumnsKeyValueMap,
     history_mem: KeyValueMapByBlock,
+    /// Cache of reads from `inner`, only if it's immutable, e.g. a snapshot.
+    read_cache: Option<ReadCache>,
 }
 
 impl<S> MemStore<S> {
@@ -47,8 +50,15 @@ impl<S> MemStore<S> {
             inner: inner.into(),
             mem: Default::default(),
             history_mem: Default::default(),
+            read_cache: None,
         }
     }
+
+    /// Cache account reads from `inner`, which must not change.
+    pub fn with_read_cache(mut self, read_cache: ReadCache) -> Self {
+        self.read_cache = Some(read_cache);
+        self
+    }
 }
 
 impl<S: KVStoreRead> ChainStore for MemStore<S> {}
@@ -80,7 +90,10 @@ impl<S: KVStoreRead> KVStoreRead for MemStore<S> {
         match self.mem.get(&(col, key) as &dyn Key) {
             Some(Value::Exist(v)) => Some(v.clone().into_boxed_slice()),
             Some(Value::Deleted) => None,
-            None => self.inner.get(col, key),
+            None => match self.read_cache {
+                Some(ref cache) => cache.get(col, key, || self.inner.get(col, key)),
+                None => self.inner.get(col, key),
+            },
         }
     }
 }
@@ -155,6 +168,7 @@ impl<S> Clone for MemStore<S> {
             inner: self.inner.clone(),
             mem: self.mem.clone(),
             history_mem: self.history_mem.clone(),
+            read_cache: self.read_cache.clone(),
         }
     }
 }
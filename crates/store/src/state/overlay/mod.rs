@@ -1,2 +1,3 @@
 pub mod mem_state;
 pub mod mem_store;
+pub mod read_cache;
@@ -0,0 +1,194 @@
+//! LRU cache of account state reads from the snapshot under a `MemStore`,
+//! i.e. SMT leaves and branches, and scripts.
+//!
+//! Account fields, e.g. nonces, script hashes, registry addresses and
+//! balances, are SMT leaves, so hot accounts are read from RocksDB again and
+//! again, e.g. to verify each of their txs, and so are the branches near the
+//! root by every SMT update. Writes go to the overlay of `MemStore`, which is
+//! read first.
+//!
+//! The cache is versioned by the tip block of snapshots, so that it's shared
+//! across mem pool resets. Advancing to a new tip removes leaves and branches
+//! changed by new blocks, according to their state diffs. Reads of snapshots
+//! of other tips bypass the cache.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use gw_smt::{smt_h256_ext::SMTH256, sparse_merkle_tree::BranchKey};
+use gw_types::{h256::*, prelude::*};
+use lru::LruCache;
+
+use crate::{
+    schema::{Col, COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_SCRIPT},
+    smt::serde::branch_key_to_vec,
+    snapshot::StoreSnapshot,
+    traits::chain_store::ChainStore,
+};
+
+/// Entries of each cache, about 100 bytes each.
+pub const READ_CACHE_ENTRIES: usize = 100_000;
+
+/// Clear the cache instead if the new tip is further ahead.
+const MAX_ADVANCE_BLOCKS: usize = 16;
+
+type Entries = LruCache<(Col, Vec<u8>), Option<Box<[u8]>>>;
+
+struct Inner {
+    /// Tip block hash of the snapshot that entries are read from.
+    tip: Option<H256>,
+    entries: Entries,
+}
+
+/// Shared by clones.
+#[derive(Clone)]
+pub struct ReadCache {
+    inner: Arc<Mutex<Inner>>,
+    /// Tip block hash of the snapshot read through this handle.
+    tip: Option<H256>,
+}
+
+impl ReadCache {
+    /// Reads through the returned handle are cached regardless of snapshots,
+    /// use handles returned by `advance` instead if the cache is shared.
+    pub fn new(capacity: usize) -> Self {
+        let inner = Inner {
+            tip: None,
+            entries: LruCache::new(capacity),
+        };
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            tip: None,
+        }
+    }
+
+    /// Advance the cache to the tip of `snapshot`, returns a handle to read
+    /// `snapshot` through it.
+    pub fn advance(&self, snapshot: &StoreSnapshot) -> Result<Self> {
+        let new_tip = snapshot.get_last_valid_tip_block_hash()?;
+        let mut inner = self.inner.lock().expect("lock");
+        if inner.tip != Some(new_tip) {
+            match changed_keys(snapshot, inner.tip, new_tip)? {
+                Some(keys) => {
+                    for key in keys {
+                        invalidate(&mut inner.entries, &key);
+                    }
+                }
+                None => inner.entries.clear(),
+            }
+            inner.tip = Some(new_tip);
+        }
+        Ok(Self {
+            inner: self.inner.clone(),
+            tip: Some(new_tip),
+        })
+    }
+
+    /// Value of `key`, from the cache or `read`.
+    pub fn get(
+        &self,
+        col: Col,
+        key: &[u8],
+        read: impl FnOnce() -> Option<Box<[u8]>>,
+    ) -> Option<Box<[u8]>> {
+        if !matches!(
+            col,
+            COLUMN_ACCOUNT_SMT_LEAF | COLUMN_ACCOUNT_SMT_BRANCH | COLUMN_SCRIPT
+        ) {
+            return read();
+        }
+        let cache_key = (col, key.to_vec());
+        {
+            let mut inner = self.inner.lock().expect("lock");
+            if inner.tip != self.tip {
+                return read();
+            }
+            if let Some(value) = inner.entries.get(&cache_key) {
+                return value.clone();
+            }
+        }
+        let value = read();
+        // Scripts are never changed, but may be added by new blocks.
+        if col == COLUMN_SCRIPT && value.is_none() {
+            return value;
+        }
+        let mut inner = self.inner.lock().expect("lock");
+        // Skip if advanced while reading.
+        if inner.tip == self.tip {
+            inner.entries.put(cache_key, value.clone());
+        }
+        value
+    }
+}
+
+/// State keys changed by blocks after `old_tip` up to `new_tip`, `None` if
+/// unknown, e.g. `old_tip` is not an ancestor of `new_tip` within
+/// `MAX_ADVANCE_BLOCKS`.
+fn changed_keys(
+    snapshot: &StoreSnapshot,
+    old_tip: Option<H256>,
+    new_tip: H256,
+) -> Result<Option<Vec<H256>>> {
+    let old_tip = match old_tip {
+        Some(tip) => tip,
+        None => return Ok(None),
+    };
+    let mut keys = Vec::new();
+    let mut block_hash = new_tip;
+    for _ in 0..MAX_ADVANCE_BLOCKS {
+        let block = match snapshot.get_block(&block_hash)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let diff = match snapshot.get_block_state_diff(block.raw().number().unpack()) {
+            Some(diff) => diff,
+            None => return Ok(None),
+        };
+        keys.extend(diff.entries.into_iter().map(|e| e.key));
+        block_hash = block.raw().parent_block_hash().unpack();
+        if block_hash == old_tip {
+            return Ok(Some(keys));
+        }
+    }
+    Ok(None)
+}
+
+/// Remove the leaf of `key` and branches on its path.
+fn invalidate(entries: &mut Entries, key: &H256) {
+    entries.pop(&(COLUMN_ACCOUNT_SMT_LEAF, key.as_slice().to_vec()));
+    let smt_key: SMTH256 = (*key).into();
+    for height in 0..=u8::MAX {
+        let branch_key = BranchKey::new(height, smt_key.parent_path(height));
+        entries.pop(&(COLUMN_ACCOUNT_SMT_BRANCH, branch_key_to_vec(&branch_key)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate() {
+        let cache = ReadCache::new(100);
+        let branch = |key: &H256, height: u8| {
+            let smt_key: SMTH256 = (*key).into();
+            branch_key_to_vec(&BranchKey::new(height, smt_key.parent_path(height)))
+        };
+        let (a, b) = (H256::from_u32(1), H256::from_u32(2));
+        for key in [a, b] {
+            cache.get(COLUMN_ACCOUNT_SMT_LEAF, key.as_slice(), || None);
+            cache.get(COLUMN_ACCOUNT_SMT_BRANCH, &branch(&key, 0), || None);
+        }
+        // The root branch is shared.
+        cache.get(COLUMN_ACCOUNT_SMT_BRANCH, &branch(&a, 255), || None);
+
+        let mut inner = cache.inner.lock().unwrap();
+        invalidate(&mut inner.entries, &a);
+        let contains = |col, key: &[u8]| inner.entries.contains(&(col, key.to_vec()));
+        assert!(!contains(COLUMN_ACCOUNT_SMT_LEAF, a.as_slice()));
+        assert!(!contains(COLUMN_ACCOUNT_SMT_BRANCH, &branch(&a, 0)));
+        assert!(!contains(COLUMN_ACCOUNT_SMT_BRANCH, &branch(&b, 255)));
+        assert!(contains(COLUMN_ACCOUNT_SMT_LEAF, b.as_slice()));
+        assert!(contains(COLUMN_ACCOUNT_SMT_BRANCH, &branch(&b, 0)));
+    }
+}
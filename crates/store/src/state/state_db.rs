@@ -22,7 +22,7 @@ use gw_types::{
 };
 
 use gw_common::{error::Error as StateError, state::State};
-use gw_smt::smt::SMT;
+use gw_smt::{smt::SMT, sparse_merkle_tree::CompiledMerkleProof};
 use gw_types::h256::{H256Ext, H256};
 
 use crate::{
@@ -34,7 +34,11 @@ use crate::{
 
 use super::{
     history::history_state::RWConfig,
-    overlay::{mem_state::MemStateTree, mem_store::MemStore},
+    overlay::{
+        mem_state::MemStateTree,
+        mem_store::MemStore,
+        read_cache::{ReadCache, READ_CACHE_ENTRIES},
+    },
     traits::JournalDB,
     BlockStateDB, MemStateDB,
 };
@@ -164,13 +168,20 @@ impl<S: State + CodeStore + Clone> StateDB<S> {
 }
 
 impl MemStateDB {
-    /// From store
+    /// From store, with a read cache of accounts in `store`.
     pub fn from_store(store: StoreSnapshot) -> Result<Self> {
+        Self::from_store_with_cache(store, &ReadCache::new(READ_CACHE_ENTRIES))
+    }
+
+    /// From store, with `read_cache` advanced to `store`.
+    pub fn from_store_with_cache(store: StoreSnapshot, read_cache: &ReadCache) -> Result<Self> {
         // build from last valid block
         let block = store.get_last_valid_tip_block()?;
         let tip_state = block.raw().post_account();
         let root: H256 = tip_state.merkle_root().unpack();
-        let smt = SMT::new(root.into(), SMTStateStore::new(MemStore::new(store)));
+        let read_cache = read_cache.advance(&store)?;
+        let mem_store = MemStore::new(store).with_read_cache(read_cache);
+        let smt = SMT::new(root.into(), SMTStateStore::new(mem_store));
         let inner = MemStateTree::new(smt, tip_state.count().unpack());
         Ok(Self::new(inner))
     }
@@ -197,6 +208,13 @@ impl<Store: ChainStore + HistoryStateStore + CodeStore + KVStore> BlockStateDB<S
     pub fn detach_block_state(&mut self, block_number: u64) -> Result<()> {
         self.state.detach_block_state(block_number)
     }
+
+    /// Merkle proof of `keys` against the state root
+    /// The caller must avoid has dirty state, otherwise, the proof is against the last finalised root
+    pub fn merkle_proof(&self, keys: Vec<H256>) -> Result<CompiledMerkleProof> {
+        debug_assert!(!self.is_dirty(), "merkle proof of dirty state");
+        self.state.merkle_proof(keys)
+    }
 }
 
 impl<S: State + CodeStore> StateDB<S> {
@@ -542,7 +560,11 @@ mod tests {
         snapshot::StoreSnapshot,
         state::{
             history::history_state::{HistoryState, RWConfig, ReadOpt, WriteOpt},
-            overlay::{mem_state::MemStateTree, mem_store::MemStore},
+            overlay::{
+                mem_state::MemStateTree,
+                mem_store::MemStore,
+                read_cache::{ReadCache, READ_CACHE_ENTRIES},
+            },
             traits::JournalDB,
             BlockStateDB, MemStateDB,
         },
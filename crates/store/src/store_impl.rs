@@ -1,5 +1,6 @@
 //! Storage implementation
 
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -63,6 +64,21 @@ impl Store {
         }
     }
 
+    /// Begin transaction whose commit fsyncs the WAL if `sync`.
+    pub fn begin_transaction_with_sync(&self, sync: bool) -> StoreTransaction {
+        moveit! {
+            let mut write_options = WriteOptions::new();
+            let mut transaction_options = TransactionOptions::new();
+        }
+        write_options.as_mut().sync = sync;
+        transaction_options.as_mut().set_snapshot = true;
+        StoreTransaction {
+            inner: self
+                .db
+                .begin_transaction_with_options(&write_options, &transaction_options),
+        }
+    }
+
     /// Begin transaction but disable concurrency control.
     ///
     /// This should be faster than a normal transaction when you know that there
@@ -148,6 +164,20 @@ impl Store {
         StoreSnapshot::new(self.db.snapshot())
     }
 
+    /// Flush memtables to disk, so that the next start doesn't replay the
+    /// WAL.
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Create a consistent checkpoint of the database in `dir`, see
+    /// `backup`. It's safe to call while the node is running.
+    pub fn create_checkpoint(&self, dir: &Path) -> Result<()> {
+        self.db.create_checkpoint(dir)?;
+        Ok(())
+    }
+
     pub fn as_inner(&self) -> &TransactionDb {
         &self.db
     }
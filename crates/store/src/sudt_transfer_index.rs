@@ -0,0 +1,134 @@
+//! Index of sUDT transfers, i.e. sUDT transfer logs of tx receipts, by the
+//! sender and the receiver registry addresses. Fee payments are not indexed.
+//!
+//! Keys are a serialized registry address followed by the block number and
+//! the transfer index in the block, both in big endian so that transfers of an
+//! address are in order. Values are the tx hash, the tx index in the block
+//! and the sUDT id, followed by the amount and the serialized sender and
+//! receiver addresses.
+//!
+//! Only transfers of valid blocks on the main chain are indexed.
+
+use std::convert::TryInto;
+
+use gw_types::{
+    h256::*,
+    packed::{L2Block, LogItem, TxReceipt},
+    prelude::*,
+    registry_address::RegistryAddress,
+    U256,
+};
+
+/// Service flag of sUDT transfer logs.
+pub const GW_LOG_SUDT_TRANSFER: u8 = 0x0;
+
+/// Block number and transfer index.
+const POSITION_LEN: usize = 8 + 4;
+/// Tx hash, tx index, sUDT id and amount.
+const VALUE_HEADER_LEN: usize = 32 + 4 + 4 + 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SudtTransfer {
+    pub block_number: u64,
+    /// Index of the transfer among sUDT transfers of the block.
+    pub transfer_index: u32,
+    pub tx_hash: H256,
+    pub tx_index: u32,
+    pub sudt_id: u32,
+    pub from_address: RegistryAddress,
+    pub to_address: RegistryAddress,
+    pub amount: U256,
+}
+
+/// Parse a sUDT transfer log as (sUDT id, from, to, amount), `None` for other
+/// logs or malformed ones.
+pub fn parse_sudt_transfer_log(
+    item: &LogItem,
+) -> Option<(u32, RegistryAddress, RegistryAddress, U256)> {
+    if u8::from(item.service_flag()) != GW_LOG_SUDT_TRANSFER {
+        return None;
+    }
+    let raw_data = item.data().raw_data();
+    let (from_address, to_address, amount) = parse_transfer(raw_data.as_ref())?;
+    Some((item.account_id().unpack(), from_address, to_address, amount))
+}
+
+/// from address | to address | amount (32 bytes, little endian)
+fn parse_transfer(data: &[u8]) -> Option<(RegistryAddress, RegistryAddress, U256)> {
+    let from_address = RegistryAddress::from_slice(data)?;
+    let data = &data[from_address.len()..];
+    let to_address = RegistryAddress::from_slice(data)?;
+    let data = &data[to_address.len()..];
+    if data.len() != 32 {
+        return None;
+    }
+    Some((from_address, to_address, U256::from_little_endian(data)))
+}
+
+/// Key of a transfer of `address` at `block_number`, `transfer_index`.
+pub fn position_key(address: &RegistryAddress, block_number: u64, transfer_index: u32) -> Vec<u8> {
+    let mut key = address.to_bytes();
+    key.extend_from_slice(&block_number.to_be_bytes());
+    key.extend_from_slice(&transfer_index.to_be_bytes());
+    key
+}
+
+/// Index entries of sUDT transfers of `block` with `receipts`, as (key,
+/// value).
+pub(crate) fn block_entries(block: &L2Block, receipts: &[TxReceipt]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let block_number = block.raw().number().unpack();
+    let mut entries = Vec::new();
+    let mut transfer_index = 0u32;
+    for (tx_index, (tx, receipt)) in block.transactions().into_iter().zip(receipts).enumerate() {
+        for item in receipt.logs().into_iter() {
+            let (sudt_id, from_address, to_address, amount) = match parse_sudt_transfer_log(&item) {
+                Some(transfer) => transfer,
+                None => continue,
+            };
+            let mut value = Vec::with_capacity(VALUE_HEADER_LEN + 56);
+            value.extend_from_slice(&tx.hash());
+            value.extend_from_slice(&(tx_index as u32).to_le_bytes());
+            value.extend_from_slice(&sudt_id.to_le_bytes());
+            let mut amount_bytes = [0u8; 32];
+            amount.to_little_endian(&mut amount_bytes);
+            value.extend_from_slice(&amount_bytes);
+            value.extend_from_slice(&from_address.to_bytes());
+            value.extend_from_slice(&to_address.to_bytes());
+
+            entries.push((
+                position_key(&from_address, block_number, transfer_index),
+                value.clone(),
+            ));
+            if to_address != from_address {
+                entries.push((
+                    position_key(&to_address, block_number, transfer_index),
+                    value,
+                ));
+            }
+            transfer_index += 1;
+        }
+    }
+    entries
+}
+
+/// Decode an index entry, `None` if `key` is not of `prefix`, i.e. a
+/// serialized registry address.
+pub(crate) fn decode_entry(prefix: &[u8], key: &[u8], value: &[u8]) -> Option<SudtTransfer> {
+    if key.len() != prefix.len() + POSITION_LEN || !key.starts_with(prefix) {
+        return None;
+    }
+    let position = &key[prefix.len()..];
+    let addresses = value.get(VALUE_HEADER_LEN..)?;
+    let from_address = RegistryAddress::from_slice(addresses)?;
+    let to_address = RegistryAddress::from_slice(&addresses[from_address.len()..])?;
+    Some(SudtTransfer {
+        block_number: u64::from_be_bytes(position[..8].try_into().ok()?),
+        transfer_index: u32::from_be_bytes(position[8..].try_into().ok()?),
+        tx_hash: value.get(..32)?.try_into().ok()?,
+        tx_index: u32::from_le_bytes(value.get(32..36)?.try_into().ok()?),
+        sudt_id: u32::from_le_bytes(value.get(36..40)?.try_into().ok()?),
+        from_address,
+        to_address,
+        amount: U256::from_little_endian(&value[40..VALUE_HEADER_LEN]),
+    })
+}
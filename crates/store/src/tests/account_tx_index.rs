@@ -0,0 +1,79 @@
+use gw_types::{
+    packed::{L2Block, L2Transaction, RawL2Block, RawL2Transaction},
+    prelude::*,
+};
+
+use crate::{
+    account_tx_index::{self, ROLE_RECEIVER, ROLE_SENDER},
+    schema::COLUMN_ACCOUNT_TX_INDEX,
+    traits::kv_store::KVStoreWrite,
+    Store,
+};
+
+fn block(number: u64, txs: &[(u32, u32)]) -> L2Block {
+    let txs: Vec<_> = txs
+        .iter()
+        .map(|(from_id, to_id)| {
+            let raw = RawL2Transaction::new_builder()
+                .from_id(from_id.pack())
+                .to_id(to_id.pack())
+                .build();
+            L2Transaction::new_builder().raw(raw).build()
+        })
+        .collect();
+    L2Block::new_builder()
+        .raw(RawL2Block::new_builder().number(number.pack()).build())
+        .transactions(txs.pack())
+        .build()
+}
+
+#[test]
+fn test_account_tx_index() {
+    let blocks = [block(5, &[(1, 2), (3, 3)]), block(6, &[(2, 1), (1, 4)])];
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    for block in blocks.iter() {
+        for (key, value) in account_tx_index::block_entries(block) {
+            db.insert_raw(COLUMN_ACCOUNT_TX_INDEX, &key, &value)
+                .unwrap();
+        }
+    }
+    db.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let positions = |account_id, after, descending| -> Vec<_> {
+        snap.iter_account_txs(account_id, after, descending)
+            .map(|tx| (tx.block_number, tx.tx_index, tx.role))
+            .collect()
+    };
+    assert_eq!(
+        positions(1, None, false),
+        vec![
+            (5, 0, ROLE_SENDER),
+            (6, 0, ROLE_RECEIVER),
+            (6, 1, ROLE_SENDER)
+        ]
+    );
+    assert_eq!(
+        positions(1, None, true),
+        vec![
+            (6, 1, ROLE_SENDER),
+            (6, 0, ROLE_RECEIVER),
+            (5, 0, ROLE_SENDER)
+        ]
+    );
+    assert_eq!(positions(1, Some((6, 0)), false), vec![(6, 1, ROLE_SENDER)]);
+    assert_eq!(positions(1, Some((6, 0)), true), vec![(5, 0, ROLE_SENDER)]);
+    assert_eq!(
+        positions(3, None, false),
+        vec![(5, 1, ROLE_SENDER | ROLE_RECEIVER)]
+    );
+    assert_eq!(positions(4, None, true), vec![(6, 1, ROLE_RECEIVER)]);
+    assert!(positions(5, None, false).is_empty());
+    assert!(positions(0, None, true).is_empty());
+
+    let tx_hash = blocks[1].transactions().get(1).unwrap().hash();
+    let tx = snap.iter_account_txs(4, None, false).next().unwrap();
+    assert_eq!(tx.tx_hash, tx_hash);
+}
@@ -0,0 +1,65 @@
+use ckb_fixed_hash::H256;
+use gw_types::{
+    packed::{L2Block, RawL2Block},
+    prelude::*,
+};
+
+use crate::{
+    backup::{create_backup, restore_backup, BackupManifest},
+    readonly::StoreReadonly,
+    schema::{COLUMNS, COLUMN_BLOCK},
+    traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
+    Store,
+};
+
+fn set_tip(store: &Store, number: u64) -> H256 {
+    let block = L2Block::new_builder()
+        .raw(RawL2Block::new_builder().number(number.pack()).build())
+        .build();
+    let mut db = store.begin_transaction();
+    db.insert_raw(COLUMN_BLOCK, &block.hash(), block.as_slice())
+        .unwrap();
+    db.set_tip_block_hash(block.hash()).unwrap();
+    db.commit().unwrap();
+    H256(block.hash())
+}
+
+#[test]
+fn test_backup_restore() {
+    let rollup_type_hash = H256([1u8; 32]);
+    let store = Store::open_tmp().unwrap();
+    let tip_block_hash = set_tip(&store, 7);
+
+    let dir = tempfile::tempdir().unwrap();
+    let backup_dir = dir.path().join("backup");
+    let manifest =
+        create_backup(&store, rollup_type_hash.clone(), "1.0".into(), &backup_dir).unwrap();
+    let expected = BackupManifest {
+        rollup_type_hash: rollup_type_hash.clone(),
+        tip_block_number: 7,
+        tip_block_hash: tip_block_hash.clone(),
+        version: "1.0".into(),
+    };
+    assert_eq!(manifest, expected);
+    assert_eq!(BackupManifest::read(&backup_dir).unwrap(), expected);
+    assert!(create_backup(&store, rollup_type_hash.clone(), "1.0".into(), &backup_dir).is_err());
+
+    // Writes after the backup are not in it.
+    set_tip(&store, 8);
+
+    let store_path = dir.path().join("store");
+    assert!(restore_backup(&backup_dir, &H256([2u8; 32]), &store_path).is_err());
+    assert!(!store_path.exists());
+    let restored = restore_backup(&backup_dir, &rollup_type_hash, &store_path).unwrap();
+    assert_eq!(restored, expected);
+    assert!(restore_backup(&backup_dir, &rollup_type_hash, &store_path).is_err());
+
+    let restored = StoreReadonly::open(&store_path, COLUMNS).unwrap();
+    let tip = restored.get_tip_block().unwrap();
+    assert_eq!(tip.raw().number().unpack(), 7u64);
+    assert_eq!(H256(tip.hash()), tip_block_hash);
+
+    // Restoring again from the untouched backup works.
+    let restored = restore_backup(&backup_dir, &rollup_type_hash, &dir.path().join("store2"));
+    assert_eq!(restored.unwrap(), expected);
+}
@@ -0,0 +1,64 @@
+use crate::{
+    block_cycles::{BackendCycles, BlockCyclesReport, SyscallCycles},
+    traits::chain_store::ChainStore,
+    Store,
+};
+
+#[test]
+fn test_block_cycles_report() {
+    let mut report = BlockCyclesReport::default();
+    report.add_tx([1u8; 32], 1000, vec![("store", 2, 100), ("load", 3, 30)]);
+    report.add_tx([2u8; 32], 500, vec![("load", 1, 10)]);
+    report.add_tx([1u8; 32], 2000, vec![("log", 1, 50)]);
+    assert_eq!(
+        report.backends,
+        vec![
+            BackendCycles {
+                validator_script_type_hash: [1u8; 32],
+                tx_count: 2,
+                execution_cycles: 3000,
+                syscall_cycles: 180,
+            },
+            BackendCycles {
+                validator_script_type_hash: [2u8; 32],
+                tx_count: 1,
+                execution_cycles: 500,
+                syscall_cycles: 10,
+            },
+        ]
+    );
+    let syscalls: Vec<_> = report
+        .syscalls
+        .iter()
+        .map(
+            |SyscallCycles {
+                 name,
+                 count,
+                 cycles,
+             }| (name.as_str(), *count, *cycles),
+        )
+        .collect();
+    assert_eq!(
+        syscalls,
+        vec![("load", 4, 40), ("log", 1, 50), ("store", 2, 100)]
+    );
+
+    let bytes = report.to_vec();
+    assert_eq!(BlockCyclesReport::from_slice(&bytes), Some(report.clone()));
+    assert_eq!(BlockCyclesReport::from_slice(&bytes[1..]), None);
+    assert_eq!(
+        BlockCyclesReport::from_slice(&bytes[..bytes.len() - 1]),
+        None
+    );
+    assert_eq!(
+        BlockCyclesReport::from_slice(&BlockCyclesReport::default().to_vec()),
+        Some(BlockCyclesReport::default())
+    );
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    db.set_block_cycles_report(7, &report).unwrap();
+    db.commit().unwrap();
+    assert_eq!(store.get_block_cycles_report(7), Some(report));
+    assert_eq!(store.get_block_cycles_report(8), None);
+}
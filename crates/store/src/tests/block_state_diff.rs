@@ -0,0 +1,43 @@
+use crate::{
+    block_state_diff::{BlockStateDiff, StateDiffEntry},
+    state::history::history_state::HistoryStateStore,
+    traits::chain_store::ChainStore,
+    Store,
+};
+
+#[test]
+fn test_block_state_diff() {
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    db.record_block_state(1, [1u8; 32], [10u8; 32]).unwrap();
+    db.record_block_state(1, [2u8; 32], [20u8; 32]).unwrap();
+    db.record_block_state(2, [2u8; 32], [21u8; 32]).unwrap();
+    db.record_block_state(2, [3u8; 32], [30u8; 32]).unwrap();
+
+    // key 1 is only read by block 2
+    let diff = BlockStateDiff::from_history(&db, 2, vec![[3u8; 32], [1u8; 32], [2u8; 32]]);
+    let expected = BlockStateDiff {
+        entries: vec![
+            StateDiffEntry {
+                key: [2u8; 32],
+                old_value: [20u8; 32],
+                new_value: [21u8; 32],
+            },
+            StateDiffEntry {
+                key: [3u8; 32],
+                old_value: [0u8; 32],
+                new_value: [30u8; 32],
+            },
+        ],
+    };
+    assert_eq!(diff, expected);
+
+    let bytes = diff.to_vec();
+    assert_eq!(BlockStateDiff::from_slice(&bytes), Some(diff.clone()));
+    assert_eq!(BlockStateDiff::from_slice(&bytes[1..]), None);
+
+    db.set_block_state_diff(2, &diff).unwrap();
+    db.commit().unwrap();
+    assert_eq!(store.get_block_state_diff(2), Some(diff));
+    assert_eq!(store.get_block_state_diff(3), None);
+}
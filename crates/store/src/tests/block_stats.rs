@@ -0,0 +1,32 @@
+use crate::{block_stats::BlockStats, traits::chain_store::ChainStore, Store};
+
+#[test]
+fn test_block_stats() {
+    let stats = BlockStats {
+        used_cycles: 1_000_000,
+        tx_count: 4,
+        withdrawal_count: 1,
+        deposit_count: 2,
+        gas_used: 100,
+        total_fee: 10 + 60 + 300,
+        gas_prices: vec![(1, 10), (2, 30), (5, 60)],
+    };
+    assert_eq!(BlockStats::from_slice(&stats.to_vec()), Some(stats.clone()));
+    assert_eq!(BlockStats::from_slice(&stats.to_vec()[1..]), None);
+
+    assert_eq!(
+        stats.gas_price_percentiles(&[0.0, 25.0, 50.0, 100.0]),
+        vec![1, 2, 5, 5]
+    );
+    assert_eq!(
+        BlockStats::default().gas_price_percentiles(&[10.0, 90.0]),
+        vec![0, 0]
+    );
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    db.set_block_stats(7, &stats).unwrap();
+    db.commit().unwrap();
+    assert_eq!(store.get_block_stats(7), Some(stats));
+    assert_eq!(store.get_block_stats(8), None);
+}
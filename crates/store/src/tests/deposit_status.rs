@@ -0,0 +1,72 @@
+use gw_types::{
+    packed::{CellInfo, DepositInfo, L2Block, OutPoint, RawL2Block},
+    prelude::*,
+};
+
+use crate::{
+    deposit_status::DepositStatus, traits::chain_store::ChainStore, transaction::StoreTransaction,
+    Store,
+};
+
+#[test]
+fn test_deposit_status() {
+    let credited = DepositStatus::Credited { block_number: 7 };
+    for status in [DepositStatus::Collected, DepositStatus::Packaged, credited] {
+        assert_eq!(DepositStatus::from_slice(&status.to_vec()), Some(status));
+    }
+    assert_eq!(DepositStatus::from_slice(&credited.to_vec()[1..]), None);
+    assert_eq!(DepositStatus::from_slice(&[3]), None);
+
+    let out_point = |index: u32| OutPoint::new_builder().index(index.pack()).build();
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    db.set_deposit_status(&out_point(0), DepositStatus::Packaged)
+        .unwrap();
+    db.set_deposit_status(&out_point(1), credited).unwrap();
+    db.commit().unwrap();
+    assert_eq!(
+        store.get_deposit_status(&out_point(0)),
+        Some(DepositStatus::Packaged)
+    );
+    assert_eq!(store.get_deposit_status(&out_point(1)), Some(credited));
+    assert_eq!(store.get_deposit_status(&out_point(2)), None);
+
+    let mut db = store.begin_transaction();
+    db.delete_deposit_status(&out_point(1)).unwrap();
+    db.commit().unwrap();
+    assert_eq!(store.get_deposit_status(&out_point(1)), None);
+}
+
+#[test]
+fn test_insert_block_rejects_credited_deposits() {
+    let deposit = |index: u32| {
+        let out_point = OutPoint::new_builder().index(index.pack()).build();
+        let cell = CellInfo::new_builder().out_point(out_point).build();
+        DepositInfo::new_builder().cell(cell).build()
+    };
+    let insert_block = |db: &mut StoreTransaction, deposits: Vec<DepositInfo>| {
+        let block = L2Block::new_builder()
+            .raw(RawL2Block::new_builder().number(7u64.pack()).build())
+            .build();
+        db.insert_block(
+            block,
+            Default::default(),
+            Default::default(),
+            Vec::new(),
+            deposits.pack(),
+            Vec::new(),
+        )
+    };
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    db.set_deposit_status(
+        &deposit(0).cell().out_point(),
+        DepositStatus::Credited { block_number: 3 },
+    )
+    .unwrap();
+
+    let err = insert_block(&mut db, vec![deposit(0)]).unwrap_err();
+    assert!(err.to_string().contains("already credited by block #3"));
+    let err = insert_block(&mut db, vec![deposit(1), deposit(1)]).unwrap_err();
+    assert!(err.to_string().contains("duplicated"));
+}
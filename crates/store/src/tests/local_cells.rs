@@ -0,0 +1,51 @@
+use gw_types::{bytes::Bytes, offchain::CellInfo, packed::OutPoint, prelude::*};
+
+use crate::Store;
+
+fn out_point(index: u32) -> OutPoint {
+    OutPoint::new_builder()
+        .tx_hash([1u8; 32].pack())
+        .index(index.pack())
+        .build()
+}
+
+#[test]
+fn test_local_cells() {
+    let store = Store::open_tmp().unwrap();
+    let cell = CellInfo {
+        out_point: out_point(0),
+        output: Default::default(),
+        data: Bytes::from_static(b"data"),
+    };
+    let mut db = store.begin_transaction();
+    db.insert_local_live_cell(&cell.pack().as_reader()).unwrap();
+    db.insert_local_dead_cell(&out_point(1).as_reader(), Some(&[2u8; 32]))
+        .unwrap();
+    db.insert_local_dead_cell(&out_point(2).as_reader(), None)
+        .unwrap();
+    db.commit().unwrap();
+
+    let cells = store.begin_transaction().get_local_cells();
+    assert_eq!(cells.live.len(), 1);
+    assert_eq!(cells.live[0].as_slice(), cell.pack().as_slice());
+    assert_eq!(
+        cells.dead,
+        vec![(out_point(1), Some([2u8; 32])), (out_point(2), None)]
+    );
+
+    let mut db = store.begin_transaction();
+    db.delete_local_live_cell(&out_point(0).as_reader())
+        .unwrap();
+    db.delete_local_dead_cell(&out_point(1).as_reader())
+        .unwrap();
+    db.commit().unwrap();
+    let cells = store.begin_transaction().get_local_cells();
+    assert!(cells.live.is_empty());
+    assert_eq!(cells.dead, vec![(out_point(2), None)]);
+
+    let mut db = store.begin_transaction();
+    db.clear_local_cells().unwrap();
+    db.commit().unwrap();
+    let cells = store.begin_transaction().get_local_cells();
+    assert!(cells.live.is_empty() && cells.dead.is_empty());
+}
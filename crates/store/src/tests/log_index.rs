@@ -0,0 +1,93 @@
+use gw_types::{
+    packed::{LogItem, TxReceipt},
+    prelude::*,
+};
+
+use crate::{
+    log_index::{self, LogPosition, GW_LOG_POLYJUICE_USER},
+    schema::{COLUMN_LOG_ADDRESS_INDEX, COLUMN_LOG_TOPIC_INDEX},
+    traits::kv_store::KVStoreWrite,
+    Store,
+};
+
+fn evm_log(address: [u8; 20], topics: &[[u8; 32]]) -> LogItem {
+    let mut data = address.to_vec();
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&[0xff, 0xff]);
+    data.extend_from_slice(&(topics.len() as u32).to_le_bytes());
+    for topic in topics {
+        data.extend_from_slice(topic);
+    }
+    LogItem::new_builder()
+        .service_flag(GW_LOG_POLYJUICE_USER.into())
+        .data(data.as_slice().pack())
+        .build()
+}
+
+#[test]
+fn test_log_index() {
+    let (a, b) = ([1u8; 20], [2u8; 20]);
+    let (t0, t1) = ([3u8; 32], [4u8; 32]);
+    let sudt_log = LogItem::new_builder().service_flag(0u8.into()).build();
+    let receipts = vec![
+        TxReceipt::new_builder()
+            .logs(vec![sudt_log, evm_log(a, &[t0])].pack())
+            .build(),
+        TxReceipt::new_builder()
+            .logs(vec![evm_log(b, &[t1, t0]), evm_log(a, &[])].pack())
+            .build(),
+    ];
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    for number in [5, 6] {
+        for (col, key, value) in log_index::block_entries(number, &receipts) {
+            db.insert_raw(col, &key, &value).unwrap();
+        }
+    }
+    db.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let prefix = log_index::address_prefix(&a);
+    let positions: Vec<_> = snap
+        .iter_log_positions(COLUMN_LOG_ADDRESS_INDEX, &prefix, 5, 5)
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            LogPosition {
+                block_number: 5,
+                log_index: 0,
+                tx_index: 0,
+                receipt_log_index: 1,
+            },
+            LogPosition {
+                block_number: 5,
+                log_index: 2,
+                tx_index: 1,
+                receipt_log_index: 1,
+            },
+        ]
+    );
+
+    // Topic t0 at index 1 is only in the second tx.
+    let prefix = log_index::topic_prefix(&t0, 1);
+    let positions: Vec<_> = snap
+        .iter_log_positions(COLUMN_LOG_TOPIC_INDEX, &prefix, 0, u64::MAX)
+        .map(|p| (p.block_number, p.log_index))
+        .collect();
+    assert_eq!(positions, vec![(5, 1), (6, 1)]);
+
+    // Detaching block 6 removes its entries only.
+    let mut db = store.begin_transaction();
+    for (col, key, _value) in log_index::block_entries(6, &receipts) {
+        db.delete(col, &key).unwrap();
+    }
+    db.commit().unwrap();
+    let snap = store.get_snapshot();
+    let positions: Vec<_> = snap
+        .iter_log_positions(COLUMN_LOG_TOPIC_INDEX, &prefix, 0, u64::MAX)
+        .map(|p| (p.block_number, p.log_index))
+        .collect();
+    assert_eq!(positions, vec![(5, 1)]);
+}
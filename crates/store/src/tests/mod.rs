@@ -1,2 +1,15 @@
+mod account_tx_index;
+mod backup;
+mod block_cycles;
+mod block_state_diff;
+mod block_stats;
+mod deposit_status;
+mod local_cells;
+mod log_index;
+mod registry_index;
+mod reorg_log;
+mod smt;
 mod state_db;
+mod sudt_registry;
+mod sudt_transfer_index;
 mod transaction;
@@ -0,0 +1,36 @@
+use gw_types::registry_address::RegistryAddress;
+
+use crate::{
+    registry_index, schema::COLUMN_REGISTRY_ADDRESS_INDEX, traits::kv_store::KVStoreWrite, Store,
+};
+
+#[test]
+fn test_registry_index() {
+    let (a, b) = ([1u8; 32], [2u8; 32]);
+    let eth_address = RegistryAddress::new(2, vec![3u8; 20]);
+    let other_address = RegistryAddress::new(5, vec![4u8; 20]);
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    for (script_hash, address, account_id) in [
+        (a, &eth_address, 7u32),
+        (a, &other_address, 7),
+        (b, &eth_address, 8),
+    ] {
+        let key = registry_index::key(&script_hash, address);
+        db.insert_raw(
+            COLUMN_REGISTRY_ADDRESS_INDEX,
+            &key,
+            &account_id.to_le_bytes(),
+        )
+        .unwrap();
+    }
+    db.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let entries: Vec<_> = snap.iter_registry_addresses(&a).collect();
+    assert_eq!(entries, vec![(eth_address.clone(), 7), (other_address, 7)]);
+    let entries: Vec<_> = snap.iter_registry_addresses(&b).collect();
+    assert_eq!(entries, vec![(eth_address, 8)]);
+    assert_eq!(snap.iter_registry_addresses(&[9u8; 32]).count(), 0);
+}
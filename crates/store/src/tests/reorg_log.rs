@@ -0,0 +1,50 @@
+use crate::{
+    reorg_log::{ReorgKind, ReorgRecord},
+    Store,
+};
+
+fn record(fork_point_number: u64, depth: u8) -> ReorgRecord {
+    ReorgRecord {
+        kind: ReorgKind::Detach,
+        observed_at: 1_000 + fork_point_number,
+        fork_point_number,
+        fork_point_hash: [fork_point_number as u8; 32],
+        first_block_timestamp: 10,
+        last_block_timestamp: 20,
+        tx_count: 3,
+        withdrawal_count: 1,
+        block_hashes: (0..depth).map(|i| [i; 32]).collect(),
+    }
+}
+
+#[test]
+fn test_reorg_log() {
+    let reorg = ReorgRecord {
+        kind: ReorgKind::Revert,
+        ..record(5, 2)
+    };
+    assert_eq!(
+        ReorgRecord::from_slice(&reorg.to_vec()),
+        Some(reorg.clone())
+    );
+    assert_eq!(ReorgRecord::from_slice(&reorg.to_vec()[1..]), None);
+    assert_eq!(reorg.depth(), 2);
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    for number in 0..3 {
+        assert_eq!(db.append_reorg_record(&record(number, 1)).unwrap(), number);
+    }
+    db.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let ids = |before: Option<u64>| -> Vec<u64> {
+        snap.iter_reorg_records(before).map(|(id, _)| id).collect()
+    };
+    assert_eq!(ids(None), vec![2, 1, 0]);
+    assert_eq!(ids(Some(2)), vec![1, 0]);
+    assert_eq!(ids(Some(10)), vec![2, 1, 0]);
+    assert_eq!(ids(Some(0)), Vec::<u64>::new());
+    let (_, latest) = snap.iter_reorg_records(None).next().unwrap();
+    assert_eq!(latest, record(2, 1));
+}
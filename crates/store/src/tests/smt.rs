@@ -0,0 +1,98 @@
+use gw_smt::{
+    smt::{default_store::DefaultStore, Blake2bHasher, SMT},
+    smt_h256_ext::{SMTH256Ext, SMTH256},
+};
+
+use crate::{
+    migrate::LogProgress,
+    schema::{
+        Col, COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_BLOCK_SMT_BRANCH,
+        COLUMN_BLOCK_SMT_LEAF,
+    },
+    smt::{parallel_builder::build_smt_parallel, smt_store::SMTBlockStore},
+    traits::kv_store::KVStoreWrite,
+    Store,
+};
+
+/// Build the smt of `leaves` in parallel, returns the built root and the root
+/// of a sequential build.
+fn build(store: &Store, leaf_col: Col, branch_col: Col, leaves: &[SMTH256]) -> (SMTH256, SMTH256) {
+    let mut expected = SMT::new(SMTH256::zero(), DefaultStore::<SMTH256>::default());
+    let mut tx = store.begin_transaction();
+    for (i, key) in leaves.iter().enumerate() {
+        let value = SMTH256::from_u32(i as u32 + 1);
+        expected.update(*key, value).unwrap();
+        tx.insert_raw(leaf_col, key.as_slice(), value.as_slice())
+            .unwrap();
+    }
+    tx.commit().unwrap();
+
+    let progress = LogProgress::new(leaves.len() as u64);
+    let root = build_smt_parallel(store, leaf_col, branch_col, &progress).unwrap();
+    (root, *expected.root())
+}
+
+#[test]
+fn test_build_smt_parallel() {
+    // Leaves in most partitions.
+    let store = Store::open_tmp().unwrap();
+    let leaves: Vec<SMTH256> = (1u8..=200).map(|i| [i; 32].into()).collect();
+    let (root, expected) = build(
+        &store,
+        COLUMN_ACCOUNT_SMT_LEAF,
+        COLUMN_ACCOUNT_SMT_BRANCH,
+        &leaves,
+    );
+    assert_eq!(root, expected);
+    let mut tx = store.begin_transaction();
+    let state_smt = tx.state_smt().unwrap();
+    assert_eq!(state_smt.root(), &expected);
+    // Branches are complete, e.g. for proofs.
+    let proof = state_smt.merkle_proof(vec![leaves[42]]).unwrap();
+    let root = proof
+        .compute_root::<Blake2bHasher>(vec![(leaves[42], SMTH256::from_u32(43))])
+        .unwrap();
+    assert_eq!(root, expected);
+}
+
+#[test]
+fn test_build_block_smt_parallel() {
+    // Many leaves in partition 0, a single leaf in partition 5 and none in
+    // the others.
+    let store = Store::open_tmp().unwrap();
+    let mut leaves: Vec<SMTH256> = (0u8..100)
+        .map(|i| {
+            let mut key = [i; 32];
+            key[31] = i % 16;
+            key.into()
+        })
+        .collect();
+    leaves.push([0x5a; 32].into());
+    let (root, expected) = build(
+        &store,
+        COLUMN_BLOCK_SMT_LEAF,
+        COLUMN_BLOCK_SMT_BRANCH,
+        &leaves,
+    );
+    assert_eq!(root, expected);
+    let mut tx = store.begin_transaction();
+    let smt = SMT::new_with_store(SMTBlockStore::new(&mut tx)).unwrap();
+    assert_eq!(smt.root(), &expected);
+
+    // A single leaf.
+    let store = Store::open_tmp().unwrap();
+    let leaves = [SMTH256::from_u32(7)];
+    let (root, expected) = build(
+        &store,
+        COLUMN_BLOCK_SMT_LEAF,
+        COLUMN_BLOCK_SMT_BRANCH,
+        &leaves,
+    );
+    assert_eq!(root, expected);
+
+    // No leaves.
+    let store = Store::open_tmp().unwrap();
+    let (root, expected) = build(&store, COLUMN_BLOCK_SMT_LEAF, COLUMN_BLOCK_SMT_BRANCH, &[]);
+    assert_eq!(root, SMTH256::zero());
+    assert_eq!(root, expected);
+}
@@ -1,4 +1,5 @@
 use gw_common::{merkle_utils::calculate_state_checkpoint, state::State};
+use gw_smt::smt::{Blake2bHasher, SMTH256};
 use gw_types::{
     h256::*,
     packed::{
@@ -8,9 +9,18 @@ use gw_types::{
 };
 
 use crate::{
-    schema::COLUMN_BLOCK,
-    state::{history::history_state::RWConfig, traits::JournalDB, BlockStateDB},
-    traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
+    schema::{COLUMN_BLOCK, COLUMN_SCRIPT},
+    snapshot::StoreSnapshot,
+    state::{
+        history::history_state::RWConfig,
+        overlay::{mem_store::MemStore, read_cache::ReadCache},
+        traits::JournalDB,
+        BlockStateDB,
+    },
+    traits::{
+        chain_store::ChainStore,
+        kv_store::{KVStoreRead, KVStoreWrite},
+    },
     transaction::StoreTransaction,
     Store,
 };
@@ -244,3 +254,94 @@ fn test_state_with_version() {
         assert_eq!(v, H256::zero());
     }
 }
+
+#[test]
+fn test_state_merkle_proof() {
+    let store = Store::open_tmp().unwrap();
+    let prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
+    let genesis = L2Block::new_builder()
+        .raw(
+            RawL2Block::new_builder()
+                .submit_transactions(
+                    SubmitTransactions::new_builder()
+                        .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .build();
+    let mut db = store.begin_transaction();
+    db.set_block_smt_root(H256::zero()).unwrap();
+    commit_block(&mut db, genesis);
+    db.commit().unwrap();
+
+    let mut roots = Vec::new();
+    for (number, value) in [(1u64, 1u32), (2, 2)] {
+        let mut db = store.begin_transaction();
+        let mut state = BlockStateDB::from_store(&mut db, RWConfig::attach_block(number)).unwrap();
+        state
+            .update_raw(H256::from_u32(1), H256::from_u32(value))
+            .unwrap();
+        let block = build_block(&mut state, number, prev_txs_state_checkpoint);
+        roots.push(state.calculate_root().unwrap());
+        commit_block(&mut db, block);
+        db.commit().unwrap();
+    }
+
+    let verify = |state: &BlockStateDB<_>, root: H256, value: H256| {
+        let keys = vec![H256::from_u32(1), H256::from_u32(2)];
+        let proof = state.merkle_proof(keys.clone()).unwrap();
+        let leaves: Vec<(SMTH256, SMTH256)> = vec![
+            (keys[0].into(), value.into()),
+            (keys[1].into(), H256::zero().into()),
+        ];
+        proof.verify::<Blake2bHasher>(&root.into(), leaves).unwrap()
+    };
+
+    // tip state
+    let state = BlockStateDB::from_store(
+        MemStore::new(store.begin_transaction()),
+        RWConfig::readonly(),
+    )
+    .unwrap();
+    assert!(verify(&state, roots[1], H256::from_u32(2)));
+    assert!(!verify(&state, roots[1], H256::from_u32(1)));
+
+    // post state of block 1, by detaching block 2 in memory
+    let mut state = BlockStateDB::from_store(
+        MemStore::new(store.begin_transaction()),
+        RWConfig::detach_block(),
+    )
+    .unwrap();
+    state.detach_block_state(2).unwrap();
+    assert!(verify(&state, roots[0], H256::from_u32(1)));
+}
+
+#[test]
+fn test_mem_store_read_cache() {
+    let store = Store::open_tmp().unwrap();
+    let key = H256::from_u32(1);
+    let mut db = store.begin_transaction();
+    db.insert_raw(COLUMN_SCRIPT, key.as_slice(), &[1]).unwrap();
+    db.commit().unwrap();
+
+    let cache = ReadCache::new(10);
+    let mut mem_store = MemStore::new(store.get_snapshot()).with_read_cache(cache.clone());
+    let get = |s: &MemStore<_>| s.get(COLUMN_SCRIPT, key.as_slice());
+    assert_eq!(get(&mem_store).as_deref(), Some(&[1u8][..]));
+
+    // Served from the cache.
+    let mut db = store.begin_transaction();
+    db.delete(COLUMN_SCRIPT, key.as_slice()).unwrap();
+    db.commit().unwrap();
+    let other = MemStore::<StoreSnapshot>::new(store.get_snapshot()).with_read_cache(cache);
+    assert_eq!(get(&other).as_deref(), Some(&[1u8][..]));
+
+    // Writes are read from the overlay.
+    let clone = mem_store.clone();
+    mem_store
+        .insert_raw(COLUMN_SCRIPT, key.as_slice(), &[2])
+        .unwrap();
+    assert_eq!(get(&mem_store).as_deref(), Some(&[2u8][..]));
+    assert_eq!(get(&clone).as_deref(), Some(&[1u8][..]));
+}
@@ -0,0 +1,16 @@
+use crate::{traits::chain_store::ChainStore, Store};
+
+#[test]
+fn test_sudt_registry() {
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    db.insert_sudt_registry_entry(&[2u8; 32], 5).unwrap();
+    db.insert_sudt_registry_entry(&[1u8; 32], 9).unwrap();
+    db.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let entries: Vec<_> = snap.iter_sudt_registry().collect();
+    assert_eq!(entries, vec![([1u8; 32], 9), ([2u8; 32], 5)]);
+    assert_eq!(snap.get_sudt_account_id(&[2u8; 32]), Some(5));
+    assert_eq!(snap.get_sudt_account_id(&[3u8; 32]), None);
+}
@@ -0,0 +1,108 @@
+use gw_types::{
+    packed::{L2Block, L2Transaction, LogItem, RawL2Block, RawL2Transaction, TxReceipt},
+    prelude::*,
+    registry_address::RegistryAddress,
+    U256,
+};
+
+use crate::{
+    schema::COLUMN_SUDT_TRANSFER_INDEX,
+    sudt_transfer_index::{self, GW_LOG_SUDT_TRANSFER},
+    traits::kv_store::KVStoreWrite,
+    Store,
+};
+
+fn address(byte: u8) -> RegistryAddress {
+    RegistryAddress::new(2, vec![byte; 20])
+}
+
+fn transfer_log(sudt_id: u32, from: u8, to: u8, amount: u64) -> LogItem {
+    let mut data = address(from).to_bytes();
+    data.extend_from_slice(&address(to).to_bytes());
+    let mut amount_bytes = [0u8; 32];
+    U256::from(amount).to_little_endian(&mut amount_bytes);
+    data.extend_from_slice(&amount_bytes);
+    LogItem::new_builder()
+        .account_id(sudt_id.pack())
+        .service_flag(GW_LOG_SUDT_TRANSFER.into())
+        .data(data.as_slice().pack())
+        .build()
+}
+
+fn block(number: u64, logs: Vec<Vec<LogItem>>) -> (L2Block, Vec<TxReceipt>) {
+    let txs: Vec<_> = (0..logs.len() as u32)
+        .map(|nonce| {
+            let raw = RawL2Transaction::new_builder().nonce(nonce.pack()).build();
+            L2Transaction::new_builder().raw(raw).build()
+        })
+        .collect();
+    let receipts = logs
+        .into_iter()
+        .map(|logs| TxReceipt::new_builder().logs(logs.pack()).build())
+        .collect();
+    let block = L2Block::new_builder()
+        .raw(RawL2Block::new_builder().number(number.pack()).build())
+        .transactions(txs.pack())
+        .build();
+    (block, receipts)
+}
+
+#[test]
+fn test_sudt_transfer_index() {
+    let other_log = LogItem::new_builder().service_flag(3u8.into()).build();
+    let blocks = [
+        block(
+            5,
+            vec![
+                vec![transfer_log(1, 1, 2, 100), other_log],
+                vec![transfer_log(1, 3, 3, 7)],
+            ],
+        ),
+        block(
+            6,
+            vec![
+                vec![],
+                vec![transfer_log(4, 2, 1, 50), transfer_log(1, 1, 4, 1)],
+            ],
+        ),
+    ];
+
+    let store = Store::open_tmp().unwrap();
+    let mut db = store.begin_transaction();
+    for (block, receipts) in blocks.iter() {
+        for (key, value) in sudt_transfer_index::block_entries(block, receipts) {
+            db.insert_raw(COLUMN_SUDT_TRANSFER_INDEX, &key, &value)
+                .unwrap();
+        }
+    }
+    db.commit().unwrap();
+
+    let snap = store.get_snapshot();
+    let positions = |byte, after, descending| -> Vec<_> {
+        snap.iter_sudt_transfers(&address(byte), after, descending)
+            .map(|t| (t.block_number, t.transfer_index))
+            .collect()
+    };
+    assert_eq!(positions(1, None, false), vec![(5, 0), (6, 0), (6, 1)]);
+    assert_eq!(positions(1, None, true), vec![(6, 1), (6, 0), (5, 0)]);
+    assert_eq!(positions(1, Some((6, 0)), false), vec![(6, 1)]);
+    assert_eq!(positions(1, Some((6, 0)), true), vec![(5, 0)]);
+    assert_eq!(positions(3, None, false), vec![(5, 1)]);
+    assert!(positions(5, None, true).is_empty());
+
+    let transfer = snap.iter_sudt_transfers(&address(4), None, false).next();
+    let tx_hash = blocks[1].0.transactions().get(1).unwrap().hash();
+    assert_eq!(
+        transfer,
+        Some(sudt_transfer_index::SudtTransfer {
+            block_number: 6,
+            transfer_index: 1,
+            tx_hash,
+            tx_index: 1,
+            sudt_id: 1,
+            from_address: address(1),
+            to_address: address(4),
+            amount: U256::from(1u64),
+        })
+    );
+}
@@ -1,5 +1,8 @@
 use crate::{
-    traits::kv_store::{KVStoreRead, KVStoreWrite},
+    traits::{
+        chain_store::ChainStore,
+        kv_store::{KVStoreRead, KVStoreWrite},
+    },
     Store,
 };
 use std::collections::HashMap;
@@ -301,3 +304,35 @@ fn range_search_and_delete() {
 
     assert!(store_txn.get(1, &[6, 0]).is_some());
 }
+
+#[test]
+fn expire_mem_pool_withdrawal() {
+    let store = Store::open_tmp().unwrap();
+    let hash = [1u8; 32];
+    let mut db = store.begin_transaction();
+    db.insert_mem_pool_withdrawal(&hash, Default::default())
+        .unwrap();
+    db.expire_mem_pool_withdrawal(&hash, 1000).unwrap();
+    db.commit().unwrap();
+
+    assert!(store.get_mem_pool_withdrawal(&hash).unwrap().is_none());
+    assert_eq!(store.get_expired_withdrawal(&hash), Some(1000));
+    assert_eq!(store.get_expired_withdrawal(&[2u8; 32]), None);
+}
+
+#[test]
+fn prune_expired_withdrawals() {
+    let store = Store::open_tmp().unwrap();
+    let (old, new) = ([1u8; 32], [2u8; 32]);
+    let mut db = store.begin_transaction();
+    db.expire_mem_pool_withdrawal(&old, 1000).unwrap();
+    db.expire_mem_pool_withdrawal(&new, 3000).unwrap();
+    db.commit().unwrap();
+
+    let mut db = store.begin_transaction();
+    assert_eq!(db.prune_expired_withdrawals(2000).unwrap(), 1);
+    db.commit().unwrap();
+
+    assert_eq!(store.get_expired_withdrawal(&old), None);
+    assert_eq!(store.get_expired_withdrawal(&new), Some(3000));
+}
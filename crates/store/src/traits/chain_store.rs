@@ -1,6 +1,6 @@
 #![allow(clippy::mutable_key_type)]
 
-use std::cell::RefCell;
+use std::{cell::RefCell, convert::TryInto};
 
 use anyhow::Result;
 use gw_types::{
@@ -14,7 +14,10 @@ use gw_types::{
     prelude::*,
 };
 
-use crate::{schema::*, traits::kv_store::KVStoreRead};
+use crate::{
+    block_cycles::BlockCyclesReport, block_state_diff::BlockStateDiff, block_stats::BlockStats,
+    deposit_status::DepositStatus, schema::*, traits::kv_store::KVStoreRead,
+};
 
 /// L2 block status on L1.
 pub enum BlockStatus {
@@ -129,6 +132,26 @@ pub trait ChainStore: KVStoreRead {
         Some(from_box_should_be_ok!(packed::DepositInfoVecReader, data))
     }
 
+    fn get_block_stats(&self, block_number: u64) -> Option<BlockStats> {
+        let data = self.get(COLUMN_BLOCK_STATS, &block_number.to_be_bytes())?;
+        BlockStats::from_slice(&data)
+    }
+
+    fn get_block_cycles_report(&self, block_number: u64) -> Option<BlockCyclesReport> {
+        let data = self.get(COLUMN_BLOCK_CYCLES_REPORT, &block_number.to_be_bytes())?;
+        BlockCyclesReport::from_slice(&data)
+    }
+
+    fn get_block_state_diff(&self, block_number: u64) -> Option<BlockStateDiff> {
+        let data = self.get(COLUMN_BLOCK_STATE_DIFF, &block_number.to_be_bytes())?;
+        BlockStateDiff::from_slice(&data)
+    }
+
+    fn get_deposit_status(&self, out_point: &packed::OutPoint) -> Option<DepositStatus> {
+        let data = self.get(COLUMN_DEPOSIT_STATUS, out_point.as_slice())?;
+        DepositStatus::from_slice(&data)
+    }
+
     fn get_block_post_finalized_custodian_capacity(
         &self,
         block_number: u64,
@@ -190,6 +213,27 @@ pub trait ChainStore: KVStoreRead {
         }
     }
 
+    /// Read a block with `f` without copying it into an owned `L2Block`, e.g.
+    /// to read a few fields of it. Does NOT read bad blocks.
+    fn get_block_reader<T>(
+        &self,
+        block_hash: &H256,
+        f: impl FnOnce(packed::L2BlockReader<'_>) -> T,
+    ) -> Result<Option<T>> {
+        match self.get(COLUMN_BLOCK, block_hash.as_slice()) {
+            Some(slice) => Ok(Some(f(packed::L2BlockReader::from_slice_should_be_ok(
+                slice.as_ref(),
+            )))),
+            None => Ok(None),
+        }
+    }
+
+    /// Header of a block, without its txs and withdrawals. Does NOT return bad
+    /// blocks.
+    fn get_block_raw(&self, block_hash: &H256) -> Result<Option<packed::RawL2Block>> {
+        self.get_block_reader(block_hash, |block| block.raw().to_entity())
+    }
+
     fn get_bad_block(&self, block_hash: &H256) -> Option<packed::L2Block> {
         let slice = self.get(COLUMN_BAD_BLOCK, block_hash.as_slice())?;
         Some(from_box_should_be_ok!(packed::L2BlockReader, slice))
@@ -257,6 +301,11 @@ pub trait ChainStore: KVStoreRead {
         Ok(withdrawal_info_opt)
     }
 
+    fn get_withdrawal_unlock_tx_hash(&self, withdrawal_hash: &H256) -> Option<H256> {
+        let data = self.get(COLUMN_WITHDRAWAL_UNLOCK_TX_HASH, withdrawal_hash.as_slice())?;
+        Some(packed::Byte32Reader::from_slice_should_be_ok(data.as_ref()).unpack())
+    }
+
     fn get_withdrawal_by_key(
         &self,
         withdrawal_key: &WithdrawalKey,
@@ -324,6 +373,12 @@ pub trait ChainStore: KVStoreRead {
         }
     }
 
+    /// L2 sUDT account id of the L1 sUDT script.
+    fn get_sudt_account_id(&self, l1_script_hash: &H256) -> Option<u32> {
+        let data = self.get(COLUMN_SUDT_REGISTRY, l1_script_hash.as_slice())?;
+        Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?))
+    }
+
     fn get_mem_pool_transaction(&self, tx_hash: &H256) -> Result<Option<packed::L2Transaction>> {
         Ok(self
             .get(COLUMN_MEM_POOL_TRANSACTION, tx_hash.as_slice())
@@ -351,6 +406,13 @@ pub trait ChainStore: KVStoreRead {
             };
         Ok(maybe_withdrawal)
     }
+
+    /// When the pending withdrawal was dropped by the mem pool for expiring,
+    /// unix timestamp in milliseconds.
+    fn get_expired_withdrawal(&self, withdrawal_hash: &H256) -> Option<u64> {
+        let data = self.get(COLUMN_EXPIRED_WITHDRAWAL, withdrawal_hash.as_slice())?;
+        Some(u64::from_be_bytes(data.as_ref().try_into().ok()?))
+    }
 }
 
 impl<T: ChainStore> ChainStore for &T {}
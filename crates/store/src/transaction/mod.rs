@@ -3,5 +3,5 @@ mod history_store_impl;
 mod store_transaction;
 mod transaction_snapshot;
 
-pub use store_transaction::StoreTransaction;
+pub use store_transaction::{LocalCells, StoreTransaction};
 pub use transaction_snapshot::TransactionSnapshot;
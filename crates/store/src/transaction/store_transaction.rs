@@ -1,6 +1,7 @@
 #![allow(clippy::mutable_key_type)]
 
 use std::collections::HashSet;
+use std::convert::TryFrom;
 
 use anyhow::{bail, Context, Result};
 use autorocks::moveit::slot;
@@ -18,8 +19,18 @@ use gw_types::{
     prelude::*,
 };
 
+use crate::account_tx_index;
+use crate::block_cycles::BlockCyclesReport;
+use crate::block_state_diff::BlockStateDiff;
+use crate::block_stats::BlockStats;
+use crate::deposit_status::DepositStatus;
+use crate::log_index;
+use crate::registry_index;
+use crate::reorg_log::{self, ReorgRecord};
 use crate::schema::*;
 use crate::smt::smt_store::{SMTBlockStore, SMTRevertedBlockStore, SMTStateStore};
+use crate::state::{history::history_state::RWConfig, BlockStateDB};
+use crate::sudt_transfer_index;
 use crate::traits::chain_store::ChainStore;
 use crate::traits::kv_store::KVStoreRead;
 use crate::traits::kv_store::{KVStore, KVStoreWrite};
@@ -146,10 +157,29 @@ impl StoreTransaction {
             &block_hash,
             global_state.as_slice(),
         )?;
-        self.set_block_deposit_info_vec(
-            block.raw().number().unpack(),
-            &deposit_info_vec.as_reader(),
-        )?;
+        let block_number: u64 = block.raw().number().unpack();
+        self.set_block_deposit_info_vec(block_number, &deposit_info_vec.as_reader())?;
+        // A deposit cell can only be credited once, see `deposit_status`.
+        let mut out_points = HashSet::with_capacity(deposit_info_vec.len());
+        for deposit in deposit_info_vec.into_iter() {
+            let out_point = deposit.cell().out_point();
+            if !out_points.insert(out_point.clone()) {
+                bail!("deposit {} is duplicated in the block", out_point);
+            }
+            if let Some(DepositStatus::Credited {
+                block_number: credited,
+            }) = self.get_deposit_status(&out_point)
+            {
+                if credited != block_number {
+                    bail!(
+                        "deposit {} is already credited by block #{}",
+                        out_point,
+                        credited
+                    );
+                }
+            }
+            self.set_deposit_status(&out_point, DepositStatus::Credited { block_number })?;
+        }
 
         // Verify prev tx state and insert
         {
@@ -204,6 +234,35 @@ impl StoreTransaction {
         Ok(())
     }
 
+    fn block_log_index_entries(
+        &self,
+        block: &packed::L2Block,
+    ) -> Result<Vec<(Col, Vec<u8>, [u8; 8])>> {
+        let receipts = log_index::block_receipts(self, block)?;
+        Ok(log_index::block_entries(
+            block.raw().number().unpack(),
+            &receipts,
+        ))
+    }
+
+    fn block_sudt_transfer_index_entries(
+        &self,
+        block: &packed::L2Block,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let receipts = log_index::block_receipts(self, block)?;
+        Ok(sudt_transfer_index::block_entries(block, &receipts))
+    }
+
+    /// Registry index entries of `block`, the tip block.
+    fn block_registry_index_entries(
+        &mut self,
+        block: &packed::L2Block,
+    ) -> Result<Vec<(Vec<u8>, [u8; 4])>> {
+        let state = BlockStateDB::from_store(self, RWConfig::readonly())?;
+        let script_hashes = registry_index::block_candidates(&state, block)?;
+        registry_index::entries(&state, script_hashes)
+    }
+
     pub fn insert_asset_scripts(&mut self, scripts: HashSet<Script>) -> Result<()> {
         for script in scripts.into_iter() {
             self.insert_raw(COLUMN_ASSET_SCRIPT, &script.hash(), script.as_slice())?;
@@ -212,6 +271,26 @@ impl StoreTransaction {
         Ok(())
     }
 
+    /// Script hashes of all inserted asset scripts.
+    pub fn get_asset_script_hashes(&self) -> Vec<H256> {
+        { self.get_iter(COLUMN_ASSET_SCRIPT, Direction::Forward) }
+            .map(|(key, _)| packed::Byte32Reader::from_slice_should_be_ok(key.as_ref()).unpack())
+            .collect()
+    }
+
+    /// Register the L2 sUDT account of an L1 sUDT script.
+    pub fn insert_sudt_registry_entry(
+        &mut self,
+        l1_script_hash: &H256,
+        account_id: u32,
+    ) -> Result<()> {
+        self.insert_raw(
+            COLUMN_SUDT_REGISTRY,
+            l1_script_hash.as_slice(),
+            &account_id.to_le_bytes(),
+        )
+    }
+
     pub fn block_smt(&mut self) -> Result<SMT<SMTBlockStore<&mut Self>>> {
         SMTBlockStore::new(self).to_smt()
     }
@@ -300,6 +379,18 @@ impl StoreTransaction {
         Ok(())
     }
 
+    pub fn set_withdrawal_unlock_tx_hash(
+        &mut self,
+        withdrawal_hash: &H256,
+        unlock_tx_hash: &H256,
+    ) -> Result<()> {
+        self.insert_raw(
+            COLUMN_WITHDRAWAL_UNLOCK_TX_HASH,
+            withdrawal_hash.as_slice(),
+            unlock_tx_hash.as_slice(),
+        )
+    }
+
     pub fn delete_submit_tx(&mut self, block_number: u64) -> Result<()> {
         let k = block_number.to_be_bytes();
         self.delete(COLUMN_BLOCK_SUBMIT_TX, &k)?;
@@ -323,6 +414,57 @@ impl StoreTransaction {
         self.delete(COLUMN_BLOCK_DEPOSIT_INFO_VEC, &block_number.to_be_bytes())
     }
 
+    pub fn set_deposit_status(
+        &mut self,
+        out_point: &packed::OutPoint,
+        status: DepositStatus,
+    ) -> Result<()> {
+        self.insert_raw(
+            COLUMN_DEPOSIT_STATUS,
+            out_point.as_slice(),
+            &status.to_vec(),
+        )
+    }
+
+    pub fn delete_deposit_status(&mut self, out_point: &packed::OutPoint) -> Result<()> {
+        self.delete(COLUMN_DEPOSIT_STATUS, out_point.as_slice())
+    }
+
+    /// Append a record to the reorg log, returns its id.
+    pub fn append_reorg_record(&mut self, record: &ReorgRecord) -> Result<u64> {
+        let id = reorg_log::next_id(self.get_iter(COLUMN_REORG_LOG, Direction::Backward));
+        self.insert_raw(COLUMN_REORG_LOG, &id.to_be_bytes(), &record.to_vec())?;
+        Ok(id)
+    }
+
+    pub fn set_block_stats(&mut self, block_number: u64, stats: &BlockStats) -> Result<()> {
+        self.insert_raw(
+            COLUMN_BLOCK_STATS,
+            &block_number.to_be_bytes(),
+            &stats.to_vec(),
+        )
+    }
+
+    pub fn set_block_cycles_report(
+        &mut self,
+        block_number: u64,
+        report: &BlockCyclesReport,
+    ) -> Result<()> {
+        self.insert_raw(
+            COLUMN_BLOCK_CYCLES_REPORT,
+            &block_number.to_be_bytes(),
+            &report.to_vec(),
+        )
+    }
+
+    pub fn set_block_state_diff(&mut self, block_number: u64, diff: &BlockStateDiff) -> Result<()> {
+        self.insert_raw(
+            COLUMN_BLOCK_STATE_DIFF,
+            &block_number.to_be_bytes(),
+            &diff.to_vec(),
+        )
+    }
+
     pub fn set_block_post_finalized_custodian_capacity(
         &mut self,
         block_number: u64,
@@ -451,6 +593,21 @@ impl StoreTransaction {
             self.insert_raw(COLUMN_WITHDRAWAL_INFO, &withdrawal_hash, info.as_slice())?;
         }
 
+        // build log index
+        for (col, key, value) in self.block_log_index_entries(&block)? {
+            self.insert_raw(col, &key, &value)?;
+        }
+
+        // build account tx index
+        for (key, value) in account_tx_index::block_entries(&block) {
+            self.insert_raw(COLUMN_ACCOUNT_TX_INDEX, &key, &value)?;
+        }
+
+        // build sUDT transfer index
+        for (key, value) in self.block_sudt_transfer_index_entries(&block)? {
+            self.insert_raw(COLUMN_SUDT_TRANSFER_INDEX, &key, &value)?;
+        }
+
         // build main chain index
         self.insert_raw(COLUMN_INDEX, raw_number.as_slice(), &block_hash)?;
         self.insert_raw(COLUMN_INDEX, &block_hash, raw_number.as_slice())?;
@@ -467,6 +624,11 @@ impl StoreTransaction {
         self.insert_raw(COLUMN_META, META_TIP_BLOCK_HASH_KEY, &block_hash)?;
         self.set_last_valid_tip_block_hash(&block_hash)?;
 
+        // build registry address index, from the state of the new tip
+        for (key, value) in self.block_registry_index_entries(&block)? {
+            self.insert_raw(COLUMN_REGISTRY_ADDRESS_INDEX, &key, &value)?;
+        }
+
         Ok(())
     }
 
@@ -500,6 +662,21 @@ impl StoreTransaction {
             self.delete(COLUMN_WITHDRAWAL_INFO, &withdrawal_hash)?;
         }
 
+        // remove log index
+        for (col, key, _value) in self.block_log_index_entries(block)? {
+            self.delete(col, &key)?;
+        }
+
+        // remove account tx index
+        for (key, _value) in account_tx_index::block_entries(block) {
+            self.delete(COLUMN_ACCOUNT_TX_INDEX, &key)?;
+        }
+
+        // remove sUDT transfer index
+        for (key, _value) in self.block_sudt_transfer_index_entries(block)? {
+            self.delete(COLUMN_SUDT_TRANSFER_INDEX, &key)?;
+        }
+
         let block_hash: H256 = block.hash();
 
         // remove index
@@ -515,8 +692,13 @@ impl StoreTransaction {
         let root = *block_smt.root();
         self.set_block_smt_root(root.into())?;
 
-        // update tip
+        // remove block stats, cycles report and state diff
         let block_number: u64 = block_number.unpack();
+        self.delete(COLUMN_BLOCK_STATS, &block_number.to_be_bytes())?;
+        self.delete(COLUMN_BLOCK_CYCLES_REPORT, &block_number.to_be_bytes())?;
+        self.delete(COLUMN_BLOCK_STATE_DIFF, &block_number.to_be_bytes())?;
+
+        // update tip
         let parent_number = block_number.saturating_sub(1);
         let parent_block_hash = self
             .get_block_hash_by_number(parent_number)?
@@ -550,6 +732,11 @@ impl StoreTransaction {
         }
 
         self.delete_submit_tx(block_number)?;
+        // deposits of the block are pending again
+        let deposits = self.get_block_deposit_info_vec(block_number);
+        for deposit in deposits.into_iter().flatten() {
+            self.delete_deposit_status(&deposit.cell().out_point())?;
+        }
         self.delete_block_deposit_info_vec(block_number)?;
         self.delete_block_post_finalized_custodian_capacity(block_number)?;
 
@@ -620,6 +807,38 @@ impl StoreTransaction {
         Ok(())
     }
 
+    /// Remove the pending withdrawal and record that it expired at
+    /// `expired_at`, unix timestamp in milliseconds.
+    pub fn expire_mem_pool_withdrawal(
+        &mut self,
+        withdrawal_hash: &H256,
+        expired_at: u64,
+    ) -> Result<()> {
+        self.remove_mem_pool_withdrawal(withdrawal_hash)?;
+        self.insert_raw(
+            COLUMN_EXPIRED_WITHDRAWAL,
+            withdrawal_hash.as_slice(),
+            &expired_at.to_be_bytes(),
+        )
+    }
+
+    /// Remove expired withdrawal records that expired before `expired_before`,
+    /// unix timestamp in milliseconds. Returns the number of removed records.
+    pub fn prune_expired_withdrawals(&mut self, expired_before: u64) -> Result<usize> {
+        let iter = self.get_iter(COLUMN_EXPIRED_WITHDRAWAL, Direction::Forward);
+        let pruned: Vec<Box<[u8]>> = iter
+            .filter(|(_key, value)| {
+                let expired_at = <[u8; 8]>::try_from(value.as_ref()).map(u64::from_be_bytes);
+                expired_at.map_or(true, |expired_at| expired_at < expired_before)
+            })
+            .map(|(key, _value)| key)
+            .collect();
+        for key in pruned.iter() {
+            self.delete(COLUMN_EXPIRED_WITHDRAWAL, key)?;
+        }
+        Ok(pruned.len())
+    }
+
     pub fn get_mem_pool_withdrawal_iter(
         &self,
     ) -> impl Iterator<Item = (H256, packed::WithdrawalRequestExtra)> + '_ {
@@ -632,6 +851,65 @@ impl StoreTransaction {
             })
     }
 
+    pub fn insert_local_live_cell(&mut self, cell: &packed::CellInfoReader) -> Result<()> {
+        let key = local_cell_key(LOCAL_LIVE_CELL, &cell.out_point());
+        self.insert_raw(COLUMN_LOCAL_CELLS, &key, cell.as_slice())
+    }
+
+    pub fn delete_local_live_cell(&mut self, out_point: &packed::OutPointReader) -> Result<()> {
+        self.delete(
+            COLUMN_LOCAL_CELLS,
+            &local_cell_key(LOCAL_LIVE_CELL, out_point),
+        )
+    }
+
+    /// `consumed_by` is the tx consuming the cell, if known.
+    pub fn insert_local_dead_cell(
+        &mut self,
+        out_point: &packed::OutPointReader,
+        consumed_by: Option<&H256>,
+    ) -> Result<()> {
+        let key = local_cell_key(LOCAL_DEAD_CELL, out_point);
+        let value = consumed_by.map_or(&[][..], |h| h.as_slice());
+        self.insert_raw(COLUMN_LOCAL_CELLS, &key, value)
+    }
+
+    pub fn delete_local_dead_cell(&mut self, out_point: &packed::OutPointReader) -> Result<()> {
+        self.delete(
+            COLUMN_LOCAL_CELLS,
+            &local_cell_key(LOCAL_DEAD_CELL, out_point),
+        )
+    }
+
+    pub fn clear_local_cells(&mut self) -> Result<()> {
+        let keys: Vec<_> = { self.get_iter(COLUMN_LOCAL_CELLS, Direction::Forward) }
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            self.delete(COLUMN_LOCAL_CELLS, &key)?;
+        }
+        Ok(())
+    }
+
+    /// Local live cells, and local dead cells with their consuming txs.
+    pub fn get_local_cells(&self) -> LocalCells {
+        let mut cells = LocalCells::default();
+        for (key, value) in self.get_iter(COLUMN_LOCAL_CELLS, Direction::Forward) {
+            match key[0] {
+                LOCAL_LIVE_CELL => cells
+                    .live
+                    .push(from_box_should_be_ok!(packed::CellInfoReader, value)),
+                _ => {
+                    let out_point =
+                        packed::OutPointReader::from_slice_should_be_ok(&key[1..]).to_entity();
+                    let consumed_by = <[u8; 32]>::try_from(value.as_ref()).ok();
+                    cells.dead.push((out_point, consumed_by));
+                }
+            }
+        }
+        cells
+    }
+
     pub fn get_mem_pool_transaction_iter(
         &self,
     ) -> impl Iterator<Item = (H256, packed::L2Transaction)> + '_ {
@@ -644,3 +922,20 @@ impl StoreTransaction {
             })
     }
 }
+
+const LOCAL_LIVE_CELL: u8 = 0;
+const LOCAL_DEAD_CELL: u8 = 1;
+
+fn local_cell_key(kind: u8, out_point: &packed::OutPointReader) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + out_point.as_slice().len());
+    key.push(kind);
+    key.extend_from_slice(out_point.as_slice());
+    key
+}
+
+/// See `StoreTransaction::get_local_cells`.
+#[derive(Default)]
+pub struct LocalCells {
+    pub live: Vec<packed::CellInfo>,
+    pub dead: Vec<(packed::OutPoint, Option<H256>)>,
+}
@@ -0,0 +1,76 @@
+//! Alerts of critical events that need an operator, e.g. repeated failures of
+//! submitting blocks.
+//!
+//! Alerts are raised anywhere with `alert` and delivered by the sink set at
+//! startup, e.g. a webhook configured by `alerts`. They are dropped if no sink
+//! is set.
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Alert {
+    /// Submitting the next block failed `failures` times in a row.
+    SubmissionFailures {
+        failures: u32,
+        error: String,
+    },
+    /// A block on L1 is challenged.
+    ChallengeObserved {
+        block_number: u64,
+        block_hash: String,
+        /// Registry address of the producer of the block, hex encoded.
+        block_producer: String,
+    },
+    /// The DB is inconsistent, e.g. its account tree disagrees with synced
+    /// blocks.
+    DbCorruption {
+        error: String,
+    },
+    /// Replaying a block of L1 didn't reproduce its post state, the node
+    /// enters safe mode.
+    StateDivergence {
+        block_number: u64,
+        error: String,
+    },
+    MemPoolResetFailed {
+        error: String,
+    },
+    /// Usage of the disk of the store is above the threshold.
+    DiskUsage {
+        path: String,
+        used_percent: u64,
+    },
+}
+
+impl Alert {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Alert::SubmissionFailures { .. } => "submission_failures",
+            Alert::ChallengeObserved { .. } => "challenge_observed",
+            Alert::DbCorruption { .. } => "db_corruption",
+            Alert::StateDivergence { .. } => "state_divergence",
+            Alert::MemPoolResetFailed { .. } => "mem_pool_reset_failed",
+            Alert::DiskUsage { .. } => "disk_usage",
+        }
+    }
+}
+
+/// Delivers alerts, `send` must not block.
+pub trait AlertSink: Send + Sync {
+    fn send(&self, alert: Alert);
+}
+
+static SINK: OnceCell<Box<dyn AlertSink>> = OnceCell::new();
+
+/// Set the sink of alerts, returns false if it's already set.
+pub fn set_sink(sink: impl AlertSink + 'static) -> bool {
+    SINK.set(Box::new(sink)).is_ok()
+}
+
+pub fn alert(alert: Alert) {
+    if let Some(sink) = SINK.get() {
+        sink.send(alert);
+    }
+}
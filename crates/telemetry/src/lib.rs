@@ -1,3 +1,4 @@
+pub mod alert;
 pub mod metric;
 pub mod trace;
 pub mod traits;
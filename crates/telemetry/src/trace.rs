@@ -1,9 +1,20 @@
-use tracing_appender::non_blocking;
-use tracing_subscriber::{prelude::*, EnvFilter};
+use opentelemetry::sdk::{
+    propagation::TraceContextPropagator,
+    trace::{self as sdktrace, Sampler},
+    Resource,
+};
+use opentelemetry::KeyValue;
+use std::path::Path;
+use tracing_appender::{non_blocking, rolling::RollingFileAppender};
+use tracing_subscriber::{prelude::*, reload, EnvFilter};
+
+use crate::metric::OnceCell;
 
 pub mod format;
+pub mod links;
 pub use opentelemetry::trace::*;
 pub use opentelemetry_http as http;
+pub use tracing_appender::rolling::Rotation;
 
 const ENV_OTEL_TRACES_EXPORTER: &str = "OTEL_TRACES_EXPORTER";
 const DEFAULT_LOG_LEVEL: &str = "info";
@@ -14,13 +25,49 @@ pub enum TraceInitError {
     Opentelemetry(#[from] opentelemetry::trace::TraceError),
     ParseError(#[from] tracing_subscriber::filter::ParseError),
     TryInitError(#[from] tracing_subscriber::util::TryInitError),
+    Reload(#[from] reload::Error),
 }
 
 pub enum TraceExporter {
     None,
     Jaeger,
+    Otlp,
 }
 
+/// OTLP exporter options, spans are exported via gRPC.
+pub struct OtlpOptions<'a> {
+    /// Collector endpoint, e.g. `http://localhost:4317`.
+    pub endpoint: &'a str,
+    /// Ratio of root spans to sample, child spans follow their parents.
+    pub sampling_ratio: f64,
+    pub service_name: &'a str,
+}
+
+#[derive(Default)]
+pub struct TraceOptions<'a> {
+    pub otlp: Option<OtlpOptions<'a>>,
+    pub log: LogOptions<'a>,
+}
+
+#[derive(Default)]
+pub struct LogOptions<'a> {
+    pub json: bool,
+    /// Filter directives in the `RUST_LOG` syntax, `RUST_LOG` takes precedence.
+    pub filter: Option<&'a str>,
+    /// Write to rotated files instead of stdout.
+    pub file: Option<LogFileOptions<'a>>,
+}
+
+pub struct LogFileOptions<'a> {
+    pub dir: &'a Path,
+    pub prefix: &'a str,
+    pub rotation: Rotation,
+}
+
+type ReloadLogFilter = Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>;
+
+static RELOAD_LOG_FILTER: OnceCell<ReloadLogFilter> = OnceCell::new();
+
 pub struct TraceGuard {
     _non_blocking_worker: non_blocking::WorkerGuard,
     trace_exporter: TraceExporter,
@@ -35,21 +82,40 @@ impl Drop for TraceGuard {
 }
 
 pub fn init() -> Result<TraceGuard, TraceInitError> {
-    let trace_exporter = match std::env::var(ENV_OTEL_TRACES_EXPORTER).as_deref() {
-        Ok("jaeger") => TraceExporter::Jaeger,
-        Ok("none") => TraceExporter::None,
-        Err(_) | Ok(_) => TraceExporter::None,
+    init_with(TraceOptions::default())
+}
+
+/// Init with an OTLP exporter if `options.otlp` is set, it takes precedence
+/// over `OTEL_TRACES_EXPORTER`.
+pub fn init_with(options: TraceOptions) -> Result<TraceGuard, TraceInitError> {
+    let TraceOptions { otlp, log } = options;
+    let trace_exporter = match (&otlp, std::env::var(ENV_OTEL_TRACES_EXPORTER).as_deref()) {
+        (Some(_), _) => TraceExporter::Otlp,
+        (None, Ok("jaeger")) => TraceExporter::Jaeger,
+        (None, Ok("none")) => TraceExporter::None,
+        (None, Err(_) | Ok(_)) => TraceExporter::None,
     };
 
-    let env_filter_layer =
-        EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(DEFAULT_LOG_LEVEL))?;
+    let env_filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(log.filter.unwrap_or(DEFAULT_LOG_LEVEL)))?;
+    let (env_filter_layer, reload_handle) = reload::Layer::new(env_filter);
 
     let (fmt_layer, _non_blocking_worker) = {
-        let (non_blocking_stdout, non_blocking_worker) = non_blocking(std::io::stdout());
-        let layer = tracing_subscriber::fmt::layer().with_writer(non_blocking_stdout);
+        let to_file = log.file.is_some();
+        let (writer, non_blocking_worker) = match log.file {
+            Some(file) => non_blocking(RollingFileAppender::new(
+                file.rotation,
+                file.dir,
+                file.prefix,
+            )),
+            None => non_blocking(std::io::stdout()),
+        };
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(!to_file);
 
         let layer = match trace_exporter {
-            TraceExporter::None => layer.boxed(),
+            TraceExporter::None if !log.json => layer.boxed(),
             _ => { layer.json() } // Use json for better trace info support
                 .with_current_span(true)
                 .event_format(format::TraceFormat) // Add trace info to log
@@ -60,8 +126,6 @@ pub fn init() -> Result<TraceGuard, TraceInitError> {
 
     let trace_layer = match trace_exporter {
         TraceExporter::Jaeger => {
-            // TODO: opentelemetry-otpl requires protoc cli, wait next release
-            // Reference: https://github.com/open-telemetry\/telemetry-rust/pull/881
             opentelemetry::global::set_text_map_propagator(opentelemetry_jaeger::Propagator::new());
 
             // Set serivce name through `OTEL_SERVICE_NAME` or `OTEL_RESOURCE_ATTRIBUTES: service.name`
@@ -71,6 +135,32 @@ pub fn init() -> Result<TraceGuard, TraceInitError> {
 
             Some(tracing_opentelemetry::layer().with_tracer(tracer).boxed())
         }
+        TraceExporter::Otlp => {
+            let otlp = otlp.expect("otlp options");
+            opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+            let sampler =
+                Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(otlp.sampling_ratio)));
+            let resource = Resource::new(vec![KeyValue::new(
+                "service.name",
+                otlp.service_name.to_string(),
+            )]);
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(otlp.endpoint),
+                )
+                .with_trace_config(
+                    sdktrace::config()
+                        .with_sampler(sampler)
+                        .with_resource(resource),
+                )
+                .install_batch(opentelemetry::runtime::Tokio)?;
+
+            Some(tracing_opentelemetry::layer().with_tracer(tracer).boxed())
+        }
         TraceExporter::None => None,
     };
 
@@ -83,6 +173,8 @@ pub fn init() -> Result<TraceGuard, TraceInitError> {
         None => registry.try_init()?,
     }
 
+    let _ = RELOAD_LOG_FILTER.set(Box::new(move |filter| reload_handle.reload(filter)));
+
     let guard = TraceGuard {
         _non_blocking_worker,
         trace_exporter,
@@ -90,3 +182,12 @@ pub fn init() -> Result<TraceGuard, TraceInitError> {
 
     Ok(guard)
 }
+
+/// Replace the log filter with `directives` in the `RUST_LOG` syntax.
+pub fn reload_log_filter(directives: &str) -> Result<(), TraceInitError> {
+    let filter = EnvFilter::try_new(directives)?;
+    if let Some(reload) = RELOAD_LOG_FILTER.get() {
+        reload(filter)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Span links across traces.
+//!
+//! An L2 tx is pushed in one trace and its block is submitted to L1 in
+//! another. The pushing span is recorded by tx hash, and the submission span
+//! links to it, so that a tx can be followed to its L1 submission.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use opentelemetry::trace::{SpanContext, TraceContextExt};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Recorded span contexts are dropped in FIFO order beyond this, e.g. txs
+/// that never get into a block.
+const MAX_RECORDED_SPANS: usize = 100_000;
+
+#[derive(Default)]
+struct RecordedSpans {
+    contexts: HashMap<[u8; 32], SpanContext>,
+    keys: VecDeque<[u8; 32]>,
+}
+
+static RECORDED_SPANS: Lazy<Mutex<RecordedSpans>> = Lazy::new(Default::default);
+
+/// Record the current span under `key`. Does nothing if the span is not
+/// sampled, e.g. no exporter is set up.
+pub fn record(key: [u8; 32]) {
+    let cx = tracing::Span::current().context();
+    let span_context = cx.span().span_context().clone();
+    if !span_context.is_valid() || !span_context.is_sampled() {
+        return;
+    }
+
+    let mut recorded = RECORDED_SPANS.lock().expect("lock");
+    if recorded.contexts.insert(key, span_context).is_none() {
+        recorded.keys.push_back(key);
+    }
+    while recorded.keys.len() > MAX_RECORDED_SPANS {
+        if let Some(key) = recorded.keys.pop_front() {
+            recorded.contexts.remove(&key);
+        }
+    }
+}
+
+/// Link `span` to the spans recorded under `keys`. Linked spans are forgotten.
+pub fn link(span: &tracing::Span, keys: impl IntoIterator<Item = [u8; 32]>) {
+    let mut recorded = RECORDED_SPANS.lock().expect("lock");
+    if recorded.contexts.is_empty() {
+        return;
+    }
+    for key in keys {
+        if let Some(span_context) = recorded.contexts.remove(&key) {
+            span.add_link(span_context);
+        }
+    }
+}
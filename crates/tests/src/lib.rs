@@ -1,7 +1,7 @@
 #[cfg(feature = "scripts")]
 #[cfg(test)]
 mod script_tests;
-#[cfg(test)]
-mod testing_tool;
+#[cfg(any(test, feature = "testing-tool"))]
+pub mod testing_tool;
 #[cfg(test)]
 mod tests;
@@ -88,6 +88,9 @@ fn test_example_sum() {
                 generator_path: SUM_PROGRAM_PATH.to_path_buf(),
                 validator_script_type_hash: (*SUM_PROGRAM_CODE_HASH).into(),
                 backend_type: BackendType::Unknown,
+                validator_code_hash: None,
+                generator_code_hash: None,
+                cycles: Default::default(),
             }],
         }])
         .unwrap();
@@ -227,6 +230,9 @@ fn test_example_account_operation() {
             generator_path: ACCOUNT_OP_PROGRAM_PATH.clone(),
             validator_script_type_hash: (*ACCOUNT_OP_PROGRAM_CODE_HASH).into(),
             backend_type: BackendType::Unknown,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         }],
     }])
     .unwrap();
@@ -465,6 +471,9 @@ fn test_example_recover_account() {
             generator_path: RECOVER_PROGRAM_PATH.clone(),
             validator_script_type_hash: (*RECOVER_PROGRAM_CODE_HASH).into(),
             backend_type: BackendType::Unknown,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         }],
     }])
     .unwrap();
@@ -646,6 +655,9 @@ fn test_sudt_total_supply() {
                 generator_path: SUDT_TOTAL_SUPPLY_PROGRAM_PATH.clone(),
                 validator_script_type_hash: (*SUDT_TOTAL_SUPPLY_PROGRAM_CODE_HASH).into(),
                 backend_type: BackendType::Unknown,
+                validator_code_hash: None,
+                generator_code_hash: None,
+                cycles: Default::default(),
             }],
         }])
         .unwrap();
@@ -1,9 +1,12 @@
 #![allow(clippy::mutable_key_type)]
 
+use anyhow::bail;
 use gw_block_producer::produce_block::{
     generate_produce_block_param, produce_block, ProduceBlockParam, ProduceBlockResult,
 };
-use gw_chain::chain::{Chain, L1Action, L1ActionContext, SyncParam};
+use gw_chain::chain::{
+    Chain, L1Action, L1ActionContext, RevertL1ActionContext, RevertedL1Action, SyncParam,
+};
 use gw_common::blake2b::new_blake2b;
 use gw_config::{
     BackendConfig, BackendForkConfig, ChainConfig, GenesisConfig, MemPoolConfig, SUDTProxyConfig,
@@ -25,8 +28,8 @@ use gw_types::{
     offchain::{CellInfo, DepositInfo},
     packed::{
         AllowedTypeHash, CellOutput, DepositInfoVec, DepositLockArgs, DepositRequest, L2Block,
-        OutPoint, RawTransaction, RollupAction, RollupActionUnion, RollupConfig, RollupSubmitBlock,
-        Script, Transaction, WithdrawalRequestExtra, WitnessArgs,
+        L2Transaction, OutPoint, RawTransaction, RollupAction, RollupActionUnion, RollupConfig,
+        RollupSubmitBlock, Script, Transaction, WithdrawalRequestExtra, WitnessArgs,
     },
     prelude::*,
 };
@@ -200,13 +203,14 @@ pub struct TestChain {
 
 impl TestChain {
     pub async fn setup(rollup_type_script: Script) -> Self {
-        let inner = setup_chain(rollup_type_script.clone()).await;
+        Self::builder()
+            .rollup_type_script(rollup_type_script)
+            .build()
+            .await
+    }
 
-        Self {
-            l1_committed_block_number: 1,
-            rollup_type_script,
-            inner,
-        }
+    pub fn builder() -> TestChainBuilder {
+        TestChainBuilder::default()
     }
 
     pub async fn update_mem_pool_config(self, mut mem_pool_config: MemPoolConfig) -> Self {
@@ -314,6 +318,120 @@ impl TestChain {
 
         Ok(())
     }
+
+    /// Push `txs` and `withdrawals` to the mem pool, then produce a block with
+    /// them and `deposit_info_vec`.
+    pub async fn produce_block_with(
+        &mut self,
+        txs: Vec<L2Transaction>,
+        deposit_info_vec: DepositInfoVec,
+        withdrawals: Vec<WithdrawalRequestExtra>,
+    ) -> anyhow::Result<L2Block> {
+        {
+            let mut mem_pool = self.mem_pool().await;
+            for tx in txs {
+                mem_pool.push_transaction(tx)?;
+            }
+            for withdrawal in withdrawals.iter() {
+                mem_pool
+                    .push_withdrawal_request(withdrawal.to_owned())
+                    .await?;
+            }
+        }
+        self.produce_block(deposit_info_vec, withdrawals).await?;
+
+        Ok(self.last_valid_block())
+    }
+
+    /// Detach blocks after `block_number` and reset the mem pool to the new
+    /// tip, txs and withdrawals of detached blocks are reinjected.
+    pub async fn revert_to(&mut self, block_number: u64) -> anyhow::Result<()> {
+        let tip_number: u64 = self.last_valid_block().raw().number().unpack();
+        if block_number >= tip_number {
+            bail!("revert to {}, but tip is {}", block_number, tip_number);
+        }
+
+        let mut db = self.store().begin_transaction();
+        for _ in block_number..tip_number {
+            let l2block = db.get_tip_block()?;
+            let prev_block_hash: H256 = l2block.raw().parent_block_hash().unpack();
+            let prev_global_state = db
+                .get_block_post_global_state(&prev_block_hash)?
+                .expect("prev block global state");
+            let action = RevertedL1Action {
+                prev_global_state,
+                context: RevertL1ActionContext::SubmitValidBlock { l2block },
+            };
+            self.inner.revert_l1action(&mut db, action)?;
+        }
+        db.commit()?;
+
+        let new_tip = self.store().get_tip_block_hash()?;
+        let mut mem_pool = self.mem_pool().await;
+        mem_pool.notify_new_tip(new_tip, &Default::default()).await
+    }
+}
+
+/// Builder of a [`TestChain`], e.g.
+///
+/// ```ignore
+/// let mut chain = TestChain::builder()
+///     .rollup_config(rollup_config)
+///     .build()
+///     .await;
+/// chain.produce_block_with(txs, deposits, withdrawals).await?;
+/// chain.revert_to(1).await?;
+/// ```
+#[derive(Default)]
+pub struct TestChainBuilder {
+    rollup_type_script: Script,
+    rollup_config: Option<RollupConfig>,
+    mem_pool_config: Option<MemPoolConfig>,
+}
+
+impl TestChainBuilder {
+    pub fn rollup_type_script(mut self, rollup_type_script: Script) -> Self {
+        self.rollup_type_script = rollup_type_script;
+        self
+    }
+
+    /// Rollup config of genesis, defaults to [`test_rollup_config`].
+    pub fn rollup_config(mut self, rollup_config: RollupConfig) -> Self {
+        self.rollup_config = Some(rollup_config);
+        self
+    }
+
+    pub fn mem_pool_config(mut self, mem_pool_config: MemPoolConfig) -> Self {
+        self.mem_pool_config = Some(mem_pool_config);
+        self
+    }
+
+    pub async fn build(self) -> TestChain {
+        let rollup_config = self.rollup_config.unwrap_or_else(test_rollup_config);
+        let mut account_lock_manage = AccountLockManage::default();
+        account_lock_manage
+            .register_lock_algorithm(*ALWAYS_SUCCESS_CODE_HASH, Arc::new(AlwaysSuccess));
+        account_lock_manage.register_lock_algorithm(
+            *ETH_ACCOUNT_LOCK_CODE_HASH,
+            Arc::new(Secp256k1Eth::default()),
+        );
+        let inner = setup_chain_with_account_lock_manage(
+            self.rollup_type_script.clone(),
+            rollup_config,
+            account_lock_manage,
+            None,
+            self.mem_pool_config,
+            None,
+        )
+        .await;
+        inner.notify_new_tip().await.unwrap();
+
+        TestChain {
+            l1_committed_block_number: 1,
+            rollup_type_script: self.rollup_type_script,
+            inner,
+        }
+    }
 }
 
 pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
@@ -325,24 +443,36 @@ pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
             generator_path: META_GENERATOR_PATH.into(),
             validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
             backend_type: gw_config::BackendType::Meta,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
         BackendConfig {
             validator_path: SUDT_VALIDATOR_PATH.into(),
             generator_path: SUDT_GENERATOR_PATH.into(),
             validator_script_type_hash: sudt_validator_script_type_hash.into(),
             backend_type: gw_config::BackendType::Sudt,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
         BackendConfig {
             validator_path: ETH_REGISTRY_VALIDATOR_PATH.into(),
             generator_path: ETH_REGISTRY_GENERATOR_PATH.into(),
             validator_script_type_hash: (*ETH_EOA_MAPPING_REGISTRY_VALIDATOR_CODE_HASH).into(),
             backend_type: gw_config::BackendType::EthAddrReg,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
         BackendConfig {
             validator_path: POLYJUICE_VALIDATOR_PATH.into(),
             generator_path: POLYJUICE_GENERATOR_PATH.into(),
             validator_script_type_hash: (*POLYJUICE_VALIDATOR_CODE_HASH).into(),
             backend_type: gw_config::BackendType::Polyjuice,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
     ];
     BackendManage::from_config(vec![BackendForkConfig {
@@ -356,9 +486,10 @@ pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
     .expect("default backend")
 }
 
-pub async fn setup_chain(rollup_type_script: Script) -> Chain {
-    let mut account_lock_manage = AccountLockManage::default();
-    let rollup_config = RollupConfig::new_builder()
+/// Rollup config allowing always success and eth accounts, and builtin
+/// contracts.
+pub fn test_rollup_config() -> RollupConfig {
+    RollupConfig::new_builder()
         .allowed_eoa_type_hashes(
             vec![
                 AllowedTypeHash::new(AllowedEoaType::Eth, *ETH_ACCOUNT_LOCK_CODE_HASH),
@@ -384,7 +515,12 @@ pub async fn setup_chain(rollup_type_script: Script) -> Chain {
         .l2_sudt_validator_script_type_hash(SUDT_VALIDATOR_CODE_HASH.pack())
         .finality_blocks(DEFAULT_FINALITY_BLOCKS.pack())
         .chain_id(TEST_CHAIN_ID.pack())
-        .build();
+        .build()
+}
+
+pub async fn setup_chain(rollup_type_script: Script) -> Chain {
+    let mut account_lock_manage = AccountLockManage::default();
+    let rollup_config = test_rollup_config();
     account_lock_manage.register_lock_algorithm(*ALWAYS_SUCCESS_CODE_HASH, Arc::new(AlwaysSuccess));
     account_lock_manage.register_lock_algorithm(
         *ETH_ACCOUNT_LOCK_CODE_HASH,
@@ -525,6 +661,8 @@ pub async fn setup_chain_with_account_lock_manage(
         dynamic_config_manager: Default::default(),
         sync_server: None,
         account_creator: None,
+        cycles_limit_manager: None,
+        max_custodian_inputs: None,
     };
     let mem_pool = MemPool::create(args).await.unwrap();
     Chain::create(
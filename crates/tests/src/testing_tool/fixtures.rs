@@ -0,0 +1,110 @@
+//! Generators of accounts and requests, shared by tests and benchmarks.
+//!
+//! Accounts use the always success lock, so generated txs and withdrawals
+//! are not signed.
+
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    registry_address::RegistryAddress,
+};
+use gw_types::{
+    bytes::Bytes,
+    h256::*,
+    offchain::DepositInfo,
+    packed::{
+        DepositRequest, Fee, L2Transaction, RawL2Transaction, RawWithdrawalRequest, SUDTArgs,
+        SUDTTransfer, Script, WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::*,
+    U256,
+};
+use gw_utils::RollupContext;
+
+use super::{chain::into_deposit_info_cell, common::random_always_success_script};
+
+/// Random always success accounts with their eth registry addresses.
+pub fn always_success_accounts(
+    rollup_script_hash: &H256,
+    count: usize,
+) -> Vec<(Script, RegistryAddress)> {
+    (0..count)
+        .map(|_| {
+            let script = random_always_success_script(rollup_script_hash);
+            let args: Bytes = script.args().unpack();
+            let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, args[32..].to_vec());
+            (script, address)
+        })
+        .collect()
+}
+
+/// CKB deposits to `scripts`.
+pub fn ckb_deposits(
+    rollup_context: &RollupContext,
+    scripts: impl IntoIterator<Item = Script>,
+    capacity: u64,
+) -> Vec<DepositInfo> {
+    { scripts.into_iter() }
+        .map(|script| {
+            let request = DepositRequest::new_builder()
+                .capacity(capacity.pack())
+                .sudt_script_hash(H256::zero().pack())
+                .amount(0u128.pack())
+                .script(script)
+                .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+                .build();
+            into_deposit_info_cell(rollup_context, request)
+        })
+        .collect()
+}
+
+/// CKB transfer without fee.
+pub fn ckb_transfer(
+    chain_id: u64,
+    from_id: u32,
+    nonce: u32,
+    to: &RegistryAddress,
+    amount: U256,
+) -> L2Transaction {
+    let fee = Fee::new_builder()
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .amount(0u128.pack())
+        .build();
+    let transfer = SUDTTransfer::new_builder()
+        .to_address(Bytes::from(to.to_bytes()).pack())
+        .amount(amount.pack())
+        .fee(fee)
+        .build();
+    let args = SUDTArgs::new_builder().set(transfer).build();
+    let raw = RawL2Transaction::new_builder()
+        .chain_id(chain_id.pack())
+        .from_id(from_id.pack())
+        .to_id(CKB_SUDT_ACCOUNT_ID.pack())
+        .nonce(nonce.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    L2Transaction::new_builder().raw(raw).build()
+}
+
+/// CKB withdrawal without fee, owned by the default script.
+pub fn ckb_withdrawal(
+    chain_id: u64,
+    account_script_hash: H256,
+    nonce: u32,
+    capacity: u64,
+) -> WithdrawalRequestExtra {
+    let owner_lock = Script::default();
+    let raw = RawWithdrawalRequest::new_builder()
+        .nonce(nonce.pack())
+        .capacity(capacity.pack())
+        .account_script_hash(account_script_hash.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .owner_lock_hash(owner_lock.hash().pack())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .chain_id(chain_id.pack())
+        .build();
+    let request = WithdrawalRequest::new_builder().raw(raw).build();
+    WithdrawalRequestExtra::new_builder()
+        .request(request)
+        .owner_lock(owner_lock)
+        .build()
+}
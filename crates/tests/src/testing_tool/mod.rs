@@ -2,6 +2,7 @@ pub mod bad_block;
 pub mod chain;
 pub mod common;
 pub mod eth_wallet;
+pub mod fixtures;
 pub mod mem_pool_provider;
 pub mod polyjuice;
 pub mod rpc_server;
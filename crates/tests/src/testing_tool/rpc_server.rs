@@ -49,7 +49,7 @@ impl RPCServer {
         let rollup_context = generator.rollup_context().to_owned();
         let rpc_client = {
             let ckb_client = CKBClient::with_url(&RPCClientConfig::default().ckb_url).unwrap();
-            let indexer_client = CKBIndexerClient::new(ckb_client.client().clone(), false);
+            let indexer_client = CKBIndexerClient::with_ckb_client(&ckb_client);
             let rollup_type_script =
                 ckb_types::packed::Script::new_unchecked(rollup_type_script.as_bytes());
             RPCClient::new(
@@ -72,7 +72,6 @@ impl RPCServer {
             mem_pool_config: Default::default(),
             node_mode: FullNode,
             rpc_client,
-            send_tx_rate_limit: Default::default(),
             server_config: gw_config::RPCServerConfig {
                 enable_methods: HashSet::from_iter(vec![RPCMethods::Test]),
                 ..Default::default()
@@ -83,6 +82,8 @@ impl RPCServer {
             gasless_tx_support_config: None,
             polyjuice_sender_recover,
             debug_backend_forks: None,
+            tx_relay: None,
+            contracts_dep_manager: None,
         }
     }
 
@@ -96,6 +97,19 @@ impl RPCServer {
         Ok(server)
     }
 
+    /// Server of `admin_*` methods only, as served on `rpc_server.admin.listen`.
+    pub async fn build_admin_from_registry_args(
+        registry_args: RegistryArgs<TestModeControl>,
+    ) -> Result<Self> {
+        let server = RPCServer {
+            inner: Registry::create(registry_args)
+                .await
+                .build_admin_rpc_server()?,
+        };
+
+        Ok(server)
+    }
+
     pub async fn build(chain: &TestChain, creator_wallet: Option<Wallet>) -> Result<Self> {
         let rollup_type_script = chain.rollup_type_script.to_owned();
         let registry_args =
@@ -199,6 +213,28 @@ impl RPCServer {
         Ok(hash.0)
     }
 
+    pub async fn evict_transaction(&self, hash: H256) -> Result<bool> {
+        let fixed_hash = ckb_fixed_hash::H256(hash);
+        let params = serde_json::to_value(&(fixed_hash,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("admin_evict_transaction")
+            .with_params(params)
+            .finish();
+
+        self.handle_single_request(req).await
+    }
+
+    pub async fn discover(&self) -> Result<serde_json::Value> {
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("rpc.discover")
+            .finish();
+
+        self.handle_single_request(req).await
+    }
+
     async fn handle_single_request<R: DeserializeOwned>(&self, req: RequestObject) -> Result<R> {
         let ret = match self.inner.handle(req).await {
             ResponseObjects::One(ResponseObject::Result { result, .. }) => {
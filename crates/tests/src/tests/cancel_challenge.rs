@@ -0,0 +1,247 @@
+use crate::testing_tool::chain::{
+    build_sync_tx, construct_block, into_deposit_info_cell, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+    DEFAULT_FINALITY_BLOCKS,
+};
+
+use ckb_fixed_hash::H256 as JsonH256;
+use gw_block_producer::{
+    cancel_challenge::{build_cancel_challenge, CancelChallengeArgs},
+    produce_block::ProduceBlockResult,
+};
+use gw_chain::chain::{Chain, ChallengeCell, L1Action, L1ActionContext, SyncEvent, SyncParam};
+use gw_config::ContractsCellDep;
+use gw_jsonrpc_types::blockchain::{CellDep, OutPoint};
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    bytes::Bytes,
+    core::{ChallengeTargetType, ScriptHashType, Status},
+    h256::*,
+    offchain::CellInfo,
+    packed::{
+        CellInput, CellOutput, ChallengeTarget, ChallengeWitness, DepositInfoVec, DepositRequest,
+        RawWithdrawalRequest, Script, WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::*,
+};
+
+use std::convert::TryFrom;
+
+const CKB: u64 = 100000000;
+
+async fn sync_block(
+    chain: &mut Chain,
+    rollup_cell: CellOutput,
+    deposit_info_vec: DepositInfoVec,
+) -> ProduceBlockResult {
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(chain, &mut mem_pool, deposit_info_vec.clone())
+            .await
+            .unwrap()
+    };
+    let update = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: block_result.block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: block_result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell, block_result.clone()),
+    };
+    let param = SyncParam {
+        updates: vec![update],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+    block_result
+}
+
+/// Produce a valid block with a withdrawal of the returned account script,
+/// then challenge the withdrawal.
+async fn challenge_valid_withdrawal(chain: &mut Chain, rollup_type_script: Script) -> Script {
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let alice_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_type_script.hash().to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity((4000u64 * CKB).pack())
+        .script(alice_script.clone())
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+        .build();
+    sync_block(chain, rollup_cell.clone(), deposit_info_vec).await;
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        sync_block(chain, rollup_cell.clone(), Default::default()).await;
+    }
+
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(alice_script.hash().pack())
+            .sudt_script_hash(H256::zero().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(crate::testing_tool::chain::TEST_CHAIN_ID.pack())
+            .build();
+        WithdrawalRequestExtra::new_builder()
+            .request(WithdrawalRequest::new_builder().raw(raw).build())
+            .owner_lock(owner_lock)
+            .build()
+    };
+    {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+    }
+    let block_result = sync_block(chain, rollup_cell.clone(), Default::default()).await;
+    let block = block_result.block.clone();
+    assert_eq!(block.withdrawals().len(), 1);
+
+    let target = ChallengeTarget::new_builder()
+        .block_hash(block.hash().pack())
+        .target_index(0u32.pack())
+        .target_type(ChallengeTargetType::Withdrawal.into())
+        .build();
+    let witness = {
+        let mut db = chain.store().begin_transaction();
+        let block_proof = db
+            .block_smt()
+            .unwrap()
+            .merkle_proof(vec![block.smt_key().into()])
+            .unwrap()
+            .compile(vec![block.smt_key().into()])
+            .unwrap();
+        ChallengeWitness::new_builder()
+            .raw_l2block(block.raw())
+            .block_proof(block_proof.0.pack())
+            .build()
+    };
+    let challenge_cell = ChallengeCell {
+        input: CellInput::default(),
+        output: CellOutput::default(),
+        output_data: Bytes::default(),
+    };
+    let halting_result = ProduceBlockResult {
+        global_state: { block_result.global_state.clone() }
+            .as_builder()
+            .status(Status::Halting.into())
+            .build(),
+        ..block_result
+    };
+    let challenge = L1Action {
+        context: L1ActionContext::Challenge {
+            cell: challenge_cell,
+            target,
+            witness,
+        },
+        transaction: build_sync_tx(rollup_cell, halting_result),
+    };
+    let param = SyncParam {
+        updates: vec![challenge],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    assert!(matches!(
+        chain.last_sync_event(),
+        SyncEvent::BadChallenge { .. }
+    ));
+
+    alice_script
+}
+
+fn contracts_dep_with_eoa_lock(code_hash: [u8; 32], dep: CellDep) -> ContractsCellDep {
+    let mut contracts_dep = ContractsCellDep::default();
+    contracts_dep
+        .allowed_eoa_locks
+        .insert(JsonH256(code_hash), dep);
+    contracts_dep
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_cancel_challenge_of_valid_withdrawal() {
+    let rollup_type_script = Script::default();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let alice_script = challenge_valid_withdrawal(&mut chain, rollup_type_script).await;
+
+    let context = match chain.last_sync_event() {
+        SyncEvent::BadChallenge { context, .. } => context.as_ref().clone(),
+        _ => unreachable!(),
+    };
+    let prev_global_state = chain.local_state().last_global_state().clone();
+    let eoa_dep = CellDep {
+        out_point: OutPoint {
+            tx_hash: JsonH256([1u8; 32]),
+            index: 0u32.into(),
+        },
+        ..Default::default()
+    };
+    let contracts_dep = contracts_dep_with_eoa_lock(*ALWAYS_SUCCESS_CODE_HASH, eoa_dep.clone());
+    let owner_lock = Script::new_builder().args(vec![7u8; 20].pack()).build();
+
+    let cancel = build_cancel_challenge(CancelChallengeArgs {
+        rollup_context: chain.generator().rollup_context(),
+        prev_global_state,
+        challenge_cell: &CellInfo::default(),
+        burn_lock: Script::default(),
+        owner_lock,
+        context,
+        builtin_load_data: &Default::default(),
+        load_data_strategy: None,
+        contracts_dep: &contracts_dep,
+    })
+    .unwrap();
+
+    // The verifier cell is locked by the withdrawal account lock and unlocked
+    // with the withdrawal signature.
+    let (verifier_output, _) = &cancel.output.verifier_cell;
+    assert_eq!(verifier_output.lock().as_slice(), alice_script.as_slice());
+    assert!(cancel.output.verifier_witness.is_some());
+    assert_eq!(
+        cancel.verifier_dep.as_slice(),
+        gw_types::packed::CellDep::from(eoa_dep).as_slice()
+    );
+    let post_status: u8 = cancel.output.post_global_state.status().into();
+    assert_eq!(Status::try_from(post_status), Ok(Status::Running));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_cancel_challenge_without_verifier_dep() {
+    let rollup_type_script = Script::default();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    challenge_valid_withdrawal(&mut chain, rollup_type_script).await;
+
+    let context = match chain.last_sync_event() {
+        SyncEvent::BadChallenge { context, .. } => context.as_ref().clone(),
+        _ => unreachable!(),
+    };
+    let prev_global_state = chain.local_state().last_global_state().clone();
+    // Dep of another lock.
+    let contracts_dep = contracts_dep_with_eoa_lock([2u8; 32], CellDep::default());
+
+    let result = build_cancel_challenge(CancelChallengeArgs {
+        rollup_context: chain.generator().rollup_context(),
+        prev_global_state,
+        challenge_cell: &CellInfo::default(),
+        burn_lock: Script::default(),
+        owner_lock: Script::default(),
+        context,
+        builtin_load_data: &Default::default(),
+        load_data_strategy: None,
+        contracts_dep: &contracts_dep,
+    });
+    assert!(result.is_err());
+}
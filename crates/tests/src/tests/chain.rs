@@ -2,17 +2,25 @@ use crate::testing_tool::{
     bad_block::generate_bad_block_using_first_withdrawal,
     chain::{
         build_sync_tx, construct_block, into_deposit_info_cell, restart_chain, setup_chain,
-        ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS,
+        ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS, TEST_CHAIN_ID,
     },
+    fixtures::{always_success_accounts, ckb_deposits, ckb_transfer, ckb_withdrawal},
 };
 
 use gw_block_producer::produce_block::ProduceBlockResult;
-use gw_chain::chain::{
-    Chain, ChallengeCell, L1Action, L1ActionContext, RevertL1ActionContext, RevertedL1Action,
-    SyncEvent, SyncParam,
+use gw_chain::{
+    chain::{
+        Chain, ChallengeCell, L1Action, L1ActionContext, RevertL1ActionContext, RevertedL1Action,
+        SyncEvent, SyncParam,
+    },
+    revert::RevertKind,
+};
+use gw_common::{
+    builtins::CKB_SUDT_ACCOUNT_ID, ckb_decimal::CKBCapacity, registry_address::RegistryAddress,
+    state::State,
 };
-use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, ckb_decimal::CKBCapacity, state::State};
 use gw_store::{
+    mem_pool_state::StateDivergence,
     state::{history::history_state::RWConfig, BlockStateDB},
     traits::chain_store::ChainStore,
 };
@@ -21,10 +29,11 @@ use gw_types::{
     core::{ScriptHashType, Status},
     h256::*,
     packed::{
-        CellInput, CellOutput, DepositInfoVec, DepositRequest, GlobalState, RawWithdrawalRequest,
-        Script, WithdrawalRequest, WithdrawalRequestExtra,
+        CellInput, CellOutput, DepositInfoVec, DepositRequest, GlobalState, L2Transaction,
+        RawWithdrawalRequest, Script, WithdrawalRequest, WithdrawalRequestExtra,
     },
     prelude::*,
+    U256,
 };
 
 const CKB: u64 = 100000000;
@@ -347,6 +356,14 @@ async fn test_layer1_fork() {
     let tip_block_number: u64 = tip_block.raw().number().unpack();
     assert_eq!(tip_block_number, 1);
 
+    // Both blocks are detached down to the genesis block
+    let reverted = chain.last_reverted_blocks();
+    assert_eq!(reverted.len(), 1);
+    assert_eq!(reverted[0].kind, RevertKind::Detach);
+    assert_eq!(reverted[0].blocks.len(), 2);
+    let fork_point_number: u64 = reverted[0].fork_point.number().unpack();
+    assert_eq!(fork_point_number, 0);
+
     // check account SMT, should be able to calculate account state root
     {
         let mut db = chain.store().begin_transaction();
@@ -720,6 +737,70 @@ async fn test_sync_blocks() {
     drop(chain2);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_safe_mode_on_state_divergence() {
+    let rollup_type_script = Script::default();
+    let rollup_script_hash = rollup_type_script.hash();
+    let mut chain1 = setup_chain(rollup_type_script.clone()).await;
+    let mut chain2 = setup_chain(rollup_type_script.clone()).await;
+
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+    let user_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = rollup_script_hash.to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    let deposit = DepositRequest::new_builder()
+        .capacity((400u64 * CKB).pack())
+        .script(user_script)
+        .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let mut sync_1 = produce_a_block(&mut chain1, deposit, rollup_cell, 1).await;
+    drop(chain1);
+
+    // A post account root that replaying the block doesn't reproduce.
+    let wrong_root: H256 = [1u8; 32];
+    if let L1ActionContext::SubmitBlock { l2block, .. } = &mut sync_1.updates[0].context {
+        let raw = l2block.raw();
+        let post_account = { raw.post_account().as_builder() }
+            .merkle_root(wrong_root.pack())
+            .build();
+        let raw = raw.as_builder().post_account(post_account).build();
+        *l2block = l2block.clone().as_builder().raw(raw).build();
+    }
+
+    let err = chain2.sync(sync_1.clone()).await.unwrap_err();
+    let divergence = err.downcast_ref::<StateDivergence>().expect("divergence");
+    assert_eq!(divergence.block_number, 1);
+    assert_eq!(divergence.expected_root, wrong_root);
+    assert_eq!(
+        chain2
+            .store()
+            .get_tip_block()
+            .unwrap()
+            .raw()
+            .number()
+            .unpack(),
+        0u64
+    );
+
+    // Safe mode is shared with the mem pool, and syncing is stopped.
+    let mem_pool = chain2.mem_pool().clone().unwrap();
+    let mem_pool_state = mem_pool.lock().await.mem_pool_state();
+    assert_eq!(
+        mem_pool_state.state_divergence().as_deref(),
+        Some(divergence)
+    );
+    let err = chain2.sync(sync_1).await.unwrap_err();
+    assert!(err.to_string().contains("safe mode"));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_rewind_to_last_valid_tip_just_after_bad_block_reverted() {
     let rollup_type_script = Script::default();
@@ -917,6 +998,7 @@ async fn test_rewind_to_last_valid_tip_just_after_bad_block_reverted() {
         ..bad_block_result
     };
 
+    let bad_block_hash = reverted_block_result.block.hash();
     let revert_bad_block = L1Action {
         context: L1ActionContext::Revert {
             reverted_blocks: vec![reverted_block_result.block.raw()],
@@ -929,6 +1011,10 @@ async fn test_rewind_to_last_valid_tip_just_after_bad_block_reverted() {
     };
     chain.sync(param).await.unwrap();
     assert!(chain.last_sync_event().is_success());
+    let reverted = chain.last_reverted_blocks();
+    assert_eq!(reverted.len(), 1);
+    assert_eq!(reverted[0].kind, RevertKind::Revert);
+    assert_eq!(reverted[0].blocks[0].hash(), bad_block_hash);
 
     let local_reverted_block_smt_root = db.get_reverted_block_smt_root().unwrap();
     assert_eq!(local_reverted_block_smt_root, reverted_block_smt_root);
@@ -1002,6 +1088,283 @@ async fn test_rewind_to_last_valid_tip_just_after_bad_block_reverted() {
     assert_eq!(tip_block_number, 8);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_layer1_revert_reinjects_into_mem_pool() {
+    let rollup_type_script = Script::default();
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let accounts = always_success_accounts(&rollup_type_script.hash(), 2);
+    let (producer, mut chain, ids) = setup_producer_and_syncer(rollup_type_script, &accounts).await;
+
+    let tx = ckb_transfer(TEST_CHAIN_ID, ids[0], 0, &accounts[1].1, U256::one());
+    let withdrawal = ckb_withdrawal(TEST_CHAIN_ID, accounts[1].0.hash(), 0, 1000 * CKB);
+    let block_result = construct_block_with(
+        &producer,
+        Default::default(),
+        vec![tx.clone()],
+        vec![withdrawal.clone()],
+    )
+    .await;
+    let block = block_result.block.clone();
+    assert_eq!(block.transactions().len(), 1);
+    assert_eq!(block.withdrawals().len(), 1);
+    let param = submit_block_param(rollup_cell, block_result, Default::default());
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+    {
+        let mem_pool = chain.mem_pool().as_ref().unwrap().lock().await;
+        assert!(mem_pool.mem_block().txs().is_empty());
+        assert!(mem_pool.mem_block().withdrawals().is_empty());
+    }
+
+    // Revert the block on L1, the chain resets the mem pool without
+    // `notify_new_tip`.
+    let parent_block_hash: H256 = block.raw().parent_block_hash().unpack();
+    let prev_global_state = chain
+        .store()
+        .get_block_post_global_state(&parent_block_hash)
+        .unwrap()
+        .unwrap();
+    let revert = RevertedL1Action {
+        prev_global_state,
+        context: RevertL1ActionContext::SubmitValidBlock {
+            l2block: block.clone(),
+        },
+    };
+    let param = SyncParam {
+        updates: Default::default(),
+        reverts: vec![revert],
+    };
+    chain.sync(param).await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+    assert_eq!(chain.last_reverted_blocks()[0].kind, RevertKind::Detach);
+
+    // The tx and the withdrawal of the detached block are reinjected.
+    let mem_pool = chain.mem_pool().as_ref().unwrap().lock().await;
+    let mem_block = mem_pool.mem_block();
+    let mem_block_number: u64 = mem_block.block_info().number().unpack();
+    let block_number: u64 = block.raw().number().unpack();
+    assert_eq!(mem_block_number, block_number);
+    assert!(mem_block.txs_set().contains(&tx.hash()));
+    assert!(mem_block.withdrawals_set().contains(&withdrawal.hash()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_bad_block_revert_reinjects_into_mem_pool() {
+    let rollup_type_script = Script::default();
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let accounts = always_success_accounts(&rollup_type_script.hash(), 2);
+    let (producer, mut chain, ids) = setup_producer_and_syncer(rollup_type_script, &accounts).await;
+
+    // A bad block with a tx, whose first withdrawal is bad.
+    let tx = ckb_transfer(TEST_CHAIN_ID, ids[0], 0, &accounts[1].1, U256::one());
+    let withdrawal = ckb_withdrawal(TEST_CHAIN_ID, accounts[1].0.hash(), 0, 1000 * CKB);
+    let block_result = construct_block_with(
+        &producer,
+        Default::default(),
+        vec![tx.clone()],
+        vec![withdrawal],
+    )
+    .await;
+    let mut bad_block_result = {
+        let (block, global_state) = generate_bad_block_using_first_withdrawal(
+            &producer,
+            block_result.block.clone(),
+            block_result.global_state.clone(),
+        );
+        let withdrawal_extras = { block_result.withdrawal_extras.iter() }
+            .zip(block.withdrawals().into_iter())
+            .map(|(extra, bad)| extra.clone().as_builder().request(bad).build())
+            .collect();
+        ProduceBlockResult {
+            block,
+            global_state,
+            withdrawal_extras,
+            ..block_result
+        }
+    };
+    assert_eq!(bad_block_result.block.transactions().len(), 1);
+    let param = submit_block_param(
+        rollup_cell.clone(),
+        bad_block_result.clone(),
+        Default::default(),
+    );
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    let challenge_context = match chain.last_sync_event() {
+        SyncEvent::BadBlock { context } => context.to_owned(),
+        event => panic!("unexpected sync event {:?}", event),
+    };
+
+    // Challenge the bad block
+    bad_block_result.global_state = { bad_block_result.global_state.as_builder() }
+        .status(Status::Halting.into())
+        .build();
+    let challenge = L1Action {
+        context: L1ActionContext::Challenge {
+            cell: ChallengeCell {
+                input: CellInput::default(),
+                output: CellOutput::default(),
+                output_data: Bytes::default(),
+            },
+            target: challenge_context.target,
+            witness: challenge_context.witness,
+        },
+        transaction: build_sync_tx(rollup_cell.clone(), bad_block_result.clone()),
+    };
+    let param = SyncParam {
+        updates: vec![challenge],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    let reverted_block_root = match chain.last_sync_event() {
+        SyncEvent::WaitChallenge { context, .. } => context.post_reverted_block_root,
+        event => panic!("unexpected sync event {:?}", event),
+    };
+    {
+        let mem_pool = chain.mem_pool().as_ref().unwrap().lock().await;
+        assert!(!mem_pool.mem_block().txs_set().contains(&tx.hash()));
+    }
+
+    // Revert the bad block, the chain resets the mem pool without
+    // `notify_new_tip`.
+    let reverted_block_result = {
+        let db = chain.store().begin_transaction();
+        let last_valid_tip_block = db.get_last_valid_tip_block().unwrap();
+        let block_smt = db
+            .get_block_post_global_state(&last_valid_tip_block.hash())
+            .unwrap()
+            .unwrap()
+            .block();
+        let global_state = { bad_block_result.global_state.as_builder() }
+            .status(Status::Running.into())
+            .reverted_block_root(reverted_block_root.pack())
+            .tip_block_hash(last_valid_tip_block.hash().pack())
+            .block(block_smt)
+            .account(last_valid_tip_block.raw().post_account())
+            .build();
+        ProduceBlockResult {
+            global_state,
+            ..bad_block_result
+        }
+    };
+    let revert = L1Action {
+        context: L1ActionContext::Revert {
+            reverted_blocks: vec![reverted_block_result.block.raw()],
+        },
+        transaction: build_sync_tx(rollup_cell, reverted_block_result),
+    };
+    let param = SyncParam {
+        updates: vec![revert],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+    assert_eq!(chain.last_reverted_blocks()[0].kind, RevertKind::Revert);
+
+    // The tx of the bad block is reinjected. Only txs of bad blocks are
+    // reinjected, as their withdrawals may be the bad ones.
+    let mem_pool = chain.mem_pool().as_ref().unwrap().lock().await;
+    assert!(mem_pool.mem_block().txs_set().contains(&tx.hash()));
+}
+
+/// A chain producing blocks and a chain syncing them, so that the mem pool of
+/// the latter doesn't see txs and withdrawals of the blocks. `accounts` are
+/// deposited and finalized, returns their account ids.
+async fn setup_producer_and_syncer(
+    rollup_type_script: Script,
+    accounts: &[(Script, RegistryAddress)],
+) -> (Chain, Chain, Vec<u32>) {
+    let mut producer = setup_chain(rollup_type_script.clone()).await;
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script).pack())
+        .build();
+
+    let deposits = ckb_deposits(
+        producer.generator().rollup_context(),
+        accounts.iter().map(|(script, _)| script.clone()),
+        10000 * CKB,
+    );
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .extend(deposits.into_iter().map(|d| d.pack()))
+        .build();
+    for i in 0..=DEFAULT_FINALITY_BLOCKS {
+        let deposit_info_vec = if i == 0 {
+            deposit_info_vec.clone()
+        } else {
+            Default::default()
+        };
+        let block_result =
+            construct_block_with(&producer, deposit_info_vec.clone(), vec![], vec![]).await;
+        let param = submit_block_param(rollup_cell.clone(), block_result, deposit_info_vec);
+        for node in [&mut producer, &mut chain] {
+            node.sync(param.clone()).await.unwrap();
+            node.notify_new_tip().await.unwrap();
+            assert!(node.last_sync_event().is_success());
+        }
+    }
+
+    let state = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap().lock().await;
+        mem_pool.mem_pool_state().load_state_db()
+    };
+    let ids: Vec<u32> = { accounts.iter() }
+        .map(|(script, _)| {
+            state
+                .get_account_id_by_script_hash(&script.hash())
+                .unwrap()
+                .expect("account id")
+        })
+        .collect();
+    (producer, chain, ids)
+}
+
+/// Construct a block with `txs` and `withdrawals` pushed to the mem pool.
+async fn construct_block_with(
+    chain: &Chain,
+    deposit_info_vec: DepositInfoVec,
+    txs: Vec<L2Transaction>,
+    withdrawals: Vec<WithdrawalRequestExtra>,
+) -> ProduceBlockResult {
+    let mem_pool = chain.mem_pool().as_ref().unwrap();
+    let mut mem_pool = mem_pool.lock().await;
+    for tx in txs {
+        mem_pool.push_transaction(tx).unwrap();
+    }
+    for withdrawal in withdrawals {
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+    }
+    construct_block(chain, &mut mem_pool, deposit_info_vec)
+        .await
+        .unwrap()
+}
+
+fn submit_block_param(
+    rollup_cell: CellOutput,
+    block_result: ProduceBlockResult,
+    deposit_info_vec: DepositInfoVec,
+) -> SyncParam {
+    let update = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: block_result.block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: block_result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell, block_result),
+    };
+    SyncParam {
+        updates: vec![update],
+        reverts: Default::default(),
+    }
+}
+
 async fn produce_empty_block(chain: &mut Chain, rollup_cell: CellOutput) {
     let block_result = {
         let mem_pool = chain.mem_pool().as_ref().unwrap();
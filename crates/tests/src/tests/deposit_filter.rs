@@ -0,0 +1,101 @@
+use crate::testing_tool::{
+    chain::{into_deposit_info_cell, TestChain},
+    fixtures::{always_success_accounts, ckb_deposits},
+    mem_pool_provider::DummyMemPoolProvider,
+};
+
+use ckb_fixed_hash::H256 as JsonH256;
+use gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID;
+use gw_config::{DepositFilterConfig, MemBlockConfig, MemPoolConfig};
+use gw_types::{
+    core::ScriptHashType,
+    offchain::DepositInfo,
+    packed::{DepositRequest, Script},
+    prelude::*,
+};
+use gw_utils::RollupContext;
+
+const DEPOSIT_CAPACITY: u64 = 1000_00000000;
+const MIN_CKB_CAPACITY: u64 = 500_00000000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_deposit_filter() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let allowed_sudt = sudt_type(chain.inner.generator().rollup_context(), 1);
+    let mem_pool_config = MemPoolConfig {
+        mem_block: MemBlockConfig {
+            deposit_filter: DepositFilterConfig {
+                min_ckb_capacity: MIN_CKB_CAPACITY,
+                allowed_sudt_script_hashes: vec![JsonH256(allowed_sudt.hash())],
+                max_deposits_per_account: 1,
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let chain = chain.update_mem_pool_config(mem_pool_config).await;
+    let rollup_context = chain.inner.generator().rollup_context();
+
+    let accounts = always_success_accounts(&chain.rollup_type_hash(), 4);
+    let scripts: Vec<Script> = accounts.into_iter().map(|(script, _)| script).collect();
+    let mut collected = ckb_deposits(rollup_context, scripts[..1].to_vec(), DEPOSIT_CAPACITY);
+    collected.push(sudt_deposit(rollup_context, &scripts[1], &allowed_sudt));
+
+    // Less than min_ckb_capacity.
+    let mut filtered = ckb_deposits(rollup_context, scripts[2..3].to_vec(), MIN_CKB_CAPACITY - 1);
+    // Exceeds max_deposits_per_account.
+    filtered.extend(ckb_deposits(
+        rollup_context,
+        scripts[..1].to_vec(),
+        DEPOSIT_CAPACITY,
+    ));
+    // Not in allowed_sudt_script_hashes.
+    let other_sudt = sudt_type(rollup_context, 2);
+    filtered.push(sudt_deposit(rollup_context, &scripts[3], &other_sudt));
+
+    let mut mem_pool = chain.mem_pool().await;
+    let provider = DummyMemPoolProvider {
+        deposit_cells: collected.iter().chain(filtered.iter()).cloned().collect(),
+        ..Default::default()
+    };
+    mem_pool.set_provider(Box::new(provider));
+    mem_pool.reset_mem_block(&Default::default()).await.unwrap();
+
+    let out_points: Vec<_> = { mem_pool.mem_block().deposits().iter() }
+        .map(|d| d.cell.out_point.clone())
+        .collect();
+    let expected: Vec<_> = { collected.iter() }
+        .map(|d| d.cell.out_point.clone())
+        .collect();
+    assert_eq!(out_points, expected);
+}
+
+fn sudt_type(rollup_context: &RollupContext, args: u8) -> Script {
+    Script::new_builder()
+        .code_hash(rollup_context.rollup_config.l1_sudt_script_type_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![args; 32].pack())
+        .build()
+}
+
+/// A sUDT deposit of `sudt_type` to `script`.
+fn sudt_deposit(
+    rollup_context: &RollupContext,
+    script: &Script,
+    sudt_type: &Script,
+) -> DepositInfo {
+    let request = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(sudt_type.hash().pack())
+        .amount(100u128.pack())
+        .script(script.clone())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    let mut info = into_deposit_info_cell(rollup_context, request);
+    info.cell.output = { info.cell.output.as_builder() }
+        .type_(Some(sudt_type.clone()).pack())
+        .build();
+    info
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use crate::testing_tool::chain::{
+    build_sync_tx, construct_block, into_deposit_info_cell, setup_chain, DEFAULT_FINALITY_BLOCKS,
+    TEST_CHAIN_ID,
+};
+use crate::testing_tool::common::random_always_success_script;
+use crate::testing_tool::mem_pool_provider::DummyMemPoolProvider;
+
+use ckb_types::prelude::{Builder, Entity};
+use gw_chain::chain::{L1Action, L1ActionContext, SyncParam};
+use gw_types::h256::*;
+use gw_types::packed::{
+    CellOutput, DepositRequest, RawWithdrawalRequest, Script, WithdrawalRequest,
+    WithdrawalRequestExtra,
+};
+use gw_types::prelude::{Pack, PackVec};
+use gw_utils::local_cells::LocalCellsManager;
+
+const CKB: u64 = 100000000;
+const DEPOSIT_CAPACITY: u64 = 1000000 * CKB;
+const WITHDRAWAL_CAPACITY: u64 = 1000 * CKB;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_owner_lock_mismatch_withdrawal_is_not_deferred() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let rollup_script_hash: H256 = rollup_type_script.hash();
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+    let mut chain = setup_chain(rollup_type_script).await;
+    let rollup_context = chain.generator().rollup_context();
+
+    // Deposit accounts
+    let accounts: Vec<_> = (0..2)
+        .map(|_| random_always_success_script(&rollup_script_hash))
+        .collect();
+    let deposits = accounts.iter().map(|account_script| {
+        DepositRequest::new_builder()
+            .capacity(DEPOSIT_CAPACITY.pack())
+            .sudt_script_hash(H256::zero().pack())
+            .amount(0.pack())
+            .script(account_script.to_owned())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build()
+    });
+    let deposit_info_vec = deposits
+        .map(|d| into_deposit_info_cell(rollup_context, d).pack())
+        .pack();
+
+    // Finalize the deposits, so that custodians can cover the withdrawals
+    for i in 0..=DEFAULT_FINALITY_BLOCKS {
+        let deposit_info_vec = if i == 0 {
+            deposit_info_vec.clone()
+        } else {
+            Default::default()
+        };
+        let block_result = {
+            let mem_pool = chain.mem_pool().as_ref().unwrap();
+            let mut mem_pool = mem_pool.lock().await;
+            construct_block(&chain, &mut mem_pool, deposit_info_vec.clone())
+                .await
+                .unwrap()
+        };
+        let action = L1Action {
+            context: L1ActionContext::SubmitBlock {
+                l2block: block_result.block.clone(),
+                deposit_info_vec,
+                deposit_asset_scripts: Default::default(),
+                withdrawals: Default::default(),
+            },
+            transaction: build_sync_tx(rollup_cell.clone(), block_result),
+        };
+        let param = SyncParam {
+            updates: vec![action],
+            reverts: Default::default(),
+        };
+        chain.sync(param).await.unwrap();
+        chain.notify_new_tip().await.unwrap();
+        assert!(chain.last_sync_event().is_success());
+    }
+
+    let build_withdrawal = |account_script: &Script, owner_lock: Script| {
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity(WITHDRAWAL_CAPACITY.pack())
+            .account_script_hash(account_script.hash().pack())
+            .sudt_script_hash(H256::zero().pack())
+            .owner_lock_hash(Script::default().hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .chain_id(TEST_CHAIN_ID.pack())
+            .build();
+        let withdrawal = WithdrawalRequest::new_builder().raw(raw).build();
+        WithdrawalRequestExtra::new_builder()
+            .request(withdrawal)
+            .owner_lock(owner_lock)
+            .build()
+    };
+    let withdrawal = build_withdrawal(&accounts[0], Script::default());
+    // The owner lock doesn't match the owner lock hash, more finalized
+    // custodians won't help it.
+    let mismatch_withdrawal = build_withdrawal(
+        &accounts[1],
+        random_always_success_script(&rollup_script_hash),
+    );
+
+    let mem_pool = chain.mem_pool().as_ref().unwrap();
+    let mut mem_pool = mem_pool.lock().await;
+    let provider = DummyMemPoolProvider {
+        deposit_cells: vec![],
+        fake_blocktime: Duration::from_millis(0),
+    };
+    mem_pool.set_provider(Box::new(provider));
+    mem_pool
+        .push_withdrawal_request(withdrawal.clone())
+        .await
+        .unwrap();
+    mem_pool
+        .push_withdrawal_request(mismatch_withdrawal.clone())
+        .await
+        .unwrap();
+    mem_pool
+        .reset_mem_block(&LocalCellsManager::default())
+        .await
+        .unwrap();
+
+    let withdrawals = mem_pool.mem_block().withdrawals();
+    assert_eq!(withdrawals, &[withdrawal.hash()]);
+    let deferred = mem_pool.mem_pool_state().get_deferred_withdrawals();
+    assert!(!deferred.contains(&mismatch_withdrawal.hash()));
+    assert!(deferred.is_empty());
+}
@@ -0,0 +1,199 @@
+#![allow(clippy::mutable_key_type)]
+
+//! Property tests of mem pool invariants under random sequences of pushes,
+//! reorgs, mem block resets and packaging.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    fixtures::{always_success_accounts, ckb_deposits, ckb_transfer},
+};
+
+use gw_common::{registry_address::RegistryAddress, state::State};
+use gw_mem_pool::{mem_block::MemBlock, pool::OutputParam};
+use gw_store::{
+    state::{history::history_state::RWConfig, BlockStateDB},
+    traits::chain_store::ChainStore,
+};
+use gw_types::{
+    h256::*,
+    packed::{DepositInfoVec, Script},
+    prelude::*,
+    U256,
+};
+use proptest::prelude::*;
+
+const ACCOUNTS: usize = 4;
+const DEPOSIT_CAPACITY: u64 = 1000_00000000;
+
+#[derive(Debug, Clone)]
+enum Op {
+    /// Push a transfer with a nonce relative to the sender's mem pool nonce.
+    PushTx {
+        sender: usize,
+        nonce_offset: i64,
+    },
+    ProduceBlock,
+    /// Detach the tip block and notify the mem pool of its parent.
+    Reorg,
+    ResetMemBlock,
+    Package {
+        retry_count: usize,
+    },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (0..ACCOUNTS, -1i64..=1)
+            .prop_map(|(sender, nonce_offset)| Op::PushTx { sender, nonce_offset }),
+        1 => Just(Op::ProduceBlock),
+        1 => Just(Op::Reorg),
+        1 => Just(Op::ResetMemBlock),
+        1 => (0usize..3).prop_map(|retry_count| Op::Package { retry_count }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn test_mem_pool_invariants(ops in prop::collection::vec(op(), 1..32)) {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(run_ops(ops));
+    }
+}
+
+async fn run_ops(ops: Vec<Op>) {
+    let mut chain = TestChain::setup(Script::default()).await;
+    let accounts = always_success_accounts(&chain.rollup_type_hash(), ACCOUNTS);
+    let deposits = ckb_deposits(
+        chain.inner.generator().rollup_context(),
+        accounts.iter().map(|(script, _)| script.clone()),
+        DEPOSIT_CAPACITY,
+    );
+    let deposit_info_vec = DepositInfoVec::new_builder()
+        .extend(deposits.into_iter().map(|d| d.pack()))
+        .build();
+    chain.produce_block(deposit_info_vec, vec![]).await.unwrap();
+
+    let account_ids: Vec<u32> = {
+        let state = chain.mem_pool_state().await.load_state_db();
+        accounts
+            .iter()
+            .map(|(script, _)| {
+                state
+                    .get_account_id_by_script_hash(&script.hash())
+                    .unwrap()
+                    .expect("account id")
+            })
+            .collect()
+    };
+
+    for op in ops {
+        match op {
+            Op::PushTx {
+                sender,
+                nonce_offset,
+            } => {
+                let state = chain.mem_pool_state().await.load_state_db();
+                let from_id = account_ids[sender];
+                let nonce = state.get_nonce(from_id).unwrap() as i64 + nonce_offset;
+                if nonce < 0 {
+                    continue;
+                }
+                let to: &RegistryAddress = &accounts[(sender + 1) % ACCOUNTS].1;
+                let tx = ckb_transfer(chain.chain_id(), from_id, nonce as u32, to, U256::one());
+                // Txs with invalid nonces are rejected.
+                let _ = chain.mem_pool().await.push_transaction(tx);
+            }
+            Op::ProduceBlock => chain
+                .produce_block(Default::default(), vec![])
+                .await
+                .unwrap(),
+            Op::Reorg => {
+                let tip_number: u64 = chain.last_valid_block().raw().number().unpack();
+                // Keep the deposit block.
+                if tip_number > 1 {
+                    chain.revert_to(tip_number - 1).await.unwrap();
+                }
+            }
+            Op::ResetMemBlock => {
+                let mut mem_pool = chain.mem_pool().await;
+                mem_pool.reset_mem_block(&Default::default()).await.unwrap();
+            }
+            Op::Package { retry_count } => {
+                let mem_pool = chain.mem_pool().await;
+                let (packaged, _) = mem_pool.output_mem_block(&OutputParam::new(retry_count));
+                let txs = mem_pool.mem_block().txs();
+                assert!(packaged.txs().len() <= txs.len());
+                assert_eq!(packaged.txs(), &txs[..packaged.txs().len()]);
+                drop(mem_pool);
+                check_mem_block(&chain, &packaged);
+            }
+        }
+
+        let mem_pool = chain.mem_pool().await;
+        let mem_block = mem_pool.mem_block().clone();
+        drop(mem_pool);
+        check_mem_block(&chain, &mem_block);
+        check_pending_cleaned(&chain, &mem_block);
+    }
+}
+
+/// No duplicated tx hashes and no nonce regressions inside the mem block.
+fn check_mem_block(chain: &TestChain, mem_block: &MemBlock) {
+    assert_eq!(
+        mem_block.txs().len(),
+        mem_block.txs_set().len(),
+        "duplicated tx hashes in mem block"
+    );
+
+    let mut db = chain.store().begin_transaction();
+    let mut next_nonces: HashMap<u32, u32> = HashMap::new();
+    for tx_hash in mem_block.txs() {
+        let tx = db
+            .get_mem_pool_transaction(tx_hash)
+            .unwrap()
+            .expect("mem block tx in mem pool db");
+        let from_id: u32 = tx.raw().from_id().unpack();
+        let nonce: u32 = tx.raw().nonce().unpack();
+        let expected = match next_nonces.get(&from_id) {
+            Some(next) => *next,
+            None => {
+                let tree = BlockStateDB::from_store(&mut db, RWConfig::readonly()).unwrap();
+                tree.get_nonce(from_id).unwrap()
+            }
+        };
+        assert_eq!(nonce, expected, "nonce of account {} in mem block", from_id);
+        next_nonces.insert(from_id, nonce + 1);
+    }
+}
+
+/// Txs kept in the mem pool db are all in the mem block and none of them is
+/// already committed.
+fn check_pending_cleaned(chain: &TestChain, mem_block: &MemBlock) {
+    let db = chain.store().begin_transaction();
+    let pending: HashSet<H256> = db
+        .get_mem_pool_transaction_iter()
+        .map(|(tx_hash, _)| tx_hash)
+        .collect();
+    for tx_hash in pending.iter() {
+        assert!(
+            mem_block.txs_set().contains(tx_hash),
+            "tx {} in mem pool db but not in mem block",
+            tx_hash.pack()
+        );
+    }
+    for tx_hash in mem_block.txs() {
+        assert!(
+            db.get_transaction_info(tx_hash).unwrap().is_none(),
+            "committed tx {} still in mem block",
+            tx_hash.pack()
+        );
+    }
+}
@@ -1,12 +1,19 @@
 mod calc_finalizing_range;
+mod cancel_challenge;
 mod chain;
+mod deposit_filter;
 mod deposit_withdrawal;
 mod export_import_block;
 mod mem_block_repackage;
 mod mem_pool_ckb_transfer_create_new_recipient_account;
+mod mem_pool_deferred_withdrawal;
+mod mem_pool_invariants;
 mod meta_contract_args;
 mod polyjuice_sender_recover;
+mod replay_block;
 mod restore_mem_block;
 mod restore_mem_pool_pending_withdrawal;
 mod rpc_server;
+mod submit_tx_fee_bump;
 mod unlock_withdrawal_to_owner;
+mod watch_notifier;
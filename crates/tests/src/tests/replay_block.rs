@@ -0,0 +1,79 @@
+use crate::testing_tool::chain::{
+    build_sync_tx, construct_block, into_deposit_info_cell, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+};
+
+use gw_block_producer::replay_block::replay_range;
+use gw_chain::chain::{Chain, L1Action, L1ActionContext, SyncParam};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{CellOutput, DepositInfoVec, DepositRequest, Script},
+    prelude::*,
+};
+
+const CKB: u64 = 100000000;
+
+async fn sync_block(chain: &mut Chain, rollup_cell: CellOutput, deposit_info_vec: DepositInfoVec) {
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(chain, &mut mem_pool, deposit_info_vec.clone())
+            .await
+            .unwrap()
+    };
+    let update = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: block_result.block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: block_result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell, block_result),
+    };
+    let param = SyncParam {
+        updates: vec![update],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    chain.notify_new_tip().await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_replay_block_range() {
+    let rollup_type_script = Script::default();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+
+    for i in 0..3u8 {
+        let user_script = Script::new_builder()
+            .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args({
+                let mut args = rollup_type_script.hash().to_vec();
+                args.extend(&[i; 20]);
+                args.pack()
+            })
+            .build();
+        let deposit = DepositRequest::new_builder()
+            .capacity((1000u64 * CKB).pack())
+            .script(user_script)
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build();
+        let deposit_info_vec = DepositInfoVec::new_builder()
+            .push(into_deposit_info_cell(chain.generator().rollup_context(), deposit).pack())
+            .build();
+        sync_block(&mut chain, rollup_cell.clone(), deposit_info_vec).await;
+        sync_block(&mut chain, rollup_cell.clone(), Default::default()).await;
+    }
+
+    // Replay from the genesis child to the tip, and a range in the middle.
+    let divergence = replay_range(chain.store(), chain.generator(), 1, 6).unwrap();
+    assert!(divergence.is_none(), "{:?}", divergence);
+    let divergence = replay_range(chain.store(), chain.generator(), 3, 4).unwrap();
+    assert!(divergence.is_none(), "{:?}", divergence);
+
+    // Out of the last valid tip.
+    assert!(replay_range(chain.store(), chain.generator(), 1, 7).is_err());
+}
@@ -0,0 +1,48 @@
+use gw_types::{h256::*, packed::Script};
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+fn method_names(document: &serde_json::Value) -> Vec<String> {
+    let methods = document["methods"].as_array().unwrap();
+    { methods.iter() }
+        .map(|m| m["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_admin_methods() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let hash = H256::from_u32(1);
+
+    // Not served on `listen` without `auth_token`.
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    let err = rpc_server.evict_transaction(hash).await.unwrap_err();
+    assert!(err.to_string().contains("-32601"), "{}", err);
+    let methods = method_names(&rpc_server.discover().await.unwrap());
+    assert!(!methods.iter().any(|m| m.starts_with("admin_")));
+
+    // Served on `listen` with `auth_token`, the token is checked by the HTTP
+    // server.
+    let mut args = RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+    args.server_config.admin.auth_token = Some("secret".to_string());
+    let rpc_server = RPCServer::build_from_registry_args(args).await.unwrap();
+    assert!(!rpc_server.evict_transaction(hash).await.unwrap());
+    let methods = method_names(&rpc_server.discover().await.unwrap());
+    assert!(methods.contains(&"admin_evict_transaction".to_string()));
+    assert!(methods.contains(&"gw_get_block".to_string()));
+
+    // The `admin.listen` server serves admin methods only.
+    let args = RPCServer::default_registry_args(&chain.inner, rollup_type_script, None);
+    let admin_server = RPCServer::build_admin_from_registry_args(args)
+        .await
+        .unwrap();
+    assert!(!admin_server.evict_transaction(hash).await.unwrap());
+    let methods = method_names(&admin_server.discover().await.unwrap());
+    assert!(methods.contains(&"admin_reload_config".to_string()));
+    assert!(methods
+        .iter()
+        .all(|m| m.starts_with("admin_") || m == "rpc.discover"));
+}
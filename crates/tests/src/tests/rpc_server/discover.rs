@@ -0,0 +1,35 @@
+use gw_types::packed::Script;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_rpc_discover() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    let document = rpc_server.discover().await.unwrap();
+    assert_eq!(document["openrpc"], "1.2.6");
+
+    let methods = document["methods"].as_array().unwrap();
+    let get_method = |name: &str| {
+        { methods.iter() }
+            .find(|m| m["name"] == name)
+            .unwrap_or_else(|| panic!("method {} not found", name))
+    };
+    get_method("rpc.discover");
+    get_method("gw_submit_l2transaction");
+    let get_block = get_method("gw_get_block");
+    assert_eq!(get_block["params"][0]["name"], "block_hash");
+
+    // All referenced schemas are defined.
+    let schemas = document["components"]["schemas"].as_object().unwrap();
+    let document = document.to_string();
+    for reference in document.split("\"$ref\":\"#/components/schemas/").skip(1) {
+        let name = reference.split('"').next().unwrap();
+        assert!(schemas.contains_key(name), "schema {} not defined", name);
+    }
+    assert!(schemas.contains_key("L2BlockWithStatus"));
+}
@@ -0,0 +1,144 @@
+#![allow(clippy::mutable_key_type)]
+
+use std::collections::HashSet;
+
+use gw_block_producer::block_producer::{bump_fee_rate, pay_fee_from_change};
+use gw_config::PscConfig;
+use gw_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    h256::*,
+    offchain::{CellInfo, InputCellInfo},
+    packed::{CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use gw_utils::{
+    fee::{calculate_required_tx_fee, CHANGE_CELL_CAPACITY},
+    transaction_skeleton::TransactionSkeleton,
+};
+
+const INPUT_CAPACITY: u64 = 1000_00000000;
+
+fn wallet_lock() -> Script {
+    Script::new_builder()
+        .code_hash([1u8; 32].pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![2u8; 20].pack())
+        .build()
+}
+
+/// A tx spending one wallet cell of `input_capacity`, paying `fee` and sending
+/// the rest back to the wallet as change.
+fn build_tx_skeleton(input_capacity: u64, fee: u64) -> TransactionSkeleton {
+    let mut tx_skeleton = TransactionSkeleton::new([3u8; 32]);
+    let out_point = OutPoint::new_builder().tx_hash([4u8; 32].pack()).build();
+    let cell = CellInfo {
+        out_point: out_point.clone(),
+        output: CellOutput::new_builder()
+            .capacity(input_capacity.pack())
+            .lock(wallet_lock())
+            .build(),
+        data: Default::default(),
+    };
+    let input = CellInput::new_builder().previous_output(out_point).build();
+    tx_skeleton.inputs_mut().push(InputCellInfo { input, cell });
+    let change = CellOutput::new_builder()
+        .capacity((input_capacity - fee).pack())
+        .lock(wallet_lock())
+        .build();
+    tx_skeleton.outputs_mut().push((change, Default::default()));
+    tx_skeleton
+}
+
+fn required_fee(fee_rate: u64) -> u64 {
+    let tx_size = build_tx_skeleton(INPUT_CAPACITY, 0)
+        .tx_in_block_size()
+        .unwrap();
+    calculate_required_tx_fee(tx_size, fee_rate)
+}
+
+fn wallet_locks() -> HashSet<H256> {
+    std::iter::once(wallet_lock().hash()).collect()
+}
+
+fn inputs(tx_skeleton: &TransactionSkeleton) -> Vec<Bytes> {
+    { tx_skeleton.inputs().iter() }
+        .map(|i| i.input.as_bytes())
+        .collect()
+}
+
+fn outputs(tx_skeleton: &TransactionSkeleton) -> Vec<Bytes> {
+    { tx_skeleton.outputs().iter() }
+        .map(|(output, _)| output.as_bytes())
+        .collect()
+}
+
+#[test]
+fn test_bump_fee_rate() {
+    let config = PscConfig {
+        fee_bump_percent: 150,
+        max_fee_rate: 10000,
+        ..Default::default()
+    };
+    assert_eq!(bump_fee_rate(&config, 1000), 1500);
+    // Always goes up, even if the percentage rounds down to the same rate.
+    assert_eq!(bump_fee_rate(&config, 1), 2);
+    // Capped at the maximum fee rate.
+    assert_eq!(bump_fee_rate(&config, 8000), 10000);
+    assert_eq!(bump_fee_rate(&config, 10000), 10000);
+
+    let config = PscConfig {
+        fee_bump_percent: 100,
+        ..config
+    };
+    assert_eq!(bump_fee_rate(&config, 1000), 1001);
+}
+
+#[test]
+fn test_pay_fee_from_change_with_higher_fee_rate() {
+    let old_fee = required_fee(1000);
+    let mut tx_skeleton = build_tx_skeleton(INPUT_CAPACITY, old_fee);
+    let old_inputs = inputs(&tx_skeleton);
+
+    pay_fee_from_change(&mut tx_skeleton, 1500, &wallet_locks()).unwrap();
+
+    // The replacement spends the same inputs, so only one of them can be
+    // committed.
+    assert_eq!(inputs(&tx_skeleton), old_inputs);
+    let new_fee = required_fee(1500);
+    assert!(new_fee > old_fee);
+    assert_eq!(tx_skeleton.calculate_fee().unwrap(), new_fee);
+    let (change, _) = tx_skeleton.outputs().last().unwrap();
+    let change_capacity: u64 = change.capacity().unpack();
+    assert_eq!(change_capacity, INPUT_CAPACITY - new_fee);
+}
+
+#[test]
+fn test_pay_fee_from_change_with_lower_fee_rate() {
+    let fee = required_fee(1000);
+    let mut tx_skeleton = build_tx_skeleton(INPUT_CAPACITY, fee);
+    let old_outputs = outputs(&tx_skeleton);
+
+    // A lower fee rate doesn't lower the paid fee.
+    pay_fee_from_change(&mut tx_skeleton, 500, &wallet_locks()).unwrap();
+    assert_eq!(outputs(&tx_skeleton), old_outputs);
+    assert_eq!(tx_skeleton.calculate_fee().unwrap(), fee);
+
+    pay_fee_from_change(&mut tx_skeleton, 1000, &wallet_locks()).unwrap();
+    assert_eq!(outputs(&tx_skeleton), old_outputs);
+}
+
+#[test]
+fn test_pay_fee_from_change_without_change() {
+    let fee = required_fee(1000);
+
+    // Change is not locked by the wallet.
+    let mut tx_skeleton = build_tx_skeleton(INPUT_CAPACITY, fee);
+    let err = pay_fee_from_change(&mut tx_skeleton, 1500, &HashSet::new()).unwrap_err();
+    assert_eq!(err.to_string(), "no change output");
+
+    // Change can't go below the minimal change capacity.
+    let mut tx_skeleton = build_tx_skeleton(CHANGE_CELL_CAPACITY + fee, fee);
+    let err = pay_fee_from_change(&mut tx_skeleton, 1500, &wallet_locks()).unwrap_err();
+    assert_eq!(err.to_string(), "insufficient change capacity to pay fee");
+}
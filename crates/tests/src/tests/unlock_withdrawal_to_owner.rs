@@ -357,6 +357,7 @@ async fn test_build_unlock_to_owner_tx() {
         &withdrawal_block_result.block,
         &contracts_dep,
         &withdrawal_extras.collect(),
+        usize::MAX,
     )
     .expect("generate")
     .expect("some withdrawals cell");
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use crate::testing_tool::chain::{
+    build_sync_tx, construct_block, into_deposit_info_cell, setup_chain, ALWAYS_SUCCESS_CODE_HASH,
+};
+
+use gw_block_producer::watch_notifier::{block_events, WatchEvent};
+use gw_chain::chain::{L1Action, L1ActionContext, SyncParam};
+use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{CellOutput, DepositInfoVec, DepositRequest, Script},
+    prelude::*,
+};
+
+const CKB: u64 = 100000000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_watch_list_deposit_events() {
+    let rollup_type_script = Script::default();
+    let mut chain = setup_chain(rollup_type_script.clone()).await;
+    let rollup_cell = CellOutput::new_builder()
+        .type_(Some(rollup_type_script.clone()).pack())
+        .build();
+
+    let deposit_info_vec = {
+        let ctx = chain.generator().rollup_context();
+        let deposits = [1u8, 2].iter().map(|i| {
+            let user_script = Script::new_builder()
+                .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+                .hash_type(ScriptHashType::Type.into())
+                .args({
+                    let mut args = rollup_type_script.hash().to_vec();
+                    args.extend(&[*i; 20]);
+                    args.pack()
+                })
+                .build();
+            let deposit = DepositRequest::new_builder()
+                .capacity((1000u64 * CKB).pack())
+                .script(user_script)
+                .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+                .build();
+            into_deposit_info_cell(ctx, deposit).pack()
+        });
+        DepositInfoVec::new_builder().extend(deposits).build()
+    };
+    let block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(&chain, &mut mem_pool, deposit_info_vec.clone())
+            .await
+            .unwrap()
+    };
+    let block = block_result.block.clone();
+    let update = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: block.clone(),
+            deposit_info_vec,
+            deposit_asset_scripts: Default::default(),
+            withdrawals: block_result.withdrawal_extras.clone(),
+        },
+        transaction: build_sync_tx(rollup_cell, block_result),
+    };
+    let param = SyncParam {
+        updates: vec![update],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+
+    let watched = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![1u8; 20]);
+    let addresses = HashSet::from([watched.clone()]);
+    let events = block_events(
+        chain.store(),
+        chain.generator().rollup_context(),
+        &addresses,
+        &block,
+    )
+    .unwrap();
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        WatchEvent::Deposit {
+            address, capacity, ..
+        } => {
+            let address: RegistryAddress = address.clone().into();
+            assert_eq!(address, watched);
+            assert_eq!(capacity.value(), 1000 * CKB);
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+}
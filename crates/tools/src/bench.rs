@@ -0,0 +1,501 @@
+//! Load test of a godwoken node by synthetic workloads, for capacity planning
+//! and performance regression benchmarks.
+//!
+//! Every sender submits its requests with increasing nonces through the RPC,
+//! senders run concurrently. The mem pool of a node is only reachable through
+//! its RPC, so requests are never pushed into a mem pool directly.
+//!
+//! Submit latency is the round trip of the submit RPC, commit latency is the
+//! time from submission until the request is committed to an L2 block.
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, ensure, Result};
+use ckb_fixed_hash::H256;
+use ckb_jsonrpc_types::JsonBytes;
+use ckb_types::{prelude::Builder as CKBBuilder, prelude::Entity as CKBEntity};
+use clap::arg_enum;
+use gw_common::builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID};
+use gw_common::registry_address::RegistryAddress;
+use gw_jsonrpc_types::godwoken::{L2TransactionStatus, WithdrawalStatus};
+use gw_types::bytes::Bytes as GwBytes;
+use gw_types::packed::{
+    Fee, L2Transaction, RawL2Transaction, SUDTArgs, SUDTTransfer, Script, WithdrawalRequest,
+    WithdrawalRequestExtra,
+};
+use gw_types::prelude::Pack as GwPack;
+use gw_types::U256;
+use rand::Rng;
+
+use crate::account::{eth_sign, privkey_to_l2_script_hash, read_privkey};
+use crate::godwoken_rpc::GodwokenRpcClient;
+use crate::hasher::CkbHasher;
+use crate::polyjuice::encode_polyjuice_args;
+use crate::types::ScriptsDeploymentResult;
+use crate::utils::message::{
+    generate_eip712_message_to_sign, generate_transaction_message_to_sign,
+};
+use crate::utils::sdk::{Address, HumanCapacity};
+use crate::utils::transaction::read_config;
+use crate::withdraw::{
+    create_raw_withdrawal_request, generate_withdrawal_message_to_sign,
+    minimal_withdrawal_capacity, parse_capacity,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Upper bounds of latency histogram buckets in milliseconds.
+const HISTOGRAM_BUCKETS_MS: [u64; 10] = [10, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000];
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy)]
+    pub enum WorkloadKind {
+        Transfer,
+        Polyjuice,
+        Withdrawal,
+    }
+}
+
+pub struct BenchArgs<'a> {
+    pub godwoken_rpc_url: &'a str,
+    pub config_path: &'a Path,
+    pub scripts_deployment_path: &'a Path,
+    pub privkey_paths: Vec<&'a Path>,
+    pub workload: WorkloadKind,
+    /// Requests per sender.
+    pub count: u32,
+    /// Fee of transfers and withdrawals in shannons.
+    pub fee: u128,
+    /// Receiver of transfers, or the contract called by polyjuice txs.
+    pub to_address: Option<&'a str>,
+    /// CKB amount of transfers in shannons.
+    pub amount: &'a str,
+    pub creator_account_id: u32,
+    pub gas_limit: u64,
+    pub gas_price: u128,
+    /// Calldata of polyjuice txs, padded with random bytes to `calldata_size`.
+    pub calldata: &'a str,
+    pub calldata_size: usize,
+    pub owner_ckb_address: Option<&'a str>,
+    /// CKB capacity of withdrawals, e.g. "400".
+    pub capacity: &'a str,
+    /// How long to wait for submitted requests to be committed.
+    pub timeout: Duration,
+}
+
+enum Workload {
+    Transfer {
+        args: GwBytes,
+        receiver_script_hash: H256,
+    },
+    Polyjuice {
+        to_id: u32,
+        args: GwBytes,
+        receiver_script_hash: H256,
+    },
+    Withdrawal {
+        owner_lock: Script,
+        owner_lock_hash: H256,
+        capacity: u64,
+        fee: u128,
+    },
+}
+
+enum Request {
+    Tx(L2Transaction),
+    Withdrawal(WithdrawalRequestExtra),
+}
+
+struct BenchContext {
+    rollup_type_hash: H256,
+    chain_id: u64,
+    workload: Workload,
+}
+
+struct Sender {
+    privkey: H256,
+    from_id: u32,
+    script_hash: H256,
+    address: RegistryAddress,
+    nonce: u32,
+}
+
+struct Pending {
+    hash: H256,
+    is_withdrawal: bool,
+    submitted_at: Instant,
+}
+
+#[derive(Default)]
+struct SenderStats {
+    submitted: Vec<Duration>,
+    committed: Vec<Duration>,
+    failed: usize,
+    timed_out: usize,
+    last_committed_at: Option<Instant>,
+}
+
+pub async fn bench(args: BenchArgs<'_>) -> Result<()> {
+    ensure!(!args.privkey_paths.is_empty(), "no senders");
+    let scripts_deployment_content = std::fs::read_to_string(args.scripts_deployment_path)?;
+    let scripts_deployment: ScriptsDeploymentResult =
+        serde_json::from_str(&scripts_deployment_content)?;
+    let config = read_config(args.config_path)?;
+    let rollup_type_hash = config.genesis.rollup_type_hash.clone();
+    let chain_id: u64 = config.genesis.rollup_config.chain_id.into();
+    let mut client = GodwokenRpcClient::new(args.godwoken_rpc_url);
+
+    let workload = build_workload(&mut client, &args).await?;
+    let mut senders = Vec::with_capacity(args.privkey_paths.len());
+    for privkey_path in args.privkey_paths.iter() {
+        let privkey = read_privkey(privkey_path)?;
+        let script_hash =
+            privkey_to_l2_script_hash(&privkey, &rollup_type_hash, &scripts_deployment)?;
+        let from_id = client
+            .get_account_id_by_script_hash(script_hash.clone())
+            .await?
+            .ok_or_else(|| anyhow!("account of {} not found", privkey_path.display()))?;
+        let address = client
+            .get_registry_address_by_script_hash(&script_hash)
+            .await?
+            .ok_or_else(|| anyhow!("registry address is not found"))?;
+        let nonce = client.get_nonce(from_id).await?;
+        senders.push(Sender {
+            privkey,
+            from_id,
+            script_hash,
+            address,
+            nonce,
+        });
+    }
+    let ctx = Arc::new(BenchContext {
+        rollup_type_hash,
+        chain_id,
+        workload,
+    });
+
+    log::info!(
+        "bench {} with {} senders, {} requests each",
+        args.workload,
+        senders.len(),
+        args.count
+    );
+    let started_at = Instant::now();
+    let handles: Vec<_> = { senders.into_iter() }
+        .map(|sender| {
+            let client = client.clone();
+            let ctx = Arc::clone(&ctx);
+            let (count, timeout) = (args.count, args.timeout);
+            tokio::spawn(async move { run_sender(client, ctx, sender, count, timeout).await })
+        })
+        .collect();
+    let mut stats = SenderStats::default();
+    for handle in handles {
+        let sender_stats = handle.await??;
+        stats.submitted.extend(sender_stats.submitted);
+        stats.committed.extend(sender_stats.committed);
+        stats.failed += sender_stats.failed;
+        stats.timed_out += sender_stats.timed_out;
+        stats.last_committed_at = stats.last_committed_at.max(sender_stats.last_committed_at);
+    }
+
+    print_report(args.workload, started_at, stats);
+    Ok(())
+}
+
+async fn build_workload(client: &mut GodwokenRpcClient, args: &BenchArgs<'_>) -> Result<Workload> {
+    let to_address = || -> Result<Vec<u8>> {
+        let to_address = args
+            .to_address
+            .ok_or_else(|| anyhow!("{} workload requires to address", args.workload))?;
+        let to_address = hex::decode(to_address.trim_start_matches("0x"))?;
+        ensure!(to_address.len() == 20, "invalid to address");
+        Ok(to_address)
+    };
+    let workload = match args.workload {
+        WorkloadKind::Transfer => {
+            let amount: U256 = args.amount.parse().expect("amount format error");
+            let sudt_transfer = SUDTTransfer::new_builder()
+                .to_address(GwPack::pack(&GwBytes::from(
+                    RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, to_address()?).to_bytes(),
+                )))
+                .amount(GwPack::pack(&amount))
+                .fee(
+                    Fee::new_builder()
+                        .registry_id(GwPack::pack(&ETH_REGISTRY_ACCOUNT_ID))
+                        .amount(GwPack::pack(&args.fee))
+                        .build(),
+                )
+                .build();
+            let args = SUDTArgs::new_builder().set(sudt_transfer).build();
+            Workload::Transfer {
+                args: args.as_bytes(),
+                receiver_script_hash: client.get_script_hash(CKB_SUDT_ACCOUNT_ID).await?,
+            }
+        }
+        WorkloadKind::Polyjuice => {
+            let address = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, to_address()?);
+            let script_hash = client.get_script_hash_by_registry_address(&address).await?;
+            let to_id = client
+                .get_account_id_by_script_hash(script_hash.clone())
+                .await?
+                .ok_or_else(|| anyhow!("contract not found"))?;
+            let mut calldata = hex::decode(args.calldata.trim_start_matches("0x"))?;
+            if calldata.len() < args.calldata_size {
+                let mut rng = rand::thread_rng();
+                calldata.resize_with(args.calldata_size, || rng.gen());
+            }
+            let polyjuice_args = encode_polyjuice_args(
+                args.gas_limit,
+                args.gas_price,
+                0,
+                calldata.into(),
+                to_id,
+                args.creator_account_id,
+            );
+            Workload::Polyjuice {
+                to_id,
+                args: polyjuice_args,
+                receiver_script_hash: script_hash,
+            }
+        }
+        WorkloadKind::Withdrawal => {
+            let owner_ckb_address = args
+                .owner_ckb_address
+                .ok_or_else(|| anyhow!("withdrawal workload requires owner ckb address"))?;
+            let capacity = parse_capacity(args.capacity)?;
+            let minimal_capacity = minimal_withdrawal_capacity(false)?;
+            ensure!(
+                capacity >= minimal_capacity,
+                "Withdrawal required {} CKB at least, provided {}.",
+                HumanCapacity::from(minimal_capacity),
+                HumanCapacity::from(capacity)
+            );
+            let owner_lock_script = {
+                let address = Address::from_str(owner_ckb_address).map_err(|err| anyhow!(err))?;
+                ckb_types::packed::Script::from(address.payload())
+            };
+            let owner_lock_hash: H256 = CkbHasher::new()
+                .update(owner_lock_script.as_slice())
+                .finalize();
+            Workload::Withdrawal {
+                owner_lock: Script::new_unchecked(owner_lock_script.as_bytes()),
+                owner_lock_hash,
+                capacity,
+                fee: args.fee,
+            }
+        }
+    };
+    Ok(workload)
+}
+
+impl BenchContext {
+    fn build_request(&self, sender: &Sender, nonce: u32) -> Result<Request> {
+        let (to_id, args, receiver_script_hash) = match &self.workload {
+            Workload::Transfer {
+                args,
+                receiver_script_hash,
+            } => (CKB_SUDT_ACCOUNT_ID, args, receiver_script_hash),
+            Workload::Polyjuice {
+                to_id,
+                args,
+                receiver_script_hash,
+            } => (*to_id, args, receiver_script_hash),
+            Workload::Withdrawal {
+                owner_lock,
+                owner_lock_hash,
+                capacity,
+                fee,
+            } => {
+                let raw = create_raw_withdrawal_request(
+                    nonce,
+                    *capacity,
+                    0,
+                    *fee,
+                    self.chain_id,
+                    &H256([0u8; 32]),
+                    &sender.script_hash,
+                    owner_lock_hash,
+                )?;
+                let message = generate_withdrawal_message_to_sign(
+                    raw.clone(),
+                    owner_lock.clone(),
+                    sender.address.clone(),
+                    self.chain_id,
+                )?;
+                let signature = eth_sign(&message, sender.privkey.clone())?;
+                let request = WithdrawalRequest::new_builder()
+                    .raw(raw)
+                    .signature(signature.pack())
+                    .build();
+                let extra = WithdrawalRequestExtra::new_builder()
+                    .request(request)
+                    .owner_lock(owner_lock.clone())
+                    .build();
+                return Ok(Request::Withdrawal(extra));
+            }
+        };
+
+        let raw = RawL2Transaction::new_builder()
+            .from_id(GwPack::pack(&sender.from_id))
+            .to_id(GwPack::pack(&to_id))
+            .nonce(GwPack::pack(&nonce))
+            .args(GwPack::pack(args))
+            .chain_id(self.chain_id.pack())
+            .build();
+        let message = match self.workload {
+            Workload::Polyjuice { .. } => {
+                let sender_script_hash = &sender.script_hash;
+                generate_transaction_message_to_sign(
+                    &raw,
+                    &self.rollup_type_hash,
+                    sender_script_hash,
+                    receiver_script_hash,
+                )
+            }
+            _ => generate_eip712_message_to_sign(
+                raw.clone(),
+                sender.address.clone(),
+                receiver_script_hash.0,
+            ),
+        };
+        let signature = eth_sign(&message, sender.privkey.clone())?;
+        let tx = L2Transaction::new_builder()
+            .raw(raw)
+            .signature(signature.pack())
+            .build();
+        Ok(Request::Tx(tx))
+    }
+}
+
+async fn run_sender(
+    mut client: GodwokenRpcClient,
+    ctx: Arc<BenchContext>,
+    sender: Sender,
+    count: u32,
+    timeout: Duration,
+) -> Result<SenderStats> {
+    let mut stats = SenderStats::default();
+    let mut pending = Vec::with_capacity(count as usize);
+    // Nonces of failed requests are reused.
+    let mut nonce = sender.nonce;
+    for _ in 0..count {
+        let request = ctx.build_request(&sender, nonce)?;
+        let submitted_at = Instant::now();
+        let submitted = match request {
+            Request::Tx(tx) => {
+                let bytes = JsonBytes::from_bytes(tx.as_bytes());
+                client.submit_l2transaction(bytes).await
+            }
+            Request::Withdrawal(withdrawal) => {
+                let bytes = JsonBytes::from_bytes(withdrawal.as_bytes());
+                client.submit_withdrawal_request(bytes).await
+            }
+        };
+        match submitted {
+            Ok(hash) => {
+                stats.submitted.push(submitted_at.elapsed());
+                pending.push(Pending {
+                    hash,
+                    is_withdrawal: matches!(ctx.workload, Workload::Withdrawal { .. }),
+                    submitted_at,
+                });
+                nonce += 1;
+            }
+            Err(err) => {
+                log::warn!("account {} submit error: {}", sender.from_id, err);
+                stats.failed += 1;
+            }
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    while !pending.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let mut still_pending = Vec::with_capacity(pending.len());
+        for p in pending {
+            match is_committed(&client, &p).await {
+                Ok(true) => {
+                    stats.committed.push(p.submitted_at.elapsed());
+                    stats.last_committed_at = Some(Instant::now());
+                }
+                Ok(false) => still_pending.push(p),
+                Err(err) => {
+                    log::warn!("query {:#x} error: {}", p.hash, err);
+                    still_pending.push(p);
+                }
+            }
+        }
+        pending = still_pending;
+    }
+    stats.timed_out = pending.len();
+    Ok(stats)
+}
+
+async fn is_committed(client: &GodwokenRpcClient, pending: &Pending) -> Result<bool> {
+    let committed = if pending.is_withdrawal {
+        let withdrawal = client.get_withdrawal(&pending.hash).await?;
+        withdrawal.map_or(false, |w| w.status == WithdrawalStatus::Committed)
+    } else {
+        let tx = client.get_transaction(&pending.hash).await?;
+        tx.map_or(false, |tx| tx.status == L2TransactionStatus::Committed)
+    };
+    Ok(committed)
+}
+
+fn print_report(workload: WorkloadKind, started_at: Instant, mut stats: SenderStats) {
+    let elapsed = started_at.elapsed();
+    let throughput = |n: usize, elapsed: Duration| n as f64 / elapsed.as_secs_f64().max(0.001);
+    println!("Workload: {}", workload);
+    println!(
+        "Submitted: {}, failed: {}, committed: {}, timed out: {}",
+        stats.submitted.len(),
+        stats.failed,
+        stats.committed.len(),
+        stats.timed_out
+    );
+    println!("Elapsed: {:.2}s", elapsed.as_secs_f64());
+    if let Some(last_committed_at) = stats.last_committed_at {
+        println!(
+            "Commit throughput: {:.2} req/s",
+            throughput(stats.committed.len(), last_committed_at - started_at)
+        );
+    }
+    print_latencies("Submit latency", &mut stats.submitted);
+    print_latencies("Commit latency", &mut stats.committed);
+}
+
+fn print_latencies(name: &str, latencies: &mut Vec<Duration>) {
+    if latencies.is_empty() {
+        return;
+    }
+    latencies.sort_unstable();
+    let percentile = |p: f64| {
+        let index = ((latencies.len() - 1) as f64 * p).round() as usize;
+        latencies[index].as_millis()
+    };
+    println!("========================================");
+    println!(
+        "{}: p50 {}ms, p90 {}ms, p99 {}ms, max {}ms",
+        name,
+        percentile(0.5),
+        percentile(0.9),
+        percentile(0.99),
+        percentile(1.0)
+    );
+    let mut lower = 0;
+    for upper in HISTOGRAM_BUCKETS_MS {
+        let count = { latencies.iter() }
+            .filter(|l| (lower..upper).contains(&(l.as_millis() as u64)))
+            .count();
+        println!("  {:>6}ms - {:>6}ms: {}", lower, upper, count);
+        lower = upper;
+    }
+    let count = { latencies.iter() }
+        .filter(|l| l.as_millis() as u64 >= lower)
+        .count();
+    println!("  {:>6}ms -        : {}", lower, count);
+}
@@ -135,11 +135,13 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
             let lock = gw_types::packed::Script::new_unchecked(lock.as_bytes());
             lock.into()
         },
+        ..Default::default()
     };
 
     let wallet_config: WalletConfig = WalletConfig {
         privkey_path: privkey_path.into(),
         lock,
+        signer: Default::default(),
     };
 
     let backends: Vec<BackendConfig> = vec![
@@ -151,6 +153,9 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
                 .script_type_hash
                 .clone(),
             backend_type: gw_config::BackendType::Meta,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
         BackendConfig {
             validator_path: build_scripts_result.built_scripts["l2_sudt_validator"].clone(),
@@ -160,6 +165,9 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
                 .script_type_hash
                 .clone(),
             backend_type: gw_config::BackendType::Sudt,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
         BackendConfig {
             validator_path: build_scripts_result.built_scripts["polyjuice_validator"].clone(),
@@ -169,6 +177,9 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
                 .script_type_hash
                 .clone(),
             backend_type: gw_config::BackendType::Polyjuice,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
         BackendConfig {
             validator_path: build_scripts_result.built_scripts["eth_addr_reg_validator"].clone(),
@@ -178,6 +189,9 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
                 .script_type_hash
                 .clone(),
             backend_type: gw_config::BackendType::EthAddrReg,
+            validator_code_hash: None,
+            generator_code_hash: None,
+            cycles: Default::default(),
         },
     ];
     let backend_forks = vec![BackendForkConfig {
@@ -193,6 +207,7 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
         backend_forks,
         increase_max_l2_tx_cycles_to_500m: None,
         upgrade_global_state_version_to_v2: Some(0),
+        fork_heights: Default::default(),
     };
 
     let store = StoreConfig {
@@ -213,6 +228,7 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
     let rpc_client: RPCClientConfig = RPCClientConfig {
         indexer_url,
         ckb_url,
+        ..Default::default()
     };
     let rpc_server = RPCServerConfig {
         listen: server_url,
@@ -5,7 +5,7 @@ use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAdd
 use gw_jsonrpc_types::{
     ckb_jsonrpc_types::{JsonBytes, Uint32},
     debugger::{DumpChallengeTarget, ReprMockTransaction},
-    godwoken::{RunResult, TxReceipt},
+    godwoken::{L2TransactionWithStatus, RunResult, TxReceipt, WithdrawalWithStatus},
 };
 use gw_types::U256;
 use std::{
@@ -153,6 +153,21 @@ impl GodwokenRpcClient {
             .map(|opt| opt.map(Into::into))
     }
 
+    pub async fn get_transaction(&self, tx_hash: &H256) -> Result<Option<L2TransactionWithStatus>> {
+        let params = serde_json::to_value((tx_hash,))?;
+        self.rpc::<Option<L2TransactionWithStatus>>("get_transaction", params)
+            .await
+    }
+
+    pub async fn get_withdrawal(
+        &self,
+        withdrawal_hash: &H256,
+    ) -> Result<Option<WithdrawalWithStatus>> {
+        let params = serde_json::to_value((withdrawal_hash,))?;
+        self.rpc::<Option<WithdrawalWithStatus>>("get_withdrawal", params)
+            .await
+    }
+
     pub async fn debug_dump_cancel_challenge_tx(
         &self,
         challenge_target: DumpChallengeTarget,
@@ -2,6 +2,7 @@
 
 mod account;
 mod address;
+mod bench;
 mod create_creator_account;
 mod deploy_genesis;
 mod deploy_scripts;
@@ -24,10 +25,9 @@ mod withdraw;
 
 pub use crate::utils::sdk;
 
-use crate::utils::sdk::constants::ONE_CKB;
 use account::read_privkey;
 use anyhow::{anyhow, Result};
-use ckb_types::prelude::Unpack;
+use bench::BenchArgs;
 use clap::{value_t, App, Arg, SubCommand};
 use deploy_genesis::DeployRollupCellArgs;
 use dump_tx::ChallengeBlock;
@@ -37,10 +37,12 @@ use gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID;
 use gw_jsonrpc_types::godwoken::ChallengeTargetType;
 use gw_rpc_client::indexer_client::CKBIndexerClient;
 use gw_types::offchain::CompatibleFinalizedTimepoint;
+use gw_types::prelude::Unpack;
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 use tracing_subscriber::prelude::*;
 use types::{
@@ -852,6 +854,56 @@ async fn main() -> Result<()> {
                         .default_value("16800")
                         .help("The number of blocks to finalize the layer2 state"),
                 )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("stat-withdrawal-cells")
+                .about("Output stat of layer2 withdrawal cells")
+                .arg(arg_indexer_rpc.clone().required(true))
+                .arg(arg_config_path.clone())
+                .arg(
+                    Arg::with_name("top-owners")
+                        .long("top-owners")
+                        .takes_value(true)
+                        .default_value("20")
+                        .help("The number of owners with the most cells to output"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stat-stake-cells")
+                .about("Output stat of layer2 stake cells")
+                .arg(arg_indexer_rpc.clone().required(true))
+                .arg(arg_config_path.clone())
+                .arg(
+                    Arg::with_name("top-owners")
+                        .long("top-owners")
+                        .takes_value(true)
+                        .default_value("20")
+                        .help("The number of owners with the most cells to output"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("parse-withdrawal-lock-args")
@@ -863,6 +915,120 @@ async fn main() -> Result<()> {
                         .required(true)
                         .help("input file"),
                 ))
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Load test a godwoken node with synthetic transfers, polyjuice calls or withdrawals")
+                .arg(arg_godwoken_rpc_url.clone())
+                .arg(arg_config_path.clone())
+                .arg(arg_deployment_results_path.clone())
+                .arg(
+                    Arg::with_name("privkey-path")
+                        .long("privkey-path")
+                        .short('k')
+                        .takes_value(true)
+                        .required(true)
+                        .multiple_occurrences(true)
+                        .help("Private key files of senders, senders submit requests concurrently"),
+                )
+                .arg(
+                    Arg::with_name("workload")
+                        .long("workload")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(&["transfer", "polyjuice", "withdrawal"])
+                        .help("Requests to submit"),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .short('n')
+                        .long("count")
+                        .takes_value(true)
+                        .default_value("100")
+                        .help("Requests per sender"),
+                )
+                .arg(
+                    Arg::with_name("to-address")
+                        .short('t')
+                        .long("to-address")
+                        .takes_value(true)
+                        .help("Eth address of the transfer receiver or the called contract"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .short('m')
+                        .long("amount")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("CKB to transfer in shannon"),
+                )
+                .arg(
+                    Arg::with_name("fee")
+                        .short('f')
+                        .long("fee")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Fee of transfers and withdrawals in shannon"),
+                )
+                .arg(
+                    Arg::with_name("creator-account-id")
+                        .short('c')
+                        .long("creator-account-id")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Polyjuice creator account id"),
+                )
+                .arg(
+                    Arg::with_name("gas-limit")
+                        .short('l')
+                        .long("gas-limit")
+                        .takes_value(true)
+                        .default_value("100000")
+                        .help("Gas limit of polyjuice calls"),
+                )
+                .arg(
+                    Arg::with_name("gas-price")
+                        .short('p')
+                        .long("gas-price")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Gas price of polyjuice calls"),
+                )
+                .arg(
+                    Arg::with_name("calldata")
+                        .long("calldata")
+                        .takes_value(true)
+                        .default_value("0x")
+                        .help("Calldata of polyjuice calls, e.g. a function selector"),
+                )
+                .arg(
+                    Arg::with_name("calldata-size")
+                        .long("calldata-size")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Pad calldata with random bytes to this size"),
+                )
+                .arg(
+                    Arg::with_name("owner-ckb-address")
+                        .short('a')
+                        .long("owner-ckb-address")
+                        .takes_value(true)
+                        .help("Owner ckb address of withdrawals"),
+                )
+                .arg(
+                    Arg::with_name("capacity")
+                        .long("capacity")
+                        .takes_value(true)
+                        .default_value("400")
+                        .help("CKB capacity of withdrawals"),
+                )
+                .arg(
+                    Arg::with_name("timeout")
+                        .long("timeout")
+                        .takes_value(true)
+                        .default_value("300")
+                        .help("Seconds to wait for requests to be committed"),
+                ),
+        )
         ;
 
     let matches = app.clone().get_matches();
@@ -1498,34 +1664,47 @@ async fn main() -> Result<()> {
             )
             .await?;
 
-            let ckb = stat.total_capacity / ONE_CKB as u128;
-            let shannon = stat.total_capacity - (ckb * ONE_CKB as u128);
-            println!("Cells count: {}", stat.cells_count);
-            println!("Total custodian: {}.{:0>8} CKB", ckb, shannon);
-            let finalized_ckb = stat.finalized_capacity / ONE_CKB as u128;
-            let finalized_shannon = stat.finalized_capacity - (finalized_ckb * ONE_CKB as u128);
-            println!(
-                "Finalized custodian: {}.{:0>8} CKB",
-                finalized_ckb, finalized_shannon
-            );
-            println!("CKB cells count: {}", stat.ckb_cells_count);
-            if !stat.sudt_stat.is_empty() {
-                println!("========================================");
-            }
-            for (sudt_script, sudt_stat) in stat.sudt_stat {
-                let sudt_args: ckb_types::bytes::Bytes = sudt_script.args().unpack();
-                let alias_name = alias
-                    .get(&sudt_args)
-                    .cloned()
-                    .unwrap_or_else(|| "Unknown".to_string());
-                println!(
-                    "Simple UDT ({} {}) total amount: {} finalized amount {} cells count: {}",
-                    alias_name,
-                    sudt_script.args(),
-                    sudt_stat.total_amount,
-                    sudt_stat.finalized_amount,
-                    sudt_stat.cells_count,
-                );
+            let tip_block_number = rpc_client.get_tip().await?.number().unpack();
+            let format = m.value_of("format").unwrap();
+            stat::print_custodian_stat(stat, &alias, tip_block_number, format)?;
+        }
+        Some((name @ ("stat-withdrawal-cells" | "stat-stake-cells"), m)) => {
+            let indexer_rpc_url = m.value_of("indexer-rpc-url").unwrap();
+            let config_path = Path::new(m.value_of("config-path").unwrap());
+            let top_owners: usize = m.value_of("top-owners").unwrap().parse()?;
+            let format = m.value_of("format").unwrap();
+            let rpc_client = CKBIndexerClient::with_url(indexer_rpc_url)?;
+
+            let config = read_config(config_path)?;
+            let rollup_type_script: gw_types::packed::Script =
+                config.chain.rollup_type_script.into();
+            let rollup_type_hash = rollup_type_script.hash();
+            let rollup_config: gw_types::packed::RollupConfig = config.genesis.rollup_config.into();
+            let compatible_finalized_timepoint = stat::query_finalized_timepoint(
+                &rpc_client,
+                rollup_type_script,
+                rollup_config.finality_blocks().unpack(),
+            )
+            .await?;
+
+            if name == "stat-withdrawal-cells" {
+                let stat = stat::stat_withdrawal_cells(
+                    &rpc_client,
+                    &rollup_type_hash,
+                    &rollup_config,
+                    &compatible_finalized_timepoint,
+                )
+                .await?;
+                stat::print_locked_cells_stat("Withdrawal", stat, top_owners, format)?;
+            } else {
+                let stat = stat::stat_stake_cells(
+                    &rpc_client,
+                    &rollup_type_hash,
+                    &rollup_config,
+                    &compatible_finalized_timepoint,
+                )
+                .await?;
+                stat::print_locked_cells_stat("Stake", stat, top_owners, format)?;
             }
         }
         Some(("parse-withdrawal-lock-args", m)) => {
@@ -1559,6 +1738,35 @@ async fn main() -> Result<()> {
                 std::process::exit(-1);
             };
         }
+        Some(("bench", m)) => {
+            let args = BenchArgs {
+                godwoken_rpc_url: m.value_of("godwoken-rpc-url").unwrap(),
+                config_path: Path::new(m.value_of("config-path").unwrap()),
+                scripts_deployment_path: Path::new(m.value_of("scripts-deployment-path").unwrap()),
+                privkey_paths: m.values_of("privkey-path").unwrap().map(Path::new).collect(),
+                workload: value_t!(m, "workload", bench::WorkloadKind).unwrap(),
+                count: value_t!(m, "count", u32).expect("count format error"),
+                fee: value_t!(m, "fee", u128).expect("fee format error"),
+                to_address: m.value_of("to-address"),
+                amount: m.value_of("amount").unwrap(),
+                creator_account_id: value_t!(m, "creator-account-id", u32)
+                    .expect("creator account id format error"),
+                gas_limit: value_t!(m, "gas-limit", u64).expect("gas limit format error"),
+                gas_price: value_t!(m, "gas-price", u128).expect("gas price format error"),
+                calldata: m.value_of("calldata").unwrap(),
+                calldata_size: value_t!(m, "calldata-size", usize)
+                    .expect("calldata size format error"),
+                owner_ckb_address: m.value_of("owner-ckb-address"),
+                capacity: m.value_of("capacity").unwrap(),
+                timeout: Duration::from_secs(
+                    value_t!(m, "timeout", u64).expect("timeout format error"),
+                ),
+            };
+            if let Err(err) = bench::bench(args).await {
+                log::error!("Bench error: {}", err);
+                std::process::exit(-1);
+            };
+        }
         _ => {
             app.print_help().expect("print help");
         }
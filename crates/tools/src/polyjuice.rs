@@ -255,7 +255,7 @@ async fn send(
     Ok(())
 }
 
-fn encode_polyjuice_args(
+pub(crate) fn encode_polyjuice_args(
     gas_limit: u64,
     gas_price: u128,
     value: u128,
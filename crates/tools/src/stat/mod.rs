@@ -1,9 +1,23 @@
-use anyhow::Result;
-use ckb_types::prelude::{Builder, Entity};
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use ckb_types::bytes::Bytes;
+use ckb_types::prelude::{Builder, Entity, Unpack};
 use gw_rpc_client::indexer_client::CKBIndexerClient;
+use gw_rpc_client::indexer_types::{Order, SearchKey};
+use gw_types::core::Timepoint;
 use gw_types::h256::*;
-use gw_types::offchain::CompatibleFinalizedTimepoint;
-use gw_types::{core::ScriptHashType, offchain::CustodianStat, packed::Script, prelude::Pack};
+use gw_types::offchain::{global_state_from_slice, CompatibleFinalizedTimepoint};
+use gw_types::packed::{RollupConfig, StakeLockArgs, WithdrawalLockArgs};
+use gw_types::{
+    core::ScriptHashType,
+    offchain::CustodianStat,
+    packed::Script,
+    prelude::{Pack, Unpack as GWUnpack},
+};
+use serde_json::json;
+
+use crate::utils::sdk::constants::ONE_CKB;
 
 /// Query custodian ckb from ckb-indexer
 pub async fn stat_custodian_cells(
@@ -22,3 +36,338 @@ pub async fn stat_custodian_cells(
         .stat_custodian_cells(script, min_capacity, compatible_finalized_timepoint)
         .await
 }
+
+fn format_ckb(capacity: u128) -> String {
+    let ckb = capacity / ONE_CKB as u128;
+    let shannon = capacity - (ckb * ONE_CKB as u128);
+    format!("{}.{:0>8}", ckb, shannon)
+}
+
+/// Print custodian stat as a table, or as JSON if `format` is "json".
+///
+/// `alias` maps sUDT script args to symbols, the age of the oldest
+/// unfinalized deposit is counted in blocks to `tip_block_number`.
+pub fn print_custodian_stat(
+    stat: CustodianStat,
+    alias: &HashMap<Bytes, String>,
+    tip_block_number: u64,
+    format: &str,
+) -> Result<()> {
+    let mut sudt_stat: Vec<_> = stat
+        .sudt_stat
+        .into_iter()
+        .map(|(sudt_script, sudt_stat)| {
+            let sudt_args: Bytes = sudt_script.args().unpack();
+            let alias_name = alias
+                .get(&sudt_args)
+                .cloned()
+                .unwrap_or_else(|| "Unknown".to_string());
+            (alias_name, sudt_script.args(), sudt_stat)
+        })
+        .collect();
+    sudt_stat.sort_by(|a, b| b.2.total_amount.cmp(&a.2.total_amount));
+    let oldest_unfinalized_age = stat
+        .oldest_unfinalized_block_number
+        .map(|n| tip_block_number.saturating_sub(n));
+
+    if format == "json" {
+        let sudt: Vec<_> = sudt_stat
+            .iter()
+            .map(|(alias_name, args, sudt_stat)| {
+                json!({
+                    "name": alias_name,
+                    "args": args.to_string(),
+                    "total_amount": sudt_stat.total_amount.to_string(),
+                    "finalized_amount": sudt_stat.finalized_amount.to_string(),
+                    "cells_count": sudt_stat.cells_count,
+                    "finalized_cells_count": sudt_stat.finalized_cells_count,
+                })
+            })
+            .collect();
+        let histogram: Vec<_> = stat
+            .capacity_histogram
+            .iter()
+            .map(|(bucket, count)| json!({ "min_ckb": bucket, "cells_count": count }))
+            .collect();
+        let output = json!({
+            "cells_count": stat.cells_count,
+            "finalized_cells_count": stat.finalized_cells_count,
+            "unfinalized_cells_count": stat.cells_count - stat.finalized_cells_count,
+            "ckb_cells_count": stat.ckb_cells_count,
+            "total_capacity": stat.total_capacity.to_string(),
+            "finalized_capacity": stat.finalized_capacity.to_string(),
+            "sudt": sudt,
+            "capacity_histogram": histogram,
+            "oldest_unfinalized_block_number": stat.oldest_unfinalized_block_number,
+            "oldest_unfinalized_age_blocks": oldest_unfinalized_age,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("Cells count: {}", stat.cells_count);
+    println!(
+        "Finalized / unfinalized cells count: {} / {}",
+        stat.finalized_cells_count,
+        stat.cells_count - stat.finalized_cells_count
+    );
+    println!("Total custodian: {} CKB", format_ckb(stat.total_capacity));
+    println!(
+        "Finalized custodian: {} CKB",
+        format_ckb(stat.finalized_capacity)
+    );
+    println!("CKB cells count: {}", stat.ckb_cells_count);
+    match (stat.oldest_unfinalized_block_number, oldest_unfinalized_age) {
+        (Some(number), Some(age)) => println!(
+            "Oldest unfinalized deposit: block {}, {} blocks ago",
+            number, age
+        ),
+        _ => println!("Oldest unfinalized deposit: none"),
+    }
+
+    if !sudt_stat.is_empty() {
+        println!("========================================");
+        println!(
+            "{:<8} {:<68} {:>40} {:>40} {:>8} {:>10}",
+            "Name", "Args", "Total amount", "Finalized amount", "Cells", "Finalized"
+        );
+        for (alias_name, args, sudt_stat) in &sudt_stat {
+            println!(
+                "{:<8} {:<68} {:>40} {:>40} {:>8} {:>10}",
+                alias_name,
+                args.to_string(),
+                sudt_stat.total_amount,
+                sudt_stat.finalized_amount,
+                sudt_stat.cells_count,
+                sudt_stat.finalized_cells_count,
+            );
+        }
+    }
+
+    println!("========================================");
+    println!("{:>16} {:>8}", "Capacity (CKB)", "Cells");
+    for (bucket, count) in &stat.capacity_histogram {
+        println!("{:>16} {:>8}", format!(">= {}", bucket), count);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct OwnerStat {
+    pub cells_count: usize,
+    pub capacity: u128,
+}
+
+/// Stat of cells locked by a rollup lock which are unlockable to their owners
+/// after finalized, i.e. withdrawal and stake cells.
+#[derive(Debug, Default)]
+pub struct LockedCellsStat {
+    pub cells_count: usize,
+    pub finalized_cells_count: usize,
+    pub total_capacity: u128,
+    pub finalized_capacity: u128,
+    pub sudt_amount: HashMap<ckb_types::packed::Script, u128>,
+    pub owners: HashMap<H256, OwnerStat>,
+}
+
+/// Query the last finalized timepoint from the rollup cell.
+pub async fn query_finalized_timepoint(
+    rpc_client: &CKBIndexerClient,
+    rollup_type_script: Script,
+    finality_blocks: u64,
+) -> Result<CompatibleFinalizedTimepoint> {
+    let search_key = SearchKey::with_type(rollup_type_script);
+    let cells = rpc_client
+        .get_cells(&search_key, &Order::Desc, Some(1.into()), &None)
+        .await?;
+    let rollup_cell = cells
+        .objects
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("rollup cell not found"))?
+        .info();
+    let global_state = global_state_from_slice(&rollup_cell.data)?;
+    Ok(CompatibleFinalizedTimepoint::from_global_state(
+        &global_state,
+        finality_blocks,
+    ))
+}
+
+/// Query withdrawal cells of the rollup from ckb-indexer
+pub async fn stat_withdrawal_cells(
+    rpc_client: &CKBIndexerClient,
+    rollup_type_hash: &H256,
+    rollup_config: &RollupConfig,
+    compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+) -> Result<LockedCellsStat> {
+    let lock = Script::new_builder()
+        .code_hash(rollup_config.withdrawal_script_type_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(rollup_type_hash.as_slice().to_vec().pack())
+        .build();
+    stat_locked_cells(rpc_client, lock, compatible_finalized_timepoint, |args| {
+        let args =
+            WithdrawalLockArgs::from_slice(args.get(..WithdrawalLockArgs::TOTAL_SIZE)?).ok()?;
+        Some((
+            args.owner_lock_hash().unpack(),
+            args.withdrawal_finalized_timepoint().unpack(),
+        ))
+    })
+    .await
+}
+
+/// Query stake cells of the rollup from ckb-indexer
+pub async fn stat_stake_cells(
+    rpc_client: &CKBIndexerClient,
+    rollup_type_hash: &H256,
+    rollup_config: &RollupConfig,
+    compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+) -> Result<LockedCellsStat> {
+    let lock = Script::new_builder()
+        .code_hash(rollup_config.stake_script_type_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(rollup_type_hash.as_slice().to_vec().pack())
+        .build();
+    stat_locked_cells(rpc_client, lock, compatible_finalized_timepoint, |args| {
+        let args = StakeLockArgs::from_slice(args).ok()?;
+        Some((
+            args.owner_lock_hash().unpack(),
+            args.stake_finalized_timepoint().unpack(),
+        ))
+    })
+    .await
+}
+
+/// `parse_args` returns the owner lock hash and the finalized timepoint from
+/// lock args after the rollup type hash.
+async fn stat_locked_cells<F>(
+    rpc_client: &CKBIndexerClient,
+    lock: Script,
+    compatible_finalized_timepoint: &CompatibleFinalizedTimepoint,
+    parse_args: F,
+) -> Result<LockedCellsStat>
+where
+    F: Fn(&[u8]) -> Option<(H256, u64)>,
+{
+    let search_key = SearchKey::with_lock(lock);
+    let mut stat = LockedCellsStat::default();
+    let mut cursor = None;
+    loop {
+        let cells = rpc_client
+            .get_cells(&search_key, &Order::Asc, None, &cursor)
+            .await?;
+        for cell in cells.objects {
+            let cell = cell.info();
+            let args: Bytes = cell.output.lock().args().unpack();
+            let (owner_lock_hash, timepoint) = match args.get(32..).and_then(&parse_args) {
+                Some(parsed) => parsed,
+                None => {
+                    log::warn!("invalid lock args of cell {}", cell.out_point);
+                    continue;
+                }
+            };
+            let capacity: u64 = cell.output.capacity().unpack();
+            let is_finalized =
+                compatible_finalized_timepoint.is_finalized(&Timepoint::from_full_value(timepoint));
+
+            stat.cells_count += 1;
+            stat.total_capacity += capacity as u128;
+            if is_finalized {
+                stat.finalized_cells_count += 1;
+                stat.finalized_capacity += capacity as u128;
+            }
+            if let Some(type_) = cell.output.type_().to_opt() {
+                if cell.data.len() >= 16 {
+                    let mut buf = [0u8; 16];
+                    buf.copy_from_slice(&cell.data[..16]);
+                    let type_ = ckb_types::packed::Script::new_unchecked(type_.as_bytes());
+                    *stat.sudt_amount.entry(type_).or_default() += u128::from_le_bytes(buf);
+                }
+            }
+            let owner = stat.owners.entry(owner_lock_hash).or_default();
+            owner.cells_count += 1;
+            owner.capacity += capacity as u128;
+        }
+
+        if cells.last_cursor.is_empty() {
+            return Ok(stat);
+        }
+        cursor = Some(cells.last_cursor);
+    }
+}
+
+/// Print withdrawal or stake cells stat as a table, or as JSON if `format` is
+/// "json". At most `top_owners` owners with the most cells are printed.
+pub fn print_locked_cells_stat(
+    name: &str,
+    stat: LockedCellsStat,
+    top_owners: usize,
+    format: &str,
+) -> Result<()> {
+    let unfinalized_cells_count = stat.cells_count - stat.finalized_cells_count;
+    let mut owners: Vec<_> = stat.owners.into_iter().collect();
+    owners.sort_by(|a, b| b.1.cells_count.cmp(&a.1.cells_count));
+    let owners_count = owners.len();
+    owners.truncate(top_owners);
+
+    if format == "json" {
+        let sudt: Vec<_> = stat
+            .sudt_amount
+            .iter()
+            .map(|(script, amount)| json!({ "args": script.args().to_string(), "amount": amount.to_string() }))
+            .collect();
+        let owners: Vec<_> = owners
+            .iter()
+            .map(|(owner_lock_hash, owner)| {
+                json!({
+                    "owner_lock_hash": format!("0x{}", hex::encode(owner_lock_hash)),
+                    "cells_count": owner.cells_count,
+                    "capacity": owner.capacity.to_string(),
+                })
+            })
+            .collect();
+        let output = json!({
+            "cells_count": stat.cells_count,
+            "finalized_cells_count": stat.finalized_cells_count,
+            "unfinalized_cells_count": unfinalized_cells_count,
+            "total_capacity": stat.total_capacity.to_string(),
+            "finalized_capacity": stat.finalized_capacity.to_string(),
+            "sudt": sudt,
+            "owners_count": owners_count,
+            "top_owners": owners,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("{} cells count: {}", name, stat.cells_count);
+    println!(
+        "Finalized (pending unlock) / unfinalized cells count: {} / {}",
+        stat.finalized_cells_count, unfinalized_cells_count
+    );
+    println!("Total capacity: {} CKB", format_ckb(stat.total_capacity));
+    println!(
+        "Finalized capacity: {} CKB",
+        format_ckb(stat.finalized_capacity)
+    );
+    for (script, amount) in &stat.sudt_amount {
+        println!("Simple UDT {} amount: {}", script.args(), amount);
+    }
+    println!("Owners count: {}", owners_count);
+    if !owners.is_empty() {
+        println!("========================================");
+        println!(
+            "{:<66} {:>8} {:>24}",
+            "Owner lock hash", "Cells", "Capacity (CKB)"
+        );
+        for (owner_lock_hash, owner) in &owners {
+            println!(
+                "{:<66} {:>8} {:>24}",
+                format!("0x{}", hex::encode(owner_lock_hash)),
+                owner.cells_count,
+                format_ckb(owner.capacity)
+            );
+        }
+    }
+    Ok(())
+}
@@ -139,6 +139,7 @@ pub async fn update_cell<P: AsRef<Path>>(args: UpdateCellArgs<'_, P>) -> Result<
     let wallet = Wallet::from_config(&WalletConfig {
         privkey_path: pk_path,
         lock: payment_lock.into(),
+        signer: Default::default(),
     })?;
     let tx = wallet.sign_tx_skeleton(tx_skeleton)?;
     let update_message = format!(
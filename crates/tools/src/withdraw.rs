@@ -137,7 +137,7 @@ pub async fn withdraw(
 }
 
 #[allow(clippy::too_many_arguments)]
-fn create_raw_withdrawal_request(
+pub(crate) fn create_raw_withdrawal_request(
     nonce: u32,
     capacity: u64,
     amount: u128,
@@ -167,7 +167,7 @@ fn h256_to_byte32(hash: &H256) -> Result<Byte32> {
     Ok(value)
 }
 
-fn generate_withdrawal_message_to_sign(
+pub(crate) fn generate_withdrawal_message_to_sign(
     raw_request: RawWithdrawalRequest,
     owner_lock: Script,
     address: RegistryAddress,
@@ -208,12 +208,12 @@ async fn wait_for_balance_change(
     Err(anyhow!("Timeout: {:?}", retry_timeout))
 }
 
-fn parse_capacity(capacity: &str) -> Result<u64> {
+pub(crate) fn parse_capacity(capacity: &str) -> Result<u64> {
     let human_capacity = HumanCapacity::from_str(capacity).map_err(|err| anyhow!("{}", err))?;
     Ok(human_capacity.into())
 }
 
-fn minimal_withdrawal_capacity(is_sudt: bool) -> Result<u64> {
+pub(crate) fn minimal_withdrawal_capacity(is_sudt: bool) -> Result<u64> {
     use gw_types::h256::H256Ext;
 
     // fixed size, the specific value is not important.
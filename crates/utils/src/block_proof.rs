@@ -0,0 +1,48 @@
+//! Verify that a block is part of the rollup, by the block SMT merkle proof
+//! of `gw_get_block_proof`.
+
+use anyhow::Result;
+use gw_smt::smt::{Blake2bHasher, CompiledMerkleProof, SMTH256};
+use gw_types::{h256::*, packed::RawL2Block};
+
+/// Verify a compiled block SMT merkle proof of `block_hash` at `block_number`
+/// against `block_root`, i.e. `GlobalState.block.merkle_root`.
+pub fn verify_block_proof(
+    block_root: H256,
+    block_number: u64,
+    block_hash: H256,
+    proof: Vec<u8>,
+) -> Result<bool> {
+    let key = RawL2Block::compute_smt_key(block_number);
+    let leaves: Vec<(SMTH256, SMTH256)> = vec![(key.into(), block_hash.into())];
+    let valid = CompiledMerkleProof(proof).verify::<Blake2bHasher>(&block_root.into(), leaves)?;
+    Ok(valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_smt::smt::{default_store::DefaultStore, SMT};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_block_proof() {
+        let mut tree = SMT::<DefaultStore<SMTH256>>::default();
+        for number in 0..10u64 {
+            let key = RawL2Block::compute_smt_key(number);
+            tree.update(key.into(), [number as u8 + 1; 32].into())
+                .unwrap();
+        }
+        let root: H256 = (*tree.root()).into();
+        let key: SMTH256 = RawL2Block::compute_smt_key(3).into();
+        let proof = tree
+            .merkle_proof(vec![key])
+            .unwrap()
+            .compile(vec![key])
+            .unwrap();
+
+        assert!(verify_block_proof(root, 3, [4u8; 32], proof.0.clone()).unwrap());
+        assert!(!verify_block_proof(root, 3, [5u8; 32], proof.0.clone()).unwrap());
+        assert!(!verify_block_proof(root, 4, [4u8; 32], proof.0).unwrap());
+    }
+}
@@ -1,10 +1,10 @@
 use crate::finalized_timepoint;
 use anyhow::{Context, Result};
-use gw_config::ForkConfig;
+use gw_config::{ForkConfig, ForkSwitch};
 use gw_store::traits::chain_store::ChainStore;
 use gw_types::{
     offchain::CompatibleFinalizedTimepoint,
-    packed::{L2Block, RollupConfig},
+    packed::{L2Block, RawL2Block, RollupConfig},
     prelude::*,
 };
 use std::ops::Range;
@@ -20,14 +20,14 @@ fn is_older_block_finalized(
     let older_block_hash = db
         .get_block_hash_by_number(older_block_number)?
         .context("get older block hash")?;
-    let older_block = db
-        .get_block(&older_block_hash)?
+    let older_block_timestamp: u64 = db
+        .get_block_reader(&older_block_hash, |block| block.raw().timestamp().unpack())?
         .context("get older block")?;
     let older_block_timepoint = finalized_timepoint(
         rollup_config,
         fork_config,
         older_block_number,
-        older_block.raw().timestamp().unpack(),
+        older_block_timestamp,
     );
     Ok(compatible_finalized_timepoint.is_finalized(&older_block_timepoint))
 }
@@ -37,9 +37,9 @@ fn find_finalized_upper_bound(
     rollup_config: &RollupConfig,
     fork_config: &ForkConfig,
     db: &impl ChainStore,
-    block: &L2Block,
+    block: &RawL2Block,
 ) -> Result<u64> {
-    let block_number = block.raw().number().unpack();
+    let block_number = block.number().unpack();
     let finality_blocks = rollup_config.finality_blocks().unpack();
 
     // When using block number as timepoint, `block_number - finality_blocks` is the only finalizing
@@ -58,7 +58,7 @@ fn find_finalized_upper_bound(
     let compatible_finalized_timepoint =
         CompatibleFinalizedTimepoint::from_global_state(&global_state, finality_blocks);
     let mut l = fork_config
-        .upgrade_global_state_version_to_v2
+        .fork_height(ForkSwitch::UpgradeGlobalStateVersionToV2)
         .context("upgrade_global_state_version_to_v2 configuration required")?;
 
     // When using timestamp as timepoint, binary search for the last finalized one for
@@ -67,7 +67,7 @@ fn find_finalized_upper_bound(
     // NOTE: To ensure that at least one finalized block is found below, start a binary search at
     // `upgrade_global_state_version_to_v2 - 1`.
     l = l.saturating_sub(1);
-    let mut r = block.raw().number().unpack().saturating_sub(1);
+    let mut r = block_number.saturating_sub(1);
     while l < r {
         let mid = l + (r - l + 1) / 2;
         if is_older_block_finalized(
@@ -102,11 +102,13 @@ pub fn calc_finalizing_range(
     }
 
     let parent_hash = current_block.raw().parent_block_hash().unpack();
-    let parent_block = db.get_block(&parent_hash)?.context("get parent block")?;
+    let parent_block = db
+        .get_block_raw(&parent_hash)?
+        .context("get parent block")?;
     let parent_finalized_upper_bound =
         find_finalized_upper_bound(rollup_config, fork_config, db, &parent_block)?;
     let current_finalized_upper_bound =
-        find_finalized_upper_bound(rollup_config, fork_config, db, current_block)?;
+        find_finalized_upper_bound(rollup_config, fork_config, db, &current_block.raw())?;
 
     // `0..=parent_finalized_upper_bound` blocks is finalized for `parent_block`,
     // `0..=current_finalized_upper_bound` blocks is finalized for `current_block`,
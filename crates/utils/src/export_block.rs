@@ -13,7 +13,19 @@ use gw_types::{
     prelude::{Builder, Entity, Pack, Reader, Unpack},
 };
 
-pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedBlock> {
+/// A block with its post global state, deposits and withdrawals.
+pub struct BlockWithRequests {
+    pub block: packed::L2Block,
+    pub post_global_state: GlobalState,
+    pub deposit_info_vec: packed::DepositInfoVec,
+    pub deposit_asset_scripts: Vec<packed::Script>,
+    pub withdrawals: Vec<packed::WithdrawalRequestExtra>,
+}
+
+pub fn get_block_with_requests(
+    snap: &impl ChainStore,
+    block_number: u64,
+) -> Result<BlockWithRequests> {
     let block_hash = snap
         .get_block_hash_by_number(block_number)?
         .ok_or_else(|| anyhow!("block {} not found", block_number))?;
@@ -58,13 +70,31 @@ pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedB
         extra_reqs.collect::<Result<Vec<_>>>()?
     };
 
+    Ok(BlockWithRequests {
+        block,
+        post_global_state,
+        deposit_info_vec: deposit_requests,
+        deposit_asset_scripts,
+        withdrawals,
+    })
+}
+
+pub fn export_block(snap: &StoreReadonly, block_number: u64) -> Result<ExportedBlock> {
+    let BlockWithRequests {
+        block,
+        post_global_state,
+        deposit_info_vec,
+        deposit_asset_scripts,
+        withdrawals,
+    } = get_block_with_requests(snap, block_number)?;
+
     let bad_block_hashes = get_bad_block_hashes(snap, block_number)?;
     let submit_tx_hash = snap.get_block_submit_tx_hash(block_number);
 
     let exported_block = ExportedBlock {
         block,
         post_global_state,
-        deposit_info_vec: deposit_requests,
+        deposit_info_vec,
         deposit_asset_scripts,
         withdrawals,
         bad_block_hashes,
@@ -19,8 +19,11 @@ use gw_types::{
     prelude::*,
 };
 
+/// Minimal capacity of change cells.
+pub const CHANGE_CELL_CAPACITY: u64 = 61_00000000;
+
 /// Calculate tx fee
-fn calculate_required_tx_fee(tx_size: usize, fee_rate: u64) -> u64 {
+pub fn calculate_required_tx_fee(tx_size: usize, fee_rate: u64) -> u64 {
     // tx_size * KB / MIN_FEE_RATE
     (tx_size as u64) * fee_rate / 1000
 }
@@ -33,8 +36,6 @@ pub async fn fill_tx_fee_with_local(
     local_cells_manager: &LocalCellsManager,
     fee_rate: u64,
 ) -> Result<()> {
-    const CHANGE_CELL_CAPACITY: u64 = 61_00000000;
-
     let estimate_tx_size_with_change = |tx_skeleton: &mut TransactionSkeleton| -> Result<usize> {
         let change_cell = CellOutput::new_builder()
             .lock(lock_script.clone())
@@ -1,4 +1,5 @@
 pub mod abort_on_drop;
+pub mod block_proof;
 mod calc_finalizing_range;
 pub mod compression;
 pub mod exponential_backoff;
@@ -12,6 +13,7 @@ pub mod polyjuice_parser;
 mod query_rollup_cell;
 mod rollup_context;
 pub mod script_log;
+pub mod signer;
 pub mod since;
 pub mod timepoint;
 pub mod transaction_skeleton;
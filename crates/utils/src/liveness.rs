@@ -1,11 +1,15 @@
 use std::{
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 
 pub struct Liveness {
     last_tick: Mutex<Instant>,
     duration: Duration,
+    shutting_down: AtomicBool,
 }
 
 impl Liveness {
@@ -13,6 +17,7 @@ impl Liveness {
         Self {
             last_tick: Mutex::new(Instant::now()),
             duration,
+            shutting_down: AtomicBool::new(false),
         }
     }
 
@@ -23,4 +28,19 @@ impl Liveness {
     pub fn tick(&self) {
         *self.last_tick.lock().unwrap() = Instant::now();
     }
+
+    /// Live and not shutting down, i.e. able to take new requests.
+    pub fn is_ready(&self) -> bool {
+        !self.is_shutting_down() && self.is_live()
+    }
+
+    /// Mark the node as shutting down, writes are rejected and `/readyz`
+    /// fails from now on.
+    pub fn start_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
 }
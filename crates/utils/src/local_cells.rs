@@ -3,6 +3,7 @@
 use std::{
     collections::{HashMap, HashSet},
     convert::TryFrom,
+    sync::Arc,
 };
 
 use anyhow::Result;
@@ -10,22 +11,75 @@ use gw_jsonrpc_types::ckb_jsonrpc_types::JsonBytes;
 use gw_rpc_client::{
     indexer_client::CKBIndexerClient,
     indexer_types::{Order, ScriptType, SearchKey},
+    rpc_client::RPCClient,
 };
+use gw_store::{transaction::StoreTransaction, Store};
 use gw_types::{
     bytes::Bytes,
-    offchain::CellInfo,
+    h256::H256,
+    offchain::{CellInfo, TxStatus},
     packed::{OutPoint, Transaction, TransactionReader},
     prelude::*,
 };
 
 /// Manage local dead / live cells.
+///
+/// If created by `restore`, changes are persisted in the store so that
+/// unconfirmed txs of the block producer are not double spent after restart.
 #[derive(Default)]
 pub struct LocalCellsManager {
     dead_cells: HashSet<OutPoint>,
     local_live_cells: HashMap<OutPoint, CellInfo>,
+    store: Option<Store>,
 }
 
 impl LocalCellsManager {
+    /// Restore persisted local cells and drop those that are not pending any
+    /// more, i.e. created or consumed by txs that are committed, or dropped by
+    /// CKB. Dead cells of unknown consuming txs are dropped too, e.g. deposit
+    /// cells of local blocks, callers lock them again.
+    pub async fn restore(store: Store, rpc_client: &RPCClient) -> Result<Self> {
+        let cells = store.begin_transaction().get_local_cells();
+        let mut statuses = HashMap::new();
+        let mut manager = LocalCellsManager::default();
+        for cell in cells.live {
+            let cell: CellInfo = cell.as_reader().unpack();
+            let tx_hash = cell.out_point.tx_hash().unpack();
+            if is_pending(rpc_client, &mut statuses, tx_hash).await? {
+                manager
+                    .local_live_cells
+                    .insert(cell.out_point.clone(), cell);
+            }
+        }
+        let mut dead = Vec::new();
+        for (out_point, consumed_by) in cells.dead {
+            if let Some(tx_hash) = consumed_by {
+                if is_pending(rpc_client, &mut statuses, tx_hash).await? {
+                    dead.push((out_point, tx_hash));
+                }
+            }
+        }
+        log::info!(
+            "[local cells] restored {} live cells, {} dead cells",
+            manager.local_live_cells.len(),
+            dead.len()
+        );
+
+        let mut store_tx = store.begin_transaction();
+        store_tx.clear_local_cells()?;
+        for cell in manager.local_live_cells.values() {
+            store_tx.insert_local_live_cell(&cell.pack().as_reader())?;
+        }
+        for (out_point, tx_hash) in &dead {
+            store_tx.insert_local_dead_cell(&out_point.as_reader(), Some(tx_hash))?;
+        }
+        store_tx.commit()?;
+
+        manager.dead_cells = dead.into_iter().map(|(out_point, _)| out_point).collect();
+        manager.store = Some(store);
+        Ok(manager)
+    }
+
     pub fn is_dead(&self, out_point: &OutPoint) -> bool {
         self.dead_cells.contains(out_point)
     }
@@ -40,11 +94,16 @@ impl LocalCellsManager {
 
     /// Remove from live and add to dead.
     pub fn lock_cell(&mut self, out_point: OutPoint) {
+        self.persist(|store_tx| {
+            store_tx.delete_local_live_cell(&out_point.as_reader())?;
+            store_tx.insert_local_dead_cell(&out_point.as_reader(), None)
+        });
         self.local_live_cells.remove(&out_point);
         self.dead_cells.insert(out_point);
     }
 
     pub fn add_live(&mut self, cell: CellInfo) {
+        self.persist(|store_tx| store_tx.insert_local_live_cell(&cell.pack().as_reader()));
         self.local_live_cells.insert(cell.out_point.clone(), cell);
     }
 
@@ -52,50 +111,133 @@ impl LocalCellsManager {
     ///
     /// And add transaction outputs to live cells.
     pub fn apply_tx(&mut self, tx: &TransactionReader) {
+        let tx_hash = tx.hash();
+        let outputs = tx_outputs(tx);
+        self.persist(|store_tx| {
+            for input in tx.raw().inputs().iter() {
+                let out_point = input.previous_output();
+                store_tx.delete_local_live_cell(&out_point)?;
+                store_tx.insert_local_dead_cell(&out_point, Some(&tx_hash))?;
+            }
+            for cell in &outputs {
+                store_tx.insert_local_live_cell(&cell.pack().as_reader())?;
+            }
+            Ok(())
+        });
         for input in tx.raw().inputs().iter() {
             let out_point = input.previous_output().to_entity();
-            self.lock_cell(out_point);
+            self.local_live_cells.remove(&out_point);
+            self.dead_cells.insert(out_point);
         }
-        let tx_hash = tx.hash().pack();
-        for (idx, (output, output_data)) in tx
-            .raw()
-            .outputs()
-            .iter()
-            .zip(tx.raw().outputs_data().iter())
-            .enumerate()
-        {
-            let out_point = OutPoint::new_builder()
-                .tx_hash(tx_hash.clone())
-                .index(u32::try_from(idx).unwrap().pack())
-                .build();
-            let cell_info = CellInfo {
-                out_point: out_point.clone(),
-                output: output.to_entity(),
-                data: Bytes::copy_from_slice(output_data.raw_data()),
-            };
-            self.local_live_cells.insert(out_point, cell_info);
+        for cell in outputs {
+            self.local_live_cells.insert(cell.out_point.clone(), cell);
+        }
+    }
+
+    /// Replace an applied transaction, e.g. with a fee bumped one.
+    ///
+    /// Outputs of `old` are removed, and `new` is applied.
+    pub fn replace_tx(&mut self, old: &Transaction, new: &TransactionReader) {
+        let outputs = tx_outputs(&old.as_reader());
+        self.persist(|store_tx| {
+            for cell in &outputs {
+                store_tx.delete_local_live_cell(&cell.out_point.as_reader())?;
+                store_tx.delete_local_dead_cell(&cell.out_point.as_reader())?;
+            }
+            Ok(())
+        });
+        for cell in outputs {
+            self.local_live_cells.remove(&cell.out_point);
+            self.dead_cells.remove(&cell.out_point);
         }
+        self.apply_tx(new);
     }
 
-    /// Remove transaction inputs from dead cells.
+    /// Remove transaction inputs from dead cells, and outputs from live cells
+    /// as they are returned by ckb-indexer now.
     ///
     /// You should call this after the transaction has already been confirmed by
     /// ckb/ckb-indexer.
     pub fn confirm_tx(&mut self, tx: &Transaction) {
+        let outputs = tx_outputs(&tx.as_reader());
+        self.persist(|store_tx| {
+            for input in tx.raw().inputs() {
+                store_tx.delete_local_dead_cell(&input.previous_output().as_reader())?;
+            }
+            for cell in &outputs {
+                store_tx.delete_local_live_cell(&cell.out_point.as_reader())?;
+            }
+            Ok(())
+        });
         for input in tx.raw().inputs() {
             self.dead_cells.remove(&input.previous_output());
         }
+        for cell in outputs {
+            self.local_live_cells.remove(&cell.out_point);
+        }
     }
 
     pub fn reset(&mut self) {
+        self.persist(|store_tx| store_tx.clear_local_cells());
         self.local_live_cells.clear();
         self.dead_cells.clear();
     }
+
+    /// Write changes to the store. Errors are logged, local cells in memory are
+    /// still updated.
+    fn persist(&self, f: impl FnOnce(&mut StoreTransaction) -> Result<()>) {
+        let store = match self.store {
+            Some(ref store) => store,
+            None => return,
+        };
+        let mut store_tx = store.begin_transaction();
+        if let Err(err) = f(&mut store_tx).and_then(|_| store_tx.commit()) {
+            log::error!("[local cells] persist local cells: {:#}", err);
+        }
+    }
+}
+
+/// Whether the tx is in the tx pool of CKB.
+async fn is_pending(
+    rpc_client: &RPCClient,
+    statuses: &mut HashMap<H256, bool>,
+    tx_hash: H256,
+) -> Result<bool> {
+    if let Some(&pending) = statuses.get(&tx_hash) {
+        return Ok(pending);
+    }
+    let status = rpc_client.ckb.get_transaction_status(tx_hash).await?;
+    let pending = matches!(status, Some(TxStatus::Pending) | Some(TxStatus::Proposed));
+    statuses.insert(tx_hash, pending);
+    Ok(pending)
+}
+
+fn tx_outputs(tx: &TransactionReader) -> Vec<CellInfo> {
+    let tx_hash = tx.hash().pack();
+    tx.raw()
+        .outputs()
+        .iter()
+        .zip(tx.raw().outputs_data().iter())
+        .enumerate()
+        .map(|(idx, (output, output_data))| CellInfo {
+            out_point: OutPoint::new_builder()
+                .tx_hash(tx_hash.clone())
+                .index(u32::try_from(idx).unwrap().pack())
+                .build(),
+            output: output.to_entity(),
+            data: Bytes::copy_from_slice(output_data.raw_data()),
+        })
+        .collect()
 }
 
 pub enum CollectLocalAndIndexerCursor {
     Local,
+    /// Like `Local`, but cells of the indexer are from the indexer cache if it
+    /// is enabled.
+    LocalThenCached,
     Indexer(Option<JsonBytes>),
+    /// Cells of the indexer cache and the offset of the next page.
+    Cached(Option<(Arc<Vec<CellInfo>>, usize)>),
     Ended,
 }
 
@@ -127,13 +269,18 @@ pub async fn collect_local_and_indexer_cells(
     cursor: &mut CollectLocalAndIndexerCursor,
 ) -> Result<Vec<CellInfo>> {
     match cursor {
-        CollectLocalAndIndexerCursor::Local => {
+        CollectLocalAndIndexerCursor::Local | CollectLocalAndIndexerCursor::LocalThenCached => {
             let local = local_cells_manager
                 .local_live()
                 .filter(|c| satisfy_search(search_key, *c))
                 .cloned()
                 .collect();
-            *cursor = CollectLocalAndIndexerCursor::Indexer(None);
+            let cached = matches!(cursor, CollectLocalAndIndexerCursor::LocalThenCached);
+            *cursor = if cached && indexer_client.cache().is_enabled() {
+                CollectLocalAndIndexerCursor::Cached(None)
+            } else {
+                CollectLocalAndIndexerCursor::Indexer(None)
+            };
             Ok(local)
         }
         CollectLocalAndIndexerCursor::Indexer(ref mut indexer_cursor) => {
@@ -158,6 +305,30 @@ pub async fn collect_local_and_indexer_cells(
                 })
                 .collect())
         }
+        CollectLocalAndIndexerCursor::Cached(ref mut cached) => {
+            let (cells, offset) = match cached {
+                Some(cached) => cached,
+                None => {
+                    let cells = indexer_client.get_cells_cached(search_key, *order).await?;
+                    cached.insert((cells, 0))
+                }
+            };
+            let end = limit.map_or(cells.len(), |limit| {
+                cells.len().min(*offset + limit as usize)
+            });
+            // Empty page at the end, the same as the indexer.
+            if *offset == end {
+                *cursor = CollectLocalAndIndexerCursor::Ended;
+                return Ok(Vec::new());
+            }
+            let page = cells[*offset..end]
+                .iter()
+                .filter(|c| !local_cells_manager.is_dead(&c.out_point))
+                .cloned()
+                .collect();
+            *offset = end;
+            Ok(page)
+        }
         CollectLocalAndIndexerCursor::Ended => Ok(Vec::new()),
     }
 }
@@ -0,0 +1,447 @@
+//! Signers of wallet keys: a local key file, a remote signing service over
+//! HTTP, or an external command such as a KMS client, so that operators don't
+//! have to keep the key on disk.
+//!
+//! Remote and plugin signers block the current thread, they run in
+//! `block_in_place` when called inside the tokio runtime.
+
+use std::{
+    path::Path,
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_crypto::secp::Privkey;
+use faster_hex::{hex_decode, hex_string};
+use gw_config::{PluginSignerConfig, RemoteSignerConfig, SignerConfig, WalletConfig};
+use hmac::{Hmac, Mac};
+use secp256k1::PublicKey;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::exponential_backoff::ExponentialBackoff;
+
+const TIMESTAMP_HEADER: &str = "X-Signer-Timestamp";
+const SIGNATURE_HEADER: &str = "X-Signer-Signature";
+
+pub trait Signer: Send + Sync {
+    fn public_key(&self) -> Result<PublicKey>;
+
+    /// Recoverable secp256k1 signature of `message`, i.e. `r | s | v`.
+    fn sign_message(&self, message: [u8; 32]) -> Result<[u8; 65]>;
+}
+
+pub fn build_signer(config: &WalletConfig) -> Result<Box<dyn Signer>> {
+    let signer: Box<dyn Signer> = match config.signer {
+        SignerConfig::Local => Box::new(LocalSigner::from_path(&config.privkey_path)?),
+        SignerConfig::Remote(ref c) => Box::new(RemoteSigner::new(c)?),
+        SignerConfig::Plugin(ref c) => Box::new(PluginSigner::new(c.clone())),
+    };
+    Ok(signer)
+}
+
+pub struct LocalSigner {
+    privkey: Privkey,
+}
+
+impl LocalSigner {
+    pub fn new(privkey: Privkey) -> Self {
+        LocalSigner { privkey }
+    }
+
+    pub fn from_path(privkey_path: &Path) -> Result<Self> {
+        read_privkey(privkey_path).map(Self::new)
+    }
+}
+
+impl Signer for LocalSigner {
+    fn public_key(&self) -> Result<PublicKey> {
+        Ok(PublicKey::from_slice(&self.privkey.pubkey()?.serialize())?)
+    }
+
+    fn sign_message(&self, message: [u8; 32]) -> Result<[u8; 65]> {
+        let signature = self
+            .privkey
+            .sign_recoverable(&message.into())
+            .map_err(|err| anyhow!("signing error: {}", err))?;
+        let mut inner = [0u8; 65];
+        inner.copy_from_slice(&signature.serialize());
+        Ok(inner)
+    }
+}
+
+/// Signer of a remote service.
+///
+/// Requests are `POST {url}/public_key` with `{"key_id"}` and
+/// `POST {url}/sign` with `{"key_id", "message"}`, responded with
+/// `{"public_key"}` and `{"signature"}` in hex. If `auth_key_path` is set,
+/// requests carry a unix timestamp header and a hex HMAC-SHA256 of
+/// `timestamp.body` with the key.
+pub struct RemoteSigner {
+    client: RemoteClient,
+    public_key: PublicKey,
+}
+
+struct RemoteClient {
+    client: reqwest::blocking::Client,
+    config: RemoteSignerConfig,
+    auth_key: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+impl RemoteSigner {
+    pub fn new(config: &RemoteSignerConfig) -> Result<Self> {
+        let auth_key = match config.auth_key_path {
+            Some(ref path) => {
+                let key = std::fs::read_to_string(path).with_context(|| "read signer auth key")?;
+                Some(key.trim().as_bytes().to_vec())
+            }
+            None => None,
+        };
+        let client = blocking(|| {
+            reqwest::blocking::Client::builder()
+                .timeout(Duration::from_millis(config.timeout_ms))
+                .build()
+        })?;
+        let client = RemoteClient {
+            client,
+            config: config.clone(),
+            auth_key,
+        };
+        // The key doesn't change, query it once.
+        let resp: PublicKeyResponse =
+            client.request("public_key", json!({ "key_id": config.key_id }))?;
+        let public_key = PublicKey::from_slice(&decode_hex(&resp.public_key)?)?;
+        Ok(RemoteSigner { client, public_key })
+    }
+}
+
+impl RemoteClient {
+    fn request<T: DeserializeOwned>(&self, path: &str, body: serde_json::Value) -> Result<T> {
+        let body = serde_json::to_vec(&body)?;
+        let mut backoff = ExponentialBackoff::new(Duration::from_millis(200));
+        let mut retries = 0;
+        blocking(|| loop {
+            match self.try_request(path, &body) {
+                Ok(resp) => return Ok(resp),
+                Err(err) if retries < self.config.max_retries => {
+                    retries += 1;
+                    log::warn!(
+                        "[signer] remote {} error, retry {}: {:#}",
+                        path,
+                        retries,
+                        err
+                    );
+                    std::thread::sleep(backoff.next_sleep());
+                }
+                Err(err) => return Err(err.context(format!("remote signer {}", path))),
+            }
+        })
+    }
+
+    fn try_request<T: DeserializeOwned>(&self, path: &str, body: &[u8]) -> Result<T> {
+        let url = format!("{}/{}", self.config.url.trim_end_matches('/'), path);
+        let mut req = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(ref auth_key) = self.auth_key {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let timestamp = timestamp.to_string();
+            let mut mac = Hmac::<Sha256>::new_from_slice(auth_key)
+                .map_err(|_| anyhow!("invalid signer auth key"))?;
+            mac.update(timestamp.as_bytes());
+            mac.update(b".");
+            mac.update(body);
+            let signature = hex_string(&mac.finalize().into_bytes())?;
+            req = req
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(SIGNATURE_HEADER, signature);
+        }
+        let resp = req.body(body.to_vec()).send()?.error_for_status()?;
+        Ok(resp.json()?)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> Result<PublicKey> {
+        Ok(self.public_key)
+    }
+
+    fn sign_message(&self, message: [u8; 32]) -> Result<[u8; 65]> {
+        let body = json!({
+            "key_id": self.client.config.key_id,
+            "message": format!("0x{}", hex_string(&message)?),
+        });
+        let resp: SignResponse = self.client.request("sign", body)?;
+        to_signature(&decode_hex(&resp.signature)?)
+    }
+}
+
+/// Signer of an external command.
+///
+/// `command args.. public_key` and `command args.. sign <message>` print the
+/// public key and the signature in hex to stdout.
+pub struct PluginSigner {
+    config: PluginSignerConfig,
+}
+
+impl PluginSigner {
+    pub fn new(config: PluginSignerConfig) -> Self {
+        PluginSigner { config }
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let output = blocking(|| {
+            Command::new(&self.config.command)
+                .args(&self.config.args)
+                .args(args)
+                .output()
+        })
+        .with_context(|| format!("run signer plugin {:?}", self.config.command))?;
+        if !output.status.success() {
+            bail!(
+                "signer plugin {} failed: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        decode_hex(String::from_utf8_lossy(&output.stdout).trim())
+    }
+}
+
+impl Signer for PluginSigner {
+    fn public_key(&self) -> Result<PublicKey> {
+        Ok(PublicKey::from_slice(&self.run(&["public_key"])?)?)
+    }
+
+    fn sign_message(&self, message: [u8; 32]) -> Result<[u8; 65]> {
+        let message = format!("0x{}", hex_string(&message)?);
+        to_signature(&self.run(&["sign", &message])?)
+    }
+}
+
+pub(crate) fn read_privkey(privkey_path: &Path) -> Result<Privkey> {
+    let content = std::fs::read_to_string(privkey_path).with_context(|| "read wallet privkey")?;
+    let content = content.trim_start_matches("0x").trim();
+    assert_eq!(content.as_bytes().len(), 64, "invalid privkey length");
+    let mut decoded = [0u8; 32];
+    hex_decode(content.as_bytes(), &mut decoded)?;
+    Ok(Privkey::from_slice(&decoded))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    let s = s.trim_start_matches("0x");
+    let mut buf = vec![0u8; s.len() / 2];
+    hex_decode(s.as_bytes(), &mut buf)?;
+    Ok(buf)
+}
+
+fn to_signature(bytes: &[u8]) -> Result<[u8; 65]> {
+    if bytes.len() != 65 {
+        bail!("invalid signature length {}", bytes.len());
+    }
+    let mut signature = [0u8; 65];
+    signature.copy_from_slice(bytes);
+    Ok(signature)
+}
+
+fn blocking<T>(f: impl FnOnce() -> T) -> T {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::task::block_in_place(f)
+    } else {
+        f()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+    };
+
+    use ckb_crypto::secp::Signature;
+
+    use super::*;
+
+    const MESSAGE: [u8; 32] = [7u8; 32];
+
+    fn local_signer() -> LocalSigner {
+        LocalSigner::new(Privkey::from_slice(&[1u8; 32]))
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        format!("0x{}", hex_string(bytes).unwrap())
+    }
+
+    struct Request {
+        path: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    impl Request {
+        fn header(&self, name: &str) -> Option<&str> {
+            { self.headers.iter() }
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Reply `responses` in order, one request per connection, returns the
+    /// url and the received requests.
+    fn serve(responses: Vec<(u16, serde_json::Value)>) -> (String, JoinHandle<Vec<Request>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let path = line.split(' ').nth(1).unwrap().to_string();
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((k, v)) => headers.push((k.to_string(), v.to_string())),
+                        None => break,
+                    }
+                }
+                let mut request = Request {
+                    path,
+                    headers,
+                    body: Vec::new(),
+                };
+                let len: usize = request.header("content-length").unwrap().parse().unwrap();
+                request.body.resize(len, 0);
+                reader.read_exact(&mut request.body).unwrap();
+                requests.push(request);
+
+                let body = body.to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_local_signer() {
+        let signer = local_signer();
+        let signature = signer.sign_message(MESSAGE).unwrap();
+        let recovered = Signature::from_slice(&signature)
+            .unwrap()
+            .recover(&MESSAGE.into())
+            .unwrap();
+        let public_key = signer.public_key().unwrap();
+        assert_eq!(recovered.serialize(), public_key.serialize().to_vec());
+    }
+
+    #[test]
+    fn test_remote_signer() {
+        let local = local_signer();
+        let public_key = hex(&local.public_key().unwrap().serialize());
+        let signature = hex(&local.sign_message(MESSAGE).unwrap());
+        let (url, server) = serve(vec![
+            (200, json!({ "public_key": public_key })),
+            // Retried
+            (500, json!({})),
+            (200, json!({ "signature": signature })),
+            // Retries are used up
+            (500, json!({})),
+            (503, json!({})),
+        ]);
+        let auth_key_path =
+            std::env::temp_dir().join(format!("gw-signer-auth-key-{}", std::process::id()));
+        std::fs::write(&auth_key_path, "secret\n").unwrap();
+        let config = RemoteSignerConfig {
+            url,
+            key_id: "producer".to_string(),
+            auth_key_path: Some(auth_key_path.clone()),
+            timeout_ms: 5000,
+            max_retries: 1,
+        };
+
+        let signer = RemoteSigner::new(&config).unwrap();
+        assert_eq!(signer.public_key().unwrap(), local.public_key().unwrap());
+        let signed = signer.sign_message(MESSAGE).unwrap();
+        assert_eq!(signed, local.sign_message(MESSAGE).unwrap());
+        assert!(signer.sign_message(MESSAGE).is_err());
+        std::fs::remove_file(auth_key_path).unwrap();
+
+        let requests = server.join().unwrap();
+        let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["/public_key", "/sign", "/sign", "/sign", "/sign"]);
+        let body: serde_json::Value = serde_json::from_slice(&requests[2].body).unwrap();
+        assert_eq!(
+            body,
+            json!({ "key_id": "producer", "message": hex(&MESSAGE) })
+        );
+        // Requests are signed with the trimmed auth key.
+        for request in requests.iter() {
+            let timestamp = request.header(TIMESTAMP_HEADER).unwrap();
+            let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+            mac.update(timestamp.as_bytes());
+            mac.update(b".");
+            mac.update(&request.body);
+            let expected = hex_string(&mac.finalize().into_bytes()).unwrap();
+            assert_eq!(request.header(SIGNATURE_HEADER), Some(expected.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_plugin_signer() {
+        let local = local_signer();
+        let script = format!(
+            r#"case "$1" in
+                public_key) echo {} ;;
+                sign) [ "$2" = "{}" ] && echo {} ;;
+                *) echo "unknown command $1" >&2; exit 1 ;;
+            esac"#,
+            hex(&local.public_key().unwrap().serialize()),
+            hex(&MESSAGE),
+            hex(&local.sign_message(MESSAGE).unwrap()),
+        );
+        let plugin = |script: &str| {
+            PluginSigner::new(PluginSignerConfig {
+                command: "sh".into(),
+                args: vec!["-c".to_string(), script.to_string(), "signer".to_string()],
+            })
+        };
+
+        let signer = plugin(&script);
+        assert_eq!(signer.public_key().unwrap(), local.public_key().unwrap());
+        let signed = signer.sign_message(MESSAGE).unwrap();
+        assert_eq!(signed, local.sign_message(MESSAGE).unwrap());
+        // The plugin fails on other messages.
+        assert!(signer.sign_message([8u8; 32]).is_err());
+
+        let err = plugin("echo no key >&2; exit 2").public_key().unwrap_err();
+        assert!(err.to_string().contains("no key"), "{}", err);
+        let err = plugin("echo 0x1234").sign_message(MESSAGE).unwrap_err();
+        assert!(err.to_string().contains("invalid signature length 2"));
+    }
+}
@@ -80,7 +80,7 @@ impl SealedTransaction {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct TransactionSkeleton {
     inputs: Vec<InputCellInfo>,
     cell_deps: Vec<CellDep>,
@@ -192,11 +192,21 @@ impl TransactionSkeleton {
             .iter()
             .map(|(_output, data)| data.to_owned())
             .collect::<Vec<_>>();
+        // Contracts may share a dep group, and CKB rejects duplicate cell deps.
+        let mut cell_deps: Vec<CellDep> = Vec::with_capacity(self.cell_deps.len());
+        for cell_dep in self.cell_deps.iter() {
+            if !cell_deps
+                .iter()
+                .any(|d| d.as_slice() == cell_dep.as_slice())
+            {
+                cell_deps.push(cell_dep.clone());
+            }
+        }
         let raw_tx = RawTransaction::new_builder()
             .inputs(inputs.pack())
             .outputs(outputs.pack())
             .outputs_data(outputs_data.pack())
-            .cell_deps(self.cell_deps.clone().pack())
+            .cell_deps(cell_deps.pack())
             .build();
 
         // build witnesses
@@ -1,6 +1,5 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
 use ckb_crypto::secp::Privkey;
-use faster_hex::hex_decode;
 use gw_common::blake2b::new_blake2b;
 use gw_config::WalletConfig;
 use gw_types::{
@@ -11,34 +10,54 @@ use gw_types::{
     prelude::{Builder, Entity, Pack, Unpack},
 };
 use sha3::{Digest, Keccak256};
+use std::path::Path;
 
-use crate::transaction_skeleton::{Signature, TransactionSkeleton};
+use crate::signer::{build_signer, read_privkey, LocalSigner, Signer};
+use crate::transaction_skeleton::{Signature, SignatureEntry, TransactionSkeleton};
 
 pub struct Wallet {
-    privkey: Privkey,
+    signer: Box<dyn Signer>,
     lock: Script,
 }
 
 impl Wallet {
     pub fn new(privkey: Privkey, lock: Script) -> Self {
-        Wallet { privkey, lock }
+        Self::with_signer(Box::new(LocalSigner::new(privkey)), lock)
+    }
+
+    pub fn with_signer(signer: Box<dyn Signer>, lock: Script) -> Self {
+        Wallet { signer, lock }
     }
 
     pub fn from_config(config: &WalletConfig) -> Result<Self> {
         let lock = config.lock.clone().into();
-        let privkey = {
-            let content = std::fs::read_to_string(&config.privkey_path)
-                .with_context(|| "read wallet privkey")?;
-            let content = content.trim_start_matches("0x").trim();
-            assert_eq!(content.as_bytes().len(), 64, "invalid privkey length");
-            let mut decoded = [0u8; 32];
-            hex_decode(content.as_bytes(), &mut decoded)?;
-            Privkey::from_slice(&decoded)
-        };
-        let wallet = Self::new(privkey, lock);
+        let signer = build_signer(config)?;
+        let wallet = Self::with_signer(signer, lock);
         Ok(wallet)
     }
 
+    /// Wallet with the secp256k1 blake160 sighash lock of the key.
+    pub fn sighash_from_privkey_path(
+        privkey_path: &Path,
+        sighash_type_hash: &H256,
+    ) -> Result<Self> {
+        let privkey = read_privkey(privkey_path)?;
+        let pubkey = privkey.pubkey()?;
+        let blake160 = {
+            let mut hasher = new_blake2b();
+            hasher.update(&pubkey.serialize());
+            let mut hash = [0u8; 32];
+            hasher.finalize(&mut hash);
+            Bytes::copy_from_slice(&hash[..20])
+        };
+        let lock = Script::new_builder()
+            .code_hash(sighash_type_hash.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(blake160.pack())
+            .build();
+        Ok(Self::new(privkey, lock))
+    }
+
     pub fn lock_script(&self) -> &Script {
         &self.lock
     }
@@ -48,8 +67,8 @@ impl Wallet {
         rollup_script_hash: &H256,
         eth_account_lock_code_hash: &H256,
     ) -> Result<Script> {
-        privkey_to_eth_account_script(
-            &self.privkey,
+        pubkey_to_eth_account_script(
+            &self.signer.public_key()?,
             rollup_script_hash,
             eth_account_lock_code_hash,
         )
@@ -57,70 +76,88 @@ impl Wallet {
 
     // sign message
     pub fn sign_message(&self, msg: [u8; 32]) -> Result<[u8; 65]> {
-        let signature = self
-            .privkey
-            .sign_recoverable(&msg.into())
-            .map_err(|err| anyhow!("signing error: {}", err))?;
-        let mut inner = [0u8; 65];
-        inner.copy_from_slice(&signature.serialize());
-        Ok(inner)
+        self.signer.sign_message(msg)
     }
 
     pub fn sign_tx_skeleton(&self, tx_skeleton: TransactionSkeleton) -> Result<Transaction> {
-        let signature_entries = tx_skeleton.signature_entries();
-        let dummy_signatures: Vec<_> = {
-            let entries = signature_entries.iter();
-            entries.map(Signature::zero_bytes_from_entry).collect()
-        };
+        sign_tx_skeleton_by(tx_skeleton, |_| Ok(self))
+    }
+}
 
-        // seal a dummy tx for calculation
-        let tx = tx_skeleton
-            .seal(&signature_entries, dummy_signatures)?
-            .transaction;
-        let tx_hash = {
-            let mut hasher = new_blake2b();
-            hasher.update(tx.raw().as_slice());
-            let mut hash = [0u8; 32];
-            hasher.finalize(&mut hash);
-            hash
-        };
-        let mut signatures = Vec::with_capacity(signature_entries.len());
-        for entry in &signature_entries {
-            let mut hasher = new_blake2b();
-            // hash tx_hash
-            hasher.update(&tx_hash);
-            // hash the first witness: len | witness
-            let first_witness: Bytes = tx
-                .witnesses()
-                .get(entry.indexes[0])
-                .expect("get first witness")
-                .unpack();
-            hasher.update(&(first_witness.len() as u64).to_le_bytes());
-            hasher.update(&first_witness);
-            // hash the other witnesses in the group
-            for &index in &entry.indexes[1..] {
-                let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
-                hasher.update(&(witness.len() as u64).to_le_bytes());
-                hasher.update(&witness);
-            }
-            // hash witnesses which do not in any input group
-            for index in tx.raw().inputs().len()..tx.witnesses().len() {
-                let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
-                hasher.update(&(witness.len() as u64).to_le_bytes());
-                hasher.update(&witness);
-            }
-            let mut message = [0u8; 32];
-            hasher.finalize(&mut message);
-            // sign tx
-            let signature = Signature::new(entry.kind, self.sign_message(message)?);
-            signatures.push(signature.as_bytes());
+/// Sign each input group with the wallet of its lock, for txs spending cells
+/// of several wallets. Groups of other locks, e.g. omni lock cells owned by
+/// the first wallet, are signed by the first wallet.
+pub fn sign_tx_skeleton_with_wallets(
+    wallets: &[&Wallet],
+    tx_skeleton: TransactionSkeleton,
+) -> Result<Transaction> {
+    let first = *wallets.first().ok_or_else(|| anyhow!("no wallet"))?;
+    sign_tx_skeleton_by(tx_skeleton, |entry| {
+        let wallet = wallets
+            .iter()
+            .find(|w| w.lock_script().hash() == entry.lock_hash)
+            .copied();
+        Ok(wallet.unwrap_or(first))
+    })
+}
+
+fn sign_tx_skeleton_by<'a>(
+    tx_skeleton: TransactionSkeleton,
+    wallet_of: impl Fn(&SignatureEntry) -> Result<&'a Wallet>,
+) -> Result<Transaction> {
+    let signature_entries = tx_skeleton.signature_entries();
+    let dummy_signatures: Vec<_> = {
+        let entries = signature_entries.iter();
+        entries.map(Signature::zero_bytes_from_entry).collect()
+    };
+
+    // seal a dummy tx for calculation
+    let tx = tx_skeleton
+        .seal(&signature_entries, dummy_signatures)?
+        .transaction;
+    let tx_hash = {
+        let mut hasher = new_blake2b();
+        hasher.update(tx.raw().as_slice());
+        let mut hash = [0u8; 32];
+        hasher.finalize(&mut hash);
+        hash
+    };
+    let mut signatures = Vec::with_capacity(signature_entries.len());
+    for entry in &signature_entries {
+        let mut hasher = new_blake2b();
+        // hash tx_hash
+        hasher.update(&tx_hash);
+        // hash the first witness: len | witness
+        let first_witness: Bytes = tx
+            .witnesses()
+            .get(entry.indexes[0])
+            .expect("get first witness")
+            .unpack();
+        hasher.update(&(first_witness.len() as u64).to_le_bytes());
+        hasher.update(&first_witness);
+        // hash the other witnesses in the group
+        for &index in &entry.indexes[1..] {
+            let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
+            hasher.update(&(witness.len() as u64).to_le_bytes());
+            hasher.update(&witness);
+        }
+        // hash witnesses which do not in any input group
+        for index in tx.raw().inputs().len()..tx.witnesses().len() {
+            let witness: Bytes = tx.witnesses().get(index).expect("get witness").unpack();
+            hasher.update(&(witness.len() as u64).to_le_bytes());
+            hasher.update(&witness);
         }
-        // seal
-        let sealed_tx = tx_skeleton.seal(&signature_entries, signatures)?;
-        // check fee rate
-        sealed_tx.check_fee_rate()?;
-        Ok(sealed_tx.transaction)
+        let mut message = [0u8; 32];
+        hasher.finalize(&mut message);
+        // sign tx
+        let signature = Signature::new(entry.kind, wallet_of(entry)?.sign_message(message)?);
+        signatures.push(signature.as_bytes());
     }
+    // seal
+    let sealed_tx = tx_skeleton.seal(&signature_entries, signatures)?;
+    // check fee rate
+    sealed_tx.check_fee_rate()?;
+    Ok(sealed_tx.transaction)
 }
 
 pub fn privkey_to_eth_account_script(
@@ -129,6 +166,14 @@ pub fn privkey_to_eth_account_script(
     eth_account_lock_code_hash: &H256,
 ) -> Result<Script> {
     let pubkey = secp256k1::PublicKey::from_slice(&privkey.pubkey()?.serialize())?;
+    pubkey_to_eth_account_script(&pubkey, rollup_script_hash, eth_account_lock_code_hash)
+}
+
+pub fn pubkey_to_eth_account_script(
+    pubkey: &secp256k1::PublicKey,
+    rollup_script_hash: &H256,
+    eth_account_lock_code_hash: &H256,
+) -> Result<Script> {
     let pubkey_hash = {
         let mut hasher = Keccak256::new();
         hasher.update(&pubkey.serialize_uncompressed()[1..]);
@@ -476,18 +476,27 @@ impl Config {
                     validator_path: [base_path, META_VALIDATOR_PATH].iter().collect(),
                     generator_path: [base_path, META_GENERATOR_PATH].iter().collect(),
                     validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
                 BackendConfig {
                     backend_type: BackendType::Sudt,
                     validator_path: [base_path, SUDT_VALIDATOR_PATH].iter().collect(),
                     generator_path: [base_path, SUDT_GENERATOR_PATH].iter().collect(),
                     validator_script_type_hash: SUDT_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
                 BackendConfig {
                     backend_type: BackendType::Polyjuice,
                     validator_path: [base_path, POLYJUICE_VALIDATOR_NAME].iter().collect(),
                     generator_path: [base_path, POLYJUICE_GENERATOR_NAME].iter().collect(),
                     validator_script_type_hash: polyjuice_validator_code_hash.into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
                 BackendConfig {
                     backend_type: BackendType::EthAddrReg,
@@ -498,6 +507,9 @@ impl Config {
                         .iter()
                         .collect(),
                     validator_script_type_hash: eth_addr_reg_code_hash.into(),
+                    validator_code_hash: None,
+                    generator_code_hash: None,
+                    cycles: Default::default(),
                 },
             ],
         };
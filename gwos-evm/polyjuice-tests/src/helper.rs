@@ -451,24 +451,36 @@ pub fn setup() -> (Store, DummyState, Generator) {
                 validator_path: META_VALIDATOR_PATH.into(),
                 generator_path: META_GENERATOR_PATH.into(),
                 validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+                validator_code_hash: None,
+                generator_code_hash: None,
+                cycles: Default::default(),
             },
             BackendConfig {
                 backend_type: BackendType::Sudt,
                 validator_path: SUDT_VALIDATOR_PATH.into(),
                 generator_path: SUDT_GENERATOR_PATH.into(),
                 validator_script_type_hash: SUDT_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+                validator_code_hash: None,
+                generator_code_hash: None,
+                cycles: Default::default(),
             },
             BackendConfig {
                 backend_type: BackendType::Polyjuice,
                 validator_path: POLYJUICE_VALIDATOR_NAME.into(),
                 generator_path: POLYJUICE_GENERATOR_NAME.into(),
                 validator_script_type_hash: (*POLYJUICE_PROGRAM_CODE_HASH).into(),
+                validator_code_hash: None,
+                generator_code_hash: None,
+                cycles: Default::default(),
             },
             BackendConfig {
                 backend_type: BackendType::EthAddrReg,
                 validator_path: ETH_ADDRESS_REGISTRY_VALIDATOR_NAME.into(),
                 generator_path: ETH_ADDRESS_REGISTRY_GENERATOR_NAME.into(),
                 validator_script_type_hash: (*ETH_ADDRESS_REGISTRY_PROGRAM_CODE_HASH).into(),
+                validator_code_hash: None,
+                generator_code_hash: None,
+                cycles: Default::default(),
             },
         ],
     }];
@@ -505,6 +517,7 @@ pub fn setup() -> (Store, DummyState, Generator) {
     let fork_config = ForkConfig {
         increase_max_l2_tx_cycles_to_500m: None,
         upgrade_global_state_version_to_v2: None,
+        fork_heights: Default::default(),
         backend_forks: fork_configs,
     };
     let rollup_context = RollupContext {
@@ -1998,6 +1998,8 @@ impl ::core::fmt::Display for P2PSyncRequest {
         write!(f, "{} {{ ", Self::NAME)?;
         write!(f, "{}: {}", "block_number", self.block_number())?;
         write!(f, ", {}: {}", "block_hash", self.block_hash())?;
+        write!(f, ", {}: {}", "publisher_id", self.publisher_id())?;
+        write!(f, ", {}: {}", "last_sequence", self.last_sequence())?;
         write!(f, " }}")
     }
 }
@@ -2005,21 +2007,27 @@ impl ::core::default::Default for P2PSyncRequest {
     fn default() -> Self {
         let v: Vec<u8> = vec![
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         ];
         P2PSyncRequest::new_unchecked(v.into())
     }
 }
 impl P2PSyncRequest {
-    pub const TOTAL_SIZE: usize = 40;
-    pub const FIELD_SIZES: [usize; 2] = [8, 32];
-    pub const FIELD_COUNT: usize = 2;
+    pub const TOTAL_SIZE: usize = 56;
+    pub const FIELD_SIZES: [usize; 4] = [8, 32, 8, 8];
+    pub const FIELD_COUNT: usize = 4;
     pub fn block_number(&self) -> Uint64 {
         Uint64::new_unchecked(self.0.slice(0..8))
     }
     pub fn block_hash(&self) -> Byte32 {
         Byte32::new_unchecked(self.0.slice(8..40))
     }
+    pub fn publisher_id(&self) -> Uint64 {
+        Uint64::new_unchecked(self.0.slice(40..48))
+    }
+    pub fn last_sequence(&self) -> Uint64 {
+        Uint64::new_unchecked(self.0.slice(48..56))
+    }
     pub fn as_reader<'r>(&'r self) -> P2PSyncRequestReader<'r> {
         P2PSyncRequestReader::new_unchecked(self.as_slice())
     }
@@ -2049,6 +2057,8 @@ impl molecule::prelude::Entity for P2PSyncRequest {
         Self::new_builder()
             .block_number(self.block_number())
             .block_hash(self.block_hash())
+            .publisher_id(self.publisher_id())
+            .last_sequence(self.last_sequence())
     }
 }
 #[derive(Clone, Copy)]
@@ -2072,19 +2082,27 @@ impl<'r> ::core::fmt::Display for P2PSyncRequestReader<'r> {
         write!(f, "{} {{ ", Self::NAME)?;
         write!(f, "{}: {}", "block_number", self.block_number())?;
         write!(f, ", {}: {}", "block_hash", self.block_hash())?;
+        write!(f, ", {}: {}", "publisher_id", self.publisher_id())?;
+        write!(f, ", {}: {}", "last_sequence", self.last_sequence())?;
         write!(f, " }}")
     }
 }
 impl<'r> P2PSyncRequestReader<'r> {
-    pub const TOTAL_SIZE: usize = 40;
-    pub const FIELD_SIZES: [usize; 2] = [8, 32];
-    pub const FIELD_COUNT: usize = 2;
+    pub const TOTAL_SIZE: usize = 56;
+    pub const FIELD_SIZES: [usize; 4] = [8, 32, 8, 8];
+    pub const FIELD_COUNT: usize = 4;
     pub fn block_number(&self) -> Uint64Reader<'r> {
         Uint64Reader::new_unchecked(&self.as_slice()[0..8])
     }
     pub fn block_hash(&self) -> Byte32Reader<'r> {
         Byte32Reader::new_unchecked(&self.as_slice()[8..40])
     }
+    pub fn publisher_id(&self) -> Uint64Reader<'r> {
+        Uint64Reader::new_unchecked(&self.as_slice()[40..48])
+    }
+    pub fn last_sequence(&self) -> Uint64Reader<'r> {
+        Uint64Reader::new_unchecked(&self.as_slice()[48..56])
+    }
 }
 impl<'r> molecule::prelude::Reader<'r> for P2PSyncRequestReader<'r> {
     type Entity = P2PSyncRequest;
@@ -2111,11 +2129,13 @@ impl<'r> molecule::prelude::Reader<'r> for P2PSyncRequestReader<'r> {
 pub struct P2PSyncRequestBuilder {
     pub(crate) block_number: Uint64,
     pub(crate) block_hash: Byte32,
+    pub(crate) publisher_id: Uint64,
+    pub(crate) last_sequence: Uint64,
 }
 impl P2PSyncRequestBuilder {
-    pub const TOTAL_SIZE: usize = 40;
-    pub const FIELD_SIZES: [usize; 2] = [8, 32];
-    pub const FIELD_COUNT: usize = 2;
+    pub const TOTAL_SIZE: usize = 56;
+    pub const FIELD_SIZES: [usize; 4] = [8, 32, 8, 8];
+    pub const FIELD_COUNT: usize = 4;
     pub fn block_number(mut self, v: Uint64) -> Self {
         self.block_number = v;
         self
@@ -2124,6 +2144,14 @@ impl P2PSyncRequestBuilder {
         self.block_hash = v;
         self
     }
+    pub fn publisher_id(mut self, v: Uint64) -> Self {
+        self.publisher_id = v;
+        self
+    }
+    pub fn last_sequence(mut self, v: Uint64) -> Self {
+        self.last_sequence = v;
+        self
+    }
 }
 impl molecule::prelude::Builder for P2PSyncRequestBuilder {
     type Entity = P2PSyncRequest;
@@ -2134,6 +2162,8 @@ impl molecule::prelude::Builder for P2PSyncRequestBuilder {
     fn write<W: molecule::io::Write>(&self, writer: &mut W) -> molecule::io::Result<()> {
         writer.write_all(self.block_number.as_slice())?;
         writer.write_all(self.block_hash.as_slice())?;
+        writer.write_all(self.publisher_id.as_slice())?;
+        writer.write_all(self.last_sequence.as_slice())?;
         Ok(())
     }
     fn build(&self) -> Self::Entity {
@@ -2456,36 +2486,22 @@ impl ::core::fmt::Debug for Found {
 impl ::core::fmt::Display for Found {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         write!(f, "{} {{ ", Self::NAME)?;
-        let extra_count = self.count_extra_fields();
-        if extra_count != 0 {
-            write!(f, ".. ({} fields)", extra_count)?;
-        }
+        write!(f, "{}: {}", "publisher_id", self.publisher_id())?;
         write!(f, " }}")
     }
 }
 impl ::core::default::Default for Found {
     fn default() -> Self {
-        let v: Vec<u8> = vec![4, 0, 0, 0];
+        let v: Vec<u8> = vec![0, 0, 0, 0, 0, 0, 0, 0];
         Found::new_unchecked(v.into())
     }
 }
 impl Found {
-    pub const FIELD_COUNT: usize = 0;
-    pub fn total_size(&self) -> usize {
-        molecule::unpack_number(self.as_slice()) as usize
-    }
-    pub fn field_count(&self) -> usize {
-        if self.total_size() == molecule::NUMBER_SIZE {
-            0
-        } else {
-            (molecule::unpack_number(&self.as_slice()[molecule::NUMBER_SIZE..]) as usize / 4) - 1
-        }
-    }
-    pub fn count_extra_fields(&self) -> usize {
-        self.field_count() - Self::FIELD_COUNT
-    }
-    pub fn has_extra_fields(&self) -> bool {
-        Self::FIELD_COUNT != self.field_count()
+    pub const TOTAL_SIZE: usize = 8;
+    pub const FIELD_SIZES: [usize; 1] = [8];
+    pub const FIELD_COUNT: usize = 1;
+    pub fn publisher_id(&self) -> Uint64 {
+        Uint64::new_unchecked(self.0.slice(0..8))
     }
     pub fn as_reader<'r>(&'r self) -> FoundReader<'r> {
         FoundReader::new_unchecked(self.as_slice())
@@ -2513,7 +2529,7 @@ impl molecule::prelude::Entity for Found {
         ::core::default::Default::default()
     }
     fn as_builder(self) -> Self::Builder {
-        Self::new_builder()
+        Self::new_builder().publisher_id(self.publisher_id())
     }
 }
 #[derive(Clone, Copy)]
@@ -2535,30 +2551,16 @@ impl<'r> ::core::fmt::Debug for FoundReader<'r> {
 impl<'r> ::core::fmt::Display for FoundReader<'r> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         write!(f, "{} {{ ", Self::NAME)?;
-        let extra_count = self.count_extra_fields();
-        if extra_count != 0 {
-            write!(f, ".. ({} fields)", extra_count)?;
-        }
+        write!(f, "{}: {}", "publisher_id", self.publisher_id())?;
         write!(f, " }}")
     }
 }
 impl<'r> FoundReader<'r> {
-    pub const FIELD_COUNT: usize = 0;
-    pub fn total_size(&self) -> usize {
-        molecule::unpack_number(self.as_slice()) as usize
-    }
-    pub fn field_count(&self) -> usize {
-        if self.total_size() == molecule::NUMBER_SIZE {
-            0
-        } else {
-            (molecule::unpack_number(&self.as_slice()[molecule::NUMBER_SIZE..]) as usize / 4) - 1
-        }
-    }
-    pub fn count_extra_fields(&self) -> usize {
-        self.field_count() - Self::FIELD_COUNT
-    }
-    pub fn has_extra_fields(&self) -> bool {
-        Self::FIELD_COUNT != self.field_count()
+    pub const TOTAL_SIZE: usize = 8;
+    pub const FIELD_SIZES: [usize; 1] = [8];
+    pub const FIELD_COUNT: usize = 1;
+    pub fn publisher_id(&self) -> Uint64Reader<'r> {
+        Uint64Reader::new_unchecked(&self.as_slice()[0..8])
     }
 }
 impl<'r> molecule::prelude::Reader<'r> for FoundReader<'r> {
@@ -2573,37 +2575,36 @@ impl<'r> molecule::prelude::Reader<'r> for FoundReader<'r> {
     fn as_slice(&self) -> &'r [u8] {
         self.0
     }
-    fn verify(slice: &[u8], compatible: bool) -> molecule::error::VerificationResult<()> {
+    fn verify(slice: &[u8], _compatible: bool) -> molecule::error::VerificationResult<()> {
         use molecule::verification_error as ve;
         let slice_len = slice.len();
-        if slice_len < molecule::NUMBER_SIZE {
-            return ve!(Self, HeaderIsBroken, molecule::NUMBER_SIZE, slice_len);
-        }
-        let total_size = molecule::unpack_number(slice) as usize;
-        if slice_len != total_size {
-            return ve!(Self, TotalSizeNotMatch, total_size, slice_len);
-        }
-        if slice_len > molecule::NUMBER_SIZE && !compatible {
-            return ve!(Self, FieldCountNotMatch, Self::FIELD_COUNT, !0);
+        if slice_len != Self::TOTAL_SIZE {
+            return ve!(Self, TotalSizeNotMatch, Self::TOTAL_SIZE, slice_len);
         }
         Ok(())
     }
 }
 #[derive(Debug, Default)]
-pub struct FoundBuilder {}
+pub struct FoundBuilder {
+    pub(crate) publisher_id: Uint64,
+}
 impl FoundBuilder {
-    pub const FIELD_COUNT: usize = 0;
+    pub const TOTAL_SIZE: usize = 8;
+    pub const FIELD_SIZES: [usize; 1] = [8];
+    pub const FIELD_COUNT: usize = 1;
+    pub fn publisher_id(mut self, v: Uint64) -> Self {
+        self.publisher_id = v;
+        self
+    }
 }
 impl molecule::prelude::Builder for FoundBuilder {
     type Entity = Found;
     const NAME: &'static str = "FoundBuilder";
     fn expected_length(&self) -> usize {
-        molecule::NUMBER_SIZE
+        Self::TOTAL_SIZE
     }
     fn write<W: molecule::io::Write>(&self, writer: &mut W) -> molecule::io::Result<()> {
-        writer.write_all(&molecule::pack_number(
-            molecule::NUMBER_SIZE as molecule::Number,
-        ))?;
+        writer.write_all(self.publisher_id.as_slice())?;
         Ok(())
     }
     fn build(&self) -> Self::Entity {
@@ -3372,3 +3373,504 @@ impl molecule::prelude::Builder for PushTransactionBuilder {
         PushTransaction::new_unchecked(inner.into())
     }
 }
+#[derive(Clone)]
+pub struct SequencedBlockSync(molecule::bytes::Bytes);
+impl ::core::fmt::LowerHex for SequencedBlockSync {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        use molecule::hex_string;
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex_string(self.as_slice()))
+    }
+}
+impl ::core::fmt::Debug for SequencedBlockSync {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{}({:#x})", Self::NAME, self)
+    }
+}
+impl ::core::fmt::Display for SequencedBlockSync {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{} {{ ", Self::NAME)?;
+        write!(f, "{}: {}", "sequence", self.sequence())?;
+        write!(f, ", {}: {}", "message", self.message())?;
+        let extra_count = self.count_extra_fields();
+        if extra_count != 0 {
+            write!(f, ", .. ({} fields)", extra_count)?;
+        }
+        write!(f, " }}")
+    }
+}
+impl ::core::default::Default for SequencedBlockSync {
+    fn default() -> Self {
+        let v: Vec<u8> = vec![
+            64, 0, 0, 0, 12, 0, 0, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ];
+        SequencedBlockSync::new_unchecked(v.into())
+    }
+}
+impl SequencedBlockSync {
+    pub const FIELD_COUNT: usize = 2;
+    pub fn total_size(&self) -> usize {
+        molecule::unpack_number(self.as_slice()) as usize
+    }
+    pub fn field_count(&self) -> usize {
+        if self.total_size() == molecule::NUMBER_SIZE {
+            0
+        } else {
+            (molecule::unpack_number(&self.as_slice()[molecule::NUMBER_SIZE..]) as usize / 4) - 1
+        }
+    }
+    pub fn count_extra_fields(&self) -> usize {
+        self.field_count() - Self::FIELD_COUNT
+    }
+    pub fn has_extra_fields(&self) -> bool {
+        Self::FIELD_COUNT != self.field_count()
+    }
+    pub fn sequence(&self) -> Uint64 {
+        let slice = self.as_slice();
+        let start = molecule::unpack_number(&slice[4..]) as usize;
+        let end = molecule::unpack_number(&slice[8..]) as usize;
+        Uint64::new_unchecked(self.0.slice(start..end))
+    }
+    pub fn message(&self) -> BlockSync {
+        let slice = self.as_slice();
+        let start = molecule::unpack_number(&slice[8..]) as usize;
+        if self.has_extra_fields() {
+            let end = molecule::unpack_number(&slice[12..]) as usize;
+            BlockSync::new_unchecked(self.0.slice(start..end))
+        } else {
+            BlockSync::new_unchecked(self.0.slice(start..))
+        }
+    }
+    pub fn as_reader<'r>(&'r self) -> SequencedBlockSyncReader<'r> {
+        SequencedBlockSyncReader::new_unchecked(self.as_slice())
+    }
+}
+impl molecule::prelude::Entity for SequencedBlockSync {
+    type Builder = SequencedBlockSyncBuilder;
+    const NAME: &'static str = "SequencedBlockSync";
+    fn new_unchecked(data: molecule::bytes::Bytes) -> Self {
+        SequencedBlockSync(data)
+    }
+    fn as_bytes(&self) -> molecule::bytes::Bytes {
+        self.0.clone()
+    }
+    fn as_slice(&self) -> &[u8] {
+        &self.0[..]
+    }
+    fn from_slice(slice: &[u8]) -> molecule::error::VerificationResult<Self> {
+        SequencedBlockSyncReader::from_slice(slice).map(|reader| reader.to_entity())
+    }
+    fn from_compatible_slice(slice: &[u8]) -> molecule::error::VerificationResult<Self> {
+        SequencedBlockSyncReader::from_compatible_slice(slice).map(|reader| reader.to_entity())
+    }
+    fn new_builder() -> Self::Builder {
+        ::core::default::Default::default()
+    }
+    fn as_builder(self) -> Self::Builder {
+        Self::new_builder()
+            .sequence(self.sequence())
+            .message(self.message())
+    }
+}
+#[derive(Clone, Copy)]
+pub struct SequencedBlockSyncReader<'r>(&'r [u8]);
+impl<'r> ::core::fmt::LowerHex for SequencedBlockSyncReader<'r> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        use molecule::hex_string;
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex_string(self.as_slice()))
+    }
+}
+impl<'r> ::core::fmt::Debug for SequencedBlockSyncReader<'r> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{}({:#x})", Self::NAME, self)
+    }
+}
+impl<'r> ::core::fmt::Display for SequencedBlockSyncReader<'r> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{} {{ ", Self::NAME)?;
+        write!(f, "{}: {}", "sequence", self.sequence())?;
+        write!(f, ", {}: {}", "message", self.message())?;
+        let extra_count = self.count_extra_fields();
+        if extra_count != 0 {
+            write!(f, ", .. ({} fields)", extra_count)?;
+        }
+        write!(f, " }}")
+    }
+}
+impl<'r> SequencedBlockSyncReader<'r> {
+    pub const FIELD_COUNT: usize = 2;
+    pub fn total_size(&self) -> usize {
+        molecule::unpack_number(self.as_slice()) as usize
+    }
+    pub fn field_count(&self) -> usize {
+        if self.total_size() == molecule::NUMBER_SIZE {
+            0
+        } else {
+            (molecule::unpack_number(&self.as_slice()[molecule::NUMBER_SIZE..]) as usize / 4) - 1
+        }
+    }
+    pub fn count_extra_fields(&self) -> usize {
+        self.field_count() - Self::FIELD_COUNT
+    }
+    pub fn has_extra_fields(&self) -> bool {
+        Self::FIELD_COUNT != self.field_count()
+    }
+    pub fn sequence(&self) -> Uint64Reader<'r> {
+        let slice = self.as_slice();
+        let start = molecule::unpack_number(&slice[4..]) as usize;
+        let end = molecule::unpack_number(&slice[8..]) as usize;
+        Uint64Reader::new_unchecked(&self.as_slice()[start..end])
+    }
+    pub fn message(&self) -> BlockSyncReader<'r> {
+        let slice = self.as_slice();
+        let start = molecule::unpack_number(&slice[8..]) as usize;
+        if self.has_extra_fields() {
+            let end = molecule::unpack_number(&slice[12..]) as usize;
+            BlockSyncReader::new_unchecked(&self.as_slice()[start..end])
+        } else {
+            BlockSyncReader::new_unchecked(&self.as_slice()[start..])
+        }
+    }
+}
+impl<'r> molecule::prelude::Reader<'r> for SequencedBlockSyncReader<'r> {
+    type Entity = SequencedBlockSync;
+    const NAME: &'static str = "SequencedBlockSyncReader";
+    fn to_entity(&self) -> Self::Entity {
+        Self::Entity::new_unchecked(self.as_slice().to_owned().into())
+    }
+    fn new_unchecked(slice: &'r [u8]) -> Self {
+        SequencedBlockSyncReader(slice)
+    }
+    fn as_slice(&self) -> &'r [u8] {
+        self.0
+    }
+    fn verify(slice: &[u8], compatible: bool) -> molecule::error::VerificationResult<()> {
+        use molecule::verification_error as ve;
+        let slice_len = slice.len();
+        if slice_len < molecule::NUMBER_SIZE {
+            return ve!(Self, HeaderIsBroken, molecule::NUMBER_SIZE, slice_len);
+        }
+        let total_size = molecule::unpack_number(slice) as usize;
+        if slice_len != total_size {
+            return ve!(Self, TotalSizeNotMatch, total_size, slice_len);
+        }
+        if slice_len == molecule::NUMBER_SIZE && Self::FIELD_COUNT == 0 {
+            return Ok(());
+        }
+        if slice_len < molecule::NUMBER_SIZE * 2 {
+            return ve!(Self, HeaderIsBroken, molecule::NUMBER_SIZE * 2, slice_len);
+        }
+        let offset_first = molecule::unpack_number(&slice[molecule::NUMBER_SIZE..]) as usize;
+        if offset_first % molecule::NUMBER_SIZE != 0 || offset_first < molecule::NUMBER_SIZE * 2 {
+            return ve!(Self, OffsetsNotMatch);
+        }
+        if slice_len < offset_first {
+            return ve!(Self, HeaderIsBroken, offset_first, slice_len);
+        }
+        let field_count = offset_first / molecule::NUMBER_SIZE - 1;
+        if field_count < Self::FIELD_COUNT {
+            return ve!(Self, FieldCountNotMatch, Self::FIELD_COUNT, field_count);
+        } else if !compatible && field_count > Self::FIELD_COUNT {
+            return ve!(Self, FieldCountNotMatch, Self::FIELD_COUNT, field_count);
+        };
+        let mut offsets: Vec<usize> = slice[molecule::NUMBER_SIZE..offset_first]
+            .chunks_exact(molecule::NUMBER_SIZE)
+            .map(|x| molecule::unpack_number(x) as usize)
+            .collect();
+        offsets.push(total_size);
+        if offsets.windows(2).any(|i| i[0] > i[1]) {
+            return ve!(Self, OffsetsNotMatch);
+        }
+        Uint64Reader::verify(&slice[offsets[0]..offsets[1]], compatible)?;
+        BlockSyncReader::verify(&slice[offsets[1]..offsets[2]], compatible)?;
+        Ok(())
+    }
+}
+#[derive(Debug, Default)]
+pub struct SequencedBlockSyncBuilder {
+    pub(crate) sequence: Uint64,
+    pub(crate) message: BlockSync,
+}
+impl SequencedBlockSyncBuilder {
+    pub const FIELD_COUNT: usize = 2;
+    pub fn sequence(mut self, v: Uint64) -> Self {
+        self.sequence = v;
+        self
+    }
+    pub fn message(mut self, v: BlockSync) -> Self {
+        self.message = v;
+        self
+    }
+}
+impl molecule::prelude::Builder for SequencedBlockSyncBuilder {
+    type Entity = SequencedBlockSync;
+    const NAME: &'static str = "SequencedBlockSyncBuilder";
+    fn expected_length(&self) -> usize {
+        molecule::NUMBER_SIZE * (Self::FIELD_COUNT + 1)
+            + self.sequence.as_slice().len()
+            + self.message.as_slice().len()
+    }
+    fn write<W: molecule::io::Write>(&self, writer: &mut W) -> molecule::io::Result<()> {
+        let mut total_size = molecule::NUMBER_SIZE * (Self::FIELD_COUNT + 1);
+        let mut offsets = Vec::with_capacity(Self::FIELD_COUNT);
+        offsets.push(total_size);
+        total_size += self.sequence.as_slice().len();
+        offsets.push(total_size);
+        total_size += self.message.as_slice().len();
+        writer.write_all(&molecule::pack_number(total_size as molecule::Number))?;
+        for offset in offsets.into_iter() {
+            writer.write_all(&molecule::pack_number(offset as molecule::Number))?;
+        }
+        writer.write_all(self.sequence.as_slice())?;
+        writer.write_all(self.message.as_slice())?;
+        Ok(())
+    }
+    fn build(&self) -> Self::Entity {
+        let mut inner = Vec::with_capacity(self.expected_length());
+        self.write(&mut inner)
+            .unwrap_or_else(|_| panic!("{} build should be ok", Self::NAME));
+        SequencedBlockSync::new_unchecked(inner.into())
+    }
+}
+#[derive(Clone)]
+pub struct RelayTransactions(molecule::bytes::Bytes);
+impl ::core::fmt::LowerHex for RelayTransactions {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        use molecule::hex_string;
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex_string(self.as_slice()))
+    }
+}
+impl ::core::fmt::Debug for RelayTransactions {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{}({:#x})", Self::NAME, self)
+    }
+}
+impl ::core::fmt::Display for RelayTransactions {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{} {{ ", Self::NAME)?;
+        write!(f, "{}: {}", "transactions", self.transactions())?;
+        let extra_count = self.count_extra_fields();
+        if extra_count != 0 {
+            write!(f, ", .. ({} fields)", extra_count)?;
+        }
+        write!(f, " }}")
+    }
+}
+impl ::core::default::Default for RelayTransactions {
+    fn default() -> Self {
+        let v: Vec<u8> = vec![12, 0, 0, 0, 8, 0, 0, 0, 4, 0, 0, 0];
+        RelayTransactions::new_unchecked(v.into())
+    }
+}
+impl RelayTransactions {
+    pub const FIELD_COUNT: usize = 1;
+    pub fn total_size(&self) -> usize {
+        molecule::unpack_number(self.as_slice()) as usize
+    }
+    pub fn field_count(&self) -> usize {
+        if self.total_size() == molecule::NUMBER_SIZE {
+            0
+        } else {
+            (molecule::unpack_number(&self.as_slice()[molecule::NUMBER_SIZE..]) as usize / 4) - 1
+        }
+    }
+    pub fn count_extra_fields(&self) -> usize {
+        self.field_count() - Self::FIELD_COUNT
+    }
+    pub fn has_extra_fields(&self) -> bool {
+        Self::FIELD_COUNT != self.field_count()
+    }
+    pub fn transactions(&self) -> L2TransactionVec {
+        let slice = self.as_slice();
+        let start = molecule::unpack_number(&slice[4..]) as usize;
+        if self.has_extra_fields() {
+            let end = molecule::unpack_number(&slice[8..]) as usize;
+            L2TransactionVec::new_unchecked(self.0.slice(start..end))
+        } else {
+            L2TransactionVec::new_unchecked(self.0.slice(start..))
+        }
+    }
+    pub fn as_reader<'r>(&'r self) -> RelayTransactionsReader<'r> {
+        RelayTransactionsReader::new_unchecked(self.as_slice())
+    }
+}
+impl molecule::prelude::Entity for RelayTransactions {
+    type Builder = RelayTransactionsBuilder;
+    const NAME: &'static str = "RelayTransactions";
+    fn new_unchecked(data: molecule::bytes::Bytes) -> Self {
+        RelayTransactions(data)
+    }
+    fn as_bytes(&self) -> molecule::bytes::Bytes {
+        self.0.clone()
+    }
+    fn as_slice(&self) -> &[u8] {
+        &self.0[..]
+    }
+    fn from_slice(slice: &[u8]) -> molecule::error::VerificationResult<Self> {
+        RelayTransactionsReader::from_slice(slice).map(|reader| reader.to_entity())
+    }
+    fn from_compatible_slice(slice: &[u8]) -> molecule::error::VerificationResult<Self> {
+        RelayTransactionsReader::from_compatible_slice(slice).map(|reader| reader.to_entity())
+    }
+    fn new_builder() -> Self::Builder {
+        ::core::default::Default::default()
+    }
+    fn as_builder(self) -> Self::Builder {
+        Self::new_builder().transactions(self.transactions())
+    }
+}
+#[derive(Clone, Copy)]
+pub struct RelayTransactionsReader<'r>(&'r [u8]);
+impl<'r> ::core::fmt::LowerHex for RelayTransactionsReader<'r> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        use molecule::hex_string;
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex_string(self.as_slice()))
+    }
+}
+impl<'r> ::core::fmt::Debug for RelayTransactionsReader<'r> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{}({:#x})", Self::NAME, self)
+    }
+}
+impl<'r> ::core::fmt::Display for RelayTransactionsReader<'r> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        write!(f, "{} {{ ", Self::NAME)?;
+        write!(f, "{}: {}", "transactions", self.transactions())?;
+        let extra_count = self.count_extra_fields();
+        if extra_count != 0 {
+            write!(f, ", .. ({} fields)", extra_count)?;
+        }
+        write!(f, " }}")
+    }
+}
+impl<'r> RelayTransactionsReader<'r> {
+    pub const FIELD_COUNT: usize = 1;
+    pub fn total_size(&self) -> usize {
+        molecule::unpack_number(self.as_slice()) as usize
+    }
+    pub fn field_count(&self) -> usize {
+        if self.total_size() == molecule::NUMBER_SIZE {
+            0
+        } else {
+            (molecule::unpack_number(&self.as_slice()[molecule::NUMBER_SIZE..]) as usize / 4) - 1
+        }
+    }
+    pub fn count_extra_fields(&self) -> usize {
+        self.field_count() - Self::FIELD_COUNT
+    }
+    pub fn has_extra_fields(&self) -> bool {
+        Self::FIELD_COUNT != self.field_count()
+    }
+    pub fn transactions(&self) -> L2TransactionVecReader<'r> {
+        let slice = self.as_slice();
+        let start = molecule::unpack_number(&slice[4..]) as usize;
+        if self.has_extra_fields() {
+            let end = molecule::unpack_number(&slice[8..]) as usize;
+            L2TransactionVecReader::new_unchecked(&self.as_slice()[start..end])
+        } else {
+            L2TransactionVecReader::new_unchecked(&self.as_slice()[start..])
+        }
+    }
+}
+impl<'r> molecule::prelude::Reader<'r> for RelayTransactionsReader<'r> {
+    type Entity = RelayTransactions;
+    const NAME: &'static str = "RelayTransactionsReader";
+    fn to_entity(&self) -> Self::Entity {
+        Self::Entity::new_unchecked(self.as_slice().to_owned().into())
+    }
+    fn new_unchecked(slice: &'r [u8]) -> Self {
+        RelayTransactionsReader(slice)
+    }
+    fn as_slice(&self) -> &'r [u8] {
+        self.0
+    }
+    fn verify(slice: &[u8], compatible: bool) -> molecule::error::VerificationResult<()> {
+        use molecule::verification_error as ve;
+        let slice_len = slice.len();
+        if slice_len < molecule::NUMBER_SIZE {
+            return ve!(Self, HeaderIsBroken, molecule::NUMBER_SIZE, slice_len);
+        }
+        let total_size = molecule::unpack_number(slice) as usize;
+        if slice_len != total_size {
+            return ve!(Self, TotalSizeNotMatch, total_size, slice_len);
+        }
+        if slice_len == molecule::NUMBER_SIZE && Self::FIELD_COUNT == 0 {
+            return Ok(());
+        }
+        if slice_len < molecule::NUMBER_SIZE * 2 {
+            return ve!(Self, HeaderIsBroken, molecule::NUMBER_SIZE * 2, slice_len);
+        }
+        let offset_first = molecule::unpack_number(&slice[molecule::NUMBER_SIZE..]) as usize;
+        if offset_first % molecule::NUMBER_SIZE != 0 || offset_first < molecule::NUMBER_SIZE * 2 {
+            return ve!(Self, OffsetsNotMatch);
+        }
+        if slice_len < offset_first {
+            return ve!(Self, HeaderIsBroken, offset_first, slice_len);
+        }
+        let field_count = offset_first / molecule::NUMBER_SIZE - 1;
+        if field_count < Self::FIELD_COUNT {
+            return ve!(Self, FieldCountNotMatch, Self::FIELD_COUNT, field_count);
+        } else if !compatible && field_count > Self::FIELD_COUNT {
+            return ve!(Self, FieldCountNotMatch, Self::FIELD_COUNT, field_count);
+        };
+        let mut offsets: Vec<usize> = slice[molecule::NUMBER_SIZE..offset_first]
+            .chunks_exact(molecule::NUMBER_SIZE)
+            .map(|x| molecule::unpack_number(x) as usize)
+            .collect();
+        offsets.push(total_size);
+        if offsets.windows(2).any(|i| i[0] > i[1]) {
+            return ve!(Self, OffsetsNotMatch);
+        }
+        L2TransactionVecReader::verify(&slice[offsets[0]..offsets[1]], compatible)?;
+        Ok(())
+    }
+}
+#[derive(Debug, Default)]
+pub struct RelayTransactionsBuilder {
+    pub(crate) transactions: L2TransactionVec,
+}
+impl RelayTransactionsBuilder {
+    pub const FIELD_COUNT: usize = 1;
+    pub fn transactions(mut self, v: L2TransactionVec) -> Self {
+        self.transactions = v;
+        self
+    }
+}
+impl molecule::prelude::Builder for RelayTransactionsBuilder {
+    type Entity = RelayTransactions;
+    const NAME: &'static str = "RelayTransactionsBuilder";
+    fn expected_length(&self) -> usize {
+        molecule::NUMBER_SIZE * (Self::FIELD_COUNT + 1) + self.transactions.as_slice().len()
+    }
+    fn write<W: molecule::io::Write>(&self, writer: &mut W) -> molecule::io::Result<()> {
+        let mut total_size = molecule::NUMBER_SIZE * (Self::FIELD_COUNT + 1);
+        let mut offsets = Vec::with_capacity(Self::FIELD_COUNT);
+        offsets.push(total_size);
+        total_size += self.transactions.as_slice().len();
+        writer.write_all(&molecule::pack_number(total_size as molecule::Number))?;
+        for offset in offsets.into_iter() {
+            writer.write_all(&molecule::pack_number(offset as molecule::Number))?;
+        }
+        writer.write_all(self.transactions.as_slice())?;
+        Ok(())
+    }
+    fn build(&self) -> Self::Entity {
+        let mut inner = Vec::with_capacity(self.expected_length());
+        self.write(&mut inner)
+            .unwrap_or_else(|_| panic!("{} build should be ok", Self::NAME));
+        RelayTransactions::new_unchecked(inner.into())
+    }
+}
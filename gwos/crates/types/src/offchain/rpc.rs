@@ -3,7 +3,7 @@ use crate::{
     bytes::Bytes,
     packed::{CellInput, CellOutput, OutPoint},
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Default)]
 pub struct CellInfo {
@@ -78,6 +78,7 @@ pub struct SUDTStat {
     pub total_amount: u128,
     pub finalized_amount: u128,
     pub cells_count: usize,
+    pub finalized_cells_count: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -85,6 +86,12 @@ pub struct CustodianStat {
     pub total_capacity: u128,
     pub finalized_capacity: u128,
     pub cells_count: usize,
+    pub finalized_cells_count: usize,
     pub ckb_cells_count: usize,
     pub sudt_stat: HashMap<ckb_types::packed::Script, SUDTStat>,
+    /// Cells count by capacity, keyed by the lower bound of the bucket in CKB.
+    /// Buckets are powers of 10.
+    pub capacity_histogram: BTreeMap<u64, usize>,
+    /// L1 block number of the oldest unfinalized custodian cell.
+    pub oldest_unfinalized_block_number: Option<u64>,
 }